@@ -4,7 +4,7 @@ use crate::utils::to_err;
 use js_sys::{Array, Map};
 use oxigraph::io::{DatasetFormat, GraphFormat};
 use oxigraph::model::*;
-use oxigraph::sparql::QueryResults;
+use oxigraph::sparql::{PreparedQuery, QueryResults, Variable};
 use oxigraph::store::Store;
 use std::io::Cursor;
 use wasm_bindgen::prelude::*;
@@ -103,36 +103,13 @@ impl JsStore {
     }
 
     pub fn query(&self, query: &str) -> Result<JsValue, JsValue> {
-        let results = self.store.query(query).map_err(to_err)?;
-        let output = match results {
-            QueryResults::Solutions(solutions) => {
-                let results = Array::new();
-                for solution in solutions {
-                    let solution = solution.map_err(to_err)?;
-                    let result = Map::new();
-                    for (variable, value) in solution.iter() {
-                        result.set(
-                            &variable.as_str().into(),
-                            &JsTerm::from(value.clone()).into(),
-                        );
-                    }
-                    results.push(&result.into());
-                }
-                results.into()
-            }
-            QueryResults::Graph(quads) => {
-                let results = Array::new();
-                for quad in quads {
-                    results.push(
-                        &JsQuad::from(quad.map_err(to_err)?.in_graph(GraphName::DefaultGraph))
-                            .into(),
-                    );
-                }
-                results.into()
-            }
-            QueryResults::Boolean(b) => b.into(),
-        };
-        Ok(output)
+        query_results_to_js(self.store.query(query).map_err(to_err)?)
+    }
+
+    pub fn prepare(&self, query: &str) -> Result<JsPreparedQuery, JsValue> {
+        Ok(JsPreparedQuery {
+            inner: self.store.prepare(query).map_err(to_err)?,
+        })
     }
 
     pub fn update(&self, update: &str) -> Result<(), JsValue> {
@@ -220,3 +197,67 @@ impl JsStore {
         String::from_utf8(buffer).map_err(to_err)
     }
 }
+
+#[wasm_bindgen(js_name = PreparedQuery)]
+pub struct JsPreparedQuery {
+    inner: PreparedQuery,
+}
+
+#[wasm_bindgen(js_class = PreparedQuery)]
+impl JsPreparedQuery {
+    pub fn execute(&self, bindings: &JsValue) -> Result<JsValue, JsValue> {
+        let mut values = Vec::new();
+        if !bindings.is_null() && !bindings.is_undefined() {
+            let bindings: &Map = bindings
+                .dyn_ref()
+                .ok_or_else(|| format_err!("The bindings should be a Map"))?;
+            for entry in bindings.entries() {
+                let entry = Array::from(&entry?);
+                let variable = if let Some(name) = entry.get(0).as_string() {
+                    Variable::new(name).map_err(to_err)?
+                } else if let JsTerm::Variable(variable) =
+                    FROM_JS.with(|c| c.to_term(&entry.get(0)))?
+                {
+                    variable.into()
+                } else {
+                    return Err(format_err!(
+                        "The bindings keys should be strings or variables"
+                    ));
+                };
+                let value: Term = FROM_JS.with(|c| c.to_term(&entry.get(1)))?.try_into()?;
+                values.push((variable, value));
+            }
+        }
+        query_results_to_js(self.inner.execute(values).map_err(to_err)?)
+    }
+}
+
+fn query_results_to_js(results: QueryResults) -> Result<JsValue, JsValue> {
+    Ok(match results {
+        QueryResults::Solutions(solutions) => {
+            let results = Array::new();
+            for solution in solutions {
+                let solution = solution.map_err(to_err)?;
+                let result = Map::new();
+                for (variable, value) in solution.iter() {
+                    result.set(
+                        &variable.as_str().into(),
+                        &JsTerm::from(value.clone()).into(),
+                    );
+                }
+                results.push(&result.into());
+            }
+            results.into()
+        }
+        QueryResults::Graph(quads) => {
+            let results = Array::new();
+            for quad in quads {
+                results.push(
+                    &JsQuad::from(quad.map_err(to_err)?.in_graph(GraphName::DefaultGraph)).into(),
+                );
+            }
+            results.into()
+        }
+        QueryResults::Boolean(b) => b.into(),
+    })
+}
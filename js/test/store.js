@@ -90,6 +90,26 @@ describe('Store', function () {
     })
   })
 
+  describe('#prepare()', function () {
+    it('SELECT with bindings', function () {
+      const store = new Store([dataFactory.triple(ex, ex, ex)])
+      const query = store.prepare('SELECT ?s ?o WHERE { ?s ?p ?o }')
+      assert.strictEqual(1, query.execute().length)
+      const results = query.execute(new Map([['s', ex]]))
+      assert.strictEqual(1, results.length)
+      assert(ex.equals(results[0].get('o')))
+      assert.strictEqual(0, query.execute(new Map([[dataFactory.variable('o'), dataFactory.literal('foo')]])).length)
+    })
+
+    it('ASK after update', function () {
+      const store = new Store()
+      const query = store.prepare('ASK { ?s ?p ?o }')
+      assert.strictEqual(false, query.execute())
+      store.add(dataFactory.triple(ex, ex, ex))
+      assert.strictEqual(true, query.execute())
+    })
+  })
+
   describe('#update()', function () {
     it('INSERT DATA', function () {
       const store = new Store()
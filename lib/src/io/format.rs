@@ -1,6 +1,7 @@
 /// [RDF graph](https://www.w3.org/TR/rdf11-concepts/#dfn-graph) serialization formats.
 ///
-/// This enumeration is non exhaustive. New formats like JSON-LD will be added in the future.
+/// This enumeration is non exhaustive. New formats may be added in the future.
+/// JSON-LD is available as [`DatasetFormat::JsonLd`].
 #[derive(Eq, PartialEq, Debug, Clone, Copy, Hash)]
 #[non_exhaustive]
 pub enum GraphFormat {
@@ -102,7 +103,7 @@ impl GraphFormat {
 
 /// [RDF dataset](https://www.w3.org/TR/rdf11-concepts/#dfn-rdf-dataset) serialization formats.
 ///
-/// This enumeration is non exhaustive. New formats like JSON-LD will be added in the future.
+/// This enumeration is non exhaustive. New formats may be added in the future.
 #[derive(Eq, PartialEq, Debug, Clone, Copy, Hash)]
 #[non_exhaustive]
 pub enum DatasetFormat {
@@ -110,6 +111,8 @@ pub enum DatasetFormat {
     NQuads,
     /// [TriG](https://www.w3.org/TR/trig/)
     TriG,
+    /// [JSON-LD](https://www.w3.org/TR/json-ld11/)
+    JsonLd,
 }
 
 impl DatasetFormat {
@@ -125,6 +128,7 @@ impl DatasetFormat {
         match self {
             DatasetFormat::NQuads => "http://www.w3.org/ns/formats/N-Quads",
             DatasetFormat::TriG => "http://www.w3.org/ns/formats/TriG",
+            DatasetFormat::JsonLd => "http://www.w3.org/ns/formats/JSON-LD",
         }
     }
 
@@ -140,6 +144,7 @@ impl DatasetFormat {
         match self {
             DatasetFormat::NQuads => "application/n-quads",
             DatasetFormat::TriG => "application/trig",
+            DatasetFormat::JsonLd => "application/ld+json",
         }
     }
 
@@ -155,6 +160,7 @@ impl DatasetFormat {
         match self {
             DatasetFormat::NQuads => "nq",
             DatasetFormat::TriG => "trig",
+            DatasetFormat::JsonLd => "jsonld",
         }
     }
     /// Looks for a known format from a media type.
@@ -171,6 +177,7 @@ impl DatasetFormat {
         match media_type.split(';').next()?.trim() {
             "application/n-quads" | "text/x-nquads" | "text/nquads" => Some(Self::NQuads),
             "application/trig" | "application/x-trig" => Some(Self::TriG),
+            "application/ld+json" => Some(Self::JsonLd),
             _ => None,
         }
     }
@@ -189,6 +196,7 @@ impl DatasetFormat {
         match extension {
             "nq" | "txt" => Some(Self::NQuads),
             "trig" => Some(Self::TriG),
+            "jsonld" => Some(Self::JsonLd),
             _ => None,
         }
     }
@@ -202,6 +210,7 @@ impl TryFrom<DatasetFormat> for GraphFormat {
         match value {
             DatasetFormat::NQuads => Ok(Self::NTriples),
             DatasetFormat::TriG => Ok(Self::Turtle),
+            DatasetFormat::JsonLd => Err(()),
         }
     }
 }
@@ -0,0 +1,908 @@
+use crate::io::jsonld::context::{Context, Processor};
+use crate::io::jsonld::json::{JsonObject, JsonValue};
+use crate::io::jsonld::{
+    add_value, error, is_graph_object, is_list_object, is_simple_graph_object, is_value_object,
+};
+use oxiri::Iri;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io;
+use std::sync::Arc;
+
+/// The [inverse context](https://www.w3.org/TR/json-ld11-api/#dfn-inverse-context):
+/// for each IRI and container the terms by type and language
+pub type InverseContext = HashMap<String, HashMap<String, TypeLanguageMap>>;
+
+#[derive(Default)]
+pub struct TypeLanguageMap {
+    language: HashMap<String, String>,
+    type_: HashMap<String, String>,
+    any: HashMap<String, String>,
+}
+
+impl TypeLanguageMap {
+    fn get(&self, type_language: &str) -> &HashMap<String, String> {
+        match type_language {
+            "@language" => &self.language,
+            "@type" => &self.type_,
+            _ => &self.any,
+        }
+    }
+}
+
+/// The [inverse context creation algorithm](https://www.w3.org/TR/json-ld11-api/#inverse-context-creation)
+fn inverse(active: &Context) -> Arc<InverseContext> {
+    if let Some(inverse) = &*active.inverse.lock().unwrap() {
+        return inverse.clone();
+    }
+    let mut result = InverseContext::new();
+    let default_language = active
+        .default_language
+        .as_deref()
+        .map_or_else(|| "@none".to_owned(), str::to_lowercase);
+    let mut terms = active.term_definitions.iter().collect::<Vec<_>>();
+    terms.sort_unstable_by(|(a, _), (b, _)| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+    for (term, definition) in terms {
+        let iri = if let Some(iri) = &definition.iri_mapping {
+            iri
+        } else {
+            continue;
+        };
+        let container = if definition.container.is_empty() {
+            "@none".to_owned()
+        } else {
+            definition.container.concat()
+        };
+        let maps = result
+            .entry(iri.clone())
+            .or_default()
+            .entry(container)
+            .or_insert_with(|| TypeLanguageMap {
+                any: HashMap::from([("@none".to_owned(), term.clone())]),
+                ..TypeLanguageMap::default()
+            });
+        let add = |map: &mut HashMap<String, String>, key: String| {
+            map.entry(key).or_insert_with(|| term.clone());
+        };
+        if definition.reverse {
+            add(&mut maps.type_, "@reverse".into());
+        } else if definition.type_mapping.as_deref() == Some("@none") {
+            add(&mut maps.language, "@any".into());
+            add(&mut maps.type_, "@any".into());
+        } else if let Some(type_mapping) = &definition.type_mapping {
+            add(&mut maps.type_, type_mapping.clone());
+        } else if let (Some(language), Some(direction)) =
+            (&definition.language, &definition.direction)
+        {
+            let key = match (language, direction) {
+                (Some(language), Some(direction)) => format!("{}_{}", language, direction),
+                (Some(language), None) => language.clone(),
+                (None, Some(direction)) => format!("_{}", direction),
+                (None, None) => "@null".into(),
+            };
+            add(&mut maps.language, key.to_lowercase());
+        } else if let Some(language) = &definition.language {
+            let key = language
+                .as_deref()
+                .map_or_else(|| "@null".into(), str::to_lowercase);
+            add(&mut maps.language, key);
+        } else if let Some(direction) = &definition.direction {
+            let key = direction
+                .as_ref()
+                .map_or_else(|| "@none".into(), |d| format!("_{}", d));
+            add(&mut maps.language, key);
+        } else if let Some(direction) = &active.default_base_direction {
+            let key = format!(
+                "{}_{}",
+                active.default_language.as_deref().unwrap_or(""),
+                direction
+            )
+            .to_lowercase();
+            add(&mut maps.language, key);
+            add(&mut maps.language, "@none".into());
+            add(&mut maps.type_, "@none".into());
+        } else {
+            add(&mut maps.language, default_language.clone());
+            add(&mut maps.language, "@none".into());
+            add(&mut maps.type_, "@none".into());
+        }
+    }
+    let result = Arc::new(result);
+    *active.inverse.lock().unwrap() = Some(result.clone());
+    result
+}
+
+/// The [IRI compaction algorithm](https://www.w3.org/TR/json-ld11-api/#iri-compaction)
+pub fn compact_iri(
+    active: &Context,
+    var: &str,
+    value: Option<&JsonValue>,
+    vocab: bool,
+    reverse: bool,
+) -> io::Result<String> {
+    let inverse = inverse(active);
+    if vocab {
+        if let Some(container_map) = inverse.get(var) {
+            if let Some(term) = select_term_for_value(active, container_map, value, reverse) {
+                return Ok(term);
+            }
+        }
+        if let Some(vocabulary_mapping) = &active.vocabulary_mapping {
+            if let Some(suffix) = var.strip_prefix(vocabulary_mapping.as_str()) {
+                if !suffix.is_empty() && active.term_definition(suffix).is_none() {
+                    return Ok(suffix.into());
+                }
+            }
+        }
+    }
+    let mut compact_iri: Option<String> = None;
+    for (term, definition) in active.term_definitions.iter() {
+        let iri_mapping = match &definition.iri_mapping {
+            Some(iri_mapping) if definition.prefix && iri_mapping != var => iri_mapping,
+            _ => continue,
+        };
+        let suffix = if let Some(suffix) = var.strip_prefix(iri_mapping.as_str()) {
+            suffix
+        } else {
+            continue;
+        };
+        let candidate = format!("{}:{}", term, suffix);
+        let is_better = compact_iri.as_ref().map_or(true, |c| {
+            candidate.len() < c.len() || (candidate.len() == c.len() && candidate < *c)
+        });
+        let is_usable = active.term_definition(&candidate).map_or(true, |d| {
+            d.iri_mapping.as_deref() == Some(var) && value.is_none()
+        });
+        if is_better && is_usable {
+            compact_iri = Some(candidate);
+        }
+    }
+    if let Some(compact_iri) = compact_iri {
+        return Ok(compact_iri);
+    }
+    if let Ok(iri) = Iri::parse(var) {
+        if iri.authority().is_none()
+            && active
+                .term_definition(iri.scheme())
+                .map_or(false, |d| d.prefix)
+        {
+            return Err(error(
+                "IRI confused with prefix",
+                format!("{} can't be distinguished from a compact IRI", var),
+            ));
+        }
+    }
+    if !vocab {
+        if let Some(base_iri) = &active.base_iri {
+            if let Some(relative) = relativize(base_iri.as_str(), var) {
+                return Ok(relative);
+            }
+        }
+    }
+    Ok(var.into())
+}
+
+/// Selects the containers and preferred values for the IRI compaction and applies the
+/// [term selection algorithm](https://www.w3.org/TR/json-ld11-api/#term-selection)
+fn select_term_for_value(
+    active: &Context,
+    container_map: &HashMap<String, TypeLanguageMap>,
+    value: Option<&JsonValue>,
+    reverse: bool,
+) -> Option<String> {
+    let object = value.and_then(JsonValue::as_object);
+    let has = |key: &str| object.map_or(false, |o| o.contains_key(key));
+    let default_language = if let Some(direction) = &active.default_base_direction {
+        format!(
+            "{}_{}",
+            active.default_language.as_deref().unwrap_or(""),
+            direction
+        )
+        .to_lowercase()
+    } else {
+        active
+            .default_language
+            .as_deref()
+            .map_or_else(|| "@none".into(), str::to_lowercase)
+    };
+    let mut containers = Vec::<&str>::new();
+    let mut type_language = "@language";
+    let mut type_language_value = "@null".to_owned();
+    if has("@index") && !value.map_or(false, is_graph_object) {
+        containers.extend(["@index", "@index@set"]);
+    }
+    if reverse {
+        type_language = "@type";
+        type_language_value = "@reverse".into();
+        containers.push("@set");
+    } else if let Some(list) = value.filter(|v| is_list_object(v)).and_then(|v| {
+        v.as_object()
+            .and_then(|v| v.get("@list"))
+            .map(JsonValue::as_slice)
+    }) {
+        if !has("@index") {
+            containers.push("@list");
+        }
+        let mut common_type: Option<String> = None;
+        let mut common_language = if list.is_empty() {
+            Some(default_language.clone())
+        } else {
+            None
+        };
+        for item in list {
+            let mut item_language = "@none".to_owned();
+            let mut item_type = "@none".to_owned();
+            if let (true, Some(item)) = (is_value_object(item), item.as_object()) {
+                if let Some(direction) = item.get("@direction").and_then(JsonValue::as_str) {
+                    item_language = format!(
+                        "{}_{}",
+                        item.get("@language")
+                            .and_then(JsonValue::as_str)
+                            .unwrap_or(""),
+                        direction
+                    )
+                    .to_lowercase();
+                } else if let Some(language) = item.get("@language").and_then(JsonValue::as_str) {
+                    item_language = language.to_lowercase();
+                } else if let Some(t) = item.get("@type").and_then(JsonValue::as_str) {
+                    item_type = t.into();
+                } else {
+                    item_language = "@null".into();
+                }
+            } else {
+                item_type = "@id".into();
+            }
+            match &common_language {
+                None => common_language = Some(item_language),
+                Some(language) if *language != item_language && is_value_object(item) => {
+                    common_language = Some("@none".into())
+                }
+                _ => (),
+            }
+            match &common_type {
+                None => common_type = Some(item_type),
+                Some(t) if *t != item_type => common_type = Some("@none".into()),
+                _ => (),
+            }
+            if common_language.as_deref() == Some("@none")
+                && common_type.as_deref() == Some("@none")
+            {
+                break;
+            }
+        }
+        let common_language = common_language.unwrap_or_else(|| "@none".into());
+        let common_type = common_type.unwrap_or_else(|| "@none".into());
+        if common_type == "@none" {
+            type_language_value = common_language;
+        } else {
+            type_language = "@type";
+            type_language_value = common_type;
+        }
+    } else if value.map_or(false, is_graph_object) {
+        if has("@index") {
+            containers.extend(["@graph@index", "@graph@index@set"]);
+        }
+        if has("@id") {
+            containers.extend(["@graph@id", "@graph@id@set"]);
+        }
+        containers.extend(["@graph", "@graph@set", "@set"]);
+        if !has("@index") {
+            containers.extend(["@graph@index", "@graph@index@set"]);
+        }
+        if !has("@id") {
+            containers.extend(["@graph@id", "@graph@id@set"]);
+        }
+        containers.extend(["@index", "@index@set"]);
+        type_language = "@type";
+        type_language_value = "@id".into();
+    } else {
+        if let (true, Some(object)) = (value.map_or(false, is_value_object), object) {
+            let language = object.get("@language").and_then(JsonValue::as_str);
+            if let (Some(direction), false) = (
+                object.get("@direction").and_then(JsonValue::as_str),
+                has("@index"),
+            ) {
+                type_language_value =
+                    format!("{}_{}", language.unwrap_or(""), direction).to_lowercase();
+                containers.extend(["@language", "@language@set"]);
+            } else if let (Some(language), false) = (language, has("@index")) {
+                type_language_value = language.to_lowercase();
+                containers.extend(["@language", "@language@set"]);
+            } else if let Some(t) = object.get("@type").and_then(JsonValue::as_str) {
+                type_language_value = t.into();
+                type_language = "@type";
+            }
+        } else {
+            type_language = "@type";
+            type_language_value = "@id".into();
+            containers.extend(["@id", "@id@set", "@type", "@set@type"]);
+        }
+        containers.push("@set");
+    }
+    containers.push("@none");
+    if !has("@index") {
+        containers.extend(["@index", "@index@set"]);
+    }
+    if object.map_or(false, |o| o.len() == 1 && o.contains_key("@value")) {
+        containers.extend(["@language", "@language@set"]);
+    }
+
+    let mut preferred_values = Vec::new();
+    if type_language_value == "@reverse" {
+        preferred_values.push("@reverse".to_owned());
+    }
+    let id = object
+        .and_then(|o| o.get("@id"))
+        .and_then(JsonValue::as_str);
+    match (type_language_value.as_str(), id) {
+        ("@id" | "@reverse", Some(id)) => {
+            let compacted = compact_iri(active, id, None, true, false).ok();
+            if compacted
+                .and_then(|c| active.term_definition(&c))
+                .map_or(false, |d| d.iri_mapping.as_deref() == Some(id))
+            {
+                preferred_values.extend(["@vocab".into(), "@id".into(), "@none".into()]);
+            } else {
+                preferred_values.extend(["@id".into(), "@vocab".into(), "@none".into()]);
+            }
+        }
+        _ => {
+            preferred_values.push(type_language_value.clone());
+            preferred_values.push("@none".into());
+            if value
+                .and_then(JsonValue::as_object)
+                .and_then(|v| v.get("@list"))
+                .map_or(false, |l| l.as_slice().is_empty())
+            {
+                type_language = "@any";
+            }
+        }
+    }
+    preferred_values.push("@any".into());
+    if let Some(direction) = preferred_values
+        .iter()
+        .find_map(|v| v.find('_').map(|i| v[i..].to_owned()))
+    {
+        preferred_values.push(direction);
+    }
+
+    for container in containers {
+        if let Some(type_language_map) = container_map.get(container) {
+            let value_map = type_language_map.get(type_language);
+            for item in &preferred_values {
+                if let Some(term) = value_map.get(item) {
+                    return Some(term.clone());
+                }
+            }
+        }
+    }
+    None
+}
+
+pub fn alias(active: &Context, keyword: &str) -> io::Result<String> {
+    compact_iri(active, keyword, None, true, false)
+}
+
+/// The [value compaction algorithm](https://www.w3.org/TR/json-ld11-api/#value-compaction)
+fn compact_value(
+    active: &Context,
+    active_property: Option<&str>,
+    value: &JsonObject,
+) -> io::Result<JsonValue> {
+    let definition = active_property.and_then(|p| active.term_definition(p));
+    let language = match definition.and_then(|d| d.language.as_ref()) {
+        Some(language) => language.as_deref(),
+        None => active.default_language.as_deref(),
+    };
+    let direction = match definition.and_then(|d| d.direction.as_ref()) {
+        Some(direction) => direction.as_deref(),
+        None => active.default_base_direction.as_deref(),
+    };
+    let type_mapping = definition.and_then(|d| d.type_mapping.as_deref());
+    let has_index_container = active.has_container(active_property, "@index");
+    if let Some(id) = value.get("@id").and_then(JsonValue::as_str) {
+        if value.keys().all(|k| k == "@id" || k == "@index") {
+            match type_mapping {
+                Some("@id") => return Ok(compact_iri(active, id, None, false, false)?.into()),
+                Some("@vocab") => return Ok(compact_iri(active, id, None, true, false)?.into()),
+                _ => (),
+            }
+        }
+    }
+    let mut result = value.clone();
+    let value_type = value.get("@type");
+    let inner = value.get("@value");
+    if value_type.is_some() && value_type.and_then(JsonValue::as_str) == type_mapping {
+        if let Some(inner) = inner {
+            if !value.contains_key("@index") || has_index_container {
+                return Ok(inner.clone());
+            }
+            result.remove("@type");
+        }
+    } else if type_mapping == Some("@none") || value_type.is_some() {
+        if let Some(value_type) = result.get_mut("@type") {
+            *value_type = match value_type {
+                JsonValue::Array(types) => JsonValue::Array(
+                    types
+                        .iter()
+                        .map(|t| {
+                            Ok(t.as_str()
+                                .map(|t| compact_iri(active, t, None, true, false))
+                                .transpose()?
+                                .map_or_else(|| t.clone(), JsonValue::String))
+                        })
+                        .collect::<io::Result<_>>()?,
+                ),
+                JsonValue::String(t) => compact_iri(active, t, None, true, false)?.into(),
+                _ => value_type.clone(),
+            };
+        }
+    } else if let Some(inner) = inner {
+        if !matches!(inner, JsonValue::String(_)) {
+            if !value.contains_key("@index") || has_index_container {
+                return Ok(inner.clone());
+            }
+        } else if value
+            .get("@language")
+            .and_then(JsonValue::as_str)
+            .map(str::to_lowercase)
+            == language.map(str::to_lowercase)
+            && value.get("@direction").and_then(JsonValue::as_str) == direction
+            && (!value.contains_key("@index") || has_index_container)
+        {
+            return Ok(inner.clone());
+        }
+    }
+    let mut aliased = JsonObject::new();
+    for (key, value) in result {
+        if key == "@index" && has_index_container {
+            continue;
+        }
+        aliased.insert(alias(active, &key)?, value);
+    }
+    Ok(JsonValue::Object(aliased))
+}
+
+/// Compacts a full expanded document against a context.
+///
+/// If `force_graph` is set the nodes are always in a top-level `@graph` array.
+pub fn compact(
+    processor: &Processor,
+    expanded: &JsonValue,
+    context: &JsonValue,
+    force_graph: bool,
+) -> io::Result<JsonValue> {
+    let context = local_context(context);
+    let active =
+        processor.process_context(&Context::default(), context, None, &[], false, true, true)?;
+    let compacted = compact_element(processor, &active, None, expanded)?;
+    let mut result = match compacted {
+        JsonValue::Array(values) if values.is_empty() && !force_graph => JsonObject::new(),
+        JsonValue::Array(values) => {
+            JsonObject::from([(alias(&active, "@graph")?, JsonValue::Array(values))])
+        }
+        JsonValue::Object(object) if force_graph => JsonObject::from([(
+            alias(&active, "@graph")?,
+            JsonValue::Array(vec![object.into()]),
+        )]),
+        JsonValue::Object(object) => object,
+        _ => JsonObject::new(),
+    };
+    if !is_empty_context(context) {
+        result.insert("@context".into(), context.clone());
+    }
+    Ok(JsonValue::Object(result))
+}
+
+/// The context to compact with: the `@context` entry if a full JSON-LD document is given
+pub fn local_context(context: &JsonValue) -> &JsonValue {
+    context
+        .as_object()
+        .and_then(|c| c.get("@context"))
+        .unwrap_or(context)
+}
+
+/// Returns `true` if the context is not worth writing in the compacted document
+pub fn is_empty_context(context: &JsonValue) -> bool {
+    match context {
+        JsonValue::Null => true,
+        JsonValue::Array(values) => values.is_empty(),
+        JsonValue::Object(values) => values.is_empty(),
+        _ => false,
+    }
+}
+
+/// The [compaction algorithm](https://www.w3.org/TR/json-ld11-api/#compaction-algorithm)
+pub fn compact_element(
+    processor: &Processor,
+    active: &Context,
+    active_property: Option<&str>,
+    element: &JsonValue,
+) -> io::Result<JsonValue> {
+    let element = match element {
+        JsonValue::Array(elements) => {
+            let mut result = Vec::new();
+            for element in elements {
+                let compacted = compact_element(processor, active, active_property, element)?;
+                if !compacted.is_null() {
+                    result.push(compacted);
+                }
+            }
+            let keep_array = result.len() != 1
+                || matches!(active_property, Some("@graph" | "@set"))
+                || active.has_container(active_property, "@list")
+                || active.has_container(active_property, "@set");
+            return Ok(if keep_array {
+                JsonValue::Array(result)
+            } else {
+                result.pop().unwrap_or(JsonValue::Null)
+            });
+        }
+        JsonValue::Object(element) => element,
+        element => return Ok(element.clone()),
+    };
+
+    let mut active = Cow::Borrowed(active);
+    let type_scoped = active.clone();
+    if let Some(previous_context) = active.previous_context.clone() {
+        if !(element.contains_key("@value") || element.len() == 1 && element.contains_key("@id")) {
+            active = Cow::Owned(Context::clone(&previous_context));
+        }
+    }
+    let property_definition = active_property.and_then(|p| type_scoped.term_definition(p));
+    if let Some(definition) = property_definition {
+        if let Some(context) = &definition.context {
+            active = Cow::Owned(processor.process_context(
+                &active,
+                context,
+                definition.base_url.as_ref(),
+                &[],
+                true,
+                true,
+                true,
+            )?);
+        }
+    }
+    if element.contains_key("@value") || element.contains_key("@id") {
+        let result = compact_value(&active, active_property, element)?;
+        if result.is_scalar()
+            || active_property
+                .and_then(|p| active.term_definition(p))
+                .map_or(false, |d| d.type_mapping.as_deref() == Some("@json"))
+        {
+            return Ok(result);
+        }
+    }
+    if let Some(list) = element.get("@list") {
+        if active.has_container(active_property, "@list") {
+            return compact_element(processor, &active, active_property, list);
+        }
+    }
+    let inside_reverse = active_property == Some("@reverse");
+    let mut result = JsonObject::new();
+
+    if let Some(types) = element.get("@type") {
+        let mut compacted_types = types
+            .as_slice()
+            .iter()
+            .filter_map(JsonValue::as_str)
+            .map(|t| compact_iri(&type_scoped, t, None, true, false))
+            .collect::<io::Result<Vec<_>>>()?;
+        compacted_types.sort_unstable();
+        for t in compacted_types {
+            if let Some(definition) = type_scoped.term_definition(&t) {
+                if let Some(context) = &definition.context {
+                    active = Cow::Owned(processor.process_context(
+                        &active,
+                        context,
+                        definition.base_url.as_ref(),
+                        &[],
+                        false,
+                        false,
+                        true,
+                    )?);
+                }
+            }
+        }
+    }
+
+    for (expanded_property, expanded_value) in element {
+        match expanded_property.as_str() {
+            "@id" => {
+                let compacted = if let JsonValue::String(id) = expanded_value {
+                    compact_iri(&active, id, None, false, false)?.into()
+                } else {
+                    expanded_value.clone()
+                };
+                result.insert(alias(&active, "@id")?, compacted);
+                continue;
+            }
+            "@type" => {
+                let compacted = match expanded_value {
+                    JsonValue::Array(types) => JsonValue::Array(
+                        types
+                            .iter()
+                            .map(|t| {
+                                Ok(match t {
+                                    JsonValue::String(t) => {
+                                        compact_iri(&type_scoped, t, None, true, false)?.into()
+                                    }
+                                    t => t.clone(),
+                                })
+                            })
+                            .collect::<io::Result<_>>()?,
+                    ),
+                    JsonValue::String(t) => compact_iri(&type_scoped, t, None, true, false)?.into(),
+                    t => t.clone(),
+                };
+                let alias = alias(&active, "@type")?;
+                let as_array = active.has_container(Some(&alias), "@set");
+                add_value(&mut result, &alias, compacted, as_array);
+                continue;
+            }
+            "@reverse" => {
+                if let JsonValue::Object(mut compacted) =
+                    compact_element(processor, &active, Some("@reverse"), expanded_value)?
+                {
+                    let reverse_properties = compacted
+                        .keys()
+                        .filter(|p| active.term_definition(p).map_or(false, |d| d.reverse))
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    for property in reverse_properties {
+                        if let Some(value) = compacted.remove(&property) {
+                            let as_array = active.has_container(Some(&property), "@set");
+                            add_value(&mut result, &property, value, as_array);
+                        }
+                    }
+                    if !compacted.is_empty() {
+                        result.insert(alias(&active, "@reverse")?, compacted.into());
+                    }
+                }
+                continue;
+            }
+            "@index" if active.has_container(active_property, "@index") => continue,
+            "@direction" | "@index" | "@language" | "@value" => {
+                result.insert(alias(&active, expanded_property)?, expanded_value.clone());
+                continue;
+            }
+            _ => (),
+        }
+
+        if let JsonValue::Array(values) = expanded_value {
+            if values.is_empty() {
+                let item_active_property = compact_iri(
+                    &active,
+                    expanded_property,
+                    Some(expanded_value),
+                    true,
+                    inside_reverse,
+                )?;
+                let nest_result = nest_result(&active, &mut result, &item_active_property)?;
+                add_value(
+                    nest_result,
+                    &item_active_property,
+                    JsonValue::Array(Vec::new()),
+                    true,
+                );
+            }
+        }
+
+        for expanded_item in expanded_value.as_slice() {
+            let item_active_property = compact_iri(
+                &active,
+                expanded_property,
+                Some(expanded_item),
+                true,
+                inside_reverse,
+            )?;
+            let definition = active.term_definition(&item_active_property);
+            let has_container =
+                |container: &str| definition.map_or(false, |d| d.has_container(container));
+            let as_array = has_container("@set")
+                || item_active_property == "@graph"
+                || item_active_property == "@list";
+            let item = expanded_item.as_object();
+            let inner = if is_list_object(expanded_item) {
+                item.and_then(|i| i.get("@list"))
+            } else if is_graph_object(expanded_item) {
+                item.and_then(|i| i.get("@graph"))
+            } else {
+                None
+            };
+            let mut compacted_item = compact_element(
+                processor,
+                &active,
+                Some(&item_active_property),
+                inner.unwrap_or(expanded_item),
+            )?;
+            let index = item.and_then(|i| i.get("@index"));
+            let index_container_key = definition
+                .and_then(|d| d.index.clone())
+                .unwrap_or_else(|| "@index".into());
+            let nest_result = nest_result(&active, &mut result, &item_active_property)?;
+
+            if is_list_object(expanded_item) {
+                compacted_item = JsonValue::Array(compacted_item.into_array());
+                if has_container("@list") {
+                    nest_result.insert(item_active_property, compacted_item);
+                } else {
+                    let mut list = JsonObject::from([(alias(&active, "@list")?, compacted_item)]);
+                    if let Some(index) = index {
+                        list.insert(alias(&active, "@index")?, index.clone());
+                    }
+                    add_value(nest_result, &item_active_property, list.into(), as_array);
+                }
+            } else if is_graph_object(expanded_item) {
+                let id = item.and_then(|i| i.get("@id")).and_then(JsonValue::as_str);
+                if has_container("@graph") && has_container("@id") {
+                    let map_key = if let Some(id) = id {
+                        compact_iri(&active, id, None, false, false)?
+                    } else {
+                        alias(&active, "@none")?
+                    };
+                    let map_object = map_object(nest_result, &item_active_property);
+                    add_value(map_object, &map_key, compacted_item, as_array);
+                } else if has_container("@graph")
+                    && has_container("@index")
+                    && is_simple_graph_object(expanded_item)
+                {
+                    let map_key = if let Some(JsonValue::String(index)) = index {
+                        index.clone()
+                    } else {
+                        alias(&active, "@none")?
+                    };
+                    let map_object = map_object(nest_result, &item_active_property);
+                    add_value(map_object, &map_key, compacted_item, as_array);
+                } else if has_container("@graph") && is_simple_graph_object(expanded_item) {
+                    if matches!(&compacted_item, JsonValue::Array(items) if items.len() > 1) {
+                        compacted_item =
+                            JsonObject::from([(alias(&active, "@included")?, compacted_item)])
+                                .into();
+                    }
+                    add_value(nest_result, &item_active_property, compacted_item, as_array);
+                } else {
+                    let mut graph = JsonObject::from([(alias(&active, "@graph")?, compacted_item)]);
+                    if let Some(id) = id {
+                        graph.insert(
+                            alias(&active, "@id")?,
+                            compact_iri(&active, id, None, false, false)?.into(),
+                        );
+                    }
+                    if let Some(index) = index {
+                        graph.insert(alias(&active, "@index")?, index.clone());
+                    }
+                    add_value(nest_result, &item_active_property, graph.into(), as_array);
+                }
+            } else if (has_container("@language")
+                || has_container("@index")
+                || has_container("@id")
+                || has_container("@type"))
+                && !has_container("@graph")
+            {
+                let mut map_key = None;
+                if has_container("@language") {
+                    if let Some(value) = item.and_then(|i| i.get("@value")) {
+                        compacted_item = value.clone();
+                    }
+                    map_key = item
+                        .and_then(|i| i.get("@language"))
+                        .and_then(JsonValue::as_str)
+                        .map(ToOwned::to_owned);
+                } else if has_container("@index") && index_container_key == "@index" {
+                    map_key = index.and_then(JsonValue::as_str).map(ToOwned::to_owned);
+                } else if has_container("@index") {
+                    let container_key = alias(&active, &index_container_key)?;
+                    map_key = take_first_string(&mut compacted_item, &container_key);
+                } else if has_container("@id") {
+                    let container_key = alias(&active, "@id")?;
+                    if let Some(compacted) = compacted_item.as_object_mut() {
+                        map_key = compacted
+                            .remove(&container_key)
+                            .and_then(|k| k.as_str().map(ToOwned::to_owned));
+                    }
+                } else {
+                    let container_key = alias(&active, "@type")?;
+                    map_key = take_first_string(&mut compacted_item, &container_key);
+                    let id_alias = alias(&active, "@id")?;
+                    if compacted_item
+                        .as_object()
+                        .map_or(false, |c| c.len() == 1 && c.contains_key(&id_alias))
+                    {
+                        if let Some(id) = item.and_then(|i| i.get("@id")) {
+                            compacted_item = compact_element(
+                                processor,
+                                &active,
+                                Some(&item_active_property),
+                                &JsonObject::from([("@id".to_owned(), id.clone())]).into(),
+                            )?;
+                        }
+                    }
+                }
+                let map_key = if let Some(map_key) = map_key {
+                    map_key
+                } else {
+                    alias(&active, "@none")?
+                };
+                let map_object = map_object(nest_result, &item_active_property);
+                add_value(map_object, &map_key, compacted_item, as_array);
+            } else {
+                add_value(nest_result, &item_active_property, compacted_item, as_array);
+            }
+        }
+    }
+    Ok(JsonValue::Object(result))
+}
+
+/// The object a compacted property should be added to, taking care of `@nest`
+fn nest_result<'b>(
+    active: &Context,
+    result: &'b mut JsonObject,
+    item_active_property: &str,
+) -> io::Result<&'b mut JsonObject> {
+    let nest_term = if let Some(nest_term) = active
+        .term_definition(item_active_property)
+        .and_then(|d| d.nest.as_ref())
+    {
+        nest_term
+    } else {
+        return Ok(result);
+    };
+    if nest_term != "@nest" && active.expand_iri(nest_term, false, true).as_deref() != Some("@nest")
+    {
+        return Err(error(
+            "invalid @nest value",
+            format!("{} does not expand to @nest", nest_term),
+        ));
+    }
+    Ok(map_object(result, nest_term))
+}
+
+/// The map stored in the `key` entry of `object`, created if needed
+fn map_object<'b>(object: &'b mut JsonObject, key: &str) -> &'b mut JsonObject {
+    let entry = object
+        .entry(key.into())
+        .or_insert_with(|| JsonValue::Object(JsonObject::new()));
+    if !matches!(entry, JsonValue::Object(_)) {
+        *entry = JsonValue::Object(JsonObject::new());
+    }
+    match entry {
+        JsonValue::Object(entry) => entry,
+        _ => unreachable!(),
+    }
+}
+
+/// Removes the first string value of the `key` entry of an object and returns it
+fn take_first_string(object: &mut JsonValue, key: &str) -> Option<String> {
+    let object = object.as_object_mut()?;
+    let mut values = object.remove(key)?.into_array();
+    let position = values.iter().position(|v| v.as_str().is_some())?;
+    let first = values.remove(position).as_str().map(ToOwned::to_owned);
+    match values.len() {
+        0 => (),
+        1 => {
+            object.insert(key.into(), values.remove(0));
+        }
+        _ => {
+            object.insert(key.into(), JsonValue::Array(values));
+        }
+    }
+    first
+}
+
+/// Makes an IRI relative to a base IRI if it is in the same directory
+fn relativize(base: &str, iri: &str) -> Option<String> {
+    if let Some(fragment) = iri.strip_prefix(base) {
+        if fragment.starts_with('#') {
+            return Some(fragment.into());
+        }
+    }
+    let path_end = base.find(['?', '#']).unwrap_or(base.len());
+    let base = &base[..=base[..path_end].rfind('/')?];
+    let relative = iri.strip_prefix(base)?;
+    let first_segment = relative.split(['/', '?', '#']).next()?;
+    if relative.is_empty() || first_segment.contains(':') || base.ends_with("//") {
+        return None;
+    }
+    Some(relative.into())
+}
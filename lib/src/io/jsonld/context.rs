@@ -0,0 +1,962 @@
+use crate::io::jsonld::compact::InverseContext;
+use crate::io::jsonld::json::{read_json, JsonObject, JsonValue};
+use crate::io::jsonld::{error, has_keyword_form, is_absolute_iri, is_keyword, DocumentLoader};
+use oxiri::Iri;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io;
+use std::sync::{Arc, Mutex};
+
+/// Maximal number of nested remote contexts
+const MAX_REMOTE_CONTEXTS: usize = 32;
+
+/// A JSON-LD [active context](https://www.w3.org/TR/json-ld11-api/#dfn-active-context)
+#[derive(Default)]
+pub struct Context {
+    pub base_iri: Option<Iri<String>>,
+    pub original_base_url: Option<Iri<String>>,
+    pub vocabulary_mapping: Option<String>,
+    pub default_language: Option<String>,
+    pub default_base_direction: Option<String>,
+    pub term_definitions: Arc<HashMap<String, TermDefinition>>,
+    pub previous_context: Option<Arc<Self>>,
+    /// Cache of the inverse context used by the compaction
+    pub inverse: Mutex<Option<Arc<InverseContext>>>,
+}
+
+impl Clone for Context {
+    /// Clones the context without its inverse context cache
+    fn clone(&self) -> Self {
+        Self {
+            base_iri: self.base_iri.clone(),
+            original_base_url: self.original_base_url.clone(),
+            vocabulary_mapping: self.vocabulary_mapping.clone(),
+            default_language: self.default_language.clone(),
+            default_base_direction: self.default_base_direction.clone(),
+            term_definitions: self.term_definitions.clone(),
+            previous_context: self.previous_context.clone(),
+            inverse: Mutex::default(),
+        }
+    }
+}
+
+/// A JSON-LD [term definition](https://www.w3.org/TR/json-ld11-api/#dfn-term-definition)
+#[derive(Clone, Default)]
+pub struct TermDefinition {
+    /// The IRI the term is mapped to, `None` if the term is mapped to `null`
+    pub iri_mapping: Option<String>,
+    pub prefix: bool,
+    pub protected: bool,
+    pub reverse: bool,
+    pub base_url: Option<Iri<String>>,
+    /// The term-scoped context
+    pub context: Option<JsonValue>,
+    pub container: Vec<String>,
+    /// The direction mapping, `Some(None)` if it is explicitly set to `null`
+    pub direction: Option<Option<String>>,
+    pub index: Option<String>,
+    /// The language mapping, `Some(None)` if it is explicitly set to `null`
+    pub language: Option<Option<String>>,
+    pub nest: Option<String>,
+    pub type_mapping: Option<String>,
+}
+
+impl TermDefinition {
+    pub fn has_container(&self, container: &str) -> bool {
+        self.container.iter().any(|c| c == container)
+    }
+
+    /// Compares the definitions ignoring the `protected` flag
+    fn is_same_as(&self, other: &Self) -> bool {
+        self.iri_mapping == other.iri_mapping
+            && self.prefix == other.prefix
+            && self.reverse == other.reverse
+            && self.base_url.as_ref().map(Iri::as_str) == other.base_url.as_ref().map(Iri::as_str)
+            && self.context == other.context
+            && self.container == other.container
+            && self.direction == other.direction
+            && self.index == other.index
+            && self.language == other.language
+            && self.nest == other.nest
+            && self.type_mapping == other.type_mapping
+    }
+}
+
+impl Context {
+    pub fn new(base_iri: Option<Iri<String>>) -> Self {
+        Self {
+            original_base_url: base_iri.clone(),
+            base_iri,
+            ..Self::default()
+        }
+    }
+
+    pub fn term_definition(&self, term: &str) -> Option<&TermDefinition> {
+        self.term_definitions.get(term)
+    }
+
+    /// Returns `true` if the term has a definition with this container
+    pub fn has_container(&self, term: Option<&str>, container: &str) -> bool {
+        term.and_then(|term| self.term_definition(term))
+            .map_or(false, |d| d.has_container(container))
+    }
+
+    /// The [IRI expansion algorithm](https://www.w3.org/TR/json-ld11-api/#iri-expansion), returns `None` for `null`
+    pub fn expand_iri(&self, value: &str, document_relative: bool, vocab: bool) -> Option<String> {
+        if is_keyword(value) {
+            return Some(value.into());
+        }
+        if has_keyword_form(value) {
+            return None;
+        }
+        if let Some(definition) = self.term_definition(value) {
+            if let Some(mapping) = &definition.iri_mapping {
+                if is_keyword(mapping) {
+                    return Some(mapping.clone());
+                }
+            }
+            if vocab {
+                return definition.iri_mapping.clone();
+            }
+        }
+        if let Some((prefix, suffix)) = split_prefix(value) {
+            if prefix == "_" || suffix.starts_with("//") {
+                return Some(value.into());
+            }
+            if let Some(definition) = self.term_definition(prefix) {
+                if let Some(mapping) = &definition.iri_mapping {
+                    if definition.prefix {
+                        return Some(format!("{}{}", mapping, suffix));
+                    }
+                }
+            }
+            if is_absolute_iri(value) {
+                return Some(value.into());
+            }
+        }
+        if vocab {
+            if let Some(vocabulary_mapping) = &self.vocabulary_mapping {
+                return Some(format!("{}{}", vocabulary_mapping, value));
+            }
+        }
+        if document_relative {
+            if let Some(base_iri) = &self.base_iri {
+                if let Ok(iri) = base_iri.resolve(value) {
+                    return Some(iri.into_inner());
+                }
+            }
+        }
+        Some(value.into())
+    }
+}
+
+/// Splits a compact IRI at its first colon that is not the first character
+pub fn split_prefix(value: &str) -> Option<(&str, &str)> {
+    let position = value.get(1..)?.find(':')? + 1;
+    Some((&value[..position], &value[position + 1..]))
+}
+
+/// State of the creation of the term definitions of a local context
+struct Definitions<'b> {
+    local: &'b JsonObject,
+    /// `false` for the terms being defined, `true` for the defined ones
+    defined: HashMap<String, bool>,
+    base_url: Option<&'b Iri<String>>,
+    protected: bool,
+    override_protected: bool,
+    remote_contexts: &'b [String],
+}
+
+/// Shared state of the JSON-LD algorithms: the document loader and the already loaded documents
+pub struct Processor {
+    loader: Option<Arc<dyn DocumentLoader>>,
+    documents: RefCell<HashMap<String, Arc<JsonValue>>>,
+}
+
+impl Processor {
+    pub fn new(loader: Option<Arc<dyn DocumentLoader>>) -> Self {
+        Self {
+            loader,
+            documents: RefCell::default(),
+        }
+    }
+
+    /// Loads a remote JSON document
+    fn load_document(&self, url: &str) -> Result<Arc<JsonValue>, io::Error> {
+        if let Some(document) = self.documents.borrow().get(url) {
+            return Ok(document.clone());
+        }
+        let loader = self.loader.as_ref().ok_or_else(|| {
+            error(
+                "loading remote context failed",
+                format!(
+                    "{} can't be loaded because no document loader has been provided",
+                    url
+                ),
+            )
+        })?;
+        let content = loader.load_document(url).map_err(|e| {
+            error(
+                "loading remote context failed",
+                format!("{} can't be loaded: {}", url, e),
+            )
+        })?;
+        let document = Arc::new(read_json(content.as_slice()).map_err(|e| {
+            error(
+                "loading document failed",
+                format!("{} is not a valid JSON document: {}", url, e),
+            )
+        })?);
+        self.documents
+            .borrow_mut()
+            .insert(url.into(), document.clone());
+        Ok(document)
+    }
+
+    /// The [context processing algorithm](https://www.w3.org/TR/json-ld11-api/#context-processing-algorithm)
+    #[allow(clippy::too_many_arguments)]
+    pub fn process_context(
+        &self,
+        active: &Context,
+        local: &JsonValue,
+        base_url: Option<&Iri<String>>,
+        remote_contexts: &[String],
+        override_protected: bool,
+        mut propagate: bool,
+        validate_scoped_context: bool,
+    ) -> io::Result<Context> {
+        let mut result = active.clone();
+        if let JsonValue::Object(local) = local {
+            if let Some(value) = local.get("@propagate") {
+                if let JsonValue::Boolean(value) = value {
+                    propagate = *value;
+                } else {
+                    return Err(error(
+                        "invalid @propagate value",
+                        "@propagate must be a boolean",
+                    ));
+                }
+            }
+        }
+        if !propagate && result.previous_context.is_none() {
+            result.previous_context = Some(Arc::new(active.clone()));
+        }
+        for context in local.as_slice() {
+            match context {
+                JsonValue::Null => {
+                    if !override_protected && result.term_definitions.values().any(|d| d.protected)
+                    {
+                        return Err(error(
+                            "invalid context nullification",
+                            "a context with protected terms can't be set to null",
+                        ));
+                    }
+                    let previous = result;
+                    result = Context::new(active.original_base_url.clone());
+                    if !propagate {
+                        result.previous_context = Some(Arc::new(previous));
+                    }
+                }
+                JsonValue::String(context) => {
+                    let context = if let Some(base_url) = base_url {
+                        base_url
+                            .resolve(context)
+                            .map_err(|e| {
+                                error(
+                                    "loading document failed",
+                                    format!("invalid context IRI {}: {}", context, e),
+                                )
+                            })?
+                            .into_inner()
+                    } else if is_absolute_iri(context) {
+                        context.clone()
+                    } else {
+                        return Err(error(
+                            "loading document failed",
+                            format!("the context IRI {} is relative", context),
+                        ));
+                    };
+                    if !validate_scoped_context && remote_contexts.contains(&context) {
+                        continue;
+                    }
+                    if remote_contexts.len() >= MAX_REMOTE_CONTEXTS {
+                        return Err(error(
+                            "context overflow",
+                            format!("too many nested remote contexts when loading {}", context),
+                        ));
+                    }
+                    let mut remote_contexts = remote_contexts.to_vec();
+                    remote_contexts.push(context.clone());
+                    let document = self.load_document(&context)?;
+                    let loaded_context = document
+                        .as_object()
+                        .and_then(|document| document.get("@context"))
+                        .ok_or_else(|| {
+                            error(
+                                "invalid remote context",
+                                format!("{} is not a JSON-LD context document", context),
+                            )
+                        })?;
+                    let context_url = Iri::parse(context.clone()).ok();
+                    result = self.process_context(
+                        &result,
+                        loaded_context,
+                        context_url.as_ref(),
+                        &remote_contexts,
+                        false,
+                        true,
+                        validate_scoped_context,
+                    )?;
+                }
+                JsonValue::Object(context) => {
+                    self.process_context_definition(
+                        &mut result,
+                        context,
+                        base_url,
+                        remote_contexts,
+                        override_protected,
+                    )?;
+                }
+                _ => {
+                    return Err(error(
+                        "invalid local context",
+                        "a context must be null, a string, an object or an array",
+                    ))
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    fn process_context_definition(
+        &self,
+        result: &mut Context,
+        context: &JsonObject,
+        base_url: Option<&Iri<String>>,
+        remote_contexts: &[String],
+        override_protected: bool,
+    ) -> io::Result<()> {
+        if let Some(version) = context.get("@version") {
+            if !matches!(version, JsonValue::Number(v) if v == "1.1") {
+                return Err(error(
+                    "invalid @version value",
+                    "@version must be the number 1.1",
+                ));
+            }
+        }
+        let imported;
+        let mut context = context;
+        if let Some(import) = context.get("@import") {
+            let import = import
+                .as_str()
+                .ok_or_else(|| error("invalid @import value", "@import must be a string"))?;
+            let import = if let Some(base_url) = base_url {
+                base_url.resolve(import).map(Iri::into_inner)
+            } else {
+                Iri::parse(import.to_owned()).map(Iri::into_inner)
+            }
+            .map_err(|e| error("invalid @import value", e))?;
+            let document = self.load_document(&import)?;
+            let import_context = document
+                .as_object()
+                .and_then(|document| document.get("@context"))
+                .and_then(JsonValue::as_object)
+                .ok_or_else(|| {
+                    error(
+                        "invalid remote context",
+                        format!("{} does not contain a JSON-LD context object", import),
+                    )
+                })?;
+            if import_context.contains_key("@import") {
+                return Err(error(
+                    "invalid context entry",
+                    "an imported context can't contain @import",
+                ));
+            }
+            let mut merged = import_context.clone();
+            for (key, value) in context {
+                merged.insert(key.clone(), value.clone());
+            }
+            imported = merged;
+            context = &imported;
+        }
+        if remote_contexts.is_empty() {
+            if let Some(value) = context.get("@base") {
+                match value {
+                    JsonValue::Null => result.base_iri = None,
+                    JsonValue::String(value) => {
+                        result.base_iri = Some(if let Ok(iri) = Iri::parse(value.clone()) {
+                            iri
+                        } else if let Some(base_iri) = &result.base_iri {
+                            base_iri
+                                .resolve(value)
+                                .map_err(|e| error("invalid base IRI", e))?
+                        } else {
+                            return Err(error(
+                                "invalid base IRI",
+                                format!("the relative IRI {} can't be resolved", value),
+                            ));
+                        })
+                    }
+                    _ => return Err(error("invalid base IRI", "@base must be a string")),
+                }
+            }
+        }
+        if let Some(value) = context.get("@vocab") {
+            match value {
+                JsonValue::Null => result.vocabulary_mapping = None,
+                JsonValue::String(value) => {
+                    let vocab = result.expand_iri(value, true, true);
+                    match vocab {
+                        Some(vocab) if is_absolute_iri(&vocab) || vocab.starts_with("_:") => {
+                            result.vocabulary_mapping = Some(vocab)
+                        }
+                        _ => {
+                            return Err(error(
+                                "invalid vocab mapping",
+                                format!("{} is not an IRI", value),
+                            ))
+                        }
+                    }
+                }
+                _ => return Err(error("invalid vocab mapping", "@vocab must be a string")),
+            }
+        }
+        if let Some(value) = context.get("@language") {
+            result.default_language = match value {
+                JsonValue::Null => None,
+                JsonValue::String(value) => Some(value.clone()),
+                _ => {
+                    return Err(error(
+                        "invalid default language",
+                        "@language must be a string",
+                    ))
+                }
+            };
+        }
+        if let Some(value) = context.get("@direction") {
+            result.default_base_direction = match value {
+                JsonValue::Null => None,
+                JsonValue::String(value) if value == "ltr" || value == "rtl" => Some(value.clone()),
+                _ => {
+                    return Err(error(
+                        "invalid base direction",
+                        "@direction must be \"ltr\" or \"rtl\"",
+                    ))
+                }
+            };
+        }
+        if let Some(value) = context.get("@propagate") {
+            if !matches!(value, JsonValue::Boolean(_)) {
+                return Err(error(
+                    "invalid @propagate value",
+                    "@propagate must be a boolean",
+                ));
+            }
+        }
+        let protected = match context.get("@protected") {
+            None => false,
+            Some(JsonValue::Boolean(value)) => *value,
+            Some(_) => {
+                return Err(error(
+                    "invalid @protected value",
+                    "@protected must be a boolean",
+                ))
+            }
+        };
+        let mut definitions = Definitions {
+            local: context,
+            defined: HashMap::new(),
+            base_url,
+            protected,
+            override_protected,
+            remote_contexts,
+        };
+        for term in context.keys() {
+            if !matches!(
+                term.as_str(),
+                "@base"
+                    | "@direction"
+                    | "@import"
+                    | "@language"
+                    | "@propagate"
+                    | "@protected"
+                    | "@version"
+                    | "@vocab"
+            ) {
+                self.create_term_definition(result, term, &mut definitions)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The [create term definition algorithm](https://www.w3.org/TR/json-ld11-api/#create-term-definition)
+    fn create_term_definition(
+        &self,
+        active: &mut Context,
+        term: &str,
+        definitions: &mut Definitions<'_>,
+    ) -> io::Result<()> {
+        match definitions.defined.get(term) {
+            Some(true) => return Ok(()),
+            Some(false) => {
+                return Err(error(
+                    "cyclic IRI mapping",
+                    format!("the definition of {} depends on itself", term),
+                ))
+            }
+            None => (),
+        }
+        if term.is_empty() {
+            return Err(error(
+                "invalid term definition",
+                "the empty string can't be defined",
+            ));
+        }
+        definitions.defined.insert(term.into(), false);
+        let value = &definitions.local[term];
+        if term == "@type" {
+            let valid = value.as_object().map_or(false, |value| {
+                !value.is_empty()
+                    && value.iter().all(|(k, v)| match k.as_str() {
+                        "@container" => v.as_str() == Some("@set"),
+                        "@protected" => true,
+                        _ => false,
+                    })
+            });
+            if !valid {
+                return Err(error(
+                    "keyword redefinition",
+                    "@type can only be given a @set container",
+                ));
+            }
+        } else if is_keyword(term) {
+            return Err(error(
+                "keyword redefinition",
+                format!("the keyword {} can't be redefined", term),
+            ));
+        } else if has_keyword_form(term) {
+            // Terms looking like keywords are ignored
+            return Ok(());
+        }
+        let previous_definition = Arc::make_mut(&mut active.term_definitions).remove(term);
+
+        let null_definition = JsonObject::from([("@id".to_owned(), JsonValue::Null)]);
+        let string_definition;
+        let (value, simple_term) = match value {
+            JsonValue::Null => (&null_definition, false),
+            JsonValue::String(id) => {
+                string_definition = JsonObject::from([("@id".to_owned(), id.as_str().into())]);
+                (&string_definition, true)
+            }
+            JsonValue::Object(value) => (value, false),
+            _ => {
+                return Err(error(
+                    "invalid term definition",
+                    format!("the definition of {} must be a string or an object", term),
+                ))
+            }
+        };
+
+        let mut definition = TermDefinition {
+            protected: match value.get("@protected") {
+                None => definitions.protected,
+                Some(JsonValue::Boolean(value)) => *value,
+                Some(_) => {
+                    return Err(error(
+                        "invalid @protected value",
+                        "@protected must be a boolean",
+                    ))
+                }
+            },
+            ..TermDefinition::default()
+        };
+
+        if let Some(type_mapping) = value.get("@type") {
+            let type_mapping = type_mapping.as_str().ok_or_else(|| {
+                error(
+                    "invalid type mapping",
+                    format!("the @type of {} must be a string", term),
+                )
+            })?;
+            let type_mapping = self
+                .expand_iri_with_definitions(active, type_mapping, false, true, definitions)?
+                .filter(|t| {
+                    matches!(t.as_str(), "@id" | "@json" | "@none" | "@vocab") || is_absolute_iri(t)
+                })
+                .ok_or_else(|| {
+                    error(
+                        "invalid type mapping",
+                        format!("{} is not a valid type for {}", type_mapping, term),
+                    )
+                })?;
+            definition.type_mapping = Some(type_mapping);
+        }
+
+        if let Some(reverse) = value.get("@reverse") {
+            if value.contains_key("@id") || value.contains_key("@nest") {
+                return Err(error(
+                    "invalid reverse property",
+                    format!("the reverse property {} can't have @id or @nest", term),
+                ));
+            }
+            let reverse = reverse.as_str().ok_or_else(|| {
+                error(
+                    "invalid IRI mapping",
+                    format!("the @reverse of {} must be a string", term),
+                )
+            })?;
+            if has_keyword_form(reverse) {
+                return Ok(());
+            }
+            let mapping = self
+                .expand_iri_with_definitions(active, reverse, false, true, definitions)?
+                .filter(|m| is_absolute_iri(m) || m.starts_with("_:"))
+                .ok_or_else(|| {
+                    error(
+                        "invalid IRI mapping",
+                        format!("{} is not a valid IRI for {}", reverse, term),
+                    )
+                })?;
+            definition.iri_mapping = Some(mapping);
+            if let Some(container) = value.get("@container") {
+                match container {
+                    JsonValue::Null => (),
+                    JsonValue::String(c) if c == "@set" || c == "@index" => {
+                        definition.container = vec![c.clone()]
+                    }
+                    _ => {
+                        return Err(error(
+                            "invalid reverse property",
+                            "the container of a reverse property must be @set or @index",
+                        ))
+                    }
+                }
+            }
+            definition.reverse = true;
+            Arc::make_mut(&mut active.term_definitions).insert(term.into(), definition);
+            definitions.defined.insert(term.into(), true);
+            return Ok(());
+        }
+
+        match value.get("@id") {
+            Some(id) if id.as_str() != Some(term) => match id {
+                JsonValue::Null => (),
+                JsonValue::String(id) => {
+                    if !is_keyword(id) && has_keyword_form(id) {
+                        return Ok(());
+                    }
+                    let mapping = self
+                        .expand_iri_with_definitions(active, id, false, true, definitions)?
+                        .filter(|m| is_keyword(m) || is_absolute_iri(m) || m.starts_with("_:"))
+                        .ok_or_else(|| {
+                            error(
+                                "invalid IRI mapping",
+                                format!("{} is not a valid IRI for {}", id, term),
+                            )
+                        })?;
+                    if mapping == "@context" {
+                        return Err(error("invalid keyword alias", "@context can't be aliased"));
+                    }
+                    let inner = term
+                        .char_indices()
+                        .skip(1)
+                        .take_while(|(i, c)| i + c.len_utf8() < term.len());
+                    if inner.clone().any(|(_, c)| c == ':') || term.contains('/') {
+                        definitions.defined.insert(term.into(), true);
+                        if self.expand_iri_with_definitions(
+                            active,
+                            term,
+                            false,
+                            true,
+                            definitions,
+                        )? != Some(mapping.clone())
+                        {
+                            return Err(error(
+                                "invalid IRI mapping",
+                                format!("the IRI of {} must be {}", term, mapping),
+                            ));
+                        }
+                    }
+                    if !term.contains(':')
+                        && !term.contains('/')
+                        && simple_term
+                        && (mapping.starts_with("_:")
+                            || mapping.ends_with(|c| {
+                                matches!(c, ':' | '/' | '?' | '#' | '[' | ']' | '@')
+                            }))
+                    {
+                        definition.prefix = true;
+                    }
+                    definition.iri_mapping = Some(mapping);
+                }
+                _ => {
+                    return Err(error(
+                        "invalid IRI mapping",
+                        format!("the @id of {} must be a string", term),
+                    ))
+                }
+            },
+            _ => {
+                if let Some((prefix, suffix)) = split_prefix(term) {
+                    if definitions.local.contains_key(prefix) {
+                        self.create_term_definition(active, prefix, definitions)?;
+                    }
+                    definition.iri_mapping = Some(
+                        if let Some(mapping) = active
+                            .term_definition(prefix)
+                            .and_then(|d| d.iri_mapping.as_ref())
+                        {
+                            format!("{}{}", mapping, suffix)
+                        } else {
+                            term.into()
+                        },
+                    );
+                } else if term.contains('/') {
+                    definition.iri_mapping = Some(
+                        active
+                            .expand_iri(term, false, true)
+                            .filter(|m| is_absolute_iri(m))
+                            .ok_or_else(|| {
+                                error(
+                                    "invalid IRI mapping",
+                                    format!("{} is not a valid IRI", term),
+                                )
+                            })?,
+                    );
+                } else if term == "@type" {
+                    definition.iri_mapping = Some("@type".into());
+                } else if let Some(vocabulary_mapping) = &active.vocabulary_mapping {
+                    definition.iri_mapping = Some(format!("{}{}", vocabulary_mapping, term));
+                } else {
+                    return Err(error(
+                        "invalid IRI mapping",
+                        format!("{} can't be mapped to an IRI without @vocab", term),
+                    ));
+                }
+            }
+        }
+
+        if let Some(container) = value.get("@container") {
+            let container = container
+                .as_slice()
+                .iter()
+                .map(|c| c.as_str().map(ToOwned::to_owned))
+                .collect::<Option<Vec<_>>>()
+                .filter(|c| is_valid_container(c))
+                .ok_or_else(|| {
+                    error(
+                        "invalid container mapping",
+                        format!("invalid @container for {}", term),
+                    )
+                })?;
+            definition.container = container;
+            definition.container.sort();
+            if definition.has_container("@type") {
+                let type_mapping = definition.type_mapping.get_or_insert_with(|| "@id".into());
+                if type_mapping != "@id" && type_mapping != "@vocab" {
+                    return Err(error(
+                        "invalid type mapping",
+                        format!("the @type of {} must be @id or @vocab", term),
+                    ));
+                }
+            }
+        }
+
+        if let Some(index) = value.get("@index") {
+            let index = index
+                .as_str()
+                .filter(|index| {
+                    definition.has_container("@index")
+                        && active
+                            .expand_iri(index, false, true)
+                            .map_or(false, |i| is_absolute_iri(&i))
+                })
+                .ok_or_else(|| {
+                    error(
+                        "invalid term definition",
+                        format!("invalid @index for {}", term),
+                    )
+                })?;
+            definition.index = Some(index.into());
+        }
+
+        if let Some(context) = value.get("@context") {
+            self.process_context(
+                active,
+                context,
+                definitions.base_url,
+                definitions.remote_contexts,
+                true,
+                true,
+                false,
+            )
+            .map_err(|e| {
+                error(
+                    "invalid scoped context",
+                    format!("the context of {} is invalid: {}", term, e),
+                )
+            })?;
+            definition.context = Some(context.clone());
+            definition.base_url = definitions.base_url.cloned();
+        }
+
+        if !value.contains_key("@type") {
+            if let Some(language) = value.get("@language") {
+                definition.language = Some(match language {
+                    JsonValue::Null => None,
+                    JsonValue::String(language) => Some(language.clone()),
+                    _ => {
+                        return Err(error(
+                            "invalid language mapping",
+                            format!("the @language of {} must be a string or null", term),
+                        ))
+                    }
+                });
+            }
+            if let Some(direction) = value.get("@direction") {
+                definition.direction = Some(match direction {
+                    JsonValue::Null => None,
+                    JsonValue::String(direction) if direction == "ltr" || direction == "rtl" => {
+                        Some(direction.clone())
+                    }
+                    _ => {
+                        return Err(error(
+                            "invalid base direction",
+                            format!(
+                                "the @direction of {} must be \"ltr\", \"rtl\" or null",
+                                term
+                            ),
+                        ))
+                    }
+                });
+            }
+        }
+
+        if let Some(nest) = value.get("@nest") {
+            definition.nest = Some(
+                nest.as_str()
+                    .filter(|n| *n == "@nest" || !is_keyword(n))
+                    .ok_or_else(|| {
+                        error("invalid @nest value", format!("invalid @nest for {}", term))
+                    })?
+                    .into(),
+            );
+        }
+
+        if let Some(prefix) = value.get("@prefix") {
+            if term.contains(':') || term.contains('/') {
+                return Err(error(
+                    "invalid term definition",
+                    format!("{} can't be used as a prefix", term),
+                ));
+            }
+            definition.prefix = if let JsonValue::Boolean(prefix) = prefix {
+                *prefix
+            } else {
+                return Err(error("invalid @prefix value", "@prefix must be a boolean"));
+            };
+            if definition.prefix && definition.iri_mapping.as_deref().map_or(false, is_keyword) {
+                return Err(error(
+                    "invalid term definition",
+                    format!("the keyword alias {} can't be a prefix", term),
+                ));
+            }
+        }
+
+        if let Some(key) = value.keys().find(|k| {
+            !matches!(
+                k.as_str(),
+                "@id"
+                    | "@reverse"
+                    | "@container"
+                    | "@context"
+                    | "@direction"
+                    | "@index"
+                    | "@language"
+                    | "@nest"
+                    | "@prefix"
+                    | "@protected"
+                    | "@type"
+            )
+        }) {
+            return Err(error(
+                "invalid term definition",
+                format!("unexpected key {} in the definition of {}", key, term),
+            ));
+        }
+
+        if !definitions.override_protected {
+            if let Some(previous_definition) = previous_definition {
+                if previous_definition.protected {
+                    if !definition.is_same_as(&previous_definition) {
+                        return Err(error(
+                            "protected term redefinition",
+                            format!("the protected term {} can't be redefined", term),
+                        ));
+                    }
+                    definition = previous_definition;
+                }
+            }
+        }
+
+        Arc::make_mut(&mut active.term_definitions).insert(term.into(), definition);
+        definitions.defined.insert(term.into(), true);
+        Ok(())
+    }
+
+    /// IRI expansion defining first the terms of the local context the IRI depends on
+    fn expand_iri_with_definitions(
+        &self,
+        active: &mut Context,
+        value: &str,
+        document_relative: bool,
+        vocab: bool,
+        definitions: &mut Definitions<'_>,
+    ) -> io::Result<Option<String>> {
+        if !is_keyword(value) && !has_keyword_form(value) {
+            if definitions.local.contains_key(value)
+                && definitions.defined.get(value) != Some(&true)
+            {
+                self.create_term_definition(active, value, definitions)?;
+            }
+            if let Some((prefix, suffix)) = split_prefix(value) {
+                if prefix != "_"
+                    && !suffix.starts_with("//")
+                    && definitions.local.contains_key(prefix)
+                    && definitions.defined.get(prefix) != Some(&true)
+                {
+                    self.create_term_definition(active, prefix, definitions)?;
+                }
+            }
+        }
+        Ok(active.expand_iri(value, document_relative, vocab))
+    }
+}
+
+/// Checks that a `@container` value is allowed
+fn is_valid_container(container: &[String]) -> bool {
+    if container.is_empty()
+        || !container.iter().all(|c| {
+            matches!(
+                c.as_str(),
+                "@graph" | "@id" | "@index" | "@language" | "@list" | "@set" | "@type"
+            )
+        })
+    {
+        return false;
+    }
+    let mut others = container
+        .iter()
+        .filter(|c| *c != "@set")
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    if others.len() + 1 < container.len() {
+        return false; // @set is repeated
+    }
+    if others.contains(&"@list") {
+        return container.len() == 1;
+    }
+    others.sort_unstable();
+    matches!(others.as_slice(), [] | [_] | ["@graph", "@id" | "@index"])
+}
@@ -0,0 +1,708 @@
+use crate::io::jsonld::context::{Context, Processor};
+use crate::io::jsonld::json::{JsonObject, JsonValue};
+use crate::io::jsonld::{
+    add_value, error, is_graph_object, is_list_object, is_node_object, is_value_object,
+};
+use oxiri::Iri;
+use std::borrow::Cow;
+use std::io;
+
+/// The [expansion algorithm](https://www.w3.org/TR/json-ld11-api/#expansion-algorithm) applied to a full document.
+///
+/// Always returns an array.
+pub fn expand(
+    processor: &Processor,
+    active: &Context,
+    document: &JsonValue,
+    base_iri: Option<&Iri<String>>,
+) -> io::Result<JsonValue> {
+    let expanded = expand_element(processor, active, None, document, base_iri, false)?;
+    Ok(JsonValue::Array(match expanded {
+        JsonValue::Null => Vec::new(),
+        JsonValue::Object(mut object) if object.len() == 1 && object.contains_key("@graph") => {
+            object
+                .remove("@graph")
+                .unwrap_or(JsonValue::Null)
+                .into_array()
+        }
+        expanded => expanded.into_array(),
+    }))
+}
+
+/// The [expansion algorithm](https://www.w3.org/TR/json-ld11-api/#expansion-algorithm) applied to an element
+pub fn expand_element(
+    processor: &Processor,
+    active: &Context,
+    active_property: Option<&str>,
+    element: &JsonValue,
+    base_url: Option<&Iri<String>>,
+    from_map: bool,
+) -> io::Result<JsonValue> {
+    let property_definition = active_property.and_then(|p| active.term_definition(p));
+    match element {
+        JsonValue::Null => Ok(JsonValue::Null),
+        JsonValue::Array(elements) => {
+            let is_list = active.has_container(active_property, "@list");
+            let mut result = Vec::new();
+            for element in elements {
+                let expanded = expand_element(
+                    processor,
+                    active,
+                    active_property,
+                    element,
+                    base_url,
+                    from_map,
+                )?;
+                match expanded {
+                    JsonValue::Null => (),
+                    JsonValue::Array(values) if is_list => result.push(JsonValue::Object(
+                        JsonObject::from([("@list".to_owned(), JsonValue::Array(values))]),
+                    )),
+                    JsonValue::Array(values) => result.extend(values),
+                    expanded => result.push(expanded),
+                }
+            }
+            Ok(JsonValue::Array(result))
+        }
+        JsonValue::Object(element) => {
+            let mut active = Cow::Borrowed(active);
+            if let Some(previous_context) = active.previous_context.clone() {
+                let is_value = element
+                    .keys()
+                    .any(|k| active.expand_iri(k, false, true).as_deref() == Some("@value"));
+                let is_reference = element.len() == 1
+                    && element
+                        .keys()
+                        .all(|k| active.expand_iri(k, false, true).as_deref() == Some("@id"));
+                if !from_map && !is_value && !is_reference {
+                    active = Cow::Owned(Context::clone(&previous_context));
+                }
+            }
+            if let Some(definition) = property_definition {
+                if let Some(context) = &definition.context {
+                    active = Cow::Owned(processor.process_context(
+                        &active,
+                        context,
+                        definition.base_url.as_ref(),
+                        &[],
+                        true,
+                        true,
+                        true,
+                    )?);
+                }
+            }
+            if let Some(context) = element.get("@context") {
+                active = Cow::Owned(processor.process_context(
+                    &active,
+                    context,
+                    base_url,
+                    &[],
+                    false,
+                    true,
+                    true,
+                )?);
+            }
+            let type_scoped = active.clone();
+            let mut input_type = None;
+            for (key, value) in element {
+                if active.expand_iri(key, false, true).as_deref() != Some("@type") {
+                    continue;
+                }
+                let mut types = value
+                    .as_slice()
+                    .iter()
+                    .filter_map(JsonValue::as_str)
+                    .collect::<Vec<_>>();
+                if input_type.is_none() {
+                    input_type = types
+                        .last()
+                        .and_then(|t| type_scoped.expand_iri(t, true, true));
+                }
+                types.sort_unstable();
+                for t in types {
+                    if let Some(definition) = type_scoped.term_definition(t) {
+                        if let Some(context) = &definition.context {
+                            active = Cow::Owned(processor.process_context(
+                                &active,
+                                context,
+                                definition.base_url.as_ref(),
+                                &[],
+                                false,
+                                false,
+                                true,
+                            )?);
+                        }
+                    }
+                }
+            }
+            let mut result = JsonObject::new();
+            expand_entries(
+                processor,
+                &active,
+                &type_scoped,
+                active_property,
+                element,
+                &mut result,
+                input_type.as_deref(),
+                base_url,
+                from_map,
+            )?;
+            post_process_expanded_object(result, active_property)
+        }
+        _ => {
+            if active_property.map_or(true, |p| p == "@graph") {
+                return Ok(JsonValue::Null);
+            }
+            if let Some(definition) = property_definition {
+                if let Some(context) = &definition.context {
+                    let active = processor.process_context(
+                        active,
+                        context,
+                        definition.base_url.as_ref(),
+                        &[],
+                        true,
+                        true,
+                        true,
+                    )?;
+                    return Ok(expand_value(&active, active_property, element));
+                }
+            }
+            Ok(expand_value(active, active_property, element))
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn expand_entries(
+    processor: &Processor,
+    active: &Context,
+    type_scoped: &Context,
+    active_property: Option<&str>,
+    element: &JsonObject,
+    result: &mut JsonObject,
+    input_type: Option<&str>,
+    base_url: Option<&Iri<String>>,
+    from_map: bool,
+) -> io::Result<()> {
+    let mut nests = Vec::new();
+    for (key, value) in element {
+        if key == "@context" {
+            continue;
+        }
+        let expanded_property = match active.expand_iri(key, false, true) {
+            Some(p) if p.contains(':') || p.starts_with('@') => p,
+            _ => continue,
+        };
+        if expanded_property.starts_with('@') {
+            if active_property == Some("@reverse") {
+                return Err(error(
+                    "invalid reverse property map",
+                    "a reverse property map can't contain keywords",
+                ));
+            }
+            if result.contains_key(&expanded_property)
+                && expanded_property != "@included"
+                && expanded_property != "@type"
+            {
+                return Err(error(
+                    "colliding keywords",
+                    format!("{} is used multiple times", expanded_property),
+                ));
+            }
+            let expanded_value = match expanded_property.as_str() {
+                "@id" => {
+                    let id = value
+                        .as_str()
+                        .ok_or_else(|| error("invalid @id value", "@id values must be strings"))?;
+                    active.expand_iri(id, true, false).map(JsonValue::String)
+                }
+                "@type" => {
+                    let types = value
+                        .as_slice()
+                        .iter()
+                        .map(|t| {
+                            let t = t.as_str().ok_or_else(|| {
+                                error("invalid type value", "@type values must be strings")
+                            })?;
+                            Ok(type_scoped
+                                .expand_iri(t, true, true)
+                                .map_or(JsonValue::Null, JsonValue::String))
+                        })
+                        .collect::<io::Result<Vec<_>>>()?;
+                    let expanded = if let Some(existing) = result.remove("@type") {
+                        let mut existing = existing.into_array();
+                        existing.extend(types);
+                        JsonValue::Array(existing)
+                    } else if matches!(value, JsonValue::Array(_)) {
+                        JsonValue::Array(types)
+                    } else {
+                        types.into_iter().next().unwrap_or(JsonValue::Null)
+                    };
+                    Some(expanded)
+                }
+                "@graph" => Some(JsonValue::Array(
+                    expand_element(processor, active, Some("@graph"), value, base_url, from_map)?
+                        .into_array()
+                        .into_iter()
+                        .filter(|v| !v.is_null())
+                        .collect(),
+                )),
+                "@included" => {
+                    let included =
+                        expand_element(processor, active, None, value, base_url, from_map)?
+                            .into_array()
+                            .into_iter()
+                            .filter(|v| !v.is_null())
+                            .collect::<Vec<_>>();
+                    if !included.iter().all(is_node_object) {
+                        return Err(error(
+                            "invalid @included value",
+                            "@included values must be node objects",
+                        ));
+                    }
+                    let mut existing = result
+                        .remove("@included")
+                        .map_or_else(Vec::new, JsonValue::into_array);
+                    existing.extend(included);
+                    Some(JsonValue::Array(existing))
+                }
+                "@value" => {
+                    if input_type == Some("@json") || value.is_null() || value.is_scalar() {
+                        result.insert("@value".into(), value.clone());
+                        continue;
+                    }
+                    return Err(error(
+                        "invalid value object value",
+                        "@value must be a scalar or null",
+                    ));
+                }
+                "@language" => Some(
+                    value
+                        .as_str()
+                        .ok_or_else(|| {
+                            error(
+                                "invalid language-tagged string",
+                                "@language must be a string",
+                            )
+                        })?
+                        .into(),
+                ),
+                "@direction" => Some(
+                    value
+                        .as_str()
+                        .filter(|d| *d == "ltr" || *d == "rtl")
+                        .ok_or_else(|| {
+                            error(
+                                "invalid base direction",
+                                "@direction must be \"ltr\" or \"rtl\"",
+                            )
+                        })?
+                        .into(),
+                ),
+                "@index" => Some(
+                    value
+                        .as_str()
+                        .ok_or_else(|| error("invalid @index value", "@index must be a string"))?
+                        .into(),
+                ),
+                "@list" => {
+                    if active_property.map_or(true, |p| p == "@graph") {
+                        continue;
+                    }
+                    Some(JsonValue::Array(
+                        expand_element(
+                            processor,
+                            active,
+                            active_property,
+                            value,
+                            base_url,
+                            from_map,
+                        )?
+                        .into_array()
+                        .into_iter()
+                        .filter(|v| !v.is_null())
+                        .collect(),
+                    ))
+                }
+                "@set" => Some(expand_element(
+                    processor,
+                    active,
+                    active_property,
+                    value,
+                    base_url,
+                    from_map,
+                )?),
+                "@reverse" => {
+                    if !matches!(value, JsonValue::Object(_)) {
+                        return Err(error(
+                            "invalid @reverse value",
+                            "@reverse must be an object",
+                        ));
+                    }
+                    let expanded = expand_element(
+                        processor,
+                        active,
+                        Some("@reverse"),
+                        value,
+                        base_url,
+                        from_map,
+                    )?;
+                    if let JsonValue::Object(mut expanded) = expanded {
+                        if let Some(JsonValue::Object(reversed)) = expanded.remove("@reverse") {
+                            for (property, items) in reversed {
+                                add_value(result, &property, items, true);
+                            }
+                        }
+                        for (property, items) in expanded {
+                            for item in items.into_array() {
+                                add_reverse_value(result, &property, item)?;
+                            }
+                        }
+                    }
+                    continue;
+                }
+                "@nest" => {
+                    nests.push(key);
+                    continue;
+                }
+                _ => None,
+            };
+            if let Some(expanded_value) = expanded_value {
+                result.insert(expanded_property, expanded_value);
+            }
+            continue;
+        }
+
+        let definition = active.term_definition(key);
+        let has_container =
+            |container: &str| definition.map_or(false, |d| d.has_container(container));
+        let expanded_value = if definition
+            .map_or(false, |d| d.type_mapping.as_deref() == Some("@json"))
+        {
+            JsonValue::Object(JsonObject::from([
+                ("@value".to_owned(), value.clone()),
+                ("@type".to_owned(), "@json".into()),
+            ]))
+        } else if let (true, JsonValue::Object(value)) = (has_container("@language"), value) {
+            let direction = match definition.and_then(|d| d.direction.as_ref()) {
+                Some(direction) => direction.clone(),
+                None => active.default_base_direction.clone(),
+            };
+            let mut expanded = Vec::new();
+            for (language, language_value) in value {
+                for item in language_value.as_slice() {
+                    let item = match item {
+                        JsonValue::Null => continue,
+                        JsonValue::String(item) => item,
+                        _ => {
+                            return Err(error(
+                                "invalid language map value",
+                                "language map values must be strings",
+                            ))
+                        }
+                    };
+                    let mut object =
+                        JsonObject::from([("@value".to_owned(), item.as_str().into())]);
+                    if active.expand_iri(language, false, true).as_deref() != Some("@none") {
+                        object.insert("@language".into(), language.as_str().into());
+                    }
+                    if let Some(direction) = &direction {
+                        object.insert("@direction".into(), direction.as_str().into());
+                    }
+                    expanded.push(JsonValue::Object(object));
+                }
+            }
+            JsonValue::Array(expanded)
+        } else if let (true, JsonValue::Object(value)) = (
+            has_container("@index") || has_container("@type") || has_container("@id"),
+            value,
+        ) {
+            let index_key = definition
+                .and_then(|d| d.index.as_deref())
+                .unwrap_or("@index");
+            let mut expanded = Vec::new();
+            for (index, index_value) in value {
+                let mut map_context = Cow::Borrowed(active);
+                if has_container("@id") || has_container("@type") {
+                    if let Some(previous_context) = &active.previous_context {
+                        map_context = Cow::Borrowed(previous_context);
+                    }
+                }
+                if has_container("@type") {
+                    if let Some(index_definition) = map_context.term_definition(index) {
+                        if let Some(context) = &index_definition.context {
+                            map_context = Cow::Owned(processor.process_context(
+                                &map_context,
+                                context,
+                                index_definition.base_url.as_ref(),
+                                &[],
+                                false,
+                                true,
+                                true,
+                            )?);
+                        }
+                    }
+                }
+                let expanded_index = active.expand_iri(index, false, true);
+                let is_none = expanded_index.as_deref() == Some("@none");
+                let items = expand_element(
+                    processor,
+                    &map_context,
+                    Some(key),
+                    &JsonValue::Array(index_value.as_slice().to_vec()),
+                    base_url,
+                    true,
+                )?;
+                for mut item in items.into_array() {
+                    if has_container("@graph") && !is_graph_object(&item) {
+                        item = JsonValue::Object(JsonObject::from([(
+                            "@graph".to_owned(),
+                            JsonValue::Array(vec![item]),
+                        )]));
+                    }
+                    let object = item.as_object_mut().ok_or_else(|| {
+                        error("invalid value object", "indexed values must be objects")
+                    })?;
+                    if has_container("@index") && index_key != "@index" && !is_none {
+                        let re_expanded_index =
+                            expand_value(active, Some(index_key), &index.as_str().into());
+                        let expanded_index_key = active
+                            .expand_iri(index_key, false, true)
+                            .unwrap_or_else(|| index_key.into());
+                        let mut values = vec![re_expanded_index];
+                        if let Some(existing) = object.remove(&expanded_index_key) {
+                            values.extend(existing.into_array());
+                        }
+                        object.insert(expanded_index_key, JsonValue::Array(values));
+                        if object.contains_key("@value") {
+                            return Err(error(
+                                "invalid value object",
+                                "property-valued indexes can't be used on value objects",
+                            ));
+                        }
+                    } else if has_container("@index") && !object.contains_key("@index") && !is_none
+                    {
+                        object.insert("@index".into(), index.as_str().into());
+                    } else if has_container("@id") && !object.contains_key("@id") && !is_none {
+                        if let Some(id) = active.expand_iri(index, true, false) {
+                            object.insert("@id".into(), id.into());
+                        }
+                    } else if has_container("@type") && !is_none {
+                        let mut types = expanded_index
+                            .iter()
+                            .map(|t| JsonValue::String(t.clone()))
+                            .collect::<Vec<_>>();
+                        if let Some(existing) = object.remove("@type") {
+                            types.extend(existing.into_array());
+                        }
+                        object.insert("@type".into(), JsonValue::Array(types));
+                    }
+                    expanded.push(item);
+                }
+            }
+            JsonValue::Array(expanded)
+        } else {
+            expand_element(processor, active, Some(key), value, base_url, false)?
+        };
+        if expanded_value.is_null() {
+            continue;
+        }
+        let mut expanded_value = expanded_value;
+        if has_container("@list") && !is_list_object(&expanded_value) {
+            expanded_value = JsonValue::Object(JsonObject::from([(
+                "@list".to_owned(),
+                JsonValue::Array(expanded_value.into_array()),
+            )]));
+        }
+        if has_container("@graph") && !has_container("@id") && !has_container("@index") {
+            expanded_value = JsonValue::Array(
+                expanded_value
+                    .into_array()
+                    .into_iter()
+                    .map(|v| {
+                        JsonValue::Object(JsonObject::from([(
+                            "@graph".to_owned(),
+                            JsonValue::Array(vec![v]),
+                        )]))
+                    })
+                    .collect(),
+            );
+        }
+        if definition.map_or(false, |d| d.reverse) {
+            for item in expanded_value.into_array() {
+                add_reverse_value(result, &expanded_property, item)?;
+            }
+        } else {
+            add_value(result, &expanded_property, expanded_value, true);
+        }
+    }
+
+    for nesting_key in nests {
+        for nested_value in element[nesting_key].as_slice() {
+            let nested_value = nested_value
+                .as_object()
+                .filter(|nested_value| {
+                    nested_value
+                        .keys()
+                        .all(|k| active.expand_iri(k, false, true).as_deref() != Some("@value"))
+                })
+                .ok_or_else(|| error("invalid @nest value", "@nest values must be node objects"))?;
+            expand_entries(
+                processor,
+                active,
+                type_scoped,
+                active_property,
+                nested_value,
+                result,
+                input_type,
+                base_url,
+                from_map,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Adds a value to the `@reverse` entry of a node object
+fn add_reverse_value(result: &mut JsonObject, property: &str, item: JsonValue) -> io::Result<()> {
+    if is_value_object(&item) || is_list_object(&item) {
+        return Err(error(
+            "invalid reverse property value",
+            "the value of a reverse property must be a node object",
+        ));
+    }
+    if let JsonValue::Object(reverse_map) = result
+        .entry("@reverse".into())
+        .or_insert_with(|| JsonValue::Object(JsonObject::new()))
+    {
+        add_value(reverse_map, property, item, true);
+    }
+    Ok(())
+}
+
+/// Checks and simplifies the expanded object (steps 15 to 20 of the expansion algorithm)
+fn post_process_expanded_object(
+    mut result: JsonObject,
+    active_property: Option<&str>,
+) -> io::Result<JsonValue> {
+    if let Some(value) = result.get("@value") {
+        if let Some(key) = result.keys().find(|k| {
+            !matches!(
+                k.as_str(),
+                "@direction" | "@index" | "@language" | "@type" | "@value"
+            )
+        }) {
+            return Err(error(
+                "invalid value object",
+                format!("value objects can't contain {}", key),
+            ));
+        }
+        let has_language = result.contains_key("@language") || result.contains_key("@direction");
+        let value_type = result.get("@type");
+        if has_language && value_type.is_some() {
+            return Err(error(
+                "invalid value object",
+                "value objects can't have both a @type and a @language or @direction",
+            ));
+        }
+        if value_type.and_then(JsonValue::as_str) != Some("@json") {
+            if value.is_null() {
+                return Ok(JsonValue::Null);
+            }
+            if !matches!(value, JsonValue::String(_)) && has_language {
+                return Err(error(
+                    "invalid language-tagged value",
+                    "only strings can have a language",
+                ));
+            }
+            if let Some(value_type) = value_type {
+                if !value_type.as_str().map_or(false, |t| Iri::parse(t).is_ok()) {
+                    return Err(error(
+                        "invalid typed value",
+                        "the @type of a value must be an IRI",
+                    ));
+                }
+            }
+        }
+    } else if let Some(types) = result.get_mut("@type") {
+        if !matches!(types, JsonValue::Array(_)) {
+            *types = JsonValue::Array(vec![types.clone()]);
+        }
+    } else if result.contains_key("@set") || result.contains_key("@list") {
+        if result.len() > 2 || (result.len() == 2 && !result.contains_key("@index")) {
+            return Err(error(
+                "invalid set or list object",
+                "@set and @list objects can only contain an @index",
+            ));
+        }
+        if let Some(set) = result.remove("@set") {
+            return Ok(set);
+        }
+    }
+    if result.len() == 1 && result.contains_key("@language") {
+        return Ok(JsonValue::Null);
+    }
+    if active_property.map_or(true, |p| p == "@graph")
+        && (result.is_empty()
+            || result.contains_key("@value")
+            || result.contains_key("@list")
+            || (result.len() == 1 && result.contains_key("@id")))
+    {
+        return Ok(JsonValue::Null);
+    }
+    Ok(JsonValue::Object(result))
+}
+
+/// The [value expansion algorithm](https://www.w3.org/TR/json-ld11-api/#value-expansion)
+pub fn expand_value(
+    active: &Context,
+    active_property: Option<&str>,
+    value: &JsonValue,
+) -> JsonValue {
+    let definition = active_property.and_then(|p| active.term_definition(p));
+    let type_mapping = definition.and_then(|d| d.type_mapping.as_deref());
+    if let JsonValue::String(value) = value {
+        match type_mapping {
+            Some("@id") => {
+                return active
+                    .expand_iri(value, true, false)
+                    .map_or(JsonValue::Null, |id| {
+                        JsonValue::Object(JsonObject::from([("@id".to_owned(), id.into())]))
+                    })
+            }
+            Some("@vocab") => {
+                return active
+                    .expand_iri(value, true, true)
+                    .map_or(JsonValue::Null, |id| {
+                        JsonValue::Object(JsonObject::from([("@id".to_owned(), id.into())]))
+                    })
+            }
+            _ => (),
+        }
+    }
+    let mut result = JsonObject::from([("@value".to_owned(), value.clone())]);
+    match type_mapping {
+        Some(type_mapping) if !matches!(type_mapping, "@id" | "@vocab" | "@none") => {
+            result.insert("@type".into(), type_mapping.into());
+        }
+        _ => {
+            if matches!(value, JsonValue::String(_)) {
+                let language = match definition.and_then(|d| d.language.as_ref()) {
+                    Some(language) => language.as_ref(),
+                    None => active.default_language.as_ref(),
+                };
+                if let Some(language) = language {
+                    result.insert("@language".into(), language.as_str().into());
+                }
+                let direction = match definition.and_then(|d| d.direction.as_ref()) {
+                    Some(direction) => direction.as_ref(),
+                    None => active.default_base_direction.as_ref(),
+                };
+                if let Some(direction) = direction {
+                    result.insert("@direction".into(), direction.as_str().into());
+                }
+            }
+        }
+    }
+    JsonValue::Object(result)
+}
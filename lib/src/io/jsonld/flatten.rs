@@ -0,0 +1,46 @@
+use crate::io::jsonld::compact::compact;
+use crate::io::jsonld::context::Processor;
+use crate::io::jsonld::json::{JsonObject, JsonValue};
+use crate::io::jsonld::to_rdf::{generate_node_map, BlankNodeIdGenerator, NodeMap};
+use std::io;
+
+/// The [flattening algorithm](https://www.w3.org/TR/json-ld11-api/#flattening-algorithm).
+///
+/// If a context is given the flattened document is compacted with it, keeping a top-level `@graph`.
+pub fn flatten(
+    processor: &Processor,
+    expanded: &JsonValue,
+    context: Option<&JsonValue>,
+) -> io::Result<JsonValue> {
+    let mut node_map = NodeMap::new();
+    generate_node_map(
+        expanded,
+        &mut node_map,
+        &mut BlankNodeIdGenerator::default(),
+    )?;
+    let mut default_graph = node_map.remove("@default").unwrap_or_default();
+    for (graph_name, graph) in node_map {
+        default_graph
+            .entry(graph_name.clone())
+            .or_insert_with(|| JsonObject::from([("@id".to_owned(), graph_name.into())]))
+            .insert(
+                "@graph".into(),
+                JsonValue::Array(nodes(graph.into_values())),
+            );
+    }
+    let flattened = JsonValue::Array(nodes(default_graph.into_values()));
+    if let Some(context) = context {
+        compact(processor, &flattened, context, true)
+    } else {
+        Ok(flattened)
+    }
+}
+
+/// The node objects that are not only a reference
+fn nodes(nodes: impl IntoIterator<Item = JsonObject>) -> Vec<JsonValue> {
+    nodes
+        .into_iter()
+        .filter(|node| node.len() > 1 || !node.contains_key("@id"))
+        .map(JsonValue::Object)
+        .collect()
+}
@@ -0,0 +1,232 @@
+use crate::error::invalid_input_error;
+use crate::io::jsonld::json::{read_json, JsonObject, JsonValue};
+use crate::io::jsonld::{error, is_blank_node_id};
+use crate::model::vocab::{rdf, xsd};
+use crate::model::*;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+
+const RDF_JSON: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#JSON";
+
+/// The place a value has been added to
+#[derive(Clone)]
+struct Usage {
+    graph: String,
+    node: String,
+    property: String,
+    index: usize,
+}
+
+/// The [serialize RDF as JSON-LD algorithm](https://www.w3.org/TR/json-ld11-api/#serialize-rdf-as-json-ld-algorithm)
+/// without native types conversion nor `rdf:type` preservation.
+///
+/// Returns the document in expanded form.
+pub fn from_rdf(quads: &[Quad]) -> io::Result<JsonValue> {
+    let mut graph_map = BTreeMap::<String, BTreeMap<String, JsonObject>>::new();
+    graph_map.entry("@default".into()).or_default();
+    // The rdf:nil usages of each graph
+    let mut nil_usages = BTreeMap::<String, Vec<Usage>>::new();
+    // The blank nodes used once as object, `None` if they are used multiple times
+    let mut referenced_once = HashMap::<String, Option<Usage>>::new();
+
+    for quad in quads {
+        let graph = match &quad.graph_name {
+            GraphName::NamedNode(node) => node.as_str().to_owned(),
+            GraphName::BlankNode(node) => format!("_:{}", node.as_str()),
+            GraphName::DefaultGraph => "@default".into(),
+        };
+        if graph != "@default" {
+            graph_map
+                .entry("@default".into())
+                .or_default()
+                .entry(graph.clone())
+                .or_insert_with(|| node_object(&graph));
+        }
+        let node_map = graph_map.entry(graph.clone()).or_default();
+        let subject = subject_id(quad.subject.as_ref())?;
+        node_map
+            .entry(subject.clone())
+            .or_insert_with(|| node_object(&subject));
+        let object_id = match &quad.object {
+            Term::NamedNode(node) => Some(node.as_str().to_owned()),
+            Term::BlankNode(node) => Some(format!("_:{}", node.as_str())),
+            Term::Literal(_) => None,
+            Term::Triple(_) => return Err(rdf_star_error()),
+        };
+        if let Some(object_id) = &object_id {
+            node_map
+                .entry(object_id.clone())
+                .or_insert_with(|| node_object(object_id));
+        }
+        let node = node_map
+            .get_mut(&subject)
+            .ok_or_else(|| error("internal", "the subject node should exist"))?;
+        if quad.predicate == rdf::TYPE {
+            if let Some(object_id) = object_id {
+                add_unique(node, "@type", object_id.into());
+                continue;
+            }
+        }
+        let value = match (&quad.object, &object_id) {
+            (Term::Literal(literal), _) => literal_to_object(literal.as_ref())?,
+            (_, Some(id)) => node_object(id).into(),
+            (_, None) => return Err(rdf_star_error()),
+        };
+        let property = quad.predicate.as_str();
+        let index = add_unique(node, property, value);
+        let usage = Usage {
+            graph: graph.clone(),
+            node: subject,
+            property: property.into(),
+            index,
+        };
+        match &quad.object {
+            Term::NamedNode(object) if object.as_ref() == rdf::NIL => {
+                nil_usages.entry(graph).or_default().push(usage);
+            }
+            Term::BlankNode(object) => {
+                referenced_once
+                    .entry(format!("_:{}", object.as_str()))
+                    .and_modify(|u| *u = None)
+                    .or_insert(Some(usage));
+            }
+            _ => (),
+        }
+    }
+
+    for (graph, usages) in nil_usages {
+        for usage in usages {
+            let mut node_id = usage.node.clone();
+            let mut property = usage.property.clone();
+            let mut head = usage;
+            let mut list = Vec::new();
+            let mut list_nodes = Vec::new();
+            while property == rdf::REST.as_str() && is_blank_node_id(&node_id) {
+                let node_usage = if let Some(Some(node_usage)) = referenced_once.get(&node_id) {
+                    node_usage.clone()
+                } else {
+                    break;
+                };
+                let node = if let Some(node) = graph_map[&graph].get(&node_id) {
+                    node
+                } else {
+                    break;
+                };
+                if !is_well_formed_list_node(node) || node_usage.graph != graph {
+                    break;
+                }
+                list.push(node[rdf::FIRST.as_str()].as_slice()[0].clone());
+                list_nodes.push(node_id);
+                node_id = node_usage.node.clone();
+                property = node_usage.property.clone();
+                head = node_usage;
+            }
+            let graph_object = graph_map
+                .get_mut(&graph)
+                .ok_or_else(|| error("internal", "the graph should exist"))?;
+            if let Some(JsonValue::Array(values)) = graph_object
+                .get_mut(&head.node)
+                .and_then(|node| node.get_mut(&head.property))
+            {
+                if let Some(JsonValue::Object(head)) = values.get_mut(head.index) {
+                    head.remove("@id");
+                    list.reverse();
+                    head.insert("@list".into(), JsonValue::Array(list));
+                }
+            }
+            for node_id in list_nodes {
+                graph_object.remove(&node_id);
+            }
+        }
+    }
+
+    let default_graph = graph_map.remove("@default").unwrap_or_default();
+    let mut result = Vec::new();
+    for (subject, mut node) in default_graph {
+        if let Some(graph) = graph_map.remove(&subject) {
+            node.insert(
+                "@graph".into(),
+                JsonValue::Array(
+                    graph
+                        .into_values()
+                        .filter(|n| n.len() > 1 || !n.contains_key("@id"))
+                        .map(JsonValue::Object)
+                        .collect(),
+                ),
+            );
+        }
+        if node.len() > 1 || !node.contains_key("@id") {
+            result.push(JsonValue::Object(node));
+        }
+    }
+    Ok(JsonValue::Array(result))
+}
+
+pub fn rdf_star_error() -> io::Error {
+    invalid_input_error("RDF-star triples can't be serialized in JSON-LD")
+}
+
+/// The JSON-LD node identifier of a subject
+pub fn subject_id(subject: SubjectRef<'_>) -> io::Result<String> {
+    match subject {
+        SubjectRef::NamedNode(node) => Ok(node.as_str().to_owned()),
+        SubjectRef::BlankNode(node) => Ok(format!("_:{}", node.as_str())),
+        SubjectRef::Triple(_) => Err(rdf_star_error()),
+    }
+}
+
+pub fn node_object(id: &str) -> JsonObject {
+    JsonObject::from([("@id".to_owned(), id.into())])
+}
+
+/// Adds the value if it is not already there and returns its position
+pub fn add_unique(node: &mut JsonObject, property: &str, value: JsonValue) -> usize {
+    let values = node
+        .entry(property.into())
+        .or_insert_with(|| JsonValue::Array(Vec::new()));
+    if let JsonValue::Array(values) = values {
+        if let Some(index) = values.iter().position(|v| *v == value) {
+            index
+        } else {
+            values.push(value);
+            values.len() - 1
+        }
+    } else {
+        0
+    }
+}
+
+/// A blank node with exactly one `rdf:first` and one `rdf:rest` and optionally the `rdf:List` type
+fn is_well_formed_list_node(node: &JsonObject) -> bool {
+    node.iter().all(|(key, value)| match key.as_str() {
+        "@id" => true,
+        "@type" => value.as_slice() == [JsonValue::from(rdf::LIST.as_str())],
+        k if k == rdf::FIRST.as_str() || k == rdf::REST.as_str() => value.as_slice().len() == 1,
+        _ => false,
+    }) && node.contains_key(rdf::FIRST.as_str())
+        && node.contains_key(rdf::REST.as_str())
+}
+
+/// The [RDF to object conversion](https://www.w3.org/TR/json-ld11-api/#rdf-to-object-conversion) of a literal
+pub fn literal_to_object(literal: LiteralRef<'_>) -> io::Result<JsonValue> {
+    let mut result = JsonObject::new();
+    if let Some(language) = literal.language() {
+        result.insert("@value".into(), literal.value().into());
+        result.insert("@language".into(), language.into());
+    } else if literal.datatype().as_str() == RDF_JSON {
+        let value = read_json(literal.value().as_bytes()).map_err(|e| {
+            error(
+                "invalid JSON literal",
+                format!("{} is not valid JSON: {}", literal.value(), e),
+            )
+        })?;
+        result.insert("@value".into(), value);
+        result.insert("@type".into(), "@json".into());
+    } else {
+        result.insert("@value".into(), literal.value().into());
+        if literal.datatype() != xsd::STRING {
+            result.insert("@type".into(), literal.datatype().as_str().into());
+        }
+    }
+    Ok(JsonValue::Object(result))
+}
@@ -0,0 +1,194 @@
+use crate::error::invalid_data_error;
+use json_event_parser::{JsonEvent, JsonReader, JsonWriter};
+use std::collections::BTreeMap;
+use std::io;
+use std::io::{BufRead, Write};
+
+pub type JsonObject = BTreeMap<String, JsonValue>;
+
+/// A JSON value. The object keys are kept sorted to make the algorithms output deterministic.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub enum JsonValue {
+    Null,
+    Boolean(bool),
+    /// A number, kept in its lexical form
+    Number(String),
+    String(String),
+    Array(Vec<Self>),
+    Object(JsonObject),
+}
+
+impl JsonValue {
+    pub fn is_null(&self) -> bool {
+        matches!(self, Self::Null)
+    }
+
+    pub fn is_scalar(&self) -> bool {
+        matches!(self, Self::Boolean(_) | Self::Number(_) | Self::String(_))
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        if let Self::String(value) = self {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&JsonObject> {
+        if let Self::Object(value) = self {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_object_mut(&mut self) -> Option<&mut JsonObject> {
+        if let Self::Object(value) = self {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// The value itself if it is an array, an array containing it else
+    pub fn into_array(self) -> Vec<Self> {
+        if let Self::Array(values) = self {
+            values
+        } else {
+            vec![self]
+        }
+    }
+
+    /// The values of the array or the value itself
+    pub fn as_slice(&self) -> &[Self] {
+        if let Self::Array(values) = self {
+            values
+        } else {
+            std::slice::from_ref(self)
+        }
+    }
+
+    /// Serializes the value without any whitespace, the object keys being sorted
+    pub fn to_canonical_string(&self) -> String {
+        let mut buffer = Vec::new();
+        let mut writer = JsonWriter::from_writer(&mut buffer);
+        if let Err(e) = write_json(self, &mut writer) {
+            unreachable!("Writing JSON into a Vec should not fail: {}", e)
+        }
+        String::from_utf8(buffer).unwrap()
+    }
+}
+
+impl From<&str> for JsonValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.into())
+    }
+}
+
+impl From<String> for JsonValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<Vec<Self>> for JsonValue {
+    fn from(value: Vec<Self>) -> Self {
+        Self::Array(value)
+    }
+}
+
+impl From<JsonObject> for JsonValue {
+    fn from(value: JsonObject) -> Self {
+        Self::Object(value)
+    }
+}
+
+/// Reads a full JSON document
+pub fn read_json(reader: impl BufRead) -> io::Result<JsonValue> {
+    let mut reader = JsonReader::from_reader(reader);
+    let mut buffer = Vec::new();
+    let mut builder = JsonValueBuilder::default();
+    loop {
+        if let Some(value) = builder.push(reader.read_event(&mut buffer)?)? {
+            return Ok(value);
+        }
+    }
+}
+
+/// Builds a JSON value from the events of a JSON reader
+#[derive(Default)]
+pub struct JsonValueBuilder {
+    stack: Vec<(JsonValue, Option<String>)>,
+}
+
+impl JsonValueBuilder {
+    /// Adds the next event and returns the value once it is complete
+    pub fn push(&mut self, event: JsonEvent<'_>) -> io::Result<Option<JsonValue>> {
+        let value = match event {
+            JsonEvent::String(value) => JsonValue::String(value.into()),
+            JsonEvent::Number(value) => JsonValue::Number(value.into()),
+            JsonEvent::Boolean(value) => JsonValue::Boolean(value),
+            JsonEvent::Null => JsonValue::Null,
+            JsonEvent::StartArray => {
+                self.stack.push((JsonValue::Array(Vec::new()), None));
+                return Ok(None);
+            }
+            JsonEvent::StartObject => {
+                self.stack
+                    .push((JsonValue::Object(JsonObject::new()), None));
+                return Ok(None);
+            }
+            JsonEvent::ObjectKey(key) => {
+                if let Some((_, current_key)) = self.stack.last_mut() {
+                    *current_key = Some(key.into());
+                }
+                return Ok(None);
+            }
+            JsonEvent::EndArray | JsonEvent::EndObject => {
+                if let Some((value, _)) = self.stack.pop() {
+                    value
+                } else {
+                    return Err(invalid_data_error("Unexpected end of JSON array or object"));
+                }
+            }
+            JsonEvent::Eof => return Err(io::Error::from(io::ErrorKind::UnexpectedEof)),
+        };
+        match self.stack.last_mut() {
+            Some((JsonValue::Array(values), _)) => values.push(value),
+            Some((JsonValue::Object(object), key)) => {
+                if let Some(key) = key.take() {
+                    object.insert(key, value);
+                }
+            }
+            Some(_) => unreachable!(),
+            None => return Ok(Some(value)),
+        }
+        Ok(None)
+    }
+}
+
+/// Writes a JSON value
+pub fn write_json(value: &JsonValue, writer: &mut JsonWriter<impl Write>) -> io::Result<()> {
+    match value {
+        JsonValue::Null => writer.write_event(JsonEvent::Null),
+        JsonValue::Boolean(value) => writer.write_event(JsonEvent::Boolean(*value)),
+        JsonValue::Number(value) => writer.write_event(JsonEvent::Number(value)),
+        JsonValue::String(value) => writer.write_event(JsonEvent::String(value)),
+        JsonValue::Array(values) => {
+            writer.write_event(JsonEvent::StartArray)?;
+            for value in values {
+                write_json(value, writer)?;
+            }
+            writer.write_event(JsonEvent::EndArray)
+        }
+        JsonValue::Object(object) => {
+            writer.write_event(JsonEvent::StartObject)?;
+            for (key, value) in object {
+                writer.write_event(JsonEvent::ObjectKey(key))?;
+                write_json(value, writer)?;
+            }
+            writer.write_event(JsonEvent::EndObject)
+        }
+    }
+}
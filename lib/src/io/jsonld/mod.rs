@@ -0,0 +1,260 @@
+//! Implementation of the [JSON-LD 1.1 processing algorithms](https://www.w3.org/TR/json-ld11-api/).
+
+mod compact;
+mod context;
+mod expand;
+mod flatten;
+mod from_rdf;
+mod json;
+mod reader;
+mod to_rdf;
+mod writer;
+
+use crate::error::invalid_data_error;
+use crate::io::jsonld::context::Processor;
+use crate::io::jsonld::flatten::flatten;
+use crate::io::jsonld::from_rdf::from_rdf;
+use crate::io::jsonld::json::{read_json, write_json, JsonValue};
+use crate::model::*;
+use json_event_parser::JsonWriter;
+use oxiri::Iri;
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::io::Write;
+use std::sync::Arc;
+
+/// Loads the remote JSON-LD documents, like the contexts referenced by their IRIs.
+///
+/// No document is loaded from the network by default: the JSON-LD parser and serializer
+/// only load remote contexts using the document loader they have been given.
+///
+/// Any function from a URL to the document content implements this trait:
+/// ```
+/// use oxigraph::io::{DatasetFormat, DatasetParser};
+/// use std::io::{Cursor, Error, ErrorKind};
+///
+/// let parser = DatasetParser::from_format(DatasetFormat::JsonLd).with_document_loader(|url: &str| {
+///     if url == "http://example.com/context.jsonld" {
+///         Ok(br#"{"@context": {"@vocab": "http://example.com/"}}"#.to_vec())
+///     } else {
+///         Err(Error::new(ErrorKind::NotFound, format!("{} not found", url)))
+///     }
+/// });
+/// let file = r#"{"@context": "http://example.com/context.jsonld", "@id": "http://example.com/s", "p": "o"}"#;
+/// let quads = parser.read_quads(Cursor::new(file))?.collect::<Result<Vec<_>,_>>()?;
+/// assert_eq!(quads[0].predicate.as_str(), "http://example.com/p");
+/// # std::io::Result::Ok(())
+/// ```
+pub trait DocumentLoader: Send + Sync {
+    /// Returns the content of the JSON document at the given URL.
+    fn load_document(&self, url: &str) -> io::Result<Vec<u8>>;
+}
+
+impl<F: Fn(&str) -> io::Result<Vec<u8>> + Send + Sync> DocumentLoader for F {
+    #[inline]
+    fn load_document(&self, url: &str) -> io::Result<Vec<u8>> {
+        self(url)
+    }
+}
+
+/// A [`DocumentLoader`] returning documents provided in advance, allowing to process JSON-LD offline.
+///
+/// ```
+/// use oxigraph::io::{DatasetFormat, DatasetParser, StaticDocumentLoader};
+/// use std::io::Cursor;
+///
+/// let loader = StaticDocumentLoader::new().with_document(
+///     "http://schema.org/",
+///     r#"{"@context": {"@vocab": "http://schema.org/"}}"#,
+/// );
+/// let parser = DatasetParser::from_format(DatasetFormat::JsonLd).with_document_loader(loader);
+/// let file = r#"{"@context": "http://schema.org/", "@id": "http://example.com/alice", "name": "Alice"}"#;
+/// let quads = parser.read_quads(Cursor::new(file))?.collect::<Result<Vec<_>,_>>()?;
+/// assert_eq!(quads[0].predicate.as_str(), "http://schema.org/name");
+/// # std::io::Result::Ok(())
+/// ```
+#[derive(Default, Clone)]
+pub struct StaticDocumentLoader {
+    documents: HashMap<String, Vec<u8>>,
+}
+
+impl StaticDocumentLoader {
+    /// Builds a loader without any document
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a document that is returned when `url` is loaded
+    #[inline]
+    pub fn with_document(mut self, url: impl Into<String>, content: impl Into<Vec<u8>>) -> Self {
+        self.documents.insert(url.into(), content.into());
+        self
+    }
+}
+
+impl DocumentLoader for StaticDocumentLoader {
+    fn load_document(&self, url: &str) -> io::Result<Vec<u8>> {
+        self.documents.get(url).cloned().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("The document {} is not available offline", url),
+            )
+        })
+    }
+}
+
+pub use self::reader::JsonLdQuadReader;
+pub use self::writer::JsonLdQuadWriter;
+
+/// The shape of the serialized JSON-LD documents
+#[derive(Clone, Default)]
+pub struct JsonLdWriterOptions {
+    /// The context to compact the document with
+    pub context: Option<Arc<JsonValue>>,
+    /// If the document should be in flattened form
+    pub flatten: bool,
+}
+
+/// Serializes quads into a JSON-LD document in [flattened form](https://www.w3.org/TR/json-ld11/#flattened-document-form)
+pub fn write_flattened_json_ld(
+    quads: &[Quad],
+    writer: impl Write,
+    context: Option<&JsonValue>,
+    loader: Option<Arc<dyn DocumentLoader>>,
+) -> io::Result<()> {
+    let processor = Processor::new(loader);
+    let expanded = from_rdf(quads)?;
+    let document = flatten(&processor, &expanded, context)?;
+    let mut writer = JsonWriter::from_writer(writer);
+    write_json(&document, &mut writer)
+}
+
+/// Parses the JSON document of a JSON-LD context given to a serializer
+pub fn read_json_ld_context(context: &str) -> io::Result<JsonValue> {
+    read_json(context.as_bytes())
+}
+
+/// A JSON-LD processing error, identified by its [error code](https://www.w3.org/TR/json-ld11-api/#jsonlderrorcode)
+fn error(code: &str, details: impl fmt::Display) -> io::Error {
+    invalid_data_error(format!("JSON-LD error '{}': {}", code, details))
+}
+
+const KEYWORDS: [&str; 24] = [
+    "@base",
+    "@container",
+    "@context",
+    "@default",
+    "@direction",
+    "@embed",
+    "@explicit",
+    "@graph",
+    "@id",
+    "@import",
+    "@included",
+    "@index",
+    "@json",
+    "@language",
+    "@list",
+    "@nest",
+    "@none",
+    "@omitDefault",
+    "@prefix",
+    "@preserve",
+    "@protected",
+    "@requireAll",
+    "@reverse",
+    "@set",
+];
+
+/// Returns `true` if the string is a JSON-LD keyword
+fn is_keyword(value: &str) -> bool {
+    KEYWORDS.contains(&value)
+        || matches!(
+            value,
+            "@type" | "@value" | "@version" | "@vocab" | "@propagate"
+        )
+}
+
+/// Returns `true` if the string has the form of a keyword, i.e. "@" followed by ALPHA characters
+fn has_keyword_form(value: &str) -> bool {
+    value.len() > 1 && value.starts_with('@') && value[1..].bytes().all(|b| b.is_ascii_alphabetic())
+}
+
+/// Returns `true` if the string is an absolute IRI
+fn is_absolute_iri(value: &str) -> bool {
+    Iri::parse(value).is_ok()
+}
+
+fn is_blank_node_id(value: &str) -> bool {
+    value.starts_with("_:")
+}
+
+/// Returns `true` if the value is a [value object](https://www.w3.org/TR/json-ld11/#dfn-value-object)
+fn is_value_object(value: &JsonValue) -> bool {
+    value
+        .as_object()
+        .map_or(false, |value| value.contains_key("@value"))
+}
+
+/// Returns `true` if the value is a [list object](https://www.w3.org/TR/json-ld11/#dfn-list-object)
+fn is_list_object(value: &JsonValue) -> bool {
+    value
+        .as_object()
+        .map_or(false, |value| value.contains_key("@list"))
+}
+
+/// Returns `true` if the value is a [graph object](https://www.w3.org/TR/json-ld11/#dfn-graph-object)
+fn is_graph_object(value: &JsonValue) -> bool {
+    value.as_object().map_or(false, |value| {
+        value.contains_key("@graph")
+            && value
+                .keys()
+                .all(|k| matches!(k.as_str(), "@graph" | "@id" | "@index" | "@context"))
+    })
+}
+
+/// Returns `true` if the value is a graph object without `@id`
+fn is_simple_graph_object(value: &JsonValue) -> bool {
+    is_graph_object(value) && !value.as_object().map_or(false, |v| v.contains_key("@id"))
+}
+
+/// Returns `true` if the value is a [node object](https://www.w3.org/TR/json-ld11/#dfn-node-object)
+fn is_node_object(value: &JsonValue) -> bool {
+    value.as_object().map_or(false, |value| {
+        !value.contains_key("@value") && !value.contains_key("@list") && !value.contains_key("@set")
+    }) && !is_graph_object(value)
+}
+
+/// Appends a value to an object entry, converting the entry to an array if needed
+fn add_value(object: &mut json::JsonObject, key: &str, value: JsonValue, as_array: bool) {
+    if let JsonValue::Array(values) = value {
+        if values.is_empty() && as_array {
+            object
+                .entry(key.into())
+                .or_insert_with(|| JsonValue::Array(Vec::new()));
+        }
+        for value in values {
+            add_value(object, key, value, as_array);
+        }
+        return;
+    }
+    match object.get_mut(key) {
+        Some(JsonValue::Array(values)) => values.push(value),
+        Some(existing) => {
+            let previous = std::mem::replace(existing, JsonValue::Null);
+            *existing = JsonValue::Array(vec![previous, value]);
+        }
+        None => {
+            object.insert(
+                key.into(),
+                if as_array {
+                    JsonValue::Array(vec![value])
+                } else {
+                    value
+                },
+            );
+        }
+    }
+}
@@ -0,0 +1,294 @@
+use crate::error::invalid_data_error;
+use crate::io::jsonld::context::{Context, Processor};
+use crate::io::jsonld::expand::{expand, expand_element};
+use crate::io::jsonld::json::{JsonObject, JsonValue, JsonValueBuilder};
+use crate::io::jsonld::to_rdf::RdfConverter;
+use crate::io::jsonld::DocumentLoader;
+use crate::model::Quad;
+use json_event_parser::{JsonEvent, JsonReader};
+use oxiri::Iri;
+use std::io;
+use std::io::BufRead;
+use std::mem::replace;
+use std::sync::Arc;
+
+/// Parses a JSON-LD document into quads while reading it.
+///
+/// The elements of the top-level array and of the `@graph` array of the top-level object are converted as soon as they are read.
+/// This requires the `@context` and `@id` entries of the top-level object to come before `@graph`,
+/// like in the [streaming document form](https://w3c.github.io/json-ld-streaming/#streaming-document-form).
+/// If an other entry comes before `@graph`, the `@graph` entry is buffered until the end of the top-level object.
+pub struct JsonLdQuadReader<R: BufRead> {
+    reader: JsonReader<R>,
+    buffer: Vec<u8>,
+    processor: Processor,
+    base_iri: Option<Iri<String>>,
+    converter: RdfConverter,
+    state: ReaderState,
+}
+
+enum ReaderState {
+    Start,
+    /// Inside of the top-level array
+    TopLevelArray,
+    /// Inside of the top-level object
+    TopLevelObject(TopLevelObject),
+    /// Inside of the `@graph` array of the top-level object
+    TopLevelGraph(TopLevelObject),
+    /// After the top-level value
+    End,
+    /// The end of the document has been reached or an error has been returned
+    Done,
+}
+
+struct TopLevelObject {
+    /// The context defined by the leading `@context` entry
+    active: Context,
+    /// The entries not converted yet
+    entries: JsonObject,
+    /// The expanded `@id` entry
+    id: Option<String>,
+    /// If the `@graph` entry has already been converted
+    graph_converted: bool,
+}
+
+impl<R: BufRead> JsonLdQuadReader<R> {
+    /// Builds a reader for a JSON-LD document
+    pub fn new(
+        reader: R,
+        base_iri: Option<Iri<String>>,
+        loader: Option<Arc<dyn DocumentLoader>>,
+    ) -> Self {
+        Self {
+            reader: JsonReader::from_reader(reader),
+            buffer: Vec::new(),
+            processor: Processor::new(loader),
+            base_iri,
+            converter: RdfConverter::default(),
+            state: ReaderState::Start,
+        }
+    }
+
+    /// Reads the document until the next element is converted and appends its quads to `quads`.
+    ///
+    /// Returns `None` at the end of the document.
+    pub fn read(&mut self, quads: &mut Vec<Quad>) -> Option<io::Result<()>> {
+        let state = replace(&mut self.state, ReaderState::Done);
+        match self.read_in_state(state, quads) {
+            Ok(ReaderState::Done) => None,
+            Ok(state) => {
+                self.state = state;
+                Some(Ok(()))
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+
+    fn read_in_state(
+        &mut self,
+        state: ReaderState,
+        quads: &mut Vec<Quad>,
+    ) -> io::Result<ReaderState> {
+        Ok(match state {
+            ReaderState::Start => match self.reader.read_event(&mut self.buffer)? {
+                JsonEvent::StartArray => ReaderState::TopLevelArray,
+                JsonEvent::StartObject => ReaderState::TopLevelObject(TopLevelObject {
+                    active: Context::new(self.base_iri.clone()),
+                    entries: JsonObject::new(),
+                    id: None,
+                    graph_converted: false,
+                }),
+                JsonEvent::Eof => return Err(io::Error::from(io::ErrorKind::UnexpectedEof)),
+                // A scalar value does not contain any quad
+                _ => ReaderState::End,
+            },
+            ReaderState::TopLevelArray => {
+                let mut builder = JsonValueBuilder::default();
+                let element = match self.reader.read_event(&mut self.buffer)? {
+                    JsonEvent::EndArray => return Ok(ReaderState::End),
+                    event => builder.push(event)?,
+                };
+                let element = if let Some(element) = element {
+                    element
+                } else {
+                    self.read_value(builder)?
+                };
+                let expanded = expand_element(
+                    &self.processor,
+                    &Context::new(self.base_iri.clone()),
+                    None,
+                    &element,
+                    self.base_iri.as_ref(),
+                    false,
+                )?;
+                self.converter.convert(&expanded, quads)?;
+                ReaderState::TopLevelArray
+            }
+            ReaderState::TopLevelObject(object) => self.read_top_level_entry(object, quads)?,
+            ReaderState::TopLevelGraph(mut object) => {
+                let mut builder = JsonValueBuilder::default();
+                let element = match self.reader.read_event(&mut self.buffer)? {
+                    JsonEvent::EndArray => {
+                        object.graph_converted = true;
+                        return Ok(ReaderState::TopLevelObject(object));
+                    }
+                    event => builder.push(event)?,
+                };
+                let element = if let Some(element) = element {
+                    element
+                } else {
+                    self.read_value(builder)?
+                };
+                self.convert_graph_element(&object, &element, quads)?;
+                ReaderState::TopLevelGraph(object)
+            }
+            ReaderState::End => match self.reader.read_event(&mut self.buffer)? {
+                JsonEvent::Eof => ReaderState::Done,
+                _ => {
+                    return Err(invalid_data_error(
+                        "Unexpected content after the end of the JSON-LD document",
+                    ))
+                }
+            },
+            ReaderState::Done => ReaderState::Done,
+        })
+    }
+
+    fn read_top_level_entry(
+        &mut self,
+        mut object: TopLevelObject,
+        quads: &mut Vec<Quad>,
+    ) -> io::Result<ReaderState> {
+        let key = match self.reader.read_event(&mut self.buffer)? {
+            JsonEvent::ObjectKey(key) => key.to_owned(),
+            JsonEvent::EndObject => {
+                self.convert_top_level_object(object, quads)?;
+                return Ok(ReaderState::End);
+            }
+            _ => return Err(invalid_data_error("Invalid JSON object")),
+        };
+        if key == "@context" {
+            if object.graph_converted {
+                return Err(late_entry_error(&key));
+            }
+            if object.entries.is_empty() {
+                let context = self.read_value(JsonValueBuilder::default())?;
+                object.active = self.processor.process_context(
+                    &object.active,
+                    &context,
+                    self.base_iri.as_ref(),
+                    &[],
+                    false,
+                    true,
+                    true,
+                )?;
+                return Ok(ReaderState::TopLevelObject(object));
+            }
+        }
+        let is_id =
+            |key: &str| object.active.expand_iri(key, false, true).as_deref() == Some("@id");
+        let keyword = object.active.expand_iri(&key, false, true);
+        if keyword.as_deref() == Some("@graph")
+            && !object.graph_converted
+            && object.entries.keys().all(|k| is_id(k))
+        {
+            let mut builder = JsonValueBuilder::default();
+            let element = match self.reader.read_event(&mut self.buffer)? {
+                JsonEvent::StartArray => return Ok(ReaderState::TopLevelGraph(object)),
+                event => builder.push(event)?,
+            };
+            let element = if let Some(element) = element {
+                element
+            } else {
+                self.read_value(builder)?
+            };
+            self.convert_graph_element(&object, &element, quads)?;
+            object.graph_converted = true;
+            return Ok(ReaderState::TopLevelObject(object));
+        }
+        if object.graph_converted && (object.id.is_none() || is_id(&key)) {
+            // The converted @graph content would have been in a different graph
+            return Err(late_entry_error(&key));
+        }
+        let value = self.read_value(JsonValueBuilder::default())?;
+        if is_id(&key) {
+            object.id = value
+                .as_str()
+                .and_then(|id| object.active.expand_iri(id, true, false));
+        }
+        object.entries.insert(key, value);
+        Ok(ReaderState::TopLevelObject(object))
+    }
+
+    /// Converts an element of the top-level `@graph` entry
+    fn convert_graph_element(
+        &mut self,
+        object: &TopLevelObject,
+        element: &JsonValue,
+        quads: &mut Vec<Quad>,
+    ) -> io::Result<()> {
+        let expanded = expand_element(
+            &self.processor,
+            &object.active,
+            Some("@graph"),
+            element,
+            self.base_iri.as_ref(),
+            false,
+        )?;
+        if let Some(id) = &object.id {
+            self.converter.convert(
+                &JsonObject::from([
+                    ("@id".to_owned(), id.as_str().into()),
+                    ("@graph".to_owned(), JsonValue::Array(expanded.into_array())),
+                ])
+                .into(),
+                quads,
+            )
+        } else {
+            self.converter.convert(&expanded, quads)
+        }
+    }
+
+    /// Converts the entries of the top-level object that have not been converted yet
+    fn convert_top_level_object(
+        &mut self,
+        object: TopLevelObject,
+        quads: &mut Vec<Quad>,
+    ) -> io::Result<()> {
+        let document = JsonValue::Object(object.entries);
+        let expanded = if object.graph_converted {
+            expand_element(
+                &self.processor,
+                &object.active,
+                None,
+                &document,
+                self.base_iri.as_ref(),
+                false,
+            )?
+        } else {
+            expand(
+                &self.processor,
+                &object.active,
+                &document,
+                self.base_iri.as_ref(),
+            )?
+        };
+        self.converter.convert(&expanded, quads)
+    }
+
+    /// Reads the rest of a JSON value
+    fn read_value(&mut self, mut builder: JsonValueBuilder) -> io::Result<JsonValue> {
+        loop {
+            if let Some(value) = builder.push(self.reader.read_event(&mut self.buffer)?)? {
+                return Ok(value);
+            }
+        }
+    }
+}
+
+fn late_entry_error(key: &str) -> io::Error {
+    invalid_data_error(format!(
+        "The '{}' entry of the top-level JSON-LD object must be before its '@graph' entry that has already been read",
+        key
+    ))
+}
@@ -0,0 +1,523 @@
+use crate::io::jsonld::json::{JsonObject, JsonValue};
+use crate::io::jsonld::{error, is_blank_node_id, is_list_object, is_value_object};
+use crate::model::vocab::{rdf, xsd};
+use crate::model::*;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+
+/// The nodes of each graph, indexed by graph name and by node identifier
+pub type NodeMap = BTreeMap<String, BTreeMap<String, JsonObject>>;
+
+const RDF_JSON: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#JSON";
+
+/// Relabels the blank nodes with fresh `_:bN` identifiers
+#[derive(Default)]
+pub struct BlankNodeIdGenerator {
+    counter: usize,
+    identifiers: HashMap<String, String>,
+}
+
+impl BlankNodeIdGenerator {
+    pub fn generate(&mut self, identifier: Option<&str>) -> String {
+        if let Some(identifier) = identifier {
+            if let Some(new_identifier) = self.identifiers.get(identifier) {
+                return new_identifier.clone();
+            }
+        }
+        let new_identifier = format!("_:b{}", self.counter);
+        self.counter += 1;
+        if let Some(identifier) = identifier {
+            self.identifiers
+                .insert(identifier.into(), new_identifier.clone());
+        }
+        new_identifier
+    }
+}
+
+/// The subject the values of the current element are added to
+enum ActiveSubject<'b> {
+    None,
+    Node(&'b str),
+    /// A reference to the subject of a reverse property
+    Reverse(&'b JsonValue),
+}
+
+/// The [node map generation algorithm](https://www.w3.org/TR/json-ld11-api/#node-map-generation)
+pub fn generate_node_map(
+    element: &JsonValue,
+    node_map: &mut NodeMap,
+    generator: &mut BlankNodeIdGenerator,
+) -> io::Result<()> {
+    node_map.entry("@default".into()).or_default();
+    add_to_node_map(
+        element,
+        node_map,
+        "@default",
+        &ActiveSubject::None,
+        None,
+        None,
+        generator,
+    )
+}
+
+fn add_to_node_map(
+    element: &JsonValue,
+    node_map: &mut NodeMap,
+    active_graph: &str,
+    active_subject: &ActiveSubject<'_>,
+    active_property: Option<&str>,
+    mut list: Option<&mut Vec<JsonValue>>,
+    generator: &mut BlankNodeIdGenerator,
+) -> io::Result<()> {
+    let element = match element {
+        JsonValue::Array(elements) => {
+            for element in elements {
+                add_to_node_map(
+                    element,
+                    node_map,
+                    active_graph,
+                    active_subject,
+                    active_property,
+                    list.as_deref_mut(),
+                    generator,
+                )?;
+            }
+            return Ok(());
+        }
+        JsonValue::Object(element) => element,
+        _ => return Ok(()),
+    };
+    let mut element = element.clone();
+    if let Some(types) = element.get_mut("@type") {
+        for t in types_mut(types) {
+            if let JsonValue::String(t) = t {
+                if is_blank_node_id(t) {
+                    *t = generator.generate(Some(t));
+                }
+            }
+        }
+    }
+
+    if element.contains_key("@value") {
+        let element = JsonValue::Object(element);
+        if let Some(list) = list {
+            list.push(element);
+        } else {
+            add_to_subject(
+                node_map,
+                active_graph,
+                active_subject,
+                active_property,
+                element,
+                true,
+            );
+        }
+    } else if let Some(values) = element.get("@list") {
+        let mut result = Vec::new();
+        add_to_node_map(
+            values,
+            node_map,
+            active_graph,
+            active_subject,
+            active_property,
+            Some(&mut result),
+            generator,
+        )?;
+        let mut result = JsonObject::from([("@list".to_owned(), JsonValue::Array(result))]);
+        if let Some(index) = element.get("@index") {
+            result.insert("@index".into(), index.clone());
+        }
+        let result = JsonValue::Object(result);
+        if let Some(list) = list {
+            list.push(result);
+        } else {
+            add_to_subject(
+                node_map,
+                active_graph,
+                active_subject,
+                active_property,
+                result,
+                false,
+            );
+        }
+    } else {
+        let id = match element.remove("@id") {
+            Some(JsonValue::String(id)) if is_blank_node_id(&id) => generator.generate(Some(&id)),
+            Some(JsonValue::String(id)) => id,
+            _ => generator.generate(None),
+        };
+        let graph = node_map.entry(active_graph.into()).or_default();
+        graph
+            .entry(id.clone())
+            .or_insert_with(|| JsonObject::from([("@id".to_owned(), id.as_str().into())]));
+        let reference =
+            JsonValue::Object(JsonObject::from([("@id".to_owned(), id.as_str().into())]));
+        if let ActiveSubject::Reverse(subject) = active_subject {
+            if let Some(property) = active_property {
+                if let Some(node) = node_map
+                    .get_mut(active_graph)
+                    .and_then(|graph| graph.get_mut(&id))
+                {
+                    add_unique_value(node, property, (*subject).clone());
+                }
+            }
+        } else if active_property.is_some() {
+            if let Some(list) = list {
+                list.push(reference.clone());
+            } else {
+                add_to_subject(
+                    node_map,
+                    active_graph,
+                    active_subject,
+                    active_property,
+                    reference.clone(),
+                    true,
+                );
+            }
+        }
+        if let Some(types) = element.remove("@type") {
+            let node = node_of(node_map, active_graph, &id);
+            for t in types.into_array() {
+                add_unique_value(node, "@type", t);
+            }
+        }
+        if let Some(index) = element.remove("@index") {
+            let node = node_of(node_map, active_graph, &id);
+            if node.get("@index").map_or(false, |i| *i != index) {
+                return Err(error(
+                    "conflicting indexes",
+                    format!("the node {} has multiple indexes", id),
+                ));
+            }
+            node.insert("@index".into(), index);
+        }
+        if let Some(JsonValue::Object(reverse_map)) = element.remove("@reverse") {
+            for (property, values) in reverse_map {
+                for value in values.as_slice() {
+                    add_to_node_map(
+                        value,
+                        node_map,
+                        active_graph,
+                        &ActiveSubject::Reverse(&reference),
+                        Some(&property),
+                        None,
+                        generator,
+                    )?;
+                }
+            }
+        }
+        if let Some(graph) = element.remove("@graph") {
+            node_map.entry(id.clone()).or_default();
+            add_to_node_map(
+                &graph,
+                node_map,
+                &id,
+                &ActiveSubject::None,
+                None,
+                None,
+                generator,
+            )?;
+        }
+        if let Some(included) = element.remove("@included") {
+            add_to_node_map(
+                &included,
+                node_map,
+                active_graph,
+                &ActiveSubject::None,
+                None,
+                None,
+                generator,
+            )?;
+        }
+        for (property, value) in element {
+            let property = if is_blank_node_id(&property) {
+                generator.generate(Some(&property))
+            } else {
+                property
+            };
+            node_of(node_map, active_graph, &id)
+                .entry(property.clone())
+                .or_insert_with(|| JsonValue::Array(Vec::new()));
+            add_to_node_map(
+                &value,
+                node_map,
+                active_graph,
+                &ActiveSubject::Node(&id),
+                Some(&property),
+                None,
+                generator,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn types_mut(types: &mut JsonValue) -> &mut [JsonValue] {
+    if let JsonValue::Array(types) = types {
+        types
+    } else {
+        std::slice::from_mut(types)
+    }
+}
+
+fn node_of<'b>(node_map: &'b mut NodeMap, graph: &str, id: &str) -> &'b mut JsonObject {
+    node_map
+        .entry(graph.into())
+        .or_default()
+        .entry(id.into())
+        .or_insert_with(|| JsonObject::from([("@id".to_owned(), id.into())]))
+}
+
+fn add_to_subject(
+    node_map: &mut NodeMap,
+    active_graph: &str,
+    active_subject: &ActiveSubject<'_>,
+    active_property: Option<&str>,
+    value: JsonValue,
+    unique: bool,
+) {
+    if let (ActiveSubject::Node(subject), Some(property)) = (active_subject, active_property) {
+        let node = node_of(node_map, active_graph, subject);
+        if unique {
+            add_unique_value(node, property, value);
+        } else {
+            match node
+                .entry(property.into())
+                .or_insert_with(|| JsonValue::Array(Vec::new()))
+            {
+                JsonValue::Array(values) => values.push(value),
+                existing => *existing = JsonValue::Array(vec![existing.clone(), value]),
+            }
+        }
+    }
+}
+
+/// Adds a value to an array entry if it is not already in it
+fn add_unique_value(node: &mut JsonObject, property: &str, value: JsonValue) {
+    match node
+        .entry(property.into())
+        .or_insert_with(|| JsonValue::Array(Vec::new()))
+    {
+        JsonValue::Array(values) => {
+            if !values.contains(&value) {
+                values.push(value)
+            }
+        }
+        existing => {
+            if *existing != value {
+                *existing = JsonValue::Array(vec![existing.clone(), value])
+            }
+        }
+    }
+}
+
+/// The [deserialization of JSON-LD to RDF](https://www.w3.org/TR/json-ld11-api/#deserialize-json-ld-to-rdf-algorithm)
+/// of a sequence of expanded elements, the same blank node identifiers denoting the same blank nodes in all of them
+#[derive(Default)]
+pub struct RdfConverter {
+    generator: BlankNodeIdGenerator,
+    serializer: RdfSerializer,
+}
+
+impl RdfConverter {
+    /// Converts an expanded element and appends the generated quads to `quads`
+    pub fn convert(&mut self, expanded: &JsonValue, quads: &mut Vec<Quad>) -> io::Result<()> {
+        let mut node_map = NodeMap::new();
+        generate_node_map(expanded, &mut node_map, &mut self.generator)?;
+        let serializer = &mut self.serializer;
+        for (graph_name, graph) in &node_map {
+            let graph_name = if graph_name == "@default" {
+                GraphName::DefaultGraph
+            } else if let Some(graph_name) = serializer.subject(graph_name) {
+                match graph_name {
+                    Subject::NamedNode(node) => node.into(),
+                    Subject::BlankNode(node) => node.into(),
+                    Subject::Triple(_) => continue,
+                }
+            } else {
+                continue;
+            };
+            for (subject, node) in graph {
+                let subject = if let Some(subject) = serializer.subject(subject) {
+                    subject
+                } else {
+                    continue;
+                };
+                for (property, values) in node {
+                    if property == "@type" {
+                        for t in values.as_slice() {
+                            if let Some(Subject::NamedNode(t)) =
+                                t.as_str().and_then(|t| serializer.subject(t))
+                            {
+                                serializer.quads.push(Quad::new(
+                                    subject.clone(),
+                                    rdf::TYPE,
+                                    t,
+                                    graph_name.clone(),
+                                ));
+                            }
+                        }
+                    } else if property.starts_with('@') || is_blank_node_id(property) {
+                        // Keywords and blank node properties do not produce triples
+                    } else if let Ok(predicate) = NamedNode::new(property.as_str()) {
+                        for item in values.as_slice() {
+                            if let Some(object) = serializer.object(item, &graph_name) {
+                                serializer.quads.push(Quad::new(
+                                    subject.clone(),
+                                    predicate.clone(),
+                                    object,
+                                    graph_name.clone(),
+                                ));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        quads.append(&mut serializer.quads);
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct RdfSerializer {
+    blank_nodes: HashMap<String, BlankNode>,
+    quads: Vec<Quad>,
+}
+
+impl RdfSerializer {
+    /// Converts a node identifier to a RDF term, `None` if it is not well-formed
+    fn subject(&mut self, id: &str) -> Option<Subject> {
+        if is_blank_node_id(id) {
+            Some(
+                self.blank_nodes
+                    .entry(id.into())
+                    .or_insert_with(BlankNode::default)
+                    .clone()
+                    .into(),
+            )
+        } else {
+            Some(NamedNode::new(id).ok()?.into())
+        }
+    }
+
+    /// The [object to RDF conversion](https://www.w3.org/TR/json-ld11-api/#object-to-rdf-conversion)
+    fn object(&mut self, item: &JsonValue, graph_name: &GraphName) -> Option<Term> {
+        if is_list_object(item) {
+            let list = item.as_object()?.get("@list")?;
+            return Some(self.list(list.as_slice(), graph_name));
+        }
+        if !is_value_object(item) {
+            return Some(
+                match self.subject(item.as_object()?.get("@id")?.as_str()?)? {
+                    Subject::NamedNode(node) => node.into(),
+                    Subject::BlankNode(node) => node.into(),
+                    Subject::Triple(triple) => triple.into(),
+                },
+            );
+        }
+        let item = item.as_object()?;
+        let value = item.get("@value")?;
+        let datatype = item.get("@type").and_then(JsonValue::as_str);
+        if datatype == Some("@json") {
+            return Some(
+                Literal::new_typed_literal(
+                    value.to_canonical_string(),
+                    NamedNode::new_unchecked(RDF_JSON),
+                )
+                .into(),
+            );
+        }
+        let datatype = if let Some(datatype) = datatype {
+            Some(NamedNode::new(datatype).ok()?)
+        } else {
+            None
+        };
+        let (value, datatype) = match value {
+            JsonValue::Boolean(value) => (
+                value.to_string(),
+                datatype.unwrap_or_else(|| xsd::BOOLEAN.into()),
+            ),
+            JsonValue::Number(value) => {
+                let number = value.parse::<f64>().ok()?;
+                if number.fract() != 0.0
+                    || number.abs() >= 1e21
+                    || datatype
+                        .as_ref()
+                        .map_or(false, |d| d.as_ref() == xsd::DOUBLE)
+                {
+                    (
+                        canonical_double(number),
+                        datatype.unwrap_or_else(|| xsd::DOUBLE.into()),
+                    )
+                } else {
+                    (
+                        canonical_integer(value, number),
+                        datatype.unwrap_or_else(|| xsd::INTEGER.into()),
+                    )
+                }
+            }
+            JsonValue::String(value) => {
+                if let Some(datatype) = datatype {
+                    (value.clone(), datatype)
+                } else if let Some(language) = item.get("@language").and_then(JsonValue::as_str) {
+                    return Literal::new_language_tagged_literal(value.clone(), language)
+                        .ok()
+                        .map(Term::from);
+                } else {
+                    (value.clone(), xsd::STRING.into())
+                }
+            }
+            _ => return None,
+        };
+        Some(Literal::new_typed_literal(value, datatype).into())
+    }
+
+    /// The [list to RDF conversion](https://www.w3.org/TR/json-ld11-api/#list-to-rdf-conversion)
+    fn list(&mut self, items: &[JsonValue], graph_name: &GraphName) -> Term {
+        let nodes = items
+            .iter()
+            .map(|_| BlankNode::default())
+            .collect::<Vec<_>>();
+        for (i, (item, node)) in items.iter().zip(&nodes).enumerate() {
+            if let Some(object) = self.object(item, graph_name) {
+                self.quads.push(Quad::new(
+                    node.clone(),
+                    rdf::FIRST,
+                    object,
+                    graph_name.clone(),
+                ));
+            }
+            let rest: Term = if let Some(next) = nodes.get(i + 1) {
+                next.clone().into()
+            } else {
+                rdf::NIL.into()
+            };
+            self.quads
+                .push(Quad::new(node.clone(), rdf::REST, rest, graph_name.clone()));
+        }
+        nodes
+            .into_iter()
+            .next()
+            .map_or_else(|| rdf::NIL.into(), Term::from)
+    }
+}
+
+/// The canonical lexical form of a `xsd:double`, like `1.1E0`
+pub fn canonical_double(value: f64) -> String {
+    let value = format!("{:E}", value);
+    if let Some((mantissa, exponent)) = value.split_once('E') {
+        if !mantissa.contains('.') {
+            return format!("{}.0E{}", mantissa, exponent);
+        }
+    }
+    value
+}
+
+fn canonical_integer(lexical: &str, value: f64) -> String {
+    if lexical.bytes().all(|b| b.is_ascii_digit() || b == b'-') {
+        lexical.into()
+    } else {
+        format!("{:.0}", value)
+    }
+}
@@ -0,0 +1,175 @@
+use crate::io::jsonld::compact::{
+    alias, compact_element, compact_iri, is_empty_context, local_context,
+};
+use crate::io::jsonld::context::{Context, Processor};
+use crate::io::jsonld::from_rdf::{
+    add_unique, literal_to_object, node_object, rdf_star_error, subject_id,
+};
+use crate::io::jsonld::json::{write_json, JsonObject, JsonValue};
+use crate::io::jsonld::DocumentLoader;
+use crate::model::vocab::rdf;
+use crate::model::*;
+use json_event_parser::{JsonEvent, JsonWriter};
+use std::io;
+use std::io::Write;
+use std::sync::Arc;
+
+/// Writes a JSON-LD document while the quads are written.
+///
+/// The consecutive quads with the same graph name and subject are written as a single node object
+/// and the consecutive quads with the same named graph in the same `@graph` array.
+/// Lists are not converted to `@list` objects.
+pub struct JsonLdQuadWriter<W: Write> {
+    writer: JsonWriter<W>,
+    processor: Processor,
+    /// The context the node objects are compacted with
+    active: Option<Context>,
+    /// The graph name of the last written quad
+    graph_name: GraphName,
+    /// The node object of the last written subject
+    node: Option<(Subject, JsonObject)>,
+}
+
+impl<W: Write> JsonLdQuadWriter<W> {
+    /// Starts a document in expanded form or, if a context is given, in compacted form with a top-level `@graph`
+    pub fn new(
+        writer: W,
+        context: Option<&JsonValue>,
+        loader: Option<Arc<dyn DocumentLoader>>,
+    ) -> io::Result<Self> {
+        let processor = Processor::new(loader);
+        let mut writer = JsonWriter::from_writer(writer);
+        let active = if let Some(context) = context {
+            let context = local_context(context);
+            let active = processor.process_context(
+                &Context::default(),
+                context,
+                None,
+                &[],
+                false,
+                true,
+                true,
+            )?;
+            writer.write_event(JsonEvent::StartObject)?;
+            if !is_empty_context(context) {
+                writer.write_event(JsonEvent::ObjectKey("@context"))?;
+                write_json(context, &mut writer)?;
+            }
+            writer.write_event(JsonEvent::ObjectKey(&alias(&active, "@graph")?))?;
+            Some(active)
+        } else {
+            None
+        };
+        writer.write_event(JsonEvent::StartArray)?;
+        Ok(Self {
+            writer,
+            processor,
+            active,
+            graph_name: GraphName::DefaultGraph,
+            node: None,
+        })
+    }
+
+    /// Writes a quad
+    pub fn write(&mut self, quad: QuadRef<'_>) -> io::Result<()> {
+        if quad.graph_name != self.graph_name.as_ref() {
+            self.write_node()?;
+            self.end_graph()?;
+            self.start_graph(quad.graph_name)?;
+        } else if let Some((subject, _)) = &self.node {
+            if subject.as_ref() != quad.subject {
+                self.write_node()?;
+            }
+        }
+        let node = if let Some((_, node)) = &mut self.node {
+            node
+        } else {
+            let id = subject_id(quad.subject)?;
+            &mut self
+                .node
+                .insert((quad.subject.into_owned(), node_object(&id)))
+                .1
+        };
+        let (property, value) = match quad.object {
+            TermRef::NamedNode(object) if quad.predicate == rdf::TYPE => {
+                ("@type", object.as_str().into())
+            }
+            TermRef::BlankNode(object) if quad.predicate == rdf::TYPE => {
+                ("@type", format!("_:{}", object.as_str()).into())
+            }
+            TermRef::NamedNode(object) => {
+                (quad.predicate.as_str(), node_object(object.as_str()).into())
+            }
+            TermRef::BlankNode(object) => (
+                quad.predicate.as_str(),
+                node_object(&format!("_:{}", object.as_str())).into(),
+            ),
+            TermRef::Literal(literal) => (quad.predicate.as_str(), literal_to_object(literal)?),
+            TermRef::Triple(_) => return Err(rdf_star_error()),
+        };
+        add_unique(node, property, value);
+        Ok(())
+    }
+
+    /// Writes the last node object and closes the document
+    pub fn finish(mut self) -> io::Result<()> {
+        self.write_node()?;
+        self.end_graph()?;
+        self.writer.write_event(JsonEvent::EndArray)?;
+        if self.active.is_some() {
+            self.writer.write_event(JsonEvent::EndObject)?;
+        }
+        Ok(())
+    }
+
+    fn write_node(&mut self) -> io::Result<()> {
+        if let Some((_, node)) = self.node.take() {
+            let node = JsonValue::Object(node);
+            if let Some(active) = &self.active {
+                let compacted = compact_element(&self.processor, active, None, &node)?;
+                write_json(&compacted, &mut self.writer)?;
+            } else {
+                write_json(&node, &mut self.writer)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Opens the `@graph` array of a named graph
+    fn start_graph(&mut self, graph_name: GraphNameRef<'_>) -> io::Result<()> {
+        let id = match graph_name {
+            GraphNameRef::NamedNode(node) => node.as_str().to_owned(),
+            GraphNameRef::BlankNode(node) => format!("_:{}", node.as_str()),
+            GraphNameRef::DefaultGraph => {
+                self.graph_name = GraphName::DefaultGraph;
+                return Ok(());
+            }
+        };
+        self.writer.write_event(JsonEvent::StartObject)?;
+        if let Some(active) = &self.active {
+            self.writer
+                .write_event(JsonEvent::ObjectKey(&alias(active, "@id")?))?;
+            self.writer.write_event(JsonEvent::String(&compact_iri(
+                active, &id, None, false, false,
+            )?))?;
+            self.writer
+                .write_event(JsonEvent::ObjectKey(&alias(active, "@graph")?))?;
+        } else {
+            self.writer.write_event(JsonEvent::ObjectKey("@id"))?;
+            self.writer.write_event(JsonEvent::String(&id))?;
+            self.writer.write_event(JsonEvent::ObjectKey("@graph"))?;
+        }
+        self.writer.write_event(JsonEvent::StartArray)?;
+        self.graph_name = graph_name.into_owned();
+        Ok(())
+    }
+
+    /// Closes the `@graph` array of the current named graph
+    fn end_graph(&mut self) -> io::Result<()> {
+        if !self.graph_name.is_default_graph() {
+            self.writer.write_event(JsonEvent::EndArray)?;
+            self.writer.write_event(JsonEvent::EndObject)?;
+        }
+        Ok(())
+    }
+}
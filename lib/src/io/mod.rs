@@ -1,11 +1,13 @@
 //! Utilities to read and write RDF graphs and datasets.
 
 mod format;
+mod jsonld;
 pub mod read;
 pub mod write;
 
 pub use self::format::DatasetFormat;
 pub use self::format::GraphFormat;
+pub use self::jsonld::{DocumentLoader, StaticDocumentLoader};
 pub use self::read::DatasetParser;
 pub use self::read::GraphParser;
 pub use self::write::DatasetSerializer;
@@ -1,6 +1,7 @@
 //! Utilities to read RDF graphs and datasets.
 
-use crate::io::{DatasetFormat, GraphFormat};
+use crate::io::jsonld::JsonLdQuadReader;
+use crate::io::{DatasetFormat, DocumentLoader, GraphFormat};
 use crate::model::*;
 use oxiri::{Iri, IriParseError};
 use rio_api::model as rio;
@@ -10,6 +11,7 @@ use rio_xml::RdfXmlParser;
 use std::collections::HashMap;
 use std::io;
 use std::io::BufRead;
+use std::sync::Arc;
 
 /// Parsers for RDF graph serialization formats.
 ///
@@ -166,6 +168,12 @@ impl<R: BufRead> TripleReader<R> {
 /// It currently supports the following formats:
 /// * [N-Quads](https://www.w3.org/TR/n-quads/) ([`DatasetFormat::NQuads`](super::DatasetFormat::NQuads))
 /// * [TriG](https://www.w3.org/TR/trig/) ([`DatasetFormat::TriG`](super::DatasetFormat::TriG))
+/// * [JSON-LD](https://www.w3.org/TR/json-ld11/) ([`DatasetFormat::JsonLd`](super::DatasetFormat::JsonLd))
+///
+/// JSON-LD documents are read in a streaming way if their top-level object starts with its `@context` and `@id` entries,
+/// like in the [streaming document form](https://w3c.github.io/json-ld-streaming/#streaming-document-form):
+/// the quads of each node object of the top-level array or `@graph` are returned once the node object is read.
+/// Remote contexts are only loaded using the [`DocumentLoader`] given to [`DatasetParser::with_document_loader`].
 ///
 /// ```
 /// use oxigraph::io::{DatasetFormat, DatasetParser};
@@ -183,6 +191,7 @@ impl<R: BufRead> TripleReader<R> {
 pub struct DatasetParser {
     format: DatasetFormat,
     base_iri: Option<Iri<String>>,
+    document_loader: Option<Arc<dyn DocumentLoader>>,
 }
 
 impl DatasetParser {
@@ -191,6 +200,7 @@ impl DatasetParser {
         Self {
             format,
             base_iri: None,
+            document_loader: None,
         }
     }
 
@@ -214,6 +224,16 @@ impl DatasetParser {
         Ok(self)
     }
 
+    /// Provides the [`DocumentLoader`] used to load the remote JSON-LD contexts.
+    ///
+    /// Without it, JSON-LD documents referencing remote contexts are rejected.
+    /// See [`StaticDocumentLoader`](super::StaticDocumentLoader) to provide the contexts offline.
+    #[inline]
+    pub fn with_document_loader(mut self, loader: impl DocumentLoader + 'static) -> Self {
+        self.document_loader = Some(Arc::new(loader));
+        self
+    }
+
     /// Executes the parsing itself on a [`BufRead`](std::io::BufRead) implementation and returns an iterator of quads
     #[allow(clippy::unnecessary_wraps)]
     pub fn read_quads<R: BufRead>(&self, reader: R) -> io::Result<QuadReader<R>> {
@@ -224,6 +244,11 @@ impl DatasetParser {
                 DatasetFormat::TriG => {
                     QuadReaderKind::TriG(TriGParser::new(reader, self.base_iri.clone()))
                 }
+                DatasetFormat::JsonLd => QuadReaderKind::JsonLd(Box::new(JsonLdQuadReader::new(
+                    reader,
+                    self.base_iri.clone(),
+                    self.document_loader.clone(),
+                ))),
             },
             buffer: Vec::new(),
         })
@@ -256,6 +281,7 @@ pub struct QuadReader<R: BufRead> {
 enum QuadReaderKind<R: BufRead> {
     NQuads(NQuadsParser<R>),
     TriG(TriGParser<R>),
+    JsonLd(Box<JsonLdQuadReader<R>>),
 }
 
 impl<R: BufRead> Iterator for QuadReader<R> {
@@ -274,6 +300,13 @@ impl<R: BufRead> Iterator for QuadReader<R> {
                 QuadReaderKind::TriG(parser) => {
                     Self::read(parser, &mut self.buffer, &mut self.mapper)
                 }
+                QuadReaderKind::JsonLd(parser) => {
+                    let start = self.buffer.len();
+                    let result = parser.read(&mut self.buffer);
+                    // The buffer is read from the end
+                    self.buffer[start..].reverse();
+                    result
+                }
             }? {
                 return Some(Err(error));
             }
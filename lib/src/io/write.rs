@@ -1,13 +1,17 @@
 //! Utilities to write RDF graphs and datasets.
 
 use crate::error::invalid_input_error;
-use crate::io::{DatasetFormat, GraphFormat};
+use crate::io::jsonld::{
+    read_json_ld_context, write_flattened_json_ld, JsonLdQuadWriter, JsonLdWriterOptions,
+};
+use crate::io::{DatasetFormat, DocumentLoader, GraphFormat};
 use crate::model::*;
 use rio_api::formatter::TriplesFormatter;
 use rio_api::model as rio;
 use rio_xml::RdfXmlFormatter;
 use std::io;
 use std::io::Write;
+use std::sync::Arc;
 
 /// A serializer for RDF graph serialization formats.
 ///
@@ -153,6 +157,16 @@ impl<W: Write> TripleWriter<W> {
 /// It currently supports the following formats:
 /// * [N-Quads](https://www.w3.org/TR/n-quads/) ([`DatasetFormat::NQuads`](super::DatasetFormat::NQuads))
 /// * [TriG](https://www.w3.org/TR/trig/) ([`DatasetFormat::TriG`](super::DatasetFormat::TriG))
+/// * [JSON-LD](https://www.w3.org/TR/json-ld11/) ([`DatasetFormat::JsonLd`](super::DatasetFormat::JsonLd))
+///
+/// JSON-LD documents are written in [expanded form](https://www.w3.org/TR/json-ld11/#expanded-document-form) by default.
+/// They could be [compacted](https://www.w3.org/TR/json-ld11/#compacted-document-form) against a context
+/// using [`DatasetSerializer::with_context`] and [flattened](https://www.w3.org/TR/json-ld11/#flattened-document-form)
+/// using [`DatasetSerializer::with_flattening`].
+/// Expanded and compacted documents are written while the quads are written:
+/// the consecutive quads sharing the same graph name and subject, like the ones returned by [`Store::iter`](crate::store::Store::iter),
+/// are grouped into a single node object and lists are kept as `rdf:first` and `rdf:rest` triples.
+/// Flattening requires to keep the quads in memory until [`QuadWriter::finish`] is called.
 ///
 /// ```
 /// use oxigraph::io::{DatasetFormat, DatasetSerializer};
@@ -171,24 +185,95 @@ impl<W: Write> TripleWriter<W> {
 ///assert_eq!(buffer.as_slice(), "<http://example.com/s> <http://example.com/p> <http://example.com/o> <http://example.com/g> .\n".as_bytes());
 /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
 /// ```
-#[allow(missing_copy_implementations)]
 pub struct DatasetSerializer {
     format: DatasetFormat,
+    json_ld_options: JsonLdWriterOptions,
+    document_loader: Option<Arc<dyn DocumentLoader>>,
 }
 
 impl DatasetSerializer {
     /// Builds a serializer for the given format
     pub fn from_format(format: DatasetFormat) -> Self {
-        Self { format }
+        Self {
+            format,
+            json_ld_options: JsonLdWriterOptions::default(),
+            document_loader: None,
+        }
+    }
+
+    /// Compacts the written JSON-LD documents against the given [JSON-LD context](https://www.w3.org/TR/json-ld11/#the-context).
+    ///
+    /// The context could be a context object, the IRI of a remote context or a full JSON-LD document with a `@context` entry.
+    /// Returns an [`InvalidInput`](std::io::ErrorKind::InvalidInput) error if the context is not valid JSON.
+    ///
+    /// ```
+    /// use oxigraph::io::{DatasetFormat, DatasetSerializer};
+    /// use oxigraph::model::*;
+    ///
+    /// let mut buffer = Vec::new();
+    /// let mut writer = DatasetSerializer::from_format(DatasetFormat::JsonLd)
+    ///     .with_context(r#"{"@vocab": "http://schema.org/"}"#)?
+    ///     .quad_writer(&mut buffer)?;
+    /// writer.write(QuadRef::new(
+    ///     NamedNodeRef::new("http://example.com/alice")?,
+    ///     NamedNodeRef::new("http://schema.org/name")?,
+    ///     LiteralRef::new_simple_literal("Alice"),
+    ///     GraphNameRef::DefaultGraph,
+    /// ))?;
+    /// writer.finish()?;
+    ///
+    /// assert_eq!(
+    ///     String::from_utf8(buffer)?,
+    ///     r#"{"@context":{"@vocab":"http://schema.org/"},"@graph":[{"@id":"http://example.com/alice","name":"Alice"}]}"#
+    /// );
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn with_context(mut self, context: &str) -> io::Result<Self> {
+        let context = read_json_ld_context(context).map_err(|e| {
+            invalid_input_error(format!("The JSON-LD context is not valid JSON: {}", e))
+        })?;
+        self.json_ld_options.context = Some(Arc::new(context));
+        Ok(self)
+    }
+
+    /// Writes the JSON-LD documents in [flattened form](https://www.w3.org/TR/json-ld11/#flattened-document-form):
+    /// all the node objects are in a top-level `@graph` array and reference each other by identifier.
+    ///
+    /// All the quads are kept in memory until the end of the writing.
+    #[inline]
+    pub fn with_flattening(mut self) -> Self {
+        self.json_ld_options.flatten = true;
+        self
+    }
+
+    /// Provides the [`DocumentLoader`] used to load the remote JSON-LD contexts referenced by the [context](DatasetSerializer::with_context).
+    #[inline]
+    pub fn with_document_loader(mut self, loader: impl DocumentLoader + 'static) -> Self {
+        self.document_loader = Some(Arc::new(loader));
+        self
     }
 
     /// Returns a `QuadWriter` allowing writing triples into the given [`Write`](std::io::Write) implementation
-    #[allow(clippy::unnecessary_wraps)]
+    ///
+    /// Returns an error if the JSON-LD context could not be processed.
     pub fn quad_writer<W: Write>(&self, writer: W) -> io::Result<QuadWriter<W>> {
         Ok(QuadWriter {
             formatter: match self.format {
                 DatasetFormat::NQuads => QuadWriterKind::NQuads(writer),
                 DatasetFormat::TriG => QuadWriterKind::TriG(writer),
+                DatasetFormat::JsonLd if self.json_ld_options.flatten => {
+                    QuadWriterKind::FlattenedJsonLd {
+                        writer,
+                        quads: Vec::new(),
+                        options: self.json_ld_options.clone(),
+                        document_loader: self.document_loader.clone(),
+                    }
+                }
+                DatasetFormat::JsonLd => QuadWriterKind::JsonLd(Box::new(JsonLdQuadWriter::new(
+                    writer,
+                    self.json_ld_options.context.as_deref(),
+                    self.document_loader.clone(),
+                )?)),
             },
         })
     }
@@ -224,6 +309,13 @@ pub struct QuadWriter<W: Write> {
 enum QuadWriterKind<W: Write> {
     NQuads(W),
     TriG(W),
+    JsonLd(Box<JsonLdQuadWriter<W>>),
+    FlattenedJsonLd {
+        writer: W,
+        quads: Vec<Quad>,
+        options: JsonLdWriterOptions,
+        document_loader: Option<Arc<dyn DocumentLoader>>,
+    },
 }
 
 impl<W: Write> QuadWriter<W> {
@@ -246,13 +338,39 @@ impl<W: Write> QuadWriter<W> {
                     writeln!(writer, "{} .", quad)?;
                 }
             }
+            QuadWriterKind::JsonLd(writer) => {
+                check_no_rdf_star(quad)?;
+                writer.write(quad)?;
+            }
+            QuadWriterKind::FlattenedJsonLd { quads, .. } => {
+                check_no_rdf_star(quad)?;
+                quads.push(quad.into_owned());
+            }
         }
         Ok(())
     }
 
     /// Writes the last bytes of the file
-    #[allow(clippy::unused_self, clippy::unnecessary_wraps)]
     pub fn finish(self) -> io::Result<()> {
+        match self.formatter {
+            QuadWriterKind::NQuads(_) | QuadWriterKind::TriG(_) => Ok(()),
+            QuadWriterKind::JsonLd(writer) => writer.finish(),
+            QuadWriterKind::FlattenedJsonLd {
+                writer,
+                quads,
+                options,
+                document_loader,
+            } => {
+                write_flattened_json_ld(&quads, writer, options.context.as_deref(), document_loader)
+            }
+        }
+    }
+}
+
+fn check_no_rdf_star(quad: QuadRef<'_>) -> io::Result<()> {
+    if matches!(quad.subject, SubjectRef::Triple(_)) || matches!(quad.object, TermRef::Triple(_)) {
+        Err(invalid_input_error("JSON-LD does not support RDF-star"))
+    } else {
         Ok(())
     }
 }
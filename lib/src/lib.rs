@@ -174,6 +174,8 @@
 mod error;
 pub mod io;
 pub mod model;
+pub mod reasoning;
+pub mod shacl;
 #[cfg(feature = "sophia")]
 mod sophia;
 pub mod sparql;
@@ -0,0 +1,784 @@
+//! [GeoSPARQL](https://www.ogc.org/standards/geosparql) geometries serialized as [WKT](https://www.ogc.org/standards/sfa) literals
+//! and the simple features relations between them.
+//!
+//! The relations are computed in the plane of the longitudes and latitudes.
+
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+pub const WKT_LITERAL: &str = "http://www.opengis.net/ont/geosparql#wktLiteral";
+const CRS84: &str = "http://www.opengis.net/def/crs/OGC/1.3/CRS84";
+const EPSG_4326: &str = "http://www.opengis.net/def/crs/EPSG/0/4326";
+const METRE: &str = "http://www.opengis.net/def/uom/OGC/1.0/metre";
+const RADIAN: &str = "http://www.opengis.net/def/uom/OGC/1.0/radian";
+const DEGREE: &str = "http://www.opengis.net/def/uom/OGC/1.0/degree";
+/// The mean radius of the Earth in metres
+const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// A longitude and a latitude
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    fn distance(self, other: Self) -> f64 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+
+    /// The angle between the two points from the center of the Earth in radians using the haversine formula
+    fn central_angle(self, other: Self) -> f64 {
+        let (lat1, lat2) = (self.y.to_radians(), other.y.to_radians());
+        let h = (lat1.cos() * lat2.cos()).mul_add(
+            ((other.x - self.x).to_radians() / 2.).sin().powi(2),
+            ((lat2 - lat1) / 2.).sin().powi(2),
+        );
+        2. * h.sqrt().min(1.).asin()
+    }
+}
+
+/// A bounding box
+#[derive(Debug, Clone, Copy)]
+pub struct Rect {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl Rect {
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+    }
+
+    fn extend(&mut self, point: Point) {
+        self.min.x = self.min.x.min(point.x);
+        self.min.y = self.min.y.min(point.y);
+        self.max.x = self.max.x.max(point.x);
+        self.max.y = self.max.y.max(point.y);
+    }
+
+    fn bits(&self) -> [u64; 4] {
+        [
+            self.min.x.to_bits(),
+            self.min.y.to_bits(),
+            self.max.x.to_bits(),
+            self.max.y.to_bits(),
+        ]
+    }
+}
+
+impl PartialEq for Rect {
+    fn eq(&self, other: &Self) -> bool {
+        self.bits() == other.bits()
+    }
+}
+
+impl Eq for Rect {}
+
+impl Hash for Rect {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.bits().hash(state)
+    }
+}
+
+impl fmt::Display for Rect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}, {} {}",
+            self.min.x, self.min.y, self.max.x, self.max.y
+        )
+    }
+}
+
+/// The unit of [`Geometry::distance`]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DistanceUnit {
+    Metre,
+    Radian,
+    Degree,
+}
+
+impl DistanceUnit {
+    pub fn from_iri(iri: &str) -> Option<Self> {
+        match iri {
+            METRE => Some(Self::Metre),
+            RADIAN => Some(Self::Radian),
+            DEGREE => Some(Self::Degree),
+            _ => None,
+        }
+    }
+}
+
+/// A geometry with coordinates in the [CRS84](http://www.opengis.net/def/crs/OGC/1.3/CRS84) reference system.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Geometry {
+    Point(Point),
+    LineString(Vec<Point>),
+    /// The exterior ring followed by the interior rings. The rings are closed.
+    Polygon(Vec<Vec<Point>>),
+    /// Multi points, multi line strings, multi polygons, geometry collections and empty geometries
+    Collection(Vec<Self>),
+}
+
+impl Geometry {
+    pub fn bounding_box(&self) -> Option<Rect> {
+        let parts = self.parts();
+        let mut vertices = parts.vertices();
+        let first = vertices.next()?;
+        let mut rect = Rect {
+            min: first,
+            max: first,
+        };
+        for vertex in vertices {
+            rect.extend(vertex);
+        }
+        Some(rect)
+    }
+
+    /// [sfEquals](http://www.opengis.net/def/function/geosparql/sfEquals)
+    pub fn equals(&self, other: &Self) -> bool {
+        let (a, b) = (self.parts(), other.parts());
+        a.covers(&b) && b.covers(&a)
+    }
+
+    /// [sfIntersects](http://www.opengis.net/def/function/geosparql/sfIntersects)
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.parts().intersects(&other.parts())
+    }
+
+    /// [sfDisjoint](http://www.opengis.net/def/function/geosparql/sfDisjoint)
+    pub fn disjoint(&self, other: &Self) -> bool {
+        !self.intersects(other)
+    }
+
+    /// [sfContains](http://www.opengis.net/def/function/geosparql/sfContains)
+    pub fn contains(&self, other: &Self) -> bool {
+        self.parts().contains(&other.parts())
+    }
+
+    /// [sfWithin](http://www.opengis.net/def/function/geosparql/sfWithin)
+    pub fn within(&self, other: &Self) -> bool {
+        other.contains(self)
+    }
+
+    /// The [distance](http://www.opengis.net/def/function/geosparql/distance) between the closest points of the two geometries.
+    ///
+    /// The closest points are found in the plane and their distance is measured on the sphere.
+    pub fn distance(&self, other: &Self, unit: DistanceUnit) -> Option<f64> {
+        let (a, b) = (self.parts(), other.parts());
+        if a.intersects(&b) {
+            return Some(0.);
+        }
+        let (p, q) = a
+            .vertices()
+            .filter_map(|p| Some((p, b.closest_point(p)?)))
+            .chain(b.vertices().filter_map(|q| Some((a.closest_point(q)?, q))))
+            .min_by(|(p1, q1), (p2, q2)| {
+                p1.distance(*q1)
+                    .partial_cmp(&p2.distance(*q2))
+                    .unwrap_or(Ordering::Equal)
+            })?;
+        let angle = p.central_angle(q);
+        Some(match unit {
+            DistanceUnit::Metre => angle * EARTH_RADIUS,
+            DistanceUnit::Radian => angle,
+            DistanceUnit::Degree => angle.to_degrees(),
+        })
+    }
+
+    fn parts(&self) -> Parts<'_> {
+        let mut parts = Parts::default();
+        self.add_parts(&mut parts);
+        parts
+    }
+
+    fn add_parts<'a>(&'a self, parts: &mut Parts<'a>) {
+        match self {
+            Self::Point(point) => parts.points.push(*point),
+            Self::LineString(line) => parts.lines.push(line),
+            Self::Polygon(rings) => parts.polygons.push(rings),
+            Self::Collection(geometries) => {
+                for geometry in geometries {
+                    geometry.add_parts(parts);
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for Geometry {
+    type Err = GeoParseError;
+
+    /// Parses a WKT literal, optionally prefixed by the IRI of its coordinate reference system
+    fn from_str(wkt: &str) -> Result<Self, GeoParseError> {
+        let mut parser = WktParser {
+            input: wkt,
+            swap_axes: false,
+        };
+        if parser.consume('<') {
+            let end = parser.input.find('>').ok_or_else(|| {
+                GeoParseError::msg("Unterminated coordinate reference system IRI")
+            })?;
+            match &parser.input[..end] {
+                CRS84 => (),
+                // This reference system is using the latitude then longitude order
+                EPSG_4326 => parser.swap_axes = true,
+                crs => {
+                    return Err(GeoParseError::msg(format!(
+                        "Unsupported coordinate reference system <{}>",
+                        crs
+                    )))
+                }
+            }
+            parser.input = &parser.input[end + 1..];
+        }
+        let geometry = parser.geometry()?;
+        if parser.input.trim().is_empty() {
+            Ok(geometry)
+        } else {
+            Err(GeoParseError::msg(format!(
+                "Unexpected content at the end of the WKT literal: '{}'",
+                parser.input.trim()
+            )))
+        }
+    }
+}
+
+/// An error raised during WKT literal parsing
+#[derive(Debug, Clone)]
+pub struct GeoParseError {
+    message: String,
+}
+
+impl GeoParseError {
+    fn msg(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for GeoParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid WKT literal: {}", self.message)
+    }
+}
+
+impl Error for GeoParseError {}
+
+struct WktParser<'a> {
+    input: &'a str,
+    swap_axes: bool,
+}
+
+impl<'a> WktParser<'a> {
+    fn consume(&mut self, c: char) -> bool {
+        self.input = self.input.trim_start();
+        if let Some(rest) = self.input.strip_prefix(c) {
+            self.input = rest;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), GeoParseError> {
+        if self.consume(c) {
+            Ok(())
+        } else {
+            Err(GeoParseError::msg(format!("'{}' expected", c)))
+        }
+    }
+
+    fn keyword(&mut self) -> String {
+        self.input = self.input.trim_start();
+        let end = self
+            .input
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(self.input.len());
+        let keyword = self.input[..end].to_ascii_uppercase();
+        self.input = &self.input[end..];
+        keyword
+    }
+
+    fn number(&mut self) -> Result<f64, GeoParseError> {
+        self.input = self.input.trim_start();
+        let end = self
+            .input
+            .find(|c: char| !matches!(c, '0'..='9' | '-' | '+' | '.' | 'e' | 'E'))
+            .unwrap_or(self.input.len());
+        let number = f64::from_str(&self.input[..end])
+            .ok()
+            .filter(|number| number.is_finite())
+            .ok_or_else(|| {
+                GeoParseError::msg(format!("Invalid coordinate '{}'", &self.input[..end]))
+            })?;
+        self.input = &self.input[end..];
+        Ok(number)
+    }
+
+    fn is_number_next(&mut self) -> bool {
+        self.input = self.input.trim_start();
+        self.input
+            .starts_with(|c: char| matches!(c, '0'..='9' | '-' | '+' | '.'))
+    }
+
+    fn point(&mut self) -> Result<Point, GeoParseError> {
+        let (x, y) = (self.number()?, self.number()?);
+        // We ignore the Z and M coordinates
+        while self.is_number_next() {
+            self.number()?;
+        }
+        Ok(if self.swap_axes {
+            Point { x: y, y: x }
+        } else {
+            Point { x, y }
+        })
+    }
+
+    fn list<T>(
+        &mut self,
+        mut element: impl FnMut(&mut Self) -> Result<T, GeoParseError>,
+    ) -> Result<Vec<T>, GeoParseError> {
+        self.expect('(')?;
+        let mut elements = vec![element(self)?];
+        while self.consume(',') {
+            elements.push(element(self)?);
+        }
+        self.expect(')')?;
+        Ok(elements)
+    }
+
+    fn line(&mut self) -> Result<Vec<Point>, GeoParseError> {
+        let line = self.list(Self::point)?;
+        if line.len() < 2 {
+            return Err(GeoParseError::msg(
+                "A line string should have at least 2 points",
+            ));
+        }
+        Ok(line)
+    }
+
+    fn polygon(&mut self) -> Result<Vec<Vec<Point>>, GeoParseError> {
+        self.list(|parser| {
+            let ring = parser.list(Self::point)?;
+            if ring.len() < 4 || ring.first() != ring.last() {
+                return Err(GeoParseError::msg(
+                    "A polygon ring should be closed and have at least 4 points",
+                ));
+            }
+            Ok(ring)
+        })
+    }
+
+    fn geometry(&mut self) -> Result<Geometry, GeoParseError> {
+        let kind = self.keyword();
+        let mut modifier = self.keyword();
+        if matches!(modifier.as_str(), "Z" | "M" | "ZM") {
+            modifier = self.keyword();
+        }
+        match modifier.as_str() {
+            "EMPTY" => return Ok(Geometry::Collection(Vec::new())),
+            "" => (),
+            _ => return Err(GeoParseError::msg(format!("Unexpected '{}'", modifier))),
+        }
+        Ok(match kind.as_str() {
+            "POINT" => {
+                self.expect('(')?;
+                let point = self.point()?;
+                self.expect(')')?;
+                Geometry::Point(point)
+            }
+            "LINESTRING" => Geometry::LineString(self.line()?),
+            "POLYGON" => Geometry::Polygon(self.polygon()?),
+            "MULTIPOINT" => Geometry::Collection(self.list(|parser| {
+                // The points might be or not be in parenthesis
+                Ok(Geometry::Point(if parser.consume('(') {
+                    let point = parser.point()?;
+                    parser.expect(')')?;
+                    point
+                } else {
+                    parser.point()?
+                }))
+            })?),
+            "MULTILINESTRING" => {
+                Geometry::Collection(self.list(|parser| Ok(Geometry::LineString(parser.line()?)))?)
+            }
+            "MULTIPOLYGON" => {
+                Geometry::Collection(self.list(|parser| Ok(Geometry::Polygon(parser.polygon()?)))?)
+            }
+            "GEOMETRYCOLLECTION" => Geometry::Collection(self.list(Self::geometry)?),
+            _ => {
+                return Err(GeoParseError::msg(format!(
+                    "Unsupported geometry type '{}'",
+                    kind
+                )))
+            }
+        })
+    }
+}
+
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+enum Location {
+    Interior,
+    Boundary,
+    Exterior,
+}
+
+/// The points, line strings and polygons composing a geometry
+#[derive(Default)]
+struct Parts<'a> {
+    points: Vec<Point>,
+    lines: Vec<&'a [Point]>,
+    polygons: Vec<&'a [Vec<Point>]>,
+}
+
+impl<'a> Parts<'a> {
+    fn is_empty(&self) -> bool {
+        self.points.is_empty() && self.lines.is_empty() && self.polygons.is_empty()
+    }
+
+    fn vertices(&self) -> impl Iterator<Item = Point> + '_ {
+        self.points
+            .iter()
+            .chain(self.lines.iter().flat_map(|line| line.iter()))
+            .chain(
+                self.polygons
+                    .iter()
+                    .flat_map(|rings| rings.iter().flatten()),
+            )
+            .copied()
+    }
+
+    fn segments(&self) -> Vec<(Point, Point)> {
+        self.lines
+            .iter()
+            .copied()
+            .chain(
+                self.polygons
+                    .iter()
+                    .flat_map(|rings| rings.iter().map(Vec::as_slice)),
+            )
+            .flat_map(|line| line.windows(2).map(|s| (s[0], s[1])))
+            .collect()
+    }
+
+    /// A point of each connected part
+    fn representative_points(&self) -> impl Iterator<Item = Point> + '_ {
+        self.points
+            .iter()
+            .chain(self.lines.iter().filter_map(|line| line.first()))
+            .chain(
+                self.polygons
+                    .iter()
+                    .filter_map(|rings| rings.first()?.first()),
+            )
+            .copied()
+    }
+
+    fn locate(&self, point: Point) -> Location {
+        let mut location = Location::Exterior;
+        if self.points.contains(&point) {
+            return Location::Interior;
+        }
+        for line in &self.lines {
+            if line.windows(2).any(|s| is_on_segment(point, s[0], s[1])) {
+                let is_closed = line.first() == line.last();
+                if is_closed || (line.first() != Some(&point) && line.last() != Some(&point)) {
+                    return Location::Interior;
+                }
+                location = Location::Boundary;
+            }
+        }
+        for rings in &self.polygons {
+            match locate_in_polygon(point, rings) {
+                Location::Interior => return Location::Interior,
+                Location::Boundary => location = Location::Boundary,
+                Location::Exterior => (),
+            }
+        }
+        location
+    }
+
+    fn closest_point(&self, point: Point) -> Option<Point> {
+        self.points
+            .iter()
+            .copied()
+            .chain(
+                self.segments()
+                    .into_iter()
+                    .map(|(a, b)| closest_point_on_segment(point, a, b)),
+            )
+            .min_by(|a, b| {
+                a.distance(point)
+                    .partial_cmp(&b.distance(point))
+                    .unwrap_or(Ordering::Equal)
+            })
+    }
+
+    fn intersects(&self, other: &Parts<'_>) -> bool {
+        if self.is_empty() || other.is_empty() {
+            return false;
+        }
+        let other_segments = other.segments();
+        self.segments().iter().any(|(a, b)| {
+            other_segments
+                .iter()
+                .any(|(c, d)| do_segments_intersect(*a, *b, *c, *d))
+        }) || self
+            .representative_points()
+            .any(|p| other.locate(p) != Location::Exterior)
+            || other
+                .representative_points()
+                .any(|p| self.locate(p) != Location::Exterior)
+    }
+
+    /// The points to check in order to know if `other` is covered by `self`:
+    /// the vertices of `other` and the middles of the pieces of its segments between the segments of `self`
+    fn sample_points(&self, other: &Parts<'_>) -> Vec<Point> {
+        let segments = self.segments();
+        let mut samples = other.vertices().collect::<Vec<_>>();
+        for (p, q) in other.segments() {
+            if p == q {
+                continue;
+            }
+            let mut positions = vec![0., 1.];
+            for (a, b) in &segments {
+                add_meeting_positions(p, q, *a, *b, &mut positions);
+            }
+            positions.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+            for w in positions.windows(2) {
+                if w[0] < w[1] {
+                    let t = (w[0] + w[1]) / 2.;
+                    samples.push(Point {
+                        x: p.x + t * (q.x - p.x),
+                        y: p.y + t * (q.y - p.y),
+                    });
+                }
+            }
+        }
+        samples
+    }
+
+    /// If all the points of `other` are in `self`
+    fn covers(&self, other: &Parts<'_>) -> bool {
+        if other.is_empty() || (!other.polygons.is_empty() && self.polygons.is_empty()) {
+            return false;
+        }
+        if !self
+            .sample_points(other)
+            .into_iter()
+            .all(|p| self.locate(p) != Location::Exterior)
+        {
+            return false;
+        }
+        // The holes of self should not be inside of other
+        self.polygons.iter().all(|rings| {
+            rings.iter().skip(1).all(|hole| {
+                let hole = Parts {
+                    lines: vec![hole],
+                    ..Parts::default()
+                };
+                other
+                    .sample_points(&hole)
+                    .into_iter()
+                    .all(|p| other.locate(p) != Location::Interior)
+            })
+        })
+    }
+
+    /// If `other` is covered by `self` and their interiors intersect
+    fn contains(&self, other: &Parts<'_>) -> bool {
+        self.covers(other)
+            && (!other.polygons.is_empty()
+                || self.sample_points(other).into_iter().any(|p| {
+                    self.locate(p) == Location::Interior && other.locate(p) == Location::Interior
+                }))
+    }
+}
+
+fn locate_in_polygon(point: Point, rings: &[Vec<Point>]) -> Location {
+    if rings
+        .iter()
+        .any(|ring| ring.windows(2).any(|s| is_on_segment(point, s[0], s[1])))
+    {
+        return Location::Boundary;
+    }
+    let mut rings = rings.iter();
+    if rings.next().map_or(false, |ring| is_in_ring(point, ring))
+        && !rings.any(|ring| is_in_ring(point, ring))
+    {
+        Location::Interior
+    } else {
+        Location::Exterior
+    }
+}
+
+/// Ray casting algorithm
+fn is_in_ring(point: Point, ring: &[Point]) -> bool {
+    let mut inside = false;
+    for s in ring.windows(2) {
+        let (a, b) = (s[0], s[1]);
+        if (a.y > point.y) != (b.y > point.y)
+            && point.x < (b.x - a.x) * (point.y - a.y) / (b.y - a.y) + a.x
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// The cross product of (b - a) and (c - a)
+fn orientation(a: Point, b: Point, c: Point) -> f64 {
+    (b.x - a.x).mul_add(c.y - a.y, -((b.y - a.y) * (c.x - a.x)))
+}
+
+fn is_in_segment_box(point: Point, a: Point, b: Point) -> bool {
+    a.x.min(b.x) <= point.x
+        && point.x <= a.x.max(b.x)
+        && a.y.min(b.y) <= point.y
+        && point.y <= a.y.max(b.y)
+}
+
+fn is_on_segment(point: Point, a: Point, b: Point) -> bool {
+    orientation(a, b, point) == 0. && is_in_segment_box(point, a, b)
+}
+
+fn do_segments_intersect(a: Point, b: Point, c: Point, d: Point) -> bool {
+    let (o1, o2) = (orientation(a, b, c), orientation(a, b, d));
+    let (o3, o4) = (orientation(c, d, a), orientation(c, d, b));
+    if o1 * o2 < 0. && o3 * o4 < 0. {
+        return true;
+    }
+    is_on_segment(c, a, b)
+        || is_on_segment(d, a, b)
+        || is_on_segment(a, c, d)
+        || is_on_segment(b, c, d)
+}
+
+/// Adds the positions along [p, q], between 0 and 1, where it meets [a, b]
+fn add_meeting_positions(p: Point, q: Point, a: Point, b: Point, positions: &mut Vec<f64>) {
+    let denominator = (q.x - p.x).mul_add(b.y - a.y, -((q.y - p.y) * (b.x - a.x)));
+    if denominator == 0. {
+        // The segments are parallel: they only meet if they are on the same line
+        for c in [a, b] {
+            if is_on_segment(c, p, q) {
+                positions.push(if (q.x - p.x).abs() > (q.y - p.y).abs() {
+                    (c.x - p.x) / (q.x - p.x)
+                } else {
+                    (c.y - p.y) / (q.y - p.y)
+                });
+            }
+        }
+    } else {
+        let t = (a.x - p.x).mul_add(b.y - a.y, -((a.y - p.y) * (b.x - a.x))) / denominator;
+        let u = (a.x - p.x).mul_add(q.y - p.y, -((a.y - p.y) * (q.x - p.x))) / denominator;
+        if (0. ..=1.).contains(&t) && (0. ..=1.).contains(&u) {
+            positions.push(t);
+        }
+    }
+}
+
+fn closest_point_on_segment(point: Point, a: Point, b: Point) -> Point {
+    let length = (b.x - a.x).mul_add(b.x - a.x, (b.y - a.y).powi(2));
+    if length == 0. {
+        return a;
+    }
+    let t =
+        ((point.x - a.x).mul_add(b.x - a.x, (point.y - a.y) * (b.y - a.y)) / length).clamp(0., 1.);
+    Point {
+        x: a.x + t * (b.x - a.x),
+        y: a.y + t * (b.y - a.y),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wkt(value: &str) -> Geometry {
+        Geometry::from_str(value).unwrap()
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!(wkt("POINT(1 2)"), Geometry::Point(Point { x: 1., y: 2. }));
+        assert_eq!(
+            wkt(" point z ( 1 2 3 ) "),
+            Geometry::Point(Point { x: 1., y: 2. })
+        );
+        assert_eq!(
+            wkt("<http://www.opengis.net/def/crs/EPSG/0/4326> POINT(1 2)"),
+            Geometry::Point(Point { x: 2., y: 1. })
+        );
+        assert_eq!(wkt("LINESTRING EMPTY"), Geometry::Collection(Vec::new()));
+        assert_eq!(
+            wkt("MULTIPOINT((1 2), 3 4)"),
+            Geometry::Collection(vec![
+                Geometry::Point(Point { x: 1., y: 2. }),
+                Geometry::Point(Point { x: 3., y: 4. })
+            ])
+        );
+        assert!(matches!(
+            wkt("GEOMETRYCOLLECTION(POINT(1 2), POLYGON((0 0, 1 0, 1 1, 0 0)), MULTILINESTRING((0 0, 1 1)))"),
+            Geometry::Collection(g) if g.len() == 3
+        ));
+        assert!(Geometry::from_str("POINT(1)").is_err());
+        assert!(Geometry::from_str("POINT(1 2) foo").is_err());
+        assert!(Geometry::from_str("POLYGON((0 0, 1 0, 1 1))").is_err());
+        assert!(Geometry::from_str("<http://example.com/crs> POINT(1 2)").is_err());
+    }
+
+    #[test]
+    fn relations() {
+        let square = wkt("POLYGON((0 0, 10 0, 10 10, 0 10, 0 0))");
+        let donut = wkt("POLYGON((0 0, 10 0, 10 10, 0 10, 0 0), (4 4, 6 4, 6 6, 4 6, 4 4))");
+        let inner = wkt("POLYGON((1 1, 2 1, 2 2, 1 2, 1 1))");
+
+        assert!(square.contains(&wkt("POINT(5 5)")));
+        assert!(!square.contains(&wkt("POINT(0 5)")));
+        assert!(square.intersects(&wkt("POINT(0 5)")));
+        assert!(square.disjoint(&wkt("POINT(11 5)")));
+        assert!(!donut.intersects(&wkt("POINT(5 5)")));
+        assert!(inner.within(&square));
+        assert!(inner.within(&donut));
+        assert!(!square.within(&inner));
+        assert!(!wkt("POLYGON((3 3, 7 3, 7 7, 3 7, 3 3))").within(&donut));
+        assert!(wkt("LINESTRING(1 1, 9 9)").within(&square));
+        assert!(!wkt("LINESTRING(1 1, 9 9)").within(&donut));
+        assert!(!wkt("LINESTRING(0 0, 10 0)").within(&square));
+        assert!(wkt("LINESTRING(-1 5, 11 5)").intersects(&square));
+        assert!(wkt("LINESTRING(0 0, 1 1, 2 2)").equals(&wkt("LINESTRING(2 2, 0 0)")));
+        assert!(square.equals(&wkt("POLYGON((10 10, 0 10, 0 0, 10 0, 10 10))")));
+        assert!(!square.equals(&inner));
+        assert!(!wkt("POINT EMPTY").intersects(&square));
+    }
+
+    #[test]
+    fn distance() {
+        let paris = wkt("POINT(2.3522 48.8566)");
+        let london = wkt("POINT(-0.1276 51.5072)");
+        let metres = paris.distance(&london, DistanceUnit::Metre).unwrap();
+        assert!((340_000. ..350_000.).contains(&metres));
+        assert_eq!(
+            wkt("POLYGON((0 0, 10 0, 10 10, 0 10, 0 0))")
+                .distance(&wkt("POINT(5 5)"), DistanceUnit::Degree),
+            Some(0.)
+        );
+        let degrees = wkt("LINESTRING(0 -10, 0 10)")
+            .distance(&wkt("POINT(1 0)"), DistanceUnit::Degree)
+            .unwrap();
+        assert!((degrees - 1.).abs() < 1e-9);
+        assert_eq!(
+            wkt("POINT EMPTY").distance(&paris, DistanceUnit::Metre),
+            None
+        );
+    }
+}
@@ -4,6 +4,7 @@
 
 mod blank_node;
 pub mod dataset;
+pub(crate) mod geo;
 pub mod graph;
 mod interning;
 mod literal;
@@ -0,0 +1,661 @@
+//! Evaluation of user-defined rules written as SPARQL `CONSTRUCT` queries.
+
+use crate::model::*;
+use crate::sparql::{EvaluationError, Query, QueryOptions, QueryResults};
+use crate::store::{Store, Transaction};
+use rand::random;
+use spargebra::algebra::{
+    AggregateExpression, Expression, Function, GraphPattern, OrderExpression,
+    PropertyPathExpression,
+};
+use spargebra::term::{NamedNodePattern, TermPattern, TriplePattern};
+use std::collections::HashSet;
+
+/// A set of rules written as SPARQL [`CONSTRUCT`](https://www.w3.org/TR/sparql11-query/#construct) queries.
+///
+/// The rules are applied to a store with [`Store::apply_rules`] until they do not produce new triples anymore.
+/// Each rule reads the store default graph and the graph of the inferred triples
+/// unless its query has its own dataset specification with `FROM` or `FROM NAMED`.
+///
+/// The rules are evaluated following their dependencies:
+/// a rule is only evaluated after all the rules that might produce triples matching its patterns.
+/// The mutually recursive rules are evaluated together until a fixpoint is reached
+/// and a rule is only evaluated again if the previous iteration produced triples with one of the predicates it reads.
+/// The evaluation is semi-naive: after the first iteration, a rule only looks for the solutions
+/// using at least one of the triples produced by the previous iteration.
+/// The rules with `OPTIONAL`, `MINUS`, `EXISTS`, aggregates, `LIMIT`, `GRAPH` or `SERVICE`
+/// and the rules with their own dataset specification are fully evaluated at each iteration.
+///
+/// The recursive rules creating new blank nodes or values with `BNODE()`, `UUID()`, `STRUUID()` or `RAND()`
+/// would never reach a fixpoint and are rejected.
+///
+/// Usage example:
+/// ```
+/// use oxigraph::model::*;
+/// use oxigraph::reasoning::ConstructRules;
+/// use oxigraph::sparql::Query;
+/// use oxigraph::store::Store;
+///
+/// let rules = ConstructRules::new([Query::parse(
+///     "PREFIX ex: <http://example.com/> CONSTRUCT { ?x ex:ancestor ?z } WHERE { ?x ex:parent|ex:ancestor ?y . ?y ex:parent ?z }",
+///     None,
+/// )?])?;
+///
+/// let store = Store::new()?;
+/// let ex = |name| NamedNode::new_unchecked(format!("http://example.com/{}", name));
+/// store.insert(&Quad::new(ex("a"), ex("parent"), ex("b"), GraphName::DefaultGraph))?;
+/// store.insert(&Quad::new(ex("b"), ex("parent"), ex("c"), GraphName::DefaultGraph))?;
+/// store.insert(&Quad::new(ex("c"), ex("parent"), ex("d"), GraphName::DefaultGraph))?;
+///
+/// let inferred = ex("inferred");
+/// assert_eq!(store.apply_rules(&rules, &inferred)?, 3);
+/// assert!(store.contains(&Quad::new(ex("a"), ex("ancestor"), ex("d"), inferred))?);
+/// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConstructRules {
+    rules: Vec<ConstructRule>,
+    /// The groups of rules in evaluation order and if they are recursive
+    strata: Vec<(Vec<usize>, bool)>,
+    max_iterations: usize,
+}
+
+#[derive(Debug, Clone)]
+struct ConstructRule {
+    query: Query,
+    reads: Predicates,
+    writes: Predicates,
+    creates_new_terms: bool,
+    /// If the pattern might be evaluated only on the new triples of the previous iteration
+    is_monotonic: bool,
+}
+
+impl ConstructRules {
+    /// Builds the rules from `CONSTRUCT` queries.
+    ///
+    /// Fails if one of the queries is not a `CONSTRUCT` query or if some recursive rules would never reach a fixpoint.
+    pub fn new(queries: impl IntoIterator<Item = Query>) -> Result<Self, EvaluationError> {
+        let rules = queries
+            .into_iter()
+            .map(ConstructRule::new)
+            .collect::<Result<Vec<_>, _>>()?;
+        let strata = stratify(&rules);
+        for (stratum, is_recursive) in &strata {
+            if *is_recursive {
+                if let Some(rule) = stratum.iter().find(|i| rules[**i].creates_new_terms) {
+                    return Err(EvaluationError::msg(format!(
+                        "The rule {} is recursive and creates new blank nodes or values: it would never reach a fixpoint",
+                        rules[*rule].query
+                    )));
+                }
+            }
+        }
+        Ok(Self {
+            rules,
+            strata,
+            max_iterations: 1000,
+        })
+    }
+
+    /// Sets the maximal number of iterations of a group of recursive rules. It is 1000 by default.
+    ///
+    /// When the limit is reached, the evaluation fails with [`EvaluationError::LimitExceeded`].
+    #[inline]
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Evaluates the rules on the store until a fixpoint is reached and returns the number of inserted triples
+    ///
+    /// All the triples are inserted in a single transaction.
+    pub(crate) fn apply(
+        &self,
+        store: &Store,
+        target_graph: NamedOrBlankNodeRef<'_>,
+    ) -> Result<usize, EvaluationError> {
+        let target_graph = match target_graph {
+            NamedOrBlankNodeRef::NamedNode(node) => GraphName::from(node),
+            NamedOrBlankNodeRef::BlankNode(node) => GraphName::from(node),
+        };
+        store.transaction(|mut transaction| self.apply_in(&mut transaction, &target_graph))
+    }
+
+    fn apply_in(
+        &self,
+        transaction: &mut Transaction<'_>,
+        target_graph: &GraphName,
+    ) -> Result<usize, EvaluationError> {
+        // Name of the in-memory graph with the triples produced by the previous iteration of a recursive group
+        let delta = NamedNode::new_unchecked(format!("urn:oxigraph:delta:{:x}", random::<u128>()));
+        let mut inserted_count = 0;
+        for (stratum, is_recursive) in &self.strata {
+            let mut to_evaluate = stratum.clone();
+            let mut previous = None;
+            let mut iterations = 0;
+            while !to_evaluate.is_empty() {
+                if iterations == self.max_iterations {
+                    return Err(EvaluationError::LimitExceeded(format!(
+                        "The rules did not reach a fixpoint after {} iterations",
+                        self.max_iterations
+                    )));
+                }
+                iterations += 1;
+                let mut new_triples = HashSet::new();
+                for rule in &to_evaluate {
+                    self.rules[*rule].evaluate(
+                        transaction,
+                        target_graph,
+                        previous
+                            .as_ref()
+                            .map(|(triples, written)| (delta.as_ref(), triples, written)),
+                        &mut new_triples,
+                    )?;
+                }
+                inserted_count += new_triples.len();
+                let mut new_written = Predicates::default();
+                for triple in &new_triples {
+                    new_written.insert(triple.predicate.as_str());
+                }
+                transaction.extend(
+                    new_triples
+                        .iter()
+                        .map(|triple| triple.as_ref().in_graph(target_graph)),
+                )?;
+                to_evaluate = if *is_recursive {
+                    stratum
+                        .iter()
+                        .copied()
+                        .filter(|rule| self.rules[*rule].reads.intersects(&new_written))
+                        .collect()
+                } else {
+                    Vec::new()
+                };
+                previous = Some((new_triples, new_written));
+            }
+        }
+        Ok(inserted_count)
+    }
+}
+
+impl ConstructRule {
+    fn new(query: Query) -> Result<Self, EvaluationError> {
+        let (template, pattern) = if let spargebra::Query::Construct {
+            template, pattern, ..
+        } = &query.inner
+        {
+            (template, pattern)
+        } else {
+            return Err(EvaluationError::msg(format!(
+                "The rule {} is not a CONSTRUCT query",
+                query
+            )));
+        };
+        let mut analysis = PatternAnalysis::default();
+        analysis.add_pattern(pattern);
+        let mut writes = Predicates::default();
+        for triple in template {
+            match &triple.predicate {
+                NamedNodePattern::NamedNode(predicate) => writes.insert(&predicate.iri),
+                NamedNodePattern::Variable(_) => writes.any = true,
+            }
+            analysis.creates_new_terms |= has_blank_node(triple);
+        }
+        Ok(Self {
+            query,
+            reads: analysis.reads,
+            writes,
+            creates_new_terms: analysis.creates_new_terms,
+            is_monotonic: !analysis.is_not_monotonic,
+        })
+    }
+
+    /// Adds to `new_triples` the triples produced by the rule that are not in the store yet
+    ///
+    /// If `delta` is set with a graph name, the triples produced by the previous iteration and their predicates,
+    /// only the solutions using at least one of these triples are evaluated when it is possible.
+    /// The rewritten pattern reads these triples from an in-memory graph with the given name.
+    fn evaluate(
+        &self,
+        transaction: &Transaction<'_>,
+        target_graph: &GraphName,
+        delta: Option<(NamedNodeRef<'_>, &HashSet<Triple>, &Predicates)>,
+        new_triples: &mut HashSet<Triple>,
+    ) -> Result<(), EvaluationError> {
+        let mut query = self.query.clone();
+        let mut delta_triples = None;
+        if query.dataset().is_default_dataset() {
+            query
+                .dataset_mut()
+                .set_default_graph(vec![GraphName::DefaultGraph, target_graph.clone()]);
+            if let (Some((delta, triples, written)), true) = (delta, self.is_monotonic) {
+                if let spargebra::Query::Construct { pattern, .. } = &mut query.inner {
+                    let delta_name = spargebra::term::NamedNode {
+                        iri: delta.as_str().to_owned(),
+                    };
+                    if let Some(rewrites) = delta_patterns(pattern, &delta_name, written) {
+                        match rewrites
+                            .into_iter()
+                            .reduce(|left, right| GraphPattern::Union {
+                                left: Box::new(left),
+                                right: Box::new(right),
+                            }) {
+                            Some(rewrite) => *pattern = rewrite,
+                            // No pattern might match the new triples
+                            None => return Ok(()),
+                        }
+                        delta_triples = Some((delta, triples));
+                    }
+                }
+            }
+        }
+        let results = if let Some((delta, triples)) = delta_triples {
+            transaction.query_with_memory_graph(query, delta, triples.iter().map(Triple::as_ref))?
+        } else {
+            transaction.query_opt(query, QueryOptions::default())?
+        };
+        if let QueryResults::Graph(triples) = results {
+            for triple in triples {
+                let triple = triple?;
+                if !new_triples.contains(&triple)
+                    && !transaction
+                        .contains(triple.as_ref().in_graph(GraphNameRef::DefaultGraph))?
+                    && !transaction.contains(triple.as_ref().in_graph(target_graph))?
+                {
+                    new_triples.insert(triple);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Rewrites a pattern into patterns returning together the solutions of the pattern
+/// that use at least one of the triples of the `delta` graph, written with one of the `written` predicates
+///
+/// Each rewrite reads one of the triple patterns or one-step paths only from the `delta` graph.
+/// Returns `None` if the pattern can not be rewritten.
+fn delta_patterns(
+    pattern: &GraphPattern,
+    delta: &spargebra::term::NamedNode,
+    written: &Predicates,
+) -> Option<Vec<GraphPattern>> {
+    let in_delta = |inner| GraphPattern::Graph {
+        name: NamedNodePattern::NamedNode(delta.clone()),
+        inner: Box::new(inner),
+    };
+    Some(match pattern {
+        GraphPattern::Bgp { patterns } => patterns
+            .iter()
+            .enumerate()
+            .filter(|(_, triple)| match &triple.predicate {
+                NamedNodePattern::NamedNode(predicate) => written.contains(&predicate.iri),
+                NamedNodePattern::Variable(_) => !written.is_empty(),
+            })
+            .map(|(i, triple)| {
+                let others = patterns
+                    .iter()
+                    .enumerate()
+                    .filter(|(j, _)| *j != i)
+                    .map(|(_, triple)| triple.clone())
+                    .collect::<Vec<_>>();
+                let triple = in_delta(GraphPattern::Bgp {
+                    patterns: vec![triple.clone()],
+                });
+                if others.is_empty() {
+                    triple
+                } else {
+                    GraphPattern::Join {
+                        left: Box::new(GraphPattern::Bgp { patterns: others }),
+                        right: Box::new(triple),
+                    }
+                }
+            })
+            .collect(),
+        GraphPattern::Path { path, .. } => {
+            if !is_one_step(path) {
+                return None;
+            }
+            vec![in_delta(pattern.clone())]
+        }
+        GraphPattern::Join { left, right } => {
+            let mut rewrites = delta_patterns(left, delta, written)?
+                .into_iter()
+                .map(|left| GraphPattern::Join {
+                    left: Box::new(left),
+                    right: right.clone(),
+                })
+                .collect::<Vec<_>>();
+            rewrites.extend(
+                delta_patterns(right, delta, written)?
+                    .into_iter()
+                    .map(|right| GraphPattern::Join {
+                        left: left.clone(),
+                        right: Box::new(right),
+                    }),
+            );
+            rewrites
+        }
+        GraphPattern::Union { left, right } => {
+            let mut rewrites = delta_patterns(left, delta, written)?;
+            rewrites.extend(delta_patterns(right, delta, written)?);
+            rewrites
+        }
+        GraphPattern::Filter { expr, inner } => delta_patterns(inner, delta, written)?
+            .into_iter()
+            .map(|inner| GraphPattern::Filter {
+                expr: expr.clone(),
+                inner: Box::new(inner),
+            })
+            .collect(),
+        GraphPattern::Extend {
+            inner,
+            variable,
+            expression,
+        } => delta_patterns(inner, delta, written)?
+            .into_iter()
+            .map(|inner| GraphPattern::Extend {
+                inner: Box::new(inner),
+                variable: variable.clone(),
+                expression: expression.clone(),
+            })
+            .collect(),
+        GraphPattern::OrderBy { inner, expression } => delta_patterns(inner, delta, written)?
+            .into_iter()
+            .map(|inner| GraphPattern::OrderBy {
+                inner: Box::new(inner),
+                expression: expression.clone(),
+            })
+            .collect(),
+        GraphPattern::Project { inner, variables } => delta_patterns(inner, delta, written)?
+            .into_iter()
+            .map(|inner| GraphPattern::Project {
+                inner: Box::new(inner),
+                variables: variables.clone(),
+            })
+            .collect(),
+        GraphPattern::Distinct { inner } => delta_patterns(inner, delta, written)?
+            .into_iter()
+            .map(|inner| GraphPattern::Distinct {
+                inner: Box::new(inner),
+            })
+            .collect(),
+        GraphPattern::Reduced { inner } => delta_patterns(inner, delta, written)?
+            .into_iter()
+            .map(|inner| GraphPattern::Reduced {
+                inner: Box::new(inner),
+            })
+            .collect(),
+        // The inline values do not read the store
+        GraphPattern::Values { .. } => Vec::new(),
+        _ => return None,
+    })
+}
+
+/// If the path only matches single triples
+fn is_one_step(path: &PropertyPathExpression) -> bool {
+    match path {
+        PropertyPathExpression::NamedNode(_) | PropertyPathExpression::NegatedPropertySet(_) => {
+            true
+        }
+        PropertyPathExpression::Reverse(inner) => is_one_step(inner),
+        PropertyPathExpression::Alternative(left, right) => is_one_step(left) && is_one_step(right),
+        _ => false,
+    }
+}
+
+/// Groups the mutually recursive rules together and sorts the groups following their dependencies
+fn stratify(rules: &[ConstructRule]) -> Vec<(Vec<usize>, bool)> {
+    // reachable[i][j] if the rule j might read triples produced by the rule i, directly or not
+    let mut reachable = rules
+        .iter()
+        .map(|producer| {
+            rules
+                .iter()
+                .map(|consumer| consumer.reads.intersects(&producer.writes))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    for k in 0..rules.len() {
+        for i in 0..rules.len() {
+            if reachable[i][k] {
+                let through_k = reachable[k].clone();
+                for (to_j, through_k) in reachable[i].iter_mut().zip(through_k) {
+                    *to_j |= through_k;
+                }
+            }
+        }
+    }
+    let mut strata = Vec::new();
+    let mut done = vec![false; rules.len()];
+    while done.iter().any(|done| !done) {
+        // A rule is ready if all the rules it depends on outside of its group are done
+        for i in 0..rules.len() {
+            if done[i] {
+                continue;
+            }
+            let is_ready = (0..rules.len()).all(|j| done[j] || !reachable[j][i] || reachable[i][j]);
+            if is_ready {
+                let stratum = (0..rules.len())
+                    .filter(|j| *j == i || (reachable[i][*j] && reachable[*j][i]))
+                    .collect::<Vec<_>>();
+                for j in &stratum {
+                    done[*j] = true;
+                }
+                strata.push((stratum, reachable[i][i]));
+            }
+        }
+    }
+    strata
+}
+
+/// A set of predicates
+#[derive(Debug, Clone, Default)]
+struct Predicates {
+    /// If any predicate might be used
+    any: bool,
+    iris: HashSet<String>,
+}
+
+impl Predicates {
+    fn insert(&mut self, iri: &str) {
+        self.iris.insert(iri.to_owned());
+    }
+
+    fn is_empty(&self) -> bool {
+        !self.any && self.iris.is_empty()
+    }
+
+    fn contains(&self, iri: &str) -> bool {
+        self.any || self.iris.contains(iri)
+    }
+
+    fn intersects(&self, other: &Self) -> bool {
+        (self.any && !other.is_empty())
+            || (other.any && !self.is_empty())
+            || self.iris.iter().any(|iri| other.iris.contains(iri))
+    }
+}
+
+/// The predicates read by a graph pattern, if it creates new terms
+/// and if it uses some operations preventing its semi-naive evaluation
+#[derive(Default)]
+struct PatternAnalysis {
+    reads: Predicates,
+    creates_new_terms: bool,
+    is_not_monotonic: bool,
+}
+
+impl PatternAnalysis {
+    fn add_pattern(&mut self, pattern: &GraphPattern) {
+        match pattern {
+            GraphPattern::Bgp { patterns } => {
+                for triple in patterns {
+                    match &triple.predicate {
+                        NamedNodePattern::NamedNode(predicate) => self.reads.insert(&predicate.iri),
+                        NamedNodePattern::Variable(_) => self.reads.any = true,
+                    }
+                }
+            }
+            GraphPattern::Path { path, .. } => self.add_path(path),
+            GraphPattern::Join { left, right } | GraphPattern::Union { left, right } => {
+                self.add_pattern(left);
+                self.add_pattern(right);
+            }
+            GraphPattern::Minus { left, right } => {
+                self.is_not_monotonic = true;
+                self.add_pattern(left);
+                self.add_pattern(right);
+            }
+            GraphPattern::LeftJoin {
+                left,
+                right,
+                expression,
+            } => {
+                self.is_not_monotonic = true;
+                self.add_pattern(left);
+                self.add_pattern(right);
+                if let Some(expression) = expression {
+                    self.add_expression(expression);
+                }
+            }
+            GraphPattern::Filter { expr, inner } => {
+                self.add_expression(expr);
+                self.add_pattern(inner);
+            }
+            GraphPattern::Extend {
+                inner, expression, ..
+            } => {
+                self.add_expression(expression);
+                self.add_pattern(inner);
+            }
+            GraphPattern::OrderBy { inner, expression } => {
+                for expression in expression {
+                    match expression {
+                        OrderExpression::Asc(expression) | OrderExpression::Desc(expression) => {
+                            self.add_expression(expression)
+                        }
+                    }
+                }
+                self.add_pattern(inner);
+            }
+            GraphPattern::Group {
+                inner, aggregates, ..
+            } => {
+                self.is_not_monotonic = true;
+                for (_, aggregate) in aggregates {
+                    match aggregate {
+                        AggregateExpression::Count { expr, .. } => {
+                            if let Some(expr) = expr {
+                                self.add_expression(expr);
+                            }
+                        }
+                        AggregateExpression::Sum { expr, .. }
+                        | AggregateExpression::Avg { expr, .. }
+                        | AggregateExpression::Min { expr, .. }
+                        | AggregateExpression::Max { expr, .. }
+                        | AggregateExpression::GroupConcat { expr, .. }
+                        | AggregateExpression::Sample { expr, .. }
+                        | AggregateExpression::Custom { expr, .. } => self.add_expression(expr),
+                    }
+                }
+                self.add_pattern(inner);
+            }
+            GraphPattern::Graph { inner, .. } | GraphPattern::Slice { inner, .. } => {
+                self.is_not_monotonic = true;
+                self.add_pattern(inner);
+            }
+            GraphPattern::Project { inner, .. }
+            | GraphPattern::Distinct { inner }
+            | GraphPattern::Reduced { inner } => self.add_pattern(inner),
+            // The remote services do not depend on the store content
+            GraphPattern::Service { .. } => self.is_not_monotonic = true,
+            // The inline values do not depend on the store content
+            GraphPattern::Values { .. } => (),
+        }
+    }
+
+    fn add_path(&mut self, path: &PropertyPathExpression) {
+        match path {
+            PropertyPathExpression::NamedNode(predicate) => self.reads.insert(&predicate.iri),
+            PropertyPathExpression::Reverse(inner)
+            | PropertyPathExpression::ZeroOrMore(inner)
+            | PropertyPathExpression::OneOrMore(inner)
+            | PropertyPathExpression::ZeroOrOne(inner) => self.add_path(inner),
+            PropertyPathExpression::Sequence(left, right)
+            | PropertyPathExpression::Alternative(left, right) => {
+                self.add_path(left);
+                self.add_path(right);
+            }
+            PropertyPathExpression::NegatedPropertySet(_) => self.reads.any = true,
+        }
+    }
+
+    fn add_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::NamedNode(_)
+            | Expression::Literal(_)
+            | Expression::Variable(_)
+            | Expression::Bound(_) => (),
+            Expression::Or(left, right)
+            | Expression::And(left, right)
+            | Expression::Equal(left, right)
+            | Expression::SameTerm(left, right)
+            | Expression::Greater(left, right)
+            | Expression::GreaterOrEqual(left, right)
+            | Expression::Less(left, right)
+            | Expression::LessOrEqual(left, right)
+            | Expression::Add(left, right)
+            | Expression::Subtract(left, right)
+            | Expression::Multiply(left, right)
+            | Expression::Divide(left, right) => {
+                self.add_expression(left);
+                self.add_expression(right);
+            }
+            Expression::UnaryPlus(inner)
+            | Expression::UnaryMinus(inner)
+            | Expression::Not(inner) => self.add_expression(inner),
+            Expression::In(inner, list) => {
+                self.add_expression(inner);
+                for expression in list {
+                    self.add_expression(expression);
+                }
+            }
+            Expression::Exists(pattern) => {
+                self.is_not_monotonic = true;
+                self.add_pattern(pattern);
+            }
+            Expression::If(condition, then, otherwise) => {
+                self.add_expression(condition);
+                self.add_expression(then);
+                self.add_expression(otherwise);
+            }
+            Expression::Coalesce(list) => {
+                for expression in list {
+                    self.add_expression(expression);
+                }
+            }
+            Expression::FunctionCall(function, arguments) => {
+                self.creates_new_terms |= matches!(
+                    function,
+                    Function::BNode | Function::Uuid | Function::StrUuid | Function::Rand
+                );
+                for expression in arguments {
+                    self.add_expression(expression);
+                }
+            }
+        }
+    }
+}
+
+fn has_blank_node(triple: &TriplePattern) -> bool {
+    is_blank_node(&triple.subject) || is_blank_node(&triple.object)
+}
+
+fn is_blank_node(term: &TermPattern) -> bool {
+    match term {
+        TermPattern::BlankNode(_) => true,
+        TermPattern::Triple(triple) => has_blank_node(triple),
+        _ => false,
+    }
+}
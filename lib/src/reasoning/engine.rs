@@ -0,0 +1,323 @@
+//! Semi-naive evaluation of rules to fixpoint and their incremental maintenance.
+
+use crate::model::Term;
+use crate::reasoning::{Fact, FactBase};
+use std::collections::{HashMap, HashSet};
+
+/// A term of a rule atom
+#[derive(Eq, PartialEq, Debug, Clone, Hash)]
+pub enum RuleTerm {
+    Variable(usize),
+    Constant(Term),
+}
+
+/// A triple pattern of a rule
+pub type Atom = [RuleTerm; 3];
+
+/// A rule inferring the head triples for each solution of the body triple patterns
+#[derive(Debug, Clone)]
+pub struct Rule {
+    body: Vec<Atom>,
+    head: Vec<Atom>,
+    /// Pairs of variables that should not be bound to the same term
+    different: Vec<(usize, usize)>,
+    variable_count: usize,
+}
+
+impl Rule {
+    pub fn new(body: Vec<Atom>, head: Vec<Atom>) -> Self {
+        let variable_count = body
+            .iter()
+            .chain(&head)
+            .flatten()
+            .filter_map(|term| match term {
+                RuleTerm::Variable(id) => Some(id + 1),
+                RuleTerm::Constant(_) => None,
+            })
+            .max()
+            .unwrap_or(0);
+        Self {
+            body,
+            head,
+            different: Vec::new(),
+            variable_count,
+        }
+    }
+
+    /// Only fires the rule if the two variables are bound to different terms
+    pub fn with_different(mut self, left: usize, right: usize) -> Self {
+        self.different.push((left, right));
+        self
+    }
+}
+
+/// Rules indexed by the predicates of their body atoms
+pub struct RuleSet {
+    rules: Vec<Rule>,
+    by_predicate: HashMap<Term, Vec<(usize, usize)>>,
+    with_variable_predicate: Vec<(usize, usize)>,
+}
+
+impl RuleSet {
+    pub fn new(rules: Vec<Rule>) -> Self {
+        let mut by_predicate = HashMap::<_, Vec<_>>::new();
+        let mut with_variable_predicate = Vec::new();
+        for (rule_id, rule) in rules.iter().enumerate() {
+            for (atom_id, atom) in rule.body.iter().enumerate() {
+                match &atom[1] {
+                    RuleTerm::Constant(predicate) => by_predicate
+                        .entry(predicate.clone())
+                        .or_default()
+                        .push((rule_id, atom_id)),
+                    RuleTerm::Variable(_) => with_variable_predicate.push((rule_id, atom_id)),
+                }
+            }
+        }
+        Self {
+            rules,
+            by_predicate,
+            with_variable_predicate,
+        }
+    }
+
+    /// The body atoms that might match the fact
+    fn atoms_for(&self, fact: &Fact) -> impl Iterator<Item = (&Rule, usize)> {
+        self.by_predicate
+            .get(&fact[1])
+            .into_iter()
+            .flatten()
+            .chain(&self.with_variable_predicate)
+            .map(move |(rule_id, atom_id)| (&self.rules[*rule_id], *atom_id))
+    }
+
+    /// Calls `on_fact` with the facts inferred by the rules using `fact` in their body
+    ///
+    /// The other body atoms are evaluated against the fact base and the `extra` facts.
+    fn infer_from<F: FactBase>(
+        &self,
+        facts: &F,
+        extra: &HashSet<Fact>,
+        fact: &Fact,
+        on_fact: &mut impl FnMut(Fact) -> Result<(), F::Error>,
+    ) -> Result<(), F::Error> {
+        for (rule, atom_id) in self.atoms_for(fact) {
+            let mut bindings = vec![None; rule.variable_count];
+            if !bind(&rule.body[atom_id], fact, &mut bindings) {
+                continue;
+            }
+            let others = rule
+                .body
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| *i != atom_id)
+                .map(|(_, atom)| atom)
+                .collect::<Vec<_>>();
+            join(facts, extra, &others, &mut bindings, &mut |bindings| {
+                if rule
+                    .different
+                    .iter()
+                    .any(|(left, right)| bindings[*left] == bindings[*right])
+                {
+                    return Ok(());
+                }
+                for atom in &rule.head {
+                    if let Some(fact) = instantiate(atom, bindings) {
+                        on_fact(fact)?;
+                    }
+                }
+                Ok(())
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Checks if the fact is inferred in one step by a rule from the fact base
+    fn is_derivable<F: FactBase>(&self, facts: &F, fact: &Fact) -> Result<bool, F::Error> {
+        let no_extra = HashSet::new();
+        for rule in &self.rules {
+            for head in &rule.head {
+                let mut bindings = vec![None; rule.variable_count];
+                if !bind(head, fact, &mut bindings) {
+                    continue;
+                }
+                let body = rule.body.iter().collect::<Vec<_>>();
+                let mut is_derivable = false;
+                join(facts, &no_extra, &body, &mut bindings, &mut |bindings| {
+                    is_derivable |= !rule
+                        .different
+                        .iter()
+                        .any(|(left, right)| bindings[*left] == bindings[*right]);
+                    Ok(())
+                })?;
+                if is_derivable {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// Adds to the fact base the facts inferred by the rules until a fixpoint is reached
+///
+/// `delta` are the facts that have not been used yet to fire the rules.
+pub fn saturate<F: FactBase>(
+    facts: &mut F,
+    rules: &RuleSet,
+    mut delta: Vec<Fact>,
+) -> Result<(), F::Error> {
+    let no_extra = HashSet::new();
+    while !delta.is_empty() {
+        let mut inferred = HashSet::new();
+        for fact in &delta {
+            rules.infer_from(facts, &no_extra, fact, &mut |new_fact| {
+                if !inferred.contains(&new_fact) && !facts.contains(&new_fact)? {
+                    inferred.insert(new_fact);
+                }
+                Ok(())
+            })?;
+        }
+        for fact in &inferred {
+            facts.insert_inferred(fact)?;
+        }
+        delta = inferred.into_iter().collect();
+    }
+    Ok(())
+}
+
+/// Updates the inferred facts after the removal of some facts using the "delete and rederive" algorithm
+///
+/// The inferred facts that might depend on the removed facts are removed
+/// and the ones that could still be inferred from the remaining facts are added back.
+pub fn retract<F: FactBase>(
+    facts: &mut F,
+    rules: &RuleSet,
+    removed: Vec<Fact>,
+) -> Result<(), F::Error> {
+    // The inferences are computed against the fact base as it was before the removal
+    let before = removed.iter().cloned().collect::<HashSet<_>>();
+    let mut overdeleted = HashSet::new();
+    let mut delta = removed;
+    while !delta.is_empty() {
+        let mut new_delta = Vec::new();
+        for fact in &delta {
+            rules.infer_from(facts, &before, fact, &mut |new_fact| {
+                if !overdeleted.contains(&new_fact) && facts.is_inferred(&new_fact)? {
+                    overdeleted.insert(new_fact.clone());
+                    new_delta.push(new_fact);
+                }
+                Ok(())
+            })?;
+        }
+        delta = new_delta;
+    }
+    for fact in &overdeleted {
+        facts.remove_inferred(fact)?;
+    }
+    let mut rederived = Vec::new();
+    for fact in overdeleted {
+        if !facts.contains(&fact)? && rules.is_derivable(facts, &fact)? {
+            facts.insert_inferred(&fact)?;
+            rederived.push(fact);
+        }
+    }
+    saturate(facts, rules, rederived)
+}
+
+/// Binds the atom variables to the fact terms and returns `false` if they do not match
+fn bind(atom: &Atom, fact: &Fact, bindings: &mut [Option<Term>]) -> bool {
+    for (pattern, term) in atom.iter().zip(fact) {
+        match pattern {
+            RuleTerm::Constant(constant) => {
+                if constant != term {
+                    return false;
+                }
+            }
+            RuleTerm::Variable(id) => {
+                if let Some(value) = &bindings[*id] {
+                    if value != term {
+                        return false;
+                    }
+                } else {
+                    bindings[*id] = Some(term.clone());
+                }
+            }
+        }
+    }
+    true
+}
+
+fn resolve<'a>(term: &'a RuleTerm, bindings: &'a [Option<Term>]) -> Option<&'a Term> {
+    match term {
+        RuleTerm::Constant(constant) => Some(constant),
+        RuleTerm::Variable(id) => bindings[*id].as_ref(),
+    }
+}
+
+fn instantiate(atom: &Atom, bindings: &[Option<Term>]) -> Option<Fact> {
+    let fact = [
+        resolve(&atom[0], bindings)?.clone(),
+        resolve(&atom[1], bindings)?.clone(),
+        resolve(&atom[2], bindings)?.clone(),
+    ];
+    // The fact should be a valid RDF triple
+    if matches!(fact[0], Term::Literal(_)) || !matches!(fact[1], Term::NamedNode(_)) {
+        return None;
+    }
+    Some(fact)
+}
+
+/// Evaluates the atoms against the fact base, starting with the atom with the most bound terms
+fn join<F: FactBase>(
+    facts: &F,
+    extra: &HashSet<Fact>,
+    atoms: &[&Atom],
+    bindings: &mut Vec<Option<Term>>,
+    on_solution: &mut impl FnMut(&[Option<Term>]) -> Result<(), F::Error>,
+) -> Result<(), F::Error> {
+    let next = atoms.iter().enumerate().max_by_key(|(_, atom)| {
+        atom.iter()
+            .filter(|term| resolve(term, bindings).is_some())
+            .count()
+    });
+    let (next_id, atom) = if let Some((next_id, atom)) = next {
+        (next_id, *atom)
+    } else {
+        return on_solution(bindings);
+    };
+    let others = atoms
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != next_id)
+        .map(|(_, atom)| *atom)
+        .collect::<Vec<_>>();
+    let pattern = [
+        resolve(&atom[0], bindings).cloned(),
+        resolve(&atom[1], bindings).cloned(),
+        resolve(&atom[2], bindings).cloned(),
+    ];
+    let mut candidates = facts.facts_for_pattern(
+        pattern[0].as_ref(),
+        pattern[1].as_ref(),
+        pattern[2].as_ref(),
+    )?;
+    candidates.extend(
+        extra
+            .iter()
+            .filter(|fact| {
+                pattern
+                    .iter()
+                    .zip(fact.iter())
+                    .all(|(pattern, term)| pattern.as_ref().map_or(true, |p| p == term))
+            })
+            .cloned(),
+    );
+    for fact in candidates {
+        let previous = bindings.clone();
+        if bind(atom, &fact, bindings) {
+            join(facts, extra, &others, bindings, on_solution)?;
+        }
+        *bindings = previous;
+    }
+    Ok(())
+}
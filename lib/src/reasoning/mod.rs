@@ -0,0 +1,473 @@
+//! Materialization of the triples inferred by [RDFS](https://www.w3.org/TR/rdf11-mt/#rdfs-entailment)
+//! or [OWL 2 RL](https://www.w3.org/TR/owl2-profiles/#OWL_2_RL) rules.
+//!
+//! The rules are applied to the triples of all the graphs of the dataset until no new triple is inferred.
+//! The inferred triples are written into a dedicated graph.
+//!
+//! For OWL 2 RL, the rules inferring inconsistencies (`false`), the datatype rules,
+//! the axiomatic triples and the `owl:sameAs` reflexivity (`eq-ref`) are not implemented.
+//!
+//! Usage example:
+//! ```
+//! use oxigraph::model::*;
+//! use oxigraph::model::vocab::{rdf, rdfs};
+//! use oxigraph::reasoning::{materialize, Ruleset};
+//!
+//! let mut dataset = Dataset::new();
+//! let ex = NamedNodeRef::new("http://example.com")?;
+//! let class = NamedNodeRef::new("http://example.com/Class")?;
+//! let super_class = NamedNodeRef::new("http://example.com/SuperClass")?;
+//! let inferred = NamedNodeRef::new("http://example.com/inferred")?;
+//! dataset.insert(QuadRef::new(class, rdfs::SUB_CLASS_OF, super_class, GraphNameRef::DefaultGraph));
+//! dataset.insert(QuadRef::new(ex, rdf::TYPE, class, GraphNameRef::DefaultGraph));
+//!
+//! materialize(&mut dataset, Ruleset::Rdfs, inferred);
+//! assert!(dataset.contains(QuadRef::new(ex, rdf::TYPE, super_class, inferred)));
+//! # Result::<_,Box<dyn std::error::Error>>::Ok(())
+//! ```
+
+mod construct;
+mod engine;
+mod rules;
+
+pub use crate::reasoning::construct::ConstructRules;
+
+use crate::error::{invalid_data_error, UnwrapInfallible};
+use crate::model::*;
+use crate::storage::numeric_encoder::{Decoder, EncodedQuad, EncodedTerm};
+use crate::storage::StorageWriter;
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::io::{Read, Result};
+
+/// The rules used to infer new triples.
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Hash)]
+#[non_exhaustive]
+pub enum Ruleset {
+    /// The [RDFS entailment patterns](https://www.w3.org/TR/rdf11-mt/#patterns-of-rdfs-entailment-informative)
+    /// about `rdfs:domain`, `rdfs:range`, `rdfs:subPropertyOf` and `rdfs:subClassOf`.
+    Rdfs,
+    /// The [OWL 2 RL rules](https://www.w3.org/TR/owl2-profiles/#Reasoning_in_OWL_2_RL_and_RDF_Graphs_using_Rules)
+    /// about equality, properties, classes and schema.
+    Owl2Rl,
+}
+
+impl Ruleset {
+    fn id(self) -> u8 {
+        match self {
+            Self::Rdfs => 0,
+            Self::Owl2Rl => 1,
+        }
+    }
+
+    fn from_id(id: u8) -> Result<Self> {
+        match id {
+            0 => Ok(Self::Rdfs),
+            1 => Ok(Self::Owl2Rl),
+            _ => Err(invalid_data_error(format!("Unknown ruleset {}", id))),
+        }
+    }
+}
+
+/// Adds to the `target_graph` graph of the dataset the triples inferred using the `ruleset` rules
+/// from the triples of all the graphs.
+///
+/// The triples already in one of the dataset graphs are not added again to the target graph.
+pub fn materialize<'a>(
+    dataset: &mut Dataset,
+    ruleset: Ruleset,
+    target_graph: impl Into<NamedOrBlankNodeRef<'a>>,
+) {
+    rebuild(
+        &mut DatasetFacts {
+            dataset,
+            target_graph: target_graph.into(),
+        },
+        ruleset,
+    )
+    .unwrap_infallible()
+}
+
+/// A triple with the terms in subject, predicate and object order
+pub(crate) type Fact = [Term; 3];
+
+/// The triples the rules are applied on
+pub(crate) trait FactBase {
+    type Error;
+
+    /// The distinct triples of all the graphs matching the pattern
+    fn facts_for_pattern(
+        &self,
+        subject: Option<&Term>,
+        predicate: Option<&Term>,
+        object: Option<&Term>,
+    ) -> std::result::Result<Vec<Fact>, Self::Error>;
+
+    /// If the triple is in one of the graphs
+    fn contains(&self, fact: &Fact) -> std::result::Result<bool, Self::Error>;
+
+    /// If the triple is in the graph of the inferred triples
+    fn is_inferred(&self, fact: &Fact) -> std::result::Result<bool, Self::Error>;
+
+    fn insert_inferred(&mut self, fact: &Fact) -> std::result::Result<(), Self::Error>;
+
+    fn remove_inferred(&mut self, fact: &Fact) -> std::result::Result<(), Self::Error>;
+
+    /// The distinct triples outside of the graph of the inferred triples
+    fn asserted_facts(&self) -> std::result::Result<Vec<Fact>, Self::Error>;
+
+    fn clear_inferred(&mut self) -> std::result::Result<(), Self::Error>;
+}
+
+/// Replaces the inferred triples by the ones inferred from the current triples
+pub(crate) fn rebuild<F: FactBase>(
+    facts: &mut F,
+    ruleset: Ruleset,
+) -> std::result::Result<(), F::Error> {
+    facts.clear_inferred()?;
+    let (rules, axioms) = rules::rules(facts, ruleset)?;
+    let mut delta = facts.asserted_facts()?;
+    for axiom in axioms {
+        if !facts.contains(&axiom)? {
+            facts.insert_inferred(&axiom)?;
+            delta.push(axiom);
+        }
+    }
+    engine::saturate(facts, &rules, delta)
+}
+
+/// Updates the inferred triples after the insertion and the removal of some triples
+pub(crate) fn update<F: FactBase>(
+    facts: &mut F,
+    ruleset: Ruleset,
+    inserted: Vec<Fact>,
+    removed: Vec<Fact>,
+) -> std::result::Result<(), F::Error> {
+    if inserted
+        .iter()
+        .chain(&removed)
+        .any(|fact| rules::is_structural(fact, ruleset))
+    {
+        // The rules built from the RDF lists might have changed
+        return rebuild(facts, ruleset);
+    }
+    let (rules, _) = rules::rules(facts, ruleset)?;
+    let mut actually_removed = Vec::new();
+    for fact in removed {
+        // The triple might still be in an other graph
+        if !facts.contains(&fact)? {
+            actually_removed.push(fact);
+        }
+    }
+    engine::retract(facts, &rules, actually_removed)?;
+    engine::saturate(facts, &rules, inserted)
+}
+
+/// The configuration of the materialization maintained by a store
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub(crate) struct Materialization {
+    pub ruleset: Ruleset,
+    pub target_graph: NamedOrBlankNode,
+}
+
+impl Materialization {
+    pub fn write(&self, sink: &mut Vec<u8>) {
+        sink.push(self.ruleset.id());
+        match &self.target_graph {
+            NamedOrBlankNode::NamedNode(node) => {
+                sink.push(0);
+                sink.extend_from_slice(node.as_str().as_bytes());
+            }
+            NamedOrBlankNode::BlankNode(node) => {
+                sink.push(1);
+                sink.extend_from_slice(node.as_str().as_bytes());
+            }
+        }
+    }
+
+    pub fn read(mut buffer: &[u8]) -> Result<Self> {
+        let mut header = [0; 2];
+        buffer.read_exact(&mut header)?;
+        let name = String::from_utf8(buffer.to_vec()).map_err(invalid_data_error)?;
+        Ok(Self {
+            ruleset: Ruleset::from_id(header[0])?,
+            target_graph: match header[1] {
+                0 => NamedNode::new(name).map_err(invalid_data_error)?.into(),
+                1 => BlankNode::new(name).map_err(invalid_data_error)?.into(),
+                kind => {
+                    return Err(invalid_data_error(format!(
+                        "Unknown materialization graph kind {}",
+                        kind
+                    )))
+                }
+            },
+        })
+    }
+}
+
+/// The triples of a storage transaction
+pub(crate) struct StorageFacts<'a, 'b> {
+    writer: &'a mut StorageWriter<'b>,
+    target_graph: NamedOrBlankNodeRef<'a>,
+    encoded_target_graph: EncodedTerm,
+}
+
+impl<'a, 'b> StorageFacts<'a, 'b> {
+    pub fn new(writer: &'a mut StorageWriter<'b>, target_graph: NamedOrBlankNodeRef<'a>) -> Self {
+        Self {
+            writer,
+            target_graph,
+            encoded_target_graph: target_graph.into(),
+        }
+    }
+
+    fn decode_facts(&self, quads: impl Iterator<Item = Result<EncodedQuad>>) -> Result<Vec<Fact>> {
+        let reader = self.writer.reader();
+        let mut seen = HashSet::new();
+        let mut facts = Vec::new();
+        for quad in quads {
+            let quad = quad?;
+            if seen.insert((
+                quad.subject.clone(),
+                quad.predicate.clone(),
+                quad.object.clone(),
+            )) {
+                facts.push([
+                    reader.decode_term(&quad.subject)?,
+                    reader.decode_term(&quad.predicate)?,
+                    reader.decode_term(&quad.object)?,
+                ]);
+            }
+        }
+        Ok(facts)
+    }
+}
+
+impl FactBase for StorageFacts<'_, '_> {
+    type Error = std::io::Error;
+
+    fn facts_for_pattern(
+        &self,
+        subject: Option<&Term>,
+        predicate: Option<&Term>,
+        object: Option<&Term>,
+    ) -> Result<Vec<Fact>> {
+        let subject = subject.map(|t| EncodedTerm::from(t.as_ref()));
+        let predicate = predicate.map(|t| EncodedTerm::from(t.as_ref()));
+        let object = object.map(|t| EncodedTerm::from(t.as_ref()));
+        self.decode_facts(self.writer.reader().quads_for_pattern(
+            subject.as_ref(),
+            predicate.as_ref(),
+            object.as_ref(),
+            None,
+        ))
+    }
+
+    fn contains(&self, fact: &Fact) -> Result<bool> {
+        let [subject, predicate, object] = encode_fact(fact);
+        Ok(self
+            .writer
+            .reader()
+            .quads_for_pattern(Some(&subject), Some(&predicate), Some(&object), None)
+            .next()
+            .transpose()?
+            .is_some())
+    }
+
+    fn is_inferred(&self, fact: &Fact) -> Result<bool> {
+        let [subject, predicate, object] = encode_fact(fact);
+        self.writer.reader().contains(&EncodedQuad::new(
+            subject,
+            predicate,
+            object,
+            self.encoded_target_graph.clone(),
+        ))
+    }
+
+    fn insert_inferred(&mut self, fact: &Fact) -> Result<()> {
+        if let Some(triple) = fact_to_triple(fact) {
+            self.writer
+                .insert(triple.in_graph(graph_name(self.target_graph)))?;
+        }
+        Ok(())
+    }
+
+    fn remove_inferred(&mut self, fact: &Fact) -> Result<()> {
+        if let Some(triple) = fact_to_triple(fact) {
+            self.writer
+                .remove(triple.in_graph(graph_name(self.target_graph)))?;
+        }
+        Ok(())
+    }
+
+    fn asserted_facts(&self) -> Result<Vec<Fact>> {
+        let target_graph = &self.encoded_target_graph;
+        self.decode_facts(self.writer.reader().quads().filter(|quad| {
+            quad.as_ref()
+                .map_or(true, |quad| quad.graph_name != *target_graph)
+        }))
+    }
+
+    fn clear_inferred(&mut self) -> Result<()> {
+        self.writer.clear_graph(graph_name(self.target_graph))
+    }
+}
+
+/// The triples of an in-memory dataset
+struct DatasetFacts<'a> {
+    dataset: &'a mut Dataset,
+    target_graph: NamedOrBlankNodeRef<'a>,
+}
+
+impl DatasetFacts<'_> {
+    fn quads_for_pattern<'a>(
+        &'a self,
+        subject: Option<&'a Term>,
+        predicate: Option<&'a Term>,
+        object: Option<&'a Term>,
+    ) -> Box<dyn Iterator<Item = QuadRef<'a>> + 'a> {
+        let quads: Box<dyn Iterator<Item = QuadRef<'a>>> = if let Some(subject) = subject {
+            match term_to_subject(subject) {
+                Some(subject) => Box::new(self.dataset.quads_for_subject(subject)),
+                None => return Box::new(std::iter::empty()),
+            }
+        } else if let Some(object) = object {
+            Box::new(self.dataset.quads_for_object(object))
+        } else if let Some(predicate) = predicate {
+            match predicate {
+                Term::NamedNode(predicate) => Box::new(self.dataset.quads_for_predicate(predicate)),
+                _ => return Box::new(std::iter::empty()),
+            }
+        } else {
+            Box::new(self.dataset.iter())
+        };
+        Box::new(quads.filter(move |quad| {
+            subject.map_or(true, |s| TermRef::from(quad.subject) == s.as_ref())
+                && predicate.map_or(true, |p| TermRef::from(quad.predicate) == p.as_ref())
+                && object.map_or(true, |o| quad.object == o.as_ref())
+        }))
+    }
+}
+
+impl FactBase for DatasetFacts<'_> {
+    type Error = Infallible;
+
+    fn facts_for_pattern(
+        &self,
+        subject: Option<&Term>,
+        predicate: Option<&Term>,
+        object: Option<&Term>,
+    ) -> std::result::Result<Vec<Fact>, Infallible> {
+        let mut seen = HashSet::new();
+        Ok(self
+            .quads_for_pattern(subject, predicate, object)
+            .filter(|quad| seen.insert((quad.subject, quad.predicate, quad.object)))
+            .map(|quad| {
+                [
+                    quad.subject.into(),
+                    quad.predicate.into(),
+                    quad.object.into_owned(),
+                ]
+            })
+            .collect())
+    }
+
+    fn contains(&self, fact: &Fact) -> std::result::Result<bool, Infallible> {
+        Ok(self
+            .quads_for_pattern(Some(&fact[0]), Some(&fact[1]), Some(&fact[2]))
+            .next()
+            .is_some())
+    }
+
+    fn is_inferred(&self, fact: &Fact) -> std::result::Result<bool, Infallible> {
+        Ok(fact_to_triple(fact).map_or(false, |triple| {
+            self.dataset
+                .contains(triple.in_graph(graph_name(self.target_graph)))
+        }))
+    }
+
+    fn insert_inferred(&mut self, fact: &Fact) -> std::result::Result<(), Infallible> {
+        if let Some(triple) = fact_to_triple(fact) {
+            self.dataset
+                .insert(triple.in_graph(graph_name(self.target_graph)));
+        }
+        Ok(())
+    }
+
+    fn remove_inferred(&mut self, fact: &Fact) -> std::result::Result<(), Infallible> {
+        if let Some(triple) = fact_to_triple(fact) {
+            self.dataset
+                .remove(triple.in_graph(graph_name(self.target_graph)));
+        }
+        Ok(())
+    }
+
+    fn asserted_facts(&self) -> std::result::Result<Vec<Fact>, Infallible> {
+        let target_graph = graph_name(self.target_graph);
+        let mut seen = HashSet::new();
+        Ok(self
+            .dataset
+            .iter()
+            .filter(|quad| quad.graph_name != target_graph)
+            .filter(|quad| seen.insert((quad.subject, quad.predicate, quad.object)))
+            .map(|quad| {
+                [
+                    quad.subject.into(),
+                    quad.predicate.into(),
+                    quad.object.into_owned(),
+                ]
+            })
+            .collect())
+    }
+
+    fn clear_inferred(&mut self) -> std::result::Result<(), Infallible> {
+        let target_graph = graph_name(self.target_graph);
+        let inferred = self
+            .dataset
+            .iter()
+            .filter(|quad| quad.graph_name == target_graph)
+            .map(QuadRef::into_owned)
+            .collect::<Vec<_>>();
+        for quad in &inferred {
+            self.dataset.remove(quad);
+        }
+        Ok(())
+    }
+}
+
+/// Converts a fact to a triple if it is a valid RDF triple
+fn fact_to_triple(fact: &Fact) -> Option<TripleRef<'_>> {
+    let predicate = match &fact[1] {
+        Term::NamedNode(predicate) => predicate.as_ref(),
+        _ => return None,
+    };
+    Some(TripleRef::new(
+        term_to_subject(&fact[0])?,
+        predicate,
+        &fact[2],
+    ))
+}
+
+fn term_to_subject(term: &Term) -> Option<SubjectRef<'_>> {
+    match term {
+        Term::NamedNode(node) => Some(node.into()),
+        Term::BlankNode(node) => Some(node.into()),
+        Term::Triple(triple) => Some(triple.as_ref().into()),
+        Term::Literal(_) => None,
+    }
+}
+
+fn encode_fact(fact: &Fact) -> [EncodedTerm; 3] {
+    [
+        fact[0].as_ref().into(),
+        fact[1].as_ref().into(),
+        fact[2].as_ref().into(),
+    ]
+}
+
+pub(crate) fn graph_name(node: NamedOrBlankNodeRef<'_>) -> GraphNameRef<'_> {
+    match node {
+        NamedOrBlankNodeRef::NamedNode(node) => node.into(),
+        NamedOrBlankNodeRef::BlankNode(node) => node.into(),
+    }
+}
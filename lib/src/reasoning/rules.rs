@@ -0,0 +1,640 @@
+//! The rules of the [RDFS](https://www.w3.org/TR/rdf11-mt/#patterns-of-rdfs-entailment-informative)
+//! and [OWL 2 RL](https://www.w3.org/TR/owl2-profiles/#Reasoning_in_OWL_2_RL_and_RDF_Graphs_using_Rules) rulesets.
+
+use crate::model::vocab::{rdf, rdfs, xsd};
+use crate::model::{Literal, NamedNodeRef, Term};
+use crate::reasoning::engine::{Rule, RuleSet, RuleTerm};
+use crate::reasoning::{Fact, FactBase, Ruleset};
+use std::collections::HashSet;
+
+mod owl {
+    use crate::model::NamedNodeRef;
+
+    pub const ALL_VALUES_FROM: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#allValuesFrom");
+    pub const CLASS: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#Class");
+    pub const DATATYPE_PROPERTY: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#DatatypeProperty");
+    pub const EQUIVALENT_CLASS: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#equivalentClass");
+    pub const EQUIVALENT_PROPERTY: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#equivalentProperty");
+    pub const FUNCTIONAL_PROPERTY: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#FunctionalProperty");
+    pub const HAS_KEY: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#hasKey");
+    pub const HAS_VALUE: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#hasValue");
+    pub const INTERSECTION_OF: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#intersectionOf");
+    pub const INVERSE_FUNCTIONAL_PROPERTY: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#InverseFunctionalProperty");
+    pub const INVERSE_OF: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#inverseOf");
+    pub const MAX_CARDINALITY: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#maxCardinality");
+    pub const MAX_QUALIFIED_CARDINALITY: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#maxQualifiedCardinality");
+    pub const NOTHING: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#Nothing");
+    pub const OBJECT_PROPERTY: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#ObjectProperty");
+    pub const ON_CLASS: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#onClass");
+    pub const ON_PROPERTY: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#onProperty");
+    pub const ONE_OF: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#oneOf");
+    pub const PROPERTY_CHAIN_AXIOM: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#propertyChainAxiom");
+    pub const SAME_AS: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#sameAs");
+    pub const SOME_VALUES_FROM: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#someValuesFrom");
+    pub const SYMMETRIC_PROPERTY: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#SymmetricProperty");
+    pub const THING: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#Thing");
+    pub const TRANSITIVE_PROPERTY: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#TransitiveProperty");
+    pub const UNION_OF: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/2002/07/owl#unionOf");
+}
+
+/// The properties whose values are RDF lists compiled into ontology specific rules
+const LIST_PROPERTIES: [NamedNodeRef<'_>; 7] = [
+    rdf::FIRST,
+    rdf::REST,
+    owl::INTERSECTION_OF,
+    owl::UNION_OF,
+    owl::ONE_OF,
+    owl::PROPERTY_CHAIN_AXIOM,
+    owl::HAS_KEY,
+];
+
+/// Returns the rules of the ruleset and the facts inferred without any condition
+///
+/// The rules depending on RDF lists like `owl:intersectionOf` are instantiated for the lists of the fact base.
+pub fn rules<F: FactBase>(facts: &F, ruleset: Ruleset) -> Result<(RuleSet, Vec<Fact>), F::Error> {
+    Ok(match ruleset {
+        Ruleset::Rdfs => (RuleSet::new(rdfs_rules()), Vec::new()),
+        Ruleset::Owl2Rl => {
+            let mut rules = owl2_rl_rules();
+            let axioms = add_list_rules(facts, &mut rules)?;
+            (RuleSet::new(rules), axioms)
+        }
+    })
+}
+
+/// If a change of this fact might change the rules instantiated from the fact base
+pub fn is_structural(fact: &Fact, ruleset: Ruleset) -> bool {
+    ruleset == Ruleset::Owl2Rl
+        && LIST_PROPERTIES
+            .iter()
+            .any(|property| fact[1] == property.into_owned().into())
+}
+
+fn rdfs_rules() -> Vec<Rule> {
+    vec![
+        // rdfs2
+        Rule::new(
+            vec![[v(0), c(rdfs::DOMAIN), v(1)], [v(2), v(0), v(3)]],
+            vec![[v(2), c(rdf::TYPE), v(1)]],
+        ),
+        // rdfs3
+        Rule::new(
+            vec![[v(0), c(rdfs::RANGE), v(1)], [v(2), v(0), v(3)]],
+            vec![[v(3), c(rdf::TYPE), v(1)]],
+        ),
+        // rdfs5
+        Rule::new(
+            vec![
+                [v(0), c(rdfs::SUB_PROPERTY_OF), v(1)],
+                [v(1), c(rdfs::SUB_PROPERTY_OF), v(2)],
+            ],
+            vec![[v(0), c(rdfs::SUB_PROPERTY_OF), v(2)]],
+        ),
+        // rdfs7
+        Rule::new(
+            vec![[v(0), c(rdfs::SUB_PROPERTY_OF), v(1)], [v(2), v(0), v(3)]],
+            vec![[v(2), v(1), v(3)]],
+        ),
+        // rdfs9
+        Rule::new(
+            vec![
+                [v(0), c(rdfs::SUB_CLASS_OF), v(1)],
+                [v(2), c(rdf::TYPE), v(0)],
+            ],
+            vec![[v(2), c(rdf::TYPE), v(1)]],
+        ),
+        // rdfs11
+        Rule::new(
+            vec![
+                [v(0), c(rdfs::SUB_CLASS_OF), v(1)],
+                [v(1), c(rdfs::SUB_CLASS_OF), v(2)],
+            ],
+            vec![[v(0), c(rdfs::SUB_CLASS_OF), v(2)]],
+        ),
+    ]
+}
+
+#[allow(clippy::too_many_lines)]
+fn owl2_rl_rules() -> Vec<Rule> {
+    let one = RuleTerm::Constant(Literal::new_typed_literal("1", xsd::NON_NEGATIVE_INTEGER).into());
+    vec![
+        // eq-sym
+        Rule::new(
+            vec![[v(0), c(owl::SAME_AS), v(1)]],
+            vec![[v(1), c(owl::SAME_AS), v(0)]],
+        ),
+        // eq-trans
+        Rule::new(
+            vec![[v(0), c(owl::SAME_AS), v(1)], [v(1), c(owl::SAME_AS), v(2)]],
+            vec![[v(0), c(owl::SAME_AS), v(2)]],
+        ),
+        // eq-rep-s
+        Rule::new(
+            vec![[v(0), c(owl::SAME_AS), v(1)], [v(0), v(2), v(3)]],
+            vec![[v(1), v(2), v(3)]],
+        ),
+        // eq-rep-p
+        Rule::new(
+            vec![[v(0), c(owl::SAME_AS), v(1)], [v(2), v(0), v(3)]],
+            vec![[v(2), v(1), v(3)]],
+        ),
+        // eq-rep-o
+        Rule::new(
+            vec![[v(0), c(owl::SAME_AS), v(1)], [v(2), v(3), v(0)]],
+            vec![[v(2), v(3), v(1)]],
+        ),
+        // prp-dom
+        Rule::new(
+            vec![[v(0), c(rdfs::DOMAIN), v(1)], [v(2), v(0), v(3)]],
+            vec![[v(2), c(rdf::TYPE), v(1)]],
+        ),
+        // prp-rng
+        Rule::new(
+            vec![[v(0), c(rdfs::RANGE), v(1)], [v(2), v(0), v(3)]],
+            vec![[v(3), c(rdf::TYPE), v(1)]],
+        ),
+        // prp-fp
+        Rule::new(
+            vec![
+                [v(0), c(rdf::TYPE), c(owl::FUNCTIONAL_PROPERTY)],
+                [v(1), v(0), v(2)],
+                [v(1), v(0), v(3)],
+            ],
+            vec![[v(2), c(owl::SAME_AS), v(3)]],
+        )
+        .with_different(2, 3),
+        // prp-ifp
+        Rule::new(
+            vec![
+                [v(0), c(rdf::TYPE), c(owl::INVERSE_FUNCTIONAL_PROPERTY)],
+                [v(1), v(0), v(3)],
+                [v(2), v(0), v(3)],
+            ],
+            vec![[v(1), c(owl::SAME_AS), v(2)]],
+        )
+        .with_different(1, 2),
+        // prp-symp
+        Rule::new(
+            vec![
+                [v(0), c(rdf::TYPE), c(owl::SYMMETRIC_PROPERTY)],
+                [v(1), v(0), v(2)],
+            ],
+            vec![[v(2), v(0), v(1)]],
+        ),
+        // prp-trp
+        Rule::new(
+            vec![
+                [v(0), c(rdf::TYPE), c(owl::TRANSITIVE_PROPERTY)],
+                [v(1), v(0), v(2)],
+                [v(2), v(0), v(3)],
+            ],
+            vec![[v(1), v(0), v(3)]],
+        ),
+        // prp-spo1
+        Rule::new(
+            vec![[v(0), c(rdfs::SUB_PROPERTY_OF), v(1)], [v(2), v(0), v(3)]],
+            vec![[v(2), v(1), v(3)]],
+        ),
+        // prp-eqp1
+        Rule::new(
+            vec![
+                [v(0), c(owl::EQUIVALENT_PROPERTY), v(1)],
+                [v(2), v(0), v(3)],
+            ],
+            vec![[v(2), v(1), v(3)]],
+        ),
+        // prp-eqp2
+        Rule::new(
+            vec![
+                [v(0), c(owl::EQUIVALENT_PROPERTY), v(1)],
+                [v(2), v(1), v(3)],
+            ],
+            vec![[v(2), v(0), v(3)]],
+        ),
+        // prp-inv1
+        Rule::new(
+            vec![[v(0), c(owl::INVERSE_OF), v(1)], [v(2), v(0), v(3)]],
+            vec![[v(3), v(1), v(2)]],
+        ),
+        // prp-inv2
+        Rule::new(
+            vec![[v(0), c(owl::INVERSE_OF), v(1)], [v(2), v(1), v(3)]],
+            vec![[v(3), v(0), v(2)]],
+        ),
+        // cls-svf1
+        Rule::new(
+            vec![
+                [v(0), c(owl::SOME_VALUES_FROM), v(1)],
+                [v(0), c(owl::ON_PROPERTY), v(2)],
+                [v(3), v(2), v(4)],
+                [v(4), c(rdf::TYPE), v(1)],
+            ],
+            vec![[v(3), c(rdf::TYPE), v(0)]],
+        ),
+        // cls-svf2
+        Rule::new(
+            vec![
+                [v(0), c(owl::SOME_VALUES_FROM), c(owl::THING)],
+                [v(0), c(owl::ON_PROPERTY), v(1)],
+                [v(2), v(1), v(3)],
+            ],
+            vec![[v(2), c(rdf::TYPE), v(0)]],
+        ),
+        // cls-avf
+        Rule::new(
+            vec![
+                [v(0), c(owl::ALL_VALUES_FROM), v(1)],
+                [v(0), c(owl::ON_PROPERTY), v(2)],
+                [v(3), c(rdf::TYPE), v(0)],
+                [v(3), v(2), v(4)],
+            ],
+            vec![[v(4), c(rdf::TYPE), v(1)]],
+        ),
+        // cls-hv1
+        Rule::new(
+            vec![
+                [v(0), c(owl::HAS_VALUE), v(1)],
+                [v(0), c(owl::ON_PROPERTY), v(2)],
+                [v(3), c(rdf::TYPE), v(0)],
+            ],
+            vec![[v(3), v(2), v(1)]],
+        ),
+        // cls-hv2
+        Rule::new(
+            vec![
+                [v(0), c(owl::HAS_VALUE), v(1)],
+                [v(0), c(owl::ON_PROPERTY), v(2)],
+                [v(3), v(2), v(1)],
+            ],
+            vec![[v(3), c(rdf::TYPE), v(0)]],
+        ),
+        // cls-maxc2
+        Rule::new(
+            vec![
+                [v(0), c(owl::MAX_CARDINALITY), one.clone()],
+                [v(0), c(owl::ON_PROPERTY), v(1)],
+                [v(2), c(rdf::TYPE), v(0)],
+                [v(2), v(1), v(3)],
+                [v(2), v(1), v(4)],
+            ],
+            vec![[v(3), c(owl::SAME_AS), v(4)]],
+        )
+        .with_different(3, 4),
+        // cls-maxqc3
+        Rule::new(
+            vec![
+                [v(0), c(owl::MAX_QUALIFIED_CARDINALITY), one.clone()],
+                [v(0), c(owl::ON_PROPERTY), v(1)],
+                [v(0), c(owl::ON_CLASS), v(2)],
+                [v(3), c(rdf::TYPE), v(0)],
+                [v(3), v(1), v(4)],
+                [v(4), c(rdf::TYPE), v(2)],
+                [v(3), v(1), v(5)],
+                [v(5), c(rdf::TYPE), v(2)],
+            ],
+            vec![[v(4), c(owl::SAME_AS), v(5)]],
+        )
+        .with_different(4, 5),
+        // cls-maxqc4
+        Rule::new(
+            vec![
+                [v(0), c(owl::MAX_QUALIFIED_CARDINALITY), one],
+                [v(0), c(owl::ON_PROPERTY), v(1)],
+                [v(0), c(owl::ON_CLASS), c(owl::THING)],
+                [v(2), c(rdf::TYPE), v(0)],
+                [v(2), v(1), v(3)],
+                [v(2), v(1), v(4)],
+            ],
+            vec![[v(3), c(owl::SAME_AS), v(4)]],
+        )
+        .with_different(3, 4),
+        // cax-sco
+        Rule::new(
+            vec![
+                [v(0), c(rdfs::SUB_CLASS_OF), v(1)],
+                [v(2), c(rdf::TYPE), v(0)],
+            ],
+            vec![[v(2), c(rdf::TYPE), v(1)]],
+        ),
+        // cax-eqc1
+        Rule::new(
+            vec![
+                [v(0), c(owl::EQUIVALENT_CLASS), v(1)],
+                [v(2), c(rdf::TYPE), v(0)],
+            ],
+            vec![[v(2), c(rdf::TYPE), v(1)]],
+        ),
+        // cax-eqc2
+        Rule::new(
+            vec![
+                [v(0), c(owl::EQUIVALENT_CLASS), v(1)],
+                [v(2), c(rdf::TYPE), v(1)],
+            ],
+            vec![[v(2), c(rdf::TYPE), v(0)]],
+        ),
+        // scm-cls
+        Rule::new(
+            vec![[v(0), c(rdf::TYPE), c(owl::CLASS)]],
+            vec![
+                [v(0), c(rdfs::SUB_CLASS_OF), v(0)],
+                [v(0), c(owl::EQUIVALENT_CLASS), v(0)],
+                [v(0), c(rdfs::SUB_CLASS_OF), c(owl::THING)],
+                [c(owl::NOTHING), c(rdfs::SUB_CLASS_OF), v(0)],
+            ],
+        ),
+        // scm-sco
+        Rule::new(
+            vec![
+                [v(0), c(rdfs::SUB_CLASS_OF), v(1)],
+                [v(1), c(rdfs::SUB_CLASS_OF), v(2)],
+            ],
+            vec![[v(0), c(rdfs::SUB_CLASS_OF), v(2)]],
+        ),
+        // scm-eqc1
+        Rule::new(
+            vec![[v(0), c(owl::EQUIVALENT_CLASS), v(1)]],
+            vec![
+                [v(0), c(rdfs::SUB_CLASS_OF), v(1)],
+                [v(1), c(rdfs::SUB_CLASS_OF), v(0)],
+            ],
+        ),
+        // scm-eqc2
+        Rule::new(
+            vec![
+                [v(0), c(rdfs::SUB_CLASS_OF), v(1)],
+                [v(1), c(rdfs::SUB_CLASS_OF), v(0)],
+            ],
+            vec![[v(0), c(owl::EQUIVALENT_CLASS), v(1)]],
+        ),
+        // scm-op
+        Rule::new(
+            vec![[v(0), c(rdf::TYPE), c(owl::OBJECT_PROPERTY)]],
+            vec![
+                [v(0), c(rdfs::SUB_PROPERTY_OF), v(0)],
+                [v(0), c(owl::EQUIVALENT_PROPERTY), v(0)],
+            ],
+        ),
+        // scm-dp
+        Rule::new(
+            vec![[v(0), c(rdf::TYPE), c(owl::DATATYPE_PROPERTY)]],
+            vec![
+                [v(0), c(rdfs::SUB_PROPERTY_OF), v(0)],
+                [v(0), c(owl::EQUIVALENT_PROPERTY), v(0)],
+            ],
+        ),
+        // scm-spo
+        Rule::new(
+            vec![
+                [v(0), c(rdfs::SUB_PROPERTY_OF), v(1)],
+                [v(1), c(rdfs::SUB_PROPERTY_OF), v(2)],
+            ],
+            vec![[v(0), c(rdfs::SUB_PROPERTY_OF), v(2)]],
+        ),
+        // scm-eqp1
+        Rule::new(
+            vec![[v(0), c(owl::EQUIVALENT_PROPERTY), v(1)]],
+            vec![
+                [v(0), c(rdfs::SUB_PROPERTY_OF), v(1)],
+                [v(1), c(rdfs::SUB_PROPERTY_OF), v(0)],
+            ],
+        ),
+        // scm-eqp2
+        Rule::new(
+            vec![
+                [v(0), c(rdfs::SUB_PROPERTY_OF), v(1)],
+                [v(1), c(rdfs::SUB_PROPERTY_OF), v(0)],
+            ],
+            vec![[v(0), c(owl::EQUIVALENT_PROPERTY), v(1)]],
+        ),
+        // scm-dom1
+        Rule::new(
+            vec![
+                [v(0), c(rdfs::DOMAIN), v(1)],
+                [v(1), c(rdfs::SUB_CLASS_OF), v(2)],
+            ],
+            vec![[v(0), c(rdfs::DOMAIN), v(2)]],
+        ),
+        // scm-dom2
+        Rule::new(
+            vec![
+                [v(0), c(rdfs::DOMAIN), v(1)],
+                [v(2), c(rdfs::SUB_PROPERTY_OF), v(0)],
+            ],
+            vec![[v(2), c(rdfs::DOMAIN), v(1)]],
+        ),
+        // scm-rng1
+        Rule::new(
+            vec![
+                [v(0), c(rdfs::RANGE), v(1)],
+                [v(1), c(rdfs::SUB_CLASS_OF), v(2)],
+            ],
+            vec![[v(0), c(rdfs::RANGE), v(2)]],
+        ),
+        // scm-rng2
+        Rule::new(
+            vec![
+                [v(0), c(rdfs::RANGE), v(1)],
+                [v(2), c(rdfs::SUB_PROPERTY_OF), v(0)],
+            ],
+            vec![[v(2), c(rdfs::RANGE), v(1)]],
+        ),
+        // scm-hv
+        Rule::new(
+            vec![
+                [v(0), c(owl::HAS_VALUE), v(2)],
+                [v(0), c(owl::ON_PROPERTY), v(3)],
+                [v(1), c(owl::HAS_VALUE), v(2)],
+                [v(1), c(owl::ON_PROPERTY), v(4)],
+                [v(3), c(rdfs::SUB_PROPERTY_OF), v(4)],
+            ],
+            vec![[v(0), c(rdfs::SUB_CLASS_OF), v(1)]],
+        ),
+        // scm-svf1
+        Rule::new(
+            vec![
+                [v(0), c(owl::SOME_VALUES_FROM), v(2)],
+                [v(0), c(owl::ON_PROPERTY), v(4)],
+                [v(1), c(owl::SOME_VALUES_FROM), v(3)],
+                [v(1), c(owl::ON_PROPERTY), v(4)],
+                [v(2), c(rdfs::SUB_CLASS_OF), v(3)],
+            ],
+            vec![[v(0), c(rdfs::SUB_CLASS_OF), v(1)]],
+        ),
+        // scm-svf2
+        Rule::new(
+            vec![
+                [v(0), c(owl::SOME_VALUES_FROM), v(2)],
+                [v(0), c(owl::ON_PROPERTY), v(3)],
+                [v(1), c(owl::SOME_VALUES_FROM), v(2)],
+                [v(1), c(owl::ON_PROPERTY), v(4)],
+                [v(3), c(rdfs::SUB_PROPERTY_OF), v(4)],
+            ],
+            vec![[v(0), c(rdfs::SUB_CLASS_OF), v(1)]],
+        ),
+        // scm-avf1
+        Rule::new(
+            vec![
+                [v(0), c(owl::ALL_VALUES_FROM), v(2)],
+                [v(0), c(owl::ON_PROPERTY), v(4)],
+                [v(1), c(owl::ALL_VALUES_FROM), v(3)],
+                [v(1), c(owl::ON_PROPERTY), v(4)],
+                [v(2), c(rdfs::SUB_CLASS_OF), v(3)],
+            ],
+            vec![[v(0), c(rdfs::SUB_CLASS_OF), v(1)]],
+        ),
+        // scm-avf2
+        Rule::new(
+            vec![
+                [v(0), c(owl::ALL_VALUES_FROM), v(2)],
+                [v(0), c(owl::ON_PROPERTY), v(3)],
+                [v(1), c(owl::ALL_VALUES_FROM), v(2)],
+                [v(1), c(owl::ON_PROPERTY), v(4)],
+                [v(3), c(rdfs::SUB_PROPERTY_OF), v(4)],
+            ],
+            vec![[v(1), c(rdfs::SUB_CLASS_OF), v(0)]],
+        ),
+    ]
+}
+
+/// Adds the rules cls-int1, cls-int2, cls-uni, prp-spo2 and prp-key for the lists of the fact base
+/// and returns the facts inferred by the rules cls-oo, scm-int and scm-uni
+fn add_list_rules<F: FactBase>(facts: &F, rules: &mut Vec<Rule>) -> Result<Vec<Fact>, F::Error> {
+    let mut axioms = Vec::new();
+    for [class, _, list] in
+        facts.facts_for_pattern(None, Some(&term(owl::INTERSECTION_OF)), None)?
+    {
+        if let Some(members) = read_list(facts, &list)? {
+            // cls-int1
+            rules.push(Rule::new(
+                members
+                    .iter()
+                    .map(|member| [v(0), c(rdf::TYPE), RuleTerm::Constant(member.clone())])
+                    .collect(),
+                vec![[v(0), c(rdf::TYPE), RuleTerm::Constant(class.clone())]],
+            ));
+            for member in members {
+                // cls-int2
+                rules.push(Rule::new(
+                    vec![[v(0), c(rdf::TYPE), RuleTerm::Constant(class.clone())]],
+                    vec![[v(0), c(rdf::TYPE), RuleTerm::Constant(member.clone())]],
+                ));
+                // scm-int
+                axioms.push([class.clone(), term(rdfs::SUB_CLASS_OF), member]);
+            }
+        }
+    }
+    for [class, _, list] in facts.facts_for_pattern(None, Some(&term(owl::UNION_OF)), None)? {
+        if let Some(members) = read_list(facts, &list)? {
+            for member in members {
+                // cls-uni
+                rules.push(Rule::new(
+                    vec![[v(0), c(rdf::TYPE), RuleTerm::Constant(member.clone())]],
+                    vec![[v(0), c(rdf::TYPE), RuleTerm::Constant(class.clone())]],
+                ));
+                // scm-uni
+                axioms.push([member, term(rdfs::SUB_CLASS_OF), class.clone()]);
+            }
+        }
+    }
+    for [class, _, list] in facts.facts_for_pattern(None, Some(&term(owl::ONE_OF)), None)? {
+        if let Some(members) = read_list(facts, &list)? {
+            for member in members {
+                // cls-oo
+                axioms.push([member, term(rdf::TYPE), class.clone()]);
+            }
+        }
+    }
+    for [property, _, list] in
+        facts.facts_for_pattern(None, Some(&term(owl::PROPERTY_CHAIN_AXIOM)), None)?
+    {
+        if let Some(chain) = read_list(facts, &list)? {
+            if chain.is_empty() {
+                continue;
+            }
+            // prp-spo2
+            rules.push(Rule::new(
+                chain
+                    .iter()
+                    .enumerate()
+                    .map(|(i, link)| [v(i), RuleTerm::Constant(link.clone()), v(i + 1)])
+                    .collect(),
+                vec![[v(0), RuleTerm::Constant(property), v(chain.len())]],
+            ));
+        }
+    }
+    for [class, _, list] in facts.facts_for_pattern(None, Some(&term(owl::HAS_KEY)), None)? {
+        if let Some(keys) = read_list(facts, &list)? {
+            // prp-key: the variables 0 and 1 are the two instances and the next ones the key values
+            let mut body = vec![
+                [v(0), c(rdf::TYPE), RuleTerm::Constant(class.clone())],
+                [v(1), c(rdf::TYPE), RuleTerm::Constant(class)],
+            ];
+            for (i, key) in keys.into_iter().enumerate() {
+                body.push([v(0), RuleTerm::Constant(key.clone()), v(i + 2)]);
+                body.push([v(1), RuleTerm::Constant(key), v(i + 2)]);
+            }
+            rules.push(Rule::new(body, vec![[v(0), c(owl::SAME_AS), v(1)]]).with_different(0, 1));
+        }
+    }
+    Ok(axioms)
+}
+
+/// Returns the members of a well-formed RDF list
+fn read_list<F: FactBase>(facts: &F, list: &Term) -> Result<Option<Vec<Term>>, F::Error> {
+    let nil = term(rdf::NIL);
+    let mut members = Vec::new();
+    let mut visited = HashSet::new();
+    let mut current = list.clone();
+    while current != nil {
+        if !visited.insert(current.clone()) {
+            return Ok(None); // Cycle
+        }
+        let first = facts.facts_for_pattern(Some(&current), Some(&term(rdf::FIRST)), None)?;
+        let rest = facts.facts_for_pattern(Some(&current), Some(&term(rdf::REST)), None)?;
+        match (first.as_slice(), rest.as_slice()) {
+            ([[_, _, first]], [[_, _, rest]]) => {
+                members.push(first.clone());
+                current = rest.clone();
+            }
+            _ => return Ok(None),
+        }
+    }
+    Ok(Some(members))
+}
+
+fn v(id: usize) -> RuleTerm {
+    RuleTerm::Variable(id)
+}
+
+fn c(node: NamedNodeRef<'_>) -> RuleTerm {
+    RuleTerm::Constant(term(node))
+}
+
+fn term(node: NamedNodeRef<'_>) -> Term {
+    node.into_owned().into()
+}
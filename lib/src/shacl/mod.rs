@@ -0,0 +1,319 @@
+//! Validation of RDF graphs against [SHACL Core](https://www.w3.org/TR/shacl/#core-components) shapes.
+//!
+//! The shapes graph is first compiled into a [`ShapesGraph`] that is then used to validate data graphs
+//! or graphs of a [`Store`](crate::store::Store) using [`Store::validate`](crate::store::Store::validate) and returns a [`ValidationReport`].
+//! The shapes could also be registered on a store with [`Store::register_shapes`](crate::store::Store::register_shapes)
+//! in order to reject the writes that would make a graph not conform to them.
+//!
+//! All the SHACL Core targets, property paths and constraint components are supported.
+//! Recursive shapes are considered valid when they are reached again while validating the same focus node.
+//!
+//! [SPARQL-based constraints](https://www.w3.org/TR/shacl/#sparql-constraints) (`sh:sparql`) are evaluated with the Oxigraph SPARQL engine.
+//! The `$this` variable is bound to the focus node and the `$PATH` placeholder is replaced by the path of the property shape.
+//! The `$shapesGraph` and `$currentShape` variables are not supported.
+//!
+//! Usage example:
+//! ```
+//! use oxigraph::io::GraphFormat;
+//! use oxigraph::model::*;
+//! use oxigraph::shacl::ShapesGraph;
+//!
+//! let mut shapes = Graph::new();
+//! shapes.load(
+//!     "@prefix sh: <http://www.w3.org/ns/shacl#> .
+//!      <http://example.com/PersonShape> a sh:NodeShape ;
+//!         sh:targetClass <http://example.com/Person> ;
+//!         sh:property [ sh:path <http://example.com/name> ; sh:minCount 1 ] .".as_bytes(),
+//!     GraphFormat::Turtle,
+//!     None,
+//! )?;
+//! let shapes = ShapesGraph::new(&shapes)?;
+//!
+//! let mut data = Graph::new();
+//! data.load(
+//!     "<http://example.com/alice> a <http://example.com/Person> .".as_bytes(),
+//!     GraphFormat::Turtle,
+//!     None,
+//! )?;
+//! let report = shapes.validate(&data)?;
+//! assert!(!report.conforms());
+//! assert_eq!(report.results()[0].focus_node(), &NamedNode::new("http://example.com/alice")?.into());
+//! # Result::<_,Box<dyn std::error::Error>>::Ok(())
+//! ```
+
+mod report;
+mod shape;
+mod validator;
+mod vocab;
+
+pub use crate::shacl::report::{ValidationReport, ValidationResult};
+
+use crate::model::*;
+use crate::shacl::shape::{as_subject, parse_shapes, Shape};
+use crate::shacl::validator::Validator;
+use crate::sparql::{
+    evaluate_query_with_bindings, EvaluationError, Query, QueryOptions, QueryResults,
+    QuerySolution, Variable,
+};
+use crate::storage::numeric_encoder::{Decoder, EncodedTerm};
+use crate::storage::{Storage, StorageReader};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::error::Error;
+use std::{fmt, io};
+
+/// A compiled SHACL shapes graph.
+///
+/// The shapes graph is checked and compiled once and can then be used to validate multiple data graphs.
+#[derive(Debug, Clone)]
+pub struct ShapesGraph {
+    shapes: Vec<Shape>,
+    /// The triples describing the complex property paths, to be copied in the validation reports
+    path_definitions: HashMap<Term, Vec<Triple>>,
+}
+
+impl ShapesGraph {
+    /// Compiles the shapes described in a graph.
+    ///
+    /// Returns an [`InvalidData`](std::io::ErrorKind::InvalidData) error if the shapes are not well-formed.
+    pub fn new(graph: &Graph) -> io::Result<Self> {
+        let (shapes, path_definitions) = parse_shapes(graph)?;
+        Ok(Self {
+            shapes,
+            path_definitions,
+        })
+    }
+
+    /// Validates a graph against the shapes.
+    ///
+    /// Fails only if the evaluation of a SPARQL-based constraint fails.
+    pub fn validate(&self, data: &Graph) -> Result<ValidationReport, EvaluationError> {
+        self.validate_data(&GraphData {
+            graph: data,
+            storage: RefCell::new(None),
+        })
+    }
+
+    /// Validates the `graph_name` graph of the storage, using `new_reader` to get readers on it.
+    ///
+    /// If `changes` is set, only the nodes whose conformance might have been changed by these triples are validated.
+    pub(crate) fn validate_storage(
+        &self,
+        new_reader: &dyn Fn() -> StorageReader,
+        graph_name: GraphNameRef<'_>,
+        changes: Option<&[Triple]>,
+    ) -> Result<ValidationReport, EvaluationError> {
+        let data = StorageGraph {
+            reader: new_reader(),
+            new_reader,
+            graph_name: graph_name.into_owned(),
+        };
+        let mut validator = Validator::new(&self.shapes, &data);
+        let nodes = if let Some(changes) = changes {
+            validator.affected_nodes(changes)?
+        } else {
+            None
+        };
+        let results = if let Some(nodes) = nodes {
+            validator.validate_nodes(&nodes)?
+        } else {
+            validator.validate()?
+        };
+        Ok(ValidationReport::new(results, &self.path_definitions))
+    }
+
+    fn validate_data(&self, data: &impl DataGraph) -> Result<ValidationReport, EvaluationError> {
+        let results = Validator::new(&self.shapes, data).validate()?;
+        Ok(ValidationReport::new(results, &self.path_definitions))
+    }
+}
+
+/// An error returned when a write would make a graph of a [`Store`](crate::store::Store)
+/// not conform to the shapes registered with [`Store::register_shapes`](crate::store::Store::register_shapes).
+///
+/// It is returned inside of an [`InvalidData`](std::io::ErrorKind::InvalidData) [`io::Error`].
+/// See [`Store::register_shapes`](crate::store::Store::register_shapes) for an example.
+#[derive(Debug)]
+pub struct ValidationError {
+    graph_name: GraphName,
+    report: ValidationReport,
+}
+
+impl ValidationError {
+    pub(crate) fn new(graph_name: GraphName, report: ValidationReport) -> Self {
+        Self { graph_name, report }
+    }
+
+    /// The graph that does not conform to the shapes.
+    #[inline]
+    pub fn graph_name(&self) -> GraphNameRef<'_> {
+        self.graph_name.as_ref()
+    }
+
+    /// The validation report of the graph.
+    #[inline]
+    pub fn report(&self) -> &ValidationReport {
+        &self.report
+    }
+
+    #[inline]
+    pub fn into_report(self) -> ValidationReport {
+        self.report
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The graph {} does not conform to its shapes: {} validation results",
+            self.graph_name,
+            self.report.results().len()
+        )?;
+        if let Some(result) = self.report.results().first() {
+            write!(
+                f,
+                ", the first one being a {} on the focus node {}",
+                result.source_constraint_component(),
+                result.focus_node()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ValidationError {}
+
+/// A graph to validate
+pub(crate) trait DataGraph {
+    /// The triples matching the pattern
+    fn triples(
+        &self,
+        subject: Option<&Term>,
+        predicate: Option<NamedNodeRef<'_>>,
+        object: Option<&Term>,
+    ) -> Result<Vec<Triple>, EvaluationError>;
+
+    /// Evaluates the `SELECT` query on the graph with the `$this` variable bound to the focus node
+    fn select(&self, query: &Query, this: &Term) -> Result<Vec<QuerySolution>, EvaluationError>;
+}
+
+struct GraphData<'a> {
+    graph: &'a Graph,
+    /// A storage containing the graph in its default graph, built when a SPARQL query is evaluated
+    storage: RefCell<Option<Storage>>,
+}
+
+impl DataGraph for GraphData<'_> {
+    fn triples(
+        &self,
+        subject: Option<&Term>,
+        predicate: Option<NamedNodeRef<'_>>,
+        object: Option<&Term>,
+    ) -> Result<Vec<Triple>, EvaluationError> {
+        let matches = |triple: &TripleRef<'_>| {
+            predicate.map_or(true, |p| triple.predicate == p)
+                && object.map_or(true, |o| triple.object == o.as_ref())
+        };
+        Ok(if let Some(subject) = subject {
+            if let Some(subject) = as_subject(subject.as_ref()) {
+                self.graph
+                    .triples_for_subject(subject)
+                    .filter(matches)
+                    .map(TripleRef::into_owned)
+                    .collect()
+            } else {
+                Vec::new()
+            }
+        } else if let Some(object) = object {
+            self.graph
+                .triples_for_object(object)
+                .filter(matches)
+                .map(TripleRef::into_owned)
+                .collect()
+        } else if let Some(predicate) = predicate {
+            self.graph
+                .triples_for_predicate(predicate)
+                .map(TripleRef::into_owned)
+                .collect()
+        } else {
+            self.graph.iter().map(TripleRef::into_owned).collect()
+        })
+    }
+
+    fn select(&self, query: &Query, this: &Term) -> Result<Vec<QuerySolution>, EvaluationError> {
+        let mut storage = self.storage.borrow_mut();
+        if storage.is_none() {
+            let new_storage = Storage::new()?;
+            new_storage.transaction(|mut writer| {
+                for triple in self.graph {
+                    writer.insert(triple.in_graph(GraphNameRef::DefaultGraph))?;
+                }
+                Ok::<_, io::Error>(())
+            })?;
+            *storage = Some(new_storage);
+        }
+        let reader = storage.as_ref().unwrap().snapshot();
+        select(reader, query.clone(), this)
+    }
+}
+
+/// A graph of a storage
+struct StorageGraph<'a> {
+    reader: StorageReader,
+    new_reader: &'a dyn Fn() -> StorageReader,
+    graph_name: GraphName,
+}
+
+impl DataGraph for StorageGraph<'_> {
+    fn triples(
+        &self,
+        subject: Option<&Term>,
+        predicate: Option<NamedNodeRef<'_>>,
+        object: Option<&Term>,
+    ) -> Result<Vec<Triple>, EvaluationError> {
+        let subject = if let Some(subject) = subject {
+            if let Some(subject) = as_subject(subject.as_ref()) {
+                Some(EncodedTerm::from(subject))
+            } else {
+                return Ok(Vec::new());
+            }
+        } else {
+            None
+        };
+        self.reader
+            .quads_for_pattern(
+                subject.as_ref(),
+                predicate.map(EncodedTerm::from).as_ref(),
+                object.map(|o| EncodedTerm::from(o.as_ref())).as_ref(),
+                Some(&self.graph_name.as_ref().into()),
+            )
+            .map(|quad| Ok(self.reader.decode_quad(&quad?)?.into()))
+            .collect()
+    }
+
+    fn select(&self, query: &Query, this: &Term) -> Result<Vec<QuerySolution>, EvaluationError> {
+        let mut query = query.clone();
+        query
+            .dataset_mut()
+            .set_default_graph(vec![self.graph_name.clone()]);
+        select((self.new_reader)(), query, this)
+    }
+}
+
+fn select(
+    reader: StorageReader,
+    query: Query,
+    this: &Term,
+) -> Result<Vec<QuerySolution>, EvaluationError> {
+    if let QueryResults::Solutions(solutions) = evaluate_query_with_bindings(
+        reader,
+        query,
+        QueryOptions::default(),
+        [(Variable::new_unchecked("this"), this.clone())],
+    )? {
+        solutions.collect()
+    } else {
+        Err(EvaluationError::msg("A SELECT query was expected"))
+    }
+}
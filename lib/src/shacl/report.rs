@@ -0,0 +1,156 @@
+use crate::model::vocab::rdf;
+use crate::model::*;
+use crate::shacl::vocab as sh;
+use std::collections::HashMap;
+
+/// A [SHACL validation report](https://www.w3.org/TR/shacl/#validation-report).
+///
+/// It is returned by [`ShapesGraph::validate`](super::ShapesGraph::validate) and [`Store::validate`](crate::store::Store::validate).
+#[derive(Debug)]
+pub struct ValidationReport {
+    results: Vec<ValidationResult>,
+    graph: Graph,
+}
+
+impl ValidationReport {
+    pub(crate) fn new(
+        results: Vec<ValidationResult>,
+        path_definitions: &HashMap<Term, Vec<Triple>>,
+    ) -> Self {
+        let mut graph = Graph::new();
+        let report = BlankNode::default();
+        graph.insert(TripleRef::new(&report, rdf::TYPE, sh::VALIDATION_REPORT));
+        graph.insert(TripleRef::new(
+            &report,
+            sh::CONFORMS,
+            &Literal::from(results.is_empty()),
+        ));
+        for result in &results {
+            let node = BlankNode::default();
+            graph.insert(TripleRef::new(&report, sh::RESULT, &node));
+            graph.insert(TripleRef::new(&node, rdf::TYPE, sh::VALIDATION_RESULT));
+            graph.insert(TripleRef::new(&node, sh::FOCUS_NODE, &result.focus_node));
+            if let Some(path) = &result.path {
+                graph.insert(TripleRef::new(&node, sh::RESULT_PATH, path));
+                for triple in path_definitions.get(path).into_iter().flatten() {
+                    graph.insert(triple);
+                }
+            }
+            if let Some(value) = &result.value {
+                graph.insert(TripleRef::new(&node, sh::VALUE, value));
+            }
+            graph.insert(TripleRef::new(
+                &node,
+                sh::SOURCE_SHAPE,
+                &result.source_shape,
+            ));
+            graph.insert(TripleRef::new(
+                &node,
+                sh::SOURCE_CONSTRAINT_COMPONENT,
+                &result.source_constraint_component,
+            ));
+            if let Some(constraint) = &result.source_constraint {
+                graph.insert(TripleRef::new(&node, sh::SOURCE_CONSTRAINT, constraint));
+            }
+            graph.insert(TripleRef::new(&node, sh::RESULT_SEVERITY, &result.severity));
+            for message in &result.messages {
+                graph.insert(TripleRef::new(&node, sh::RESULT_MESSAGE, message));
+            }
+        }
+        Self { results, graph }
+    }
+
+    /// Returns `true` if the data graph conforms to the shapes graph, i.e. there are no validation results.
+    #[inline]
+    pub fn conforms(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// Returns `true` if there is a result with the `sh:Violation` severity.
+    pub(crate) fn has_violations(&self) -> bool {
+        self.results
+            .iter()
+            .any(|result| result.severity == sh::VIOLATION)
+    }
+
+    /// The validation results
+    #[inline]
+    pub fn results(&self) -> &[ValidationResult] {
+        &self.results
+    }
+
+    /// The report as an RDF graph following the [SHACL validation report vocabulary](https://www.w3.org/TR/shacl/#validation-report).
+    #[inline]
+    pub fn graph(&self) -> &Graph {
+        &self.graph
+    }
+
+    /// Consumes the report and returns its RDF graph.
+    #[inline]
+    pub fn into_graph(self) -> Graph {
+        self.graph
+    }
+}
+
+/// A [SHACL validation result](https://www.w3.org/TR/shacl/#results-validation-result).
+#[derive(Eq, PartialEq, Debug, Clone, Hash)]
+pub struct ValidationResult {
+    pub(crate) focus_node: Term,
+    pub(crate) path: Option<Term>,
+    pub(crate) value: Option<Term>,
+    pub(crate) source_shape: Term,
+    pub(crate) source_constraint_component: NamedNode,
+    pub(crate) source_constraint: Option<Term>,
+    pub(crate) severity: NamedNode,
+    pub(crate) messages: Vec<Literal>,
+}
+
+impl ValidationResult {
+    /// The focus node that has caused the result (`sh:focusNode`)
+    #[inline]
+    pub fn focus_node(&self) -> &Term {
+        &self.focus_node
+    }
+
+    /// The path of the property shape or the property not allowed by a closed shape (`sh:resultPath`)
+    #[inline]
+    pub fn path(&self) -> Option<&Term> {
+        self.path.as_ref()
+    }
+
+    /// The value node that has caused the result (`sh:value`)
+    #[inline]
+    pub fn value(&self) -> Option<&Term> {
+        self.value.as_ref()
+    }
+
+    /// The shape that has been validated (`sh:sourceShape`)
+    #[inline]
+    pub fn source_shape(&self) -> &Term {
+        &self.source_shape
+    }
+
+    /// The constraint component that has caused the result (`sh:sourceConstraintComponent`)
+    #[inline]
+    pub fn source_constraint_component(&self) -> NamedNodeRef<'_> {
+        self.source_constraint_component.as_ref()
+    }
+
+    /// The SPARQL-based constraint that has caused the result (`sh:sourceConstraint`)
+    #[inline]
+    pub fn source_constraint(&self) -> Option<&Term> {
+        self.source_constraint.as_ref()
+    }
+
+    /// The severity of the result (`sh:resultSeverity`), `sh:Violation` by default
+    #[inline]
+    pub fn severity(&self) -> NamedNodeRef<'_> {
+        self.severity.as_ref()
+    }
+
+    /// The messages of the shape (`sh:resultMessage`)
+    #[inline]
+    pub fn messages(&self) -> &[Literal] {
+        &self.messages
+    }
+}
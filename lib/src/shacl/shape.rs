@@ -0,0 +1,713 @@
+//! Parsing of the shapes graph into shapes and constraints.
+
+use crate::error::invalid_data_error;
+use crate::model::vocab::{rdf, rdfs, xsd};
+use crate::model::*;
+use crate::shacl::vocab as sh;
+use crate::sparql::{build_regex, Query};
+use regex::Regex;
+use spargebra::algebra::GraphPattern;
+use spargebra::term::Variable;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::Result;
+
+/// A node or property shape
+#[derive(Debug, Clone)]
+pub struct Shape {
+    pub node: Term,
+    /// The path of a property shape and its node in the shapes graph
+    pub path: Option<(Term, Path)>,
+    pub targets: Vec<Target>,
+    pub constraints: Vec<Constraint>,
+    pub severity: NamedNode,
+    pub messages: Vec<Literal>,
+    pub deactivated: bool,
+}
+
+/// A [SHACL property path](https://www.w3.org/TR/shacl/#property-paths)
+#[derive(Debug, Clone)]
+pub enum Path {
+    Predicate(NamedNode),
+    Inverse(Box<Self>),
+    Sequence(Vec<Self>),
+    Alternative(Vec<Self>),
+    ZeroOrMore(Box<Self>),
+    OneOrMore(Box<Self>),
+    ZeroOrOne(Box<Self>),
+}
+
+impl fmt::Display for Path {
+    /// Writes the path using the SPARQL property path syntax
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Predicate(predicate) => write!(f, "{}", predicate),
+            Self::Inverse(path) => write!(f, "^({})", path),
+            Self::Sequence(paths) => {
+                write!(f, "(")?;
+                for (i, path) in paths.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "/")?;
+                    }
+                    write!(f, "{}", path)?;
+                }
+                write!(f, ")")
+            }
+            Self::Alternative(paths) => {
+                write!(f, "(")?;
+                for (i, path) in paths.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "|")?;
+                    }
+                    write!(f, "{}", path)?;
+                }
+                write!(f, ")")
+            }
+            Self::ZeroOrMore(path) => write!(f, "({})*", path),
+            Self::OneOrMore(path) => write!(f, "({})+", path),
+            Self::ZeroOrOne(path) => write!(f, "({})?", path),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Target {
+    Node(Term),
+    Class(Term),
+    SubjectsOf(NamedNode),
+    ObjectsOf(NamedNode),
+}
+
+/// A constraint of a shape. The shapes are referred to using their index in the shapes list.
+#[derive(Debug, Clone)]
+pub enum Constraint {
+    Class(Term),
+    Datatype(NamedNode),
+    NodeKind(NamedNode),
+    MinCount(u64),
+    MaxCount(u64),
+    MinExclusive(Term),
+    MinInclusive(Term),
+    MaxExclusive(Term),
+    MaxInclusive(Term),
+    MinLength(u64),
+    MaxLength(u64),
+    Pattern(Regex),
+    LanguageIn(Vec<String>),
+    UniqueLang,
+    Equals(NamedNode),
+    Disjoint(NamedNode),
+    LessThan(NamedNode),
+    LessThanOrEquals(NamedNode),
+    Not(usize),
+    And(Vec<usize>),
+    Or(Vec<usize>),
+    Xone(Vec<usize>),
+    Node(usize),
+    Property(usize),
+    QualifiedMinCount {
+        shape: usize,
+        count: u64,
+        siblings: Vec<usize>,
+    },
+    QualifiedMaxCount {
+        shape: usize,
+        count: u64,
+        siblings: Vec<usize>,
+    },
+    Closed {
+        allowed: HashSet<NamedNode>,
+    },
+    HasValue(Term),
+    In(Vec<Term>),
+    /// A SPARQL-based constraint with its node in the shapes graph
+    Sparql {
+        node: Term,
+        query: Box<Query>,
+        messages: Vec<Literal>,
+    },
+}
+
+impl Constraint {
+    /// The constraint component reported in the validation results
+    pub fn component(&self) -> NamedNodeRef<'static> {
+        match self {
+            Self::Class(_) => sh::CLASS_CONSTRAINT_COMPONENT,
+            Self::Datatype(_) => sh::DATATYPE_CONSTRAINT_COMPONENT,
+            Self::NodeKind(_) => sh::NODE_KIND_CONSTRAINT_COMPONENT,
+            Self::MinCount(_) => sh::MIN_COUNT_CONSTRAINT_COMPONENT,
+            Self::MaxCount(_) => sh::MAX_COUNT_CONSTRAINT_COMPONENT,
+            Self::MinExclusive(_) => sh::MIN_EXCLUSIVE_CONSTRAINT_COMPONENT,
+            Self::MinInclusive(_) => sh::MIN_INCLUSIVE_CONSTRAINT_COMPONENT,
+            Self::MaxExclusive(_) => sh::MAX_EXCLUSIVE_CONSTRAINT_COMPONENT,
+            Self::MaxInclusive(_) => sh::MAX_INCLUSIVE_CONSTRAINT_COMPONENT,
+            Self::MinLength(_) => sh::MIN_LENGTH_CONSTRAINT_COMPONENT,
+            Self::MaxLength(_) => sh::MAX_LENGTH_CONSTRAINT_COMPONENT,
+            Self::Pattern(_) => sh::PATTERN_CONSTRAINT_COMPONENT,
+            Self::LanguageIn(_) => sh::LANGUAGE_IN_CONSTRAINT_COMPONENT,
+            Self::UniqueLang => sh::UNIQUE_LANG_CONSTRAINT_COMPONENT,
+            Self::Equals(_) => sh::EQUALS_CONSTRAINT_COMPONENT,
+            Self::Disjoint(_) => sh::DISJOINT_CONSTRAINT_COMPONENT,
+            Self::LessThan(_) => sh::LESS_THAN_CONSTRAINT_COMPONENT,
+            Self::LessThanOrEquals(_) => sh::LESS_THAN_OR_EQUALS_CONSTRAINT_COMPONENT,
+            Self::Not(_) => sh::NOT_CONSTRAINT_COMPONENT,
+            Self::And(_) => sh::AND_CONSTRAINT_COMPONENT,
+            Self::Or(_) => sh::OR_CONSTRAINT_COMPONENT,
+            Self::Xone(_) => sh::XONE_CONSTRAINT_COMPONENT,
+            // The results of property shapes are reported with their own component
+            Self::Node(_) | Self::Property(_) => sh::NODE_CONSTRAINT_COMPONENT,
+            Self::QualifiedMinCount { .. } => sh::QUALIFIED_MIN_COUNT_CONSTRAINT_COMPONENT,
+            Self::QualifiedMaxCount { .. } => sh::QUALIFIED_MAX_COUNT_CONSTRAINT_COMPONENT,
+            Self::Closed { .. } => sh::CLOSED_CONSTRAINT_COMPONENT,
+            Self::HasValue(_) => sh::HAS_VALUE_CONSTRAINT_COMPONENT,
+            Self::In(_) => sh::IN_CONSTRAINT_COMPONENT,
+            Self::Sparql { .. } => sh::SPARQL_CONSTRAINT_COMPONENT,
+        }
+    }
+}
+
+/// Parses the shapes of a shapes graph
+///
+/// Returns the shapes and the triples describing the complex property paths, indexed by their root node.
+pub fn parse_shapes(graph: &Graph) -> Result<(Vec<Shape>, HashMap<Term, Vec<Triple>>)> {
+    let mut parser = ShapesParser {
+        graph,
+        ids: HashMap::new(),
+        nodes: Vec::new(),
+        path_definitions: HashMap::new(),
+    };
+    for class in [sh::NODE_SHAPE, sh::PROPERTY_SHAPE] {
+        for shape in graph.subjects_for_predicate_object(rdf::TYPE, class) {
+            parser.shape_id(shape.into());
+        }
+    }
+    for predicate in [
+        sh::TARGET_NODE,
+        sh::TARGET_CLASS,
+        sh::TARGET_SUBJECTS_OF,
+        sh::TARGET_OBJECTS_OF,
+        sh::PATH,
+    ] {
+        for triple in graph.triples_for_predicate(predicate) {
+            parser.shape_id(triple.subject.into());
+        }
+    }
+    let mut shapes = Vec::new();
+    while shapes.len() < parser.nodes.len() {
+        let node = parser.nodes[shapes.len()];
+        shapes.push(parser.parse_shape(node)?);
+    }
+    Ok((shapes, parser.path_definitions))
+}
+
+struct ShapesParser<'a> {
+    graph: &'a Graph,
+    ids: HashMap<TermRef<'a>, usize>,
+    /// The shape nodes, the shape id being their position in the list
+    nodes: Vec<TermRef<'a>>,
+    path_definitions: HashMap<Term, Vec<Triple>>,
+}
+
+impl<'a> ShapesParser<'a> {
+    /// Returns the id of the shape, registering it to be parsed if it is new
+    fn shape_id(&mut self, node: TermRef<'a>) -> usize {
+        if let Some(id) = self.ids.get(&node) {
+            return *id;
+        }
+        let id = self.nodes.len();
+        self.ids.insert(node, id);
+        self.nodes.push(node);
+        id
+    }
+
+    fn parse_shape(&mut self, node: TermRef<'a>) -> Result<Shape> {
+        let path = if let Some(path) = self.object(node, sh::PATH)? {
+            let mut definition = Vec::new();
+            self.describe(path, &mut definition, &mut HashSet::new());
+            if !definition.is_empty() {
+                self.path_definitions.insert(path.into_owned(), definition);
+            }
+            Some((path.into_owned(), self.parse_path(path)?))
+        } else {
+            None
+        };
+
+        let mut targets = Vec::new();
+        for target in self.objects(node, sh::TARGET_NODE) {
+            targets.push(Target::Node(target.into_owned()));
+        }
+        for target in self.objects(node, sh::TARGET_CLASS) {
+            targets.push(Target::Class(target.into_owned()));
+        }
+        if self.has_object(node, rdf::TYPE, rdfs::CLASS) {
+            // Implicit class target
+            targets.push(Target::Class(node.into_owned()));
+        }
+        for target in self.objects(node, sh::TARGET_SUBJECTS_OF) {
+            targets.push(Target::SubjectsOf(named_node(target)?));
+        }
+        for target in self.objects(node, sh::TARGET_OBJECTS_OF) {
+            targets.push(Target::ObjectsOf(named_node(target)?));
+        }
+
+        let mut constraints = Vec::new();
+        for class in self.objects(node, sh::CLASS) {
+            constraints.push(Constraint::Class(class.into_owned()));
+        }
+        for datatype in self.objects(node, sh::DATATYPE) {
+            constraints.push(Constraint::Datatype(named_node(datatype)?));
+        }
+        for kind in self.objects(node, sh::NODE_KIND) {
+            let kind = named_node(kind)?;
+            if ![
+                sh::IRI,
+                sh::BLANK_NODE,
+                sh::LITERAL,
+                sh::BLANK_NODE_OR_IRI,
+                sh::BLANK_NODE_OR_LITERAL,
+                sh::IRI_OR_LITERAL,
+            ]
+            .contains(&kind.as_ref())
+            {
+                return Err(invalid_data_error(format!("Unknown node kind {}", kind)));
+            }
+            constraints.push(Constraint::NodeKind(kind));
+        }
+        for count in self.objects(node, sh::MIN_COUNT) {
+            constraints.push(Constraint::MinCount(integer(count)?));
+        }
+        for count in self.objects(node, sh::MAX_COUNT) {
+            constraints.push(Constraint::MaxCount(integer(count)?));
+        }
+        for bound in self.objects(node, sh::MIN_EXCLUSIVE) {
+            constraints.push(Constraint::MinExclusive(bound.into_owned()));
+        }
+        for bound in self.objects(node, sh::MIN_INCLUSIVE) {
+            constraints.push(Constraint::MinInclusive(bound.into_owned()));
+        }
+        for bound in self.objects(node, sh::MAX_EXCLUSIVE) {
+            constraints.push(Constraint::MaxExclusive(bound.into_owned()));
+        }
+        for bound in self.objects(node, sh::MAX_INCLUSIVE) {
+            constraints.push(Constraint::MaxInclusive(bound.into_owned()));
+        }
+        for length in self.objects(node, sh::MIN_LENGTH) {
+            constraints.push(Constraint::MinLength(integer(length)?));
+        }
+        for length in self.objects(node, sh::MAX_LENGTH) {
+            constraints.push(Constraint::MaxLength(integer(length)?));
+        }
+        let flags = if let Some(flags) = self.object(node, sh::FLAGS)? {
+            Some(string(flags)?)
+        } else {
+            None
+        };
+        for pattern in self.objects(node, sh::PATTERN) {
+            constraints.push(Constraint::Pattern(compile_pattern(
+                string(pattern)?,
+                flags,
+            )?));
+        }
+        for list in self.objects(node, sh::LANGUAGE_IN) {
+            let languages = self
+                .list(list)?
+                .into_iter()
+                .map(|language| Ok(string(language)?.to_ascii_lowercase()))
+                .collect::<Result<_>>()?;
+            constraints.push(Constraint::LanguageIn(languages));
+        }
+        for unique in self.objects(node, sh::UNIQUE_LANG) {
+            if boolean(unique)? {
+                constraints.push(Constraint::UniqueLang);
+            }
+        }
+        for property in self.objects(node, sh::EQUALS) {
+            constraints.push(Constraint::Equals(named_node(property)?));
+        }
+        for property in self.objects(node, sh::DISJOINT) {
+            constraints.push(Constraint::Disjoint(named_node(property)?));
+        }
+        for property in self.objects(node, sh::LESS_THAN) {
+            constraints.push(Constraint::LessThan(named_node(property)?));
+        }
+        for property in self.objects(node, sh::LESS_THAN_OR_EQUALS) {
+            constraints.push(Constraint::LessThanOrEquals(named_node(property)?));
+        }
+        for shape in self.objects(node, sh::NOT) {
+            constraints.push(Constraint::Not(self.shape_id(shape)));
+        }
+        for list in self.objects(node, sh::AND) {
+            constraints.push(Constraint::And(self.shape_list(list)?));
+        }
+        for list in self.objects(node, sh::OR) {
+            constraints.push(Constraint::Or(self.shape_list(list)?));
+        }
+        for list in self.objects(node, sh::XONE) {
+            constraints.push(Constraint::Xone(self.shape_list(list)?));
+        }
+        for shape in self.objects(node, sh::NODE) {
+            constraints.push(Constraint::Node(self.shape_id(shape)));
+        }
+        for shape in self.objects(node, sh::PROPERTY) {
+            if self.object(shape, sh::PATH)?.is_none() {
+                return Err(invalid_data_error(format!(
+                    "The property shape {} has no sh:path",
+                    shape
+                )));
+            }
+            constraints.push(Constraint::Property(self.shape_id(shape)));
+        }
+        for shape in self.objects(node, sh::QUALIFIED_VALUE_SHAPE) {
+            let siblings =
+                if let Some(disjoint) = self.object(node, sh::QUALIFIED_VALUE_SHAPES_DISJOINT)? {
+                    if boolean(disjoint)? {
+                        self.sibling_shapes(node, shape)
+                    } else {
+                        Vec::new()
+                    }
+                } else {
+                    Vec::new()
+                };
+            let shape = self.shape_id(shape);
+            if let Some(count) = self.object(node, sh::QUALIFIED_MIN_COUNT)? {
+                constraints.push(Constraint::QualifiedMinCount {
+                    shape,
+                    count: integer(count)?,
+                    siblings: siblings.clone(),
+                });
+            }
+            if let Some(count) = self.object(node, sh::QUALIFIED_MAX_COUNT)? {
+                constraints.push(Constraint::QualifiedMaxCount {
+                    shape,
+                    count: integer(count)?,
+                    siblings,
+                });
+            }
+        }
+        if let Some(closed) = self.object(node, sh::CLOSED)? {
+            if boolean(closed)? {
+                let mut allowed = HashSet::new();
+                for property in self.objects(node, sh::PROPERTY) {
+                    if let Some(TermRef::NamedNode(path)) = self.object(property, sh::PATH)? {
+                        allowed.insert(path.into_owned());
+                    }
+                }
+                if let Some(ignored) = self.object(node, sh::IGNORED_PROPERTIES)? {
+                    for property in self.list(ignored)? {
+                        allowed.insert(named_node(property)?);
+                    }
+                }
+                constraints.push(Constraint::Closed { allowed });
+            }
+        }
+        for value in self.objects(node, sh::HAS_VALUE) {
+            constraints.push(Constraint::HasValue(value.into_owned()));
+        }
+        for list in self.objects(node, sh::IN) {
+            let values = self
+                .list(list)?
+                .into_iter()
+                .map(TermRef::into_owned)
+                .collect();
+            constraints.push(Constraint::In(values));
+        }
+        for constraint in self.objects(node, sh::SPARQL) {
+            if let Some(constraint) =
+                self.sparql_constraint(constraint, path.as_ref().map(|(_, path)| path))?
+            {
+                constraints.push(constraint);
+            }
+        }
+
+        let severity = if let Some(severity) = self.object(node, sh::SEVERITY)? {
+            named_node(severity)?
+        } else {
+            sh::VIOLATION.into_owned()
+        };
+        let messages = self
+            .objects(node, sh::MESSAGE)
+            .into_iter()
+            .filter_map(|message| match message {
+                TermRef::Literal(message) => Some(message.into_owned()),
+                _ => None,
+            })
+            .collect();
+        let deactivated = if let Some(deactivated) = self.object(node, sh::DEACTIVATED)? {
+            boolean(deactivated)?
+        } else {
+            false
+        };
+        Ok(Shape {
+            node: node.into_owned(),
+            path,
+            targets,
+            constraints,
+            severity,
+            messages,
+            deactivated,
+        })
+    }
+
+    /// Parses a [SPARQL-based constraint](https://www.w3.org/TR/shacl/#sparql-constraints)
+    ///
+    /// The `$PATH` placeholder is replaced by the path of the property shape
+    /// and the query is modified to always return the `$this` variable in order to be able to bind it.
+    fn sparql_constraint(
+        &self,
+        node: TermRef<'a>,
+        path: Option<&Path>,
+    ) -> Result<Option<Constraint>> {
+        if let Some(deactivated) = self.object(node, sh::DEACTIVATED)? {
+            if boolean(deactivated)? {
+                return Ok(None);
+            }
+        }
+        let select = self.object(node, sh::SELECT)?.ok_or_else(|| {
+            invalid_data_error(format!("The SPARQL constraint {} has no sh:select", node))
+        })?;
+        let mut text = String::new();
+        for prefixes in self.objects(node, sh::PREFIXES) {
+            for declaration in self.objects(prefixes, sh::DECLARE) {
+                let prefix = self.object(declaration, sh::PREFIX)?.ok_or_else(|| {
+                    invalid_data_error(format!("{} has no sh:prefix", declaration))
+                })?;
+                let namespace = self.object(declaration, sh::NAMESPACE)?.ok_or_else(|| {
+                    invalid_data_error(format!("{} has no sh:namespace", declaration))
+                })?;
+                text.push_str(&format!(
+                    "PREFIX {}: <{}>\n",
+                    string(prefix)?,
+                    string(namespace)?
+                ));
+            }
+        }
+        let select = string(select)?;
+        if let Some(path) = path {
+            text.push_str(&select.replace("$PATH", &path.to_string()));
+        } else {
+            text.push_str(select);
+        }
+        let mut query = Query::parse(&text, None).map_err(invalid_data_error)?;
+        if let spargebra::Query::Select { pattern, .. } = &mut query.inner {
+            project_this(pattern);
+        } else {
+            return Err(invalid_data_error(format!(
+                "The sh:select value of {} should be a SELECT query",
+                node
+            )));
+        }
+        let messages = self
+            .objects(node, sh::MESSAGE)
+            .into_iter()
+            .filter_map(|message| match message {
+                TermRef::Literal(message) => Some(message.into_owned()),
+                _ => None,
+            })
+            .collect();
+        Ok(Some(Constraint::Sparql {
+            node: node.into_owned(),
+            query: Box::new(query),
+            messages,
+        }))
+    }
+
+    fn parse_path(&self, node: TermRef<'a>) -> Result<Path> {
+        Ok(match node {
+            TermRef::NamedNode(predicate) => Path::Predicate(predicate.into_owned()),
+            TermRef::BlankNode(_) => {
+                if self.object(node, rdf::FIRST)?.is_some() {
+                    Path::Sequence(self.path_list(node)?)
+                } else if let Some(path) = self.object(node, sh::INVERSE_PATH)? {
+                    Path::Inverse(Box::new(self.parse_path(path)?))
+                } else if let Some(list) = self.object(node, sh::ALTERNATIVE_PATH)? {
+                    Path::Alternative(self.path_list(list)?)
+                } else if let Some(path) = self.object(node, sh::ZERO_OR_MORE_PATH)? {
+                    Path::ZeroOrMore(Box::new(self.parse_path(path)?))
+                } else if let Some(path) = self.object(node, sh::ONE_OR_MORE_PATH)? {
+                    Path::OneOrMore(Box::new(self.parse_path(path)?))
+                } else if let Some(path) = self.object(node, sh::ZERO_OR_ONE_PATH)? {
+                    Path::ZeroOrOne(Box::new(self.parse_path(path)?))
+                } else {
+                    return Err(invalid_data_error(format!("Invalid SHACL path {}", node)));
+                }
+            }
+            _ => return Err(invalid_data_error(format!("Invalid SHACL path {}", node))),
+        })
+    }
+
+    fn path_list(&self, list: TermRef<'a>) -> Result<Vec<Path>> {
+        self.list(list)?
+            .into_iter()
+            .map(|path| self.parse_path(path))
+            .collect()
+    }
+
+    fn shape_list(&mut self, list: TermRef<'a>) -> Result<Vec<usize>> {
+        Ok(self
+            .list(list)?
+            .into_iter()
+            .map(|shape| self.shape_id(shape))
+            .collect())
+    }
+
+    /// The qualified value shapes of the other property shapes of the shapes having `shape` as property shape
+    fn sibling_shapes(&mut self, shape: TermRef<'a>, qualified_shape: TermRef<'a>) -> Vec<usize> {
+        let graph = self.graph;
+        let mut siblings = Vec::new();
+        for parent in graph.subjects_for_predicate_object(sh::PROPERTY, shape) {
+            for sibling in self.objects(parent.into(), sh::PROPERTY) {
+                if sibling == shape {
+                    continue;
+                }
+                for sibling_shape in self.objects(sibling, sh::QUALIFIED_VALUE_SHAPE) {
+                    if sibling_shape != qualified_shape {
+                        siblings.push(self.shape_id(sibling_shape));
+                    }
+                }
+            }
+        }
+        siblings
+    }
+
+    /// Collects the triples describing the node following the blank nodes
+    fn describe(
+        &self,
+        node: TermRef<'a>,
+        triples: &mut Vec<Triple>,
+        visited: &mut HashSet<TermRef<'a>>,
+    ) {
+        if !matches!(node, TermRef::BlankNode(_)) || !visited.insert(node) {
+            return;
+        }
+        if let Some(subject) = as_subject(node) {
+            for triple in self.graph.triples_for_subject(subject) {
+                triples.push(triple.into_owned());
+                self.describe(triple.object, triples, visited);
+            }
+        }
+    }
+
+    fn objects(&self, node: TermRef<'a>, predicate: NamedNodeRef<'_>) -> Vec<TermRef<'a>> {
+        if let Some(subject) = as_subject(node) {
+            self.graph
+                .objects_for_subject_predicate(subject, predicate)
+                .collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Returns the value of a single-valued property
+    fn object(
+        &self,
+        node: TermRef<'a>,
+        predicate: NamedNodeRef<'_>,
+    ) -> Result<Option<TermRef<'a>>> {
+        let mut objects = self.objects(node, predicate).into_iter();
+        let object = objects.next();
+        if objects.next().is_some() {
+            return Err(invalid_data_error(format!(
+                "{} should have at most one value for {}",
+                node, predicate
+            )));
+        }
+        Ok(object)
+    }
+
+    fn has_object(
+        &self,
+        node: TermRef<'a>,
+        predicate: NamedNodeRef<'_>,
+        object: NamedNodeRef<'_>,
+    ) -> bool {
+        as_subject(node).map_or(false, |subject| {
+            self.graph
+                .contains(TripleRef::new(subject, predicate, object))
+        })
+    }
+
+    fn list(&self, mut list: TermRef<'a>) -> Result<Vec<TermRef<'a>>> {
+        let mut elements = Vec::new();
+        let mut visited = HashSet::new();
+        while list != rdf::NIL.into() {
+            if !visited.insert(list) {
+                return Err(invalid_data_error(format!("The list {} is cyclic", list)));
+            }
+            elements.push(
+                self.object(list, rdf::FIRST)?
+                    .ok_or_else(|| invalid_data_error(format!("{} is not a valid list", list)))?,
+            );
+            list = self
+                .object(list, rdf::REST)?
+                .ok_or_else(|| invalid_data_error(format!("{} is not a valid list", list)))?;
+        }
+        Ok(elements)
+    }
+}
+
+fn named_node(term: TermRef<'_>) -> Result<NamedNode> {
+    if let TermRef::NamedNode(node) = term {
+        Ok(node.into_owned())
+    } else {
+        Err(invalid_data_error(format!("{} should be an IRI", term)))
+    }
+}
+
+fn string<'a>(term: TermRef<'a>) -> Result<&'a str> {
+    if let TermRef::Literal(literal) = term {
+        Ok(literal.value())
+    } else {
+        Err(invalid_data_error(format!("{} should be a literal", term)))
+    }
+}
+
+fn integer(term: TermRef<'_>) -> Result<u64> {
+    match term {
+        TermRef::Literal(literal) if literal.datatype() == xsd::INTEGER => literal
+            .value()
+            .parse()
+            .map_err(|_| invalid_data_error(format!("{} should be a non negative integer", term))),
+        _ => Err(invalid_data_error(format!(
+            "{} should be a non negative integer",
+            term
+        ))),
+    }
+}
+
+fn boolean(term: TermRef<'_>) -> Result<bool> {
+    match term {
+        TermRef::Literal(literal) if literal.datatype() == xsd::BOOLEAN => {
+            Ok(matches!(literal.value(), "true" | "1"))
+        }
+        _ => Err(invalid_data_error(format!("{} should be a boolean", term))),
+    }
+}
+
+/// Adds the `$this` variable to the projection of a `SELECT` query
+fn project_this(pattern: &mut GraphPattern) {
+    match pattern {
+        GraphPattern::Project { variables, .. } if !variables.iter().any(|v| v.name == "this") => {
+            variables.push(Variable {
+                name: "this".into(),
+            });
+        }
+        GraphPattern::Distinct { inner }
+        | GraphPattern::Reduced { inner }
+        | GraphPattern::Slice { inner, .. }
+        | GraphPattern::OrderBy { inner, .. } => project_this(inner),
+        _ => (),
+    }
+}
+
+fn compile_pattern(pattern: &str, flags: Option<&str>) -> Result<Regex> {
+    build_regex(pattern, flags).map_err(invalid_data_error)
+}
+
+pub fn as_subject(term: TermRef<'_>) -> Option<SubjectRef<'_>> {
+    match term {
+        TermRef::NamedNode(node) => Some(node.into()),
+        TermRef::BlankNode(node) => Some(node.into()),
+        TermRef::Triple(triple) => Some(SubjectRef::Triple(triple)),
+        TermRef::Literal(_) => None,
+    }
+}
@@ -0,0 +1,1011 @@
+//! Evaluation of the shapes against a data graph.
+
+use crate::model::vocab::{rdf, rdfs, xsd};
+use crate::model::xsd::*;
+use crate::model::*;
+use crate::shacl::report::ValidationResult;
+use crate::shacl::shape::{Constraint, Path, Shape, Target};
+use crate::shacl::vocab as sh;
+use crate::shacl::DataGraph;
+use crate::sparql::{EvaluationError, QuerySolution};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+pub struct Validator<'a, D: DataGraph> {
+    shapes: &'a [Shape],
+    data: &'a D,
+    /// The (shape, focus node) pairs being validated, used to stop on recursive shapes
+    stack: HashSet<(usize, Term)>,
+}
+
+impl<'a, D: DataGraph> Validator<'a, D> {
+    pub fn new(shapes: &'a [Shape], data: &'a D) -> Self {
+        Self {
+            shapes,
+            data,
+            stack: HashSet::new(),
+        }
+    }
+
+    /// Validates the focus nodes of all the shapes with targets
+    pub fn validate(&mut self) -> Result<Vec<ValidationResult>, EvaluationError> {
+        let shapes = self.shapes;
+        let mut results = Vec::new();
+        for (id, shape) in shapes.iter().enumerate() {
+            if shape.deactivated {
+                continue;
+            }
+            for focus in self.focus_nodes(shape)? {
+                results.extend(self.validate_shape(id, &focus)?);
+            }
+        }
+        Ok(results)
+    }
+
+    /// Validates the given nodes against the shapes they are a focus node of
+    pub fn validate_nodes(
+        &mut self,
+        nodes: &[Term],
+    ) -> Result<Vec<ValidationResult>, EvaluationError> {
+        let shapes = self.shapes;
+        let mut results = Vec::new();
+        for (id, shape) in shapes.iter().enumerate() {
+            if shape.deactivated {
+                continue;
+            }
+            for node in nodes {
+                if self.is_focus_node(shape, node)? {
+                    results.extend(self.validate_shape(id, node)?);
+                }
+            }
+        }
+        Ok(results)
+    }
+
+    /// The nodes whose conformance might be changed by the insertion or the removal of the `changes` triples.
+    ///
+    /// Returns `None` if the validation could not be restricted to some nodes,
+    /// for example because of recursive shapes, SPARQL-based constraints or `*` and `+` paths.
+    pub fn affected_nodes(&self, changes: &[Triple]) -> Result<Option<Vec<Term>>, EvaluationError> {
+        if changes
+            .iter()
+            .any(|triple| triple.predicate == rdfs::SUB_CLASS_OF)
+        {
+            // The class hierarchies might be arbitrarily deep
+            return Ok(None);
+        }
+        let mut reach = Reach::default();
+        for (id, shape) in self.shapes.iter().enumerate() {
+            if shape.deactivated || shape.targets.is_empty() {
+                continue;
+            }
+            if let Some(shape_reach) = shape_reach(self.shapes, id, &mut HashSet::new()) {
+                reach = reach.union(shape_reach);
+            } else {
+                return Ok(None);
+            }
+        }
+
+        // A changed triple is read from its subject or, with inverse paths, from its object
+        let mut nodes = UniqueTerms::default();
+        let mut visited = HashSet::new();
+        let mut current = Vec::new();
+        for triple in changes {
+            let subject = Term::from(triple.subject.clone());
+            nodes.insert(subject.clone());
+            if visited.insert(subject.clone()) {
+                current.push(subject);
+            }
+            // The object might be a focus node because of a sh:targetObjectsOf target
+            nodes.insert(triple.object.clone());
+            if reach.inverse && visited.insert(triple.object.clone()) {
+                current.push(triple.object.clone());
+            }
+        }
+        // We look for the focus nodes from which the changed triples are reachable
+        for _ in 1..reach.depth {
+            let mut next = Vec::new();
+            for node in current {
+                for triple in self.data.triples(None, None, Some(&node))? {
+                    let subject = Term::from(triple.subject);
+                    nodes.insert(subject.clone());
+                    if visited.insert(subject.clone()) {
+                        next.push(subject);
+                    }
+                }
+                if reach.inverse {
+                    for triple in self.data.triples(Some(&node), None, None)? {
+                        nodes.insert(triple.object.clone());
+                        if visited.insert(triple.object.clone()) {
+                            next.push(triple.object);
+                        }
+                    }
+                }
+            }
+            current = next;
+        }
+        Ok(Some(nodes.into_vec()))
+    }
+
+    fn is_focus_node(&self, shape: &Shape, node: &Term) -> Result<bool, EvaluationError> {
+        for target in &shape.targets {
+            if match target {
+                Target::Node(target) => target == node,
+                Target::Class(class) => self.instances_types(node)?.contains(class),
+                Target::SubjectsOf(predicate) => !self
+                    .data
+                    .triples(Some(node), Some(predicate.as_ref()), None)?
+                    .is_empty(),
+                Target::ObjectsOf(predicate) => !self
+                    .data
+                    .triples(None, Some(predicate.as_ref()), Some(node))?
+                    .is_empty(),
+            } {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn focus_nodes(&self, shape: &Shape) -> Result<Vec<Term>, EvaluationError> {
+        let mut nodes = UniqueTerms::default();
+        for target in &shape.targets {
+            match target {
+                Target::Node(node) => {
+                    nodes.insert(node.clone());
+                }
+                Target::Class(class) => nodes.extend(self.instances(class)?),
+                Target::SubjectsOf(predicate) => nodes.extend(
+                    self.data
+                        .triples(None, Some(predicate.as_ref()), None)?
+                        .into_iter()
+                        .map(|t| t.subject.into()),
+                ),
+                Target::ObjectsOf(predicate) => nodes.extend(
+                    self.data
+                        .triples(None, Some(predicate.as_ref()), None)?
+                        .into_iter()
+                        .map(|t| t.object),
+                ),
+            }
+        }
+        Ok(nodes.into_vec())
+    }
+
+    /// Validates the focus node against the shape and returns the validation results
+    pub fn validate_shape(
+        &mut self,
+        id: usize,
+        focus: &Term,
+    ) -> Result<Vec<ValidationResult>, EvaluationError> {
+        let shapes = self.shapes;
+        let shape = &shapes[id];
+        if shape.deactivated || !self.stack.insert((id, focus.clone())) {
+            // Recursive shapes are assumed to be valid
+            return Ok(Vec::new());
+        }
+        let values = if let Some((_, path)) = &shape.path {
+            self.evaluate_path(path, vec![focus.clone()], false)?
+        } else {
+            vec![focus.clone()]
+        };
+        let mut results = Vec::new();
+        for constraint in &shape.constraints {
+            self.check(shape, constraint, focus, &values, &mut results)?;
+        }
+        self.stack.remove(&(id, focus.clone()));
+        Ok(results)
+    }
+
+    fn conforms(&mut self, id: usize, node: &Term) -> Result<bool, EvaluationError> {
+        Ok(self.validate_shape(id, node)?.is_empty())
+    }
+
+    fn check(
+        &mut self,
+        shape: &Shape,
+        constraint: &Constraint,
+        focus: &Term,
+        values: &[Term],
+        results: &mut Vec<ValidationResult>,
+    ) -> Result<(), EvaluationError> {
+        let mut violations = Vec::new();
+        match constraint {
+            Constraint::Class(class) => {
+                for value in values {
+                    if !self.instances_types(value)?.contains(class) {
+                        violations.push(Some(value.clone()));
+                    }
+                }
+            }
+            Constraint::Datatype(datatype) => violations.extend(
+                values
+                    .iter()
+                    .filter(|value| !has_datatype(value, datatype.as_ref()))
+                    .map(|value| Some(value.clone())),
+            ),
+            Constraint::NodeKind(kind) => violations.extend(
+                values
+                    .iter()
+                    .filter(|value| !has_node_kind(value, kind.as_ref()))
+                    .map(|value| Some(value.clone())),
+            ),
+            Constraint::MinCount(count) => {
+                if (values.len() as u64) < *count {
+                    violations.push(None);
+                }
+            }
+            Constraint::MaxCount(count) => {
+                if (values.len() as u64) > *count {
+                    violations.push(None);
+                }
+            }
+            Constraint::MinExclusive(bound) => violations.extend(
+                values
+                    .iter()
+                    .filter(|value| compare(value, bound) != Some(Ordering::Greater))
+                    .map(|value| Some(value.clone())),
+            ),
+            Constraint::MinInclusive(bound) => violations.extend(
+                values
+                    .iter()
+                    .filter(|value| {
+                        !matches!(
+                            compare(value, bound),
+                            Some(Ordering::Greater | Ordering::Equal)
+                        )
+                    })
+                    .map(|value| Some(value.clone())),
+            ),
+            Constraint::MaxExclusive(bound) => violations.extend(
+                values
+                    .iter()
+                    .filter(|value| compare(value, bound) != Some(Ordering::Less))
+                    .map(|value| Some(value.clone())),
+            ),
+            Constraint::MaxInclusive(bound) => violations.extend(
+                values
+                    .iter()
+                    .filter(|value| {
+                        !matches!(
+                            compare(value, bound),
+                            Some(Ordering::Less | Ordering::Equal)
+                        )
+                    })
+                    .map(|value| Some(value.clone())),
+            ),
+            Constraint::MinLength(length) => violations.extend(
+                values
+                    .iter()
+                    .filter(|value| {
+                        string_value(value).map_or(true, |v| (v.chars().count() as u64) < *length)
+                    })
+                    .map(|value| Some(value.clone())),
+            ),
+            Constraint::MaxLength(length) => violations.extend(
+                values
+                    .iter()
+                    .filter(|value| {
+                        string_value(value).map_or(true, |v| (v.chars().count() as u64) > *length)
+                    })
+                    .map(|value| Some(value.clone())),
+            ),
+            Constraint::Pattern(regex) => violations.extend(
+                values
+                    .iter()
+                    .filter(|value| string_value(value).map_or(true, |v| !regex.is_match(v)))
+                    .map(|value| Some(value.clone())),
+            ),
+            Constraint::LanguageIn(ranges) => violations.extend(
+                values
+                    .iter()
+                    .filter(|value| {
+                        language(value).map_or(true, |language| {
+                            !ranges.iter().any(|range| language_matches(language, range))
+                        })
+                    })
+                    .map(|value| Some(value.clone())),
+            ),
+            Constraint::UniqueLang => {
+                let mut seen = HashSet::new();
+                let mut duplicates = HashSet::new();
+                for language in values.iter().filter_map(language) {
+                    let language = language.to_ascii_lowercase();
+                    if !seen.insert(language.clone()) && duplicates.insert(language) {
+                        violations.push(None);
+                    }
+                }
+            }
+            Constraint::Equals(property) => {
+                let others = self.objects(focus, property.as_ref())?;
+                violations.extend(
+                    values
+                        .iter()
+                        .filter(|value| !others.contains(value))
+                        .chain(others.iter().filter(|other| !values.contains(other)))
+                        .map(|value| Some(value.clone())),
+                );
+            }
+            Constraint::Disjoint(property) => {
+                let others = self.objects(focus, property.as_ref())?;
+                violations.extend(
+                    values
+                        .iter()
+                        .filter(|value| others.contains(value))
+                        .map(|value| Some(value.clone())),
+                );
+            }
+            Constraint::LessThan(property) => {
+                let others = self.objects(focus, property.as_ref())?;
+                for value in values {
+                    for other in &others {
+                        if compare(value, other) != Some(Ordering::Less) {
+                            violations.push(Some(value.clone()));
+                        }
+                    }
+                }
+            }
+            Constraint::LessThanOrEquals(property) => {
+                let others = self.objects(focus, property.as_ref())?;
+                for value in values {
+                    for other in &others {
+                        if !matches!(
+                            compare(value, other),
+                            Some(Ordering::Less | Ordering::Equal)
+                        ) {
+                            violations.push(Some(value.clone()));
+                        }
+                    }
+                }
+            }
+            Constraint::Not(not) => {
+                for value in values {
+                    if self.conforms(*not, value)? {
+                        violations.push(Some(value.clone()));
+                    }
+                }
+            }
+            Constraint::And(shapes) => {
+                for value in values {
+                    for shape in shapes {
+                        if !self.conforms(*shape, value)? {
+                            violations.push(Some(value.clone()));
+                            break;
+                        }
+                    }
+                }
+            }
+            Constraint::Or(shapes) => {
+                for value in values {
+                    let mut conforms = false;
+                    for shape in shapes {
+                        if self.conforms(*shape, value)? {
+                            conforms = true;
+                            break;
+                        }
+                    }
+                    if !conforms {
+                        violations.push(Some(value.clone()));
+                    }
+                }
+            }
+            Constraint::Xone(shapes) => {
+                for value in values {
+                    let mut count = 0;
+                    for shape in shapes {
+                        if self.conforms(*shape, value)? {
+                            count += 1;
+                        }
+                    }
+                    if count != 1 {
+                        violations.push(Some(value.clone()));
+                    }
+                }
+            }
+            Constraint::Node(node) => {
+                for value in values {
+                    if !self.conforms(*node, value)? {
+                        violations.push(Some(value.clone()));
+                    }
+                }
+            }
+            Constraint::Property(property) => {
+                // The results of the property shape are reported directly
+                for value in values {
+                    results.extend(self.validate_shape(*property, value)?);
+                }
+            }
+            Constraint::QualifiedMinCount {
+                shape,
+                count,
+                siblings,
+            } => {
+                if self.qualified_count(*shape, siblings, values)? < *count {
+                    violations.push(None);
+                }
+            }
+            Constraint::QualifiedMaxCount {
+                shape,
+                count,
+                siblings,
+            } => {
+                if self.qualified_count(*shape, siblings, values)? > *count {
+                    violations.push(None);
+                }
+            }
+            Constraint::Closed { allowed } => {
+                for value in values {
+                    for triple in self.data.triples(Some(value), None, None)? {
+                        if !allowed.contains(&triple.predicate) {
+                            let mut result =
+                                new_result(shape, constraint, focus, Some(triple.object));
+                            result.path = Some(triple.predicate.into());
+                            results.push(result);
+                        }
+                    }
+                }
+            }
+            Constraint::HasValue(expected) => {
+                if !values.contains(expected) {
+                    violations.push(None);
+                }
+            }
+            Constraint::In(allowed) => violations.extend(
+                values
+                    .iter()
+                    .filter(|value| !allowed.contains(value))
+                    .map(|value| Some(value.clone())),
+            ),
+            Constraint::Sparql {
+                node,
+                query,
+                messages,
+            } => {
+                for solution in self.data.select(query, focus)? {
+                    if let Some(Term::Literal(failure)) = solution.get("failure") {
+                        if failure.value() == "true" {
+                            return Err(EvaluationError::msg(format!(
+                                "The SPARQL constraint {} has failed on the focus node {}",
+                                node, focus
+                            )));
+                        }
+                    }
+                    // Without ?value, the value of a node shape is the focus node
+                    let value = solution.get("value").cloned().or_else(|| {
+                        if shape.path.is_none() {
+                            Some(focus.clone())
+                        } else {
+                            None
+                        }
+                    });
+                    let mut result = new_result(shape, constraint, focus, value);
+                    if let Some(path) = solution.get("path") {
+                        result.path = Some(path.clone());
+                    }
+                    result.source_constraint = Some(node.clone());
+                    if !messages.is_empty() {
+                        result.messages = messages
+                            .iter()
+                            .map(|message| substitute_variables(message, &solution))
+                            .collect();
+                    }
+                    results.push(result);
+                }
+            }
+        }
+        results.extend(
+            violations
+                .into_iter()
+                .map(|value| new_result(shape, constraint, focus, value)),
+        );
+        Ok(())
+    }
+
+    /// Counts the values conforming to the shape and to none of the sibling shapes
+    fn qualified_count(
+        &mut self,
+        shape: usize,
+        siblings: &[usize],
+        values: &[Term],
+    ) -> Result<u64, EvaluationError> {
+        let mut count = 0;
+        'values: for value in values {
+            if !self.conforms(shape, value)? {
+                continue;
+            }
+            for sibling in siblings {
+                if self.conforms(*sibling, value)? {
+                    continue 'values;
+                }
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Evaluates the path from the start nodes, or the inverse path if `inverse` is set
+    fn evaluate_path(
+        &self,
+        path: &Path,
+        start: Vec<Term>,
+        inverse: bool,
+    ) -> Result<Vec<Term>, EvaluationError> {
+        Ok(match path {
+            Path::Predicate(predicate) => {
+                let mut nodes = UniqueTerms::default();
+                for node in &start {
+                    if inverse {
+                        nodes.extend(
+                            self.data
+                                .triples(None, Some(predicate.as_ref()), Some(node))?
+                                .into_iter()
+                                .map(|t| t.subject.into()),
+                        );
+                    } else {
+                        nodes.extend(self.objects(node, predicate.as_ref())?);
+                    }
+                }
+                nodes.into_vec()
+            }
+            Path::Inverse(path) => self.evaluate_path(path, start, !inverse)?,
+            Path::Sequence(paths) => {
+                let mut nodes = start;
+                if inverse {
+                    for path in paths.iter().rev() {
+                        nodes = self.evaluate_path(path, nodes, inverse)?;
+                    }
+                } else {
+                    for path in paths {
+                        nodes = self.evaluate_path(path, nodes, inverse)?;
+                    }
+                }
+                nodes
+            }
+            Path::Alternative(paths) => {
+                let mut nodes = UniqueTerms::default();
+                for path in paths {
+                    nodes.extend(self.evaluate_path(path, start.clone(), inverse)?);
+                }
+                nodes.into_vec()
+            }
+            Path::ZeroOrMore(path) => self.closure(path, start, inverse)?,
+            Path::OneOrMore(path) => {
+                let start = self.evaluate_path(path, start, inverse)?;
+                self.closure(path, start, inverse)?
+            }
+            Path::ZeroOrOne(path) => {
+                let mut nodes = UniqueTerms::default();
+                nodes.extend(start.clone());
+                nodes.extend(self.evaluate_path(path, start, inverse)?);
+                nodes.into_vec()
+            }
+        })
+    }
+
+    /// The nodes reachable from the start nodes using the path zero or more times
+    fn closure(
+        &self,
+        path: &Path,
+        start: Vec<Term>,
+        inverse: bool,
+    ) -> Result<Vec<Term>, EvaluationError> {
+        let mut nodes = UniqueTerms::default();
+        let mut current = start;
+        while !current.is_empty() {
+            let new = current
+                .into_iter()
+                .filter(|node| nodes.insert(node.clone()))
+                .collect::<Vec<_>>();
+            current = self.evaluate_path(path, new, inverse)?;
+        }
+        Ok(nodes.into_vec())
+    }
+
+    fn objects(
+        &self,
+        subject: &Term,
+        predicate: NamedNodeRef<'_>,
+    ) -> Result<Vec<Term>, EvaluationError> {
+        Ok(self
+            .data
+            .triples(Some(subject), Some(predicate), None)?
+            .into_iter()
+            .map(|t| t.object)
+            .collect())
+    }
+
+    /// The classes of the node, including their super classes
+    fn instances_types(&self, node: &Term) -> Result<HashSet<Term>, EvaluationError> {
+        let mut classes = HashSet::new();
+        let mut current = self.objects(node, rdf::TYPE)?;
+        while let Some(class) = current.pop() {
+            if classes.insert(class.clone()) {
+                current.extend(self.objects(&class, rdfs::SUB_CLASS_OF)?);
+            }
+        }
+        Ok(classes)
+    }
+
+    /// The instances of the class or of one of its sub classes
+    fn instances(&self, class: &Term) -> Result<Vec<Term>, EvaluationError> {
+        let mut classes = HashSet::new();
+        let mut current = vec![class.clone()];
+        let mut instances = UniqueTerms::default();
+        while let Some(class) = current.pop() {
+            if !classes.insert(class.clone()) {
+                continue;
+            }
+            instances.extend(
+                self.data
+                    .triples(None, Some(rdf::TYPE), Some(&class))?
+                    .into_iter()
+                    .map(|t| t.subject.into()),
+            );
+            current.extend(
+                self.data
+                    .triples(None, Some(rdfs::SUB_CLASS_OF), Some(&class))?
+                    .into_iter()
+                    .map(|t| t.subject.into()),
+            );
+        }
+        Ok(instances.into_vec())
+    }
+}
+
+fn new_result(
+    shape: &Shape,
+    constraint: &Constraint,
+    focus: &Term,
+    value: Option<Term>,
+) -> ValidationResult {
+    ValidationResult {
+        focus_node: focus.clone(),
+        path: shape.path.as_ref().map(|(node, _)| node.clone()),
+        value,
+        source_shape: shape.node.clone(),
+        source_constraint_component: constraint.component().into_owned(),
+        source_constraint: None,
+        severity: shape.severity.clone(),
+        messages: shape.messages.clone(),
+    }
+}
+
+/// Replaces the `{?var}` and `{$var}` placeholders of the message with the values of the solution
+fn substitute_variables(message: &Literal, solution: &QuerySolution) -> Literal {
+    let mut value = message.value().to_owned();
+    for (variable, term) in solution.iter() {
+        let replacement = match term {
+            Term::Literal(literal) => literal.value().to_owned(),
+            term => term.to_string(),
+        };
+        for prefix in ['?', '$'] {
+            value = value.replace(
+                &format!("{{{}{}}}", prefix, variable.as_str()),
+                &replacement,
+            );
+        }
+    }
+    if let Some(language) = message.language() {
+        Literal::new_language_tagged_literal_unchecked(value, language)
+    } else {
+        Literal::new_simple_literal(value)
+    }
+}
+
+/// How far from a focus node the validation reads the data graph
+#[derive(Clone, Copy, Default)]
+struct Reach {
+    /// The maximal number of triples between the focus node and a read triple, this one included
+    depth: usize,
+    /// If some triples are read from their object
+    inverse: bool,
+}
+
+impl Reach {
+    fn union(self, other: Self) -> Self {
+        Self {
+            depth: self.depth.max(other.depth),
+            inverse: self.inverse || other.inverse,
+        }
+    }
+
+    fn followed_by(self, other: Self) -> Self {
+        Self {
+            depth: self.depth + other.depth,
+            inverse: self.inverse || other.inverse,
+        }
+    }
+}
+
+/// The reach of the validation of a shape or `None` if it is unbounded
+fn shape_reach(shapes: &[Shape], id: usize, visiting: &mut HashSet<usize>) -> Option<Reach> {
+    if !visiting.insert(id) {
+        // Recursive shape
+        return None;
+    }
+    let shape = &shapes[id];
+    let values = if let Some((_, path)) = &shape.path {
+        path_reach(path)?
+    } else {
+        Reach::default()
+    };
+    let one_triple = Reach {
+        depth: 1,
+        inverse: false,
+    };
+    let mut reach = values;
+    for constraint in &shape.constraints {
+        reach = reach.union(match constraint {
+            // The types and the properties of the value nodes
+            Constraint::Class(_) | Constraint::Closed { .. } => values.followed_by(one_triple),
+            // The properties of the focus node
+            Constraint::Equals(_)
+            | Constraint::Disjoint(_)
+            | Constraint::LessThan(_)
+            | Constraint::LessThanOrEquals(_) => one_triple,
+            Constraint::Not(shape) | Constraint::Node(shape) | Constraint::Property(shape) => {
+                values.followed_by(shape_reach(shapes, *shape, visiting)?)
+            }
+            Constraint::And(shapes_ids)
+            | Constraint::Or(shapes_ids)
+            | Constraint::Xone(shapes_ids) => {
+                let mut shapes_reach = Reach::default();
+                for shape in shapes_ids {
+                    shapes_reach = shapes_reach.union(shape_reach(shapes, *shape, visiting)?);
+                }
+                values.followed_by(shapes_reach)
+            }
+            Constraint::QualifiedMinCount {
+                shape, siblings, ..
+            }
+            | Constraint::QualifiedMaxCount {
+                shape, siblings, ..
+            } => {
+                let mut shapes_reach = shape_reach(shapes, *shape, visiting)?;
+                for sibling in siblings {
+                    shapes_reach = shapes_reach.union(shape_reach(shapes, *sibling, visiting)?);
+                }
+                values.followed_by(shapes_reach)
+            }
+            // Arbitrary queries
+            Constraint::Sparql { .. } => return None,
+            _ => values,
+        });
+    }
+    visiting.remove(&id);
+    Some(reach)
+}
+
+fn path_reach(path: &Path) -> Option<Reach> {
+    Some(match path {
+        Path::Predicate(_) => Reach {
+            depth: 1,
+            inverse: false,
+        },
+        Path::Inverse(path) => Reach {
+            inverse: true,
+            ..path_reach(path)?
+        },
+        Path::Sequence(paths) => {
+            let mut reach = Reach::default();
+            for path in paths {
+                reach = reach.followed_by(path_reach(path)?);
+            }
+            reach
+        }
+        Path::Alternative(paths) => {
+            let mut reach = Reach::default();
+            for path in paths {
+                reach = reach.union(path_reach(path)?);
+            }
+            reach
+        }
+        Path::ZeroOrOne(path) => path_reach(path)?,
+        Path::ZeroOrMore(_) | Path::OneOrMore(_) => return None,
+    })
+}
+
+/// A list of terms without duplicates keeping the insertion order
+#[derive(Default)]
+struct UniqueTerms {
+    seen: HashSet<Term>,
+    terms: Vec<Term>,
+}
+
+impl UniqueTerms {
+    fn insert(&mut self, term: Term) -> bool {
+        if self.seen.insert(term.clone()) {
+            self.terms.push(term);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn extend(&mut self, terms: impl IntoIterator<Item = Term>) {
+        for term in terms {
+            self.insert(term);
+        }
+    }
+
+    fn into_vec(self) -> Vec<Term> {
+        self.terms
+    }
+}
+
+fn has_node_kind(term: &Term, kind: NamedNodeRef<'_>) -> bool {
+    match term {
+        Term::NamedNode(_) => [sh::IRI, sh::BLANK_NODE_OR_IRI, sh::IRI_OR_LITERAL].contains(&kind),
+        Term::BlankNode(_) => [
+            sh::BLANK_NODE,
+            sh::BLANK_NODE_OR_IRI,
+            sh::BLANK_NODE_OR_LITERAL,
+        ]
+        .contains(&kind),
+        Term::Literal(_) => {
+            [sh::LITERAL, sh::BLANK_NODE_OR_LITERAL, sh::IRI_OR_LITERAL].contains(&kind)
+        }
+        Term::Triple(_) => false,
+    }
+}
+
+/// Checks that the term is a well-formed literal of the datatype
+fn has_datatype(term: &Term, datatype: NamedNodeRef<'_>) -> bool {
+    let literal = if let Term::Literal(literal) = term {
+        literal
+    } else {
+        return false;
+    };
+    if literal.datatype() != datatype {
+        return false;
+    }
+    let value = literal.value();
+    match datatype {
+        xsd::BOOLEAN => matches!(value, "true" | "false" | "1" | "0"),
+        xsd::DECIMAL => Decimal::from_str(value).is_ok(),
+        xsd::INTEGER => {
+            let digits = value.strip_prefix(&['+', '-'][..]).unwrap_or(value);
+            !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+        }
+        xsd::LONG => value.parse::<i64>().is_ok(),
+        xsd::INT => value.parse::<i32>().is_ok(),
+        xsd::SHORT => value.parse::<i16>().is_ok(),
+        xsd::BYTE => value.parse::<i8>().is_ok(),
+        xsd::UNSIGNED_LONG => value.parse::<u64>().is_ok(),
+        xsd::UNSIGNED_INT => value.parse::<u32>().is_ok(),
+        xsd::UNSIGNED_SHORT => value.parse::<u16>().is_ok(),
+        xsd::UNSIGNED_BYTE => value.parse::<u8>().is_ok(),
+        xsd::NON_NEGATIVE_INTEGER => value.parse::<i128>().map_or(false, |v| v >= 0),
+        xsd::POSITIVE_INTEGER => value.parse::<i128>().map_or(false, |v| v > 0),
+        xsd::NON_POSITIVE_INTEGER => value.parse::<i128>().map_or(false, |v| v <= 0),
+        xsd::NEGATIVE_INTEGER => value.parse::<i128>().map_or(false, |v| v < 0),
+        xsd::FLOAT => Float::from_str(value).is_ok(),
+        xsd::DOUBLE => Double::from_str(value).is_ok(),
+        xsd::DATE_TIME => DateTime::from_str(value).is_ok(),
+        xsd::DATE_TIME_STAMP => DateTime::from_str(value).map_or(false, |v| v.timezone().is_some()),
+        xsd::DATE => Date::from_str(value).is_ok(),
+        xsd::TIME => Time::from_str(value).is_ok(),
+        xsd::G_YEAR_MONTH => GYearMonth::from_str(value).is_ok(),
+        xsd::G_YEAR => GYear::from_str(value).is_ok(),
+        xsd::G_MONTH_DAY => GMonthDay::from_str(value).is_ok(),
+        xsd::G_MONTH => GMonth::from_str(value).is_ok(),
+        xsd::G_DAY => GDay::from_str(value).is_ok(),
+        xsd::DURATION => Duration::from_str(value).is_ok(),
+        xsd::DAY_TIME_DURATION => DayTimeDuration::from_str(value).is_ok(),
+        xsd::YEAR_MONTH_DURATION => YearMonthDuration::from_str(value).is_ok(),
+        _ => true,
+    }
+}
+
+fn is_numeric(datatype: NamedNodeRef<'_>) -> bool {
+    matches!(
+        datatype,
+        xsd::INTEGER
+            | xsd::DECIMAL
+            | xsd::FLOAT
+            | xsd::DOUBLE
+            | xsd::LONG
+            | xsd::INT
+            | xsd::SHORT
+            | xsd::BYTE
+            | xsd::UNSIGNED_LONG
+            | xsd::UNSIGNED_INT
+            | xsd::UNSIGNED_SHORT
+            | xsd::UNSIGNED_BYTE
+            | xsd::NON_NEGATIVE_INTEGER
+            | xsd::POSITIVE_INTEGER
+            | xsd::NON_POSITIVE_INTEGER
+            | xsd::NEGATIVE_INTEGER
+    )
+}
+
+/// Compares two literals following the SPARQL `<` operator and returns `None` if they are not comparable
+fn compare(left: &Term, right: &Term) -> Option<Ordering> {
+    let (left, right) = match (left, right) {
+        (Term::Literal(left), Term::Literal(right)) => (left, right),
+        _ => return None,
+    };
+    let (left_type, right_type) = (left.datatype(), right.datatype());
+    if is_numeric(left_type) && is_numeric(right_type) {
+        return if [xsd::FLOAT, xsd::DOUBLE].contains(&left_type)
+            || [xsd::FLOAT, xsd::DOUBLE].contains(&right_type)
+        {
+            Double::from_str(left.value())
+                .ok()?
+                .partial_cmp(&Double::from_str(right.value()).ok()?)
+        } else {
+            Decimal::from_str(left.value())
+                .ok()?
+                .partial_cmp(&Decimal::from_str(right.value()).ok()?)
+        };
+    }
+    if left_type != right_type {
+        return None;
+    }
+    let (left, right) = (left.value(), right.value());
+    match left_type {
+        xsd::STRING => Some(left.cmp(right)),
+        xsd::BOOLEAN => {
+            let parse = |v: &str| matches!(v, "true" | "1");
+            Some(parse(left).cmp(&parse(right)))
+        }
+        xsd::DATE_TIME | xsd::DATE_TIME_STAMP => DateTime::from_str(left)
+            .ok()?
+            .partial_cmp(&DateTime::from_str(right).ok()?),
+        xsd::DATE => Date::from_str(left)
+            .ok()?
+            .partial_cmp(&Date::from_str(right).ok()?),
+        xsd::TIME => Time::from_str(left)
+            .ok()?
+            .partial_cmp(&Time::from_str(right).ok()?),
+        xsd::G_YEAR_MONTH => GYearMonth::from_str(left)
+            .ok()?
+            .partial_cmp(&GYearMonth::from_str(right).ok()?),
+        xsd::G_YEAR => GYear::from_str(left)
+            .ok()?
+            .partial_cmp(&GYear::from_str(right).ok()?),
+        xsd::G_MONTH_DAY => GMonthDay::from_str(left)
+            .ok()?
+            .partial_cmp(&GMonthDay::from_str(right).ok()?),
+        xsd::G_MONTH => GMonth::from_str(left)
+            .ok()?
+            .partial_cmp(&GMonth::from_str(right).ok()?),
+        xsd::G_DAY => GDay::from_str(left)
+            .ok()?
+            .partial_cmp(&GDay::from_str(right).ok()?),
+        xsd::DURATION | xsd::DAY_TIME_DURATION | xsd::YEAR_MONTH_DURATION => {
+            Duration::from_str(left)
+                .ok()?
+                .partial_cmp(&Duration::from_str(right).ok()?)
+        }
+        _ => None,
+    }
+}
+
+/// The string used by `sh:minLength`, `sh:maxLength` and `sh:pattern`
+fn string_value(term: &Term) -> Option<&str> {
+    match term {
+        Term::NamedNode(node) => Some(node.as_str()),
+        Term::Literal(literal) => Some(literal.value()),
+        Term::BlankNode(_) | Term::Triple(_) => None,
+    }
+}
+
+fn language(term: &Term) -> Option<&str> {
+    if let Term::Literal(literal) = term {
+        literal.language()
+    } else {
+        None
+    }
+}
+
+/// The SPARQL `langMatches` basic filtering, `range` being lowercase
+fn language_matches(language: &str, range: &str) -> bool {
+    if range == "*" {
+        return !language.is_empty();
+    }
+    let language = language.to_ascii_lowercase();
+    language == range
+        || language.starts_with(range) && language.as_bytes().get(range.len()) == Some(&b'-')
+}
@@ -0,0 +1,271 @@
+//! The [SHACL](https://www.w3.org/TR/shacl/) vocabulary
+
+use crate::model::NamedNodeRef;
+
+/// The class of node shapes.
+pub const NODE_SHAPE: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#NodeShape");
+/// The class of property shapes.
+pub const PROPERTY_SHAPE: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#PropertyShape");
+/// Links a shape to individual nodes.
+pub const TARGET_NODE: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#targetNode");
+/// Links a shape to a class, indicating that all instances of the class must conform to the shape.
+pub const TARGET_CLASS: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#targetClass");
+/// Links a shape to a property, indicating that all subjects of triples with the property must conform to the shape.
+pub const TARGET_SUBJECTS_OF: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#targetSubjectsOf");
+/// Links a shape to a property, indicating that all objects of triples with the property must conform to the shape.
+pub const TARGET_OBJECTS_OF: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#targetObjectsOf");
+/// Specifies the property path of a property shape.
+pub const PATH: NamedNodeRef<'_> = NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#path");
+/// The (single) value of this property represents an inverse path.
+pub const INVERSE_PATH: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#inversePath");
+/// The (single) value of this property must be a list of path elements, representing the elements of alternative paths.
+pub const ALTERNATIVE_PATH: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#alternativePath");
+/// The (single) value of this property represents a path that is matched zero or more times.
+pub const ZERO_OR_MORE_PATH: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#zeroOrMorePath");
+/// The (single) value of this property represents a path that is matched one or more times.
+pub const ONE_OR_MORE_PATH: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#oneOrMorePath");
+/// The (single) value of this property represents a path that is matched zero or one times.
+pub const ZERO_OR_ONE_PATH: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#zeroOrOnePath");
+/// If set to true then all nodes conform to this shape.
+pub const DEACTIVATED: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#deactivated");
+/// Defines the severity that validation results produced by a shape must have.
+pub const SEVERITY: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#severity");
+/// A human-readable message explaining the cause of the result.
+pub const MESSAGE: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#message");
+/// The severity for a violation validation result.
+pub const VIOLATION: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#Violation");
+pub const CLASS: NamedNodeRef<'_> = NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#class");
+/// The datatype that all value nodes must have.
+pub const DATATYPE: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#datatype");
+/// The node kind that all value nodes must have.
+pub const NODE_KIND: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#nodeKind");
+/// The node kind of all IRIs.
+pub const IRI: NamedNodeRef<'_> = NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#IRI");
+/// The node kind of all blank nodes.
+pub const BLANK_NODE: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#BlankNode");
+/// The node kind of all literals.
+pub const LITERAL: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#Literal");
+/// The node kind of all blank nodes or IRIs.
+pub const BLANK_NODE_OR_IRI: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#BlankNodeOrIRI");
+/// The node kind of all blank nodes or literals.
+pub const BLANK_NODE_OR_LITERAL: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#BlankNodeOrLiteral");
+/// The node kind of all IRIs or literals.
+pub const IRI_OR_LITERAL: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#IRIOrLiteral");
+/// The minimum number of value nodes.
+pub const MIN_COUNT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#minCount");
+/// The maximum number of value nodes.
+pub const MAX_COUNT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#maxCount");
+/// The minimum exclusive value that the value nodes must have.
+pub const MIN_EXCLUSIVE: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#minExclusive");
+/// The minimum inclusive value that the value nodes must have.
+pub const MIN_INCLUSIVE: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#minInclusive");
+/// The maximum exclusive value that the value nodes must have.
+pub const MAX_EXCLUSIVE: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#maxExclusive");
+/// The maximum inclusive value that the value nodes must have.
+pub const MAX_INCLUSIVE: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#maxInclusive");
+/// The minimum length of the string representation of the value nodes.
+pub const MIN_LENGTH: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#minLength");
+/// The maximum length of the string representation of the value nodes.
+pub const MAX_LENGTH: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#maxLength");
+/// A regular expression that all value nodes must match.
+pub const PATTERN: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#pattern");
+/// An optional flag to be used with regular expression pattern matching.
+pub const FLAGS: NamedNodeRef<'_> = NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#flags");
+/// Specifies a list of language tags that all value nodes must have.
+pub const LANGUAGE_IN: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#languageIn");
+/// Specifies whether all value nodes must have unique language tags.
+pub const UNIQUE_LANG: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#uniqueLang");
+/// Specifies a property that must have the same values as the value nodes.
+pub const EQUALS: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#equals");
+/// Specifies a property whose values must be disjoint with the value nodes.
+pub const DISJOINT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#disjoint");
+/// Specifies a property that must have greater values than the value nodes.
+pub const LESS_THAN: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#lessThan");
+/// Specifies a property that must have greater or equal values than the value nodes.
+pub const LESS_THAN_OR_EQUALS: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#lessThanOrEquals");
+/// Specifies a shape that the value nodes must not conform to.
+pub const NOT: NamedNodeRef<'_> = NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#not");
+/// A list of shapes that the value nodes must all conform to.
+pub const AND: NamedNodeRef<'_> = NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#and");
+/// A list of shapes that the value nodes must conform to at least one of.
+pub const OR: NamedNodeRef<'_> = NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#or");
+/// A list of shapes that the value nodes must conform to exactly one of.
+pub const XONE: NamedNodeRef<'_> = NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#xone");
+/// Specifies a node shape that all value nodes must conform to.
+pub const NODE: NamedNodeRef<'_> = NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#node");
+/// Links a shape to its property shapes.
+pub const PROPERTY: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#property");
+/// The shape that a specified number of values must conform to.
+pub const QUALIFIED_VALUE_SHAPE: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#qualifiedValueShape");
+/// The minimum number of value nodes that conform to the qualified value shape.
+pub const QUALIFIED_MIN_COUNT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#qualifiedMinCount");
+/// The maximum number of value nodes that can conform to the qualified value shape.
+pub const QUALIFIED_MAX_COUNT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#qualifiedMaxCount");
+/// Whether the value nodes conforming to the qualified value shape must not conform to the sibling qualified value shapes.
+pub const QUALIFIED_VALUE_SHAPES_DISJOINT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#qualifiedValueShapesDisjoint");
+/// If set to true then the value nodes may only have the properties of the property shapes.
+pub const CLOSED: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#closed");
+/// An optional list of properties that are also permitted in addition to those explicitly enumerated via sh:property.
+pub const IGNORED_PROPERTIES: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#ignoredProperties");
+/// Specifies a value that must be among the value nodes.
+pub const HAS_VALUE: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#hasValue");
+/// Specifies a list of allowed values so that each value node must be among the members of the given list.
+pub const IN: NamedNodeRef<'_> = NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#in");
+/// The class of SHACL validation reports.
+pub const VALIDATION_REPORT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#ValidationReport");
+/// The class of validation results.
+pub const VALIDATION_RESULT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#ValidationResult");
+/// True if the validation did not produce any validation results, and false otherwise.
+pub const CONFORMS: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#conforms");
+/// The validation results contained in a validation report.
+pub const RESULT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#result");
+/// The focus node that was validated when the result was produced.
+pub const FOCUS_NODE: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#focusNode");
+/// The path of a validation result, based on the path of the validated property shape.
+pub const RESULT_PATH: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#resultPath");
+/// An RDF node that has caused the result.
+pub const VALUE: NamedNodeRef<'_> = NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#value");
+/// The shape that was validated when the result was produced.
+pub const SOURCE_SHAPE: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#sourceShape");
+/// The constraint component that is the source of the result.
+pub const SOURCE_CONSTRAINT_COMPONENT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#sourceConstraintComponent");
+/// The severity of the result.
+pub const RESULT_SEVERITY: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#resultSeverity");
+/// Human-readable messages explaining the cause of the result.
+pub const RESULT_MESSAGE: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#resultMessage");
+/// Links a shape with SPARQL constraints.
+pub const SPARQL: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#sparql");
+/// The SPARQL SELECT query to execute.
+pub const SELECT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#select");
+/// The prefixes that shall be applied before parsing the associated SPARQL query.
+pub const PREFIXES: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#prefixes");
+/// Links a resource with its namespace prefix declarations.
+pub const DECLARE: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#declare");
+/// The prefix of a prefix declaration.
+pub const PREFIX: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#prefix");
+/// The namespace associated with a prefix in a prefix declaration.
+pub const NAMESPACE: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#namespace");
+/// The constraint that was validated when the result was produced.
+pub const SOURCE_CONSTRAINT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#sourceConstraint");
+
+// Constraint components
+pub const CLASS_CONSTRAINT_COMPONENT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#ClassConstraintComponent");
+pub const DATATYPE_CONSTRAINT_COMPONENT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#DatatypeConstraintComponent");
+pub const NODE_KIND_CONSTRAINT_COMPONENT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#NodeKindConstraintComponent");
+pub const MIN_COUNT_CONSTRAINT_COMPONENT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#MinCountConstraintComponent");
+pub const MAX_COUNT_CONSTRAINT_COMPONENT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#MaxCountConstraintComponent");
+pub const MIN_EXCLUSIVE_CONSTRAINT_COMPONENT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#MinExclusiveConstraintComponent");
+pub const MIN_INCLUSIVE_CONSTRAINT_COMPONENT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#MinInclusiveConstraintComponent");
+pub const MAX_EXCLUSIVE_CONSTRAINT_COMPONENT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#MaxExclusiveConstraintComponent");
+pub const MAX_INCLUSIVE_CONSTRAINT_COMPONENT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#MaxInclusiveConstraintComponent");
+pub const MIN_LENGTH_CONSTRAINT_COMPONENT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#MinLengthConstraintComponent");
+pub const MAX_LENGTH_CONSTRAINT_COMPONENT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#MaxLengthConstraintComponent");
+pub const PATTERN_CONSTRAINT_COMPONENT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#PatternConstraintComponent");
+pub const LANGUAGE_IN_CONSTRAINT_COMPONENT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#LanguageInConstraintComponent");
+pub const UNIQUE_LANG_CONSTRAINT_COMPONENT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#UniqueLangConstraintComponent");
+pub const EQUALS_CONSTRAINT_COMPONENT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#EqualsConstraintComponent");
+pub const DISJOINT_CONSTRAINT_COMPONENT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#DisjointConstraintComponent");
+pub const LESS_THAN_CONSTRAINT_COMPONENT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#LessThanConstraintComponent");
+pub const LESS_THAN_OR_EQUALS_CONSTRAINT_COMPONENT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#LessThanOrEqualsConstraintComponent");
+pub const NOT_CONSTRAINT_COMPONENT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#NotConstraintComponent");
+pub const AND_CONSTRAINT_COMPONENT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#AndConstraintComponent");
+pub const OR_CONSTRAINT_COMPONENT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#OrConstraintComponent");
+pub const XONE_CONSTRAINT_COMPONENT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#XoneConstraintComponent");
+pub const NODE_CONSTRAINT_COMPONENT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#NodeConstraintComponent");
+pub const QUALIFIED_MIN_COUNT_CONSTRAINT_COMPONENT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#QualifiedMinCountConstraintComponent");
+pub const QUALIFIED_MAX_COUNT_CONSTRAINT_COMPONENT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#QualifiedMaxCountConstraintComponent");
+pub const CLOSED_CONSTRAINT_COMPONENT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#ClosedConstraintComponent");
+pub const HAS_VALUE_CONSTRAINT_COMPONENT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#HasValueConstraintComponent");
+pub const IN_CONSTRAINT_COMPONENT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#InConstraintComponent");
+pub const SPARQL_CONSTRAINT_COMPONENT: NamedNodeRef<'_> =
+    NamedNodeRef::new_unchecked("http://www.w3.org/ns/shacl#SPARQLConstraintComponent");
@@ -28,7 +28,7 @@ use std::str::FromStr;
 /// ```
 #[derive(Eq, PartialEq, Debug, Clone, Hash)]
 pub struct Query {
-    pub(super) inner: spargebra::Query,
+    pub(crate) inner: spargebra::Query,
     pub(super) dataset: QueryDataset,
 }
 
@@ -0,0 +1,278 @@
+//! A LRU cache of query results.
+
+use crate::model::{Subject, Term, Triple};
+use crate::sparql::algebra::{Query, QueryDataset};
+use crate::sparql::error::EvaluationError;
+use crate::sparql::limits::ResourceLimits;
+use crate::sparql::model::{QueryResults, QuerySolutionIter, QueryTripleIter, Variable};
+use crate::sparql::QueryOptions;
+use std::collections::{BTreeMap, HashMap};
+use std::iter::once;
+use std::mem::size_of;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+/// Keeps in memory the results of the last evaluated queries.
+///
+/// The results are identified by the query, its dataset, its entailment regime and the generation of the store they have been computed on
+/// so results computed before a write are never returned after it.
+pub struct QueryCache {
+    capacity: usize,
+    max_memory_size: usize,
+    state: Mutex<CacheState>,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<CacheKey, CacheEntry>,
+    /// The entries keys by last use
+    recency: BTreeMap<u64, CacheKey>,
+    clock: u64,
+    /// The sum of the memory sizes of the entries
+    memory_size: usize,
+}
+
+struct CacheEntry {
+    results: CachedResults,
+    memory_size: usize,
+    last_use: u64,
+}
+
+#[derive(Eq, PartialEq, Hash, Clone)]
+struct CacheKey {
+    /// The serialization of the parsed query, that does not depend on its formatting or its prefixes
+    query: String,
+    dataset: QueryDataset,
+    rdfs_entailment: bool,
+    generation: u64,
+}
+
+impl QueryCache {
+    pub fn new(capacity: usize, max_memory_size: usize) -> Self {
+        Self {
+            capacity,
+            max_memory_size,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Returns the cached results of the query for the given store generation
+    /// or computes them with `evaluate` and caches them.
+    ///
+    /// The result limits of the options are applied to the cached results.
+    /// The results that do not fit in the cache memory or that contain an error are returned without being cached.
+    pub fn get_or_evaluate(
+        &self,
+        query: Query,
+        options: QueryOptions,
+        generation: u64,
+        evaluate: impl FnOnce(Query, QueryOptions) -> Result<QueryResults, EvaluationError>,
+    ) -> Result<QueryResults, EvaluationError> {
+        let key = CacheKey {
+            query: query.to_string(),
+            dataset: query.dataset.clone(),
+            rdfs_entailment: options.rdfs_entailment,
+            generation,
+        };
+        let limits = options.limits();
+        if let Some(results) = self.get(&key) {
+            return Ok(results.to_results(&limits));
+        }
+        if self.capacity == 0 {
+            return evaluate(query, options);
+        }
+        // The limits have already been applied during the evaluation
+        match CachedResults::read(evaluate(query, options)?, self.max_memory_size) {
+            Ok((results, memory_size)) => {
+                self.insert(key, results.clone(), memory_size);
+                Ok(results.to_results(&ResourceLimits::default()))
+            }
+            Err(results) => Ok(results),
+        }
+    }
+
+    fn get(&self, key: &CacheKey) -> Option<CachedResults> {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        let entry = state.entries.get_mut(key)?;
+        state.recency.remove(&entry.last_use);
+        state.clock += 1;
+        entry.last_use = state.clock;
+        state.recency.insert(entry.last_use, key.clone());
+        Some(entry.results.clone())
+    }
+
+    fn insert(&self, key: CacheKey, results: CachedResults, memory_size: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.clock += 1;
+        let last_use = state.clock;
+        state.recency.insert(last_use, key.clone());
+        state.memory_size += memory_size;
+        if let Some(previous) = state.entries.insert(
+            key,
+            CacheEntry {
+                results,
+                memory_size,
+                last_use,
+            },
+        ) {
+            // An other thread has evaluated the same query at the same time
+            state.recency.remove(&previous.last_use);
+            state.memory_size -= previous.memory_size;
+        }
+        while state.entries.len() > self.capacity || state.memory_size > self.max_memory_size {
+            let oldest = match state.recency.keys().next() {
+                Some(oldest) => *oldest,
+                None => break,
+            };
+            if let Some(key) = state.recency.remove(&oldest) {
+                if let Some(entry) = state.entries.remove(&key) {
+                    state.memory_size -= entry.memory_size;
+                }
+            }
+        }
+    }
+}
+
+/// Query results fully loaded in memory.
+#[derive(Clone)]
+enum CachedResults {
+    Solutions {
+        variables: Vec<Variable>,
+        rows: Arc<Vec<Vec<Option<Term>>>>,
+    },
+    Graph(Arc<Vec<Triple>>),
+    Boolean(bool),
+}
+
+impl CachedResults {
+    /// Loads the results in memory and returns them with their memory size.
+    ///
+    /// If they contain an error or are bigger than `max_memory_size`,
+    /// returns instead the same results by chaining the ones already loaded with the not read yet ones.
+    fn read(results: QueryResults, max_memory_size: usize) -> Result<(Self, usize), QueryResults> {
+        match results {
+            QueryResults::Solutions(mut solutions) => {
+                let variables = solutions.variables().to_vec();
+                let mut rows = Vec::new();
+                let mut memory_size = 0;
+                for solution in &mut solutions {
+                    let row = match solution {
+                        Ok(solution) => solution.values().map(|v| v.cloned()).collect::<Vec<_>>(),
+                        Err(e) => {
+                            return Err(QueryResults::Solutions(QuerySolutionIter::new(
+                                Rc::new(variables),
+                                Box::new(rows.into_iter().map(Ok).chain(once(Err(e))).chain(
+                                    solutions.map(|solution| {
+                                        Ok(solution?.values().map(|v| v.cloned()).collect())
+                                    }),
+                                )),
+                            )))
+                        }
+                    };
+                    memory_size += size_of::<Vec<Option<Term>>>()
+                        + row
+                            .iter()
+                            .map(|v| v.as_ref().map_or(size_of::<Option<Term>>(), term_size))
+                            .sum::<usize>();
+                    rows.push(row);
+                    if memory_size > max_memory_size {
+                        return Err(QueryResults::Solutions(QuerySolutionIter::new(
+                            Rc::new(variables),
+                            Box::new(rows.into_iter().map(Ok).chain(solutions.map(|solution| {
+                                Ok(solution?.values().map(|v| v.cloned()).collect())
+                            }))),
+                        )));
+                    }
+                }
+                Ok((
+                    Self::Solutions {
+                        variables,
+                        rows: Arc::new(rows),
+                    },
+                    memory_size,
+                ))
+            }
+            QueryResults::Graph(mut triples) => {
+                let mut loaded = Vec::new();
+                let mut memory_size = 0;
+                for triple in &mut triples {
+                    let triple = match triple {
+                        Ok(triple) => triple,
+                        Err(e) => {
+                            return Err(QueryResults::Graph(QueryTripleIter {
+                                iter: Box::new(
+                                    loaded
+                                        .into_iter()
+                                        .map(Ok)
+                                        .chain(once(Err(e)))
+                                        .chain(triples),
+                                ),
+                            }))
+                        }
+                    };
+                    memory_size += triple_size(&triple);
+                    loaded.push(triple);
+                    if memory_size > max_memory_size {
+                        return Err(QueryResults::Graph(QueryTripleIter {
+                            iter: Box::new(loaded.into_iter().map(Ok).chain(triples)),
+                        }));
+                    }
+                }
+                Ok((Self::Graph(Arc::new(loaded)), memory_size))
+            }
+            QueryResults::Boolean(value) => Ok((Self::Boolean(value), 0)),
+        }
+    }
+
+    fn to_results(&self, limits: &ResourceLimits) -> QueryResults {
+        match self {
+            Self::Solutions { variables, rows } => {
+                let rows = rows.clone();
+                QueryResults::Solutions(QuerySolutionIter::new(
+                    Rc::new(variables.clone()),
+                    Box::new(
+                        limits.limit_results((0..rows.len()).map(move |i| Ok(rows[i].clone()))),
+                    ),
+                ))
+            }
+            Self::Graph(triples) => {
+                let triples = triples.clone();
+                QueryResults::Graph(QueryTripleIter {
+                    iter: Box::new(
+                        limits
+                            .limit_results((0..triples.len()).map(move |i| Ok(triples[i].clone()))),
+                    ),
+                })
+            }
+            Self::Boolean(value) => QueryResults::Boolean(*value),
+        }
+    }
+}
+
+/// An estimation of the memory used by a term
+fn term_size(term: &Term) -> usize {
+    size_of::<Term>()
+        + match term {
+            Term::NamedNode(node) => node.as_str().len(),
+            Term::BlankNode(node) => node.as_str().len(),
+            Term::Literal(literal) => {
+                literal.value().len()
+                    + literal.datatype().as_str().len()
+                    + literal.language().map_or(0, str::len)
+            }
+            Term::Triple(triple) => triple_size(triple),
+        }
+}
+
+fn triple_size(triple: &Triple) -> usize {
+    size_of::<Triple>()
+        + match &triple.subject {
+            Subject::NamedNode(node) => node.as_str().len(),
+            Subject::BlankNode(node) => node.as_str().len(),
+            Subject::Triple(triple) => triple_size(triple),
+        }
+        + triple.predicate.as_str().len()
+        + term_size(&triple.object)
+        - size_of::<Term>()
+}
@@ -0,0 +1,118 @@
+use crate::sparql::EvaluationError;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A token allowing to cancel a running SPARQL evaluation from another thread.
+///
+/// It should be given to the evaluation using [`QueryOptions::with_cancellation_token`](super::QueryOptions::with_cancellation_token).
+/// The evaluation then fails with [`EvaluationError::Cancelled`] as soon as [`cancel`](CancellationToken::cancel) is called.
+///
+/// ```
+/// use oxigraph::store::Store;
+/// use oxigraph::sparql::{CancellationToken, EvaluationError, QueryOptions, QueryResults};
+///
+/// let store = Store::new()?;
+/// let token = CancellationToken::new();
+/// if let QueryResults::Solutions(mut solutions) = store.query_opt(
+///     "SELECT * WHERE { ?s ?p ?o }",
+///     QueryOptions::default().with_cancellation_token(token.clone()),
+/// )? {
+///     token.cancel();
+///     assert!(matches!(solutions.next(), Some(Err(EvaluationError::Cancelled))));
+/// }
+/// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+/// ```
+#[derive(Clone, Default, Debug)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Builds a new token that is not cancelled yet.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels all the evaluations using this token.
+    #[inline]
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns if [`cancel`](CancellationToken::cancel) has been called.
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Checks if an evaluation should be stopped because of a cancellation or a timeout.
+#[derive(Clone, Default)]
+pub struct Interrupter {
+    token: Option<CancellationToken>,
+    deadline: Option<Instant>,
+}
+
+impl Interrupter {
+    pub fn new(token: Option<CancellationToken>, timeout: Option<Duration>) -> Self {
+        Self {
+            token,
+            deadline: timeout.and_then(|timeout| Instant::now().checked_add(timeout)),
+        }
+    }
+
+    pub fn check(&self) -> Result<(), EvaluationError> {
+        if let Some(token) = &self.token {
+            if token.is_cancelled() {
+                return Err(EvaluationError::Cancelled);
+            }
+        }
+        if let Some(deadline) = self.deadline {
+            if Instant::now() >= deadline {
+                return Err(EvaluationError::Timeout);
+            }
+        }
+        Ok(())
+    }
+
+    /// Wraps an iterator in order to stop it when the evaluation is interrupted.
+    pub fn wrap<T, I: Iterator<Item = Result<T, EvaluationError>>>(
+        &self,
+        iter: I,
+    ) -> InterruptibleIterator<I> {
+        InterruptibleIterator {
+            iter,
+            interrupter: self.clone(),
+            interrupted: false,
+        }
+    }
+}
+
+pub struct InterruptibleIterator<I> {
+    iter: I,
+    interrupter: Interrupter,
+    interrupted: bool,
+}
+
+impl<T, I: Iterator<Item = Result<T, EvaluationError>>> Iterator for InterruptibleIterator<I> {
+    type Item = Result<T, EvaluationError>;
+
+    fn next(&mut self) -> Option<Result<T, EvaluationError>> {
+        if self.interrupted {
+            return None;
+        }
+        if let Err(error) = self.interrupter.check() {
+            // We only return the error once to not continue the evaluation
+            self.interrupted = true;
+            return Some(Err(error));
+        }
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (_, max) = self.iter.size_hint();
+        (0, max.and_then(|max| max.checked_add(1)))
+    }
+}
@@ -1,18 +1,45 @@
-use crate::model::TermRef;
+use crate::model::geo::Rect;
+use crate::model::{NamedNodeRef, Term, TermRef, TripleRef};
 use crate::sparql::algebra::QueryDataset;
 use crate::sparql::EvaluationError;
-use crate::storage::numeric_encoder::{insert_term, EncodedQuad, EncodedTerm, StrHash, StrLookup};
-use crate::storage::StorageReader;
-use std::cell::RefCell;
+use crate::storage::numeric_encoder::{
+    insert_term, Decoder, EncodedQuad, EncodedTerm, StrHash, StrLookup,
+};
+use crate::storage::statistics::Statistics;
+use crate::storage::text::{TextIndexOptions, TextToken};
+use crate::storage::{SharedStorageReader, StorageReader};
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::convert::Infallible;
 use std::iter::empty;
+use std::sync::{Arc, Mutex};
 
 pub struct DatasetView {
     reader: StorageReader,
-    extra: RefCell<HashMap<StrHash, String>>,
+    extra: Arc<Mutex<HashMap<StrHash, String>>>,
     dataset: EncodedDatasetSpec,
+    memory_graph: Option<Arc<MemoryGraph>>,
+}
+
+/// A [`DatasetView`] that could be sent to another thread.
+///
+/// The strings built during the evaluation are shared with the original view.
+pub struct SharedDatasetView {
+    reader: SharedStorageReader,
+    extra: Arc<Mutex<HashMap<StrHash, String>>>,
+    dataset: EncodedDatasetSpec,
+    memory_graph: Option<Arc<MemoryGraph>>,
+}
+
+impl SharedDatasetView {
+    pub fn into_dataset_view(self) -> DatasetView {
+        DatasetView {
+            reader: self.reader.into_reader(),
+            extra: self.extra,
+            dataset: self.dataset,
+            memory_graph: self.memory_graph,
+        }
+    }
 }
 
 impl DatasetView {
@@ -27,8 +54,125 @@ impl DatasetView {
         };
         Self {
             reader,
-            extra: RefCell::new(HashMap::default()),
+            extra: Arc::new(Mutex::new(HashMap::default())),
             dataset,
+            memory_graph: None,
+        }
+    }
+
+    /// Replaces the content of the `graph_name` named graph by the given triples, kept in memory.
+    ///
+    /// The graph is only read by the patterns explicitly using its name like `GRAPH <graph_name> { ... }`.
+    pub fn with_memory_graph<'a>(
+        mut self,
+        graph_name: NamedNodeRef<'_>,
+        triples: impl IntoIterator<Item = TripleRef<'a>>,
+    ) -> Self {
+        let mut graph = MemoryGraph {
+            name: self.encode_term(graph_name),
+            quads: Vec::new(),
+            by_subject: HashMap::new(),
+            by_object: HashMap::new(),
+        };
+        for triple in triples {
+            graph.insert(
+                self.encode_term(triple.subject),
+                self.encode_term(triple.predicate),
+                self.encode_term(triple.object),
+            );
+        }
+        self.memory_graph = Some(Arc::new(graph));
+        self
+    }
+
+    /// Makes available strings that are not in the store, like the ones returned by [`extra_strings`](Self::extra_strings).
+    pub fn with_extra_strings(self, strings: HashMap<StrHash, String>) -> Self {
+        self.extra.lock().unwrap().extend(strings);
+        self
+    }
+
+    /// Returns the strings used by the terms encoded with this view that are not in the store.
+    pub fn extra_strings(&self) -> HashMap<StrHash, String> {
+        self.extra.lock().unwrap().clone()
+    }
+
+    /// Returns a view on the same snapshot that could be sent to another thread.
+    ///
+    /// Returns `None` if the view is built on top of a running transaction.
+    pub fn share(&self) -> Option<SharedDatasetView> {
+        Some(SharedDatasetView {
+            reader: self.reader.share()?,
+            extra: self.extra.clone(),
+            dataset: self.dataset.clone(),
+            memory_graph: self.memory_graph.clone(),
+        })
+    }
+
+    /// The cardinality statistics of the underlying store if they have been computed
+    pub fn statistics(&self) -> Option<Arc<Statistics>> {
+        self.reader.statistics()
+    }
+
+    /// The configuration of the full-text index of the underlying store if it is enabled
+    pub fn text_index(&self) -> Option<Arc<TextIndexOptions>> {
+        self.reader.text_index()
+    }
+
+    /// The literals of the underlying store containing all the given tokens
+    pub fn text_search(&self, tokens: &[TextToken]) -> Result<Vec<EncodedTerm>, EvaluationError> {
+        Ok(self.reader.text_search(tokens)?)
+    }
+
+    /// Checks if the term is a literal containing all the given tokens
+    pub fn text_matches(
+        &self,
+        term: &EncodedTerm,
+        tokens: &[TextToken],
+    ) -> Result<bool, EvaluationError> {
+        let options = if let Some(options) = self.text_index() {
+            options
+        } else {
+            return Ok(false);
+        };
+        if let Term::Literal(literal) = self.decode_term(term)? {
+            if literal.is_plain() {
+                let literal_tokens = options
+                    .tokenizer(literal.language())
+                    .tokenize(literal.value());
+                return Ok(tokens
+                    .iter()
+                    .all(|token| literal_tokens.iter().any(|t| token.matches(t))));
+            }
+        }
+        Ok(false)
+    }
+
+    /// If the underlying store has a spatial index
+    pub fn is_geo_index_enabled(&self) -> bool {
+        self.reader.is_geo_index_enabled()
+    }
+
+    /// The WKT literals of the underlying store whose bounding box might intersect the given one
+    pub fn geo_search(&self, rect: &Rect) -> Result<Vec<EncodedTerm>, EvaluationError> {
+        Ok(self.reader.geo_search(rect)?)
+    }
+
+    /// Returns if [`encoded_quads_for_pattern`](Self::encoded_quads_for_pattern) returns the quads of this graph
+    /// in the order of the store index used for the pattern.
+    pub fn is_graph_sorted(&self, graph_name: &EncodedTerm) -> bool {
+        if self
+            .memory_graph
+            .as_ref()
+            .map_or(false, |graph| graph.name == *graph_name)
+        {
+            false
+        } else if graph_name.is_default_graph() {
+            self.dataset
+                .default
+                .as_ref()
+                .map_or(false, |graphs| graphs.len() == 1)
+        } else {
+            true
         }
     }
 
@@ -53,6 +197,16 @@ impl DatasetView {
         graph_name: Option<&EncodedTerm>,
     ) -> Box<dyn Iterator<Item = Result<EncodedQuad, EvaluationError>>> {
         if let Some(graph_name) = graph_name {
+            if let Some(memory_graph) = &self.memory_graph {
+                if memory_graph.name == *graph_name {
+                    return Box::new(
+                        memory_graph
+                            .quads_for_pattern(subject, predicate, object)
+                            .into_iter()
+                            .map(Ok),
+                    );
+                }
+            }
             if graph_name.is_default_graph() {
                 if let Some(default_graph_graphs) = &self.dataset.default {
                     if default_graph_graphs.len() == 1 {
@@ -150,7 +304,7 @@ impl DatasetView {
     }
 
     pub fn insert_str(&self, key: &StrHash, value: &str) {
-        if let Entry::Vacant(e) = self.extra.borrow_mut().entry(*key) {
+        if let Entry::Vacant(e) = self.extra.lock().unwrap().entry(*key) {
             if !matches!(self.reader.contains_str(key), Ok(true)) {
                 e.insert(value.to_owned());
             }
@@ -162,7 +316,7 @@ impl StrLookup for DatasetView {
     type Error = EvaluationError;
 
     fn get_str(&self, key: &StrHash) -> Result<Option<String>, EvaluationError> {
-        Ok(if let Some(value) = self.extra.borrow().get(key) {
+        Ok(if let Some(value) = self.extra.lock().unwrap().get(key) {
             Some(value.clone())
         } else {
             self.reader.get_str(key)?
@@ -170,10 +324,68 @@ impl StrLookup for DatasetView {
     }
 
     fn contains_str(&self, key: &StrHash) -> Result<bool, EvaluationError> {
-        Ok(self.extra.borrow().contains_key(key) || self.reader.contains_str(key)?)
+        Ok(self.extra.lock().unwrap().contains_key(key) || self.reader.contains_str(key)?)
+    }
+}
+
+/// A named graph kept in memory, indexed by subject and object
+struct MemoryGraph {
+    name: EncodedTerm,
+    quads: Vec<EncodedQuad>,
+    by_subject: HashMap<EncodedTerm, Vec<usize>>,
+    by_object: HashMap<EncodedTerm, Vec<usize>>,
+}
+
+impl MemoryGraph {
+    fn insert(&mut self, subject: EncodedTerm, predicate: EncodedTerm, object: EncodedTerm) {
+        let id = self.quads.len();
+        self.by_subject.entry(subject.clone()).or_default().push(id);
+        self.by_object.entry(object.clone()).or_default().push(id);
+        self.quads.push(EncodedQuad::new(
+            subject,
+            predicate,
+            object,
+            self.name.clone(),
+        ));
+    }
+
+    fn quads_for_pattern(
+        &self,
+        subject: Option<&EncodedTerm>,
+        predicate: Option<&EncodedTerm>,
+        object: Option<&EncodedTerm>,
+    ) -> Vec<EncodedQuad> {
+        let candidates: Box<dyn Iterator<Item = &EncodedQuad>> = if let Some(subject) = subject {
+            Box::new(self.indexed(&self.by_subject, subject))
+        } else if let Some(object) = object {
+            Box::new(self.indexed(&self.by_object, object))
+        } else {
+            Box::new(self.quads.iter())
+        };
+        candidates
+            .filter(|quad| {
+                subject.map_or(true, |s| quad.subject == *s)
+                    && predicate.map_or(true, |p| quad.predicate == *p)
+                    && object.map_or(true, |o| quad.object == *o)
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn indexed<'a>(
+        &'a self,
+        index: &'a HashMap<EncodedTerm, Vec<usize>>,
+        key: &EncodedTerm,
+    ) -> impl Iterator<Item = &'a EncodedQuad> + 'a {
+        index
+            .get(key)
+            .into_iter()
+            .flatten()
+            .map(move |id| &self.quads[*id])
     }
 }
 
+#[derive(Clone)]
 struct EncodedDatasetSpec {
     default: Option<Vec<EncodedTerm>>,
     named: Option<Vec<EncodedTerm>>,
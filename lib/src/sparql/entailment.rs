@@ -0,0 +1,276 @@
+//! Query rewriting implementing the [RDFS entailment regime](https://www.w3.org/TR/sparql11-entailment/#RDFSEntRegime).
+//!
+//! Each triple pattern of a basic graph pattern is replaced by a pattern returning the triples entailed
+//! by the `rdfs:subClassOf`, `rdfs:subPropertyOf`, `rdfs:domain` and `rdfs:range` statements of the queried graph.
+//! The schema is read by property paths at evaluation time so the rewritten plans stay valid after writes.
+//!
+//! The axiomatic triples, the `rdfs:Resource` typing of all terms and the reflexivity of `rdfs:subClassOf`
+//! and `rdfs:subPropertyOf` are not entailed.
+
+use crate::model::vocab::{rdf, rdfs};
+use crate::model::NamedNodeRef;
+use rand::random;
+use spargebra::algebra::*;
+use spargebra::term::*;
+use std::collections::HashMap;
+
+/// Rewrites the basic graph pattern into a pattern returning its solutions under the RDFS entailment regime
+pub fn rewrite_bgp(patterns: &[TriplePattern]) -> GraphPattern {
+    let mut blank_nodes = HashMap::new();
+    patterns
+        .iter()
+        .map(|pattern| {
+            let pattern = TriplePattern {
+                subject: blank_node_to_variable(&pattern.subject, &mut blank_nodes),
+                predicate: pattern.predicate.clone(),
+                object: blank_node_to_variable(&pattern.object, &mut blank_nodes),
+            };
+            let mut variables = Vec::new();
+            add_triple_variables(&pattern, &mut variables);
+            // The same triple might be entailed in multiple ways
+            GraphPattern::Distinct {
+                inner: Box::new(GraphPattern::Project {
+                    inner: Box::new(rewrite_triple(pattern)),
+                    variables,
+                }),
+            }
+        })
+        .reduce(|left, right| GraphPattern::Join {
+            left: Box::new(left),
+            right: Box::new(right),
+        })
+        .unwrap_or_else(|| GraphPattern::Bgp {
+            patterns: Vec::new(),
+        })
+}
+
+fn rewrite_triple(pattern: TriplePattern) -> GraphPattern {
+    let TriplePattern {
+        subject,
+        predicate,
+        object,
+    } = pattern;
+    match predicate {
+        NamedNodePattern::NamedNode(predicate) => {
+            if predicate.iri == rdf::TYPE.as_str() {
+                rewrite_type(subject, object)
+            } else if predicate.iri == rdfs::SUB_CLASS_OF.as_str() {
+                transitive_closure(subject, rdfs::SUB_CLASS_OF, object)
+            } else if predicate.iri == rdfs::SUB_PROPERTY_OF.as_str() {
+                transitive_closure(subject, rdfs::SUB_PROPERTY_OF, object)
+            } else {
+                rewrite_property(subject, predicate.into(), object)
+            }
+        }
+        NamedNodePattern::Variable(predicate) => union(vec![
+            rewrite_property(subject.clone(), predicate.clone().into(), object.clone()),
+            bind_predicate(
+                rewrite_type(subject.clone(), object.clone()),
+                &predicate,
+                rdf::TYPE,
+            ),
+            bind_predicate(
+                transitive_closure(subject.clone(), rdfs::SUB_CLASS_OF, object.clone()),
+                &predicate,
+                rdfs::SUB_CLASS_OF,
+            ),
+            bind_predicate(
+                transitive_closure(subject, rdfs::SUB_PROPERTY_OF, object),
+                &predicate,
+                rdfs::SUB_PROPERTY_OF,
+            ),
+        ]),
+    }
+}
+
+/// `subject rdf:type class` with the `rdfs:subClassOf`, `rdfs:domain` and `rdfs:range` rules
+fn rewrite_type(subject: TermPattern, class: TermPattern) -> GraphPattern {
+    let super_classes = PropertyPathExpression::ZeroOrMore(Box::new(path(rdfs::SUB_CLASS_OF)));
+    let explicit_class = new_variable();
+    let domain_property = new_variable();
+    let range_property = new_variable();
+    union(vec![
+        // rdfs9
+        GraphPattern::Join {
+            left: Box::new(GraphPattern::Bgp {
+                patterns: vec![TriplePattern {
+                    subject: subject.clone(),
+                    predicate: named_node(rdf::TYPE).into(),
+                    object: explicit_class.clone().into(),
+                }],
+            }),
+            right: Box::new(GraphPattern::Path {
+                subject: explicit_class.into(),
+                path: super_classes.clone(),
+                object: class.clone(),
+            }),
+        },
+        // rdfs2
+        GraphPattern::Join {
+            left: Box::new(GraphPattern::Bgp {
+                patterns: vec![TriplePattern {
+                    subject: subject.clone(),
+                    predicate: domain_property.clone().into(),
+                    object: new_variable().into(),
+                }],
+            }),
+            right: Box::new(GraphPattern::Path {
+                subject: domain_property.into(),
+                path: schema_path(rdfs::DOMAIN, super_classes.clone()),
+                object: class.clone(),
+            }),
+        },
+        // rdfs3
+        GraphPattern::Join {
+            left: Box::new(GraphPattern::Bgp {
+                patterns: vec![TriplePattern {
+                    subject: new_variable().into(),
+                    predicate: range_property.clone().into(),
+                    object: subject,
+                }],
+            }),
+            right: Box::new(GraphPattern::Path {
+                subject: range_property.into(),
+                path: schema_path(rdfs::RANGE, super_classes),
+                object: class,
+            }),
+        },
+    ])
+}
+
+/// `subject predicate object` with the `rdfs:subPropertyOf` rule (rdfs7)
+fn rewrite_property(
+    subject: TermPattern,
+    predicate: TermPattern,
+    object: TermPattern,
+) -> GraphPattern {
+    let sub_property = new_variable();
+    GraphPattern::Join {
+        left: Box::new(GraphPattern::Bgp {
+            patterns: vec![TriplePattern {
+                subject,
+                predicate: sub_property.clone().into(),
+                object,
+            }],
+        }),
+        right: Box::new(GraphPattern::Path {
+            subject: sub_property.into(),
+            path: PropertyPathExpression::ZeroOrMore(Box::new(path(rdfs::SUB_PROPERTY_OF))),
+            object: predicate,
+        }),
+    }
+}
+
+/// The transitivity of `rdfs:subClassOf` (rdfs11) and `rdfs:subPropertyOf` (rdfs5)
+fn transitive_closure(
+    subject: TermPattern,
+    predicate: NamedNodeRef<'_>,
+    object: TermPattern,
+) -> GraphPattern {
+    GraphPattern::Path {
+        subject,
+        path: PropertyPathExpression::OneOrMore(Box::new(path(predicate))),
+        object,
+    }
+}
+
+/// `rdfs:subPropertyOf*/schema_property/rdfs:subClassOf*`
+fn schema_path(
+    schema_property: NamedNodeRef<'_>,
+    super_classes: PropertyPathExpression,
+) -> PropertyPathExpression {
+    PropertyPathExpression::Sequence(
+        Box::new(PropertyPathExpression::Sequence(
+            Box::new(PropertyPathExpression::ZeroOrMore(Box::new(path(
+                rdfs::SUB_PROPERTY_OF,
+            )))),
+            Box::new(path(schema_property)),
+        )),
+        Box::new(super_classes),
+    )
+}
+
+fn bind_predicate(
+    pattern: GraphPattern,
+    variable: &Variable,
+    predicate: NamedNodeRef<'_>,
+) -> GraphPattern {
+    GraphPattern::Extend {
+        inner: Box::new(pattern),
+        variable: variable.clone(),
+        expression: Expression::NamedNode(named_node(predicate)),
+    }
+}
+
+fn union(patterns: Vec<GraphPattern>) -> GraphPattern {
+    patterns
+        .into_iter()
+        .reduce(|left, right| GraphPattern::Union {
+            left: Box::new(left),
+            right: Box::new(right),
+        })
+        .unwrap_or_else(|| GraphPattern::Bgp {
+            patterns: Vec::new(),
+        })
+}
+
+fn path(predicate: NamedNodeRef<'_>) -> PropertyPathExpression {
+    PropertyPathExpression::NamedNode(named_node(predicate))
+}
+
+fn named_node(node: NamedNodeRef<'_>) -> NamedNode {
+    NamedNode {
+        iri: node.as_str().to_owned(),
+    }
+}
+
+fn new_variable() -> Variable {
+    Variable {
+        name: format!("{:x}", random::<u128>()),
+    }
+}
+
+/// The blank nodes of basic graph patterns behave like variables that are not returned
+///
+/// Each blank node is mapped to a fresh variable so that it does not join with the query variables.
+fn blank_node_to_variable(
+    term: &TermPattern,
+    blank_nodes: &mut HashMap<BlankNode, Variable>,
+) -> TermPattern {
+    match term {
+        TermPattern::BlankNode(bnode) => blank_nodes
+            .entry(bnode.clone())
+            .or_insert_with(new_variable)
+            .clone()
+            .into(),
+        TermPattern::Triple(triple) => TriplePattern {
+            subject: blank_node_to_variable(&triple.subject, blank_nodes),
+            predicate: triple.predicate.clone(),
+            object: blank_node_to_variable(&triple.object, blank_nodes),
+        }
+        .into(),
+        term => term.clone(),
+    }
+}
+
+fn add_triple_variables(pattern: &TriplePattern, variables: &mut Vec<Variable>) {
+    add_term_variables(&pattern.subject, variables);
+    if let NamedNodePattern::Variable(variable) = &pattern.predicate {
+        add_variable(variable, variables);
+    }
+    add_term_variables(&pattern.object, variables);
+}
+
+fn add_term_variables(term: &TermPattern, variables: &mut Vec<Variable>) {
+    match term {
+        TermPattern::Variable(variable) => add_variable(variable, variables),
+        TermPattern::Triple(triple) => add_triple_variables(triple, variables),
+        _ => (),
+    }
+}
+
+fn add_variable(variable: &Variable, variables: &mut Vec<Variable>) {
+    if !variables.contains(variable) {
+        variables.push(variable.clone());
+    }
+}
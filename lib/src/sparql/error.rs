@@ -16,6 +16,13 @@ pub enum EvaluationError {
     Io(io::Error),
     /// An error returned during the query evaluation itself
     Query(QueryError),
+    /// The evaluation has been stopped because its timeout has been reached
+    Timeout,
+    /// The evaluation has been stopped using a [`CancellationToken`](super::CancellationToken)
+    Cancelled,
+    /// The evaluation has been stopped because it exceeded one of the limits set in [`QueryOptions`](super::QueryOptions)
+    /// or the maximal number of iterations of [`ConstructRules`](crate::reasoning::ConstructRules)
+    LimitExceeded(String),
     #[doc(hidden)]
     Extra,
 }
@@ -37,6 +44,9 @@ impl fmt::Display for EvaluationError {
             Self::Parsing(error) => error.fmt(f),
             Self::Io(error) => error.fmt(f),
             Self::Query(error) => error.fmt(f),
+            Self::Timeout => write!(f, "The evaluation timeout has been reached"),
+            Self::Cancelled => write!(f, "The evaluation has been cancelled"),
+            Self::LimitExceeded(msg) => write!(f, "{}", msg),
             Self::Extra => write!(f, "Unknown error"),
         }
     }
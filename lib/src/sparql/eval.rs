@@ -1,13 +1,20 @@
+use crate::model::geo::{DistanceUnit, Geometry, WKT_LITERAL};
 use crate::model::vocab::{rdf, xsd};
 use crate::model::xsd::*;
 use crate::model::{BlankNode, LiteralRef, NamedNodeRef};
 use crate::model::{NamedNode, Term, Triple};
 use crate::sparql::algebra::{Query, QueryDataset};
+use crate::sparql::cancellation::{Interrupter, InterruptibleIterator};
 use crate::sparql::dataset::DatasetView;
 use crate::sparql::error::EvaluationError;
+use crate::sparql::limits::{MemorySize, Reservation, ResourceLimits};
 use crate::sparql::model::*;
+use crate::sparql::parallel::{tuple_channel, Parallelism, TupleReceiver, WorkerContext};
 use crate::sparql::plan::*;
-use crate::sparql::service::ServiceHandler;
+use crate::sparql::profiling::Profiler;
+use crate::sparql::service::{default_service_handler, ServiceHandler};
+use crate::sparql::spill::{merge_sorted_runs, write_sorted_run, Partitions};
+use crate::storage::binary_encoder::encode_term;
 use crate::storage::numeric_encoder::*;
 use crate::storage::small_string::SmallString;
 use digest::Digest;
@@ -20,13 +27,15 @@ use sha1::Sha1;
 use sha2::{Sha256, Sha384, Sha512};
 use spargebra::algebra::GraphPattern;
 use std::cmp::Ordering;
-use std::collections::hash_map::DefaultHasher;
+use std::collections::hash_map::{DefaultHasher, Entry};
 use std::collections::{HashMap, HashSet};
 use std::hash::{Hash, Hasher};
 use std::iter::Iterator;
 use std::iter::{empty, once};
+use std::panic::{catch_unwind, AssertUnwindSafe};
 use std::rc::Rc;
 use std::str;
+use std::sync::Arc;
 
 const REGEX_SIZE_LIMIT: usize = 1_000_000;
 
@@ -39,6 +48,10 @@ pub struct SimpleEvaluator {
     now: DateTime,
     service_handler: Rc<dyn ServiceHandler<Error = EvaluationError>>,
     custom_functions: Rc<HashMap<NamedNode, Rc<dyn Fn(&[Term]) -> Option<Term>>>>,
+    interrupter: Interrupter,
+    limits: ResourceLimits,
+    profiler: Option<Profiler>,
+    parallelism: Option<Rc<Parallelism>>,
 }
 
 impl SimpleEvaluator {
@@ -47,6 +60,9 @@ impl SimpleEvaluator {
         base_iri: Option<Rc<Iri<String>>>,
         service_handler: Rc<dyn ServiceHandler<Error = EvaluationError>>,
         custom_functions: Rc<HashMap<NamedNode, Rc<dyn Fn(&[Term]) -> Option<Term>>>>,
+        interrupter: Interrupter,
+        limits: ResourceLimits,
+        profiler: Option<Profiler>,
     ) -> Self {
         Self {
             dataset,
@@ -54,6 +70,43 @@ impl SimpleEvaluator {
             now: DateTime::now().unwrap(),
             service_handler,
             custom_functions,
+            interrupter,
+            limits,
+            profiler,
+            parallelism: None,
+        }
+    }
+
+    /// Evaluates the independent subtrees of the plan on the thread pool of `parallelism` if set.
+    pub fn with_parallelism(mut self, parallelism: Option<Rc<Parallelism>>) -> Self {
+        self.parallelism = parallelism;
+        self
+    }
+
+    /// Builds the evaluator of a pool thread.
+    ///
+    /// It evaluates all the plan in the current thread.
+    fn for_worker(context: WorkerContext) -> Self {
+        let mut evaluator = Self::new(
+            Rc::new(context.dataset.into_dataset_view()),
+            context.base_iri.map(Rc::new),
+            default_service_handler(context.http_timeout),
+            Rc::default(),
+            context.interrupter,
+            context.limits,
+            None,
+        );
+        evaluator.now = context.now;
+        evaluator
+    }
+
+    /// The evaluator to use for the subtrees evaluated once per input tuple.
+    ///
+    /// Sending them to the thread pool would cost more than evaluating them directly.
+    fn without_parallelism(&self) -> Self {
+        Self {
+            parallelism: None,
+            ..self.clone()
         }
     }
 
@@ -61,14 +114,21 @@ impl SimpleEvaluator {
         &self,
         plan: &PlanNode,
         variables: Rc<Vec<Variable>>,
+        from: EncodedTuple,
     ) -> QueryResults {
-        let iter = self.plan_evaluator(plan)(EncodedTuple::with_capacity(variables.len()));
+        let iter = Box::new(
+            self.limits
+                .limit_results(self.interrupter.wrap(self.root_evaluator(plan, from))),
+        );
         QueryResults::Solutions(decode_bindings(self.dataset.clone(), iter, variables))
     }
 
-    pub fn evaluate_ask_plan(&self, plan: &PlanNode) -> Result<QueryResults, EvaluationError> {
-        let from = EncodedTuple::with_capacity(plan.used_variables().len());
-        match self.plan_evaluator(plan)(from).next() {
+    pub fn evaluate_ask_plan(
+        &self,
+        plan: &PlanNode,
+        from: EncodedTuple,
+    ) -> Result<QueryResults, EvaluationError> {
+        match self.root_evaluator(plan, from).next() {
             Some(Ok(_)) => Ok(QueryResults::Boolean(true)),
             Some(Err(error)) => Err(error),
             None => Ok(QueryResults::Boolean(false)),
@@ -79,33 +139,120 @@ impl SimpleEvaluator {
         &self,
         plan: &PlanNode,
         template: Vec<TripleTemplate>,
+        from: EncodedTuple,
     ) -> QueryResults {
-        let from = EncodedTuple::with_capacity(plan.used_variables().len());
         QueryResults::Graph(QueryTripleIter {
-            iter: Box::new(ConstructIterator {
+            iter: Box::new(self.limits.limit_results(ConstructIterator {
                 eval: self.clone(),
-                iter: self.plan_evaluator(plan)(from),
+                iter: Box::new(self.interrupter.wrap(self.root_evaluator(plan, from))),
                 template,
                 buffered_results: Vec::default(),
                 bnodes: Vec::default(),
-            }),
+            })),
         })
     }
 
-    pub fn evaluate_describe_plan(&self, plan: &PlanNode) -> QueryResults {
-        let from = EncodedTuple::with_capacity(plan.used_variables().len());
+    pub fn evaluate_describe_plan(&self, plan: &PlanNode, from: EncodedTuple) -> QueryResults {
         QueryResults::Graph(QueryTripleIter {
-            iter: Box::new(DescribeIterator {
+            iter: Box::new(self.limits.limit_results(DescribeIterator {
                 eval: self.clone(),
-                iter: self.plan_evaluator(plan)(from),
+                iter: Box::new(self.interrupter.wrap(self.root_evaluator(plan, from))),
                 quads: Box::new(empty()),
-            }),
+            })),
         })
     }
 
+    /// Evaluates the query plan with the given initial bindings.
+    ///
+    /// The operators creating a new variable scope like aggregates do not keep the bindings of the variables not in their scope
+    /// so the results are joined again with the initial bindings.
+    fn root_evaluator(&self, plan: &PlanNode, from: EncodedTuple) -> EncodedTuplesIterator {
+        let iter = self.plan_evaluator(plan)(from.clone());
+        if from.iter().all(|value| value.is_none()) {
+            return iter;
+        }
+        Box::new(iter.filter_map(move |tuple| match tuple {
+            Ok(tuple) => tuple.combine_with(&from).map(Ok),
+            Err(error) => Some(Err(error)),
+        }))
+    }
+
     pub fn plan_evaluator(
         &self,
         node: &PlanNode,
+    ) -> Rc<dyn Fn(EncodedTuple) -> EncodedTuplesIterator> {
+        let evaluator = self.build_plan_evaluator(node);
+        if let Some(profiler) = &self.profiler {
+            let statistics = profiler.statistics(node);
+            Rc::new(move |from| {
+                let iter = statistics.measure(|| evaluator(from));
+                Box::new(statistics.clone().wrap(iter))
+            })
+        } else {
+            evaluator
+        }
+    }
+
+    /// Builds an evaluator running the node on the thread pool.
+    ///
+    /// Returns `None` if the parallel evaluation is disabled or if the node can't be evaluated on another thread.
+    /// The returned evaluator returns `None` if all the threads of the evaluation are already used.
+    /// `evaluator` is the evaluator of the same node on the current thread,
+    /// used if the thread pool does not start the job before its results are needed.
+    fn parallel_plan_evaluator(
+        &self,
+        node: &PlanNode,
+        evaluator: &Rc<dyn Fn(EncodedTuple) -> EncodedTuplesIterator>,
+    ) -> Option<Rc<dyn Fn(EncodedTuple) -> Option<TupleReceiver>>> {
+        let parallelism = self.parallelism.clone()?;
+        let http_timeout = parallelism.http_timeout();
+        if !node.is_parallelizable(http_timeout.is_some()) || self.dataset.share().is_none() {
+            return None;
+        }
+        let plan = Arc::new(node.clone());
+        let eval = self.clone();
+        let evaluator = evaluator.clone();
+        Some(Rc::new(move |from| {
+            let slot = parallelism.acquire()?;
+            let context = WorkerContext {
+                dataset: eval.dataset.share()?,
+                base_iri: eval.base_iri.as_ref().map(|iri| iri.as_ref().clone()),
+                now: eval.now,
+                http_timeout: http_timeout.flatten(),
+                interrupter: eval.interrupter.clone(),
+                limits: eval.limits.clone(),
+            };
+            let plan = plan.clone();
+            let evaluator = evaluator.clone();
+            let fallback_from = from.clone();
+            let (mut sender, receiver) =
+                tuple_channel(&eval.limits, move || evaluator(fallback_from));
+            parallelism.execute(slot, move || {
+                if !sender.start() {
+                    return; // The evaluation thread is already doing the job
+                }
+                let result = catch_unwind(AssertUnwindSafe(|| {
+                    let eval = Self::for_worker(context);
+                    for tuple in eval.interrupter.wrap(eval.plan_evaluator(&plan)(from)) {
+                        if !sender.send(tuple) {
+                            return; // The results are not needed anymore
+                        }
+                    }
+                }));
+                if result.is_err() {
+                    sender.send(Err(EvaluationError::msg(
+                        "The evaluation thread has panicked",
+                    )));
+                }
+                sender.flush();
+            });
+            Some(receiver)
+        }))
+    }
+
+    fn build_plan_evaluator(
+        &self,
+        node: &PlanNode,
     ) -> Rc<dyn Fn(EncodedTuple) -> EncodedTuplesIterator> {
         match node {
             PlanNode::StaticBindings { tuples } => {
@@ -164,9 +311,9 @@ impl SimpleEvaluator {
                 let predicate = predicate.clone();
                 let object = object.clone();
                 let graph_name = graph_name.clone();
-                let dataset = self.dataset.clone();
+                let eval = self.clone();
                 Rc::new(move |from| {
-                    let iter = dataset.encoded_quads_for_pattern(
+                    let iter = eval.encoded_quads_for_pattern(
                         get_pattern_value(&subject, &from).as_ref(),
                         get_pattern_value(&predicate, &from).as_ref(),
                         get_pattern_value(&object, &from).as_ref(),
@@ -189,6 +336,62 @@ impl SimpleEvaluator {
                     }))
                 })
             }
+            PlanNode::TextSearch { literal, tokens } => {
+                let literal = literal.clone();
+                let tokens = tokens.clone();
+                let dataset = self.dataset.clone();
+                Rc::new(move |from| {
+                    if let Some(term) = get_pattern_value(&literal, &from) {
+                        return match dataset.text_matches(&term, &tokens) {
+                            Ok(true) => Box::new(once(Ok(from))),
+                            Ok(false) => Box::new(empty()),
+                            Err(error) => Box::new(once(Err(error))),
+                        };
+                    }
+                    match dataset.text_search(&tokens) {
+                        Ok(terms) => {
+                            let literal = literal.clone();
+                            Box::new(terms.into_iter().filter_map(move |term| {
+                                let mut new_tuple = from.clone();
+                                put_pattern_value(&literal, term, &mut new_tuple)?;
+                                Some(Ok(new_tuple))
+                            }))
+                        }
+                        Err(error) => Box::new(once(Err(error))),
+                    }
+                })
+            }
+            PlanNode::GeoSearch {
+                literal,
+                bounding_box,
+            } => {
+                let literal = literal.clone();
+                let bounding_box = *bounding_box;
+                let dataset = self.dataset.clone();
+                Rc::new(move |from| {
+                    if let Some(term) = get_pattern_value(&literal, &from) {
+                        return if to_geometry(&dataset, &term)
+                            .and_then(|geometry| geometry.bounding_box())
+                            .map_or(false, |rect| rect.intersects(&bounding_box))
+                        {
+                            Box::new(once(Ok(from)))
+                        } else {
+                            Box::new(empty())
+                        };
+                    }
+                    match dataset.geo_search(&bounding_box) {
+                        Ok(terms) => {
+                            let literal = literal.clone();
+                            Box::new(terms.into_iter().filter_map(move |term| {
+                                let mut new_tuple = from.clone();
+                                put_pattern_value(&literal, term, &mut new_tuple)?;
+                                Some(Ok(new_tuple))
+                            }))
+                        }
+                        Err(error) => Box::new(once(Err(error))),
+                    }
+                })
+            }
             PlanNode::PathPattern {
                 subject,
                 path,
@@ -280,51 +483,106 @@ impl SimpleEvaluator {
                     .copied()
                     .collect();
                 let left = self.plan_evaluator(left);
-                let right = self.plan_evaluator(right);
+                let right_node = right;
+                let right = self.plan_evaluator(right_node);
+                let parallel_right = self.parallel_plan_evaluator(right_node, &right);
+                let limits = self.limits.clone();
                 if join_keys.is_empty() {
                     // Cartesian product
                     Rc::new(move |from| {
-                        let mut errors = Vec::default();
-                        let right_values = right(from.clone())
-                            .filter_map(|result| match result {
-                                Ok(result) => Some(result),
-                                Err(error) => {
-                                    errors.push(Err(error));
-                                    None
-                                }
-                            })
-                            .collect::<Vec<_>>();
-                        Box::new(CartesianProductJoinIterator {
-                            left_iter: left(from),
+                        let mut reservation = limits.reserve();
+                        let mut right_values = Vec::default();
+                        let (errors, left_iter) = match materialize_build_side(
+                            &right,
+                            parallel_right.as_deref(),
+                            &left,
+                            from,
+                            &mut reservation,
+                            |tuple| right_values.push(tuple),
+                        ) {
+                            Ok(result) => result,
+                            Err(error) => return Box::new(once(Err(error))),
+                        };
+                        Box::new(reservation.attach(CartesianProductJoinIterator {
+                            left_iter,
                             right: right_values,
                             buffered_results: errors,
-                        })
+                        }))
                     })
                 } else {
                     // Real hash join
                     Rc::new(move |from| {
-                        let mut errors = Vec::default();
+                        let mut reservation = limits.reserve();
                         let mut right_values = EncodedTupleSet::new(join_keys.clone());
-                        right_values.extend(right(from.clone()).filter_map(
-                            |result| match result {
-                                Ok(result) => Some(result),
-                                Err(error) => {
-                                    errors.push(Err(error));
-                                    None
-                                }
-                            },
-                        ));
-                        Box::new(HashJoinIterator {
-                            left_iter: left(from),
+                        let (errors, left_iter) = match materialize_build_side(
+                            &right,
+                            parallel_right.as_deref(),
+                            &left,
+                            from,
+                            &mut reservation,
+                            |tuple| right_values.insert(tuple),
+                        ) {
+                            Ok(result) => result,
+                            Err(error) => return Box::new(once(Err(error))),
+                        };
+                        Box::new(reservation.attach(HashJoinIterator {
+                            left_iter,
                             right: right_values,
                             buffered_results: errors,
-                        })
+                        }))
                     })
                 }
             }
-            PlanNode::ForLoopJoin { left, right } => {
+            PlanNode::SymmetricHashJoin { left, right } => {
+                let join_keys: Vec<_> = left
+                    .always_bound_variables()
+                    .intersection(&right.always_bound_variables())
+                    .copied()
+                    .collect();
+                let left = self.plan_evaluator(left);
+                let right = self.plan_evaluator(right);
+                let limits = self.limits.clone();
+                Rc::new(move |from| {
+                    Box::new(SymmetricHashJoinIterator {
+                        left_iter: Some(left(from.clone())),
+                        right_iter: Some(right(from)),
+                        left: EncodedTupleSet::new(join_keys.clone()),
+                        right: EncodedTupleSet::new(join_keys.clone()),
+                        next_is_left: true,
+                        buffered_results: Vec::default(),
+                        reservation: limits.reserve(),
+                    })
+                })
+            }
+            PlanNode::MergeJoin { left, right, key } => {
+                let mut variables = Vec::new();
+                left.lookup_used_variables(&mut |v| variables.push(v));
+                right.lookup_used_variables(&mut |v| variables.push(v));
                 let left = self.plan_evaluator(left);
                 let right = self.plan_evaluator(right);
+                let key = *key;
+                Rc::new(move |from| {
+                    if variables.iter().any(|v| from.contains(*v)) {
+                        // The inputs might not be sorted by the key anymore, we use a for loop join
+                        let right = right.clone();
+                        return Box::new(left(from).flat_map(move |t| match t {
+                            Ok(t) => right(t),
+                            Err(e) => Box::new(once(Err(e))),
+                        }));
+                    }
+                    Box::new(MergeJoinIterator {
+                        left_iter: left(from.clone()),
+                        right_iter: right(from),
+                        key,
+                        left_next: None,
+                        right_next: None,
+                        buffered_results: Vec::new(),
+                    })
+                })
+            }
+            PlanNode::ForLoopJoin { left, right } => {
+                let left = self.plan_evaluator(left);
+                let right = self.without_parallelism().plan_evaluator(right);
                 Rc::new(move |from| {
                     let right = right.clone();
                     Box::new(left(from).flat_map(move |t| match t {
@@ -341,27 +599,40 @@ impl SimpleEvaluator {
                     .collect();
                 let left = self.plan_evaluator(left);
                 let right = self.plan_evaluator(right);
+                let limits = self.limits.clone();
                 if join_keys.is_empty() {
                     Rc::new(move |from| {
-                        let right: Vec<_> = right(from.clone())
-                            .filter_map(std::result::Result::ok)
-                            .collect();
-                        Box::new(left(from).filter(move |left_tuple| {
+                        let mut reservation = limits.reserve();
+                        let mut right_values = Vec::default();
+                        if let Err(error) =
+                            materialize(right(from.clone()), &mut reservation, |tuple| {
+                                right_values.push(tuple)
+                            })
+                        {
+                            return Box::new(once(Err(error)));
+                        }
+                        Box::new(reservation.attach(left(from).filter(move |left_tuple| {
                             if let Ok(left_tuple) = left_tuple {
-                                !right.iter().any(|right_tuple| {
+                                !right_values.iter().any(|right_tuple| {
                                     are_compatible_and_not_disjointed(left_tuple, right_tuple)
                                 })
                             } else {
                                 true
                             }
-                        }))
+                        })))
                     })
                 } else {
                     Rc::new(move |from| {
+                        let mut reservation = limits.reserve();
                         let mut right_values = EncodedTupleSet::new(join_keys.clone());
-                        right_values
-                            .extend(right(from.clone()).filter_map(std::result::Result::ok));
-                        Box::new(left(from).filter(move |left_tuple| {
+                        if let Err(error) =
+                            materialize(right(from.clone()), &mut reservation, |tuple| {
+                                right_values.insert(tuple)
+                            })
+                        {
+                            return Box::new(once(Err(error)));
+                        }
+                        Box::new(reservation.attach(left(from).filter(move |left_tuple| {
                             if let Ok(left_tuple) = left_tuple {
                                 !right_values.get(left_tuple).iter().any(|right_tuple| {
                                     are_compatible_and_not_disjointed(left_tuple, right_tuple)
@@ -369,7 +640,7 @@ impl SimpleEvaluator {
                             } else {
                                 true
                             }
-                        }))
+                        })))
                     })
                 }
             }
@@ -379,7 +650,7 @@ impl SimpleEvaluator {
                 possible_problem_vars,
             } => {
                 let left = self.plan_evaluator(left);
-                let right = self.plan_evaluator(right);
+                let right = self.without_parallelism().plan_evaluator(right);
                 let possible_problem_vars = possible_problem_vars.clone();
                 Rc::new(move |from| {
                     if possible_problem_vars.is_empty() {
@@ -415,10 +686,38 @@ impl SimpleEvaluator {
                 })
             }
             PlanNode::Union { children } => {
+                let child_nodes = children;
                 let children: Vec<_> = children
                     .iter()
                     .map(|child| self.plan_evaluator(child))
                     .collect();
+                let parallel_children: Vec<_> = if children.len() > 1 {
+                    child_nodes
+                        .iter()
+                        .zip(&children)
+                        .map(|(node, child)| self.parallel_plan_evaluator(node, child))
+                        .collect()
+                } else {
+                    Vec::new()
+                };
+                if parallel_children.iter().any(Option::is_some) {
+                    return Rc::new(move |from| {
+                        // We start all the parallel evaluations before consuming the first child
+                        let mut spawned = parallel_children
+                            .iter()
+                            .map(|child| child.as_ref().and_then(|child| child(from.clone())))
+                            .collect::<Vec<_>>();
+                        let children = children.clone();
+                        Box::new((0..children.len()).flat_map(move |i| {
+                            if let Some(receiver) = spawned[i].take() {
+                                let iter: EncodedTuplesIterator = Box::new(receiver);
+                                iter
+                            } else {
+                                children[i](from.clone())
+                            }
+                        }))
+                    });
+                }
                 Rc::new(move |from| {
                     Box::new(UnionIterator {
                         plans: children.clone(),
@@ -461,18 +760,8 @@ impl SimpleEvaluator {
                     })
                     .collect();
                 let dataset = self.dataset.clone();
-                Rc::new(move |from| {
-                    let mut errors = Vec::default();
-                    let mut values = child(from)
-                        .filter_map(|result| match result {
-                            Ok(result) => Some(result),
-                            Err(error) => {
-                                errors.push(Err(error));
-                                None
-                            }
-                        })
-                        .collect::<Vec<_>>();
-                    values.sort_unstable_by(|a, b| {
+                let comparator: Rc<dyn Fn(&EncodedTuple, &EncodedTuple) -> Ordering> =
+                    Rc::new(move |a, b| {
                         for comp in &by {
                             match comp {
                                 ComparatorFunction::Asc(expression) => {
@@ -501,12 +790,54 @@ impl SimpleEvaluator {
                         }
                         Ordering::Equal
                     });
-                    Box::new(errors.into_iter().chain(values.into_iter().map(Ok)))
+                let limits = self.limits.clone();
+                Rc::new(move |from| {
+                    let mut reservation = limits.reserve();
+                    let mut errors = Vec::default();
+                    let mut values = Vec::default();
+                    let mut runs = Vec::default();
+                    for result in child(from) {
+                        match result {
+                            Ok(tuple) => {
+                                if let Err(error) = reservation.add(&tuple) {
+                                    return Box::new(once(Err(error)));
+                                }
+                                values.push(tuple);
+                                if reservation.should_spill() {
+                                    // We move the sorted values to a temporary file and merge them at the end
+                                    values.sort_unstable_by(|a, b| comparator(a, b));
+                                    match write_sorted_run(values.drain(..)) {
+                                        Ok(run) => runs.push(run),
+                                        Err(error) => return Box::new(once(Err(error.into()))),
+                                    }
+                                    reservation.release();
+                                }
+                            }
+                            Err(error) => errors.push(Err(error)),
+                        }
+                    }
+                    values.sort_unstable_by(|a, b| comparator(a, b));
+                    if runs.is_empty() {
+                        Box::new(
+                            reservation
+                                .attach(errors.into_iter().chain(values.into_iter().map(Ok))),
+                        )
+                    } else {
+                        match merge_sorted_runs(runs, values, comparator.clone()) {
+                            Ok(merged) => {
+                                Box::new(reservation.attach(errors.into_iter().chain(merged)))
+                            }
+                            Err(error) => Box::new(once(Err(error))),
+                        }
+                    }
                 })
             }
             PlanNode::HashDeduplicate { child } => {
                 let child = self.plan_evaluator(child);
-                Rc::new(move |from| Box::new(hash_deduplicate(child(from))))
+                let limits = self.limits.clone();
+                Rc::new(move |from| {
+                    Box::new(SpillingHashDeduplicate::new(child(from), limits.clone(), 0))
+                })
             }
             PlanNode::Reduced { child } => {
                 let child = self.plan_evaluator(child);
@@ -527,7 +858,7 @@ impl SimpleEvaluator {
                 let count = *count;
                 Rc::new(move |from| Box::new(child(from).take(count)))
             }
-            PlanNode::Project { child, mapping } => {
+            PlanNode::Project { child, mapping, .. } => {
                 let child = self.plan_evaluator(child);
                 let mapping = mapping.clone();
                 Rc::new(move |from| {
@@ -555,9 +886,12 @@ impl SimpleEvaluator {
                 child,
                 key_mapping,
                 aggregates,
+                child_variables,
             } => {
                 let child = self.plan_evaluator(child);
+                let child_size = child_variables.len();
                 let key_mapping = key_mapping.clone();
+                let inner_key_mapping = key_mapping.clone();
                 let aggregate_input_expressions: Vec<_> = aggregates
                     .iter()
                     .map(|(aggregate, _)| {
@@ -579,81 +913,46 @@ impl SimpleEvaluator {
                     .collect();
                 let accumulator_variables: Vec<_> =
                     aggregates.iter().map(|(_, var)| *var).collect();
+                let group_by = Rc::new(HashGroupBy {
+                    key_mapping,
+                    aggregate_input_expressions,
+                    accumulator_builders,
+                    accumulator_variables,
+                    limits: self.limits.clone(),
+                });
                 Rc::new(move |from| {
                     let tuple_size = from.capacity(); //TODO: not nice
-                    let key_mapping = key_mapping.clone();
-                    let mut errors = Vec::default();
-                    let mut accumulators_for_group =
-                        HashMap::<Vec<Option<EncodedTerm>>, Vec<Box<dyn Accumulator>>>::default();
-                    child(from)
-                        .filter_map(|result| match result {
-                            Ok(result) => Some(result),
-                            Err(error) => {
-                                errors.push(error);
-                                None
-                            }
-                        })
-                        .for_each(|tuple| {
-                            //TODO avoid copy for key?
-                            let key = key_mapping
-                                .iter()
-                                .map(|(v, _)| tuple.get(*v).cloned())
-                                .collect();
-
-                            let key_accumulators =
-                                accumulators_for_group.entry(key).or_insert_with(|| {
-                                    accumulator_builders.iter().map(|c| c()).collect::<Vec<_>>()
-                                });
-                            for (accumulator, input_expression) in key_accumulators
-                                .iter_mut()
-                                .zip(&aggregate_input_expressions)
-                            {
-                                accumulator.add(
-                                    input_expression
-                                        .as_ref()
-                                        .and_then(|parameter| parameter(&tuple)),
-                                );
-                            }
-                        });
-                    if accumulators_for_group.is_empty() {
-                        // There is always at least one group
-                        accumulators_for_group
-                            .insert(vec![None; key_mapping.len()], Vec::default());
+                                                      // We map forward the "from" values of the group keys, the other variables are not in scope
+                    let mut inner_from = EncodedTuple::with_capacity(child_size);
+                    for (input_key, output_key) in inner_key_mapping.iter() {
+                        if let Some(value) = from.get(*output_key) {
+                            inner_from.set(*input_key, value.clone());
+                        }
                     }
-                    let accumulator_variables = accumulator_variables.clone();
-                    Box::new(
-                        errors
-                            .into_iter()
-                            .map(Err)
-                            .chain(accumulators_for_group.into_iter().map(
-                                move |(key, accumulators)| {
-                                    let mut result = EncodedTuple::with_capacity(tuple_size);
-                                    for (from_position, to_position) in key_mapping.iter() {
-                                        if let Some(value) = &key[*from_position] {
-                                            result.set(*to_position, value.clone());
-                                        }
-                                    }
-                                    for (accumulator, variable) in
-                                        accumulators.into_iter().zip(&accumulator_variables)
-                                    {
-                                        if let Some(value) = accumulator.state() {
-                                            result.set(*variable, value);
-                                        }
-                                    }
-                                    Ok(result)
-                                },
-                            )),
-                    )
+                    group_by.clone().evaluate(child(inner_from), tuple_size, 0)
                 })
             }
         }
     }
 
+    fn encoded_quads_for_pattern(
+        &self,
+        subject: Option<&EncodedTerm>,
+        predicate: Option<&EncodedTerm>,
+        object: Option<&EncodedTerm>,
+        graph_name: Option<&EncodedTerm>,
+    ) -> InterruptibleIterator<Box<dyn Iterator<Item = Result<EncodedQuad, EvaluationError>>>> {
+        self.interrupter.wrap(
+            self.dataset
+                .encoded_quads_for_pattern(subject, predicate, object, graph_name),
+        )
+    }
+
     fn evaluate_service(
         &self,
         service_name: &PatternValue,
         graph_pattern: &GraphPattern,
-        variables: Rc<Vec<Variable>>,
+        variables: Arc<Vec<Variable>>,
         from: &EncodedTuple,
     ) -> Result<EncodedTuplesIterator, EvaluationError> {
         let service_name = get_pattern_value(service_name, from)
@@ -743,8 +1042,7 @@ impl SimpleEvaluator {
     ) -> Box<dyn Iterator<Item = Result<EncodedTerm, EvaluationError>>> {
         match path {
             PlanPropertyPath::Path(p) => Box::new(
-                self.dataset
-                    .encoded_quads_for_pattern(Some(start), Some(p), None, Some(graph_name))
+                self.encoded_quads_for_pattern(Some(start), Some(p), None, Some(graph_name))
                     .map(|t| Ok(t?.object)),
             ),
             PlanPropertyPath::Reverse(p) => self.eval_path_to(p, start, graph_name),
@@ -765,9 +1063,11 @@ impl SimpleEvaluator {
                 let eval = self.clone();
                 let p = p.clone();
                 let graph_name2 = graph_name.clone();
-                Box::new(transitive_closure(Some(Ok(start.clone())), move |e| {
-                    eval.eval_path_from(&p, &e, &graph_name2)
-                }))
+                Box::new(transitive_closure(
+                    Some(Ok(start.clone())),
+                    move |e| eval.eval_path_from(&p, &e, &graph_name2),
+                    self.limits.reserve(),
+                ))
             }
             PlanPropertyPath::OneOrMore(p) => {
                 let eval = self.clone();
@@ -776,16 +1076,17 @@ impl SimpleEvaluator {
                 Box::new(transitive_closure(
                     self.eval_path_from(&p, start, graph_name),
                     move |e| eval.eval_path_from(&p, &e, &graph_name2),
+                    self.limits.reserve(),
                 ))
             }
             PlanPropertyPath::ZeroOrOne(p) => Box::new(hash_deduplicate(
                 once(Ok(start.clone())).chain(self.eval_path_from(p, start, graph_name)),
+                self.limits.reserve(),
             )),
             PlanPropertyPath::NegatedPropertySet(ps) => {
                 let ps = ps.clone();
                 Box::new(
-                    self.dataset
-                        .encoded_quads_for_pattern(Some(start), None, None, Some(graph_name))
+                    self.encoded_quads_for_pattern(Some(start), None, None, Some(graph_name))
                         .filter_map(move |t| match t {
                             Ok(t) => {
                                 if ps.contains(&t.predicate) {
@@ -809,8 +1110,7 @@ impl SimpleEvaluator {
     ) -> Box<dyn Iterator<Item = Result<EncodedTerm, EvaluationError>>> {
         match path {
             PlanPropertyPath::Path(p) => Box::new(
-                self.dataset
-                    .encoded_quads_for_pattern(None, Some(p), Some(end), Some(graph_name))
+                self.encoded_quads_for_pattern(None, Some(p), Some(end), Some(graph_name))
                     .map(|t| Ok(t?.subject)),
             ),
             PlanPropertyPath::Reverse(p) => self.eval_path_from(p, end, graph_name),
@@ -831,9 +1131,11 @@ impl SimpleEvaluator {
                 let eval = self.clone();
                 let p = p.clone();
                 let graph_name2 = graph_name.clone();
-                Box::new(transitive_closure(Some(Ok(end.clone())), move |e| {
-                    eval.eval_path_to(&p, &e, &graph_name2)
-                }))
+                Box::new(transitive_closure(
+                    Some(Ok(end.clone())),
+                    move |e| eval.eval_path_to(&p, &e, &graph_name2),
+                    self.limits.reserve(),
+                ))
             }
             PlanPropertyPath::OneOrMore(p) => {
                 let eval = self.clone();
@@ -842,16 +1144,17 @@ impl SimpleEvaluator {
                 Box::new(transitive_closure(
                     self.eval_path_to(&p, end, graph_name),
                     move |e| eval.eval_path_to(&p, &e, &graph_name2),
+                    self.limits.reserve(),
                 ))
             }
             PlanPropertyPath::ZeroOrOne(p) => Box::new(hash_deduplicate(
                 once(Ok(end.clone())).chain(self.eval_path_to(p, end, graph_name)),
+                self.limits.reserve(),
             )),
             PlanPropertyPath::NegatedPropertySet(ps) => {
                 let ps = ps.clone();
                 Box::new(
-                    self.dataset
-                        .encoded_quads_for_pattern(None, None, Some(end), Some(graph_name))
+                    self.encoded_quads_for_pattern(None, None, Some(end), Some(graph_name))
                         .filter_map(move |t| match t {
                             Ok(t) => {
                                 if ps.contains(&t.predicate) {
@@ -874,8 +1177,7 @@ impl SimpleEvaluator {
     ) -> Box<dyn Iterator<Item = Result<(EncodedTerm, EncodedTerm), EvaluationError>>> {
         match path {
             PlanPropertyPath::Path(p) => Box::new(
-                self.dataset
-                    .encoded_quads_for_pattern(None, Some(p), None, Some(graph_name))
+                self.encoded_quads_for_pattern(None, Some(p), None, Some(graph_name))
                     .map(|t| t.map(|t| (t.subject, t.object))),
             ),
             PlanPropertyPath::Reverse(p) => Box::new(
@@ -908,6 +1210,7 @@ impl SimpleEvaluator {
                         eval.eval_path_from(&p, &middle, &graph_name2)
                             .map(move |end| Ok((start.clone(), end?)))
                     },
+                    self.limits.reserve(),
                 ))
             }
             PlanPropertyPath::OneOrMore(p) => {
@@ -920,17 +1223,18 @@ impl SimpleEvaluator {
                         eval.eval_path_from(&p, &middle, &graph_name2)
                             .map(move |end| Ok((start.clone(), end?)))
                     },
+                    self.limits.reserve(),
                 ))
             }
             PlanPropertyPath::ZeroOrOne(p) => Box::new(hash_deduplicate(
                 self.get_subject_or_object_identity_pairs(graph_name)
                     .chain(self.eval_open_path(p, graph_name)),
+                self.limits.reserve(),
             )),
             PlanPropertyPath::NegatedPropertySet(ps) => {
                 let ps = ps.clone();
                 Box::new(
-                    self.dataset
-                        .encoded_quads_for_pattern(None, None, None, Some(graph_name))
+                    self.encoded_quads_for_pattern(None, None, None, Some(graph_name))
                         .filter_map(move |t| match t {
                             Ok(t) => {
                                 if ps.contains(&t.predicate) {
@@ -950,8 +1254,7 @@ impl SimpleEvaluator {
         &self,
         graph_name: &EncodedTerm,
     ) -> impl Iterator<Item = Result<(EncodedTerm, EncodedTerm), EvaluationError>> {
-        self.dataset
-            .encoded_quads_for_pattern(None, None, None, Some(graph_name))
+        self.encoded_quads_for_pattern(None, None, None, Some(graph_name))
             .flat_map_ok(|t| once(Ok(t.subject)).chain(once(Ok(t.object))))
             .map(|e| e.map(|e| (e.clone(), e)))
     }
@@ -972,7 +1275,7 @@ impl SimpleEvaluator {
             }
             PlanExpression::Exists(plan) => {
                 let plan = plan.clone();
-                let eval = self.plan_evaluator(&plan);
+                let eval = self.without_parallelism().plan_evaluator(&plan);
                 Rc::new(move |tuple| Some(eval(tuple.clone()).next().is_some().into()))
             }
             PlanExpression::Or(a, b) => {
@@ -2019,6 +2322,31 @@ impl SimpleEvaluator {
                     _ => None,
                 })
             }
+            PlanExpression::SfEquals(a, b) => self.geometry_relation(a, b, Geometry::equals),
+            PlanExpression::SfDisjoint(a, b) => self.geometry_relation(a, b, Geometry::disjoint),
+            PlanExpression::SfIntersects(a, b) => {
+                self.geometry_relation(a, b, Geometry::intersects)
+            }
+            PlanExpression::SfWithin(a, b) => self.geometry_relation(a, b, Geometry::within),
+            PlanExpression::SfContains(a, b) => self.geometry_relation(a, b, Geometry::contains),
+            PlanExpression::Distance(a, b, unit) => {
+                let a = self.expression_evaluator(a);
+                let b = self.expression_evaluator(b);
+                let unit = self.expression_evaluator(unit);
+                let dataset = self.dataset.clone();
+                Rc::new(move |tuple| {
+                    let unit = if let EncodedTerm::NamedNode { iri_id } = unit(tuple)? {
+                        DistanceUnit::from_iri(&dataset.get_str(&iri_id).ok()??)?
+                    } else {
+                        return None;
+                    };
+                    Some(
+                        to_geometry(&dataset, &a(tuple)?)?
+                            .distance(&to_geometry(&dataset, &b(tuple)?)?, unit)?
+                            .into(),
+                    )
+                })
+            }
             PlanExpression::CustomFunction(function_name, args) => {
                 if let Some(function) = self.custom_functions.get(function_name).cloned() {
                     let args = args
@@ -2040,6 +2368,26 @@ impl SimpleEvaluator {
         }
     }
 
+    fn geometry_relation(
+        &self,
+        a: &PlanExpression,
+        b: &PlanExpression,
+        relation: fn(&Geometry, &Geometry) -> bool,
+    ) -> Rc<dyn Fn(&EncodedTuple) -> Option<EncodedTerm>> {
+        let a = self.expression_evaluator(a);
+        let b = self.expression_evaluator(b);
+        let dataset = self.dataset.clone();
+        Rc::new(move |tuple| {
+            Some(
+                relation(
+                    &to_geometry(&dataset, &a(tuple)?)?,
+                    &to_geometry(&dataset, &b(tuple)?)?,
+                )
+                .into(),
+            )
+        })
+    }
+
     fn hash<H: Digest>(
         &self,
         arg: &PlanExpression,
@@ -2069,6 +2417,24 @@ fn to_bool(term: &EncodedTerm) -> Option<bool> {
     }
 }
 
+fn to_geometry(dataset: &DatasetView, term: &EncodedTerm) -> Option<Geometry> {
+    let (value, datatype_id) = match term {
+        EncodedTerm::SmallTypedLiteral { value, datatype_id } => {
+            (String::from(*value), datatype_id)
+        }
+        EncodedTerm::BigTypedLiteral {
+            value_id,
+            datatype_id,
+        } => (dataset.get_str(value_id).ok()??, datatype_id),
+        _ => return None,
+    };
+    if *datatype_id == StrHash::new(WKT_LITERAL) {
+        value.parse().ok()
+    } else {
+        None
+    }
+}
+
 fn to_string_id(dataset: &DatasetView, term: &EncodedTerm) -> Option<SmallStringOrId> {
     match term {
         EncodedTerm::NamedNode { iri_id } => Some((*iri_id).into()),
@@ -2272,10 +2638,19 @@ fn compile_pattern(
 ) -> Option<Regex> {
     // TODO Avoid to compile the regex each time
     let pattern = to_simple_string(dataset, pattern)?;
-    let mut regex_builder = RegexBuilder::new(&pattern);
+    let flags = if let Some(flags) = flags {
+        Some(to_simple_string(dataset, &flags)?)
+    } else {
+        None
+    };
+    build_regex(&pattern, flags.as_deref()).ok()
+}
+
+/// Builds a regular expression following the [XPath `fn:matches`](https://www.w3.org/TR/xpath-functions/#func-matches) pattern and flags
+pub fn build_regex(pattern: &str, flags: Option<&str>) -> Result<Regex, regex::Error> {
+    let mut regex_builder = RegexBuilder::new(pattern);
     regex_builder.size_limit(REGEX_SIZE_LIMIT);
     if let Some(flags) = flags {
-        let flags = to_simple_string(dataset, &flags)?;
         for flag in flags.chars() {
             match flag {
                 's' => {
@@ -2294,7 +2669,7 @@ fn compile_pattern(
             }
         }
     }
-    regex_builder.build().ok()
+    regex_builder.build()
 }
 
 fn decode_bindings(
@@ -2320,7 +2695,7 @@ fn decode_bindings(
 // this is used to encode results from a BindingIterator into an EncodedTuplesIterator. This happens when SERVICE clauses are evaluated
 fn encode_bindings(
     dataset: Rc<DatasetView>,
-    variables: Rc<Vec<Variable>>,
+    variables: Arc<Vec<Variable>>,
     iter: QuerySolutionIter,
 ) -> EncodedTuplesIterator {
     Box::new(iter.map(move |solution| {
@@ -3135,6 +3510,178 @@ impl Iterator for HashJoinIterator {
     }
 }
 
+/// Pulls alternately from both sides and joins each new tuple with the already seen tuples of the other side.
+///
+/// When a side is exhausted, the tuples of the other side do not need to be stored anymore.
+struct SymmetricHashJoinIterator {
+    left_iter: Option<EncodedTuplesIterator>,
+    right_iter: Option<EncodedTuplesIterator>,
+    left: EncodedTupleSet,
+    right: EncodedTupleSet,
+    next_is_left: bool,
+    buffered_results: Vec<Result<EncodedTuple, EvaluationError>>,
+    reservation: Reservation,
+}
+
+impl Iterator for SymmetricHashJoinIterator {
+    type Item = Result<EncodedTuple, EvaluationError>;
+
+    fn next(&mut self) -> Option<Result<EncodedTuple, EvaluationError>> {
+        loop {
+            if let Some(result) = self.buffered_results.pop() {
+                return Some(result);
+            }
+            let is_left = match (&self.left_iter, &self.right_iter) {
+                (Some(_), Some(_)) => {
+                    self.next_is_left = !self.next_is_left;
+                    !self.next_is_left
+                }
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (None, None) => return None,
+            };
+            let (iter, table, other_iter, other_table) = if is_left {
+                (
+                    &mut self.left_iter,
+                    &mut self.left,
+                    &self.right_iter,
+                    &mut self.right,
+                )
+            } else {
+                (
+                    &mut self.right_iter,
+                    &mut self.right,
+                    &self.left_iter,
+                    &mut self.left,
+                )
+            };
+            let tuple = match iter.as_mut().and_then(Iterator::next) {
+                Some(Ok(tuple)) => tuple,
+                Some(Err(error)) => return Some(Err(error)),
+                None => {
+                    *iter = None;
+                    // The tuples of the other side are not going to be probed anymore
+                    *other_table = EncodedTupleSet::new(Vec::new());
+                    continue;
+                }
+            };
+            for other_tuple in other_table.get(&tuple) {
+                if let Some(result_tuple) = tuple.combine_with(other_tuple) {
+                    self.buffered_results.push(Ok(result_tuple))
+                }
+            }
+            if other_iter.is_some() {
+                if let Err(error) = self.reservation.add(&tuple) {
+                    return Some(Err(error));
+                }
+                table.insert(tuple);
+            }
+        }
+    }
+}
+
+/// Joins two iterators returning tuples sorted by the binary encoding of their key.
+///
+/// The output is also sorted by the key.
+struct MergeJoinIterator {
+    left_iter: EncodedTuplesIterator,
+    right_iter: EncodedTuplesIterator,
+    key: usize,
+    left_next: Option<(Vec<u8>, EncodedTuple)>,
+    right_next: Option<(Vec<u8>, EncodedTuple)>,
+    buffered_results: Vec<Result<EncodedTuple, EvaluationError>>,
+}
+
+impl MergeJoinIterator {
+    fn pull(
+        iter: &mut EncodedTuplesIterator,
+        key: usize,
+    ) -> Option<Result<(Vec<u8>, EncodedTuple), EvaluationError>> {
+        Some(
+            iter.next()?
+                .map(|tuple| (tuple.get(key).map(encode_term).unwrap_or_default(), tuple)),
+        )
+    }
+
+    /// Returns all the tuples with the given key at the beginning of the iterator.
+    fn group(
+        iter: &mut EncodedTuplesIterator,
+        next: &mut Option<(Vec<u8>, EncodedTuple)>,
+        key: usize,
+        errors: &mut Vec<Result<EncodedTuple, EvaluationError>>,
+    ) -> Vec<EncodedTuple> {
+        let (group_key, first) = if let Some(next) = next.take() {
+            next
+        } else {
+            return Vec::new();
+        };
+        let mut group = vec![first];
+        while let Some(result) = Self::pull(iter, key) {
+            match result {
+                Ok((tuple_key, tuple)) => {
+                    if tuple_key == group_key {
+                        group.push(tuple)
+                    } else {
+                        *next = Some((tuple_key, tuple));
+                        break;
+                    }
+                }
+                Err(error) => errors.push(Err(error)),
+            }
+        }
+        group
+    }
+}
+
+impl Iterator for MergeJoinIterator {
+    type Item = Result<EncodedTuple, EvaluationError>;
+
+    fn next(&mut self) -> Option<Result<EncodedTuple, EvaluationError>> {
+        loop {
+            if let Some(result) = self.buffered_results.pop() {
+                return Some(result);
+            }
+            if self.left_next.is_none() {
+                match Self::pull(&mut self.left_iter, self.key)? {
+                    Ok(next) => self.left_next = Some(next),
+                    Err(error) => return Some(Err(error)),
+                }
+            }
+            if self.right_next.is_none() {
+                match Self::pull(&mut self.right_iter, self.key)? {
+                    Ok(next) => self.right_next = Some(next),
+                    Err(error) => return Some(Err(error)),
+                }
+            }
+            match self.left_next.as_ref()?.0.cmp(&self.right_next.as_ref()?.0) {
+                Ordering::Less => self.left_next = None,
+                Ordering::Greater => self.right_next = None,
+                Ordering::Equal => {
+                    let left_group = Self::group(
+                        &mut self.left_iter,
+                        &mut self.left_next,
+                        self.key,
+                        &mut self.buffered_results,
+                    );
+                    let right_group = Self::group(
+                        &mut self.right_iter,
+                        &mut self.right_next,
+                        self.key,
+                        &mut self.buffered_results,
+                    );
+                    for left_tuple in &left_group {
+                        for right_tuple in &right_group {
+                            if let Some(result_tuple) = left_tuple.combine_with(right_tuple) {
+                                self.buffered_results.push(Ok(result_tuple))
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
 struct LeftJoinIterator {
     right_evaluator: Rc<dyn Fn(EncodedTuple) -> EncodedTuplesIterator>,
     left_iter: EncodedTuplesIterator,
@@ -3167,7 +3714,7 @@ struct BadLeftJoinIterator {
     left_iter: EncodedTuplesIterator,
     current_left: Option<EncodedTuple>,
     current_right: EncodedTuplesIterator,
-    problem_vars: Rc<Vec<usize>>,
+    problem_vars: Arc<Vec<usize>>,
 }
 
 impl Iterator for BadLeftJoinIterator {
@@ -3275,6 +3822,212 @@ impl Iterator for ConsecutiveDeduplication {
     }
 }
 
+/// Hash based deduplication that moves the tuples not seen yet to temporary files when the memory is exhausted.
+///
+/// The spilled tuples are partitioned by hash and each partition is then deduplicated independently.
+struct SpillingHashDeduplicate {
+    inner: EncodedTuplesIterator,
+    already_seen: HashSet<EncodedTuple>,
+    reservation: Reservation,
+    limits: ResourceLimits,
+    level: u64,
+    partitions: Option<Partitions>,
+    spilled: Option<EncodedTuplesIterator>,
+}
+
+impl SpillingHashDeduplicate {
+    fn new(inner: EncodedTuplesIterator, limits: ResourceLimits, level: u64) -> Self {
+        Self {
+            inner,
+            already_seen: HashSet::default(),
+            reservation: limits.reserve(),
+            limits,
+            level,
+            partitions: None,
+            spilled: None,
+        }
+    }
+
+    fn spill(&mut self, tuple: &EncodedTuple) -> Result<(), EvaluationError> {
+        let level = self.level;
+        Ok(self
+            .partitions
+            .get_or_insert_with(|| Partitions::new(level))
+            .write(tuple, tuple)?)
+    }
+}
+
+impl Iterator for SpillingHashDeduplicate {
+    type Item = Result<EncodedTuple, EvaluationError>;
+
+    fn next(&mut self) -> Option<Result<EncodedTuple, EvaluationError>> {
+        if let Some(spilled) = &mut self.spilled {
+            return spilled.next();
+        }
+        loop {
+            let tuple = match self.inner.next() {
+                Some(Ok(tuple)) => tuple,
+                Some(Err(error)) => return Some(Err(error)),
+                None => break,
+            };
+            if self.already_seen.contains(&tuple) {
+                continue;
+            }
+            if self.partitions.is_some() {
+                // The tuple might be a duplicate of an other spilled tuple
+                if let Err(error) = self.spill(&tuple) {
+                    return Some(Err(error));
+                }
+                continue;
+            }
+            if let Err(error) = self.reservation.add(&tuple) {
+                return Some(Err(error));
+            }
+            self.already_seen.insert(tuple.clone());
+            if self.reservation.should_spill() {
+                self.partitions = Some(Partitions::new(self.level));
+            }
+            return Some(Ok(tuple));
+        }
+
+        // The spilled tuples are not equal to the ones already returned
+        let partitions = self.partitions.take()?;
+        self.already_seen = HashSet::default();
+        self.reservation.release();
+        let limits = self.limits.clone();
+        let level = self.level + 1;
+        let mut spilled: EncodedTuplesIterator = Box::new(partitions.into_files().flat_map(
+            move |file| -> EncodedTuplesIterator {
+                match file.read() {
+                    Ok(tuples) => Box::new(Self::new(Box::new(tuples), limits.clone(), level)),
+                    Err(error) => Box::new(once(Err(error.into()))),
+                }
+            },
+        ));
+        let result = spilled.next();
+        self.spilled = Some(spilled);
+        result
+    }
+}
+
+/// Hash based GROUP BY that moves the tuples of the groups not in memory yet to temporary files when the memory is exhausted.
+///
+/// The spilled tuples are partitioned by group key and each partition is then aggregated independently.
+struct HashGroupBy {
+    key_mapping: Arc<Vec<(usize, usize)>>,
+    aggregate_input_expressions: Vec<Option<Rc<dyn Fn(&EncodedTuple) -> Option<EncodedTerm>>>>,
+    accumulator_builders: Vec<Box<dyn Fn() -> Box<dyn Accumulator>>>,
+    accumulator_variables: Vec<usize>,
+    limits: ResourceLimits,
+}
+
+impl HashGroupBy {
+    fn evaluate(
+        self: Rc<Self>,
+        input: EncodedTuplesIterator,
+        tuple_size: usize,
+        level: u64,
+    ) -> EncodedTuplesIterator {
+        let mut reservation = self.limits.reserve();
+        let mut errors = Vec::default();
+        let mut partitions: Option<Partitions> = None;
+        let mut accumulators_for_group =
+            HashMap::<Vec<Option<EncodedTerm>>, Vec<Box<dyn Accumulator>>>::default();
+        for result in input {
+            let tuple = match result {
+                Ok(tuple) => tuple,
+                Err(error) => {
+                    errors.push(error);
+                    continue;
+                }
+            };
+            //TODO avoid copy for key?
+            let key: Vec<_> = self
+                .key_mapping
+                .iter()
+                .map(|(v, _)| tuple.get(*v).cloned())
+                .collect();
+
+            let key_accumulators = match accumulators_for_group.entry(key) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    if let Some(partitions) = &mut partitions {
+                        // There is no room for a new group, we aggregate it later
+                        if let Err(error) = partitions.write(entry.key(), &tuple) {
+                            return Box::new(once(Err(error.into())));
+                        }
+                        continue;
+                    }
+                    // Each new group is a new row to keep in memory
+                    if let Err(error) = reservation.add(entry.key()) {
+                        return Box::new(once(Err(error)));
+                    }
+                    if reservation.should_spill() {
+                        partitions = Some(Partitions::new(level));
+                    }
+                    entry.insert(
+                        self.accumulator_builders
+                            .iter()
+                            .map(|c| c())
+                            .collect::<Vec<_>>(),
+                    )
+                }
+            };
+            for (accumulator, input_expression) in key_accumulators
+                .iter_mut()
+                .zip(&self.aggregate_input_expressions)
+            {
+                accumulator.add(
+                    input_expression
+                        .as_ref()
+                        .and_then(|parameter| parameter(&tuple)),
+                );
+            }
+        }
+        if level == 0 && accumulators_for_group.is_empty() {
+            // There is always at least one group
+            accumulators_for_group.insert(vec![None; self.key_mapping.len()], Vec::default());
+        }
+        let this = self.clone();
+        let groups = accumulators_for_group
+            .into_iter()
+            .map(move |(key, accumulators)| {
+                let mut result = EncodedTuple::with_capacity(tuple_size);
+                for (from_position, to_position) in this.key_mapping.iter() {
+                    if let Some(value) = &key[*from_position] {
+                        result.set(*to_position, value.clone());
+                    }
+                }
+                for (accumulator, variable) in
+                    accumulators.into_iter().zip(&this.accumulator_variables)
+                {
+                    if let Some(value) = accumulator.state() {
+                        result.set(*variable, value);
+                    }
+                }
+                Ok(result)
+            });
+        let spilled = partitions
+            .into_iter()
+            .flat_map(Partitions::into_files)
+            .flat_map(move |file| -> EncodedTuplesIterator {
+                match file.read() {
+                    Ok(tuples) => self
+                        .clone()
+                        .evaluate(Box::new(tuples), tuple_size, level + 1),
+                    Err(error) => Box::new(once(Err(error.into()))),
+                }
+            });
+        Box::new(
+            errors
+                .into_iter()
+                .map(Err)
+                .chain(reservation.attach(groups))
+                .chain(spilled),
+        )
+    }
+}
+
 struct ConstructIterator {
     eval: SimpleEvaluator,
     iter: EncodedTuplesIterator,
@@ -3394,17 +4147,18 @@ impl Iterator for DescribeIterator {
             };
             let eval = self.eval.clone();
             self.quads = Box::new(tuple.into_iter().flatten().flat_map(move |subject| {
-                eval.dataset
-                    .encoded_quads_for_pattern(
-                        Some(&subject),
-                        None,
-                        None,
-                        Some(&EncodedTerm::DefaultGraph),
-                    )
-                    .chain(
-                        eval.dataset
-                            .encoded_quads_for_pattern(Some(&subject), None, None, None),
-                    )
+                eval.encoded_quads_for_pattern(
+                    Some(&subject),
+                    None,
+                    None,
+                    Some(&EncodedTerm::DefaultGraph),
+                )
+                .chain(eval.encoded_quads_for_pattern(
+                    Some(&subject),
+                    None,
+                    None,
+                    None,
+                ))
             }));
         }
     }
@@ -3434,68 +4188,171 @@ impl<T1, T2, I1: Iterator<Item = T1>, I2: Iterator<Item = T2>> Iterator
     }
 }
 
-fn transitive_closure<T: Clone + Eq + Hash, NI: Iterator<Item = Result<T, EvaluationError>>>(
+fn transitive_closure<
+    T: Clone + Eq + Hash + MemorySize,
+    NI: Iterator<Item = Result<T, EvaluationError>>,
+>(
     start: impl IntoIterator<Item = Result<T, EvaluationError>>,
     next: impl Fn(T) -> NI,
+    mut reservation: Reservation,
 ) -> impl Iterator<Item = Result<T, EvaluationError>> {
     //TODO: optimize
     let mut all = HashSet::<T>::default();
     let mut errors = Vec::default();
-    let mut current = start
-        .into_iter()
-        .filter_map(|e| match e {
+    if let Err(error) =
+        fill_transitive_closure(start, next, &mut all, &mut errors, &mut reservation)
+    {
+        errors.push(error);
+    }
+    reservation.attach(errors.into_iter().map(Err).chain(all.into_iter().map(Ok)))
+}
+
+fn fill_transitive_closure<
+    T: Clone + Eq + Hash + MemorySize,
+    NI: Iterator<Item = Result<T, EvaluationError>>,
+>(
+    start: impl IntoIterator<Item = Result<T, EvaluationError>>,
+    next: impl Fn(T) -> NI,
+    all: &mut HashSet<T>,
+    errors: &mut Vec<EvaluationError>,
+    reservation: &mut Reservation,
+) -> Result<(), EvaluationError> {
+    let mut current = Vec::default();
+    for e in start {
+        match e {
             Ok(e) => {
+                reservation.add(&e)?;
                 all.insert(e.clone());
-                Some(e)
-            }
-            Err(error) => {
-                errors.push(error);
-                None
+                current.push(e);
             }
-        })
-        .collect::<Vec<_>>();
-
+            Err(error) => errors.push(error),
+        }
+    }
     while !current.is_empty() {
-        current = current
-            .into_iter()
-            .flat_map(&next)
-            .filter_map(|e| match e {
+        let mut new_current = Vec::default();
+        for e in current.into_iter().flat_map(&next) {
+            match e {
                 Ok(e) => {
-                    if all.contains(&e) {
-                        None
-                    } else {
+                    if !all.contains(&e) {
+                        reservation.add(&e)?;
                         all.insert(e.clone());
-                        Some(e)
+                        new_current.push(e);
                     }
                 }
-                Err(error) => {
-                    errors.push(error);
-                    None
-                }
-            })
-            .collect();
+                Err(error) => errors.push(error),
+            }
+        }
+        current = new_current;
     }
-    errors.into_iter().map(Err).chain(all.into_iter().map(Ok))
+    Ok(())
 }
 
-fn hash_deduplicate<T: Eq + Hash + Clone>(
+fn hash_deduplicate<T: Eq + Hash + Clone + MemorySize>(
     iter: impl Iterator<Item = Result<T, EvaluationError>>,
+    mut reservation: Reservation,
 ) -> impl Iterator<Item = Result<T, EvaluationError>> {
     let mut already_seen = HashSet::with_capacity(iter.size_hint().0);
-    iter.filter(move |e| {
-        if let Ok(e) = e {
-            if already_seen.contains(e) {
-                false
+    iter.filter_map(move |e| match e {
+        Ok(e) => {
+            if already_seen.contains(&e) {
+                None
+            } else if let Err(error) = reservation.add(&e) {
+                Some(Err(error))
             } else {
                 already_seen.insert(e.clone());
-                true
+                Some(Ok(e))
             }
-        } else {
-            true
         }
+        Err(error) => Some(Err(error)),
     })
 }
 
+/// Calls `f` on all the tuples returned by `iter` while registering them in `reservation`.
+///
+/// Returns the evaluation errors or fails if a resource limit is exceeded.
+fn materialize(
+    iter: EncodedTuplesIterator,
+    reservation: &mut Reservation,
+    mut f: impl FnMut(EncodedTuple),
+) -> Result<Vec<Result<EncodedTuple, EvaluationError>>, EvaluationError> {
+    let mut errors = Vec::default();
+    for result in iter {
+        match result {
+            Ok(tuple) => {
+                reservation.add(&tuple)?;
+                f(tuple);
+            }
+            Err(error) => errors.push(Err(error)),
+        }
+    }
+    Ok(errors)
+}
+
+/// Materializes the build side of a join and returns the probe side iterator.
+///
+/// If the build side is evaluated on the thread pool, the probe side is evaluated at the same time and its tuples are buffered.
+fn materialize_build_side(
+    build: &Rc<dyn Fn(EncodedTuple) -> EncodedTuplesIterator>,
+    parallel_build: Option<&dyn Fn(EncodedTuple) -> Option<TupleReceiver>>,
+    probe: &Rc<dyn Fn(EncodedTuple) -> EncodedTuplesIterator>,
+    from: EncodedTuple,
+    reservation: &mut Reservation,
+    mut f: impl FnMut(EncodedTuple),
+) -> Result<
+    (
+        Vec<Result<EncodedTuple, EvaluationError>>,
+        EncodedTuplesIterator,
+    ),
+    EvaluationError,
+> {
+    let mut build_iter = match parallel_build.and_then(|build| build(from.clone())) {
+        Some(build_iter) => build_iter,
+        None => {
+            let errors = materialize(build(from.clone()), reservation, f)?;
+            return Ok((errors, probe(from)));
+        }
+    };
+    let mut probe_iter = probe(from);
+    let mut probe_buffer = Vec::new();
+    let mut errors = Vec::default();
+    let mut add = |result, reservation: &mut Reservation| {
+        match result {
+            Ok(tuple) => {
+                reservation.add(&tuple)?;
+                f(tuple);
+            }
+            Err(error) => errors.push(Err(error)),
+        }
+        Ok::<_, EvaluationError>(())
+    };
+    let mut probe_ended = false;
+    while let Some(batch) = build_iter.try_next_batch() {
+        for result in batch {
+            add(result, reservation)?;
+        }
+        if let Some(result) = probe_iter.next() {
+            if let Ok(tuple) = &result {
+                reservation.add(tuple)?;
+            }
+            probe_buffer.push(result);
+        } else {
+            probe_ended = true;
+            break;
+        }
+    }
+    for result in build_iter {
+        add(result, reservation)?;
+    }
+    Ok((
+        errors,
+        if probe_ended {
+            Box::new(probe_buffer.into_iter())
+        } else {
+            Box::new(probe_buffer.into_iter().chain(probe_iter))
+        },
+    ))
+}
+
 trait ResultIterator<T>: Iterator<Item = Result<T, EvaluationError>> + Sized {
     fn flat_map_ok<O, F: FnMut(T) -> U, U: IntoIterator<Item = Result<O, EvaluationError>>>(
         self,
@@ -3751,11 +4608,11 @@ struct GroupConcatAccumulator {
     dataset: Rc<DatasetView>,
     concat: Option<String>,
     language: Option<Option<SmallStringOrId>>,
-    separator: Rc<String>,
+    separator: Arc<String>,
 }
 
 impl GroupConcatAccumulator {
-    fn new(dataset: Rc<DatasetView>, separator: Rc<String>) -> Self {
+    fn new(dataset: Rc<DatasetView>, separator: Arc<String>) -> Self {
         Self {
             dataset,
             concat: Some("".to_owned()),
@@ -0,0 +1,584 @@
+use crate::sparql::dataset::DatasetView;
+use crate::sparql::error::EvaluationError;
+use crate::sparql::model::Variable;
+use crate::sparql::plan::*;
+use crate::sparql::profiling::{node_id, NodeStatistics};
+use crate::storage::numeric_encoder::{Decoder, EncodedTerm};
+use json_event_parser::{JsonEvent, JsonWriter};
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+/// The evaluation plan built for a SPARQL query.
+///
+/// It is returned by [`Store::explain`](crate::store::Store::explain) and shows the join algorithms, the order of the patterns evaluation and where the filters are evaluated.
+///
+/// The [`Display`](fmt::Display) implementation writes it as an indented tree and [`write_in_json`](QueryExplanation::write_in_json) serializes it in JSON.
+///
+/// ```
+/// use oxigraph::store::Store;
+///
+/// let store = Store::new()?;
+/// let explanation = store.explain("SELECT * WHERE { ?s ?p ?o FILTER(?o = 1) }")?;
+/// assert!(explanation.to_string().contains("QuadPattern"));
+/// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct QueryExplanation {
+    root: ExplanationNode,
+}
+
+impl QueryExplanation {
+    pub(crate) fn new(
+        form: &'static str,
+        plan: &PlanNode,
+        variables: &[Variable],
+        dataset: &DatasetView,
+    ) -> Result<Self, EvaluationError> {
+        let explainer = Explainer { dataset };
+        Ok(Self {
+            root: ExplanationNode {
+                id: 0,
+                name: form,
+                attributes: Vec::new(),
+                children: vec![explainer.node(plan, variables)?],
+            },
+        })
+    }
+
+    /// Returns a copy of the explanation with the `rows` and `duration` attributes of the plan nodes evaluated until now.
+    pub(crate) fn with_statistics(&self, statistics: &HashMap<usize, Rc<NodeStatistics>>) -> Self {
+        Self {
+            root: self.root.with_statistics(statistics),
+        }
+    }
+
+    /// Writes the explanation in JSON.
+    ///
+    /// Each plan node is serialized as an object with a `type` key, one key per attribute and a `children` array.
+    pub fn write_in_json(&self, writer: impl Write) -> io::Result<()> {
+        let mut writer = JsonWriter::from_writer(writer);
+        self.root.write_in_json(&mut writer)
+    }
+}
+
+impl fmt::Display for QueryExplanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.root.fmt(f, 0)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ExplanationNode {
+    /// The plan node identifier used by the profiler
+    id: usize,
+    name: &'static str,
+    attributes: Vec<(&'static str, String)>,
+    children: Vec<Self>,
+}
+
+impl ExplanationNode {
+    fn with_statistics(&self, statistics: &HashMap<usize, Rc<NodeStatistics>>) -> Self {
+        let mut attributes = self.attributes.clone();
+        if let Some(statistics) = statistics.get(&self.id) {
+            attributes.push(("rows", statistics.rows().to_string()));
+            attributes.push(("duration", format!("{:?}", statistics.duration())));
+        }
+        Self {
+            id: self.id,
+            name: self.name,
+            attributes,
+            children: self
+                .children
+                .iter()
+                .map(|child| child.with_statistics(statistics))
+                .collect(),
+        }
+    }
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        write!(f, "{:indent$}{}", "", self.name, indent = 2 * depth)?;
+        for (key, value) in &self.attributes {
+            write!(f, " {}={}", key, value)?;
+        }
+        writeln!(f)?;
+        for child in &self.children {
+            child.fmt(f, depth + 1)?;
+        }
+        Ok(())
+    }
+
+    fn write_in_json<W: Write>(&self, writer: &mut JsonWriter<W>) -> io::Result<()> {
+        writer.write_event(JsonEvent::StartObject)?;
+        writer.write_event(JsonEvent::ObjectKey("type"))?;
+        writer.write_event(JsonEvent::String(self.name))?;
+        for (key, value) in &self.attributes {
+            writer.write_event(JsonEvent::ObjectKey(key))?;
+            writer.write_event(JsonEvent::String(value))?;
+        }
+        if !self.children.is_empty() {
+            writer.write_event(JsonEvent::ObjectKey("children"))?;
+            writer.write_event(JsonEvent::StartArray)?;
+            for child in &self.children {
+                child.write_in_json(writer)?;
+            }
+            writer.write_event(JsonEvent::EndArray)?;
+        }
+        writer.write_event(JsonEvent::EndObject)
+    }
+}
+
+struct Explainer<'a> {
+    dataset: &'a DatasetView,
+}
+
+impl<'a> Explainer<'a> {
+    fn node(
+        &self,
+        node: &PlanNode,
+        variables: &[Variable],
+    ) -> Result<ExplanationNode, EvaluationError> {
+        let mut attributes = Vec::new();
+        let mut children = Vec::new();
+        let name = match node {
+            PlanNode::StaticBindings { tuples } => {
+                attributes.push(("tuples", tuples.len().to_string()));
+                "StaticBindings"
+            }
+            PlanNode::Service {
+                service_name,
+                graph_pattern,
+                silent,
+                ..
+            } => {
+                attributes.push(("name", self.pattern_value(service_name, variables)?));
+                attributes.push(("pattern", graph_pattern.to_string()));
+                if *silent {
+                    attributes.push(("silent", "true".to_owned()));
+                }
+                "Service"
+            }
+            PlanNode::QuadPattern {
+                subject,
+                predicate,
+                object,
+                graph_name,
+            } => {
+                attributes.push(("subject", self.pattern_value(subject, variables)?));
+                attributes.push(("predicate", self.pattern_value(predicate, variables)?));
+                attributes.push(("object", self.pattern_value(object, variables)?));
+                attributes.push(("graph", self.pattern_value(graph_name, variables)?));
+                "QuadPattern"
+            }
+            PlanNode::TextSearch { literal, tokens } => {
+                attributes.push(("literal", self.pattern_value(literal, variables)?));
+                attributes.push((
+                    "query",
+                    tokens
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                ));
+                "TextSearch"
+            }
+            PlanNode::GeoSearch {
+                literal,
+                bounding_box,
+            } => {
+                attributes.push(("literal", self.pattern_value(literal, variables)?));
+                attributes.push(("box", bounding_box.to_string()));
+                "GeoSearch"
+            }
+            PlanNode::PathPattern {
+                subject,
+                path,
+                object,
+                graph_name,
+            } => {
+                attributes.push(("subject", self.pattern_value(subject, variables)?));
+                attributes.push(("path", self.path(path)?));
+                attributes.push(("object", self.pattern_value(object, variables)?));
+                attributes.push(("graph", self.pattern_value(graph_name, variables)?));
+                "PathPattern"
+            }
+            PlanNode::HashJoin { left, right } => {
+                children.push(self.node(left, variables)?);
+                children.push(self.node(right, variables)?);
+                "HashJoin"
+            }
+            PlanNode::SymmetricHashJoin { left, right } => {
+                children.push(self.node(left, variables)?);
+                children.push(self.node(right, variables)?);
+                "SymmetricHashJoin"
+            }
+            PlanNode::MergeJoin { left, right, key } => {
+                children.push(self.node(left, variables)?);
+                children.push(self.node(right, variables)?);
+                attributes.push(("key", variable(*key, variables)));
+                "MergeJoin"
+            }
+            PlanNode::ForLoopJoin { left, right } => {
+                children.push(self.node(left, variables)?);
+                children.push(self.node(right, variables)?);
+                "ForLoopJoin"
+            }
+            PlanNode::AntiJoin { left, right } => {
+                children.push(self.node(left, variables)?);
+                children.push(self.node(right, variables)?);
+                "AntiJoin"
+            }
+            PlanNode::Filter { child, expression } => {
+                children.push(self.node(child, variables)?);
+                attributes.push((
+                    "expression",
+                    self.expression(expression, variables, &mut children)?,
+                ));
+                "Filter"
+            }
+            PlanNode::Union { children: union } => {
+                for child in union {
+                    children.push(self.node(child, variables)?);
+                }
+                "Union"
+            }
+            PlanNode::LeftJoin { left, right, .. } => {
+                children.push(self.node(left, variables)?);
+                children.push(self.node(right, variables)?);
+                "LeftJoin"
+            }
+            PlanNode::Extend {
+                child,
+                position,
+                expression,
+            } => {
+                children.push(self.node(child, variables)?);
+                attributes.push(("variable", variable(*position, variables)));
+                attributes.push((
+                    "expression",
+                    self.expression(expression, variables, &mut children)?,
+                ));
+                "Extend"
+            }
+            PlanNode::Sort { child, by } => {
+                children.push(self.node(child, variables)?);
+                let by = by
+                    .iter()
+                    .map(|comparator| {
+                        Ok(match comparator {
+                            Comparator::Asc(e) => {
+                                format!("ASC({})", self.expression(e, variables, &mut children)?)
+                            }
+                            Comparator::Desc(e) => {
+                                format!("DESC({})", self.expression(e, variables, &mut children)?)
+                            }
+                        })
+                    })
+                    .collect::<Result<Vec<_>, EvaluationError>>()?;
+                attributes.push(("by", by.join(" ")));
+                "Sort"
+            }
+            PlanNode::HashDeduplicate { child } => {
+                children.push(self.node(child, variables)?);
+                "HashDeduplicate"
+            }
+            PlanNode::Reduced { child } => {
+                children.push(self.node(child, variables)?);
+                "Reduced"
+            }
+            PlanNode::Skip { child, count } => {
+                children.push(self.node(child, variables)?);
+                attributes.push(("count", count.to_string()));
+                "Skip"
+            }
+            PlanNode::Limit { child, count } => {
+                children.push(self.node(child, variables)?);
+                attributes.push(("count", count.to_string()));
+                "Limit"
+            }
+            PlanNode::Project {
+                child,
+                mapping,
+                child_variables,
+            } => {
+                children.push(self.node(child, child_variables)?);
+                attributes.push((
+                    "variables",
+                    mapping
+                        .iter()
+                        .map(|(_, output)| variable(*output, variables))
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                ));
+                "Project"
+            }
+            PlanNode::Aggregate {
+                child,
+                key_mapping,
+                aggregates,
+                child_variables,
+            } => {
+                children.push(self.node(child, child_variables)?);
+                attributes.push((
+                    "keys",
+                    key_mapping
+                        .iter()
+                        .map(|(_, output)| variable(*output, variables))
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                ));
+                let aggregates = aggregates
+                    .iter()
+                    .map(|(aggregate, output)| {
+                        let name = match &aggregate.function {
+                            PlanAggregationFunction::Count => "COUNT",
+                            PlanAggregationFunction::Sum => "SUM",
+                            PlanAggregationFunction::Min => "MIN",
+                            PlanAggregationFunction::Max => "MAX",
+                            PlanAggregationFunction::Avg => "AVG",
+                            PlanAggregationFunction::Sample => "SAMPLE",
+                            PlanAggregationFunction::GroupConcat { .. } => "GROUP_CONCAT",
+                        };
+                        let parameter = if let Some(parameter) = &aggregate.parameter {
+                            self.expression(parameter, child_variables, &mut children)?
+                        } else {
+                            "*".to_owned()
+                        };
+                        Ok(format!(
+                            "{}({}{}) AS {}",
+                            name,
+                            if aggregate.distinct { "DISTINCT " } else { "" },
+                            parameter,
+                            variable(*output, variables)
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, EvaluationError>>()?;
+                attributes.push(("aggregates", aggregates.join(" ")));
+                "Aggregate"
+            }
+        };
+        Ok(ExplanationNode {
+            id: node_id(node),
+            name,
+            attributes,
+            children,
+        })
+    }
+
+    fn term(&self, term: &EncodedTerm) -> Result<String, EvaluationError> {
+        Ok(if term.is_default_graph() {
+            "DEFAULT".to_owned()
+        } else {
+            self.dataset.decode_term(term)?.to_string()
+        })
+    }
+
+    fn pattern_value(
+        &self,
+        value: &PatternValue,
+        variables: &[Variable],
+    ) -> Result<String, EvaluationError> {
+        Ok(match value {
+            PatternValue::Constant(term) => self.term(term)?,
+            PatternValue::Variable(v) => variable(*v, variables),
+            PatternValue::Triple(triple) => format!(
+                "<< {} {} {} >>",
+                self.pattern_value(&triple.subject, variables)?,
+                self.pattern_value(&triple.predicate, variables)?,
+                self.pattern_value(&triple.object, variables)?
+            ),
+        })
+    }
+
+    fn path(&self, path: &PlanPropertyPath) -> Result<String, EvaluationError> {
+        Ok(match path {
+            PlanPropertyPath::Path(p) => self.term(p)?,
+            PlanPropertyPath::Reverse(p) => format!("^({})", self.path(p)?),
+            PlanPropertyPath::Sequence(a, b) => format!("({} / {})", self.path(a)?, self.path(b)?),
+            PlanPropertyPath::Alternative(a, b) => {
+                format!("({} | {})", self.path(a)?, self.path(b)?)
+            }
+            PlanPropertyPath::ZeroOrMore(p) => format!("({})*", self.path(p)?),
+            PlanPropertyPath::OneOrMore(p) => format!("({})+", self.path(p)?),
+            PlanPropertyPath::ZeroOrOne(p) => format!("({})?", self.path(p)?),
+            PlanPropertyPath::NegatedPropertySet(ps) => format!(
+                "!({})",
+                ps.iter()
+                    .map(|p| self.term(p))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .join(" | ")
+            ),
+        })
+    }
+
+    /// Formats the expression in a SPARQL like syntax.
+    ///
+    /// The plans of the EXISTS are added to `exists`.
+    fn expression(
+        &self,
+        expression: &PlanExpression,
+        variables: &[Variable],
+        exists: &mut Vec<ExplanationNode>,
+    ) -> Result<String, EvaluationError> {
+        let infix = |operator: &str,
+                     a: &PlanExpression,
+                     b: &PlanExpression,
+                     exists: &mut Vec<ExplanationNode>| {
+            Ok::<_, EvaluationError>(format!(
+                "({} {} {})",
+                self.expression(a, variables, exists)?,
+                operator,
+                self.expression(b, variables, exists)?
+            ))
+        };
+        let function = |name: &str, args: &[&PlanExpression], exists: &mut Vec<ExplanationNode>| {
+            Ok::<_, EvaluationError>(format!(
+                "{}({})",
+                name,
+                args.iter()
+                    .map(|e| self.expression(e, variables, exists))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .join(", ")
+            ))
+        };
+        Ok(match expression {
+            PlanExpression::Constant(t) => self.term(t)?,
+            PlanExpression::Variable(v) => variable(*v, variables),
+            PlanExpression::Exists(plan) => {
+                exists.push(self.node(plan, variables)?);
+                "EXISTS".to_owned()
+            }
+            PlanExpression::Or(a, b) => infix("||", a, b, exists)?,
+            PlanExpression::And(a, b) => infix("&&", a, b, exists)?,
+            PlanExpression::Equal(a, b) => infix("=", a, b, exists)?,
+            PlanExpression::Greater(a, b) => infix(">", a, b, exists)?,
+            PlanExpression::GreaterOrEqual(a, b) => infix(">=", a, b, exists)?,
+            PlanExpression::Less(a, b) => infix("<", a, b, exists)?,
+            PlanExpression::LessOrEqual(a, b) => infix("<=", a, b, exists)?,
+            PlanExpression::Add(a, b) => infix("+", a, b, exists)?,
+            PlanExpression::Subtract(a, b) => infix("-", a, b, exists)?,
+            PlanExpression::Multiply(a, b) => infix("*", a, b, exists)?,
+            PlanExpression::Divide(a, b) => infix("/", a, b, exists)?,
+            PlanExpression::UnaryPlus(e) => format!("+{}", self.expression(e, variables, exists)?),
+            PlanExpression::UnaryMinus(e) => {
+                format!("-{}", self.expression(e, variables, exists)?)
+            }
+            PlanExpression::Not(e) => format!("!{}", self.expression(e, variables, exists)?),
+            PlanExpression::Str(e) => function("STR", &[e], exists)?,
+            PlanExpression::Lang(e) => function("LANG", &[e], exists)?,
+            PlanExpression::LangMatches(a, b) => function("LANGMATCHES", &[a, b], exists)?,
+            PlanExpression::Datatype(e) => function("DATATYPE", &[e], exists)?,
+            PlanExpression::Bound(v) => format!("BOUND({})", variable(*v, variables)),
+            PlanExpression::Iri(e) => function("IRI", &[e], exists)?,
+            PlanExpression::BNode(e) => {
+                if let Some(e) = e {
+                    function("BNODE", &[e], exists)?
+                } else {
+                    "BNODE()".to_owned()
+                }
+            }
+            PlanExpression::Rand => "RAND()".to_owned(),
+            PlanExpression::Abs(e) => function("ABS", &[e], exists)?,
+            PlanExpression::Ceil(e) => function("CEIL", &[e], exists)?,
+            PlanExpression::Floor(e) => function("FLOOR", &[e], exists)?,
+            PlanExpression::Round(e) => function("ROUND", &[e], exists)?,
+            PlanExpression::Concat(es) => {
+                function("CONCAT", &es.iter().collect::<Vec<_>>(), exists)?
+            }
+            PlanExpression::SubStr(a, b, c) => {
+                if let Some(c) = c {
+                    function("SUBSTR", &[a, b, c], exists)?
+                } else {
+                    function("SUBSTR", &[a, b], exists)?
+                }
+            }
+            PlanExpression::StrLen(e) => function("STRLEN", &[e], exists)?,
+            PlanExpression::Replace(a, b, c, d) => {
+                if let Some(d) = d {
+                    function("REPLACE", &[a, b, c, d], exists)?
+                } else {
+                    function("REPLACE", &[a, b, c], exists)?
+                }
+            }
+            PlanExpression::UCase(e) => function("UCASE", &[e], exists)?,
+            PlanExpression::LCase(e) => function("LCASE", &[e], exists)?,
+            PlanExpression::EncodeForUri(e) => function("ENCODE_FOR_URI", &[e], exists)?,
+            PlanExpression::Contains(a, b) => function("CONTAINS", &[a, b], exists)?,
+            PlanExpression::StrStarts(a, b) => function("STRSTARTS", &[a, b], exists)?,
+            PlanExpression::StrEnds(a, b) => function("STRENDS", &[a, b], exists)?,
+            PlanExpression::StrBefore(a, b) => function("STRBEFORE", &[a, b], exists)?,
+            PlanExpression::StrAfter(a, b) => function("STRAFTER", &[a, b], exists)?,
+            PlanExpression::Year(e) => function("YEAR", &[e], exists)?,
+            PlanExpression::Month(e) => function("MONTH", &[e], exists)?,
+            PlanExpression::Day(e) => function("DAY", &[e], exists)?,
+            PlanExpression::Hours(e) => function("HOURS", &[e], exists)?,
+            PlanExpression::Minutes(e) => function("MINUTES", &[e], exists)?,
+            PlanExpression::Seconds(e) => function("SECONDS", &[e], exists)?,
+            PlanExpression::Timezone(e) => function("TIMEZONE", &[e], exists)?,
+            PlanExpression::Tz(e) => function("TZ", &[e], exists)?,
+            PlanExpression::Now => "NOW()".to_owned(),
+            PlanExpression::Uuid => "UUID()".to_owned(),
+            PlanExpression::StrUuid => "STRUUID()".to_owned(),
+            PlanExpression::Md5(e) => function("MD5", &[e], exists)?,
+            PlanExpression::Sha1(e) => function("SHA1", &[e], exists)?,
+            PlanExpression::Sha256(e) => function("SHA256", &[e], exists)?,
+            PlanExpression::Sha384(e) => function("SHA384", &[e], exists)?,
+            PlanExpression::Sha512(e) => function("SHA512", &[e], exists)?,
+            PlanExpression::Coalesce(es) => {
+                function("COALESCE", &es.iter().collect::<Vec<_>>(), exists)?
+            }
+            PlanExpression::If(a, b, c) => function("IF", &[a, b, c], exists)?,
+            PlanExpression::StrLang(a, b) => function("STRLANG", &[a, b], exists)?,
+            PlanExpression::StrDt(a, b) => function("STRDT", &[a, b], exists)?,
+            PlanExpression::SameTerm(a, b) => function("sameTerm", &[a, b], exists)?,
+            PlanExpression::IsIri(e) => function("isIRI", &[e], exists)?,
+            PlanExpression::IsBlank(e) => function("isBLANK", &[e], exists)?,
+            PlanExpression::IsLiteral(e) => function("isLITERAL", &[e], exists)?,
+            PlanExpression::IsNumeric(e) => function("isNUMERIC", &[e], exists)?,
+            PlanExpression::Regex(a, b, c) => {
+                if let Some(c) = c {
+                    function("REGEX", &[a, b, c], exists)?
+                } else {
+                    function("REGEX", &[a, b], exists)?
+                }
+            }
+            PlanExpression::Triple(a, b, c) => function("TRIPLE", &[a, b, c], exists)?,
+            PlanExpression::Subject(e) => function("SUBJECT", &[e], exists)?,
+            PlanExpression::Predicate(e) => function("PREDICATE", &[e], exists)?,
+            PlanExpression::Object(e) => function("OBJECT", &[e], exists)?,
+            PlanExpression::IsTriple(e) => function("isTRIPLE", &[e], exists)?,
+            PlanExpression::BooleanCast(e) => function("xsd:boolean", &[e], exists)?,
+            PlanExpression::DoubleCast(e) => function("xsd:double", &[e], exists)?,
+            PlanExpression::FloatCast(e) => function("xsd:float", &[e], exists)?,
+            PlanExpression::DecimalCast(e) => function("xsd:decimal", &[e], exists)?,
+            PlanExpression::IntegerCast(e) => function("xsd:integer", &[e], exists)?,
+            PlanExpression::DateCast(e) => function("xsd:date", &[e], exists)?,
+            PlanExpression::TimeCast(e) => function("xsd:time", &[e], exists)?,
+            PlanExpression::DateTimeCast(e) => function("xsd:dateTime", &[e], exists)?,
+            PlanExpression::DurationCast(e) => function("xsd:duration", &[e], exists)?,
+            PlanExpression::YearMonthDurationCast(e) => {
+                function("xsd:yearMonthDuration", &[e], exists)?
+            }
+            PlanExpression::DayTimeDurationCast(e) => {
+                function("xsd:dayTimeDuration", &[e], exists)?
+            }
+            PlanExpression::StringCast(e) => function("xsd:string", &[e], exists)?,
+            PlanExpression::SfEquals(a, b) => function("geof:sfEquals", &[a, b], exists)?,
+            PlanExpression::SfDisjoint(a, b) => function("geof:sfDisjoint", &[a, b], exists)?,
+            PlanExpression::SfIntersects(a, b) => function("geof:sfIntersects", &[a, b], exists)?,
+            PlanExpression::SfWithin(a, b) => function("geof:sfWithin", &[a, b], exists)?,
+            PlanExpression::SfContains(a, b) => function("geof:sfContains", &[a, b], exists)?,
+            PlanExpression::Distance(a, b, c) => function("geof:distance", &[a, b, c], exists)?,
+            PlanExpression::CustomFunction(name, args) => {
+                function(&name.to_string(), &args.iter().collect::<Vec<_>>(), exists)?
+            }
+        })
+    }
+}
+
+fn variable(position: usize, variables: &[Variable]) -> String {
+    variables
+        .get(position)
+        .map_or_else(|| format!("?_{}", position), Variable::to_string)
+}
@@ -0,0 +1,225 @@
+use crate::sparql::plan::EncodedTuple;
+use crate::sparql::EvaluationError;
+use crate::storage::numeric_encoder::EncodedTerm;
+use std::mem::size_of;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Enforces the resource limits set in [`QueryOptions`](super::QueryOptions) during an evaluation.
+///
+/// The memory usage is shared between all the [`Reservation`]s built from the same limits,
+/// including the ones of the subtrees evaluated on other threads.
+#[derive(Clone, Default)]
+pub struct ResourceLimits {
+    max_intermediate_rows: Option<usize>,
+    max_memory_size: Option<usize>,
+    max_result_rows: Option<usize>,
+    spill_threshold: Option<usize>,
+    used_memory: Arc<AtomicUsize>,
+}
+
+impl ResourceLimits {
+    pub fn new(
+        max_intermediate_rows: Option<usize>,
+        max_memory_size: Option<usize>,
+        max_result_rows: Option<usize>,
+        spill_threshold: Option<usize>,
+    ) -> Self {
+        Self {
+            max_intermediate_rows,
+            max_memory_size,
+            max_result_rows,
+            spill_threshold,
+            used_memory: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Starts to track the rows materialized by an operator.
+    ///
+    /// The memory is released when the returned reservation is dropped.
+    pub fn reserve(&self) -> Reservation {
+        Reservation {
+            limits: self.clone(),
+            rows: 0,
+            memory_size: 0,
+        }
+    }
+
+    /// Fails with an error if more than the maximal number of results are returned.
+    ///
+    /// The returned iterator ends after the first [`EvaluationError::LimitExceeded`] error.
+    pub fn limit_results<T, I: Iterator<Item = Result<T, EvaluationError>>>(
+        &self,
+        iter: I,
+    ) -> ResultLimitIterator<I> {
+        ResultLimitIterator {
+            iter,
+            max_result_rows: self.max_result_rows,
+            count: 0,
+            exceeded: false,
+        }
+    }
+}
+
+pub struct Reservation {
+    limits: ResourceLimits,
+    rows: usize,
+    memory_size: usize,
+}
+
+impl Reservation {
+    /// Registers a new materialized row.
+    pub fn add(&mut self, row: &impl MemorySize) -> Result<(), EvaluationError> {
+        self.rows += 1;
+        if let Some(max_intermediate_rows) = self.limits.max_intermediate_rows {
+            if self.rows > max_intermediate_rows {
+                return Err(EvaluationError::LimitExceeded(format!(
+                    "The evaluation requires to keep more than {} intermediate rows in memory",
+                    max_intermediate_rows
+                )));
+            }
+        }
+        let size = row.memory_size();
+        self.memory_size += size;
+        let used_memory = self.limits.used_memory.fetch_add(size, Ordering::Relaxed) + size;
+        if let Some(max_memory_size) = self.limits.max_memory_size {
+            if used_memory > max_memory_size {
+                return Err(EvaluationError::LimitExceeded(format!(
+                    "The evaluation requires more than {} bytes of memory",
+                    max_memory_size
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns if the operator should move its rows from memory to temporary files.
+    pub fn should_spill(&self) -> bool {
+        self.limits
+            .spill_threshold
+            .map_or(false, |spill_threshold| self.memory_size > spill_threshold)
+    }
+
+    /// Releases all the rows registered until now.
+    ///
+    /// It should be called when they have been moved out of memory.
+    pub fn release(&mut self) {
+        self.limits
+            .used_memory
+            .fetch_sub(self.memory_size, Ordering::Relaxed);
+        self.rows = 0;
+        self.memory_size = 0;
+    }
+
+    /// Keeps the reservation alive as long as the given iterator.
+    pub fn attach<I: Iterator>(self, iter: I) -> ReservedIterator<I> {
+        ReservedIterator {
+            iter,
+            _reservation: self,
+        }
+    }
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+pub struct ReservedIterator<I> {
+    iter: I,
+    _reservation: Reservation,
+}
+
+impl<I: Iterator> Iterator for ReservedIterator<I> {
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        self.iter.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+pub struct ResultLimitIterator<I> {
+    iter: I,
+    max_result_rows: Option<usize>,
+    count: usize,
+    /// If a limit has been exceeded, in which case the evaluation is stopped
+    exceeded: bool,
+}
+
+impl<T, I: Iterator<Item = Result<T, EvaluationError>>> Iterator for ResultLimitIterator<I> {
+    type Item = Result<T, EvaluationError>;
+
+    fn next(&mut self) -> Option<Result<T, EvaluationError>> {
+        if self.exceeded {
+            return None;
+        }
+        let result = self.iter.next()?;
+        if let Some(max_result_rows) = self.max_result_rows {
+            if result.is_ok() {
+                self.count += 1;
+                if self.count > max_result_rows {
+                    self.exceeded = true;
+                    return Some(Err(EvaluationError::LimitExceeded(format!(
+                        "The evaluation returns more than {} results",
+                        max_result_rows
+                    ))));
+                }
+            }
+        }
+        if matches!(result, Err(EvaluationError::LimitExceeded(_))) {
+            self.exceeded = true;
+        }
+        Some(result)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.exceeded {
+            (0, Some(0))
+        } else {
+            self.iter.size_hint()
+        }
+    }
+}
+
+/// An estimation of the memory used by a value.
+pub trait MemorySize {
+    fn memory_size(&self) -> usize;
+}
+
+impl MemorySize for EncodedTerm {
+    fn memory_size(&self) -> usize {
+        size_of::<Self>()
+    }
+}
+
+impl MemorySize for EncodedTuple {
+    fn memory_size(&self) -> usize {
+        size_of::<Self>() + self.capacity() * size_of::<Option<EncodedTerm>>()
+    }
+}
+
+impl<T: MemorySize> MemorySize for Vec<T> {
+    fn memory_size(&self) -> usize {
+        size_of::<Self>() + self.iter().map(MemorySize::memory_size).sum::<usize>()
+    }
+}
+
+impl<T: MemorySize> MemorySize for Option<T> {
+    fn memory_size(&self) -> usize {
+        self.as_ref()
+            .map_or(size_of::<Self>(), MemorySize::memory_size)
+    }
+}
+
+impl<A: MemorySize, B: MemorySize> MemorySize for (A, B) {
+    fn memory_size(&self) -> usize {
+        self.0.memory_size() + self.1.memory_size()
+    }
+}
@@ -3,121 +3,265 @@
 //! Stores execute SPARQL. See [`Store`](crate::store::Store::query()) for an example.
 
 mod algebra;
+mod cache;
+mod cancellation;
 mod csv_results;
 mod dataset;
+mod entailment;
 mod error;
 mod eval;
+mod explanation;
 mod http;
 mod json_results;
+mod limits;
 mod model;
+mod parallel;
 mod plan;
 mod plan_builder;
+mod prepared;
+mod profiling;
 mod service;
+mod spill;
 mod update;
 mod xml_results;
 
-use crate::model::{NamedNode, Term};
+use crate::model::{NamedNode, NamedNodeRef, Term, TripleRef};
 pub use crate::sparql::algebra::{Query, Update};
+pub(crate) use crate::sparql::cache::QueryCache;
+pub use crate::sparql::cancellation::CancellationToken;
+use crate::sparql::cancellation::Interrupter;
 use crate::sparql::dataset::DatasetView;
 pub use crate::sparql::error::EvaluationError;
+pub(crate) use crate::sparql::eval::build_regex;
 use crate::sparql::eval::SimpleEvaluator;
+pub use crate::sparql::explanation::QueryExplanation;
+use crate::sparql::limits::ResourceLimits;
 pub use crate::sparql::model::QueryResults;
 pub use crate::sparql::model::QueryResultsFormat;
 pub use crate::sparql::model::QuerySolution;
 pub use crate::sparql::model::QuerySolutionIter;
 pub use crate::sparql::model::QueryTripleIter;
 pub use crate::sparql::model::{Variable, VariableNameParseError};
+use crate::sparql::parallel::Parallelism;
+pub(crate) use crate::sparql::parallel::ThreadPool;
+use crate::sparql::plan::{EncodedTuple, PlanNode, TripleTemplate};
 use crate::sparql::plan_builder::PlanBuilder;
+pub use crate::sparql::prepared::PreparedQuery;
+use crate::sparql::profiling::Profiler;
+pub use crate::sparql::profiling::QueryProfile;
 pub use crate::sparql::service::ServiceHandler;
-use crate::sparql::service::{EmptyServiceHandler, ErrorConversionServiceHandler};
+use crate::sparql::service::{
+    default_service_handler, EmptyServiceHandler, ErrorConversionServiceHandler,
+};
 pub(crate) use crate::sparql::update::evaluate_update;
-use crate::storage::Storage;
+use crate::storage::StorageReader;
+use oxiri::Iri;
+use spargebra::algebra::GraphPattern;
 pub use spargebra::ParseError;
 use std::collections::HashMap;
 use std::rc::Rc;
+use std::sync::Arc;
 use std::time::Duration;
 
-#[allow(clippy::needless_pass_by_value)]
 pub(crate) fn evaluate_query(
-    storage: Storage,
+    reader: StorageReader,
     query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
     options: QueryOptions,
 ) -> Result<QueryResults, EvaluationError> {
+    evaluate_query_with_bindings(
+        reader,
+        query.try_into().map_err(std::convert::Into::into)?,
+        options,
+        [],
+    )
+}
+
+/// Evaluates the query with some of its top level variables bound to the given values
+pub(crate) fn evaluate_query_with_bindings(
+    reader: StorageReader,
+    query: Query,
+    options: QueryOptions,
+    bindings: impl IntoIterator<Item = (Variable, Term)>,
+) -> Result<QueryResults, EvaluationError> {
+    let dataset = DatasetView::new(reader, &query.dataset);
+    let plan = QueryPlan::build(&dataset, query.inner, &options)?;
+    let from = plan.bind(&dataset, bindings)?;
+    plan.evaluate(dataset, options, from)
+}
+
+/// Evaluates the query with the content of the `graph_name` named graph replaced by the given triples
+pub(crate) fn evaluate_query_with_memory_graph<'a>(
+    reader: StorageReader,
+    query: Query,
+    options: QueryOptions,
+    graph_name: NamedNodeRef<'_>,
+    triples: impl IntoIterator<Item = TripleRef<'a>>,
+) -> Result<QueryResults, EvaluationError> {
+    let dataset = DatasetView::new(reader, &query.dataset).with_memory_graph(graph_name, triples);
+    let plan = QueryPlan::build(&dataset, query.inner, &options)?;
+    let from = plan.bind(&dataset, [])?;
+    plan.evaluate(dataset, options, from)
+}
+
+pub(crate) fn explain_query(
+    reader: StorageReader,
+    query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
+    options: &QueryOptions,
+) -> Result<QueryExplanation, EvaluationError> {
     let query = query.try_into().map_err(std::convert::Into::into)?;
-    let dataset = DatasetView::new(storage.snapshot(), &query.dataset);
-    match query.inner {
-        spargebra::Query::Select {
-            pattern, base_iri, ..
-        } => {
-            let (plan, variables) =
-                PlanBuilder::build(&dataset, &pattern, true, &options.custom_functions)?;
-            Ok(SimpleEvaluator::new(
-                Rc::new(dataset),
-                base_iri.map(Rc::new),
-                options.service_handler(),
-                Rc::new(options.custom_functions),
-            )
-            .evaluate_select_plan(
-                &plan,
-                Rc::new(
-                    variables
-                        .into_iter()
-                        .map(|v| Variable::new_unchecked(v.name))
-                        .collect(),
-                ),
-            ))
-        }
-        spargebra::Query::Ask {
-            pattern, base_iri, ..
-        } => {
-            let (plan, _) =
-                PlanBuilder::build(&dataset, &pattern, false, &options.custom_functions)?;
-            SimpleEvaluator::new(
-                Rc::new(dataset),
-                base_iri.map(Rc::new),
-                options.service_handler(),
-                Rc::new(options.custom_functions),
-            )
-            .evaluate_ask_plan(&plan)
+    let dataset = DatasetView::new(reader, &query.dataset);
+    let plan = QueryPlan::build(&dataset, query.inner, options)?;
+    QueryExplanation::new(
+        plan.form.name(),
+        &plan.plan,
+        &convert_variables(&plan.variables),
+        &dataset,
+    )
+}
+
+/// A query with its evaluation plan.
+struct QueryPlan {
+    form: QueryForm,
+    plan: PlanNode,
+    variables: Vec<spargebra::term::Variable>,
+    base_iri: Option<Rc<Iri<String>>>,
+}
+
+enum QueryForm {
+    Select,
+    Ask,
+    Construct(Vec<TripleTemplate>),
+    Describe,
+}
+
+impl QueryForm {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Select => "Select",
+            Self::Ask => "Ask",
+            Self::Construct(_) => "Construct",
+            Self::Describe => "Describe",
         }
-        spargebra::Query::Construct {
-            template,
-            pattern,
-            base_iri,
-            ..
-        } => {
-            let (plan, variables) =
-                PlanBuilder::build(&dataset, &pattern, false, &options.custom_functions)?;
-            let construct = PlanBuilder::build_graph_template(
-                &dataset,
-                &template,
-                variables,
+    }
+}
+
+impl QueryPlan {
+    fn build(
+        dataset: &DatasetView,
+        query: spargebra::Query,
+        options: &QueryOptions,
+    ) -> Result<Self, EvaluationError> {
+        let build = |pattern: &GraphPattern, is_cardinality_meaningful| {
+            PlanBuilder::build(
+                dataset,
+                pattern,
+                is_cardinality_meaningful,
                 &options.custom_functions,
-            );
-            Ok(SimpleEvaluator::new(
-                Rc::new(dataset),
-                base_iri.map(Rc::new),
-                options.service_handler(),
-                Rc::new(options.custom_functions),
-            )
-            .evaluate_construct_plan(&plan, construct))
-        }
-        spargebra::Query::Describe {
-            pattern, base_iri, ..
-        } => {
-            let (plan, _) =
-                PlanBuilder::build(&dataset, &pattern, false, &options.custom_functions)?;
-            Ok(SimpleEvaluator::new(
-                Rc::new(dataset),
-                base_iri.map(Rc::new),
-                options.service_handler(),
-                Rc::new(options.custom_functions),
+                options.rdfs_entailment,
             )
-            .evaluate_describe_plan(&plan))
+        };
+        let (form, (plan, variables), base_iri) = match query {
+            spargebra::Query::Select {
+                pattern, base_iri, ..
+            } => (QueryForm::Select, build(&pattern, true)?, base_iri),
+            spargebra::Query::Ask {
+                pattern, base_iri, ..
+            } => (QueryForm::Ask, build(&pattern, false)?, base_iri),
+            spargebra::Query::Construct {
+                template,
+                pattern,
+                base_iri,
+                ..
+            } => {
+                let (plan, variables) = build(&pattern, false)?;
+                let template = PlanBuilder::build_graph_template(
+                    dataset,
+                    &template,
+                    variables.clone(),
+                    &options.custom_functions,
+                );
+                (QueryForm::Construct(template), (plan, variables), base_iri)
+            }
+            spargebra::Query::Describe {
+                pattern, base_iri, ..
+            } => (QueryForm::Describe, build(&pattern, false)?, base_iri),
+        };
+        Ok(Self {
+            form,
+            plan,
+            variables,
+            base_iri: base_iri.map(Rc::new),
+        })
+    }
+
+    /// Builds the initial bindings of the evaluation.
+    ///
+    /// Only the variables of the query top level scope could be bound.
+    fn bind(
+        &self,
+        dataset: &DatasetView,
+        bindings: impl IntoIterator<Item = (Variable, Term)>,
+    ) -> Result<EncodedTuple, EvaluationError> {
+        let mut from = EncodedTuple::with_capacity(self.variables.len());
+        for (variable, value) in bindings {
+            let position = self
+                .variables
+                .iter()
+                .position(|v| v.name == variable.as_str())
+                .ok_or_else(|| {
+                    EvaluationError::msg(format!(
+                        "The variable {} is not in the query top level scope",
+                        variable
+                    ))
+                })?;
+            from.set(position, dataset.encode_term(&value));
         }
+        Ok(from)
+    }
+
+    fn evaluate(
+        &self,
+        dataset: DatasetView,
+        options: QueryOptions,
+        from: EncodedTuple,
+    ) -> Result<QueryResults, EvaluationError> {
+        let profiler = options.profiler(self.form.name(), &self.plan, &self.variables, &dataset)?;
+        let service_handler = options.service_handler();
+        let interrupter = options.interrupter();
+        let limits = options.limits();
+        let parallelism = options.parallelism();
+        let evaluator = SimpleEvaluator::new(
+            Rc::new(dataset),
+            self.base_iri.clone(),
+            service_handler,
+            Rc::new(options.custom_functions),
+            interrupter,
+            limits,
+            profiler,
+        )
+        .with_parallelism(parallelism);
+        Ok(match &self.form {
+            QueryForm::Select => evaluator.evaluate_select_plan(
+                &self.plan,
+                Rc::new(convert_variables(&self.variables)),
+                from,
+            ),
+            QueryForm::Ask => evaluator.evaluate_ask_plan(&self.plan, from)?,
+            QueryForm::Construct(template) => {
+                evaluator.evaluate_construct_plan(&self.plan, template.clone(), from)
+            }
+            QueryForm::Describe => evaluator.evaluate_describe_plan(&self.plan, from),
+        })
     }
 }
 
+fn convert_variables(variables: &[spargebra::term::Variable]) -> Vec<Variable> {
+    variables
+        .iter()
+        .map(|v| Variable::new_unchecked(v.name.clone()))
+        .collect()
+}
+
 /// Options for SPARQL query evaluation.
 ///
 ///
@@ -128,6 +272,16 @@ pub struct QueryOptions {
     service_handler: Option<Rc<dyn ServiceHandler<Error = EvaluationError>>>,
     custom_functions: HashMap<NamedNode, Rc<dyn Fn(&[Term]) -> Option<Term>>>,
     http_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    cancellation_token: Option<CancellationToken>,
+    max_intermediate_rows: Option<usize>,
+    max_memory_size: Option<usize>,
+    max_result_rows: Option<usize>,
+    spill_threshold: Option<usize>,
+    profile: Option<QueryProfile>,
+    max_threads: Option<usize>,
+    thread_pool: Option<Arc<ThreadPool>>,
+    rdfs_entailment: bool,
 }
 
 impl QueryOptions {
@@ -154,6 +308,187 @@ impl QueryOptions {
         self
     }
 
+    /// Sets a timeout for the evaluation.
+    ///
+    /// The time spent to consume the results is included.
+    /// When the timeout is reached, the evaluation fails with [`EvaluationError::Timeout`].
+    ///
+    /// ```
+    /// use oxigraph::store::Store;
+    /// use oxigraph::sparql::{QueryOptions, QueryResults};
+    /// use std::time::Duration;
+    ///
+    /// let store = Store::new()?;
+    /// if let QueryResults::Solutions(solutions) = store.query_opt(
+    ///     "SELECT * WHERE { ?s ?p ?o }",
+    ///     QueryOptions::default().with_timeout(Duration::from_secs(10)),
+    /// )? {
+    ///     assert_eq!(solutions.count(), 0);
+    /// }
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    #[inline]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Allows to cancel the evaluation using the given [`CancellationToken`].
+    #[inline]
+    pub fn with_cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation_token = Some(token);
+        self
+    }
+
+    /// Sets the maximal number of rows an operator is allowed to keep in memory during the evaluation.
+    ///
+    /// It bounds the intermediate results of the operators that need to materialize them like hash joins, `DISTINCT`, `ORDER BY` or `GROUP BY`.
+    /// When the limit is reached, the evaluation fails with [`EvaluationError::LimitExceeded`].
+    ///
+    /// ```
+    /// use oxigraph::store::Store;
+    /// use oxigraph::model::*;
+    /// use oxigraph::sparql::{EvaluationError, QueryOptions, QueryResults};
+    ///
+    /// let store = Store::new()?;
+    /// let ex = NamedNodeRef::new("http://example.com")?;
+    /// store.insert(QuadRef::new(ex, ex, ex, GraphNameRef::DefaultGraph))?;
+    /// store.insert(QuadRef::new(ex, ex, LiteralRef::new_simple_literal("foo"), GraphNameRef::DefaultGraph))?;
+    ///
+    /// if let QueryResults::Solutions(mut solutions) = store.query_opt(
+    ///     "SELECT ?o WHERE { ?s ?p ?o } ORDER BY ?o",
+    ///     QueryOptions::default().with_max_intermediate_rows(1),
+    /// )? {
+    ///     assert!(matches!(solutions.next(), Some(Err(EvaluationError::LimitExceeded(_)))));
+    /// }
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    #[inline]
+    pub fn with_max_intermediate_rows(mut self, max_intermediate_rows: usize) -> Self {
+        self.max_intermediate_rows = Some(max_intermediate_rows);
+        self
+    }
+
+    /// Sets the maximal size in bytes of the intermediate results kept in memory during the evaluation.
+    ///
+    /// The memory usage is only an estimation based on the size of the materialized rows.
+    /// When the limit is reached, the evaluation fails with [`EvaluationError::LimitExceeded`].
+    #[inline]
+    pub fn with_max_memory_size(mut self, max_memory_size: usize) -> Self {
+        self.max_memory_size = Some(max_memory_size);
+        self
+    }
+
+    /// Sets the maximal number of results (solutions or triples) the evaluation is allowed to return.
+    ///
+    /// The evaluation fails with [`EvaluationError::LimitExceeded`] when trying to get more results.
+    #[inline]
+    pub fn with_max_result_rows(mut self, max_result_rows: usize) -> Self {
+        self.max_result_rows = Some(max_result_rows);
+        self
+    }
+
+    /// Sets the estimated memory size in bytes above which the `ORDER BY`, `DISTINCT` and `GROUP BY` operators
+    /// move their intermediate results from memory to temporary files.
+    ///
+    /// `ORDER BY` then uses an external merge sort and `DISTINCT` and `GROUP BY` partition the remaining rows by hash.
+    /// The temporary files are written in the system temporary directory and removed at the end of the evaluation.
+    ///
+    /// By default, all the intermediate results are kept in memory.
+    ///
+    /// ```
+    /// use oxigraph::store::Store;
+    /// use oxigraph::sparql::{QueryOptions, QueryResults};
+    ///
+    /// let store = Store::new()?;
+    /// if let QueryResults::Solutions(solutions) = store.query_opt(
+    ///     "SELECT ?i WHERE { VALUES ?i { 3 1 2 } } ORDER BY ?i",
+    ///     QueryOptions::default().with_spill_threshold(0),
+    /// )? {
+    ///     assert_eq!(solutions.collect::<Result<Vec<_>,_>>()?.len(), 3);
+    /// }
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    #[inline]
+    pub fn with_spill_threshold(mut self, memory_size: usize) -> Self {
+        self.spill_threshold = Some(memory_size);
+        self
+    }
+
+    /// Records the number of rows produced by each operator of the evaluation and the time spent in it in the given [`QueryProfile`].
+    ///
+    /// The profiling has a small overhead on each row produced by each operator.
+    #[inline]
+    pub fn with_profiling(mut self, profile: QueryProfile) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
+    /// Evaluates the independent parts of the query on at most `max_threads` threads at the same time.
+    ///
+    /// The threads are taken from a pool shared by all the queries on the same [`Store`](crate::store::Store).
+    /// The `UNION` branches and the hash join build sides are then evaluated at the same time as the rest of the query.
+    /// The tuples computed in advance by the pool threads are counted in the memory limit set by [`QueryOptions::with_max_memory_size`].
+    /// The subtrees evaluated in the context of each input tuple, like the right side of `OPTIONAL`, are not parallelized.
+    /// `SERVICE` calls are only done on the pool threads if no custom [`ServiceHandler`] is set and the subtrees using custom functions are always evaluated on the calling thread.
+    ///
+    /// The parallel evaluation is not possible inside of transactions.
+    /// The operators evaluated on the pool threads are not profiled.
+    ///
+    /// ```
+    /// use oxigraph::store::Store;
+    /// use oxigraph::sparql::{QueryOptions, QueryResults};
+    ///
+    /// let store = Store::new()?;
+    /// if let QueryResults::Solutions(solutions) = store.query_opt(
+    ///     "SELECT ?i WHERE { { BIND(1 AS ?i) } UNION { BIND(2 AS ?i) } }",
+    ///     QueryOptions::default().with_parallelism(4),
+    /// )? {
+    ///     assert_eq!(solutions.collect::<Result<Vec<_>,_>>()?.len(), 2);
+    /// }
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    #[inline]
+    pub fn with_parallelism(mut self, max_threads: usize) -> Self {
+        self.max_threads = Some(max_threads);
+        self
+    }
+
+    /// Evaluates the basic graph patterns using the [RDFS entailment regime](https://www.w3.org/TR/sparql11-entailment/#RDFSEntRegime).
+    ///
+    /// The `rdfs:subClassOf`, `rdfs:subPropertyOf`, `rdfs:domain` and `rdfs:range` statements of the queried graphs are taken into account
+    /// by rewriting the query so the store content is not modified.
+    /// The axiomatic triples, the `rdfs:Resource` typing of all terms and the reflexivity of `rdfs:subClassOf` and `rdfs:subPropertyOf` are not entailed.
+    ///
+    /// ```
+    /// use oxigraph::store::Store;
+    /// use oxigraph::io::GraphFormat;
+    /// use oxigraph::model::*;
+    /// use oxigraph::sparql::{QueryOptions, QueryResults};
+    ///
+    /// let store = Store::new()?;
+    /// store.load_graph(
+    ///     "<http://example.com/Cat> <http://www.w3.org/2000/01/rdf-schema#subClassOf> <http://example.com/Animal> .
+    ///     <http://example.com/tom> a <http://example.com/Cat> .".as_bytes(),
+    ///     GraphFormat::Turtle,
+    ///     GraphNameRef::DefaultGraph,
+    ///     None,
+    /// )?;
+    ///
+    /// if let QueryResults::Solutions(mut solutions) = store.query_opt(
+    ///     "SELECT ?s WHERE { ?s a <http://example.com/Animal> }",
+    ///     QueryOptions::default().with_rdfs_entailment(),
+    /// )? {
+    ///     assert_eq!(solutions.next().unwrap()?.get("s"), Some(&NamedNode::new("http://example.com/tom")?.into()));
+    /// }
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    #[inline]
+    pub fn with_rdfs_entailment(mut self) -> Self {
+        self.rdfs_entailment = true;
+        self
+    }
+
     /// Adds a custom SPARQL evaluation function.
     ///
     /// Example with a function serializing terms to N-Triples:
@@ -185,14 +520,70 @@ impl QueryOptions {
         self
     }
 
+    fn interrupter(&self) -> Interrupter {
+        Interrupter::new(self.cancellation_token.clone(), self.timeout)
+    }
+
+    fn limits(&self) -> ResourceLimits {
+        ResourceLimits::new(
+            self.max_intermediate_rows,
+            self.max_memory_size,
+            self.max_result_rows,
+            self.spill_threshold,
+        )
+    }
+
+    fn profiler(
+        &self,
+        form: &'static str,
+        plan: &PlanNode,
+        variables: &[spargebra::term::Variable],
+        dataset: &DatasetView,
+    ) -> Result<Option<Profiler>, EvaluationError> {
+        self.profile
+            .as_ref()
+            .map(|profile| {
+                Ok(profile.start(QueryExplanation::new(
+                    form,
+                    plan,
+                    &convert_variables(variables),
+                    dataset,
+                )?))
+            })
+            .transpose()
+    }
+
     fn service_handler(&self) -> Rc<dyn ServiceHandler<Error = EvaluationError>> {
-        self.service_handler.clone().unwrap_or_else(|| {
-            if cfg!(feature = "http_client") {
-                Rc::new(service::SimpleServiceHandler::new(self.http_timeout))
-            } else {
-                Rc::new(EmptyServiceHandler)
-            }
-        })
+        self.service_handler
+            .clone()
+            .unwrap_or_else(|| default_service_handler(self.http_timeout))
+    }
+
+    /// Returns `false` if the query results might not only depend on the store content
+    pub(crate) fn is_cacheable(&self) -> bool {
+        self.service_handler.is_none() && self.custom_functions.is_empty() && self.profile.is_none()
+    }
+
+    /// Sets the thread pool used if [`QueryOptions::with_parallelism`] is set
+    pub(crate) fn with_thread_pool(mut self, thread_pool: Arc<ThreadPool>) -> Self {
+        self.thread_pool = Some(thread_pool);
+        self
+    }
+
+    fn parallelism(&self) -> Option<Rc<Parallelism>> {
+        let max_threads = self.max_threads?;
+        let thread_pool = self.thread_pool.clone()?;
+        // Custom service handlers are not thread safe
+        let http_timeout = if self.service_handler.is_none() {
+            Some(self.http_timeout)
+        } else {
+            None
+        };
+        Some(Rc::new(Parallelism::new(
+            thread_pool,
+            max_threads,
+            http_timeout,
+        )))
     }
 }
 
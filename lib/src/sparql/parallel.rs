@@ -0,0 +1,300 @@
+//! Evaluation of independent plan subtrees on a thread pool.
+
+use crate::model::xsd::DateTime;
+use crate::sparql::cancellation::Interrupter;
+use crate::sparql::dataset::SharedDatasetView;
+use crate::sparql::error::EvaluationError;
+use crate::sparql::limits::{Reservation, ResourceLimits};
+use crate::sparql::plan::EncodedTuple;
+use oxiri::Iri;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, sync_channel, Receiver, Sender, SyncSender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread::Builder;
+use std::time::Duration;
+use std::vec;
+
+/// Number of tuples sent at once by the threads
+pub const BATCH_SIZE: usize = 256;
+
+/// Number of batches a thread might compute in advance before waiting for the evaluation thread
+const MAX_QUEUED_BATCHES: usize = 4;
+
+type Job = Box<dyn FnOnce() + Send>;
+type Batch = Vec<Result<EncodedTuple, EvaluationError>>;
+type TupleIterator = Box<dyn Iterator<Item = Result<EncodedTuple, EvaluationError>>>;
+
+/// The thread pool of an evaluation and the configuration shared by its threads.
+pub struct Parallelism {
+    pool: Arc<ThreadPool>,
+    max_threads: usize,
+    running: Arc<AtomicUsize>,
+    http_timeout: Option<Option<Duration>>,
+}
+
+impl Parallelism {
+    /// At most `max_threads` jobs of the evaluation are running or waiting on the pool at the same time.
+    ///
+    /// `http_timeout` should be set if the default service handler is used.
+    /// Custom service handlers are not thread safe so `SERVICE` calls are then only done on the evaluation thread.
+    pub fn new(
+        pool: Arc<ThreadPool>,
+        max_threads: usize,
+        http_timeout: Option<Option<Duration>>,
+    ) -> Self {
+        Self {
+            pool,
+            max_threads,
+            running: Arc::new(AtomicUsize::new(0)),
+            http_timeout,
+        }
+    }
+
+    /// The HTTP timeout of the default service handler if `SERVICE` calls are allowed on the pool threads
+    pub fn http_timeout(&self) -> Option<Option<Duration>> {
+        self.http_timeout
+    }
+
+    /// Reserves one of the `max_threads` slots of the evaluation.
+    ///
+    /// Returns `None` if they are all used: the job should then be done on the evaluation thread.
+    pub fn acquire(&self) -> Option<JobSlot> {
+        self.running
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |running| {
+                (running < self.max_threads).then(|| running + 1)
+            })
+            .ok()?;
+        Some(JobSlot {
+            running: self.running.clone(),
+        })
+    }
+
+    pub fn execute(&self, slot: JobSlot, job: impl FnOnce() + Send + 'static) {
+        self.pool.execute(self.max_threads, move || {
+            job();
+            drop(slot);
+        })
+    }
+}
+
+/// A running job of an evaluation, released on drop.
+pub struct JobSlot {
+    running: Arc<AtomicUsize>,
+}
+
+impl Drop for JobSlot {
+    fn drop(&mut self) {
+        self.running.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// A pool of threads executing the jobs in submission order, shared by all the evaluations on a store.
+///
+/// The threads are spawned lazily, up to the largest number of threads requested by an evaluation,
+/// and stop when the pool is dropped.
+pub struct ThreadPool {
+    threads: AtomicUsize,
+    sender: Mutex<Sender<Job>>,
+    receiver: Arc<Mutex<Receiver<Job>>>,
+}
+
+impl Default for ThreadPool {
+    fn default() -> Self {
+        let (sender, receiver) = channel();
+        Self {
+            threads: AtomicUsize::new(0),
+            sender: Mutex::new(sender),
+            receiver: Arc::new(Mutex::new(receiver)),
+        }
+    }
+}
+
+impl ThreadPool {
+    fn execute(&self, max_threads: usize, job: impl FnOnce() + Send + 'static) {
+        if self
+            .threads
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |threads| {
+                (threads < max_threads).then(|| threads + 1)
+            })
+            .is_ok()
+        {
+            let receiver = self.receiver.clone();
+            let spawned = Builder::new()
+                .name("oxigraph-query".to_owned())
+                .spawn(move || loop {
+                    let job = receiver.lock().unwrap().recv();
+                    match job {
+                        Ok(job) => job(),
+                        Err(_) => return, // The pool is dropped
+                    }
+                });
+            if spawned.is_err() {
+                self.threads.fetch_sub(1, Ordering::AcqRel);
+            }
+        }
+        if self.threads.load(Ordering::Acquire) == 0 {
+            // Threads are not available on this platform
+            job();
+        } else if let Err(error) = self.sender.lock().unwrap().send(Box::new(job)) {
+            // The receiver is owned by the pool so it is never dropped here
+            (error.0)();
+        }
+    }
+}
+
+/// Everything a pool thread needs to build its own evaluator.
+pub struct WorkerContext {
+    pub dataset: SharedDatasetView,
+    pub base_iri: Option<Iri<String>>,
+    pub now: DateTime,
+    pub http_timeout: Option<Duration>,
+    pub interrupter: Interrupter,
+    pub limits: ResourceLimits,
+}
+
+/// Sends the tuples computed by a pool thread to the evaluation thread.
+///
+/// The queued tuples are counted in the memory used by the evaluation.
+pub struct TupleSender {
+    sender: SyncSender<(Batch, Reservation)>,
+    batch: Batch,
+    limits: ResourceLimits,
+    reservation: Reservation,
+    started: Arc<AtomicBool>,
+}
+
+impl TupleSender {
+    /// Returns `false` if the job has already been done by the evaluation thread
+    /// or if the receiver has been dropped.
+    pub fn start(&self) -> bool {
+        !self.started.swap(true, Ordering::AcqRel)
+    }
+
+    /// Returns `false` if the receiver has been dropped or if a limit is exceeded and the evaluation should stop.
+    ///
+    /// Waits if the evaluation thread is too far behind.
+    pub fn send(&mut self, tuple: Result<EncodedTuple, EvaluationError>) -> bool {
+        if let Ok(tuple) = &tuple {
+            if let Err(error) = self.reservation.add(tuple) {
+                self.batch.push(Err(error));
+                self.flush();
+                return false;
+            }
+        }
+        self.batch.push(tuple);
+        if self.batch.len() < BATCH_SIZE {
+            return true;
+        }
+        self.flush()
+    }
+
+    pub fn flush(&mut self) -> bool {
+        if self.batch.is_empty() {
+            return true;
+        }
+        let batch = std::mem::replace(&mut self.batch, Vec::with_capacity(BATCH_SIZE));
+        let reservation = std::mem::replace(&mut self.reservation, self.limits.reserve());
+        self.sender.send((batch, reservation)).is_ok()
+    }
+}
+
+/// Iterates on the tuples computed by a pool thread.
+///
+/// If the job has not been started by the pool when the next tuple is needed,
+/// it is done by the evaluation thread itself to never wait for the jobs queued behind busy threads.
+pub struct TupleReceiver {
+    receiver: Receiver<(Batch, Reservation)>,
+    buffer: vec::IntoIter<Result<EncodedTuple, EvaluationError>>,
+    /// Keeps the memory of the buffered tuples reserved
+    buffer_reservation: Option<Reservation>,
+    started: Arc<AtomicBool>,
+    fallback: Option<Box<dyn FnOnce() -> TupleIterator>>,
+    inline: Option<TupleIterator>,
+}
+
+impl TupleReceiver {
+    /// Returns the tuples already computed without waiting for the other ones.
+    ///
+    /// Returns `None` if all the tuples have been received.
+    pub fn try_next_batch(&mut self) -> Option<Batch> {
+        if self.inline.is_some() {
+            return self.next().map(|tuple| vec![tuple]);
+        }
+        let mut batch = self.buffer.by_ref().collect::<Vec<_>>();
+        self.buffer_reservation = None;
+        loop {
+            match self.receiver.try_recv() {
+                Ok((received, _)) => batch.extend(received),
+                Err(TryRecvError::Empty) => return Some(batch),
+                Err(TryRecvError::Disconnected) => {
+                    return if batch.is_empty() { None } else { Some(batch) }
+                }
+            }
+        }
+    }
+}
+
+impl Iterator for TupleReceiver {
+    type Item = Result<EncodedTuple, EvaluationError>;
+
+    fn next(&mut self) -> Option<Result<EncodedTuple, EvaluationError>> {
+        loop {
+            if let Some(inline) = &mut self.inline {
+                return inline.next();
+            }
+            if let Some(tuple) = self.buffer.next() {
+                return Some(tuple);
+            }
+            self.buffer_reservation = None;
+            let (batch, reservation) = match self.receiver.try_recv() {
+                Ok(received) => received,
+                Err(TryRecvError::Empty) => {
+                    if !self.started.swap(true, Ordering::AcqRel) {
+                        // The pool has not started the job yet
+                        self.inline = self.fallback.take().map(|fallback| fallback());
+                        continue;
+                    }
+                    self.receiver.recv().ok()?
+                }
+                Err(TryRecvError::Disconnected) => return None,
+            };
+            self.buffer = batch.into_iter();
+            self.buffer_reservation = Some(reservation);
+        }
+    }
+}
+
+impl Drop for TupleReceiver {
+    fn drop(&mut self) {
+        // The job does not need to be started anymore
+        self.started.store(true, Ordering::Release);
+    }
+}
+
+/// Builds the channel between a job and the evaluation thread.
+///
+/// `fallback` evaluates the same tuples as the job on the evaluation thread.
+pub fn tuple_channel(
+    limits: &ResourceLimits,
+    fallback: impl FnOnce() -> TupleIterator + 'static,
+) -> (TupleSender, TupleReceiver) {
+    let (sender, receiver) = sync_channel(MAX_QUEUED_BATCHES);
+    let started = Arc::new(AtomicBool::new(false));
+    (
+        TupleSender {
+            sender,
+            batch: Vec::with_capacity(BATCH_SIZE),
+            limits: limits.clone(),
+            reservation: limits.reserve(),
+            started: started.clone(),
+        },
+        TupleReceiver {
+            receiver,
+            buffer: Vec::new().into_iter(),
+            buffer_reservation: None,
+            started,
+            fallback: Some(Box::new(fallback)),
+            inline: None,
+        },
+    )
+}
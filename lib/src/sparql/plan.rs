@@ -1,11 +1,13 @@
+use crate::model::geo::Rect;
 use crate::model::NamedNode;
 use crate::sparql::model::Variable;
 use crate::storage::numeric_encoder::EncodedTerm;
+use crate::storage::text::TextToken;
 use spargebra::algebra::GraphPattern;
 use std::cmp::max;
 use std::collections::btree_map::Entry;
 use std::collections::{BTreeMap, BTreeSet};
-use std::rc::Rc;
+use std::sync::Arc;
 
 #[derive(Eq, PartialEq, Debug, Clone, Hash)]
 pub enum PlanNode {
@@ -14,9 +16,9 @@ pub enum PlanNode {
     },
     Service {
         service_name: PatternValue,
-        variables: Rc<Vec<Variable>>,
+        variables: Arc<Vec<Variable>>,
         child: Box<Self>,
-        graph_pattern: Rc<GraphPattern>,
+        graph_pattern: Arc<GraphPattern>,
         silent: bool,
     },
     QuadPattern {
@@ -27,15 +29,38 @@ pub enum PlanNode {
     },
     PathPattern {
         subject: PatternValue,
-        path: Rc<PlanPropertyPath>,
+        path: Arc<PlanPropertyPath>,
         object: PatternValue,
         graph_name: PatternValue,
     },
+    /// The literals containing all the tokens according to the full-text index
+    TextSearch {
+        literal: PatternValue,
+        tokens: Vec<TextToken>,
+    },
+    /// The WKT literals whose bounding box might intersect the given one according to the spatial index
+    GeoSearch {
+        literal: PatternValue,
+        bounding_box: Rect,
+    },
     /// Streams left and materializes right join
     HashJoin {
         left: Box<Self>,
         right: Box<Self>,
     },
+    /// Streams both sides alternately, materializing each of them to probe the tuples of the other one.
+    ///
+    /// Returns its first results without waiting for one of the sides to be fully evaluated.
+    SymmetricHashJoin {
+        left: Box<Self>,
+        right: Box<Self>,
+    },
+    /// Joins two inputs sorted by the same variable by iterating on them in parallel
+    MergeJoin {
+        left: Box<Self>,
+        right: Box<Self>,
+        key: usize,
+    },
     /// Right nested in left loop
     ForLoopJoin {
         left: Box<Self>,
@@ -57,7 +82,7 @@ pub enum PlanNode {
     LeftJoin {
         left: Box<Self>,
         right: Box<Self>,
-        possible_problem_vars: Rc<Vec<usize>>, //Variables that should not be part of the entry of the left join
+        possible_problem_vars: Arc<Vec<usize>>, //Variables that should not be part of the entry of the left join
     },
     Extend {
         child: Box<Self>,
@@ -85,13 +110,15 @@ pub enum PlanNode {
     },
     Project {
         child: Box<Self>,
-        mapping: Rc<Vec<(usize, usize)>>, // pairs of (variable key in child, variable key in output)
+        mapping: Arc<Vec<(usize, usize)>>, // pairs of (variable key in child, variable key in output)
+        child_variables: Arc<Vec<Variable>>, // names of the variables in child, only used for explanations
     },
     Aggregate {
         // By definition the group by key are the range 0..key_mapping.len()
         child: Box<Self>,
-        key_mapping: Rc<Vec<(usize, usize)>>, // aggregate key pairs of (variable key in child, variable key in output)
-        aggregates: Rc<Vec<(PlanAggregation, usize)>>,
+        key_mapping: Arc<Vec<(usize, usize)>>, // aggregate key pairs of (variable key in child, variable key in output)
+        aggregates: Arc<Vec<(PlanAggregation, usize)>>,
+        child_variables: Arc<Vec<Variable>>, // names of the variables in child, only used for explanations
     },
 }
 
@@ -151,6 +178,11 @@ impl PlanNode {
                     callback(*var);
                 }
             }
+            PlanNode::TextSearch { literal, .. } | PlanNode::GeoSearch { literal, .. } => {
+                if let PatternValue::Variable(var) = literal {
+                    callback(*var);
+                }
+            }
             PlanNode::Filter { child, expression } => {
                 expression.lookup_used_variables(callback);
                 child.lookup_used_variables(callback);
@@ -161,6 +193,8 @@ impl PlanNode {
                 }
             }
             PlanNode::HashJoin { left, right }
+            | PlanNode::SymmetricHashJoin { left, right }
+            | PlanNode::MergeJoin { left, right, .. }
             | PlanNode::ForLoopJoin { left, right, .. }
             | PlanNode::AntiJoin { left, right }
             | PlanNode::LeftJoin { left, right, .. } => {
@@ -191,7 +225,7 @@ impl PlanNode {
                 }
                 child.lookup_used_variables(callback);
             }
-            PlanNode::Project { mapping, child } => {
+            PlanNode::Project { mapping, child, .. } => {
                 let child_bound = child.used_variables();
                 for (child_i, output_i) in mapping.iter() {
                     if child_bound.contains(child_i) {
@@ -214,6 +248,62 @@ impl PlanNode {
         }
     }
 
+    /// Returns if the node could be evaluated on another thread.
+    ///
+    /// Custom functions are not thread safe and `SERVICE` calls are only allowed if `allow_service` is `true`.
+    pub fn is_parallelizable(&self, allow_service: bool) -> bool {
+        match self {
+            PlanNode::StaticBindings { .. }
+            | PlanNode::QuadPattern { .. }
+            | PlanNode::PathPattern { .. }
+            | PlanNode::TextSearch { .. }
+            | PlanNode::GeoSearch { .. } => true,
+            PlanNode::Service { .. } => allow_service,
+            PlanNode::Filter { child, expression }
+            | PlanNode::Extend {
+                child, expression, ..
+            } => {
+                child.is_parallelizable(allow_service)
+                    && expression.is_parallelizable(allow_service)
+            }
+            PlanNode::Union { children } => children
+                .iter()
+                .all(|child| child.is_parallelizable(allow_service)),
+            PlanNode::HashJoin { left, right }
+            | PlanNode::SymmetricHashJoin { left, right }
+            | PlanNode::MergeJoin { left, right, .. }
+            | PlanNode::ForLoopJoin { left, right, .. }
+            | PlanNode::AntiJoin { left, right }
+            | PlanNode::LeftJoin { left, right, .. } => {
+                left.is_parallelizable(allow_service) && right.is_parallelizable(allow_service)
+            }
+            PlanNode::Sort { child, by } => {
+                child.is_parallelizable(allow_service)
+                    && by.iter().all(|comparator| match comparator {
+                        Comparator::Asc(e) | Comparator::Desc(e) => {
+                            e.is_parallelizable(allow_service)
+                        }
+                    })
+            }
+            PlanNode::HashDeduplicate { child }
+            | PlanNode::Reduced { child }
+            | PlanNode::Skip { child, .. }
+            | PlanNode::Limit { child, .. }
+            | PlanNode::Project { child, .. } => child.is_parallelizable(allow_service),
+            PlanNode::Aggregate {
+                child, aggregates, ..
+            } => {
+                child.is_parallelizable(allow_service)
+                    && aggregates.iter().all(|(aggregate, _)| {
+                        aggregate
+                            .parameter
+                            .as_ref()
+                            .map_or(true, |e| e.is_parallelizable(allow_service))
+                    })
+            }
+        }
+    }
+
     /// Returns subset of the set of variables that are always bound in the result set
     ///
     /// (subset because this function is not perfect yet)
@@ -285,6 +375,11 @@ impl PlanNode {
                     callback(*var);
                 }
             }
+            PlanNode::TextSearch { literal, .. } | PlanNode::GeoSearch { literal, .. } => {
+                if let PatternValue::Variable(var) = literal {
+                    callback(*var);
+                }
+            }
             PlanNode::Filter { child, .. } => {
                 //TODO: have a look at the expression to know if it filters out unbound variables
                 child.lookup_always_bound_variables(callback);
@@ -300,7 +395,10 @@ impl PlanNode {
                     }
                 }
             }
-            PlanNode::HashJoin { left, right } | PlanNode::ForLoopJoin { left, right, .. } => {
+            PlanNode::HashJoin { left, right }
+            | PlanNode::SymmetricHashJoin { left, right }
+            | PlanNode::MergeJoin { left, right, .. }
+            | PlanNode::ForLoopJoin { left, right, .. } => {
                 left.lookup_always_bound_variables(callback);
                 right.lookup_always_bound_variables(callback);
             }
@@ -330,7 +428,7 @@ impl PlanNode {
                     child.lookup_always_bound_variables(callback)
                 }
             }
-            PlanNode::Project { mapping, child } => {
+            PlanNode::Project { mapping, child, .. } => {
                 let child_bound = child.always_bound_variables();
                 for (child_i, output_i) in mapping.iter() {
                     if child_bound.contains(child_i) {
@@ -373,7 +471,7 @@ pub struct TriplePatternValue {
 pub enum PlanExpression {
     Constant(EncodedTerm),
     Variable(usize),
-    Exists(Rc<PlanNode>),
+    Exists(Arc<PlanNode>),
     Or(Box<Self>, Box<Self>),
     And(Box<Self>, Box<Self>),
     Equal(Box<Self>, Box<Self>),
@@ -455,6 +553,12 @@ pub enum PlanExpression {
     YearMonthDurationCast(Box<Self>),
     DayTimeDurationCast(Box<Self>),
     StringCast(Box<Self>),
+    SfEquals(Box<Self>, Box<Self>),
+    SfDisjoint(Box<Self>, Box<Self>),
+    SfIntersects(Box<Self>, Box<Self>),
+    SfWithin(Box<Self>, Box<Self>),
+    SfContains(Box<Self>, Box<Self>),
+    Distance(Box<Self>, Box<Self>, Box<Self>),
     CustomFunction(NamedNode, Vec<Self>),
 }
 
@@ -539,6 +643,11 @@ impl PlanExpression {
             | PlanExpression::StrLang(a, b)
             | PlanExpression::StrDt(a, b)
             | PlanExpression::SameTerm(a, b)
+            | PlanExpression::SfEquals(a, b)
+            | PlanExpression::SfDisjoint(a, b)
+            | PlanExpression::SfIntersects(a, b)
+            | PlanExpression::SfWithin(a, b)
+            | PlanExpression::SfContains(a, b)
             | PlanExpression::SubStr(a, b, None)
             | PlanExpression::Regex(a, b, None) => {
                 a.lookup_used_variables(callback);
@@ -548,7 +657,8 @@ impl PlanExpression {
             | PlanExpression::SubStr(a, b, Some(c))
             | PlanExpression::Regex(a, b, Some(c))
             | PlanExpression::Replace(a, b, c, None)
-            | PlanExpression::Triple(a, b, c) => {
+            | PlanExpression::Triple(a, b, c)
+            | PlanExpression::Distance(a, b, c) => {
                 a.lookup_used_variables(callback);
                 b.lookup_used_variables(callback);
                 c.lookup_used_variables(callback);
@@ -571,6 +681,121 @@ impl PlanExpression {
             }
         }
     }
+
+    /// Returns if the expression could be evaluated on another thread.
+    ///
+    /// Custom functions are not thread safe.
+    pub fn is_parallelizable(&self, allow_service: bool) -> bool {
+        match self {
+            PlanExpression::CustomFunction(..) => false,
+            PlanExpression::Variable(_)
+            | PlanExpression::Bound(_)
+            | PlanExpression::Constant(_)
+            | PlanExpression::Rand
+            | PlanExpression::Now
+            | PlanExpression::Uuid
+            | PlanExpression::StrUuid
+            | PlanExpression::BNode(None) => true,
+            PlanExpression::UnaryPlus(e)
+            | PlanExpression::UnaryMinus(e)
+            | PlanExpression::Not(e)
+            | PlanExpression::BNode(Some(e))
+            | PlanExpression::Str(e)
+            | PlanExpression::Lang(e)
+            | PlanExpression::Datatype(e)
+            | PlanExpression::Iri(e)
+            | PlanExpression::Abs(e)
+            | PlanExpression::Ceil(e)
+            | PlanExpression::Floor(e)
+            | PlanExpression::Round(e)
+            | PlanExpression::UCase(e)
+            | PlanExpression::LCase(e)
+            | PlanExpression::StrLen(e)
+            | PlanExpression::EncodeForUri(e)
+            | PlanExpression::Year(e)
+            | PlanExpression::Month(e)
+            | PlanExpression::Day(e)
+            | PlanExpression::Hours(e)
+            | PlanExpression::Minutes(e)
+            | PlanExpression::Seconds(e)
+            | PlanExpression::Timezone(e)
+            | PlanExpression::Tz(e)
+            | PlanExpression::Md5(e)
+            | PlanExpression::Sha1(e)
+            | PlanExpression::Sha256(e)
+            | PlanExpression::Sha384(e)
+            | PlanExpression::Sha512(e)
+            | PlanExpression::IsIri(e)
+            | PlanExpression::IsBlank(e)
+            | PlanExpression::IsLiteral(e)
+            | PlanExpression::IsNumeric(e)
+            | PlanExpression::IsTriple(e)
+            | PlanExpression::Subject(e)
+            | PlanExpression::Predicate(e)
+            | PlanExpression::Object(e)
+            | PlanExpression::BooleanCast(e)
+            | PlanExpression::DoubleCast(e)
+            | PlanExpression::FloatCast(e)
+            | PlanExpression::DecimalCast(e)
+            | PlanExpression::IntegerCast(e)
+            | PlanExpression::DateCast(e)
+            | PlanExpression::TimeCast(e)
+            | PlanExpression::DateTimeCast(e)
+            | PlanExpression::DurationCast(e)
+            | PlanExpression::YearMonthDurationCast(e)
+            | PlanExpression::DayTimeDurationCast(e)
+            | PlanExpression::StringCast(e) => e.is_parallelizable(allow_service),
+            PlanExpression::Or(a, b)
+            | PlanExpression::And(a, b)
+            | PlanExpression::Equal(a, b)
+            | PlanExpression::Greater(a, b)
+            | PlanExpression::GreaterOrEqual(a, b)
+            | PlanExpression::Less(a, b)
+            | PlanExpression::LessOrEqual(a, b)
+            | PlanExpression::Add(a, b)
+            | PlanExpression::Subtract(a, b)
+            | PlanExpression::Multiply(a, b)
+            | PlanExpression::Divide(a, b)
+            | PlanExpression::LangMatches(a, b)
+            | PlanExpression::Contains(a, b)
+            | PlanExpression::StrStarts(a, b)
+            | PlanExpression::StrEnds(a, b)
+            | PlanExpression::StrBefore(a, b)
+            | PlanExpression::StrAfter(a, b)
+            | PlanExpression::StrLang(a, b)
+            | PlanExpression::StrDt(a, b)
+            | PlanExpression::SameTerm(a, b)
+            | PlanExpression::SfEquals(a, b)
+            | PlanExpression::SfDisjoint(a, b)
+            | PlanExpression::SfIntersects(a, b)
+            | PlanExpression::SfWithin(a, b)
+            | PlanExpression::SfContains(a, b)
+            | PlanExpression::SubStr(a, b, None)
+            | PlanExpression::Regex(a, b, None) => {
+                a.is_parallelizable(allow_service) && b.is_parallelizable(allow_service)
+            }
+            PlanExpression::If(a, b, c)
+            | PlanExpression::SubStr(a, b, Some(c))
+            | PlanExpression::Regex(a, b, Some(c))
+            | PlanExpression::Replace(a, b, c, None)
+            | PlanExpression::Triple(a, b, c)
+            | PlanExpression::Distance(a, b, c) => {
+                a.is_parallelizable(allow_service)
+                    && b.is_parallelizable(allow_service)
+                    && c.is_parallelizable(allow_service)
+            }
+            PlanExpression::Replace(a, b, c, Some(d)) => {
+                a.is_parallelizable(allow_service)
+                    && b.is_parallelizable(allow_service)
+                    && c.is_parallelizable(allow_service)
+                    && d.is_parallelizable(allow_service)
+            }
+            PlanExpression::Concat(es) | PlanExpression::Coalesce(es) => {
+                es.iter().all(|e| e.is_parallelizable(allow_service))
+            }
+            PlanExpression::Exists(e) => e.is_parallelizable(allow_service),
+        }
+    }
 }
 
 #[derive(Eq, PartialEq, Debug, Clone, Hash)]
@@ -588,19 +813,19 @@ pub enum PlanAggregationFunction {
     Max,
     Avg,
     Sample,
-    GroupConcat { separator: Rc<String> },
+    GroupConcat { separator: Arc<String> },
 }
 
 #[derive(Eq, PartialEq, Debug, Clone, Hash)]
 pub enum PlanPropertyPath {
     Path(EncodedTerm),
-    Reverse(Rc<Self>),
-    Sequence(Rc<Self>, Rc<Self>),
-    Alternative(Rc<Self>, Rc<Self>),
-    ZeroOrMore(Rc<Self>),
-    OneOrMore(Rc<Self>),
-    ZeroOrOne(Rc<Self>),
-    NegatedPropertySet(Rc<Vec<EncodedTerm>>),
+    Reverse(Arc<Self>),
+    Sequence(Arc<Self>, Arc<Self>),
+    Alternative(Arc<Self>, Arc<Self>),
+    ZeroOrMore(Arc<Self>),
+    OneOrMore(Arc<Self>),
+    ZeroOrOne(Arc<Self>),
+    NegatedPropertySet(Arc<Vec<EncodedTerm>>),
 }
 
 #[derive(Eq, PartialEq, Debug, Clone, Hash)]
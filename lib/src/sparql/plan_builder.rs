@@ -1,20 +1,35 @@
 use crate::error::invalid_data_error;
+use crate::model::geo::{Geometry, Rect, WKT_LITERAL};
 use crate::model::{LiteralRef, NamedNode as OxNamedNode, NamedNodeRef, Term as OxTerm};
 use crate::sparql::dataset::DatasetView;
+use crate::sparql::entailment;
 use crate::sparql::error::EvaluationError;
 use crate::sparql::model::Variable as OxVariable;
 use crate::sparql::plan::*;
-use crate::storage::numeric_encoder::{EncodedTerm, EncodedTriple};
+use crate::storage::numeric_encoder::{Decoder, EncodedTerm, EncodedTriple};
+use crate::storage::statistics::Statistics;
 use rand::random;
 use spargebra::algebra::*;
 use spargebra::term::*;
+use std::cmp::Ordering;
 use std::collections::{BTreeSet, HashMap, HashSet};
 use std::mem::swap;
 use std::rc::Rc;
+use std::sync::Arc;
+
+/// The property used in basic graph patterns to search the full-text index:
+/// `?literal text:match "query"` binds `?literal` to the literals matching the query.
+const TEXT_MATCH: &str = "http://oxigraph.org/text#match";
+/// The namespace of the GeoSPARQL functions
+const GEOF: &str = "http://www.opengis.net/def/function/geosparql/";
 
 pub struct PlanBuilder<'a> {
     dataset: &'a DatasetView,
     custom_functions: &'a HashMap<OxNamedNode, Rc<dyn Fn(&[OxTerm]) -> Option<OxTerm>>>,
+    /// If only the first solutions are going to be consumed, like with `LIMIT` or `EXISTS`
+    streaming: bool,
+    /// If the basic graph patterns should be rewritten to return their RDFS entailments
+    rdfs_entailment: bool,
 }
 
 impl<'a> PlanBuilder<'a> {
@@ -23,11 +38,14 @@ impl<'a> PlanBuilder<'a> {
         pattern: &GraphPattern,
         is_cardinality_meaningful: bool,
         custom_functions: &'a HashMap<OxNamedNode, Rc<dyn Fn(&[OxTerm]) -> Option<OxTerm>>>,
+        rdfs_entailment: bool,
     ) -> Result<(PlanNode, Vec<Variable>), EvaluationError> {
         let mut variables = Vec::default();
         let plan = PlanBuilder {
             dataset,
             custom_functions,
+            streaming: false,
+            rdfs_entailment,
         }
         .build_for_graph_pattern(
             pattern,
@@ -55,10 +73,100 @@ impl<'a> PlanBuilder<'a> {
         PlanBuilder {
             dataset,
             custom_functions,
+            streaming: false,
+            rdfs_entailment: false,
         }
         .build_for_graph_template(template, &mut variables)
     }
 
+    fn convert_variables(variables: &[Variable]) -> Arc<Vec<OxVariable>> {
+        Arc::new(
+            variables
+                .iter()
+                .map(|v| OxVariable::new_unchecked(v.name.clone()))
+                .collect(),
+        )
+    }
+
+    fn build_for_triple_pattern(
+        &mut self,
+        triple: &TriplePattern,
+        variables: &mut Vec<Variable>,
+        graph_name: &PatternValue,
+    ) -> PlanNode {
+        PlanNode::QuadPattern {
+            subject: self.pattern_value_from_term_or_variable(&triple.subject, variables),
+            predicate: self.pattern_value_from_named_node_or_variable(&triple.predicate, variables),
+            object: self.pattern_value_from_term_or_variable(&triple.object, variables),
+            graph_name: graph_name.clone(),
+        }
+    }
+
+    /// Evaluates the full-text searches of the basic graph pattern first
+    /// and then the other triple patterns for each found literal.
+    fn build_for_text_search_bgp(
+        &mut self,
+        patterns: &[TriplePattern],
+        variables: &mut Vec<Variable>,
+        graph_name: &PatternValue,
+    ) -> Result<PlanNode, EvaluationError> {
+        let (searches, others): (Vec<_>, Vec<_>) =
+            patterns.iter().cloned().partition(is_text_match);
+        let mut plan = searches
+            .iter()
+            .map(|search| self.build_for_text_search(search, variables))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .reduce(|left, right| PlanNode::ForLoopJoin {
+                left: Box::new(left),
+                right: Box::new(right),
+            })
+            .ok_or_else(|| EvaluationError::msg("No full-text search in the pattern"))?;
+        if !others.is_empty() {
+            plan = PlanNode::ForLoopJoin {
+                left: Box::new(plan),
+                right: Box::new(self.build_for_graph_pattern(
+                    &GraphPattern::Bgp { patterns: others },
+                    variables,
+                    graph_name,
+                )?),
+            };
+        }
+        Ok(plan)
+    }
+
+    fn build_for_text_search(
+        &mut self,
+        pattern: &TriplePattern,
+        variables: &mut Vec<Variable>,
+    ) -> Result<PlanNode, EvaluationError> {
+        let options = self.dataset.text_index().ok_or_else(|| {
+            EvaluationError::msg(format!(
+                "The full-text index should be enabled in order to use <{}>",
+                TEXT_MATCH
+            ))
+        })?;
+        let tokens = match &pattern.object {
+            TermPattern::Literal(Literal::Simple { value })
+            | TermPattern::Literal(Literal::Typed { value, .. }) => {
+                options.parse_query(value, None)
+            }
+            TermPattern::Literal(Literal::LanguageTaggedString { value, language }) => {
+                options.parse_query(value, Some(language))
+            }
+            _ => {
+                return Err(EvaluationError::msg(format!(
+                    "The object of <{}> should be a literal",
+                    TEXT_MATCH
+                )))
+            }
+        };
+        Ok(PlanNode::TextSearch {
+            literal: self.pattern_value_from_term_or_variable(&pattern.subject, variables),
+            tokens,
+        })
+    }
+
     fn build_for_graph_pattern(
         &mut self,
         pattern: &GraphPattern,
@@ -66,33 +174,50 @@ impl<'a> PlanBuilder<'a> {
         graph_name: &PatternValue,
     ) -> Result<PlanNode, EvaluationError> {
         Ok(match pattern {
-            GraphPattern::Bgp { patterns } => sort_bgp(patterns)
-                .iter()
-                .map(|triple| PlanNode::QuadPattern {
-                    subject: self.pattern_value_from_term_or_variable(&triple.subject, variables),
-                    predicate: self
-                        .pattern_value_from_named_node_or_variable(&triple.predicate, variables),
-                    object: self.pattern_value_from_term_or_variable(&triple.object, variables),
-                    graph_name: graph_name.clone(),
-                })
-                .reduce(Self::new_join)
-                .unwrap_or_else(|| PlanNode::StaticBindings {
-                    tuples: vec![EncodedTuple::with_capacity(variables.len())],
-                }),
+            GraphPattern::Bgp { patterns } if patterns.iter().any(is_text_match) => {
+                self.build_for_text_search_bgp(patterns, variables, graph_name)?
+            }
+            GraphPattern::Bgp { patterns } if self.rdfs_entailment => {
+                let pattern = entailment::rewrite_bgp(patterns);
+                // The rewritten pattern only contains the original triple patterns and the schema lookups
+                let old_rdfs_entailment = self.rdfs_entailment;
+                self.rdfs_entailment = false;
+                let plan = self.build_for_graph_pattern(&pattern, variables, graph_name);
+                self.rdfs_entailment = old_rdfs_entailment;
+                plan?
+            }
+            GraphPattern::Bgp { patterns } => if let Some(statistics) = self.dataset.statistics() {
+                let patterns = patterns
+                    .iter()
+                    .map(|triple| self.build_for_triple_pattern(triple, variables, graph_name))
+                    .collect();
+                join_by_cardinality(patterns, &statistics, self.dataset, self.streaming)
+            } else {
+                sort_bgp(patterns)
+                    .iter()
+                    .map(|triple| self.build_for_triple_pattern(triple, variables, graph_name))
+                    .collect::<Vec<_>>()
+                    .into_iter()
+                    .reduce(|left, right| self.new_join(left, right))
+            }
+            .unwrap_or_else(|| PlanNode::StaticBindings {
+                tuples: vec![EncodedTuple::with_capacity(variables.len())],
+            }),
             GraphPattern::Path {
                 subject,
                 path,
                 object,
             } => PlanNode::PathPattern {
                 subject: self.pattern_value_from_term_or_variable(subject, variables),
-                path: Rc::new(self.build_for_path(path)),
+                path: Arc::new(self.build_for_path(path)),
                 object: self.pattern_value_from_term_or_variable(object, variables),
                 graph_name: graph_name.clone(),
             },
-            GraphPattern::Join { left, right } => Self::new_join(
-                self.build_for_graph_pattern(left, variables, graph_name)?,
-                self.build_for_graph_pattern(right, variables, graph_name)?,
-            ),
+            GraphPattern::Join { left, right } => {
+                let left = self.build_for_graph_pattern(left, variables, graph_name)?;
+                let right = self.build_for_graph_pattern(right, variables, graph_name)?;
+                self.new_join(left, right)
+            }
             GraphPattern::LeftJoin {
                 left,
                 right,
@@ -117,13 +242,17 @@ impl<'a> PlanBuilder<'a> {
                 PlanNode::LeftJoin {
                     left: Box::new(left),
                     right: Box::new(right),
-                    possible_problem_vars: Rc::new(possible_problem_vars.into_iter().collect()),
+                    possible_problem_vars: Arc::new(possible_problem_vars.into_iter().collect()),
                 }
             }
-            GraphPattern::Filter { expr, inner } => Self::push_filter(
-                Box::new(self.build_for_graph_pattern(inner, variables, graph_name)?),
-                Box::new(self.build_for_expression(expr, variables, graph_name)?),
-            ),
+            GraphPattern::Filter { expr, inner } => {
+                let child = self.build_for_graph_pattern(inner, variables, graph_name)?;
+                let expression = self.build_for_expression(expr, variables, graph_name)?;
+                Self::push_filter(
+                    Box::new(self.add_geo_search(child, &expression)),
+                    Box::new(expression),
+                )
+            }
             GraphPattern::Union { left, right } => {
                 //We flatten the UNIONs
                 let mut stack: Vec<&GraphPattern> = vec![left, right];
@@ -157,7 +286,9 @@ impl<'a> PlanBuilder<'a> {
             },
             GraphPattern::Minus { left, right } => PlanNode::AntiJoin {
                 left: Box::new(self.build_for_graph_pattern(left, variables, graph_name)?),
-                right: Box::new(self.build_for_graph_pattern(right, variables, graph_name)?),
+                right: Box::new(self.with_streaming(false, |builder| {
+                    builder.build_for_graph_pattern(right, variables, graph_name)
+                })?),
             },
             GraphPattern::Service {
                 name,
@@ -169,14 +300,9 @@ impl<'a> PlanBuilder<'a> {
                 let service_name = self.pattern_value_from_named_node_or_variable(name, variables);
                 PlanNode::Service {
                     service_name,
-                    variables: Rc::new(
-                        variables
-                            .iter()
-                            .map(|v| OxVariable::new_unchecked(v.name.clone()))
-                            .collect(),
-                    ),
+                    variables: Self::convert_variables(variables),
                     child: Box::new(child),
-                    graph_pattern: Rc::new(inner.as_ref().clone()),
+                    graph_pattern: Arc::new(inner.as_ref().clone()),
                     silent: *silent,
                 }
             }
@@ -189,33 +315,32 @@ impl<'a> PlanBuilder<'a> {
                 let inner_graph_name =
                     self.convert_pattern_value_id(graph_name, variables, &mut inner_variables);
 
+                let child = self.with_streaming(false, |builder| {
+                    builder.build_for_graph_pattern(inner, &mut inner_variables, &inner_graph_name)
+                })?;
+                let key_mapping = by
+                    .iter()
+                    .map(|k| {
+                        (
+                            variable_key(&mut inner_variables, k),
+                            variable_key(variables, k),
+                        )
+                    })
+                    .collect();
+                let aggregates = aggregates
+                    .iter()
+                    .map(|(v, a)| {
+                        Ok((
+                            self.build_for_aggregate(a, &mut inner_variables, graph_name)?,
+                            variable_key(variables, v),
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, EvaluationError>>()?;
                 PlanNode::Aggregate {
-                    child: Box::new(self.build_for_graph_pattern(
-                        inner,
-                        &mut inner_variables,
-                        &inner_graph_name,
-                    )?),
-                    key_mapping: Rc::new(
-                        by.iter()
-                            .map(|k| {
-                                (
-                                    variable_key(&mut inner_variables, k),
-                                    variable_key(variables, k),
-                                )
-                            })
-                            .collect(),
-                    ),
-                    aggregates: Rc::new(
-                        aggregates
-                            .iter()
-                            .map(|(v, a)| {
-                                Ok((
-                                    self.build_for_aggregate(a, &mut inner_variables, graph_name)?,
-                                    variable_key(variables, v),
-                                ))
-                            })
-                            .collect::<Result<Vec<_>, EvaluationError>>()?,
-                    ),
+                    child: Box::new(child),
+                    key_mapping: Arc::new(key_mapping),
+                    aggregates: Arc::new(aggregates),
+                    child_variables: Self::convert_variables(&inner_variables),
                 }
             }
             GraphPattern::Values {
@@ -237,7 +362,9 @@ impl<'a> PlanBuilder<'a> {
                     })
                     .collect();
                 PlanNode::Sort {
-                    child: Box::new(self.build_for_graph_pattern(inner, variables, graph_name)?),
+                    child: Box::new(self.with_streaming(false, |builder| {
+                        builder.build_for_graph_pattern(inner, variables, graph_name)
+                    })?),
                     by: condition?,
                 }
             }
@@ -248,13 +375,11 @@ impl<'a> PlanBuilder<'a> {
                 let mut inner_variables = projection.clone();
                 let inner_graph_name =
                     self.convert_pattern_value_id(graph_name, variables, &mut inner_variables);
+                let child =
+                    self.build_for_graph_pattern(inner, &mut inner_variables, &inner_graph_name)?;
                 PlanNode::Project {
-                    child: Box::new(self.build_for_graph_pattern(
-                        inner,
-                        &mut inner_variables,
-                        &inner_graph_name,
-                    )?),
-                    mapping: Rc::new(
+                    child: Box::new(child),
+                    mapping: Arc::new(
                         projection
                             .iter()
                             .enumerate()
@@ -263,6 +388,7 @@ impl<'a> PlanBuilder<'a> {
                             })
                             .collect(),
                     ),
+                    child_variables: Self::convert_variables(&inner_variables),
                 }
             }
             GraphPattern::Distinct { inner } => PlanNode::HashDeduplicate {
@@ -276,7 +402,10 @@ impl<'a> PlanBuilder<'a> {
                 start,
                 length,
             } => {
-                let mut plan = self.build_for_graph_pattern(inner, variables, graph_name)?;
+                let streaming = self.streaming || length.is_some();
+                let mut plan = self.with_streaming(streaming, |builder| {
+                    builder.build_for_graph_pattern(inner, variables, graph_name)
+                })?;
                 if *start > 0 {
                     plan = PlanNode::Skip {
                         child: Box::new(plan),
@@ -294,33 +423,41 @@ impl<'a> PlanBuilder<'a> {
         })
     }
 
+    fn with_streaming<T>(&mut self, streaming: bool, f: impl FnOnce(&mut Self) -> T) -> T {
+        let old_streaming = self.streaming;
+        self.streaming = streaming;
+        let result = f(self);
+        self.streaming = old_streaming;
+        result
+    }
+
     fn build_for_path(&mut self, path: &PropertyPathExpression) -> PlanPropertyPath {
         match path {
             PropertyPathExpression::NamedNode(p) => {
                 PlanPropertyPath::Path(self.build_named_node(p))
             }
             PropertyPathExpression::Reverse(p) => {
-                PlanPropertyPath::Reverse(Rc::new(self.build_for_path(p)))
+                PlanPropertyPath::Reverse(Arc::new(self.build_for_path(p)))
             }
             PropertyPathExpression::Alternative(a, b) => PlanPropertyPath::Alternative(
-                Rc::new(self.build_for_path(a)),
-                Rc::new(self.build_for_path(b)),
+                Arc::new(self.build_for_path(a)),
+                Arc::new(self.build_for_path(b)),
             ),
             PropertyPathExpression::Sequence(a, b) => PlanPropertyPath::Sequence(
-                Rc::new(self.build_for_path(a)),
-                Rc::new(self.build_for_path(b)),
+                Arc::new(self.build_for_path(a)),
+                Arc::new(self.build_for_path(b)),
             ),
             PropertyPathExpression::ZeroOrMore(p) => {
-                PlanPropertyPath::ZeroOrMore(Rc::new(self.build_for_path(p)))
+                PlanPropertyPath::ZeroOrMore(Arc::new(self.build_for_path(p)))
             }
             PropertyPathExpression::OneOrMore(p) => {
-                PlanPropertyPath::OneOrMore(Rc::new(self.build_for_path(p)))
+                PlanPropertyPath::OneOrMore(Arc::new(self.build_for_path(p)))
             }
             PropertyPathExpression::ZeroOrOne(p) => {
-                PlanPropertyPath::ZeroOrOne(Rc::new(self.build_for_path(p)))
+                PlanPropertyPath::ZeroOrOne(Arc::new(self.build_for_path(p)))
             }
             PropertyPathExpression::NegatedPropertySet(p) => PlanPropertyPath::NegatedPropertySet(
-                Rc::new(p.iter().map(|p| self.build_named_node(p)).collect()),
+                Arc::new(p.iter().map(|p| self.build_named_node(p)).collect()),
             ),
         }
     }
@@ -745,6 +882,8 @@ impl<'a> PlanBuilder<'a> {
                             graph_name,
                             "string",
                         )?
+                    } else if let Some(function) = name.iri.strip_prefix(GEOF) {
+                        self.build_geo_function(function, parameters, variables, graph_name)?
                     } else {
                         return Err(EvaluationError::msg(format!(
                             "Not supported custom function {}",
@@ -761,11 +900,9 @@ impl<'a> PlanBuilder<'a> {
             ),
             Expression::Exists(n) => {
                 let mut variables = variables.clone(); // Do not expose the exists variables outside
-                PlanExpression::Exists(Rc::new(self.build_for_graph_pattern(
-                    n,
-                    &mut variables,
-                    graph_name,
-                )?))
+                PlanExpression::Exists(Arc::new(self.with_streaming(true, |builder| {
+                    builder.build_for_graph_pattern(n, &mut variables, graph_name)
+                })?))
             }
             Expression::Coalesce(parameters) => {
                 PlanExpression::Coalesce(self.expression_list(parameters, variables, graph_name)?)
@@ -795,6 +932,97 @@ impl<'a> PlanBuilder<'a> {
         }
     }
 
+    fn build_geo_function(
+        &mut self,
+        function: &str,
+        parameters: &[Expression],
+        variables: &mut Vec<Variable>,
+        graph_name: &PatternValue,
+    ) -> Result<PlanExpression, EvaluationError> {
+        let arity = match function {
+            "sfEquals" | "sfDisjoint" | "sfIntersects" | "sfWithin" | "sfContains" => 2,
+            "distance" => 3,
+            _ => {
+                return Err(EvaluationError::msg(format!(
+                    "Not supported GeoSPARQL function geof:{}",
+                    function
+                )))
+            }
+        };
+        if parameters.len() != arity {
+            return Err(EvaluationError::msg(format!(
+                "The geof:{} function takes {} parameters",
+                function, arity
+            )));
+        }
+        let a = Box::new(self.build_for_expression(&parameters[0], variables, graph_name)?);
+        let b = Box::new(self.build_for_expression(&parameters[1], variables, graph_name)?);
+        Ok(match function {
+            "sfEquals" => PlanExpression::SfEquals(a, b),
+            "sfDisjoint" => PlanExpression::SfDisjoint(a, b),
+            "sfIntersects" => PlanExpression::SfIntersects(a, b),
+            "sfWithin" => PlanExpression::SfWithin(a, b),
+            "sfContains" => PlanExpression::SfContains(a, b),
+            _ => PlanExpression::Distance(
+                a,
+                b,
+                Box::new(self.build_for_expression(&parameters[2], variables, graph_name)?),
+            ),
+        })
+    }
+
+    /// Looks first in the spatial index for the values of a variable
+    /// if the filter only keeps the geometries intersecting a constant one.
+    fn add_geo_search(&self, child: PlanNode, filter: &PlanExpression) -> PlanNode {
+        if !self.dataset.is_geo_index_enabled() {
+            return child;
+        }
+        match self.geo_search_bounding_box(filter) {
+            // The index only contains the store literals
+            Some((variable, bounding_box)) if is_bound_by_quad_pattern_object(&child, variable) => {
+                PlanNode::ForLoopJoin {
+                    left: Box::new(PlanNode::GeoSearch {
+                        literal: PatternValue::Variable(variable),
+                        bounding_box,
+                    }),
+                    right: Box::new(child),
+                }
+            }
+            _ => child,
+        }
+    }
+
+    /// Finds a variable whose geometry bounding box should intersect a constant bounding box for the filter to pass
+    fn geo_search_bounding_box(&self, filter: &PlanExpression) -> Option<(usize, Rect)> {
+        match filter {
+            PlanExpression::And(a, b) => self
+                .geo_search_bounding_box(a)
+                .or_else(|| self.geo_search_bounding_box(b)),
+            PlanExpression::SfEquals(a, b)
+            | PlanExpression::SfIntersects(a, b)
+            | PlanExpression::SfWithin(a, b)
+            | PlanExpression::SfContains(a, b) => {
+                let (variable, constant) = match (&**a, &**b) {
+                    (PlanExpression::Variable(variable), PlanExpression::Constant(constant))
+                    | (PlanExpression::Constant(constant), PlanExpression::Variable(variable)) => {
+                        (*variable, constant)
+                    }
+                    _ => return None,
+                };
+                match self.dataset.decode_term(constant).ok()? {
+                    OxTerm::Literal(literal) if literal.datatype().as_str() == WKT_LITERAL => {
+                        Some((
+                            variable,
+                            literal.value().parse::<Geometry>().ok()?.bounding_box()?,
+                        ))
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
     fn expression_list(
         &mut self,
         l: &[Expression],
@@ -947,7 +1175,7 @@ impl<'a> PlanBuilder<'a> {
                 separator,
             } => Ok(PlanAggregation {
                 function: PlanAggregationFunction::GroupConcat {
-                    separator: Rc::new(separator.clone().unwrap_or_else(|| " ".to_owned())),
+                    separator: Arc::new(separator.clone().unwrap_or_else(|| " ".to_owned())),
                 },
                 parameter: Some(self.build_for_expression(expr, variables, graph_name)?),
                 distinct: *distinct,
@@ -1085,7 +1313,9 @@ impl<'a> PlanBuilder<'a> {
         match node {
             PlanNode::StaticBindings { .. }
             | PlanNode::QuadPattern { .. }
-            | PlanNode::PathPattern { .. } => (),
+            | PlanNode::PathPattern { .. }
+            | PlanNode::TextSearch { .. }
+            | PlanNode::GeoSearch { .. } => (),
             PlanNode::Filter { child, expression } => {
                 let always_already_bound = child.always_bound_variables();
                 expression.lookup_used_variables(&mut |v| {
@@ -1100,7 +1330,10 @@ impl<'a> PlanBuilder<'a> {
                     self.add_left_join_problematic_variables(child, set);
                 }
             }
-            PlanNode::HashJoin { left, right } | PlanNode::ForLoopJoin { left, right } => {
+            PlanNode::HashJoin { left, right }
+            | PlanNode::SymmetricHashJoin { left, right }
+            | PlanNode::MergeJoin { left, right, .. }
+            | PlanNode::ForLoopJoin { left, right } => {
                 self.add_left_join_problematic_variables(&*left, set);
                 self.add_left_join_problematic_variables(&*right, set);
             }
@@ -1141,7 +1374,7 @@ impl<'a> PlanBuilder<'a> {
                     self.add_left_join_problematic_variables(&*child, set)
                 }
             }
-            PlanNode::Project { mapping, child } => {
+            PlanNode::Project { mapping, child, .. } => {
                 let mut child_bound = BTreeSet::new();
                 self.add_left_join_problematic_variables(&*child, &mut child_bound);
                 for (child_i, output_i) in mapping.iter() {
@@ -1164,8 +1397,14 @@ impl<'a> PlanBuilder<'a> {
         }
     }
 
-    fn new_join(mut left: PlanNode, mut right: PlanNode) -> PlanNode {
-        if Self::is_fit_for_for_loop_join(&left)
+    fn new_join(&self, mut left: PlanNode, mut right: PlanNode) -> PlanNode {
+        if let Some(key) = merge_join_key(&left, &right, self.dataset) {
+            PlanNode::MergeJoin {
+                left: Box::new(left),
+                right: Box::new(right),
+                key,
+            }
+        } else if Self::is_fit_for_for_loop_join(&left)
             && Self::is_fit_for_for_loop_join(&right)
             && Self::has_some_common_variables(&left, &right)
         {
@@ -1177,7 +1416,9 @@ impl<'a> PlanBuilder<'a> {
                 left: Box::new(left),
                 right: Box::new(right),
             }
-        } else {
+        } else if matches!(left, PlanNode::StaticBindings { .. })
+            || matches!(right, PlanNode::StaticBindings { .. })
+        {
             // Let's avoid materializing right if left is already materialized
             // TODO: be smarter and reuse already existing materialization
             if matches!(left, PlanNode::StaticBindings { .. }) {
@@ -1187,6 +1428,24 @@ impl<'a> PlanBuilder<'a> {
                 left: Box::new(left),
                 right: Box::new(right),
             }
+        } else if self.streaming {
+            PlanNode::SymmetricHashJoin {
+                left: Box::new(left),
+                right: Box::new(right),
+            }
+        } else {
+            // We materialize the smallest side
+            if let Some(statistics) = self.dataset.statistics() {
+                if estimate_cardinality(&left, &statistics)
+                    < estimate_cardinality(&right, &statistics)
+                {
+                    swap(&mut left, &mut right);
+                }
+            }
+            PlanNode::HashJoin {
+                left: Box::new(left),
+                right: Box::new(right),
+            }
         }
     }
 
@@ -1203,8 +1462,12 @@ impl<'a> PlanBuilder<'a> {
             PlanNode::StaticBindings { .. }
             | PlanNode::QuadPattern { .. }
             | PlanNode::PathPattern { .. }
+            | PlanNode::TextSearch { .. }
+            | PlanNode::GeoSearch { .. }
             | PlanNode::ForLoopJoin { .. } => true,
-            PlanNode::HashJoin { left, right } => {
+            PlanNode::HashJoin { left, right }
+            | PlanNode::SymmetricHashJoin { left, right }
+            | PlanNode::MergeJoin { left, right, .. } => {
                 Self::is_fit_for_for_loop_join(left) && Self::is_fit_for_for_loop_join(right)
             }
             PlanNode::Filter { child, .. } | PlanNode::Extend { child, .. } => {
@@ -1224,6 +1487,32 @@ impl<'a> PlanBuilder<'a> {
         }
     }
 
+    fn push_filter_in_join(
+        left: Box<PlanNode>,
+        right: Box<PlanNode>,
+        filter: Box<PlanExpression>,
+        filter_variables: &BTreeSet<usize>,
+        join: impl Fn(Box<PlanNode>, Box<PlanNode>) -> PlanNode,
+    ) -> PlanNode {
+        if filter_variables.iter().all(|v| left.is_variable_bound(*v)) {
+            if filter_variables.iter().all(|v| right.is_variable_bound(*v)) {
+                join(
+                    Box::new(Self::push_filter(left, filter.clone())),
+                    Box::new(Self::push_filter(right, filter)),
+                )
+            } else {
+                join(Box::new(Self::push_filter(left, filter)), right)
+            }
+        } else if filter_variables.iter().all(|v| right.is_variable_bound(*v)) {
+            join(left, Box::new(Self::push_filter(right, filter)))
+        } else {
+            PlanNode::Filter {
+                child: Box::new(join(left, right)),
+                expression: filter,
+            }
+        }
+    }
+
     fn push_filter(node: Box<PlanNode>, filter: Box<PlanExpression>) -> PlanNode {
         if let PlanExpression::And(f1, f2) = *filter {
             return Self::push_filter(Box::new(Self::push_filter(node, f1)), f2);
@@ -1234,29 +1523,19 @@ impl<'a> PlanBuilder<'a> {
         });
         match *node {
             PlanNode::HashJoin { left, right } => {
-                if filter_variables.iter().all(|v| left.is_variable_bound(*v)) {
-                    if filter_variables.iter().all(|v| right.is_variable_bound(*v)) {
-                        PlanNode::HashJoin {
-                            left: Box::new(Self::push_filter(left, filter.clone())),
-                            right: Box::new(Self::push_filter(right, filter)),
-                        }
-                    } else {
-                        PlanNode::HashJoin {
-                            left: Box::new(Self::push_filter(left, filter)),
-                            right,
-                        }
-                    }
-                } else if filter_variables.iter().all(|v| right.is_variable_bound(*v)) {
-                    PlanNode::HashJoin {
-                        left,
-                        right: Box::new(Self::push_filter(right, filter)),
-                    }
-                } else {
-                    PlanNode::Filter {
-                        child: Box::new(PlanNode::HashJoin { left, right }),
-                        expression: filter,
-                    }
-                }
+                Self::push_filter_in_join(left, right, filter, &filter_variables, |left, right| {
+                    PlanNode::HashJoin { left, right }
+                })
+            }
+            PlanNode::SymmetricHashJoin { left, right } => {
+                Self::push_filter_in_join(left, right, filter, &filter_variables, |left, right| {
+                    PlanNode::SymmetricHashJoin { left, right }
+                })
+            }
+            PlanNode::MergeJoin { left, right, key } => {
+                Self::push_filter_in_join(left, right, filter, &filter_variables, |left, right| {
+                    PlanNode::MergeJoin { left, right, key }
+                })
             }
             PlanNode::ForLoopJoin { left, right } => {
                 if filter_variables.iter().all(|v| left.is_variable_bound(*v)) {
@@ -1391,6 +1670,26 @@ fn slice_key<T: Eq>(slice: &[T], element: &T) -> Option<usize> {
     None
 }
 
+/// If all the solutions of the node have the variable bound to the object of a quad of the dataset
+fn is_bound_by_quad_pattern_object(node: &PlanNode, variable: usize) -> bool {
+    match node {
+        PlanNode::QuadPattern { object, .. } => *object == PatternValue::Variable(variable),
+        PlanNode::HashJoin { left, right }
+        | PlanNode::SymmetricHashJoin { left, right }
+        | PlanNode::MergeJoin { left, right, .. }
+        | PlanNode::ForLoopJoin { left, right } => {
+            is_bound_by_quad_pattern_object(left, variable)
+                || is_bound_by_quad_pattern_object(right, variable)
+        }
+        PlanNode::Filter { child, .. } => is_bound_by_quad_pattern_object(child, variable),
+        _ => false,
+    }
+}
+
+fn is_text_match(pattern: &TriplePattern) -> bool {
+    matches!(&pattern.predicate, NamedNodePattern::NamedNode(predicate) if predicate.iri == TEXT_MATCH)
+}
+
 fn sort_bgp(p: &[TriplePattern]) -> Vec<&TriplePattern> {
     let mut assigned_variables = HashSet::default();
     let mut assigned_blank_nodes = HashSet::default();
@@ -1408,6 +1707,271 @@ fn sort_bgp(p: &[TriplePattern]) -> Vec<&TriplePattern> {
     new_p
 }
 
+/// Joins the quad patterns greedily, starting with the most selective ones according to the store statistics.
+///
+/// Each pattern is joined using a [`PlanNode::ForLoopJoin`] if probing it for each already computed solution
+/// is estimated to be cheaper than fully evaluating it and using a [`PlanNode::HashJoin`].
+/// If both sides are sorted by the same variable a [`PlanNode::MergeJoin`] is used instead of an hash join.
+/// The hash joins materialize their smallest side or are [`PlanNode::SymmetricHashJoin`] if `streaming` is set.
+fn join_by_cardinality(
+    mut patterns: Vec<PlanNode>,
+    statistics: &Statistics,
+    dataset: &DatasetView,
+    streaming: bool,
+) -> Option<PlanNode> {
+    let mut bound_variables = BTreeSet::new();
+    let mut plan: Option<(PlanNode, f64)> = None;
+    while !patterns.is_empty() {
+        // We prefer patterns connected to the already joined ones to avoid cartesian products
+        let position = patterns
+            .iter()
+            .map(|pattern| {
+                (
+                    plan.is_some() && !has_bound_variable(pattern, &bound_variables),
+                    estimate_quad_pattern_cardinality(pattern, &bound_variables, statistics),
+                )
+            })
+            .enumerate()
+            .min_by(|(_, (d1, c1)), (_, (d2, c2))| {
+                d1.cmp(d2)
+                    .then_with(|| c1.partial_cmp(c2).unwrap_or(Ordering::Equal))
+            })?
+            .0;
+        let pattern = patterns.remove(position);
+        let cardinality_per_solution =
+            estimate_quad_pattern_cardinality(&pattern, &bound_variables, statistics);
+        pattern.lookup_used_variables(&mut |v| {
+            bound_variables.insert(v);
+        });
+        plan = Some(if let Some((left, left_cardinality)) = plan {
+            let cardinality =
+                estimate_quad_pattern_cardinality(&pattern, &BTreeSet::new(), statistics);
+            let for_loop_cost = left_cardinality * (1. + cardinality_per_solution);
+            let hash_cost = left_cardinality + cardinality;
+            let join = if for_loop_cost <= hash_cost
+                && PlanBuilder::has_some_common_variables(&left, &pattern)
+            {
+                PlanNode::ForLoopJoin {
+                    left: Box::new(left),
+                    right: Box::new(pattern),
+                }
+            } else if let Some(key) = merge_join_key(&left, &pattern, dataset) {
+                PlanNode::MergeJoin {
+                    left: Box::new(left),
+                    right: Box::new(pattern),
+                    key,
+                }
+            } else if streaming {
+                PlanNode::SymmetricHashJoin {
+                    left: Box::new(left),
+                    right: Box::new(pattern),
+                }
+            } else if cardinality <= left_cardinality {
+                PlanNode::HashJoin {
+                    left: Box::new(left),
+                    right: Box::new(pattern),
+                }
+            } else {
+                PlanNode::HashJoin {
+                    left: Box::new(pattern),
+                    right: Box::new(left),
+                }
+            };
+            (join, left_cardinality * cardinality_per_solution)
+        } else {
+            (pattern, cardinality_per_solution)
+        });
+    }
+    plan.map(|(plan, _)| plan)
+}
+
+fn has_bound_variable(pattern: &PlanNode, bound_variables: &BTreeSet<usize>) -> bool {
+    let mut found = false;
+    pattern.lookup_used_variables(&mut |v| found |= bound_variables.contains(&v));
+    found
+}
+
+/// Returns the variable both nodes solutions are sorted by if it exists.
+fn merge_join_key(left: &PlanNode, right: &PlanNode, dataset: &DatasetView) -> Option<usize> {
+    let key = sort_variable(left, dataset)?;
+    if sort_variable(right, dataset)? == key {
+        Some(key)
+    } else {
+        None
+    }
+}
+
+/// Returns the variable the solutions of the node are sorted by when evaluated without input bindings.
+///
+/// Quad patterns are evaluated using a single index scan if their graph is known.
+/// Their solutions are then sorted by the first quad term not fixed by the pattern in the index.
+fn sort_variable(node: &PlanNode, dataset: &DatasetView) -> Option<usize> {
+    match node {
+        PlanNode::QuadPattern {
+            subject,
+            predicate,
+            object,
+            graph_name,
+        } => {
+            if let PatternValue::Constant(graph_name) = graph_name {
+                if !dataset.is_graph_sorted(graph_name) {
+                    return None;
+                }
+            } else {
+                return None;
+            }
+            let is_bound = |value: &PatternValue| matches!(value, PatternValue::Constant(_));
+            let first_free = match (is_bound(subject), is_bound(predicate), is_bound(object)) {
+                // The first free term in the spo, pos or osp index order, depending on the bound terms
+                (false, false, false) | (false, true, true) | (false, false, true) => subject,
+                (true, false, false) | (true, false, true) => predicate,
+                (true, true, false) | (false, true, false) => object,
+                (true, true, true) => return None,
+            };
+            if let PatternValue::Variable(v) = first_free {
+                Some(*v)
+            } else {
+                None
+            }
+        }
+        PlanNode::MergeJoin { key, .. } => Some(*key),
+        PlanNode::Filter { child, .. } | PlanNode::Extend { child, .. } => {
+            sort_variable(child, dataset)
+        }
+        _ => None,
+    }
+}
+
+/// Roughly estimates the number of solutions of a plan node.
+#[allow(clippy::cast_precision_loss)]
+fn estimate_cardinality(node: &PlanNode, statistics: &Statistics) -> f64 {
+    match node {
+        PlanNode::StaticBindings { tuples } => tuples.len() as f64,
+        PlanNode::QuadPattern { .. } => {
+            estimate_quad_pattern_cardinality(node, &BTreeSet::new(), statistics)
+        }
+        PlanNode::Service { .. } | PlanNode::PathPattern { .. } => statistics.quad_count() as f64,
+        // Full-text and spatial searches are expected to be selective
+        PlanNode::TextSearch { .. } | PlanNode::GeoSearch { .. } => {
+            (statistics.object_count() as f64).sqrt()
+        }
+        PlanNode::HashJoin { left, right }
+        | PlanNode::SymmetricHashJoin { left, right }
+        | PlanNode::MergeJoin { left, right, .. }
+        | PlanNode::ForLoopJoin { left, right } => {
+            let left_cardinality = estimate_cardinality(left, statistics);
+            let right_cardinality = estimate_cardinality(right, statistics);
+            if PlanBuilder::has_some_common_variables(left, right) {
+                left_cardinality.max(right_cardinality)
+            } else {
+                left_cardinality * right_cardinality
+            }
+        }
+        PlanNode::AntiJoin { left, .. } | PlanNode::LeftJoin { left, .. } => {
+            estimate_cardinality(left, statistics)
+        }
+        PlanNode::Union { children } => children
+            .iter()
+            .map(|child| estimate_cardinality(child, statistics))
+            .sum(),
+        PlanNode::Filter { child, .. }
+        | PlanNode::Extend { child, .. }
+        | PlanNode::Sort { child, .. }
+        | PlanNode::HashDeduplicate { child }
+        | PlanNode::Reduced { child }
+        | PlanNode::Project { child, .. } => estimate_cardinality(child, statistics),
+        PlanNode::Skip { child, count } => {
+            (estimate_cardinality(child, statistics) - *count as f64).max(0.)
+        }
+        PlanNode::Limit { child, count } => {
+            estimate_cardinality(child, statistics).min(*count as f64)
+        }
+        PlanNode::Aggregate {
+            child, key_mapping, ..
+        } => {
+            if key_mapping.is_empty() {
+                1.
+            } else {
+                estimate_cardinality(child, statistics)
+            }
+        }
+    }
+}
+
+/// Estimates the number of solutions of a quad pattern given the already bound variables.
+///
+/// The statistics are only refreshed by [`Store::optimize`](crate::store::Store::optimize):
+/// the predicates and graphs unknown to them are assumed to have an average size.
+#[allow(clippy::cast_precision_loss)]
+fn estimate_quad_pattern_cardinality(
+    pattern: &PlanNode,
+    bound_variables: &BTreeSet<usize>,
+    statistics: &Statistics,
+) -> f64 {
+    let (subject, predicate, object, graph_name) = if let PlanNode::QuadPattern {
+        subject,
+        predicate,
+        object,
+        graph_name,
+    } = pattern
+    {
+        (subject, predicate, object, graph_name)
+    } else {
+        return statistics.quad_count() as f64;
+    };
+    let is_bound = |value: &PatternValue| match value {
+        PatternValue::Constant(_) => true,
+        PatternValue::Variable(v) => bound_variables.contains(v),
+        PatternValue::Triple(_) => false,
+    };
+    let (mut cardinality, subject_count, object_count) =
+        if let PatternValue::Constant(predicate) = predicate {
+            if let Some(predicate) = statistics.predicate(predicate) {
+                (
+                    predicate.quad_count as f64,
+                    predicate.subject_count,
+                    predicate.object_count,
+                )
+            } else {
+                (
+                    statistics.quad_count() as f64 / statistics.predicate_count().max(1) as f64,
+                    statistics.subject_count(),
+                    statistics.object_count(),
+                )
+            }
+        } else {
+            let mut cardinality = statistics.quad_count() as f64;
+            if is_bound(predicate) {
+                cardinality /= statistics.predicate_count().max(1) as f64;
+            }
+            (
+                cardinality,
+                statistics.subject_count(),
+                statistics.object_count(),
+            )
+        };
+    if is_bound(subject) {
+        cardinality /= subject_count.max(1) as f64;
+    }
+    if is_bound(object) {
+        cardinality /= object_count.max(1) as f64;
+    }
+    match graph_name {
+        PatternValue::Constant(graph_name) if !graph_name.is_default_graph() => {
+            if let Some(graph_quad_count) = statistics.named_graph_quad_count(graph_name) {
+                cardinality *= graph_quad_count as f64 / statistics.quad_count().max(1) as f64;
+            } else {
+                cardinality /= statistics.named_graph_count().max(1) as f64;
+            }
+        }
+        PatternValue::Variable(v) if bound_variables.contains(v) => {
+            cardinality /= statistics.named_graph_count().max(1) as f64;
+        }
+        _ => (),
+    }
+    cardinality
+}
+
 fn estimate_pattern_cost(
     pattern: &TriplePattern,
     assigned_variables: &HashSet<&Variable>,
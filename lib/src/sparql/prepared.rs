@@ -0,0 +1,74 @@
+use crate::model::Term;
+use crate::sparql::algebra::{Query, QueryDataset};
+use crate::sparql::dataset::DatasetView;
+use crate::sparql::error::EvaluationError;
+use crate::sparql::model::{QueryResults, Variable};
+use crate::sparql::{QueryOptions, QueryPlan};
+use crate::storage::numeric_encoder::StrHash;
+use crate::storage::Storage;
+use std::collections::HashMap;
+
+/// A [SPARQL 1.1 query](https://www.w3.org/TR/sparql11-query/) parsed and planned once in order to be executed multiple times.
+///
+/// It is built using [`Store::prepare`](crate::store::Store::prepare).
+/// Each execution reads the current content of the store and could provide values for some of the query variables.
+///
+/// Only the variables of the query top level scope could be bound:
+/// the variables returned in the solutions for `SELECT` queries and the variables of the `WHERE` clause for the other query forms.
+/// The variables only used inside of sub-queries could not be bound.
+///
+/// ```
+/// use oxigraph::store::Store;
+/// use oxigraph::model::*;
+/// use oxigraph::sparql::{QueryResults, Variable};
+///
+/// let store = Store::new()?;
+/// let ex = NamedNodeRef::new("http://example.com")?;
+/// store.insert(QuadRef::new(ex, ex, LiteralRef::new_simple_literal("foo"), GraphNameRef::DefaultGraph))?;
+///
+/// let prepared = store.prepare("SELECT ?s ?o WHERE { ?s ?p ?o }")?;
+/// if let QueryResults::Solutions(mut solutions) = prepared.execute([(Variable::new("s")?, ex.into_owned().into())])? {
+///     assert_eq!(solutions.next().unwrap()?.get("o"), Some(&Literal::new_simple_literal("foo").into()));
+/// }
+/// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+/// ```
+pub struct PreparedQuery {
+    storage: Storage,
+    dataset: QueryDataset,
+    plan: QueryPlan,
+    strings: HashMap<StrHash, String>,
+    options: QueryOptions,
+}
+
+impl PreparedQuery {
+    pub(crate) fn new(
+        storage: Storage,
+        query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
+        options: QueryOptions,
+    ) -> Result<Self, EvaluationError> {
+        let query = query.try_into().map_err(std::convert::Into::into)?;
+        let dataset = DatasetView::new(storage.snapshot(), &query.dataset);
+        let plan = QueryPlan::build(&dataset, query.inner, &options)?;
+        Ok(Self {
+            // The strings of the query terms that are not in the store
+            strings: dataset.extra_strings(),
+            storage,
+            dataset: query.dataset,
+            plan,
+            options,
+        })
+    }
+
+    /// Executes the query with the given variable values.
+    ///
+    /// Fails if one of the variables is not in the query top level scope.
+    pub fn execute(
+        &self,
+        bindings: impl IntoIterator<Item = (Variable, Term)>,
+    ) -> Result<QueryResults, EvaluationError> {
+        let dataset = DatasetView::new(self.storage.snapshot(), &self.dataset)
+            .with_extra_strings(self.strings.clone());
+        let from = self.plan.bind(&dataset, bindings)?;
+        self.plan.evaluate(dataset, self.options.clone(), from)
+    }
+}
@@ -0,0 +1,138 @@
+use crate::sparql::error::EvaluationError;
+use crate::sparql::explanation::QueryExplanation;
+use crate::sparql::plan::{EncodedTuple, PlanNode};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// Collects the number of rows produced by each operator of a SPARQL query evaluation and the time spent in it.
+///
+/// It should be given to the evaluation using [`QueryOptions::with_profiling`](super::QueryOptions::with_profiling).
+/// The statistics are updated while the query results are consumed.
+///
+/// ```
+/// use oxigraph::store::Store;
+/// use oxigraph::sparql::{QueryOptions, QueryProfile, QueryResults};
+///
+/// let store = Store::new()?;
+/// let profile = QueryProfile::new();
+/// if let QueryResults::Solutions(solutions) = store.query_opt(
+///     "SELECT * WHERE { ?s ?p ?o }",
+///     QueryOptions::default().with_profiling(profile.clone()),
+/// )? {
+///     assert_eq!(solutions.count(), 0);
+/// }
+/// assert!(profile.explanation().unwrap().to_string().contains("QuadPattern"));
+/// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+/// ```
+#[derive(Clone, Default)]
+pub struct QueryProfile {
+    inner: Rc<RefCell<Option<(QueryExplanation, Profiler)>>>,
+}
+
+impl QueryProfile {
+    /// Builds a new profile not attached to any evaluation yet.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the evaluation plan of the last query evaluated with this profile.
+    ///
+    /// Each plan node has a `rows` attribute with the number of rows it produced until now
+    /// and a `duration` attribute with the time spent in it, including the time spent in its children.
+    ///
+    /// Returns `None` if no query has been evaluated with this profile yet.
+    pub fn explanation(&self) -> Option<QueryExplanation> {
+        let inner = self.inner.borrow();
+        let (explanation, profiler) = inner.as_ref()?;
+        let explanation = explanation.with_statistics(&profiler.nodes.borrow());
+        Some(explanation)
+    }
+
+    pub(crate) fn start(&self, explanation: QueryExplanation) -> Profiler {
+        let profiler = Profiler::default();
+        *self.inner.borrow_mut() = Some((explanation, profiler.clone()));
+        profiler
+    }
+}
+
+/// The statistics of each plan node, identified by its address.
+#[derive(Clone, Default)]
+pub struct Profiler {
+    nodes: Rc<RefCell<HashMap<usize, Rc<NodeStatistics>>>>,
+}
+
+impl Profiler {
+    pub fn statistics(&self, node: &PlanNode) -> Rc<NodeStatistics> {
+        self.nodes
+            .borrow_mut()
+            .entry(node_id(node))
+            .or_default()
+            .clone()
+    }
+}
+
+#[derive(Default)]
+pub struct NodeStatistics {
+    rows: Cell<u64>,
+    duration: Cell<Duration>,
+}
+
+impl NodeStatistics {
+    pub fn rows(&self) -> u64 {
+        self.rows.get()
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration.get()
+    }
+
+    /// Calls `f` and adds the time spent in it to the node duration.
+    pub fn measure<T>(&self, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.duration.set(self.duration.get() + start.elapsed());
+        result
+    }
+
+    /// Wraps an iterator in order to count the rows it returns and the time spent in it.
+    pub fn wrap<I: Iterator<Item = Result<EncodedTuple, EvaluationError>>>(
+        self: Rc<Self>,
+        iter: I,
+    ) -> ProfiledIterator<I> {
+        ProfiledIterator {
+            iter,
+            statistics: self,
+        }
+    }
+}
+
+pub struct ProfiledIterator<I> {
+    iter: I,
+    statistics: Rc<NodeStatistics>,
+}
+
+impl<I: Iterator<Item = Result<EncodedTuple, EvaluationError>>> Iterator for ProfiledIterator<I> {
+    type Item = Result<EncodedTuple, EvaluationError>;
+
+    fn next(&mut self) -> Option<Result<EncodedTuple, EvaluationError>> {
+        let iter = &mut self.iter;
+        let result = self.statistics.measure(|| iter.next());
+        if let Some(Ok(_)) = result {
+            self.statistics.rows.set(self.statistics.rows.get() + 1);
+        }
+        result
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+/// Identifies a plan node during the evaluation.
+pub fn node_id(node: &PlanNode) -> usize {
+    let pointer: *const PlanNode = node;
+    pointer as usize
+}
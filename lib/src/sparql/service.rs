@@ -6,6 +6,7 @@ use crate::sparql::model::QueryResults;
 use crate::sparql::QueryResultsFormat;
 use std::error::Error;
 use std::io::BufReader;
+use std::rc::Rc;
 use std::time::Duration;
 
 /// Handler for [SPARQL 1.1 Federated Query](https://www.w3.org/TR/sparql11-federated-query/) SERVICE.
@@ -92,6 +93,17 @@ impl<S: ServiceHandler> ServiceHandler for ErrorConversionServiceHandler<S> {
     }
 }
 
+/// The handler used if none is set in the [`QueryOptions`](super::QueryOptions)
+pub fn default_service_handler(
+    http_timeout: Option<Duration>,
+) -> Rc<dyn ServiceHandler<Error = EvaluationError>> {
+    if cfg!(feature = "http_client") {
+        Rc::new(SimpleServiceHandler::new(http_timeout))
+    } else {
+        Rc::new(EmptyServiceHandler)
+    }
+}
+
 pub struct SimpleServiceHandler {
     client: Client,
 }
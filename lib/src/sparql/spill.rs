@@ -0,0 +1,276 @@
+//! Temporary files used by the evaluator when the intermediate results do not fit in memory.
+
+use crate::sparql::plan::EncodedTuple;
+use crate::sparql::EvaluationError;
+use crate::storage::binary_encoder::{write_term, TermReader};
+use crate::storage::numeric_encoder::EncodedTerm;
+use rand::random;
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::env::temp_dir;
+use std::fs::{remove_file, File, OpenOptions};
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+/// Number of files in which spilled tuples are partitioned by hash.
+const PARTITION_COUNT: u64 = 16;
+/// Maximal number of sorted runs merged at once.
+const MAX_MERGE_FAN_IN: usize = 64;
+
+const UNBOUND: u8 = 0;
+const BOUND: u8 = 1;
+const BOUND_TO_DEFAULT_GRAPH: u8 = 2;
+
+type TupleIterator = Box<dyn Iterator<Item = Result<EncodedTuple, EvaluationError>>>;
+
+/// A temporary file containing serialized tuples.
+///
+/// The file is removed when it is dropped.
+pub struct TupleFile {
+    path: TempPath,
+    writer: BufWriter<File>,
+    buffer: Vec<u8>,
+    len: usize,
+}
+
+impl TupleFile {
+    pub fn new() -> io::Result<Self> {
+        let path = TempPath(temp_dir().join(format!("oxigraph-spill-{}", random::<u128>())));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path.0)?;
+        Ok(Self {
+            path,
+            writer: BufWriter::new(file),
+            buffer: Vec::new(),
+            len: 0,
+        })
+    }
+
+    pub fn write(&mut self, tuple: &EncodedTuple) -> io::Result<()> {
+        self.buffer.clear();
+        let values = tuple.iter().collect::<Vec<_>>();
+        self.buffer.extend_from_slice(
+            &u32::try_from(values.len())
+                .map_err(invalid_tuple)?
+                .to_be_bytes(),
+        );
+        for value in values {
+            match value {
+                None => self.buffer.push(UNBOUND),
+                Some(EncodedTerm::DefaultGraph) => self.buffer.push(BOUND_TO_DEFAULT_GRAPH),
+                Some(term) => {
+                    self.buffer.push(BOUND);
+                    write_term(&mut self.buffer, &term);
+                }
+            }
+        }
+        self.writer.write_all(&self.buffer)?;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Reads back all the written tuples.
+    pub fn read(self) -> io::Result<TupleFileIterator> {
+        let mut file = self
+            .writer
+            .into_inner()
+            .map_err(io::IntoInnerError::into_error)?;
+        file.seek(SeekFrom::Start(0))?;
+        Ok(TupleFileIterator {
+            reader: BufReader::new(file),
+            remaining: self.len,
+            _path: self.path,
+        })
+    }
+}
+
+pub struct TupleFileIterator {
+    reader: BufReader<File>,
+    remaining: usize,
+    _path: TempPath,
+}
+
+impl TupleFileIterator {
+    fn read_tuple(&mut self) -> io::Result<EncodedTuple> {
+        let mut len = [0; 4];
+        self.reader.read_exact(&mut len)?;
+        let len = u32::from_be_bytes(len) as usize;
+        let mut tuple = EncodedTuple::with_capacity(len);
+        for i in 0..len {
+            let mut kind = [0];
+            self.reader.read_exact(&mut kind)?;
+            match kind[0] {
+                UNBOUND => (),
+                BOUND => tuple.set(i, self.reader.read_term()?),
+                BOUND_TO_DEFAULT_GRAPH => tuple.set(i, EncodedTerm::DefaultGraph),
+                _ => return Err(invalid_tuple("Invalid value kind in a spilled tuple")),
+            }
+        }
+        Ok(tuple)
+    }
+}
+
+impl Iterator for TupleFileIterator {
+    type Item = Result<EncodedTuple, EvaluationError>;
+
+    fn next(&mut self) -> Option<Result<EncodedTuple, EvaluationError>> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        Some(self.read_tuple().map_err(EvaluationError::from))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+struct TempPath(PathBuf);
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        remove_file(&self.0).ok();
+    }
+}
+
+fn invalid_tuple(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+/// Tuples partitioned by hash into temporary files.
+///
+/// The files are only created when a tuple is written into them.
+pub struct Partitions {
+    files: Vec<Option<TupleFile>>,
+    level: u64,
+}
+
+impl Partitions {
+    /// Creates a new set of partitions.
+    ///
+    /// `level` should be different for each recursive partitioning in order to split the data in a different way.
+    pub fn new(level: u64) -> Self {
+        Self {
+            files: (0..PARTITION_COUNT).map(|_| None).collect(),
+            level,
+        }
+    }
+
+    /// Writes the tuple into the partition of the given key.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn write(&mut self, key: &impl Hash, tuple: &EncodedTuple) -> io::Result<()> {
+        let mut hasher = DefaultHasher::new();
+        self.level.hash(&mut hasher);
+        key.hash(&mut hasher);
+        let file = &mut self.files[(hasher.finish() % PARTITION_COUNT) as usize];
+        if file.is_none() {
+            *file = Some(TupleFile::new()?);
+        }
+        file.as_mut().unwrap().write(tuple)
+    }
+
+    /// Returns the non empty partitions.
+    pub fn into_files(self) -> impl Iterator<Item = TupleFile> {
+        self.files.into_iter().flatten()
+    }
+}
+
+/// Writes the already sorted tuples into a new run file.
+pub fn write_sorted_run(tuples: impl IntoIterator<Item = EncodedTuple>) -> io::Result<TupleFile> {
+    let mut file = TupleFile::new()?;
+    for tuple in tuples {
+        file.write(&tuple)?;
+    }
+    Ok(file)
+}
+
+/// Merges sorted runs into a single sorted iterator.
+///
+/// If there are too many runs, they are first merged together into bigger runs to bound the number of open files.
+pub fn merge_sorted_runs(
+    mut runs: Vec<TupleFile>,
+    last_run: Vec<EncodedTuple>,
+    comparator: Rc<dyn Fn(&EncodedTuple, &EncodedTuple) -> Ordering>,
+) -> Result<TupleIterator, EvaluationError> {
+    while runs.len() > MAX_MERGE_FAN_IN {
+        let inputs = runs
+            .drain(..MAX_MERGE_FAN_IN)
+            .map(|run| -> io::Result<TupleIterator> { Ok(Box::new(run.read()?)) })
+            .collect::<io::Result<Vec<_>>>()?;
+        let mut merged = TupleFile::new()?;
+        for tuple in MergeIterator::new(inputs, comparator.clone()) {
+            merged.write(&tuple?)?;
+        }
+        runs.push(merged);
+    }
+    let mut inputs = runs
+        .into_iter()
+        .map(|run| -> io::Result<TupleIterator> { Ok(Box::new(run.read()?)) })
+        .collect::<io::Result<Vec<_>>>()?;
+    inputs.push(Box::new(last_run.into_iter().map(Ok)));
+    Ok(Box::new(MergeIterator::new(inputs, comparator)))
+}
+
+/// k-way merge of sorted iterators.
+struct MergeIterator {
+    inputs: Vec<TupleIterator>,
+    heads: Vec<Option<EncodedTuple>>,
+    comparator: Rc<dyn Fn(&EncodedTuple, &EncodedTuple) -> Ordering>,
+    started: bool,
+}
+
+impl MergeIterator {
+    fn new(
+        inputs: Vec<TupleIterator>,
+        comparator: Rc<dyn Fn(&EncodedTuple, &EncodedTuple) -> Ordering>,
+    ) -> Self {
+        Self {
+            heads: vec![None; inputs.len()],
+            inputs,
+            comparator,
+            started: false,
+        }
+    }
+
+    fn fill(&mut self, i: usize) -> Result<(), EvaluationError> {
+        self.heads[i] = self.inputs[i].next().transpose()?;
+        Ok(())
+    }
+}
+
+impl Iterator for MergeIterator {
+    type Item = Result<EncodedTuple, EvaluationError>;
+
+    fn next(&mut self) -> Option<Result<EncodedTuple, EvaluationError>> {
+        if !self.started {
+            self.started = true;
+            for i in 0..self.inputs.len() {
+                if let Err(error) = self.fill(i) {
+                    return Some(Err(error));
+                }
+            }
+        }
+        let mut min: Option<usize> = None;
+        for (i, head) in self.heads.iter().enumerate() {
+            if let Some(head) = head {
+                if min.map_or(true, |min| {
+                    (self.comparator)(head, self.heads[min].as_ref().unwrap()) == Ordering::Less
+                }) {
+                    min = Some(i);
+                }
+            }
+        }
+        let min = min?;
+        let result = self.heads[min].take()?;
+        if let Err(error) = self.fill(min) {
+            return Some(Err(error));
+        }
+        Some(Ok(result))
+    }
+}
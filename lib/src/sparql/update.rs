@@ -12,7 +12,7 @@ use crate::sparql::plan::EncodedTuple;
 use crate::sparql::plan_builder::PlanBuilder;
 use crate::sparql::{EvaluationError, Update, UpdateOptions};
 use crate::storage::numeric_encoder::{Decoder, EncodedTerm};
-use crate::storage::{Storage, StorageWriter};
+use crate::storage::StorageWriter;
 use oxiri::Iri;
 use spargebra::algebra::{GraphPattern, GraphTarget};
 use spargebra::term::{
@@ -23,50 +23,31 @@ use spargebra::term::{
 };
 use spargebra::GraphUpdateOperation;
 use std::collections::HashMap;
-use std::io::{BufReader, Error, ErrorKind};
+use std::io::BufReader;
 use std::rc::Rc;
 
 pub fn evaluate_update(
-    storage: &Storage,
-    update: Update,
-    options: UpdateOptions,
+    transaction: &mut StorageWriter<'_>,
+    update: &Update,
+    options: &UpdateOptions,
 ) -> Result<(), EvaluationError> {
-    let base_iri = update.inner.base_iri.map(Rc::new);
-    storage
-        .transaction(move |transaction| {
-            let client = Client::new(options.query_options.http_timeout);
-            SimpleUpdateEvaluator {
-                transaction,
-                base_iri: base_iri.clone(),
-                options: options.clone(),
-                client,
-            }
-            .eval_all(&update.inner.operations, &update.using_datasets)
-            .map_err(|e| match e {
-                EvaluationError::Io(e) => e,
-                q => Error::new(ErrorKind::Other, q),
-            })
-        })
-        .map_err(|e| {
-            if e.get_ref()
-                .map_or(false, |inner| inner.is::<EvaluationError>())
-            {
-                *e.into_inner().unwrap().downcast().unwrap()
-            } else {
-                EvaluationError::Io(e)
-            }
-        })?;
-    Ok(())
+    SimpleUpdateEvaluator {
+        transaction,
+        base_iri: update.inner.base_iri.clone().map(Rc::new),
+        options: options.clone(),
+        client: Client::new(options.query_options.http_timeout),
+    }
+    .eval_all(&update.inner.operations, &update.using_datasets)
 }
 
-struct SimpleUpdateEvaluator<'a> {
-    transaction: StorageWriter<'a>,
+struct SimpleUpdateEvaluator<'a, 'b> {
+    transaction: &'a mut StorageWriter<'b>,
     base_iri: Option<Rc<Iri<String>>>,
     options: UpdateOptions,
     client: Client,
 }
 
-impl SimpleUpdateEvaluator<'_> {
+impl SimpleUpdateEvaluator<'_, '_> {
     fn eval_all(
         &mut self,
         updates: &[GraphUpdateOperation],
@@ -143,12 +124,16 @@ impl SimpleUpdateEvaluator<'_> {
             algebra,
             false,
             &self.options.query_options.custom_functions,
+            self.options.query_options.rdfs_entailment,
         )?;
         let evaluator = SimpleEvaluator::new(
             dataset.clone(),
             self.base_iri.clone(),
             self.options.query_options.service_handler(),
             Rc::new(self.options.query_options.custom_functions.clone()),
+            self.options.query_options.interrupter(),
+            self.options.query_options.limits(),
+            None,
         );
         let mut bnodes = HashMap::new();
         for tuple in evaluator.plan_evaluator(&plan)(EncodedTuple::with_capacity(variables.len())) {
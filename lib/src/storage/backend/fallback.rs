@@ -41,11 +41,29 @@ impl Db {
         Reader(InnerReader::Simple(self.0.clone()))
     }
 
-    pub fn transaction<'a, 'b: 'a, T>(
+    pub fn transaction<'a, 'b: 'a, T, E: std::error::Error + 'static + From<std::io::Error>>(
         &'b self,
-        f: impl Fn(Transaction<'a>) -> Result<T>,
-    ) -> Result<T> {
-        f(Transaction(Rc::new(RefCell::new(self.0.write().unwrap()))))
+        f: impl Fn(Transaction<'a>) -> std::result::Result<T, E>,
+    ) -> std::result::Result<T, E> {
+        let trees = Rc::new(RefCell::new(self.0.write().unwrap()));
+        let undo_log = Rc::new(RefCell::new(Vec::new()));
+        let result = f(Transaction {
+            trees: trees.clone(),
+            undo_log: undo_log.clone(),
+        });
+        if result.is_err() {
+            // We rollback the changes
+            let mut trees = trees.borrow_mut();
+            for (column_family, key, old_value) in undo_log.borrow_mut().drain(..).rev() {
+                let tree = trees.get_mut(&column_family).unwrap();
+                if let Some(old_value) = old_value {
+                    tree.insert(key, old_value);
+                } else {
+                    tree.remove(&key);
+                }
+            }
+        }
+        result
     }
 }
 
@@ -54,6 +72,15 @@ pub struct ColumnFamily(&'static str);
 
 pub struct Reader(InnerReader);
 
+/// A [`Reader`] that could be sent to another thread.
+pub struct SharedReader(Arc<RwLock<HashMap<ColumnFamily, BTreeMap<Vec<u8>, Vec<u8>>>>>);
+
+impl SharedReader {
+    pub fn into_reader(self) -> Reader {
+        Reader(InnerReader::Simple(self.0))
+    }
+}
+
 enum InnerReader {
     Simple(Arc<RwLock<HashMap<ColumnFamily, BTreeMap<Vec<u8>, Vec<u8>>>>>),
     Transaction(
@@ -62,6 +89,16 @@ enum InnerReader {
 }
 
 impl Reader {
+    /// Returns a reader on the same data that could be sent to another thread.
+    ///
+    /// Returns `None` for the readers of running transactions.
+    pub fn share(&self) -> Option<SharedReader> {
+        match &self.0 {
+            InnerReader::Simple(reader) => Some(SharedReader(reader.clone())),
+            InnerReader::Transaction(_) => None,
+        }
+    }
+
     pub fn get(&self, column_family: &ColumnFamily, key: &[u8]) -> Result<Option<Vec<u8>>> {
         match &self.0 {
             InnerReader::Simple(reader) => Ok(reader
@@ -107,6 +144,19 @@ impl Reader {
     }
 
     pub fn scan_prefix(&self, column_family: &ColumnFamily, prefix: &[u8]) -> Result<Iter> {
+        self.scan_range(column_family, prefix, prefix)
+    }
+
+    pub fn scan_from(&self, column_family: &ColumnFamily, start: &[u8]) -> Result<Iter> {
+        self.scan_range(column_family, start, &[])
+    }
+
+    fn scan_range(
+        &self,
+        column_family: &ColumnFamily,
+        start: &[u8],
+        prefix: &[u8],
+    ) -> Result<Iter> {
         let data: Vec<_> = match &self.0 {
             InnerReader::Simple(reader) => {
                 let trees = reader.read().unwrap();
@@ -118,14 +168,10 @@ impl Reader {
                         current: None,
                     });
                 };
-                if prefix.is_empty() {
-                    tree.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
-                } else {
-                    tree.range(prefix.to_vec()..)
-                        .take_while(|(k, _)| k.starts_with(prefix))
-                        .map(|(k, v)| (k.clone(), v.clone()))
-                        .collect()
-                }
+                tree.range(start.to_vec()..)
+                    .take_while(|(k, _)| k.starts_with(prefix))
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect()
             }
             InnerReader::Transaction(reader) => {
                 if let Some(reader) = reader.upgrade() {
@@ -138,14 +184,10 @@ impl Reader {
                             current: None,
                         });
                     };
-                    if prefix.is_empty() {
-                        tree.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
-                    } else {
-                        tree.range(prefix.to_vec()..)
-                            .take_while(|(k, _)| k.starts_with(prefix))
-                            .map(|(k, v)| (k.clone(), v.clone()))
-                            .collect()
-                    }
+                    tree.range(start.to_vec()..)
+                        .take_while(|(k, _)| k.starts_with(prefix))
+                        .map(|(k, v)| (k.clone(), v.clone()))
+                        .collect()
                 } else {
                     return Err(invalid_input_error("The transaction is already ended"));
                 }
@@ -197,36 +239,53 @@ impl Reader {
     }
 }
 
-pub struct Transaction<'a>(
-    Rc<RefCell<RwLockWriteGuard<'a, HashMap<ColumnFamily, BTreeMap<Vec<u8>, Vec<u8>>>>>>,
-);
+#[derive(Clone)]
+pub struct Transaction<'a> {
+    trees: Rc<RefCell<RwLockWriteGuard<'a, HashMap<ColumnFamily, BTreeMap<Vec<u8>, Vec<u8>>>>>>,
+    undo_log: Rc<RefCell<Vec<(ColumnFamily, Vec<u8>, Option<Vec<u8>>)>>>,
+}
 
 impl Transaction<'_> {
     #[allow(unsafe_code)]
     pub fn reader(&self) -> Reader {
         // This transmute is safe because we take a weak reference and the only Rc reference used is guarded by the lifetime.
         Reader(InnerReader::Transaction(Rc::downgrade(unsafe {
-            transmute(&self.0)
+            transmute(&self.trees)
         })))
     }
 
+    pub fn get_for_update(
+        &self,
+        column_family: &ColumnFamily,
+        key: &[u8],
+    ) -> Result<Option<Vec<u8>>> {
+        Ok((*self.trees)
+            .borrow()
+            .get(column_family)
+            .and_then(|cf| cf.get(key).cloned()))
+    }
+
     pub fn contains_key_for_update(
         &self,
         column_family: &ColumnFamily,
         key: &[u8],
     ) -> Result<bool> {
-        Ok((*self.0)
+        Ok((*self.trees)
             .borrow()
             .get(column_family)
             .map_or(false, |cf| cf.contains_key(key)))
     }
 
     pub fn insert(&mut self, column_family: &ColumnFamily, key: &[u8], value: &[u8]) -> Result<()> {
-        self.0
+        let old_value = self
+            .trees
             .borrow_mut()
             .get_mut(column_family)
             .unwrap()
             .insert(key.into(), value.into());
+        self.undo_log
+            .borrow_mut()
+            .push((column_family.clone(), key.into(), old_value));
         Ok(())
     }
 
@@ -235,11 +294,17 @@ impl Transaction<'_> {
     }
 
     pub fn remove(&mut self, column_family: &ColumnFamily, key: &[u8]) -> Result<()> {
-        self.0
+        let old_value = self
+            .trees
             .borrow_mut()
             .get_mut(column_family)
             .unwrap()
             .remove(key);
+        if old_value.is_some() {
+            self.undo_log
+                .borrow_mut()
+                .push((column_family.clone(), key.into(), old_value));
+        }
         Ok(())
     }
 }
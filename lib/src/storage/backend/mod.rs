@@ -2,10 +2,13 @@
 //! RocksDB is available, if not in memory
 
 #[cfg(target_arch = "wasm32")]
-pub use fallback::{ColumnFamily, ColumnFamilyDefinition, Db, Iter, Reader, Transaction};
+pub use fallback::{
+    ColumnFamily, ColumnFamilyDefinition, Db, Iter, Reader, SharedReader, Transaction,
+};
 #[cfg(not(target_arch = "wasm32"))]
 pub use rocksdb::{
-    ColumnFamily, ColumnFamilyDefinition, Db, Iter, Reader, SstFileWriter, Transaction,
+    ColumnFamily, ColumnFamilyDefinition, Db, Iter, Reader, SharedReader, SstFileWriter,
+    Transaction,
 };
 
 #[cfg(target_arch = "wasm32")]
@@ -75,7 +75,7 @@ unsafe impl Send for Db {}
 unsafe impl Sync for Db {}
 
 struct DbHandler {
-    db: *mut rocksdb_transactiondb_t,
+    db: RawDb,
     options: *mut rocksdb_options_t,
     transaction_options: *mut rocksdb_transaction_options_t,
     transactiondb_options: *mut rocksdb_transactiondb_options_t,
@@ -93,13 +93,31 @@ struct DbHandler {
     remove_path: bool,
 }
 
+#[derive(Clone, Copy)]
+enum RawDb {
+    ReadWrite(*mut rocksdb_transactiondb_t),
+    ReadOnly(*mut rocksdb_t),
+    Secondary(*mut rocksdb_t),
+}
+
+enum AccessMode {
+    ReadWrite,
+    ReadOnly,
+    Secondary(PathBuf),
+}
+
 impl Drop for DbHandler {
     fn drop(&mut self) {
         unsafe {
             for cf_handle in &self.cf_handles {
-                rocksdb_column_family_handle_destroy(*cf_handle);
+                if !cf_handle.is_null() {
+                    rocksdb_column_family_handle_destroy(*cf_handle);
+                }
+            }
+            match self.db {
+                RawDb::ReadWrite(db) => rocksdb_transactiondb_close(db),
+                RawDb::ReadOnly(db) | RawDb::Secondary(db) => rocksdb_close(db),
             }
-            rocksdb_transactiondb_close(self.db);
             for cf_option in &self.cf_options {
                 rocksdb_options_destroy(*cf_option);
             }
@@ -120,6 +138,19 @@ impl Drop for DbHandler {
     }
 }
 
+impl DbHandler {
+    fn read_write_db(&self) -> Result<*mut rocksdb_transactiondb_t> {
+        if let RawDb::ReadWrite(db) = self.db {
+            Ok(db)
+        } else {
+            Err(Error::new(
+                ErrorKind::PermissionDenied,
+                "The database has been opened in read-only mode",
+            ))
+        }
+    }
+}
+
 impl Db {
     pub fn new(column_families: Vec<ColumnFamilyDefinition>) -> Result<Self> {
         let path = if cfg!(target_os = "linux") {
@@ -128,13 +159,44 @@ impl Db {
             temp_dir()
         }
         .join(format!("oxigraph-rocksdb-{}", random::<u128>()));
-        Ok(Self(Arc::new(Self::do_open(path, column_families, true)?)))
+        Ok(Self(Arc::new(Self::do_open(
+            path,
+            column_families,
+            AccessMode::ReadWrite,
+            true,
+        )?)))
     }
 
     pub fn open(path: &Path, column_families: Vec<ColumnFamilyDefinition>) -> Result<Self> {
         Ok(Self(Arc::new(Self::do_open(
             path.to_owned(),
             column_families,
+            AccessMode::ReadWrite,
+            false,
+        )?)))
+    }
+
+    pub fn open_read_only(
+        path: &Path,
+        column_families: Vec<ColumnFamilyDefinition>,
+    ) -> Result<Self> {
+        Ok(Self(Arc::new(Self::do_open(
+            path.to_owned(),
+            column_families,
+            AccessMode::ReadOnly,
+            false,
+        )?)))
+    }
+
+    pub fn open_secondary(
+        primary_path: &Path,
+        secondary_path: &Path,
+        column_families: Vec<ColumnFamilyDefinition>,
+    ) -> Result<Self> {
+        Ok(Self(Arc::new(Self::do_open(
+            primary_path.to_owned(),
+            column_families,
+            AccessMode::Secondary(secondary_path.to_owned()),
             false,
         )?)))
     }
@@ -142,15 +204,22 @@ impl Db {
     fn do_open(
         path: PathBuf,
         mut column_families: Vec<ColumnFamilyDefinition>,
+        access_mode: AccessMode,
         in_memory: bool,
     ) -> Result<DbHandler> {
         let c_path = path_to_cstring(&path)?;
+        let is_read_write = matches!(access_mode, AccessMode::ReadWrite);
 
         unsafe {
             let options = rocksdb_options_create();
             assert!(!options.is_null(), "rocksdb_options_create returned null");
-            rocksdb_options_set_create_if_missing(options, 1);
-            rocksdb_options_set_create_missing_column_families(options, 1);
+            if is_read_write {
+                rocksdb_options_set_create_if_missing(options, 1);
+                rocksdb_options_set_create_missing_column_families(options, 1);
+            }
+            if matches!(access_mode, AccessMode::Secondary(_)) {
+                rocksdb_options_set_max_open_files(options, -1); // Required by secondary instances
+            }
             rocksdb_options_optimize_level_style_compaction(options, 512 * 1024 * 1024);
             rocksdb_options_increase_parallelism(options, num_cpus::get().try_into().unwrap());
             rocksdb_options_set_info_log_level(options, 2); // We only log warnings
@@ -200,7 +269,15 @@ impl Db {
                 })
             }
             let column_family_names = column_families.iter().map(|c| c.name).collect::<Vec<_>>();
-            let c_column_families = column_family_names
+            if !is_read_write {
+                // Read-only and secondary instances are not able to create the missing column families
+                // We only open the existing ones and the other ones are considered empty
+                let existing_column_families = list_column_families(options, &c_path)?;
+                column_families.retain(|cf| existing_column_families.iter().any(|e| e == cf.name));
+            }
+            let opened_column_family_names =
+                column_families.iter().map(|c| c.name).collect::<Vec<_>>();
+            let c_column_families = opened_column_family_names
                 .iter()
                 .map(|name| CString::new(*name))
                 .collect::<std::result::Result<Vec<_>, _>>()
@@ -223,29 +300,81 @@ impl Db {
                 .collect::<Vec<_>>();
 
             let mut cf_handles: Vec<*mut rocksdb_column_family_handle_t> =
-                vec![ptr::null_mut(); column_family_names.len()];
-            let db = ffi_result!(rocksdb_transactiondb_open_column_families(
-                options,
-                transactiondb_options,
-                c_path.as_ptr(),
-                c_column_families.len().try_into().unwrap(),
-                c_column_families
-                    .iter()
-                    .map(|cf| cf.as_ptr())
-                    .collect::<Vec<_>>()
-                    .as_ptr(),
-                cf_options.as_ptr() as *const *const rocksdb_options_t,
-                cf_handles.as_mut_ptr(),
-            ))?;
-            assert!(!db.is_null(), "rocksdb_create returned null");
+                vec![ptr::null_mut(); opened_column_family_names.len()];
+            let c_column_family_names = c_column_families
+                .iter()
+                .map(|cf| cf.as_ptr())
+                .collect::<Vec<_>>();
+            let db = match access_mode {
+                AccessMode::ReadWrite => {
+                    let db = ffi_result!(rocksdb_transactiondb_open_column_families(
+                        options,
+                        transactiondb_options,
+                        c_path.as_ptr(),
+                        c_column_families.len().try_into().unwrap(),
+                        c_column_family_names.as_ptr(),
+                        cf_options.as_ptr() as *const *const rocksdb_options_t,
+                        cf_handles.as_mut_ptr(),
+                    ))?;
+                    assert!(
+                        !db.is_null(),
+                        "rocksdb_transactiondb_open_column_families returned null"
+                    );
+                    RawDb::ReadWrite(db)
+                }
+                AccessMode::ReadOnly => {
+                    let db = ffi_result!(rocksdb_open_for_read_only_column_families(
+                        options,
+                        c_path.as_ptr(),
+                        c_column_families.len().try_into().unwrap(),
+                        c_column_family_names.as_ptr(),
+                        cf_options.as_ptr() as *const *const rocksdb_options_t,
+                        cf_handles.as_mut_ptr(),
+                        0, // We allow the primary instance to have a not empty WAL
+                    ))?;
+                    assert!(
+                        !db.is_null(),
+                        "rocksdb_open_for_read_only_column_families returned null"
+                    );
+                    RawDb::ReadOnly(db)
+                }
+                AccessMode::Secondary(secondary_path) => {
+                    let db = ffi_result!(rocksdb_open_as_secondary_column_families(
+                        options,
+                        c_path.as_ptr(),
+                        path_to_cstring(&secondary_path)?.as_ptr(),
+                        c_column_families.len().try_into().unwrap(),
+                        c_column_family_names.as_ptr(),
+                        cf_options.as_ptr() as *const *const rocksdb_options_t,
+                        cf_handles.as_mut_ptr(),
+                    ))?;
+                    assert!(
+                        !db.is_null(),
+                        "rocksdb_open_as_secondary_column_families returned null"
+                    );
+                    RawDb::Secondary(db)
+                }
+            };
             for handle in &cf_handles {
                 if handle.is_null() {
-                    rocksdb_transactiondb_close(db);
+                    match db {
+                        RawDb::ReadWrite(db) => rocksdb_transactiondb_close(db),
+                        RawDb::ReadOnly(db) | RawDb::Secondary(db) => rocksdb_close(db),
+                    }
                     return Err(other_error(
                         "Received null column family handle from RocksDB.",
                     ));
                 }
             }
+            let cf_handles = column_family_names
+                .iter()
+                .map(|name| {
+                    opened_column_family_names
+                        .iter()
+                        .position(|opened| opened == name)
+                        .map_or(ptr::null_mut(), |i| cf_handles[i])
+                })
+                .collect::<Vec<_>>();
 
             let read_options = rocksdb_readoptions_create();
             assert!(
@@ -323,34 +452,52 @@ impl Db {
         None
     }
 
+    pub fn is_read_write(&self) -> bool {
+        matches!(self.0.db, RawDb::ReadWrite(_))
+    }
+
     #[must_use]
     pub fn snapshot(&self) -> Reader {
         unsafe {
-            let snapshot = rocksdb_transactiondb_create_snapshot(self.0.db);
-            assert!(
-                !snapshot.is_null(),
-                "rocksdb_transactiondb_create_snapshot returned null"
-            );
-            let options = rocksdb_readoptions_create_copy(self.0.read_options);
-            rocksdb_readoptions_set_snapshot(options, snapshot);
-            Reader {
-                inner: InnerReader::Snapshot(Rc::new(InnerSnapshot {
-                    db: self.0.clone(),
-                    snapshot,
-                })),
-                options,
+            match self.0.db {
+                RawDb::ReadWrite(db) => {
+                    let snapshot = rocksdb_transactiondb_create_snapshot(db);
+                    assert!(
+                        !snapshot.is_null(),
+                        "rocksdb_transactiondb_create_snapshot returned null"
+                    );
+                    let options = rocksdb_readoptions_create_copy(self.0.read_options);
+                    rocksdb_readoptions_set_snapshot(options, snapshot);
+                    Reader {
+                        inner: InnerReader::Snapshot(Arc::new(InnerSnapshot {
+                            _handler: self.0.clone(),
+                            db,
+                            snapshot,
+                        })),
+                        options,
+                    }
+                }
+                // Read-only instances do not change and secondary instances only change on explicit catch up
+                RawDb::ReadOnly(db) | RawDb::Secondary(db) => Reader {
+                    inner: InnerReader::PlainDb {
+                        _handler: self.0.clone(),
+                        db,
+                    },
+                    options: rocksdb_readoptions_create_copy(self.0.read_options),
+                },
             }
         }
     }
 
-    pub fn transaction<'a, 'b: 'a, T>(
+    pub fn transaction<'a, 'b: 'a, T, E: std::error::Error + 'static + From<Error>>(
         &'b self,
-        f: impl Fn(Transaction<'a>) -> Result<T>,
-    ) -> Result<T> {
+        f: impl Fn(Transaction<'a>) -> std::result::Result<T, E>,
+    ) -> std::result::Result<T, E> {
+        let db = self.0.read_write_db()?;
         loop {
             let transaction = unsafe {
                 let transaction = rocksdb_transaction_begin(
-                    self.0.db,
+                    db,
                     self.0.write_options,
                     self.0.transaction_options,
                     ptr::null_mut(),
@@ -389,12 +536,7 @@ impl Db {
                         rocksdb_transaction_destroy(transaction);
                         rocksdb_readoptions_destroy(read_options);
                     }
-                    let is_conflict_error = e.get_ref().map_or(false, |e| {
-                        let msg = e.to_string();
-                        msg == "Resource busy: "
-                            || msg == "Operation timed out: Timeout waiting to lock key"
-                    });
-                    if is_conflict_error {
+                    if is_conflict_error(&e) {
                         // We give a chance to the OS to do something else before retrying in order to help avoiding an other conflict
                         yield_now();
                     } else {
@@ -409,7 +551,7 @@ impl Db {
     pub fn flush(&self, column_family: &ColumnFamily) -> Result<()> {
         unsafe {
             ffi_result!(rocksdb_transactiondb_flush_cf(
-                self.0.db,
+                self.0.read_write_db()?,
                 self.0.flush_options,
                 column_family.0,
             ))
@@ -420,7 +562,7 @@ impl Db {
     pub fn compact(&self, column_family: &ColumnFamily) -> Result<()> {
         unsafe {
             ffi_result!(rocksdb_transactiondb_compact_range_cf_opt(
-                self.0.db,
+                self.0.read_write_db()?,
                 column_family.0,
                 self.0.compaction_options,
                 ptr::null(),
@@ -443,7 +585,48 @@ impl Db {
         }
     }
 
+    pub fn backup(&self, target_directory: &Path) -> Result<()> {
+        if self.0.remove_path {
+            return Err(other_error(
+                "It is not possible to backup an in-memory database",
+            ));
+        }
+        // Checkpoints disable the file deletions of the database while linking its files.
+        // Read-only and secondary instances are not able to do it and to prevent their primary from deleting the files.
+        let db = if let RawDb::ReadWrite(db) = self.0.db {
+            db
+        } else {
+            return Err(Error::new(
+                ErrorKind::PermissionDenied,
+                "Backups are only possible from a store opened in read-write mode, not from read-only or secondary stores",
+            ));
+        };
+        let path = path_to_cstring(target_directory)?;
+        unsafe {
+            let checkpoint = ffi_result!(rocksdb_transactiondb_checkpoint_object_create(db))?;
+            assert!(
+                !checkpoint.is_null(),
+                "rocksdb_transactiondb_checkpoint_object_create returned null"
+            );
+            // log_size_for_flush = 0 forces a memtable flush so that the checkpoint does not depend on the WAL
+            let result = ffi_result!(rocksdb_checkpoint_create(checkpoint, path.as_ptr(), 0));
+            rocksdb_checkpoint_object_destroy(checkpoint);
+            result
+        }
+    }
+
+    pub fn catch_up_with_primary(&self) -> Result<()> {
+        if let RawDb::Secondary(db) = self.0.db {
+            unsafe { ffi_result!(rocksdb_try_catch_up_with_primary(db)) }
+        } else {
+            Err(invalid_input_error(
+                "Only secondary instances can catch up with their primary",
+            ))
+        }
+    }
+
     pub fn insert_stt_files(&self, ssts_for_cf: &[(&ColumnFamily, PathBuf)]) -> Result<()> {
+        let db = self.0.read_write_db()?;
         let mut paths_by_cf = HashMap::<_, Vec<_>>::new();
         for (cf, path) in ssts_for_cf {
             paths_by_cf
@@ -466,7 +649,7 @@ impl Db {
             .collect::<Vec<_>>();
         unsafe {
             ffi_result!(rocksdb_transactiondb_ingest_external_files(
-                self.0.db,
+                db,
                 args.as_ptr(),
                 args.len()
             ))
@@ -477,6 +660,10 @@ impl Db {
 // It is fine to not keep a lifetime: there is no way to use this type without the database being still in scope.
 // So, no use after free possible.
 #[derive(Clone, Eq, PartialEq, Hash)]
+/// A column family handle
+///
+/// The handle is null if the column family does not exist in a database opened in read-only or secondary mode.
+/// Such a column family is read as empty.
 pub struct ColumnFamily(*mut rocksdb_column_family_handle_t);
 
 unsafe impl Send for ColumnFamily {}
@@ -489,18 +676,27 @@ pub struct Reader {
 
 #[derive(Clone)]
 enum InnerReader {
-    Snapshot(Rc<InnerSnapshot>),
+    Snapshot(Arc<InnerSnapshot>),
     Transaction(Weak<*mut rocksdb_transaction_t>),
+    PlainDb {
+        _handler: Arc<DbHandler>, // needed to ensure that DB still lives while the reader is used
+        db: *mut rocksdb_t,
+    },
 }
 
 struct InnerSnapshot {
-    db: Arc<DbHandler>,
+    _handler: Arc<DbHandler>, // needed to ensure that DB still lives while the snapshot is used
+    db: *mut rocksdb_transactiondb_t,
     snapshot: *const rocksdb_snapshot_t,
 }
 
+// RocksDB snapshots are immutable and could be read from multiple threads
+unsafe impl Send for InnerSnapshot {}
+unsafe impl Sync for InnerSnapshot {}
+
 impl Drop for InnerSnapshot {
     fn drop(&mut self) {
-        unsafe { rocksdb_transactiondb_release_snapshot(self.db.db, self.snapshot) }
+        unsafe { rocksdb_transactiondb_release_snapshot(self.db, self.snapshot) }
     }
 }
 
@@ -519,12 +715,46 @@ impl Drop for Reader {
     }
 }
 
+/// A snapshot or read-only [`Reader`] that could be sent to another thread.
+pub struct SharedReader(Reader);
+
+// The transaction readers, that rely on thread local data, are never shared
+unsafe impl Send for SharedReader {}
+
+impl SharedReader {
+    pub fn into_reader(self) -> Reader {
+        self.0
+    }
+}
+
 impl Reader {
+    /// Returns a reader on the same data that could be sent to another thread.
+    ///
+    /// Returns `None` for the readers of running transactions.
+    pub fn share(&self) -> Option<SharedReader> {
+        match &self.inner {
+            InnerReader::Snapshot(_) | InnerReader::PlainDb { .. } => {
+                Some(SharedReader(self.clone()))
+            }
+            InnerReader::Transaction(_) => None,
+        }
+    }
+
     pub fn get(&self, column_family: &ColumnFamily, key: &[u8]) -> Result<Option<PinnableSlice>> {
+        if column_family.0.is_null() {
+            return Ok(None);
+        }
         unsafe {
             let slice = match &self.inner {
                 InnerReader::Snapshot(inner) => ffi_result!(rocksdb_transactiondb_get_pinned_cf(
-                    inner.db.db,
+                    inner.db,
+                    self.options,
+                    column_family.0,
+                    key.as_ptr() as *const c_char,
+                    key.len()
+                )),
+                InnerReader::PlainDb { db, .. } => ffi_result!(rocksdb_get_pinned_cf(
+                    *db,
                     self.options,
                     column_family.0,
                     key.as_ptr() as *const c_char,
@@ -578,7 +808,19 @@ impl Reader {
                 None
             }
         };
+        self.scan_range(column_family, prefix, upper_bound)
+    }
 
+    pub fn scan_from(&self, column_family: &ColumnFamily, start: &[u8]) -> Result<Iter> {
+        self.scan_range(column_family, start, None)
+    }
+
+    fn scan_range(
+        &self,
+        column_family: &ColumnFamily,
+        start: &[u8],
+        upper_bound: Option<Vec<u8>>,
+    ) -> Result<Iter> {
         unsafe {
             let options = rocksdb_readoptions_create_copy(self.options);
             assert!(
@@ -592,25 +834,35 @@ impl Reader {
                     upper_bound.len(),
                 );
             }
-            let iter = match &self.inner {
-                InnerReader::Snapshot(inner) => {
-                    rocksdb_transactiondb_create_iterator_cf(inner.db.db, options, column_family.0)
-                }
-                InnerReader::Transaction(inner) => {
-                    if let Some(inner) = inner.upgrade() {
-                        rocksdb_transaction_create_iterator_cf(*inner, options, column_family.0)
-                    } else {
-                        return Err(invalid_input_error("The transaction is already ended"));
+            let iter = if column_family.0.is_null() {
+                ptr::null_mut()
+            } else {
+                match &self.inner {
+                    InnerReader::Snapshot(inner) => {
+                        rocksdb_transactiondb_create_iterator_cf(inner.db, options, column_family.0)
+                    }
+                    InnerReader::PlainDb { db, .. } => {
+                        rocksdb_create_iterator_cf(*db, options, column_family.0)
+                    }
+                    InnerReader::Transaction(inner) => {
+                        if let Some(inner) = inner.upgrade() {
+                            rocksdb_transaction_create_iterator_cf(*inner, options, column_family.0)
+                        } else {
+                            return Err(invalid_input_error("The transaction is already ended"));
+                        }
                     }
                 }
             };
-            assert!(!iter.is_null(), "rocksdb_create_iterator returned null");
-            if prefix.is_empty() {
-                rocksdb_iter_seek_to_first(iter);
+            let is_currently_valid = if iter.is_null() {
+                false
             } else {
-                rocksdb_iter_seek(iter, prefix.as_ptr() as *const c_char, prefix.len());
-            }
-            let is_currently_valid = rocksdb_iter_valid(iter) != 0;
+                if start.is_empty() {
+                    rocksdb_iter_seek_to_first(iter);
+                } else {
+                    rocksdb_iter_seek(iter, start.as_ptr() as *const c_char, start.len());
+                }
+                rocksdb_iter_valid(iter) != 0
+            };
             Ok(Iter {
                 iter,
                 options,
@@ -639,6 +891,7 @@ impl Reader {
     }
 }
 
+#[derive(Clone)]
 pub struct Transaction<'a> {
     transaction: Rc<*mut rocksdb_transaction_t>,
     read_options: *mut rocksdb_readoptions_t,
@@ -653,11 +906,11 @@ impl Transaction<'_> {
         }
     }
 
-    pub fn contains_key_for_update(
+    pub fn get_for_update(
         &self,
         column_family: &ColumnFamily,
         key: &[u8],
-    ) -> Result<bool> {
+    ) -> Result<Option<PinnableSlice>> {
         unsafe {
             let slice = ffi_result!(rocksdb_transaction_get_for_update_pinned_cf(
                 *self.transaction,
@@ -666,10 +919,22 @@ impl Transaction<'_> {
                 key.as_ptr() as *const c_char,
                 key.len()
             ))?;
-            Ok(!slice.is_null())
+            Ok(if slice.is_null() {
+                None
+            } else {
+                Some(PinnableSlice(slice))
+            })
         }
     }
 
+    pub fn contains_key_for_update(
+        &self,
+        column_family: &ColumnFamily,
+        key: &[u8],
+    ) -> Result<bool> {
+        Ok(self.get_for_update(column_family, key)?.is_some())
+    }
+
     pub fn insert(&mut self, column_family: &ColumnFamily, key: &[u8], value: &[u8]) -> Result<()> {
         unsafe {
             ffi_result!(rocksdb_transaction_put_cf(
@@ -779,7 +1044,8 @@ impl From<Buffer> for Vec<u8> {
 }
 
 pub struct Iter {
-    iter: *mut rocksdb_iterator_t,
+    iter: *mut rocksdb_iterator_t, // null if the column family does not exist
+
     is_currently_valid: bool,
     _upper_bound: Option<Vec<u8>>,
     _reader: Reader, // needed to ensure that DB still lives while iter is used
@@ -789,7 +1055,9 @@ pub struct Iter {
 impl Drop for Iter {
     fn drop(&mut self) {
         unsafe {
-            rocksdb_iter_destroy(self.iter);
+            if !self.iter.is_null() {
+                rocksdb_iter_destroy(self.iter);
+            }
             rocksdb_readoptions_destroy(self.options);
         }
     }
@@ -804,10 +1072,16 @@ impl Iter {
     }
 
     pub fn status(&self) -> Result<()> {
+        if self.iter.is_null() {
+            return Ok(());
+        }
         unsafe { ffi_result!(rocksdb_iter_get_error(self.iter)) }
     }
 
     pub fn next(&mut self) {
+        if self.iter.is_null() {
+            return;
+        }
         unsafe {
             rocksdb_iter_next(self.iter);
             self.is_currently_valid = rocksdb_iter_valid(self.iter) != 0;
@@ -825,6 +1099,18 @@ impl Iter {
             None
         }
     }
+
+    pub fn value(&self) -> Option<&[u8]> {
+        if self.is_valid() {
+            unsafe {
+                let mut len = 0;
+                let val = rocksdb_iter_value(self.iter, &mut len);
+                Some(slice::from_raw_parts(val as *const u8, len))
+            }
+        } else {
+            None
+        }
+    }
 }
 
 pub struct SstFileWriter {
@@ -874,6 +1160,26 @@ fn convert_error(ptr: *const c_char) -> Error {
     other_error(message)
 }
 
+/// Looks in the error source chain for a RocksDB lock conflict
+fn is_conflict_error(error: &(dyn std::error::Error + 'static)) -> bool {
+    let mut error = Some(error);
+    while let Some(e) = error {
+        let is_conflict = e
+            .downcast_ref::<Error>()
+            .and_then(Error::get_ref)
+            .map_or(false, |e| {
+                let msg = e.to_string();
+                msg == "Resource busy: "
+                    || msg == "Operation timed out: Timeout waiting to lock key"
+            });
+        if is_conflict {
+            return true;
+        }
+        error = e.source();
+    }
+    false
+}
+
 fn other_error(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Error {
     Error::new(ErrorKind::Other, error)
 }
@@ -890,3 +1196,20 @@ fn path_to_cstring(path: &Path) -> Result<CString> {
     )
     .map_err(invalid_input_error)
 }
+
+unsafe fn list_column_families(
+    options: *const rocksdb_options_t,
+    path: &CStr,
+) -> Result<Vec<String>> {
+    let mut len = 0;
+    let list = ffi_result!(rocksdb_list_column_families(
+        options,
+        path.as_ptr(),
+        &mut len
+    ))?;
+    let names = (0..len)
+        .map(|i| CStr::from_ptr(*list.add(i)).to_string_lossy().into_owned())
+        .collect();
+    rocksdb_list_column_families_destroy(list, len);
+    Ok(names)
+}
@@ -0,0 +1,132 @@
+//! Cells of the spatial index.
+//!
+//! The index is a quadtree dividing the longitude and latitude space.
+//! Each geometry is stored in the smallest cell containing its bounding box.
+
+use crate::model::geo::{Point, Rect};
+
+/// The depth of the smallest cells
+const MAX_LEVEL: u8 = 16;
+/// The size of the key prefix identifying a cell
+pub const CELL_KEY_SIZE: usize = 5;
+
+/// A cell of the quadtree
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub struct Cell {
+    level: u8,
+    x: u32,
+    y: u32,
+}
+
+impl Cell {
+    /// The smallest cell containing the bounding box
+    pub fn containing(rect: &Rect) -> Self {
+        for level in (0..=MAX_LEVEL).rev() {
+            let (min, max) = (Self::at(rect.min, level), Self::at(rect.max, level));
+            if min == max {
+                return min;
+            }
+        }
+        Self {
+            level: 0,
+            x: 0,
+            y: 0,
+        }
+    }
+
+    /// The cell of the given level containing the point
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    fn at(point: Point, level: u8) -> Self {
+        let size = 1_u32 << level;
+        let coordinate = |value: f64, min: f64, max: f64| {
+            (((value.clamp(min, max) - min) / (max - min) * f64::from(size)) as u32).min(size - 1)
+        };
+        Self {
+            level,
+            x: coordinate(point.x, -180., 180.),
+            y: coordinate(point.y, -90., 90.),
+        }
+    }
+
+    /// The cell key prefix: the level then the position along the Z-order curve
+    pub fn write(&self, key: &mut Vec<u8>) {
+        key.push(self.level);
+        key.extend_from_slice(&interleave(self.x, self.y).to_be_bytes());
+    }
+
+    pub fn read(key: &[u8]) -> Option<Self> {
+        let mut code = [0; 4];
+        code.copy_from_slice(key.get(1..CELL_KEY_SIZE)?);
+        let (x, y) = deinterleave(u32::from_be_bytes(code));
+        Some(Self {
+            level: key[0],
+            x,
+            y,
+        })
+    }
+}
+
+/// The cells of a level intersecting a bounding box
+pub struct CellRange {
+    min: Cell,
+    max: Cell,
+}
+
+impl CellRange {
+    /// The ranges of cells of each level intersecting the bounding box
+    pub fn covering(rect: &Rect) -> impl Iterator<Item = Self> + '_ {
+        (0..=MAX_LEVEL).map(move |level| Self {
+            min: Cell::at(rect.min, level),
+            max: Cell::at(rect.max, level),
+        })
+    }
+
+    /// The key prefix of the first cell of the range
+    pub fn start(&self) -> Vec<u8> {
+        let mut key = Vec::with_capacity(CELL_KEY_SIZE);
+        self.min.write(&mut key);
+        key
+    }
+
+    /// If the cells after this one on the Z-order curve are not in the range anymore
+    pub fn is_after_end(&self, cell: &Cell) -> bool {
+        cell.level != self.max.level
+            || interleave(cell.x, cell.y) > interleave(self.max.x, self.max.y)
+    }
+
+    pub fn contains(&self, cell: &Cell) -> bool {
+        cell.level == self.min.level
+            && (self.min.x..=self.max.x).contains(&cell.x)
+            && (self.min.y..=self.max.y).contains(&cell.y)
+    }
+}
+
+/// The Morton code of the cell
+fn interleave(x: u32, y: u32) -> u32 {
+    (spread(x) << 1) | spread(y)
+}
+
+fn deinterleave(code: u32) -> (u32, u32) {
+    (compact(code >> 1), compact(code))
+}
+
+/// Inserts a 0 bit before each of the 16 first bits
+fn spread(value: u32) -> u32 {
+    let mut value = value & 0x0000_ffff;
+    value = (value | (value << 8)) & 0x00ff_00ff;
+    value = (value | (value << 4)) & 0x0f0f_0f0f;
+    value = (value | (value << 2)) & 0x3333_3333;
+    (value | (value << 1)) & 0x5555_5555
+}
+
+fn compact(value: u32) -> u32 {
+    let mut value = value & 0x5555_5555;
+    value = (value | (value >> 1)) & 0x3333_3333;
+    value = (value | (value >> 2)) & 0x0f0f_0f0f;
+    value = (value | (value >> 4)) & 0x00ff_00ff;
+    (value | (value >> 8)) & 0x0000_ffff
+}
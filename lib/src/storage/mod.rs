@@ -1,6 +1,10 @@
 use crate::error::invalid_data_error;
-use crate::model::{GraphNameRef, NamedOrBlankNodeRef, Quad, QuadRef, TermRef};
-use crate::storage::backend::{Reader, Transaction};
+use crate::model::geo::{Geometry, Rect, WKT_LITERAL};
+use crate::model::{GraphName, GraphNameRef, NamedOrBlankNodeRef, Quad, QuadRef, TermRef, Triple};
+use crate::reasoning::{self, Materialization, StorageFacts};
+use crate::shacl::{ShapesGraph, ValidationError};
+use crate::sparql::EvaluationError;
+use crate::storage::backend::{Reader, SharedReader, Transaction};
 #[cfg(not(target_arch = "wasm32"))]
 use crate::storage::binary_encoder::LATEST_STORAGE_VERSION;
 use crate::storage::binary_encoder::{
@@ -9,22 +13,37 @@ use crate::storage::binary_encoder::{
     write_pos_quad, write_posg_quad, write_spo_quad, write_spog_quad, write_term, QuadEncoding,
     WRITTEN_TERM_MAX_SIZE,
 };
-use crate::storage::numeric_encoder::{insert_term, EncodedQuad, EncodedTerm, StrHash, StrLookup};
+use crate::storage::geo::{Cell, CellRange, CELL_KEY_SIZE};
+use crate::storage::numeric_encoder::{
+    insert_term, Decoder, EncodedQuad, EncodedTerm, StrHash, StrLookup,
+};
+use crate::storage::statistics::Statistics;
+use crate::storage::text::{TextIndexOptions, TextToken};
 use backend::{ColumnFamily, ColumnFamilyDefinition, Db, Iter};
+use std::cell::RefCell;
 #[cfg(not(target_arch = "wasm32"))]
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::error::Error;
 use std::io::Result;
+use std::mem::replace;
 #[cfg(not(target_arch = "wasm32"))]
 use std::mem::take;
 #[cfg(not(target_arch = "wasm32"))]
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 #[cfg(not(target_arch = "wasm32"))]
 use std::thread::spawn;
 
 mod backend;
-mod binary_encoder;
+pub mod binary_encoder;
+mod geo;
 pub mod numeric_encoder;
 pub mod small_string;
+pub mod statistics;
+pub mod text;
 
 const ID2STR_CF: &str = "id2str";
 const SPOG_CF: &str = "spog";
@@ -37,14 +56,49 @@ const DSPO_CF: &str = "dspo";
 const DPOS_CF: &str = "dpos";
 const DOSP_CF: &str = "dosp";
 const GRAPHS_CF: &str = "graphs";
+const CHANGES_CF: &str = "changes";
+const TEXT_CF: &str = "text";
+const GEO_CF: &str = "geo";
 const DEFAULT_CF: &str = "default";
+const CHANGE_SEQUENCE_NUMBER_KEY: &[u8] = b"oxchangeseq";
+const STATISTICS_KEY: &[u8] = b"oxstats";
+const TEXT_INDEX_KEY: &[u8] = b"oxtextindex";
+const GEO_INDEX_KEY: &[u8] = b"oxgeoindex";
+const MATERIALIZATION_KEY: &[u8] = b"oxmaterialization";
+/// Written by each transaction maintaining the materialization so that the concurrent ones conflict
+const MATERIALIZATION_GUARD_KEY: &[u8] = b"oxmaterializationguard";
+/// Followed by the encoded graph name, written by each transaction validating the graph so that the concurrent ones conflict
+const SHAPES_GUARD_KEY_PREFIX: &[u8] = b"oxshapesguard";
+const CHANGE_INSERTION_IN_DEFAULT_GRAPH: u8 = 0;
+const CHANGE_INSERTION_IN_NAMED_GRAPH: u8 = 1;
+const CHANGE_REMOVAL_FROM_DEFAULT_GRAPH: u8 = 2;
+const CHANGE_REMOVAL_FROM_NAMED_GRAPH: u8 = 3;
+const CHANGE_LOG_TRUNCATION_BATCH_SIZE: usize = 100_000;
 #[cfg(not(target_arch = "wasm32"))]
 const BULK_LOAD_BATCH_SIZE: usize = 1024 * 1024;
 
+/// Callback called after each commit with the inserted and removed quads
+pub type CommitListener = Arc<dyn Fn(&[Quad], &[Quad]) + Send + Sync>;
+
+/// Shapes validated before each commit and the graph they apply to
+type RegisteredShapes = (GraphName, Arc<ShapesGraph>);
+
+/// The state of the change log, shared by the clones of a storage
+#[derive(Default)]
+struct ChangeLog {
+    enabled: AtomicBool,
+    /// The sequence number of the last logged transaction.
+    ///
+    /// It is locked from the allocation of the next sequence number until the commit
+    /// so sequence numbers are increasing in commit order.
+    last_sequence_number: Mutex<u64>,
+}
+
 /// Low level storage primitives
 #[derive(Clone)]
 pub struct Storage {
     db: Db,
+    commit_listeners: Arc<RwLock<Vec<CommitListener>>>,
     default_cf: ColumnFamily,
     id2str_cf: ColumnFamily,
     spog_cf: ColumnFamily,
@@ -57,6 +111,16 @@ pub struct Storage {
     dpos_cf: ColumnFamily,
     dosp_cf: ColumnFamily,
     graphs_cf: ColumnFamily,
+    changes_cf: ColumnFamily,
+    text_cf: ColumnFamily,
+    geo_cf: ColumnFamily,
+    change_log: Arc<ChangeLog>,
+    statistics: Arc<RwLock<Option<Arc<Statistics>>>>,
+    text_index: Arc<RwLock<Option<Arc<TextIndexOptions>>>>,
+    geo_index: Arc<AtomicBool>,
+    materialization: Arc<RwLock<Option<Arc<Materialization>>>>,
+    shapes: Arc<RwLock<Vec<RegisteredShapes>>>,
+    generation: Arc<AtomicU64>,
 }
 
 impl Storage {
@@ -69,6 +133,20 @@ impl Storage {
         Self::setup(Db::open(path, Self::column_families())?)
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn open_read_only(path: &Path) -> Result<Self> {
+        Self::setup(Db::open_read_only(path, Self::column_families())?)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn open_secondary(primary_path: &Path, secondary_path: &Path) -> Result<Self> {
+        Self::setup(Db::open_secondary(
+            primary_path,
+            secondary_path,
+            Self::column_families(),
+        )?)
+    }
+
     fn column_families() -> Vec<ColumnFamilyDefinition> {
         vec![
             ColumnFamilyDefinition {
@@ -126,6 +204,21 @@ impl Storage {
                 use_iter: true,
                 min_prefix_size: 17, // named or blank node start
             },
+            ColumnFamilyDefinition {
+                name: CHANGES_CF,
+                use_iter: true,
+                min_prefix_size: 0,
+            },
+            ColumnFamilyDefinition {
+                name: TEXT_CF,
+                use_iter: true,
+                min_prefix_size: 0,
+            },
+            ColumnFamilyDefinition {
+                name: GEO_CF,
+                use_iter: true,
+                min_prefix_size: 0,
+            },
         ]
     }
 
@@ -143,10 +236,26 @@ impl Storage {
             dpos_cf: db.column_family(DPOS_CF).unwrap(),
             dosp_cf: db.column_family(DOSP_CF).unwrap(),
             graphs_cf: db.column_family(GRAPHS_CF).unwrap(),
+            changes_cf: db.column_family(CHANGES_CF).unwrap(),
+            text_cf: db.column_family(TEXT_CF).unwrap(),
+            geo_cf: db.column_family(GEO_CF).unwrap(),
+            change_log: Arc::default(),
             db,
+            commit_listeners: Arc::default(),
+            statistics: Arc::default(),
+            text_index: Arc::default(),
+            geo_index: Arc::default(),
+            materialization: Arc::default(),
+            shapes: Arc::default(),
+            generation: Arc::default(),
         };
         #[cfg(not(target_arch = "wasm32"))]
         this.migrate()?;
+        this.load_statistics()?;
+        this.load_text_index()?;
+        this.load_geo_index()?;
+        this.load_materialization()?;
+        this.load_change_log()?;
         Ok(this)
     }
 
@@ -154,6 +263,11 @@ impl Storage {
     fn migrate(&self) -> Result<()> {
         let mut version = self.ensure_version()?;
         if version == 0 {
+            if !self.db.is_read_write() {
+                return Err(invalid_data_error(
+                    "The RocksDB database is using the outdated encoding version 0. Open it once in read-write mode to migrate it",
+                ));
+            }
             // We migrate to v1
             let mut graph_names = HashSet::new();
             for quad in self.snapshot().quads() {
@@ -198,7 +312,9 @@ impl Storage {
                 buffer.copy_from_slice(&version);
                 u64::from_be_bytes(buffer)
             } else {
-                self.update_version(LATEST_STORAGE_VERSION)?;
+                if self.db.is_read_write() {
+                    self.update_version(LATEST_STORAGE_VERSION)?;
+                }
                 LATEST_STORAGE_VERSION
             },
         )
@@ -212,6 +328,175 @@ impl Storage {
         self.db.flush(&self.default_cf)
     }
 
+    fn load_statistics(&self) -> Result<()> {
+        let statistics = self
+            .db
+            .snapshot()
+            .get(&self.default_cf, STATISTICS_KEY)?
+            .map(|value| Statistics::read(&value))
+            .transpose()?;
+        *self.statistics.write().unwrap() = statistics.map(Arc::new);
+        Ok(())
+    }
+
+    fn load_text_index(&self) -> Result<()> {
+        let options = self
+            .db
+            .snapshot()
+            .get(&self.default_cf, TEXT_INDEX_KEY)?
+            .map(|value| TextIndexOptions::read(&value))
+            .transpose()?;
+        *self.text_index.write().unwrap() = options.map(Arc::new);
+        Ok(())
+    }
+
+    /// The configuration of the full-text index if it is enabled
+    pub fn text_index(&self) -> Option<Arc<TextIndexOptions>> {
+        self.text_index.read().unwrap().clone()
+    }
+
+    /// Builds again the full-text index with the given options or removes it if `options` is `None`
+    pub fn set_text_index(&self, options: Option<TextIndexOptions>) -> Result<()> {
+        let options = options.map(Arc::new);
+        // The concurrent writes should already maintain the new index
+        let previous = replace(&mut *self.text_index.write().unwrap(), options.clone());
+        let result = self.transaction(|mut writer| writer.rebuild_text_index(options.as_deref()));
+        if result.is_err() {
+            *self.text_index.write().unwrap() = previous;
+        }
+        result
+    }
+
+    fn load_geo_index(&self) -> Result<()> {
+        let is_enabled = self
+            .db
+            .snapshot()
+            .contains_key(&self.default_cf, GEO_INDEX_KEY)?;
+        self.geo_index.store(is_enabled, Ordering::Release);
+        Ok(())
+    }
+
+    /// If the spatial index is enabled
+    pub fn is_geo_index_enabled(&self) -> bool {
+        self.geo_index.load(Ordering::Acquire)
+    }
+
+    /// Builds again the spatial index or removes it
+    pub fn set_geo_index(&self, enabled: bool) -> Result<()> {
+        // The concurrent writes should already maintain the new index
+        let previous = self.geo_index.swap(enabled, Ordering::AcqRel);
+        let result = self.transaction(|mut writer| writer.rebuild_geo_index(enabled));
+        if result.is_err() {
+            self.geo_index.store(previous, Ordering::Release);
+        }
+        result
+    }
+
+    fn load_change_log(&self) -> Result<()> {
+        if let Some(value) = self
+            .db
+            .snapshot()
+            .get(&self.default_cf, CHANGE_SEQUENCE_NUMBER_KEY)?
+        {
+            let mut buffer = [0; 8];
+            buffer.copy_from_slice(&value);
+            *self.change_log.last_sequence_number.lock().unwrap() = u64::from_be_bytes(buffer);
+            self.change_log.enabled.store(true, Ordering::Release);
+        }
+        Ok(())
+    }
+
+    /// Starts to log the changes of the following transactions.
+    ///
+    /// The log stays enabled when the storage is opened again.
+    pub fn enable_change_log(&self) -> Result<()> {
+        if !self.change_log.enabled.load(Ordering::Acquire) {
+            // The log was never enabled so no sequence number has been allocated yet
+            self.db.transaction(|mut t| {
+                if t.get_for_update(&self.default_cf, CHANGE_SEQUENCE_NUMBER_KEY)?
+                    .is_none()
+                {
+                    t.insert(
+                        &self.default_cf,
+                        CHANGE_SEQUENCE_NUMBER_KEY,
+                        &0_u64.to_be_bytes(),
+                    )?;
+                }
+                Ok::<_, std::io::Error>(())
+            })?;
+            self.change_log.enabled.store(true, Ordering::Release);
+        }
+        Ok(())
+    }
+
+    /// Removes from the change log all the changes with a sequence number strictly lower than `sequence_number`.
+    ///
+    /// The changes are removed by batches, each in its own transaction.
+    pub fn truncate_changes(&self, sequence_number: u64) -> Result<()> {
+        while self.transaction(|mut t| t.truncate_changes(sequence_number))? {}
+        Ok(())
+    }
+
+    fn load_materialization(&self) -> Result<()> {
+        let materialization = self
+            .db
+            .snapshot()
+            .get(&self.default_cf, MATERIALIZATION_KEY)?
+            .map(|value| Materialization::read(&value))
+            .transpose()?;
+        *self.materialization.write().unwrap() = materialization.map(Arc::new);
+        Ok(())
+    }
+
+    /// The materialization maintained on writes if there is one
+    pub fn materialization(&self) -> Option<Arc<Materialization>> {
+        self.materialization.read().unwrap().clone()
+    }
+
+    /// Computes again the inferred triples of the materialization or stops maintaining it if `materialization` is `None`
+    pub fn set_materialization(&self, materialization: Option<Materialization>) -> Result<()> {
+        let materialization = materialization.map(Arc::new);
+        let previous = replace(
+            &mut *self.materialization.write().unwrap(),
+            materialization.clone(),
+        );
+        let result = self
+            .transaction(|mut writer| writer.rebuild_materialization(materialization.as_deref()));
+        if result.is_err() {
+            *self.materialization.write().unwrap() = previous;
+        }
+        result
+    }
+
+    /// Computes again the cardinality statistics from the current content of the store and persists them
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn refresh_statistics(&self) -> Result<()> {
+        let reader = self.snapshot();
+        let mut statistics = Statistics::default();
+        for quads in [reader.dspo_quads(&[]), reader.spog_quads(&[])] {
+            let mut previous = None;
+            for quad in quads {
+                let quad = quad?;
+                statistics.add_in_subject_order(&quad, previous.as_ref());
+                previous = Some(quad);
+            }
+        }
+        for quads in [reader.dpos_quads(&[]), reader.posg_quads(&[])] {
+            let mut previous = None;
+            for quad in quads {
+                let quad = quad?;
+                statistics.add_in_predicate_order(&quad, previous.as_ref());
+                previous = Some(quad);
+            }
+        }
+        let mut buffer = Vec::new();
+        statistics.write(&mut buffer);
+        self.db
+            .transaction(|mut t| t.insert(&self.default_cf, STATISTICS_KEY, &buffer))?;
+        *self.statistics.write().unwrap() = Some(Arc::new(statistics));
+        Ok(())
+    }
+
     pub fn snapshot(&self) -> StorageReader {
         StorageReader {
             reader: self.db.snapshot(),
@@ -219,17 +504,189 @@ impl Storage {
         }
     }
 
-    pub fn transaction<'a, 'b: 'a, T>(
+    pub fn transaction<'a, 'b: 'a, T, E: Error + 'static + From<std::io::Error>>(
         &'b self,
-        f: impl Fn(StorageWriter<'a>) -> Result<T>,
-    ) -> Result<T> {
-        self.db.transaction(|transaction| {
-            f(StorageWriter {
+        f: impl Fn(StorageWriter<'a>) -> std::result::Result<T, E>,
+    ) -> std::result::Result<T, E> {
+        let listeners = self.commit_listeners.read().unwrap().clone();
+        let materialization = self.materialization();
+        let shapes = self.shapes.read().unwrap().clone();
+        let changes = Rc::new(RefCell::new(ChangeSet::default()));
+        let logged_changes = if self.change_log.enabled.load(Ordering::Acquire) {
+            Some(Rc::new(RefCell::new(Vec::new())))
+        } else {
+            None
+        };
+        // The lock on the last sequence number and the sequence number of the transaction, released after the commit
+        let sequence_number_lock = RefCell::new(None);
+        let result = self.db.transaction(|transaction| {
+            // The transaction might be a retry
+            *changes.borrow_mut() = ChangeSet::default();
+            sequence_number_lock.borrow_mut().take();
+            if let Some(logged_changes) = &logged_changes {
+                logged_changes.borrow_mut().clear();
+            }
+            let mut commit_transaction = transaction.clone();
+            let result = f(StorageWriter {
                 buffer: Vec::new(),
                 transaction,
                 storage: self,
-            })
-        })
+                logged_changes: logged_changes.clone(),
+                changes: if listeners.is_empty() && materialization.is_none() && shapes.is_empty() {
+                    None
+                } else {
+                    Some(changes.clone())
+                },
+            })?;
+            if let Some(materialization) = &materialization {
+                // The inferred triples are updated before the commit so that readers never see them outdated
+                let (inserted, removed) = {
+                    let changes = changes.borrow();
+                    (
+                        changes.inserted.iter().cloned().collect::<Vec<_>>(),
+                        changes.removed.iter().cloned().collect::<Vec<_>>(),
+                    )
+                };
+                StorageWriter {
+                    buffer: Vec::new(),
+                    transaction: commit_transaction.clone(),
+                    storage: self,
+                    logged_changes: logged_changes.clone(),
+                    changes: Some(changes.clone()),
+                }
+                .update_materialization(materialization, inserted, removed)?;
+            }
+            if !shapes.is_empty() {
+                self.validate_changes(&mut commit_transaction, &shapes, &changes.borrow())?;
+            }
+            if let Some(logged_changes) = &logged_changes {
+                let logged_changes = logged_changes.borrow();
+                if !logged_changes.is_empty() {
+                    let lock = self.change_log.last_sequence_number.lock().unwrap();
+                    let sequence_number = *lock + 1;
+                    self.write_changes(&mut commit_transaction, sequence_number, &logged_changes)?;
+                    *sequence_number_lock.borrow_mut() = Some((lock, sequence_number));
+                }
+            }
+            Ok::<_, E>(result)
+        })?;
+        if let Some((mut lock, sequence_number)) = sequence_number_lock.take() {
+            *lock = sequence_number;
+        }
+        self.bump_generation();
+        let changes = changes.take();
+        if !changes.is_empty() {
+            let inserted = changes.inserted.into_iter().collect::<Vec<_>>();
+            let removed = changes.removed.into_iter().collect::<Vec<_>>();
+            for listener in listeners {
+                listener(&inserted, &removed);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Writes the changes of a transaction into the change log with the given sequence number
+    fn write_changes(
+        &self,
+        transaction: &mut Transaction<'_>,
+        sequence_number: u64,
+        changes: &[Vec<u8>],
+    ) -> Result<()> {
+        for (i, change) in changes.iter().enumerate() {
+            let mut key = Vec::with_capacity(12);
+            key.extend_from_slice(&sequence_number.to_be_bytes());
+            key.extend_from_slice(
+                &u32::try_from(i)
+                    .map_err(|_| invalid_data_error("Too many changes in a transaction"))?
+                    .to_be_bytes(),
+            );
+            transaction.insert(&self.changes_cf, &key, change)?;
+        }
+        transaction.insert(
+            &self.default_cf,
+            CHANGE_SEQUENCE_NUMBER_KEY,
+            &sequence_number.to_be_bytes(),
+        )
+    }
+
+    /// Validates before the commit the graphs modified by a transaction that have registered shapes.
+    ///
+    /// Fails with an [`InvalidData`](std::io::ErrorKind::InvalidData) error wrapping a [`ValidationError`] if a graph has violations.
+    fn validate_changes(
+        &self,
+        transaction: &mut Transaction<'_>,
+        shapes: &[RegisteredShapes],
+        changes: &ChangeSet,
+    ) -> Result<()> {
+        for (graph_name, shapes) in shapes {
+            let graph_changes = changes
+                .inserted
+                .iter()
+                .chain(&changes.removed)
+                .filter(|quad| quad.graph_name == *graph_name)
+                .map(|quad| Triple::from(quad.clone()))
+                .collect::<Vec<_>>();
+            if graph_changes.is_empty() {
+                continue;
+            }
+            // The validation reads the snapshot of the transaction, which is not conflict checked.
+            // Writing this key makes the concurrent validations of the same graph conflict:
+            // the transaction committed last is retried and validated again against a snapshot containing the other changes.
+            let mut guard_key = SHAPES_GUARD_KEY_PREFIX.to_vec();
+            guard_key.extend_from_slice(&encode_term(&graph_name.as_ref().into()));
+            transaction.get_for_update(&self.default_cf, &guard_key)?;
+            transaction.insert_empty(&self.default_cf, &guard_key)?;
+            let report = shapes
+                .validate_storage(
+                    &|| StorageReader {
+                        reader: transaction.reader(),
+                        storage: self.clone(),
+                    },
+                    graph_name.as_ref(),
+                    Some(&graph_changes),
+                )
+                .map_err(|e| match e {
+                    EvaluationError::Io(e) => e,
+                    e => std::io::Error::new(std::io::ErrorKind::Other, e),
+                })?;
+            if report.has_violations() {
+                return Err(invalid_data_error(ValidationError::new(
+                    graph_name.clone(),
+                    report,
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Registers shapes validated before each commit modifying the `graph_name` graph, replacing the ones previously registered for this graph.
+    pub fn register_shapes(&self, graph_name: GraphName, shapes: ShapesGraph) {
+        let mut registered = self.shapes.write().unwrap();
+        registered.retain(|(g, _)| *g != graph_name);
+        registered.push((graph_name, Arc::new(shapes)));
+    }
+
+    /// Unregisters the shapes of the `graph_name` graph and returns if there were some.
+    pub fn unregister_shapes(&self, graph_name: GraphNameRef<'_>) -> bool {
+        let mut registered = self.shapes.write().unwrap();
+        let len = registered.len();
+        registered.retain(|(g, _)| g.as_ref() != graph_name);
+        registered.len() != len
+    }
+
+    /// A counter incremented after each write to the storage.
+    ///
+    /// It should be read before taking a snapshot: the snapshot then contains at least all the writes counted by it.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    fn bump_generation(&self) {
+        self.generation.fetch_add(1, Ordering::Release);
+    }
+
+    pub fn add_commit_listener(&self, listener: CommitListener) {
+        self.commit_listeners.write().unwrap().push(listener)
     }
 
     #[cfg(not(target_arch = "wasm32"))]
@@ -244,7 +701,26 @@ impl Storage {
         self.db.flush(&self.dspo_cf)?;
         self.db.flush(&self.dpos_cf)?;
         self.db.flush(&self.dosp_cf)?;
-        self.db.flush(&self.id2str_cf)
+        self.db.flush(&self.id2str_cf)?;
+        self.db.flush(&self.changes_cf)?;
+        self.db.flush(&self.text_cf)?;
+        self.db.flush(&self.geo_cf)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn backup(&self, target_directory: &Path) -> Result<()> {
+        self.db.backup(target_directory)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn catch_up_with_primary(&self) -> Result<()> {
+        self.db.catch_up_with_primary()?;
+        self.bump_generation();
+        self.load_statistics()?;
+        self.load_text_index()?;
+        self.load_geo_index()?;
+        self.load_change_log()?;
+        self.load_materialization()
     }
 
     #[cfg(not(target_arch = "wasm32"))]
@@ -259,7 +735,10 @@ impl Storage {
         self.db.compact(&self.dspo_cf)?;
         self.db.compact(&self.dpos_cf)?;
         self.db.compact(&self.dosp_cf)?;
-        self.db.compact(&self.id2str_cf)
+        self.db.compact(&self.id2str_cf)?;
+        self.db.compact(&self.changes_cf)?;
+        self.db.compact(&self.text_cf)?;
+        self.db.compact(&self.geo_cf)
     }
 }
 
@@ -268,7 +747,32 @@ pub struct StorageReader {
     storage: Storage,
 }
 
+/// A [`StorageReader`] that could be sent to another thread.
+pub struct SharedStorageReader {
+    reader: SharedReader,
+    storage: Storage,
+}
+
+impl SharedStorageReader {
+    pub fn into_reader(self) -> StorageReader {
+        StorageReader {
+            reader: self.reader.into_reader(),
+            storage: self.storage,
+        }
+    }
+}
+
 impl StorageReader {
+    /// Returns a reader on the same snapshot that could be sent to another thread.
+    ///
+    /// Returns `None` if the reader is the one of a running transaction.
+    pub fn share(&self) -> Option<SharedStorageReader> {
+        Some(SharedStorageReader {
+            reader: self.reader.share()?,
+            storage: self.storage.clone(),
+        })
+    }
+
     pub fn len(&self) -> Result<usize> {
         Ok(self.reader.len(&self.storage.gspo_cf)? + self.reader.len(&self.storage.dspo_cf)?)
     }
@@ -594,6 +1098,108 @@ impl StorageReader {
         self.reader
             .contains_key(&self.storage.id2str_cf, &key.to_be_bytes())
     }
+
+    /// The last computed cardinality statistics if any
+    pub fn statistics(&self) -> Option<Arc<Statistics>> {
+        self.storage.statistics.read().unwrap().clone()
+    }
+
+    /// The configuration of the full-text index if it is enabled
+    pub fn text_index(&self) -> Option<Arc<TextIndexOptions>> {
+        self.storage.text_index()
+    }
+
+    /// The literals containing all the given tokens according to the full-text index
+    pub fn text_search(&self, tokens: &[TextToken]) -> Result<Vec<EncodedTerm>> {
+        let mut found: Option<HashSet<EncodedTerm>> = None;
+        for token in tokens {
+            let mut prefix = token.token.as_bytes().to_vec();
+            if !token.is_prefix {
+                prefix.push(0);
+            }
+            let mut literals = HashSet::new();
+            let mut iter = self.reader.scan_prefix(&self.storage.text_cf, &prefix)?;
+            while let Some(key) = iter.key() {
+                let start = key
+                    .iter()
+                    .position(|b| *b == 0)
+                    .ok_or_else(|| invalid_data_error("Invalid full-text index key"))?;
+                let literal = decode_term(&key[start + 1..])?;
+                if found
+                    .as_ref()
+                    .map_or(true, |found| found.contains(&literal))
+                {
+                    literals.insert(literal);
+                }
+                iter.next();
+            }
+            iter.status()?;
+            let is_empty = literals.is_empty();
+            found = Some(literals);
+            if is_empty {
+                break;
+            }
+        }
+        Ok(found.map_or_else(Vec::new, |found| found.into_iter().collect()))
+    }
+
+    /// If the spatial index is enabled
+    pub fn is_geo_index_enabled(&self) -> bool {
+        self.storage.is_geo_index_enabled()
+    }
+
+    /// The WKT literals whose bounding box might intersect the given one according to the spatial index
+    pub fn geo_search(&self, rect: &Rect) -> Result<Vec<EncodedTerm>> {
+        let mut literals = HashSet::new();
+        for range in CellRange::covering(rect) {
+            let mut iter = self
+                .reader
+                .scan_from(&self.storage.geo_cf, &range.start())?;
+            while let Some(key) = iter.key() {
+                let cell = Cell::read(key)
+                    .ok_or_else(|| invalid_data_error("Invalid spatial index key"))?;
+                if range.is_after_end(&cell) {
+                    break;
+                }
+                if range.contains(&cell) {
+                    literals.insert(decode_term(&key[CELL_KEY_SIZE..])?);
+                }
+                iter.next();
+            }
+            iter.status()?;
+        }
+        Ok(literals.into_iter().collect())
+    }
+
+    /// The sequence number of the last logged transaction
+    pub fn last_change_sequence_number(&self) -> Result<Option<u64>> {
+        Ok(
+            if let Some(value) = self
+                .reader
+                .get(&self.storage.default_cf, CHANGE_SEQUENCE_NUMBER_KEY)?
+            {
+                let mut buffer = [0; 8];
+                buffer.copy_from_slice(&value);
+                Some(u64::from_be_bytes(buffer)).filter(|sequence_number| *sequence_number > 0)
+            } else {
+                None
+            },
+        )
+    }
+
+    /// Changes logged with a sequence number strictly greater than `sequence_number`
+    pub fn changes_since(&self, sequence_number: u64) -> Result<ChangeLogIterator> {
+        Ok(ChangeLogIterator {
+            iter: if let Some(start) = sequence_number.checked_add(1) {
+                Some(
+                    self.reader
+                        .scan_from(&self.storage.changes_cf, &start.to_be_bytes())?,
+                )
+            } else {
+                None
+            },
+        })
+    }
 }
 
 pub struct ChainedDecodingQuadIterator {
@@ -666,6 +1272,59 @@ impl Iterator for DecodingGraphIterator {
     }
 }
 
+/// The kind of a [`Change`](crate::store::Change).
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Hash)]
+pub enum ChangeKind {
+    /// The quad has been inserted into the store.
+    Insertion,
+    /// The quad has been removed from the store.
+    Removal,
+}
+
+/// Iterates on the change log in sequence number order
+pub struct ChangeLogIterator {
+    iter: Option<Iter>,
+}
+
+impl Iterator for ChangeLogIterator {
+    type Item = Result<(u64, ChangeKind, EncodedQuad)>;
+
+    fn next(&mut self) -> Option<Result<(u64, ChangeKind, EncodedQuad)>> {
+        let iter = self.iter.as_mut()?;
+        if let Err(e) = iter.status() {
+            return Some(Err(e));
+        }
+        let change = decode_change(iter.key()?, iter.value()?);
+        iter.next();
+        Some(change)
+    }
+}
+
+fn decode_change(key: &[u8], value: &[u8]) -> Result<(u64, ChangeKind, EncodedQuad)> {
+    if key.len() != 12 || value.is_empty() {
+        return Err(invalid_data_error("Invalid change log entry"));
+    }
+    let mut sequence_number = [0; 8];
+    sequence_number.copy_from_slice(&key[..8]);
+    let (kind, encoding) = match value[0] {
+        CHANGE_INSERTION_IN_DEFAULT_GRAPH => (ChangeKind::Insertion, QuadEncoding::Dspo),
+        CHANGE_INSERTION_IN_NAMED_GRAPH => (ChangeKind::Insertion, QuadEncoding::Spog),
+        CHANGE_REMOVAL_FROM_DEFAULT_GRAPH => (ChangeKind::Removal, QuadEncoding::Dspo),
+        CHANGE_REMOVAL_FROM_NAMED_GRAPH => (ChangeKind::Removal, QuadEncoding::Spog),
+        kind => {
+            return Err(invalid_data_error(format!(
+                "Unexpected change log entry kind: {}",
+                kind
+            )))
+        }
+    };
+    Ok((
+        u64::from_be_bytes(sequence_number),
+        kind,
+        encoding.decode(&value[1..])?,
+    ))
+}
+
 impl StrLookup for StorageReader {
     type Error = std::io::Error;
 
@@ -682,6 +1341,9 @@ pub struct StorageWriter<'a> {
     buffer: Vec<u8>,
     transaction: Transaction<'a>,
     storage: &'a Storage,
+    /// The encoded changes to write in the change log, if it is enabled
+    logged_changes: Option<Rc<RefCell<Vec<Vec<u8>>>>>,
+    changes: Option<Rc<RefCell<ChangeSet>>>,
 }
 
 impl<'a> StorageWriter<'a> {
@@ -774,6 +1436,21 @@ impl<'a> StorageWriter<'a> {
                 true
             }
         };
+        if result {
+            self.update_text_index(quad.object, &encoded, true)?;
+            self.update_geo_index(quad.object, &encoded, true)?;
+            self.log_change(
+                if quad.graph_name.is_default_graph() {
+                    CHANGE_INSERTION_IN_DEFAULT_GRAPH
+                } else {
+                    CHANGE_INSERTION_IN_NAMED_GRAPH
+                },
+                &encoded,
+            );
+            if let Some(changes) = &self.changes {
+                changes.borrow_mut().insert(quad.into_owned());
+            }
+        }
         Ok(result)
     }
 
@@ -825,6 +1502,185 @@ impl<'a> StorageWriter<'a> {
     }
 
     fn remove_encoded(&mut self, quad: &EncodedQuad) -> Result<bool> {
+        let result = self.remove_encoded_from_indexes(quad)?;
+        if result {
+            if self.storage.text_index().is_some() || self.storage.is_geo_index_enabled() {
+                let object = self.reader().decode_term(&quad.object)?;
+                self.update_text_index(object.as_ref(), quad, false)?;
+                self.update_geo_index(object.as_ref(), quad, false)?;
+            }
+            self.log_change(
+                if quad.graph_name.is_default_graph() {
+                    CHANGE_REMOVAL_FROM_DEFAULT_GRAPH
+                } else {
+                    CHANGE_REMOVAL_FROM_NAMED_GRAPH
+                },
+                quad,
+            );
+            if let Some(changes) = self.changes.clone() {
+                changes
+                    .borrow_mut()
+                    .remove(self.reader().decode_quad(quad)?);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Adds or removes the full-text index entries of a quad
+    fn update_text_index(
+        &mut self,
+        object: TermRef<'_>,
+        quad: &EncodedQuad,
+        insert: bool,
+    ) -> Result<()> {
+        if let Some(options) = self.storage.text_index() {
+            for key in text_index_keys(&options, object, quad) {
+                if insert {
+                    self.transaction.insert_empty(&self.storage.text_cf, &key)?;
+                } else {
+                    self.transaction.remove(&self.storage.text_cf, &key)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces the full-text index with one built using the given options or removes it if `options` is `None`
+    fn rebuild_text_index(&mut self, options: Option<&TextIndexOptions>) -> Result<()> {
+        let reader = self.reader();
+        let mut keys = Vec::new();
+        let mut iter = reader.reader.scan_prefix(&self.storage.text_cf, &[])?;
+        while let Some(key) = iter.key() {
+            keys.push(key.to_vec());
+            iter.next();
+        }
+        iter.status()?;
+        for key in keys {
+            self.transaction.remove(&self.storage.text_cf, &key)?;
+        }
+        if let Some(options) = options {
+            let mut buffer = Vec::new();
+            options.write(&mut buffer);
+            self.transaction
+                .insert(&self.storage.default_cf, TEXT_INDEX_KEY, &buffer)?;
+            for quad in reader.quads() {
+                let quad = quad?;
+                let object = reader.decode_term(&quad.object)?;
+                for key in text_index_keys(options, object.as_ref(), &quad) {
+                    self.transaction.insert_empty(&self.storage.text_cf, &key)?;
+                }
+            }
+        } else {
+            self.transaction
+                .remove(&self.storage.default_cf, TEXT_INDEX_KEY)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the materialization configuration and computes again its inferred triples or removes the configuration if `materialization` is `None`
+    fn rebuild_materialization(&mut self, materialization: Option<&Materialization>) -> Result<()> {
+        if let Some(materialization) = materialization {
+            let mut buffer = Vec::new();
+            materialization.write(&mut buffer);
+            self.transaction
+                .insert(&self.storage.default_cf, MATERIALIZATION_KEY, &buffer)?;
+            reasoning::rebuild(
+                &mut StorageFacts::new(self, materialization.target_graph.as_ref()),
+                materialization.ruleset,
+            )
+        } else {
+            self.transaction
+                .remove(&self.storage.default_cf, MATERIALIZATION_KEY)
+        }
+    }
+
+    /// Updates the inferred triples according to the changes done by the transaction.
+    ///
+    /// The changes in the graph of the inferred triples are ignored.
+    fn update_materialization(
+        &mut self,
+        materialization: &Materialization,
+        inserted: Vec<Quad>,
+        removed: Vec<Quad>,
+    ) -> Result<()> {
+        let target_graph = reasoning::graph_name(materialization.target_graph.as_ref());
+        let to_facts = |quads: Vec<Quad>| {
+            quads
+                .into_iter()
+                .filter(|quad| quad.graph_name.as_ref() != target_graph)
+                .map(|quad| [quad.subject.into(), quad.predicate.into(), quad.object])
+                .collect::<Vec<_>>()
+        };
+        let inserted = to_facts(inserted);
+        let removed = to_facts(removed);
+        if inserted.is_empty() && removed.is_empty() {
+            return Ok(());
+        }
+        // The inferred triples are computed from reads that are not conflict checked.
+        // Writing this key makes the concurrent maintenances conflict:
+        // the transaction committed last is retried and its inferred triples are computed again from a snapshot containing the other changes.
+        self.transaction
+            .get_for_update(&self.storage.default_cf, MATERIALIZATION_GUARD_KEY)?;
+        self.transaction
+            .insert_empty(&self.storage.default_cf, MATERIALIZATION_GUARD_KEY)?;
+        reasoning::update(
+            &mut StorageFacts::new(self, materialization.target_graph.as_ref()),
+            materialization.ruleset,
+            inserted,
+            removed,
+        )
+    }
+
+    /// Adds or removes the spatial index entry of a quad
+    fn update_geo_index(
+        &mut self,
+        object: TermRef<'_>,
+        quad: &EncodedQuad,
+        insert: bool,
+    ) -> Result<()> {
+        if self.storage.is_geo_index_enabled() {
+            if let Some(key) = geo_index_key(object, quad) {
+                if insert {
+                    self.transaction.insert_empty(&self.storage.geo_cf, &key)?;
+                } else {
+                    self.transaction.remove(&self.storage.geo_cf, &key)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces the spatial index with a new one or removes it if `enabled` is `false`
+    fn rebuild_geo_index(&mut self, enabled: bool) -> Result<()> {
+        let reader = self.reader();
+        let mut keys = Vec::new();
+        let mut iter = reader.reader.scan_prefix(&self.storage.geo_cf, &[])?;
+        while let Some(key) = iter.key() {
+            keys.push(key.to_vec());
+            iter.next();
+        }
+        iter.status()?;
+        for key in keys {
+            self.transaction.remove(&self.storage.geo_cf, &key)?;
+        }
+        if enabled {
+            self.transaction
+                .insert_empty(&self.storage.default_cf, GEO_INDEX_KEY)?;
+            for quad in reader.quads() {
+                let quad = quad?;
+                let object = reader.decode_term(&quad.object)?;
+                if let Some(key) = geo_index_key(object.as_ref(), &quad) {
+                    self.transaction.insert_empty(&self.storage.geo_cf, &key)?;
+                }
+            }
+        } else {
+            self.transaction
+                .remove(&self.storage.default_cf, GEO_INDEX_KEY)?;
+        }
+        Ok(())
+    }
+
+    fn remove_encoded_from_indexes(&mut self, quad: &EncodedQuad) -> Result<bool> {
         self.buffer.clear();
         let result = if quad.graph_name.is_default_graph() {
             write_spo_quad(&mut self.buffer, quad);
@@ -968,6 +1824,108 @@ impl<'a> StorageWriter<'a> {
         }
         Ok(())
     }
+
+    /// Appends a change to the change log of the transaction if the change log is enabled
+    ///
+    /// All the changes of a transaction share the same sequence number, allocated just before the commit.
+    fn log_change(&mut self, kind: u8, quad: &EncodedQuad) {
+        if let Some(logged_changes) = &self.logged_changes {
+            let mut value = Vec::with_capacity(1 + 4 * WRITTEN_TERM_MAX_SIZE);
+            value.push(kind);
+            if quad.graph_name.is_default_graph() {
+                write_spo_quad(&mut value, quad);
+            } else {
+                write_spog_quad(&mut value, quad);
+            }
+            logged_changes.borrow_mut().push(value);
+        }
+    }
+
+    /// Removes from the change log up to [`CHANGE_LOG_TRUNCATION_BATCH_SIZE`] changes with a sequence number strictly lower than `sequence_number`.
+    ///
+    /// Returns `true` if some changes might remain to be removed.
+    fn truncate_changes(&mut self, sequence_number: u64) -> Result<bool> {
+        let reader = self.reader();
+        let mut iter = reader.reader.iter(&self.storage.changes_cf)?;
+        let mut count = 0;
+        while let Some(key) = iter.key() {
+            if key >= &sequence_number.to_be_bytes()[..] {
+                break;
+            }
+            if count == CHANGE_LOG_TRUNCATION_BATCH_SIZE {
+                return Ok(true);
+            }
+            self.transaction.remove(&self.storage.changes_cf, key)?;
+            count += 1;
+            iter.next();
+        }
+        iter.status()?;
+        Ok(false)
+    }
+}
+
+/// Net effect of a transaction: a quad is never both in `inserted` and `removed`
+#[derive(Default)]
+struct ChangeSet {
+    inserted: HashSet<Quad>,
+    removed: HashSet<Quad>,
+}
+
+impl ChangeSet {
+    fn insert(&mut self, quad: Quad) {
+        if !self.removed.remove(&quad) {
+            self.inserted.insert(quad);
+        }
+    }
+
+    fn remove(&mut self, quad: Quad) {
+        if !self.inserted.remove(&quad) {
+            self.removed.insert(quad);
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.inserted.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// The full-text index keys of a quad: a token, a 0 byte, the literal and the rest of the quad
+fn text_index_keys(
+    options: &TextIndexOptions,
+    object: TermRef<'_>,
+    quad: &EncodedQuad,
+) -> Vec<Vec<u8>> {
+    let literal = match object {
+        TermRef::Literal(literal) if literal.is_plain() => literal,
+        _ => return Vec::new(),
+    };
+    options
+        .tokenizer(literal.language())
+        .tokenize(literal.value())
+        .into_iter()
+        .map(|token| {
+            let mut key = Vec::with_capacity(token.len() + 1 + 4 * WRITTEN_TERM_MAX_SIZE);
+            key.extend_from_slice(token.as_bytes());
+            key.push(0);
+            write_osp_quad(&mut key, quad);
+            write_term(&mut key, &quad.graph_name);
+            key
+        })
+        .collect()
+}
+
+/// The spatial index key of a quad: the cell containing the WKT literal bounding box, the literal and the rest of the quad
+fn geo_index_key(object: TermRef<'_>, quad: &EncodedQuad) -> Option<Vec<u8>> {
+    let literal = match object {
+        TermRef::Literal(literal) if literal.datatype().as_str() == WKT_LITERAL => literal,
+        _ => return None,
+    };
+    let rect = literal.value().parse::<Geometry>().ok()?.bounding_box()?;
+    let mut key = Vec::with_capacity(CELL_KEY_SIZE + 4 * WRITTEN_TERM_MAX_SIZE);
+    Cell::containing(&rect).write(&mut key);
+    write_osp_quad(&mut key, quad);
+    write_term(&mut key, &quad.graph_name);
+    Some(key)
 }
 
 /// Creates a database from a dataset files.
@@ -999,6 +1957,10 @@ struct BulkLoader {
     quads: HashSet<EncodedQuad>,
     triples: HashSet<EncodedQuad>,
     graphs: HashSet<EncodedTerm>,
+    text_index: Option<Arc<TextIndexOptions>>,
+    text: HashSet<Vec<u8>>,
+    is_geo_index_enabled: bool,
+    geo: HashSet<Vec<u8>>,
     buffer: Vec<u8>,
 }
 
@@ -1006,12 +1968,16 @@ struct BulkLoader {
 impl BulkLoader {
     fn new(storage: Storage) -> Self {
         Self {
-            storage,
             id2str: HashMap::default(),
             quads: HashSet::default(),
             triples: HashSet::default(),
             graphs: HashSet::default(),
+            text_index: storage.text_index(),
+            text: HashSet::default(),
+            is_geo_index_enabled: storage.is_geo_index_enabled(),
+            geo: HashSet::default(),
             buffer: Vec::new(),
+            storage,
         }
     }
 
@@ -1025,6 +1991,8 @@ impl BulkLoader {
                     self.insert_term(quad.subject.as_ref().into(), &encoded.subject)?;
                     self.insert_term(quad.predicate.as_ref().into(), &encoded.predicate)?;
                     self.insert_term(quad.object.as_ref(), &encoded.object)?;
+                    self.insert_text(quad.object.as_ref(), &encoded);
+                    self.insert_geo(quad.object.as_ref(), &encoded);
                 }
             } else {
                 write_spog_quad(&mut self.buffer, &encoded);
@@ -1032,6 +2000,8 @@ impl BulkLoader {
                     self.insert_term(quad.subject.as_ref().into(), &encoded.subject)?;
                     self.insert_term(quad.predicate.as_ref().into(), &encoded.predicate)?;
                     self.insert_term(quad.object.as_ref(), &encoded.object)?;
+                    self.insert_text(quad.object.as_ref(), &encoded);
+                    self.insert_geo(quad.object.as_ref(), &encoded);
 
                     self.buffer.clear();
                     write_term(&mut self.buffer, &encoded.graph_name);
@@ -1172,7 +2142,25 @@ impl BulkLoader {
             self.quads.clear();
         }
 
-        self.storage.db.insert_stt_files(&to_load)
+        if !self.text.is_empty() {
+            to_load.push((
+                &self.storage.text_cf,
+                self.build_sst_for_keys(self.text.iter().cloned())?,
+            ));
+            self.text.clear();
+        }
+
+        if !self.geo.is_empty() {
+            to_load.push((
+                &self.storage.geo_cf,
+                self.build_sst_for_keys(self.geo.iter().cloned())?,
+            ));
+            self.geo.clear();
+        }
+
+        self.storage.db.insert_stt_files(&to_load)?;
+        self.storage.bump_generation();
+        Ok(())
     }
 
     fn insert_term(&mut self, term: TermRef<'_>, encoded: &EncodedTerm) -> Result<()> {
@@ -1182,6 +2170,18 @@ impl BulkLoader {
         })
     }
 
+    fn insert_text(&mut self, object: TermRef<'_>, quad: &EncodedQuad) {
+        if let Some(options) = &self.text_index {
+            self.text.extend(text_index_keys(options, object, quad));
+        }
+    }
+
+    fn insert_geo(&mut self, object: TermRef<'_>, quad: &EncodedQuad) {
+        if self.is_geo_index_enabled {
+            self.geo.extend(geo_index_key(object, quad));
+        }
+    }
+
     fn build_sst_for_keys(&self, values: impl Iterator<Item = Vec<u8>>) -> Result<PathBuf> {
         let mut values = values.collect::<Vec<_>>();
         values.sort_unstable();
@@ -1192,3 +2192,59 @@ impl BulkLoader {
         sst.finish()
     }
 }
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use rand::random;
+    use std::env::temp_dir;
+    use std::fs::remove_dir_all;
+
+    #[test]
+    fn test_open_read_only_without_new_column_families() -> Result<()> {
+        let path = temp_dir().join(format!("oxigraph-test-{}", random::<u128>()));
+        let secondary_path = temp_dir().join(format!("oxigraph-test-{}", random::<u128>()));
+        {
+            // A database written by a version without the change log and the text and geo indexes
+            let db = Db::open(
+                &path,
+                Storage::column_families()
+                    .into_iter()
+                    .filter(|cf| ![CHANGES_CF, TEXT_CF, GEO_CF].contains(&cf.name))
+                    .collect(),
+            )?;
+            let default_cf = db.column_family(DEFAULT_CF).unwrap();
+            db.transaction(|mut t| {
+                t.insert(
+                    &default_cf,
+                    b"oxversion",
+                    &LATEST_STORAGE_VERSION.to_be_bytes(),
+                )
+            })?;
+        }
+
+        for storage in [
+            Storage::open_read_only(&path)?,
+            Storage::open_secondary(&path, &secondary_path)?,
+        ] {
+            let reader = storage.snapshot();
+            assert!(reader.is_empty()?);
+            assert_eq!(reader.last_change_sequence_number()?, None);
+            assert_eq!(reader.changes_since(0)?.count(), 0);
+            let tokens = [TextToken {
+                token: "foo".into(),
+                is_prefix: true,
+            }];
+            assert!(reader.text_search(&tokens)?.is_empty());
+        }
+        remove_dir_all(&path)?;
+        remove_dir_all(&secondary_path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_read_only_requires_migration() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/rocksdb_bc_data");
+        assert!(Storage::open_read_only(&path).is_err());
+    }
+}
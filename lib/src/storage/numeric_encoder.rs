@@ -10,7 +10,7 @@ use std::error::Error;
 use std::fmt::Debug;
 use std::hash::Hash;
 use std::hash::Hasher;
-use std::rc::Rc;
+use std::sync::Arc;
 use std::{fmt, io, str};
 
 #[derive(Eq, PartialEq, Debug, Clone, Copy, Hash)]
@@ -98,7 +98,7 @@ pub enum EncodedTerm {
     DurationLiteral(Duration),
     YearMonthDurationLiteral(YearMonthDuration),
     DayTimeDurationLiteral(DayTimeDuration),
-    Triple(Rc<EncodedTriple>),
+    Triple(Arc<EncodedTriple>),
 }
 
 impl PartialEq for EncodedTerm {
@@ -427,7 +427,7 @@ impl From<DayTimeDuration> for EncodedTerm {
 
 impl From<EncodedTriple> for EncodedTerm {
     fn from(value: EncodedTriple) -> Self {
-        Self::Triple(Rc::new(value))
+        Self::Triple(Arc::new(value))
     }
 }
 
@@ -591,7 +591,7 @@ impl From<GraphNameRef<'_>> for EncodedTerm {
 
 impl From<TripleRef<'_>> for EncodedTerm {
     fn from(triple: TripleRef<'_>) -> Self {
-        Self::Triple(Rc::new(triple.into()))
+        Self::Triple(Arc::new(triple.into()))
     }
 }
 
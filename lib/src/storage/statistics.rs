@@ -0,0 +1,164 @@
+//! Cardinality statistics about the stored quads used by the query planner.
+
+use crate::storage::binary_encoder::encode_term;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::storage::numeric_encoder::EncodedQuad;
+use crate::storage::numeric_encoder::EncodedTerm;
+use std::collections::HashMap;
+use std::io::{Read, Result};
+
+/// Quad counts of the whole store, of each predicate and of each named graph.
+///
+/// The distinct subject and object counts are upper bounds: a term used in multiple graphs or with multiple predicates might be counted multiple times.
+///
+/// The predicates and graph names are stored in their binary encoding to keep this struct [`Send`] and [`Sync`].
+#[derive(Default, Debug, Clone)]
+pub struct Statistics {
+    quad_count: u64,
+    subject_count: u64,
+    object_count: u64,
+    predicates: HashMap<Vec<u8>, PredicateStatistics>,
+    graphs: HashMap<Vec<u8>, u64>,
+}
+
+#[derive(Default, Debug, Clone, Copy)]
+pub struct PredicateStatistics {
+    pub quad_count: u64,
+    pub subject_count: u64,
+    pub object_count: u64,
+}
+
+impl Statistics {
+    pub fn quad_count(&self) -> u64 {
+        self.quad_count
+    }
+
+    pub fn subject_count(&self) -> u64 {
+        self.subject_count
+    }
+
+    pub fn object_count(&self) -> u64 {
+        self.object_count
+    }
+
+    pub fn predicate_count(&self) -> u64 {
+        self.predicates.len() as u64
+    }
+
+    pub fn predicate(&self, predicate: &EncodedTerm) -> Option<&PredicateStatistics> {
+        self.predicates.get(&encode_term(predicate))
+    }
+
+    pub fn named_graph_count(&self) -> u64 {
+        self.graphs.len() as u64
+    }
+
+    /// Number of quads in the given named graph, `None` if the graph was not there when the statistics were computed
+    pub fn named_graph_quad_count(&self, graph_name: &EncodedTerm) -> Option<u64> {
+        self.graphs.get(&encode_term(graph_name)).copied()
+    }
+
+    /// Registers a quad read from an index sorted by subject then predicate.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn add_in_subject_order(&mut self, quad: &EncodedQuad, previous: Option<&EncodedQuad>) {
+        self.quad_count += 1;
+        if !quad.graph_name.is_default_graph() {
+            *self
+                .graphs
+                .entry(encode_term(&quad.graph_name))
+                .or_default() += 1;
+        }
+        let same_subject = previous.map_or(false, |p| p.subject == quad.subject);
+        if !same_subject {
+            self.subject_count += 1;
+        }
+        let predicate = self
+            .predicates
+            .entry(encode_term(&quad.predicate))
+            .or_default();
+        predicate.quad_count += 1;
+        if !same_subject || previous.map_or(true, |p| p.predicate != quad.predicate) {
+            predicate.subject_count += 1;
+        }
+    }
+
+    /// Registers a quad read from an index sorted by predicate then object.
+    ///
+    /// It should be called after all the [`add_in_subject_order`](Statistics::add_in_subject_order) calls.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn add_in_predicate_order(&mut self, quad: &EncodedQuad, previous: Option<&EncodedQuad>) {
+        if previous.map_or(false, |p| {
+            p.predicate == quad.predicate && p.object == quad.object
+        }) {
+            return;
+        }
+        self.object_count += 1;
+        self.predicates
+            .entry(encode_term(&quad.predicate))
+            .or_default()
+            .object_count += 1;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn write(&self, sink: &mut Vec<u8>) {
+        sink.extend_from_slice(&self.quad_count.to_be_bytes());
+        sink.extend_from_slice(&self.subject_count.to_be_bytes());
+        sink.extend_from_slice(&self.object_count.to_be_bytes());
+        sink.extend_from_slice(&(self.predicates.len() as u64).to_be_bytes());
+        for (predicate, statistics) in &self.predicates {
+            write_bytes(sink, predicate);
+            sink.extend_from_slice(&statistics.quad_count.to_be_bytes());
+            sink.extend_from_slice(&statistics.subject_count.to_be_bytes());
+            sink.extend_from_slice(&statistics.object_count.to_be_bytes());
+        }
+        sink.extend_from_slice(&(self.graphs.len() as u64).to_be_bytes());
+        for (graph_name, quad_count) in &self.graphs {
+            write_bytes(sink, graph_name);
+            sink.extend_from_slice(&quad_count.to_be_bytes());
+        }
+    }
+
+    pub fn read(mut buffer: &[u8]) -> Result<Self> {
+        let mut statistics = Self {
+            quad_count: read_u64(&mut buffer)?,
+            subject_count: read_u64(&mut buffer)?,
+            object_count: read_u64(&mut buffer)?,
+            ..Self::default()
+        };
+        for _ in 0..read_u64(&mut buffer)? {
+            let predicate = read_bytes(&mut buffer)?;
+            statistics.predicates.insert(
+                predicate,
+                PredicateStatistics {
+                    quad_count: read_u64(&mut buffer)?,
+                    subject_count: read_u64(&mut buffer)?,
+                    object_count: read_u64(&mut buffer)?,
+                },
+            );
+        }
+        for _ in 0..read_u64(&mut buffer)? {
+            let graph_name = read_bytes(&mut buffer)?;
+            statistics.graphs.insert(graph_name, read_u64(&mut buffer)?);
+        }
+        Ok(statistics)
+    }
+}
+
+fn read_u64(reader: &mut impl Read) -> Result<u64> {
+    let mut buffer = [0; 8];
+    reader.read_exact(&mut buffer)?;
+    Ok(u64::from_be_bytes(buffer))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_bytes(sink: &mut Vec<u8>, bytes: &[u8]) {
+    sink.extend_from_slice(&(bytes.len() as u64).to_be_bytes());
+    sink.extend_from_slice(bytes);
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn read_bytes(reader: &mut impl Read) -> Result<Vec<u8>> {
+    let mut buffer = vec![0; read_u64(reader)? as usize];
+    reader.read_exact(&mut buffer)?;
+    Ok(buffer)
+}
@@ -0,0 +1,222 @@
+//! Tokenization of the literals indexed by the full-text index.
+
+use crate::error::invalid_data_error;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::io::{Read, Result};
+
+/// How the full-text index splits the literals into tokens.
+///
+/// The tokens are lowercased.
+#[derive(Eq, PartialEq, Debug, Clone, Copy, Hash)]
+#[non_exhaustive]
+pub enum Tokenizer {
+    /// Each maximal sequence of alphanumeric characters is a token.
+    ///
+    /// It is suitable for languages using spaces or punctuation between words.
+    Words,
+    /// Each alphanumeric character is a token.
+    ///
+    /// It is suitable for languages without word separators like Chinese or Japanese.
+    Characters,
+}
+
+impl Tokenizer {
+    /// Returns the distinct tokens of the text
+    pub(crate) fn tokenize(self, text: &str) -> BTreeSet<String> {
+        self.split(text).into_iter().collect()
+    }
+
+    /// Returns the tokens of the text in order
+    fn split(self, text: &str) -> Vec<String> {
+        match self {
+            Self::Words => text
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|word| !word.is_empty())
+                .map(str::to_lowercase)
+                .collect(),
+            Self::Characters => text
+                .chars()
+                .filter(|c| c.is_alphanumeric())
+                .map(|c| c.to_lowercase().collect())
+                .collect(),
+        }
+    }
+
+    fn id(self) -> u8 {
+        match self {
+            Self::Words => 0,
+            Self::Characters => 1,
+        }
+    }
+
+    fn from_id(id: u8) -> Result<Self> {
+        match id {
+            0 => Ok(Self::Words),
+            1 => Ok(Self::Characters),
+            _ => Err(invalid_data_error(format!(
+                "Unknown full-text index tokenizer {}",
+                id
+            ))),
+        }
+    }
+}
+
+/// Configuration of the full-text index built using [`Store::enable_text_index`](crate::store::Store::enable_text_index).
+///
+/// The tokenizer of a language tagged literal is the one registered for its language tag,
+/// or else for the language tag with its last subtags removed (`zh` for `zh-Hant-TW`),
+/// or else the default tokenizer.
+///
+/// ```
+/// use oxigraph::store::{Store, TextIndexOptions, Tokenizer};
+///
+/// let store = Store::new()?;
+/// store.enable_text_index(
+///     TextIndexOptions::default()
+///         .with_language_tokenizer("zh", Tokenizer::Characters)
+///         .with_language_tokenizer("ja", Tokenizer::Characters),
+/// )?;
+/// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+/// ```
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct TextIndexOptions {
+    default_tokenizer: Tokenizer,
+    language_tokenizers: BTreeMap<String, Tokenizer>,
+}
+
+impl Default for TextIndexOptions {
+    fn default() -> Self {
+        Self {
+            default_tokenizer: Tokenizer::Words,
+            language_tokenizers: BTreeMap::new(),
+        }
+    }
+}
+
+impl TextIndexOptions {
+    /// Sets the tokenizer used for the literals without a more specific tokenizer. It is [`Tokenizer::Words`] by default.
+    #[inline]
+    pub fn with_default_tokenizer(mut self, tokenizer: Tokenizer) -> Self {
+        self.default_tokenizer = tokenizer;
+        self
+    }
+
+    /// Sets the tokenizer used for the literals with the given language tag or one of its sub-tags.
+    #[inline]
+    pub fn with_language_tokenizer(mut self, language: &str, tokenizer: Tokenizer) -> Self {
+        self.language_tokenizers
+            .insert(language.to_ascii_lowercase(), tokenizer);
+        self
+    }
+
+    pub(crate) fn tokenizer(&self, language: Option<&str>) -> Tokenizer {
+        if let Some(language) = language {
+            let mut language = language.to_ascii_lowercase();
+            loop {
+                if let Some(tokenizer) = self.language_tokenizers.get(&language) {
+                    return *tokenizer;
+                }
+                if let Some(end) = language.rfind('-') {
+                    language.truncate(end);
+                } else {
+                    break;
+                }
+            }
+        }
+        self.default_tokenizer
+    }
+
+    /// Parses a search query: whitespace separated terms that should all be in the literal.
+    ///
+    /// A term ending with `*` matches all the tokens starting with it.
+    pub(crate) fn parse_query(&self, query: &str, language: Option<&str>) -> Vec<TextToken> {
+        let tokenizer = self.tokenizer(language);
+        let mut tokens = Vec::new();
+        for term in query.split_whitespace() {
+            let (term, is_prefix) = if let Some(term) = term.strip_suffix('*') {
+                (term, true)
+            } else {
+                (term, false)
+            };
+            // The prefix only applies to the last token of the term
+            let term_tokens = tokenizer.split(term);
+            let last = term_tokens.len().saturating_sub(1);
+            for (i, token) in term_tokens.into_iter().enumerate() {
+                tokens.push(TextToken {
+                    token,
+                    is_prefix: is_prefix && i == last,
+                });
+            }
+        }
+        tokens
+    }
+
+    pub(crate) fn write(&self, sink: &mut Vec<u8>) {
+        sink.push(self.default_tokenizer.id());
+        sink.extend_from_slice(&(self.language_tokenizers.len() as u64).to_be_bytes());
+        for (language, tokenizer) in &self.language_tokenizers {
+            sink.push(tokenizer.id());
+            sink.extend_from_slice(&(language.len() as u64).to_be_bytes());
+            sink.extend_from_slice(language.as_bytes());
+        }
+    }
+
+    pub(crate) fn read(mut buffer: &[u8]) -> Result<Self> {
+        let mut options = Self {
+            default_tokenizer: Tokenizer::from_id(read_u8(&mut buffer)?)?,
+            language_tokenizers: BTreeMap::new(),
+        };
+        for _ in 0..read_u64(&mut buffer)? {
+            let tokenizer = Tokenizer::from_id(read_u8(&mut buffer)?)?;
+            #[allow(clippy::cast_possible_truncation)]
+            let mut language = vec![0; read_u64(&mut buffer)? as usize];
+            buffer.read_exact(&mut language)?;
+            options.language_tokenizers.insert(
+                String::from_utf8(language).map_err(invalid_data_error)?,
+                tokenizer,
+            );
+        }
+        Ok(options)
+    }
+}
+
+fn read_u8(reader: &mut impl Read) -> Result<u8> {
+    let mut buffer = [0; 1];
+    reader.read_exact(&mut buffer)?;
+    Ok(buffer[0])
+}
+
+fn read_u64(reader: &mut impl Read) -> Result<u64> {
+    let mut buffer = [0; 8];
+    reader.read_exact(&mut buffer)?;
+    Ok(u64::from_be_bytes(buffer))
+}
+
+/// A token of a full-text search query
+#[derive(Eq, PartialEq, Debug, Clone, Hash)]
+pub struct TextToken {
+    pub token: String,
+    /// If all the tokens starting with `token` match
+    pub is_prefix: bool,
+}
+
+impl TextToken {
+    pub fn matches(&self, token: &str) -> bool {
+        if self.is_prefix {
+            token.starts_with(&self.token)
+        } else {
+            token == self.token
+        }
+    }
+}
+
+impl fmt::Display for TextToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_prefix {
+            write!(f, "{}*", self.token)
+        } else {
+            self.token.fmt(f)
+        }
+    }
+}
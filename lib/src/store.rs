@@ -28,17 +28,27 @@ use crate::io::{
     DatasetFormat, DatasetParser, DatasetSerializer, GraphFormat, GraphParser, GraphSerializer,
 };
 use crate::model::*;
+use crate::reasoning::{ConstructRules, Materialization, Ruleset};
+use crate::shacl::{ShapesGraph, ValidationReport};
 use crate::sparql::{
-    evaluate_query, evaluate_update, EvaluationError, Query, QueryOptions, QueryResults, Update,
-    UpdateOptions,
+    evaluate_query, evaluate_query_with_memory_graph, evaluate_update, explain_query,
+    EvaluationError, PreparedQuery, Query, QueryCache, QueryExplanation, QueryOptions,
+    QueryResults, ThreadPool, Update, UpdateOptions,
 };
 #[cfg(not(target_arch = "wasm32"))]
 use crate::storage::bulk_load;
 use crate::storage::numeric_encoder::{Decoder, EncodedQuad, EncodedTerm};
-use crate::storage::{ChainedDecodingQuadIterator, DecodingGraphIterator, Storage, StorageReader};
+pub use crate::storage::text::{TextIndexOptions, Tokenizer};
+pub use crate::storage::ChangeKind;
+use crate::storage::{
+    ChainedDecodingQuadIterator, ChangeLogIterator, DecodingGraphIterator, SharedStorageReader,
+    Storage, StorageReader, StorageWriter,
+};
+use std::error::Error;
 use std::io::{BufRead, Write};
 #[cfg(not(target_arch = "wasm32"))]
 use std::path::Path;
+use std::sync::Arc;
 use std::{fmt, io, str};
 
 /// An on-disk [RDF dataset](https://www.w3.org/TR/rdf11-concepts/#dfn-rdf-dataset).
@@ -80,6 +90,9 @@ use std::{fmt, io, str};
 #[derive(Clone)]
 pub struct Store {
     storage: Storage,
+    query_cache: Option<Arc<QueryCache>>,
+    /// The threads used by the queries evaluated in parallel
+    thread_pool: Arc<ThreadPool>,
 }
 
 //TODO: indexes for the default graph and indexes for the named graphs (no more Optional and space saving)
@@ -89,6 +102,8 @@ impl Store {
     pub fn new() -> io::Result<Self> {
         Ok(Self {
             storage: Storage::new()?,
+            query_cache: None,
+            thread_pool: Arc::default(),
         })
     }
 
@@ -97,9 +112,85 @@ impl Store {
     pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
         Ok(Self {
             storage: Storage::open(path.as_ref())?,
+            query_cache: None,
+            thread_pool: Arc::default(),
         })
     }
 
+    /// Opens a read-only [`Store`] from a database that already exists.
+    ///
+    /// The database might be opened at the same time by an other process using [`Store::open`].
+    /// In this case, the read-only store only sees the data as it was at opening time.
+    /// Use [`Store::open_secondary`] to be able to follow the changes done by the other process.
+    ///
+    /// The database is not migrated: a database written by an older Oxigraph version must be opened once with [`Store::open`] if its encoding is outdated.
+    ///
+    /// All write operations on the returned store fail with a [`PermissionDenied`](io::ErrorKind::PermissionDenied) error.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn open_read_only(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self {
+            storage: Storage::open_read_only(path.as_ref())?,
+            query_cache: None,
+            thread_pool: Arc::default(),
+        })
+    }
+
+    /// Opens a read-only [`Store`] following a primary database opened by an other process using [`Store::open`].
+    ///
+    /// The `secondary_path` directory is used to store the secondary instance own metadata.
+    /// The store sees the data as it was at opening time and is only refreshed by explicit calls to [`Store::catch_up_with_primary`].
+    ///
+    /// All write operations on the returned store fail with a [`PermissionDenied`](io::ErrorKind::PermissionDenied) error.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn open_secondary(
+        primary_path: impl AsRef<Path>,
+        secondary_path: impl AsRef<Path>,
+    ) -> io::Result<Self> {
+        Ok(Self {
+            storage: Storage::open_secondary(primary_path.as_ref(), secondary_path.as_ref())?,
+            query_cache: None,
+            thread_pool: Arc::default(),
+        })
+    }
+
+    /// Enables an in-memory cache of the results of the queries evaluated with [`Store::query`] and [`Store::query_opt`].
+    ///
+    /// The results of the `capacity` most recently used queries are kept and returned again as long as the store is not written to.
+    /// The least recently used results are evicted to keep the estimated size of the cached results below `max_memory_size` bytes.
+    /// Queries are compared after parsing so their formatting and their prefix declarations do not matter.
+    ///
+    /// The results are loaded in memory before being returned, unless they are bigger than `max_memory_size` or contain an error:
+    /// they are then returned without being cached.
+    /// The result limits set in [`QueryOptions`] also apply to the results returned from the cache.
+    /// Queries evaluated with a custom [`ServiceHandler`](crate::sparql::ServiceHandler), custom functions or a [`QueryProfile`](crate::sparql::QueryProfile) are not cached.
+    /// Beware that the values returned by functions like `NOW()` or `RAND()` are cached too.
+    ///
+    /// The cache is shared by the clones of the returned store.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::store::Store;
+    /// use oxigraph::model::*;
+    /// use oxigraph::sparql::QueryResults;
+    ///
+    /// let store = Store::new()?.with_query_cache(100, 1_000_000);
+    /// let ex = NamedNodeRef::new("http://example.com")?;
+    /// store.insert(QuadRef::new(ex, ex, ex, GraphNameRef::DefaultGraph))?;
+    ///
+    /// // The second evaluation is served from the cache
+    /// for _ in 0..2 {
+    ///     if let QueryResults::Solutions(solutions) = store.query("SELECT ?s WHERE { ?s ?p ?o }")? {
+    ///         assert_eq!(solutions.count(), 1);
+    ///     }
+    /// }
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    #[inline]
+    pub fn with_query_cache(mut self, capacity: usize, max_memory_size: usize) -> Self {
+        self.query_cache = Some(Arc::new(QueryCache::new(capacity, max_memory_size)));
+        self
+    }
+
     /// Executes a [SPARQL 1.1 query](https://www.w3.org/TR/sparql11-query/).
     ///
     /// Usage example:
@@ -133,7 +224,91 @@ impl Store {
         query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
         options: QueryOptions,
     ) -> Result<QueryResults, EvaluationError> {
-        evaluate_query(self.storage.clone(), query, options)
+        let options = options.with_thread_pool(self.thread_pool.clone());
+        if let Some(query_cache) = &self.query_cache {
+            if options.is_cacheable() {
+                let query = query.try_into().map_err(std::convert::Into::into)?;
+                // The generation is read before the snapshot is taken so the cached results are never older than it
+                let generation = self.storage.generation();
+                return query_cache.get_or_evaluate(
+                    query,
+                    options,
+                    generation,
+                    |query, options| evaluate_query(self.storage.snapshot(), query, options),
+                );
+            }
+        }
+        evaluate_query(self.storage.snapshot(), query, options)
+    }
+
+    /// Parses and plans a [SPARQL 1.1 query](https://www.w3.org/TR/sparql11-query/) in order to execute it multiple times with different variable values.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::store::Store;
+    /// use oxigraph::model::*;
+    /// use oxigraph::sparql::{QueryResults, Variable};
+    ///
+    /// let store = Store::new()?;
+    /// let ex = NamedNodeRef::new("http://example.com")?;
+    /// store.insert(QuadRef::new(ex, ex, ex, GraphNameRef::DefaultGraph))?;
+    ///
+    /// let prepared = store.prepare("ASK { ?s ?p ?o }")?;
+    /// for (subject, expected) in [(ex, true), (NamedNodeRef::new("http://example.com/other")?, false)] {
+    ///     if let QueryResults::Boolean(result) = prepared.execute([(Variable::new("s")?, subject.into_owned().into())])? {
+    ///         assert_eq!(result, expected);
+    ///     }
+    /// }
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn prepare(
+        &self,
+        query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
+    ) -> Result<PreparedQuery, EvaluationError> {
+        self.prepare_opt(query, QueryOptions::default())
+    }
+
+    /// Parses and plans a [SPARQL 1.1 query](https://www.w3.org/TR/sparql11-query/) with some options in order to execute it multiple times.
+    ///
+    /// The options are used by each execution.
+    pub fn prepare_opt(
+        &self,
+        query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
+        options: QueryOptions,
+    ) -> Result<PreparedQuery, EvaluationError> {
+        PreparedQuery::new(
+            self.storage.clone(),
+            query,
+            options.with_thread_pool(self.thread_pool.clone()),
+        )
+    }
+
+    /// Returns the plan that would be used to evaluate a [SPARQL 1.1 query](https://www.w3.org/TR/sparql11-query/) without evaluating it.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::store::Store;
+    ///
+    /// let store = Store::new()?;
+    /// let explanation = store.explain("SELECT ?s WHERE { ?s ?p ?o . ?o ?p2 ?o2 }")?;
+    /// println!("{}", explanation);
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn explain(
+        &self,
+        query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
+    ) -> Result<QueryExplanation, EvaluationError> {
+        self.explain_opt(query, QueryOptions::default())
+    }
+
+    /// Returns the plan that would be used to evaluate a [SPARQL 1.1 query](https://www.w3.org/TR/sparql11-query/) with some options without evaluating it.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn explain_opt(
+        &self,
+        query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
+        options: QueryOptions,
+    ) -> Result<QueryExplanation, EvaluationError> {
+        explain_query(self.storage.snapshot(), query, &options)
     }
 
     /// Retrieves quads with a filter on each quad component
@@ -197,10 +372,81 @@ impl Store {
         self.storage.snapshot().is_empty()
     }
 
+    /// Executes a transaction.
+    ///
+    /// Transactions ensure the "repeatable read" isolation level: the store only exposes changes that have
+    /// been "committed" (i.e. no partial writes) and the exposed state does not change for the complete duration
+    /// of the transaction. Reads done inside of the transaction see its own writes.
+    ///
+    /// The transaction is committed if the closure returns `Ok` and rolled back if it returns `Err`.
+    /// If the transaction conflicts with another concurrent write, it is rolled back and the closure is executed again.
+    /// The closure should hence not have side effects outside of the transaction.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::store::Store;
+    /// use oxigraph::model::*;
+    ///
+    /// let store = Store::new()?;
+    /// let a = NamedNodeRef::new("http://example.com/a")?;
+    /// let b = NamedNodeRef::new("http://example.com/b")?;
+    /// store.insert(QuadRef::new(a, a, a, GraphNameRef::DefaultGraph))?;
+    ///
+    /// // Copy all triples about ex:a to triples about ex:b
+    /// store.transaction(|mut transaction| {
+    ///     for q in transaction.quads_for_pattern(Some(a.into()), None, None, None) {
+    ///         let q = q?;
+    ///         transaction.insert(QuadRef::new(b, &q.predicate, &q.object, &q.graph_name))?;
+    ///     }
+    ///     std::io::Result::Ok(())
+    /// })?;
+    /// assert!(store.contains(QuadRef::new(b, a, a, GraphNameRef::DefaultGraph))?);
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn transaction<'a, 'b: 'a, T, E: Error + 'static + From<io::Error>>(
+        &'b self,
+        f: impl Fn(Transaction<'a>) -> Result<T, E>,
+    ) -> Result<T, E> {
+        self.storage.transaction(|writer| f(Transaction { writer }))
+    }
+
+    /// Registers a callback called after each successful commit with the quads inserted and removed by the commit.
+    ///
+    /// It is called for all write operations except [`bulk_load_dataset`](Store::bulk_load_dataset) and [`bulk_load_graph`](Store::bulk_load_graph):
+    /// single quad insertions and removals, graph clears, file loads, SPARQL updates and [transactions](Store::transaction).
+    /// The two slices contain the net effect of the commit: a quad inserted then removed by the same transaction is not reported
+    /// and a quad is never both in the inserted and the removed quads.
+    /// Commits that do not change any quad do not trigger the callback.
+    ///
+    /// The callback is executed synchronously by the thread doing the write, after the commit.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::store::Store;
+    /// use oxigraph::model::*;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let store = Store::new()?;
+    /// let log = Arc::new(Mutex::new(Vec::new()));
+    /// let listener_log = log.clone();
+    /// store.on_commit(move |inserted, removed| {
+    ///     listener_log.lock().unwrap().push((inserted.len(), removed.len()));
+    /// });
+    ///
+    /// let ex = NamedNodeRef::new("http://example.com")?;
+    /// store.insert(QuadRef::new(ex, ex, ex, GraphNameRef::DefaultGraph))?;
+    /// store.update("DELETE WHERE { ?s ?p ?o }")?;
+    /// assert_eq!(*log.lock().unwrap(), vec![(1, 0), (0, 1)]);
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn on_commit(&self, callback: impl Fn(&[Quad], &[Quad]) + Send + Sync + 'static) {
+        self.storage.add_commit_listener(Arc::new(callback))
+    }
+
     /// Executes a [SPARQL 1.1 update](https://www.w3.org/TR/sparql11-update/).
     ///
     /// The store does not track the existence of empty named graphs.
-    /// This method has no ACID guarantees.
+    /// This method is atomic: all the update operations are executed in a single transaction.
     ///
     /// Usage example:
     /// ```
@@ -225,16 +471,15 @@ impl Store {
     }
 
     /// Executes a [SPARQL 1.1 update](https://www.w3.org/TR/sparql11-update/) with some options.
+    #[allow(clippy::needless_pass_by_value)]
     pub fn update_opt(
         &self,
         update: impl TryInto<Update, Error = impl Into<EvaluationError>>,
         options: UpdateOptions,
     ) -> Result<(), EvaluationError> {
-        evaluate_update(
-            &self.storage,
-            update.try_into().map_err(std::convert::Into::into)?,
-            options,
-        )
+        let update = update.try_into().map_err(std::convert::Into::into)?;
+        self.storage
+            .transaction(|mut t| evaluate_update(&mut t, &update, &options))
     }
 
     /// Loads a graph file (i.e. triples) into the store.
@@ -602,12 +847,370 @@ impl Store {
     ///
     /// Useful to call after a batch upload or an other similar operation.
     ///
+    /// It also computes statistics about the store content like the number of quads per predicate.
+    /// They are persisted in the database and used by the SPARQL query planner to choose the order of the joins.
+    /// They are not updated by the following writes, so this method should be called again after significant changes.
+    ///
     /// Warning: Can take hours on huge databases.
     #[cfg(not(target_arch = "wasm32"))]
     pub fn optimize(&self) -> io::Result<()> {
+        self.storage.refresh_statistics()?;
         self.storage.compact()
     }
 
+    /// Builds a full-text index of the store literals and keeps it up to date with the following writes.
+    ///
+    /// The simple literals and the language-tagged strings are split into tokens according to the given options.
+    /// The index is persisted in the database: it does not need to be enabled again when the database is opened again.
+    /// If it is already enabled, it is built again with the new options.
+    ///
+    /// It is queried in SPARQL with the `<http://oxigraph.org/text#match>` property:
+    /// `?literal text:match "query"` binds `?literal` to the literals of the store containing all the words of the query.
+    /// A word ending with `*` matches all the tokens starting with it.
+    /// The query tokenizer is chosen using the query literal language tag.
+    /// The literals are looked for in the complete store whatever the query dataset.
+    ///
+    /// Warning: Indexes all the store content in a single transaction.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::store::{Store, TextIndexOptions};
+    /// use oxigraph::model::*;
+    /// use oxigraph::sparql::QueryResults;
+    ///
+    /// let store = Store::new()?;
+    /// store.enable_text_index(TextIndexOptions::default())?;
+    /// let ex = NamedNodeRef::new("http://example.com")?;
+    /// store.insert(QuadRef::new(ex, ex, LiteralRef::new_simple_literal("Hello World"), GraphNameRef::DefaultGraph))?;
+    ///
+    /// if let QueryResults::Solutions(mut solutions) = store.query("PREFIX text: <http://oxigraph.org/text#> SELECT ?s WHERE { ?l text:match \"wor*\" . ?s ?p ?l }")? {
+    ///     assert_eq!(solutions.next().unwrap()?.get("s"), Some(&ex.into_owned().into()));
+    /// }
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn enable_text_index(&self, options: TextIndexOptions) -> io::Result<()> {
+        self.storage.set_text_index(Some(options))
+    }
+
+    /// Removes the full-text index built by [`Store::enable_text_index`].
+    pub fn disable_text_index(&self) -> io::Result<()> {
+        self.storage.set_text_index(None)
+    }
+
+    /// Builds a spatial index of the store [WKT literals](http://www.opengis.net/ont/geosparql#wktLiteral) and keeps it up to date with the following writes.
+    ///
+    /// The index is persisted in the database: it does not need to be enabled again when the database is opened again.
+    ///
+    /// It is used by the SPARQL query planner to evaluate filters like `FILTER(geof:sfIntersects(?wkt, "POLYGON(...)"^^geo:wktLiteral))`
+    /// by first looking for the literals whose bounding box intersects the constant geometry bounding box.
+    /// The filters using `geof:sfIntersects`, `geof:sfWithin`, `geof:sfContains` and `geof:sfEquals` are optimized this way
+    /// if the variable is bound by a triple pattern object.
+    ///
+    /// Warning: Indexes all the store content in a single transaction.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::store::Store;
+    /// use oxigraph::model::*;
+    /// use oxigraph::sparql::QueryResults;
+    ///
+    /// let store = Store::new()?;
+    /// store.enable_geo_index()?;
+    /// let ex = NamedNodeRef::new("http://example.com")?;
+    /// let wkt = NamedNodeRef::new("http://www.opengis.net/ont/geosparql#wktLiteral")?;
+    /// store.insert(QuadRef::new(ex, ex, LiteralRef::new_typed_literal("POINT(2.35 48.85)", wkt), GraphNameRef::DefaultGraph))?;
+    ///
+    /// if let QueryResults::Solutions(mut solutions) = store.query("PREFIX geo: <http://www.opengis.net/ont/geosparql#> PREFIX geof: <http://www.opengis.net/def/function/geosparql/> SELECT ?s WHERE { ?s ?p ?wkt FILTER(geof:sfWithin(?wkt, \"POLYGON((2 48, 3 48, 3 49, 2 49, 2 48))\"^^geo:wktLiteral)) }")? {
+    ///     assert_eq!(solutions.next().unwrap()?.get("s"), Some(&ex.into_owned().into()));
+    /// }
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn enable_geo_index(&self) -> io::Result<()> {
+        self.storage.set_geo_index(true)
+    }
+
+    /// Removes the spatial index built by [`Store::enable_geo_index`].
+    pub fn disable_geo_index(&self) -> io::Result<()> {
+        self.storage.set_geo_index(false)
+    }
+
+    /// Writes into the `target_graph` graph the triples inferred by the `ruleset` rules from the triples of all the store graphs
+    /// and keeps them up to date with the following writes.
+    ///
+    /// The configuration is persisted in the database: the inferred triples are still maintained when the database is opened again.
+    /// Calling this method again replaces the previous configuration and computes again all the inferred triples.
+    ///
+    /// The inferred triples are updated by each transaction before its commit.
+    /// The transactions changing the triples outside of the `target_graph` graph conflict with each other while they update the inferred triples:
+    /// the one committed last is retried from the beginning, so the inferred triples always take into account all the committed changes.
+    /// The other transactions are not affected.
+    /// The triples added with [`Store::bulk_load_dataset`], [`Store::bulk_load_graph`] or [`Store::bulk_extend`] are not taken into account until this method is called again.
+    /// The triples written into the `target_graph` graph are not used to infer new triples.
+    ///
+    /// See [`reasoning`](crate::reasoning) for the supported rules.
+    ///
+    /// Warning: Computes all the inferred triples in a single transaction.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::store::Store;
+    /// use oxigraph::model::*;
+    /// use oxigraph::model::vocab::{rdf, rdfs};
+    /// use oxigraph::reasoning::Ruleset;
+    ///
+    /// let store = Store::new()?;
+    /// let ex = NamedNodeRef::new("http://example.com")?;
+    /// let class = NamedNodeRef::new("http://example.com/Class")?;
+    /// let super_class = NamedNodeRef::new("http://example.com/SuperClass")?;
+    /// let inferred = NamedNodeRef::new("http://example.com/inferred")?;
+    /// store.insert(QuadRef::new(class, rdfs::SUB_CLASS_OF, super_class, GraphNameRef::DefaultGraph))?;
+    /// store.materialize(Ruleset::Owl2Rl, inferred)?;
+    ///
+    /// store.insert(QuadRef::new(ex, rdf::TYPE, class, GraphNameRef::DefaultGraph))?;
+    /// assert!(store.contains(QuadRef::new(ex, rdf::TYPE, super_class, inferred))?);
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn materialize<'a>(
+        &self,
+        ruleset: Ruleset,
+        target_graph: impl Into<NamedOrBlankNodeRef<'a>>,
+    ) -> io::Result<()> {
+        self.storage.set_materialization(Some(Materialization {
+            ruleset,
+            target_graph: target_graph.into().into_owned(),
+        }))
+    }
+
+    /// Stops maintaining the inferred triples set up by [`Store::materialize`].
+    ///
+    /// The already inferred triples are kept in their graph.
+    pub fn stop_materialization(&self) -> io::Result<()> {
+        self.storage.set_materialization(None)
+    }
+
+    /// Evaluates the `CONSTRUCT` rules until they do not produce new triples anymore
+    /// and inserts the produced triples into the `target_graph` graph.
+    ///
+    /// Returns the number of inserted triples.
+    /// The triples already in the store default graph or in the `target_graph` graph are not inserted again.
+    ///
+    /// The evaluation is done in a single transaction:
+    /// if it fails, for example because [`ConstructRules::with_max_iterations`] is reached, nothing is inserted.
+    /// Beware that all the produced triples are kept in memory until the transaction is committed.
+    /// Only the inserted triples appear in the [change log](Store::enable_change_log).
+    ///
+    /// See [`ConstructRules`] for an example.
+    pub fn apply_rules<'a>(
+        &self,
+        rules: &ConstructRules,
+        target_graph: impl Into<NamedOrBlankNodeRef<'a>>,
+    ) -> Result<usize, EvaluationError> {
+        rules.apply(self, target_graph.into())
+    }
+
+    /// Validates the `graph_name` graph against SHACL shapes.
+    ///
+    /// The graph is read from a single snapshot of the store.
+    /// See [`shacl`](crate::shacl) for the supported features.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::io::GraphFormat;
+    /// use oxigraph::model::*;
+    /// use oxigraph::shacl::ShapesGraph;
+    /// use oxigraph::store::Store;
+    ///
+    /// let mut shapes = Graph::new();
+    /// shapes.load(
+    ///     "@prefix sh: <http://www.w3.org/ns/shacl#> .
+    ///      <http://example.com/PersonShape> sh:targetClass <http://example.com/Person> ;
+    ///         sh:sparql [ sh:select \"SELECT $this WHERE { FILTER NOT EXISTS { $this <http://example.com/name> ?name } }\" ] .".as_bytes(),
+    ///     GraphFormat::Turtle,
+    ///     None,
+    /// )?;
+    /// let shapes = ShapesGraph::new(&shapes)?;
+    ///
+    /// let store = Store::new()?;
+    /// let ex = NamedNodeRef::new("http://example.com/alice")?;
+    /// let person = NamedNodeRef::new("http://example.com/Person")?;
+    /// store.insert(QuadRef::new(ex, vocab::rdf::TYPE, person, GraphNameRef::DefaultGraph))?;
+    /// let report = store.validate(&shapes, GraphNameRef::DefaultGraph)?;
+    /// assert!(!report.conforms());
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn validate<'a>(
+        &self,
+        shapes: &ShapesGraph,
+        graph_name: impl Into<GraphNameRef<'a>>,
+    ) -> Result<ValidationReport, EvaluationError> {
+        let snapshot = self.storage.snapshot();
+        shapes.validate_storage(
+            &|| {
+                snapshot
+                    .share()
+                    .map_or_else(|| self.storage.snapshot(), SharedStorageReader::into_reader)
+            },
+            graph_name.into(),
+            None,
+        )
+    }
+
+    /// Registers SHACL shapes that the `graph_name` graph must conform to.
+    ///
+    /// Before each commit modifying the graph, including the ones of [`Store::update`] and [`Store::transaction`],
+    /// the graph is validated against the shapes.
+    /// If the validation returns results with the `sh:Violation` severity, the transaction is aborted
+    /// and an [`InvalidData`](std::io::ErrorKind::InvalidData) error wrapping a [`ValidationError`](crate::shacl::ValidationError) is returned.
+    /// The shapes previously registered for the same graph are replaced.
+    ///
+    /// The shapes are not persisted in the database and the current content of the graph is not validated.
+    /// The quads added with [`Store::bulk_load_dataset`], [`Store::bulk_load_graph`] or [`Store::bulk_extend`] are not validated.
+    ///
+    /// Only the nodes whose conformance might have been changed by the commit are validated,
+    /// except if the shapes are recursive, use SPARQL-based constraints or `*` and `+` paths
+    /// or if the commit changes a `rdfs:subClassOf` triple: the full graph is then validated.
+    /// The validation is done against the snapshot of the transaction.
+    /// The transactions changing the same validated graph conflict with each other: the one committed last is retried from the beginning
+    /// and validated again with the changes of the other one, so concurrent commits can't introduce violations together.
+    /// The transactions that do not change a graph with registered shapes are not affected.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::io::GraphFormat;
+    /// use oxigraph::model::*;
+    /// use oxigraph::shacl::{ShapesGraph, ValidationError};
+    /// use oxigraph::store::Store;
+    ///
+    /// let mut shapes = Graph::new();
+    /// shapes.load(
+    ///     "@prefix sh: <http://www.w3.org/ns/shacl#> .
+    ///      <http://example.com/PersonShape> sh:targetClass <http://example.com/Person> ;
+    ///         sh:property [ sh:path <http://example.com/name> ; sh:minCount 1 ] .".as_bytes(),
+    ///     GraphFormat::Turtle,
+    ///     None,
+    /// )?;
+    /// let store = Store::new()?;
+    /// store.register_shapes(ShapesGraph::new(&shapes)?, GraphNameRef::DefaultGraph);
+    ///
+    /// let ex = NamedNodeRef::new("http://example.com/alice")?;
+    /// let person = NamedNodeRef::new("http://example.com/Person")?;
+    /// let error = store.insert(QuadRef::new(ex, vocab::rdf::TYPE, person, GraphNameRef::DefaultGraph)).unwrap_err();
+    /// let report = error.get_ref().and_then(|e| e.downcast_ref::<ValidationError>()).unwrap().report();
+    /// assert_eq!(report.results()[0].focus_node(), &ex.into_owned().into());
+    /// assert!(store.is_empty()?);
+    ///
+    /// store.update("INSERT DATA { <http://example.com/alice> a <http://example.com/Person> ; <http://example.com/name> \"Alice\" }")?;
+    /// assert_eq!(store.len()?, 2);
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn register_shapes<'a>(
+        &self,
+        shapes: ShapesGraph,
+        graph_name: impl Into<GraphNameRef<'a>>,
+    ) {
+        self.storage
+            .register_shapes(graph_name.into().into_owned(), shapes)
+    }
+
+    /// Unregisters the shapes registered with [`Store::register_shapes`] for the `graph_name` graph.
+    ///
+    /// Returns `true` if some shapes were registered.
+    pub fn unregister_shapes<'a>(&self, graph_name: impl Into<GraphNameRef<'a>>) -> bool {
+        self.storage.unregister_shapes(graph_name.into())
+    }
+
+    /// Creates a consistent copy of the database into the `target_directory`.
+    ///
+    /// The backup is done using RocksDB checkpoints: it can be taken while the store is being read and written
+    /// and contains all the changes committed before the call.
+    /// The target directory must not exist yet. If it is on the same file system as the store,
+    /// the immutable database files are hard linked instead of copied, making regular backups cheap.
+    ///
+    /// After its creation, the backup is usable using [`Store::open`] like a regular Oxigraph database
+    /// and operates independently from the original database.
+    ///
+    /// Warning: Backups are only possible for on-disk databases created using [`Store::open`].
+    /// Temporary databases created using [`Store::new`] are not compatible with RocksDB backup system
+    /// and stores opened with [`Store::open_read_only`] or [`Store::open_secondary`] are not able to prevent
+    /// their primary from deleting the files being copied.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn backup(&self, target_directory: impl AsRef<Path>) -> io::Result<()> {
+        self.storage.backup(target_directory.as_ref())
+    }
+
+    /// Makes a store opened with [`Store::open_secondary`] see the latest changes written by its primary.
+    ///
+    /// Warning: Queries and iterators running during the catch up might see a mix of the old and the new data.
+    ///
+    /// Fails with an [`InvalidInput`](io::ErrorKind::InvalidInput) error if the store has not been opened with [`Store::open_secondary`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn catch_up_with_primary(&self) -> io::Result<()> {
+        self.storage.catch_up_with_primary()
+    }
+
+    /// Starts to keep a durable log of the quads inserted and removed by each committed transaction.
+    ///
+    /// The log stays enabled when the store is opened again. See [`Store::changes_since`] to read it
+    /// and [`Store::truncate_changes`] to bound its size.
+    pub fn enable_change_log(&self) -> io::Result<()> {
+        self.storage.enable_change_log()
+    }
+
+    /// Returns the changes committed after the transaction with the sequence number `sequence_number`.
+    ///
+    /// The changes are only logged after [`Store::enable_change_log`] has been called.
+    /// All the changes of a transaction share the same sequence number and sequence numbers are increasing in commit order.
+    /// The changes are returned in the order they have been done.
+    /// Replaying them on a copy of the store (for example one created with [`Store::backup`]) makes it catch up with this store.
+    ///
+    /// Bulk loads are not logged.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::store::{ChangeKind, Store};
+    /// use oxigraph::model::*;
+    ///
+    /// let store = Store::new()?;
+    /// store.enable_change_log()?;
+    /// let ex = NamedNodeRef::new("http://example.com")?;
+    /// let quad = QuadRef::new(ex, ex, ex, GraphNameRef::DefaultGraph);
+    /// store.insert(quad)?;
+    ///
+    /// let changes = store.changes_since(0)?.collect::<Result<Vec<_>,_>>()?;
+    /// assert_eq!(changes.len(), 1);
+    /// assert_eq!(changes[0].kind, ChangeKind::Insertion);
+    /// assert_eq!(changes[0].quad, quad.into_owned());
+    /// assert_eq!(store.changes_since(changes[0].sequence_number)?.count(), 0);
+    ///
+    /// // The changes replicated elsewhere could be removed
+    /// store.truncate_changes(changes[0].sequence_number + 1)?;
+    /// assert_eq!(store.changes_since(0)?.count(), 0);
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn changes_since(&self, sequence_number: u64) -> io::Result<ChangeIter> {
+        let reader = self.storage.snapshot();
+        Ok(ChangeIter {
+            iter: reader.changes_since(sequence_number)?,
+            reader,
+        })
+    }
+
+    /// Returns the sequence number of the last transaction recorded in the change log or `None` if no transaction has been logged yet.
+    ///
+    /// See [`Store::changes_since`].
+    pub fn last_change_sequence_number(&self) -> io::Result<Option<u64>> {
+        self.storage.snapshot().last_change_sequence_number()
+    }
+
+    /// Removes from the change log all the changes with a sequence number strictly lower than `sequence_number`.
+    ///
+    /// Useful to bound the size of the log once all the replicas have caught up.
+    /// The changes are removed by batches in multiple transactions.
+    /// See [`Store::changes_since`].
+    pub fn truncate_changes(&self, sequence_number: u64) -> io::Result<()> {
+        self.storage.truncate_changes(sequence_number)
+    }
+
     /// Loads a dataset file efficiently into the store.
     ///
     /// This function is optimized for large dataset loading speed. For small files, [`load_dataset`](Store::load_dataset) might be more convenient.
@@ -731,6 +1334,277 @@ impl fmt::Display for Store {
     }
 }
 
+/// An object to do operations during a transaction.
+///
+/// See [`Store::transaction`] for a more detailed description.
+pub struct Transaction<'a> {
+    writer: StorageWriter<'a>,
+}
+
+impl<'a> Transaction<'a> {
+    /// Executes a [SPARQL 1.1 query](https://www.w3.org/TR/sparql11-query/).
+    ///
+    /// The query sees the writes done previously in the transaction.
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::store::Store;
+    /// use oxigraph::model::*;
+    /// use oxigraph::sparql::{EvaluationError, QueryResults};
+    ///
+    /// let store = Store::new()?;
+    /// store.transaction(|mut transaction| {
+    ///     let ex = NamedNodeRef::new_unchecked("http://example.com");
+    ///     transaction.insert(QuadRef::new(ex, ex, ex, GraphNameRef::DefaultGraph))?;
+    ///     if let QueryResults::Boolean(result) = transaction.query("ASK { ?s ?p ?o }")? {
+    ///         assert!(result);
+    ///     }
+    ///     Result::<_, EvaluationError>::Ok(())
+    /// })?;
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn query(
+        &self,
+        query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
+    ) -> Result<QueryResults, EvaluationError> {
+        self.query_opt(query, QueryOptions::default())
+    }
+
+    /// Executes a [SPARQL 1.1 query](https://www.w3.org/TR/sparql11-query/) with some options.
+    pub fn query_opt(
+        &self,
+        query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
+        options: QueryOptions,
+    ) -> Result<QueryResults, EvaluationError> {
+        evaluate_query(self.writer.reader(), query, options)
+    }
+
+    /// Executes a query with the content of the `graph_name` named graph replaced by the given triples kept in memory.
+    pub(crate) fn query_with_memory_graph<'b>(
+        &self,
+        query: Query,
+        graph_name: NamedNodeRef<'_>,
+        triples: impl IntoIterator<Item = TripleRef<'b>>,
+    ) -> Result<QueryResults, EvaluationError> {
+        evaluate_query_with_memory_graph(
+            self.writer.reader(),
+            query,
+            QueryOptions::default(),
+            graph_name,
+            triples,
+        )
+    }
+
+    /// Retrieves quads with a filter on each quad component.
+    ///
+    /// The returned quads include the writes done previously in the transaction.
+    pub fn quads_for_pattern(
+        &self,
+        subject: Option<SubjectRef<'_>>,
+        predicate: Option<NamedNodeRef<'_>>,
+        object: Option<TermRef<'_>>,
+        graph_name: Option<GraphNameRef<'_>>,
+    ) -> QuadIter {
+        let reader = self.writer.reader();
+        QuadIter {
+            iter: reader.quads_for_pattern(
+                subject.map(EncodedTerm::from).as_ref(),
+                predicate.map(EncodedTerm::from).as_ref(),
+                object.map(EncodedTerm::from).as_ref(),
+                graph_name.map(EncodedTerm::from).as_ref(),
+            ),
+            reader,
+        }
+    }
+
+    /// Returns all the quads contained in the store.
+    pub fn iter(&self) -> QuadIter {
+        self.quads_for_pattern(None, None, None, None)
+    }
+
+    /// Checks if this store contains a given quad.
+    pub fn contains<'b>(&self, quad: impl Into<QuadRef<'b>>) -> io::Result<bool> {
+        let quad = EncodedQuad::from(quad.into());
+        self.writer.reader().contains(&quad)
+    }
+
+    /// Returns the number of quads in the store.
+    ///
+    /// Warning: this function executes a full scan.
+    pub fn len(&self) -> io::Result<usize> {
+        self.writer.reader().len()
+    }
+
+    /// Returns if the store is empty.
+    pub fn is_empty(&self) -> io::Result<bool> {
+        self.writer.reader().is_empty()
+    }
+
+    /// Executes a [SPARQL 1.1 update](https://www.w3.org/TR/sparql11-update/).
+    ///
+    /// Usage example:
+    /// ```
+    /// use oxigraph::store::Store;
+    /// use oxigraph::model::*;
+    /// use oxigraph::sparql::EvaluationError;
+    ///
+    /// let store = Store::new()?;
+    /// store.transaction(|mut transaction| {
+    ///     // insertion
+    ///     transaction.update("INSERT DATA { <http://example.com> <http://example.com> <http://example.com> }")?;
+    ///
+    ///     // we inspect the store contents
+    ///     let ex = NamedNodeRef::new_unchecked("http://example.com");
+    ///     assert!(transaction.contains(QuadRef::new(ex, ex, ex, GraphNameRef::DefaultGraph))?);
+    ///     Result::<_, EvaluationError>::Ok(())
+    /// })?;
+    /// # Result::<_,Box<dyn std::error::Error>>::Ok(())
+    /// ```
+    pub fn update(
+        &mut self,
+        update: impl TryInto<Update, Error = impl Into<EvaluationError>>,
+    ) -> Result<(), EvaluationError> {
+        self.update_opt(update, UpdateOptions::default())
+    }
+
+    /// Executes a [SPARQL 1.1 update](https://www.w3.org/TR/sparql11-update/) with some options.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn update_opt(
+        &mut self,
+        update: impl TryInto<Update, Error = impl Into<EvaluationError>>,
+        options: UpdateOptions,
+    ) -> Result<(), EvaluationError> {
+        evaluate_update(
+            &mut self.writer,
+            &update.try_into().map_err(std::convert::Into::into)?,
+            &options,
+        )
+    }
+
+    /// Loads a graph file (i.e. triples) into the store.
+    ///
+    /// Errors related to parameter validation like the base IRI use the [`InvalidInput`](std::io::ErrorKind::InvalidInput) error kind.
+    /// Errors related to a bad syntax in the loaded file use the [`InvalidData`](std::io::ErrorKind::InvalidData) or [`UnexpectedEof`](std::io::ErrorKind::UnexpectedEof) error kinds.
+    /// Errors related to data loading into the store use the other error kinds.
+    pub fn load_graph<'b>(
+        &mut self,
+        reader: impl BufRead,
+        format: GraphFormat,
+        to_graph_name: impl Into<GraphNameRef<'b>>,
+        base_iri: Option<&str>,
+    ) -> io::Result<()> {
+        let mut parser = GraphParser::from_format(format);
+        if let Some(base_iri) = base_iri {
+            parser = parser
+                .with_base_iri(base_iri)
+                .map_err(invalid_input_error)?;
+        }
+        let to_graph_name = to_graph_name.into();
+        for triple in parser.read_triples(reader)? {
+            self.writer
+                .insert(triple?.as_ref().in_graph(to_graph_name))?;
+        }
+        Ok(())
+    }
+
+    /// Loads a dataset file (i.e. quads) into the store.
+    ///
+    /// Errors related to parameter validation like the base IRI use the [`InvalidInput`](std::io::ErrorKind::InvalidInput) error kind.
+    /// Errors related to a bad syntax in the loaded file use the [`InvalidData`](std::io::ErrorKind::InvalidData) or [`UnexpectedEof`](std::io::ErrorKind::UnexpectedEof) error kinds.
+    /// Errors related to data loading into the store use the other error kinds.
+    pub fn load_dataset(
+        &mut self,
+        reader: impl BufRead,
+        format: DatasetFormat,
+        base_iri: Option<&str>,
+    ) -> io::Result<()> {
+        let mut parser = DatasetParser::from_format(format);
+        if let Some(base_iri) = base_iri {
+            parser = parser
+                .with_base_iri(base_iri)
+                .map_err(invalid_input_error)?;
+        }
+        for quad in parser.read_quads(reader)? {
+            self.writer.insert(quad?.as_ref())?;
+        }
+        Ok(())
+    }
+
+    /// Adds a quad to this store.
+    ///
+    /// Returns `true` if the quad was not already in the store.
+    pub fn insert<'b>(&mut self, quad: impl Into<QuadRef<'b>>) -> io::Result<bool> {
+        self.writer.insert(quad.into())
+    }
+
+    /// Adds a set of quads to this store.
+    pub fn extend<'b>(
+        &mut self,
+        quads: impl IntoIterator<Item = impl Into<QuadRef<'b>>>,
+    ) -> io::Result<()> {
+        for quad in quads {
+            self.writer.insert(quad.into())?;
+        }
+        Ok(())
+    }
+
+    /// Removes a quad from this store.
+    ///
+    /// Returns `true` if the quad was in the store and has been removed.
+    pub fn remove<'b>(&mut self, quad: impl Into<QuadRef<'b>>) -> io::Result<bool> {
+        self.writer.remove(quad.into())
+    }
+
+    /// Returns all the store named graphs.
+    pub fn named_graphs(&self) -> GraphNameIter {
+        let reader = self.writer.reader();
+        GraphNameIter {
+            iter: reader.named_graphs(),
+            reader,
+        }
+    }
+
+    /// Checks if the store contains a given graph.
+    pub fn contains_named_graph<'b>(
+        &self,
+        graph_name: impl Into<NamedOrBlankNodeRef<'b>>,
+    ) -> io::Result<bool> {
+        self.writer
+            .reader()
+            .contains_named_graph(&EncodedTerm::from(graph_name.into()))
+    }
+
+    /// Inserts a graph into this store.
+    ///
+    /// Returns `true` if the graph was not already in the store.
+    pub fn insert_named_graph<'b>(
+        &mut self,
+        graph_name: impl Into<NamedOrBlankNodeRef<'b>>,
+    ) -> io::Result<bool> {
+        self.writer.insert_named_graph(graph_name.into())
+    }
+
+    /// Clears a graph from this store.
+    pub fn clear_graph<'b>(&mut self, graph_name: impl Into<GraphNameRef<'b>>) -> io::Result<()> {
+        self.writer.clear_graph(graph_name.into())
+    }
+
+    /// Removes a graph from this store.
+    ///
+    /// Returns `true` if the graph was in the store and has been removed.
+    pub fn remove_named_graph<'b>(
+        &mut self,
+        graph_name: impl Into<NamedOrBlankNodeRef<'b>>,
+    ) -> io::Result<bool> {
+        self.writer.remove_named_graph(graph_name.into())
+    }
+
+    /// Clears the store.
+    pub fn clear(&mut self) -> io::Result<()> {
+        self.writer.clear()
+    }
+}
+
 /// An iterator returning the quads contained in a [`Store`].
 pub struct QuadIter {
     iter: ChainedDecodingQuadIterator,
@@ -770,6 +1644,35 @@ impl Iterator for GraphNameIter {
     }
 }
 
+/// A change recorded in the change log of a [`Store`]. See [`Store::changes_since`].
+#[derive(Eq, PartialEq, Debug, Clone, Hash)]
+pub struct Change {
+    /// The sequence number of the transaction that has done the change.
+    pub sequence_number: u64,
+    pub kind: ChangeKind,
+    pub quad: Quad,
+}
+
+/// An iterator returning the changes recorded in the change log of a [`Store`].
+pub struct ChangeIter {
+    iter: ChangeLogIterator,
+    reader: StorageReader,
+}
+
+impl Iterator for ChangeIter {
+    type Item = io::Result<Change>;
+
+    fn next(&mut self) -> Option<io::Result<Change>> {
+        Some(self.iter.next()?.and_then(|(sequence_number, kind, quad)| {
+            Ok(Change {
+                sequence_number,
+                kind,
+                quad: self.reader.decode_quad(&quad)?,
+            })
+        }))
+    }
+}
+
 #[test]
 fn store() -> io::Result<()> {
     use crate::model::*;
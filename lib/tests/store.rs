@@ -1,9 +1,24 @@
-use oxigraph::io::{DatasetFormat, GraphFormat};
-use oxigraph::model::vocab::{rdf, xsd};
+use oxigraph::io::read::DatasetParser;
+use oxigraph::io::write::DatasetSerializer;
+use oxigraph::io::{DatasetFormat, GraphFormat, StaticDocumentLoader};
+use oxigraph::model::vocab::{rdf, rdfs, xsd};
 use oxigraph::model::*;
-use oxigraph::store::Store;
-use std::io::{Cursor, Result};
+use oxigraph::reasoning::{materialize, ConstructRules, Ruleset};
+use oxigraph::shacl::{ShapesGraph, ValidationError};
+use oxigraph::sparql::{
+    EvaluationError, PreparedQuery, Query, QueryOptions, QueryProfile, QueryResults, Variable,
+};
+use oxigraph::store::{Change, ChangeKind, Store, TextIndexOptions, Tokenizer};
+use rand::random;
+use std::collections::HashSet;
+use std::env::temp_dir;
+use std::fs::remove_dir_all;
+use std::io::{Cursor, Error, ErrorKind, Result};
+use std::path::PathBuf;
 use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::thread::spawn;
+use std::time::Duration;
 
 const DATA: &str = r#"
 @prefix schema: <http://schema.org/> .
@@ -159,34 +174,2141 @@ fn test_dump_dataset() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_json_ld() -> Result<()> {
+    let graph = NamedNodeRef::new_unchecked("http://example.com/graph");
+    let store = Store::new()?;
+    for q in quads(GraphNameRef::DefaultGraph)
+        .into_iter()
+        .chain(quads(graph))
+    {
+        store.insert(q)?;
+    }
+    let all_quads = store.iter().collect::<Result<Vec<_>>>()?;
+
+    // Expanded, compacted and flattened documents round trip
+    let context = r#"{"schema": "http://schema.org/", "name": {"@id": "schema:name", "@container": "@language"}}"#;
+    for serializer in [
+        DatasetSerializer::from_format(DatasetFormat::JsonLd),
+        DatasetSerializer::from_format(DatasetFormat::JsonLd).with_context(context)?,
+        DatasetSerializer::from_format(DatasetFormat::JsonLd)
+            .with_context(context)?
+            .with_flattening(),
+    ] {
+        let mut buffer = Vec::new();
+        let mut writer = serializer.quad_writer(&mut buffer)?;
+        for q in &all_quads {
+            writer.write(q)?;
+        }
+        writer.finish()?;
+        let other = Store::new()?;
+        other.load_dataset(buffer.as_slice(), DatasetFormat::JsonLd, None)?;
+        assert_eq!(other.len()?, 2 * NUMBER_OF_TRIPLES);
+        for q in &all_quads {
+            assert!(other.contains(q)?);
+        }
+    }
+
+    // Remote contexts are only loaded through the document loader
+    let document = r#"{"@context": "http://example.com/context.jsonld", "@id": "http://www.wikidata.org/entity/Q90", "schema:postalCode": "75001"}"#;
+    assert!(DatasetParser::from_format(DatasetFormat::JsonLd)
+        .read_quads(document.as_bytes())?
+        .collect::<Result<Vec<_>>>()
+        .is_err());
+    let parsed = DatasetParser::from_format(DatasetFormat::JsonLd)
+        .with_document_loader(StaticDocumentLoader::new().with_document(
+            "http://example.com/context.jsonld",
+            format!(r#"{{"@context": {}}}"#, context),
+        ))
+        .read_quads(document.as_bytes())?
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(
+        parsed,
+        vec![quads(GraphNameRef::DefaultGraph)[7].into_owned()]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_json_ld_streaming() -> Result<()> {
+    let graph = NamedNodeRef::new_unchecked("http://example.com/graph");
+    let store = Store::new()?;
+    for q in quads(graph) {
+        store.insert(q)?;
+    }
+
+    // The written document is in streaming form and could be read back incrementally
+    let mut buffer = Vec::new();
+    let mut writer = DatasetSerializer::from_format(DatasetFormat::JsonLd)
+        .with_context(r#"{"schema": "http://schema.org/"}"#)?
+        .quad_writer(&mut buffer)?;
+    for q in store.iter() {
+        writer.write(&q?)?;
+    }
+    writer.finish()?;
+    assert!(buffer.starts_with(
+        br#"{"@context":{"schema":"http://schema.org/"},"@graph":[{"@id":"http://example.com/graph","@graph":["#
+    ));
+    let mut parsed = DatasetParser::from_format(DatasetFormat::JsonLd)
+        .read_quads(buffer.as_slice())?
+        .collect::<Result<Vec<_>>>()?;
+    parsed.sort_by_key(ToString::to_string);
+    let mut expected = store.iter().collect::<Result<Vec<_>>>()?;
+    expected.sort_by_key(ToString::to_string);
+    assert_eq!(parsed, expected);
+
+    // Quads are returned before the end of the document is read
+    let document = r#"{"@context": {"@vocab": "http://example.com/"}, "@id": "http://example.com/g", "@graph": [{"@id": "http://example.com/s", "p": {"@id": "_:b"}}, {"@id": "_:b", "p": "o"}, {"@id""#;
+    let mut reader =
+        DatasetParser::from_format(DatasetFormat::JsonLd).read_quads(document.as_bytes())?;
+    let first = reader.next().unwrap()?;
+    let second = reader.next().unwrap()?;
+    assert_eq!(first.graph_name.to_string(), "<http://example.com/g>");
+    assert_eq!(first.object, Term::from(second.subject));
+    assert!(reader.next().unwrap().is_err());
+
+    // The @id entry of the top-level object must be before its @graph entry
+    let document = r#"{"@graph": [{"@id": "http://example.com/s", "http://example.com/p": "o"}], "@id": "http://example.com/g"}"#;
+    assert!(DatasetParser::from_format(DatasetFormat::JsonLd)
+        .read_quads(document.as_bytes())?
+        .collect::<Result<Vec<_>>>()
+        .is_err());
+    Ok(())
+}
+
 #[test]
 fn test_snapshot_isolation_iterator() -> Result<()> {
     let quad = QuadRef::new(
         NamedNodeRef::new_unchecked("http://example.com/s"),
         NamedNodeRef::new_unchecked("http://example.com/p"),
         NamedNodeRef::new_unchecked("http://example.com/o"),
-        NamedNodeRef::new_unchecked("http://example.com/g"),
+        NamedNodeRef::new_unchecked("http://example.com/g"),
+    );
+    let store = Store::new()?;
+    store.insert(quad)?;
+    let iter = store.iter();
+    store.remove(quad)?;
+    assert_eq!(iter.collect::<Result<Vec<_>>>()?, vec![quad.into_owned()]);
+    Ok(())
+}
+
+#[test]
+fn test_bulk_load_on_existing_delete_overrides_the_delete() -> Result<()> {
+    let quad = QuadRef::new(
+        NamedNodeRef::new_unchecked("http://example.com/s"),
+        NamedNodeRef::new_unchecked("http://example.com/p"),
+        NamedNodeRef::new_unchecked("http://example.com/o"),
+        NamedNodeRef::new_unchecked("http://example.com/g"),
+    );
+    let store = Store::new()?;
+    store.remove(quad)?;
+    store.bulk_extend([quad.into_owned()])?;
+    assert_eq!(store.len()?, 1);
+    Ok(())
+}
+
+#[test]
+fn test_transaction_read_your_own_writes() -> Result<()> {
+    let store = Store::new()?;
+    store.transaction(|mut t| {
+        for q in quads(GraphNameRef::DefaultGraph) {
+            assert!(t.insert(q)?);
+        }
+        assert_eq!(t.len()?, NUMBER_OF_TRIPLES);
+        for q in quads(GraphNameRef::DefaultGraph) {
+            assert!(t.contains(q)?);
+        }
+        Result::Ok(())
+    })?;
+    assert_eq!(store.len()?, NUMBER_OF_TRIPLES);
+    Ok(())
+}
+
+#[test]
+fn test_transaction_rollback_on_error() -> Result<()> {
+    let quad = QuadRef::new(
+        NamedNodeRef::new_unchecked("http://example.com/s"),
+        NamedNodeRef::new_unchecked("http://example.com/p"),
+        NamedNodeRef::new_unchecked("http://example.com/o"),
+        GraphNameRef::DefaultGraph,
+    );
+    let store = Store::new()?;
+    let result = store.transaction(|mut t| {
+        t.insert(quad)?;
+        t.update(
+            "INSERT DATA { <http://example.com/s> <http://example.com/p> <http://example.com/o2> }",
+        )?;
+        Err::<(), _>(EvaluationError::Io(Error::new(ErrorKind::Other, "abort")))
+    });
+    assert!(result.is_err());
+    assert!(store.is_empty()?);
+    Ok(())
+}
+
+#[test]
+fn test_on_commit() -> Result<()> {
+    let s = NamedNodeRef::new_unchecked("http://example.com/s");
+    let p = NamedNodeRef::new_unchecked("http://example.com/p");
+    let o = NamedNodeRef::new_unchecked("http://example.com/o");
+    let o2 = NamedNodeRef::new_unchecked("http://example.com/o2");
+    let quad = QuadRef::new(s, p, o, GraphNameRef::DefaultGraph);
+    let quad2 = QuadRef::new(s, p, o2, GraphNameRef::DefaultGraph);
+
+    let store = Store::new()?;
+    let commits = Arc::new(Mutex::new(Vec::new()));
+    let listener_commits = commits.clone();
+    store.on_commit(move |inserted, removed| {
+        listener_commits
+            .lock()
+            .unwrap()
+            .push((inserted.to_vec(), removed.to_vec()))
+    });
+
+    store.insert(quad)?;
+    store.insert(quad)?; // No change
+    store.transaction(|mut t| {
+        t.insert(quad2)?;
+        t.remove(quad2)?; // Cancels the insertion
+        t.remove(quad)?;
+        Result::Ok(())
+    })?;
+    store
+        .update(
+            "INSERT DATA { <http://example.com/s> <http://example.com/p> <http://example.com/o2> }",
+        )
+        .unwrap();
+    let _ = store.transaction(|mut t| {
+        t.insert(quad)?;
+        Err::<(), _>(Error::new(ErrorKind::Other, "abort"))
+    });
+
+    assert_eq!(
+        *commits.lock().unwrap(),
+        vec![
+            (vec![quad.into_owned()], vec![]),
+            (vec![], vec![quad.into_owned()]),
+            (vec![quad2.into_owned()], vec![])
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_changes_since() -> Result<()> {
+    let s = NamedNodeRef::new_unchecked("http://example.com/s");
+    let p = NamedNodeRef::new_unchecked("http://example.com/p");
+    let o = NamedNodeRef::new_unchecked("http://example.com/o");
+    let quad = QuadRef::new(s, p, o, GraphNameRef::DefaultGraph);
+    let quad2 = QuadRef::new(s, p, o, s);
+    let store_dir = TempDir::default();
+
+    let store = Store::open(&store_dir.0)?;
+    store.insert(quad2)?;
+    store.remove(quad2)?;
+    assert_eq!(store.changes_since(0)?.count(), 0); // The log is disabled by default
+    store.enable_change_log()?;
+    assert_eq!(store.last_change_sequence_number()?, None);
+    store.insert(quad)?;
+    store.transaction(|mut t| {
+        t.remove(quad)?;
+        t.insert(quad2)?;
+        Result::Ok(())
+    })?;
+    let changes = store.changes_since(0)?.collect::<Result<Vec<_>>>()?;
+    assert_eq!(
+        changes,
+        vec![
+            Change {
+                sequence_number: 1,
+                kind: ChangeKind::Insertion,
+                quad: quad.into_owned()
+            },
+            Change {
+                sequence_number: 2,
+                kind: ChangeKind::Removal,
+                quad: quad.into_owned()
+            },
+            Change {
+                sequence_number: 2,
+                kind: ChangeKind::Insertion,
+                quad: quad2.into_owned()
+            }
+        ]
+    );
+    assert_eq!(store.last_change_sequence_number()?, Some(2));
+
+    // Replay
+    let replica = Store::new()?;
+    for change in store.changes_since(0)? {
+        let change = change?;
+        match change.kind {
+            ChangeKind::Insertion => replica.insert(&change.quad)?,
+            ChangeKind::Removal => replica.remove(&change.quad)?,
+        };
+    }
+    assert_eq!(
+        replica.iter().collect::<Result<Vec<_>>>()?,
+        vec![quad2.into_owned()]
+    );
+    assert_eq!(replica.changes_since(0)?.count(), 0);
+
+    store.truncate_changes(2)?;
+    assert_eq!(store.changes_since(0)?.count(), 2);
+
+    // The log stays enabled after reopening
+    drop(store);
+    let store = Store::open(&store_dir.0)?;
+    store.remove(quad2)?;
+    assert_eq!(store.last_change_sequence_number()?, Some(3));
+
+    // Concurrent transactions get distinct sequence numbers
+    let threads = (0..4)
+        .map(|i| {
+            let store = store.clone();
+            spawn(move || {
+                for j in 0..10 {
+                    store.insert(&Quad::new(
+                        s,
+                        p,
+                        Literal::from(i * 10 + j),
+                        GraphName::DefaultGraph,
+                    ))?;
+                }
+                Result::Ok(())
+            })
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        thread.join().unwrap()?;
+    }
+    let sequence_numbers = store
+        .changes_since(3)?
+        .map(|change| Ok(change?.sequence_number))
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(sequence_numbers, (4..44).collect::<Vec<_>>());
+    Ok(())
+}
+
+#[test]
+fn test_query_timeout() -> std::result::Result<(), EvaluationError> {
+    let store = Store::new()?;
+    for quad in quads(GraphNameRef::DefaultGraph) {
+        store.insert(quad)?;
+    }
+    let options = QueryOptions::default().with_timeout(Duration::from_secs(0));
+    if let QueryResults::Solutions(mut solutions) =
+        store.query_opt("SELECT * WHERE { ?s ?p ?o . ?s2 ?p2 ?o2 }", options.clone())?
+    {
+        assert!(matches!(
+            solutions.next(),
+            Some(Err(EvaluationError::Timeout))
+        ));
+        assert!(solutions.next().is_none());
+    } else {
+        unreachable!()
+    }
+    assert!(matches!(
+        store.update_opt("DELETE WHERE { ?s ?p ?o }", options.into()),
+        Err(EvaluationError::Timeout)
+    ));
+    assert_eq!(store.len()?, NUMBER_OF_TRIPLES);
+    Ok(())
+}
+
+#[test]
+fn test_query_limits() -> std::result::Result<(), EvaluationError> {
+    let store = Store::new()?;
+    for quad in quads(GraphNameRef::DefaultGraph) {
+        store.insert(quad)?;
+    }
+    for (query, options) in [
+        (
+            "SELECT * WHERE { ?s ?p ?o } ORDER BY ?o",
+            QueryOptions::default().with_max_intermediate_rows(NUMBER_OF_TRIPLES - 1),
+        ),
+        (
+            "SELECT DISTINCT ?o WHERE { ?s ?p ?o }",
+            QueryOptions::default().with_max_memory_size(64),
+        ),
+        (
+            "SELECT (COUNT(*) AS ?c) WHERE { ?s ?p ?o } GROUP BY ?o",
+            QueryOptions::default().with_max_intermediate_rows(1),
+        ),
+        (
+            "SELECT * WHERE { ?s ?p ?o }",
+            QueryOptions::default().with_max_result_rows(NUMBER_OF_TRIPLES - 1),
+        ),
+    ] {
+        if let QueryResults::Solutions(mut solutions) = store.query_opt(query, options)? {
+            assert!(solutions
+                .by_ref()
+                .any(|s| matches!(s, Err(EvaluationError::LimitExceeded(_)))));
+            // The evaluation stops after the first limit error
+            assert!(solutions.next().is_none());
+        } else {
+            unreachable!()
+        }
+    }
+    if let QueryResults::Solutions(solutions) = store.query_opt(
+        "SELECT * WHERE { ?s ?p ?o } ORDER BY ?o",
+        QueryOptions::default()
+            .with_max_intermediate_rows(NUMBER_OF_TRIPLES)
+            .with_max_result_rows(NUMBER_OF_TRIPLES),
+    )? {
+        assert_eq!(solutions.collect::<Vec<_>>().len(), NUMBER_OF_TRIPLES);
+    } else {
+        unreachable!()
+    }
+    Ok(())
+}
+
+#[test]
+fn test_spill_to_disk() -> std::result::Result<(), EvaluationError> {
+    let store = Store::new()?;
+    for quad in quads(GraphNameRef::DefaultGraph) {
+        store.insert(quad)?;
+    }
+    for (query, ordered) in [
+        ("SELECT * WHERE { ?s ?p ?o } ORDER BY ?o DESC(?p)", true),
+        ("SELECT DISTINCT ?p WHERE { ?s ?p ?o }", false),
+        ("SELECT DISTINCT ?o WHERE { ?s ?p ?o }", false),
+        (
+            "SELECT ?p (COUNT(*) AS ?c) (SAMPLE(?o) AS ?sample) WHERE { ?s ?p ?o } GROUP BY ?p",
+            false,
+        ),
+        (
+            "SELECT (COUNT(*) AS ?c) WHERE { ?s ?p ?o } GROUP BY ?o",
+            false,
+        ),
+    ] {
+        let expected = solutions_as_strings(&store, query, QueryOptions::default(), ordered)?;
+        let spilled = solutions_as_strings(
+            &store,
+            query,
+            QueryOptions::default().with_spill_threshold(0),
+            ordered,
+        )?;
+        assert_eq!(expected, spilled, "{}", query);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_parallel_evaluation() -> std::result::Result<(), EvaluationError> {
+    let store = Store::new()?;
+    for quad in quads(GraphNameRef::DefaultGraph) {
+        store.insert(quad)?;
+    }
+    for (query, ordered) in [
+        (
+            "SELECT * WHERE { { ?s a ?o } UNION { ?s ?p ?o } UNION { BIND(CONCAT(\"a\", \"b\") AS ?o) } }",
+            true,
+        ),
+        ("SELECT * WHERE { ?s a ?t . ?s2 a ?t2 }", false),
+        (
+            "SELECT * WHERE { ?s a ?t { ?s ?p ?o } UNION { ?s ?p2 ?o2 } } ORDER BY ?s ?p ?p2",
+            true,
+        ),
+        (
+            "SELECT ?s (COUNT(*) AS ?c) WHERE { { ?s ?p ?o } UNION { ?o ?p ?s } } GROUP BY ?s",
+            false,
+        ),
+        (
+            "SELECT * WHERE { ?s a ?t OPTIONAL { { ?s ?p ?o } UNION { ?o ?p ?s } } }",
+            false,
+        ),
+    ] {
+        let expected = solutions_as_strings(&store, query, QueryOptions::default(), ordered)?;
+        let parallel = solutions_as_strings(
+            &store,
+            query,
+            QueryOptions::default().with_parallelism(2),
+            ordered,
+        )?;
+        assert_eq!(expected, parallel, "{}", query);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_parallel_evaluation_shared_pool() -> std::result::Result<(), EvaluationError> {
+    let store = Store::new()?;
+    let ex = NamedNodeRef::new_unchecked("http://example.com");
+    for i in 0..2000 {
+        store.insert(QuadRef::new(
+            ex,
+            ex,
+            LiteralRef::from(&Literal::from(i)),
+            GraphNameRef::DefaultGraph,
+        ))?;
+    }
+    let query = "SELECT * WHERE { { ?s ?p ?o } UNION { ?o ?p ?s } UNION { ?s ?p ?o } }";
+
+    // The tuples queued by the pool threads are counted in the memory limit
+    assert_eq!(
+        solutions_as_strings(
+            &store,
+            query,
+            QueryOptions::default().with_max_memory_size(10_000),
+            false
+        )?
+        .len(),
+        6000
+    );
+    assert!(matches!(
+        solutions_as_strings(
+            &store,
+            query,
+            QueryOptions::default()
+                .with_parallelism(2)
+                .with_max_memory_size(10_000),
+            false
+        ),
+        Err(EvaluationError::LimitExceeded(_))
+    ));
+
+    // The queries share the same threads
+    let threads = (0..8)
+        .map(|i| {
+            let store = store.clone();
+            spawn(move || {
+                solutions_as_strings(
+                    &store,
+                    query,
+                    QueryOptions::default().with_parallelism(1 + i % 2),
+                    false,
+                )
+                .map(|solutions| solutions.len())
+            })
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        assert_eq!(thread.join().unwrap()?, 6000);
+    }
+    Ok(())
+}
+
+fn solutions_as_strings(
+    store: &Store,
+    query: impl TryInto<Query, Error = impl Into<EvaluationError>>,
+    options: QueryOptions,
+    ordered: bool,
+) -> std::result::Result<Vec<String>, EvaluationError> {
+    if let QueryResults::Solutions(solutions) = store.query_opt(query, options)? {
+        let mut solutions = solutions
+            .map(|solution| {
+                Ok(solution?
+                    .iter()
+                    .map(|(variable, value)| format!("{} {}", variable, value))
+                    .collect::<Vec<_>>()
+                    .join(" "))
+            })
+            .collect::<std::result::Result<Vec<_>, EvaluationError>>()?;
+        if !ordered {
+            solutions.sort();
+        }
+        Ok(solutions)
+    } else {
+        unreachable!()
+    }
+}
+
+#[test]
+fn test_join_ordering_with_statistics() -> std::result::Result<(), EvaluationError> {
+    let queries = [
+        "SELECT * WHERE { ?s a <http://schema.org/City> ; <http://schema.org/name> ?n ; <http://schema.org/country> ?c }",
+        "SELECT * WHERE { ?s <http://schema.org/name> ?n . ?s2 <http://schema.org/postalCode> ?c }",
+        "SELECT * WHERE { ?s <http://schema.org/name> ?n ; <http://example.com/unknown> ?o }",
+        "SELECT * WHERE { GRAPH ?g { ?s <http://schema.org/name> ?n ; ?p ?o } }",
+        "SELECT * WHERE { GRAPH <http://example.com/g> { ?s ?p ?o . ?o ?p2 ?o2 } }",
+    ];
+    let store_dir = TempDir::default();
+    let graph = NamedNodeRef::new_unchecked("http://example.com/g");
+    let expected = {
+        let store = Store::open(&store_dir.0)?;
+        for quad in quads(GraphNameRef::DefaultGraph)
+            .into_iter()
+            .chain(quads(graph))
+        {
+            store.insert(quad)?;
+        }
+        let expected = queries
+            .iter()
+            .map(|query| solutions_as_strings(&store, *query, QueryOptions::default(), false))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        store.optimize()?;
+        for (query, expected) in queries.iter().zip(&expected) {
+            assert_eq!(
+                &solutions_as_strings(&store, *query, QueryOptions::default(), false)?,
+                expected,
+                "{}",
+                query
+            );
+        }
+        expected
+    };
+
+    // The statistics are persisted
+    let store = Store::open(&store_dir.0)?;
+    for (query, expected) in queries.iter().zip(&expected) {
+        assert_eq!(
+            &solutions_as_strings(&store, *query, QueryOptions::default(), false)?,
+            expected,
+            "{}",
+            query
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_join_ordering_with_outdated_statistics() -> std::result::Result<(), EvaluationError> {
+    let store = Store::new()?;
+    let ex = |name: String| NamedNode::new_unchecked(format!("http://example.com/{}", name));
+    let quad = |s: usize, p: &str, o: usize| {
+        Quad::new(
+            ex(format!("s{}", s)),
+            ex(p.into()),
+            ex(format!("o{}", o)),
+            GraphName::DefaultGraph,
+        )
+    };
+    store.extend((0..50).map(|i| quad(i, "p", i)))?;
+    store.insert(&quad(0, "selective", 0))?;
+    store.optimize()?;
+    // Predicates inserted after the statistics computation are not assumed to be empty
+    store.extend((0..50).map(|i| quad(i, "new", i)))?;
+    let explanation = store
+        .explain("SELECT * WHERE { ?s <http://example.com/new> ?o . ?s <http://example.com/selective> <http://example.com/o0> }")?
+        .to_string();
+    assert!(
+        explanation.find("<http://example.com/selective>")
+            < explanation.find("<http://example.com/new>"),
+        "{}",
+        explanation
+    );
+    Ok(())
+}
+
+#[test]
+fn test_explain() -> std::result::Result<(), EvaluationError> {
+    let store = Store::new()?;
+    let explanation = store
+        .explain("SELECT ?s (COUNT(?v) AS ?c) WHERE { ?s <http://example.com/p> ?o . ?o <http://example.com/p2> ?v FILTER(?v > 1) } GROUP BY ?s")?
+        .to_string();
+    assert!(explanation.starts_with("Select\n  Project variables=?s ?c\n"));
+    assert!(explanation.ends_with(
+        "        ForLoopJoin
+          QuadPattern subject=?s predicate=<http://example.com/p> object=?o graph=DEFAULT
+          Filter expression=(?v > \"1\"^^<http://www.w3.org/2001/XMLSchema#integer>)
+            QuadPattern subject=?o predicate=<http://example.com/p2> object=?v graph=DEFAULT
+"
+    ));
+
+    let mut json = Vec::new();
+    store
+        .explain("ASK { ?s ?p ?o }")?
+        .write_in_json(&mut json)?;
+    assert_eq!(
+        String::from_utf8(json).unwrap(),
+        r#"{"type":"Ask","children":[{"type":"Reduced","children":[{"type":"QuadPattern","subject":"?s","predicate":"?p","object":"?o","graph":"DEFAULT"}]}]}"#
+    );
+    Ok(())
+}
+
+#[test]
+fn test_symmetric_hash_join() -> std::result::Result<(), EvaluationError> {
+    let store = Store::new()?;
+    for quad in quads(GraphNameRef::DefaultGraph) {
+        store.insert(quad)?;
+    }
+    for query in [
+        "SELECT * WHERE { ?s a ?t . ?s2 a ?t2 }",
+        "SELECT * WHERE { { ?s a ?t } { ?s ?p ?o OPTIONAL { ?o ?p2 ?o2 } } }",
+    ] {
+        let limited_query = format!("{} LIMIT 1000", query);
+        assert!(store
+            .explain(&limited_query)?
+            .to_string()
+            .contains("SymmetricHashJoin"));
+        assert!(!store
+            .explain(query)?
+            .to_string()
+            .contains("SymmetricHashJoin"));
+        let expected = solutions_as_strings(&store, query, QueryOptions::default(), false)?;
+        assert_eq!(
+            solutions_as_strings(&store, &limited_query, QueryOptions::default(), false)?,
+            expected,
+            "{}",
+            query
+        );
+        let first = solutions_as_strings(
+            &store,
+            &format!("{} LIMIT 2", query),
+            QueryOptions::default(),
+            false,
+        )?;
+        assert_eq!(first.len(), expected.len().min(2), "{}", query);
+        assert!(first.iter().all(|s| expected.contains(s)), "{}", query);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_merge_join() -> std::result::Result<(), EvaluationError> {
+    let store = Store::new()?;
+    let ex = |name: &str| NamedNode::new_unchecked(format!("http://example.com/{}", name));
+    for i in 0..100 {
+        let s = ex(&format!("s{}", i));
+        if i % 2 == 0 {
+            store.insert(QuadRef::new(
+                &s,
+                rdf::TYPE,
+                &ex("City"),
+                GraphNameRef::DefaultGraph,
+            ))?;
+        }
+        if i % 3 == 0 {
+            store.insert(QuadRef::new(
+                &s,
+                &ex("country"),
+                &ex("FR"),
+                GraphNameRef::DefaultGraph,
+            ))?;
+        }
+        if i % 5 == 0 {
+            store.insert(QuadRef::new(
+                &s,
+                &ex("capital"),
+                &ex("FR"),
+                GraphNameRef::DefaultGraph,
+            ))?;
+        }
+    }
+    for query in [
+        "SELECT ?s WHERE { ?s a <http://example.com/City> ; <http://example.com/country> <http://example.com/FR> }",
+        "SELECT ?s WHERE { ?s a <http://example.com/City> ; <http://example.com/country> <http://example.com/FR> ; <http://example.com/capital> <http://example.com/FR> }",
+        "SELECT ?s ?s2 WHERE { ?s <http://example.com/country> ?c . ?s2 <http://example.com/capital> ?c }",
+        "SELECT ?s WHERE { VALUES ?s { <http://example.com/s6> <http://example.com/s7> } ?s a <http://example.com/City> ; <http://example.com/country> <http://example.com/FR> }",
+    ] {
+        assert!(
+            store.explain(query)?.to_string().contains("MergeJoin"),
+            "{}",
+            query
+        );
+        // The union default graph is not read in index order
+        let mut union_query = Query::parse(query, None)?;
+        union_query.dataset_mut().set_default_graph_as_union();
+        assert!(!store
+            .explain(union_query.clone())?
+            .to_string()
+            .contains("MergeJoin"));
+        assert_eq!(
+            solutions_as_strings(&store, query, QueryOptions::default(), false)?,
+            solutions_as_strings(&store, union_query, QueryOptions::default(), false)?,
+            "{}",
+            query
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_prepared_query() -> std::result::Result<(), EvaluationError> {
+    let store = Store::new()?;
+    let ex = |name: &str| NamedNode::new_unchecked(format!("http://example.com/{}", name));
+    let s = Variable::new_unchecked("s");
+    let c = Variable::new_unchecked("c");
+    let run = |prepared: &PreparedQuery, bindings: Vec<(Variable, Term)>| {
+        let mut solutions = Vec::new();
+        if let QueryResults::Solutions(results) = prepared.execute(bindings)? {
+            for solution in results {
+                solutions.push(
+                    solution?
+                        .iter()
+                        .map(|(v, t)| format!("{} {}", v, t))
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                );
+            }
+        }
+        solutions.sort();
+        std::result::Result::<_, EvaluationError>::Ok(solutions)
+    };
+
+    let prepared = store.prepare(
+        "SELECT ?s ?o ?l WHERE { ?s <http://example.com/p> ?o BIND(\"a string that is not in the store\" AS ?l) }",
+    )?;
+    store.insert(QuadRef::new(
+        &ex("a"),
+        &ex("p"),
+        &ex("1"),
+        GraphNameRef::DefaultGraph,
+    ))?;
+    store.insert(QuadRef::new(
+        &ex("b"),
+        &ex("p"),
+        &ex("2"),
+        GraphNameRef::DefaultGraph,
+    ))?;
+    assert_eq!(
+        run(&prepared, vec![(s.clone(), ex("a").into())])?,
+        vec!["?s <http://example.com/a> ?o <http://example.com/1> ?l \"a string that is not in the store\""]
+    );
+    assert_eq!(run(&prepared, vec![(s.clone(), ex("c").into())])?.len(), 0);
+    assert_eq!(run(&prepared, Vec::new())?.len(), 2);
+    assert!(prepared
+        .execute([(Variable::new_unchecked("p"), ex("p").into())])
+        .is_err());
+
+    let prepared = store.prepare("SELECT ?s (COUNT(*) AS ?c) WHERE { ?s ?p ?o } GROUP BY ?s")?;
+    store.insert(QuadRef::new(
+        &ex("a"),
+        &ex("p"),
+        &ex("3"),
+        GraphNameRef::DefaultGraph,
+    ))?;
+    assert_eq!(
+        run(&prepared, vec![(s.clone(), ex("a").into())])?,
+        vec!["?s <http://example.com/a> ?c \"2\"^^<http://www.w3.org/2001/XMLSchema#integer>"]
+    );
+    assert_eq!(
+        run(&prepared, vec![(c, Literal::from(1).into())])?,
+        vec!["?s <http://example.com/b> ?c \"1\"^^<http://www.w3.org/2001/XMLSchema#integer>"]
+    );
+
+    let prepared = store.prepare("CONSTRUCT { ?s ?p ?o } WHERE { ?s ?p ?o }")?;
+    if let QueryResults::Graph(triples) = prepared.execute([(s, ex("b").into())])? {
+        assert_eq!(triples.count(), 1);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_query_cache() -> std::result::Result<(), EvaluationError> {
+    let store = Store::new()?.with_query_cache(2, 1_000_000);
+    let ex = NamedNodeRef::new_unchecked("http://example.com");
+    // RAND() returns a new value on each evaluation so an equal value means that the results are cached
+    let random = |query: &str| solutions_as_strings(&store, query, QueryOptions::default(), false);
+    let query = "SELECT (RAND() AS ?r) WHERE {}";
+
+    let cached = random(query)?;
+    assert_eq!(random(query)?, cached);
+    assert_eq!(
+        random("PREFIX ex: <http://example.com/>\nSELECT (RAND() AS ?r)\nWHERE { }")?,
+        cached
+    );
+    assert_eq!(
+        solutions_as_strings(&store.clone(), query, QueryOptions::default(), false)?,
+        cached
+    );
+    assert_ne!(
+        solutions_as_strings(
+            &store,
+            query,
+            QueryOptions::default().with_profiling(QueryProfile::new()),
+            false
+        )?,
+        cached
+    );
+    let mut union_query = Query::parse(query, None)?;
+    union_query.dataset_mut().set_default_graph_as_union();
+    assert_ne!(
+        solutions_as_strings(&store, union_query, QueryOptions::default(), false)?,
+        cached
+    );
+
+    // Writes invalidate the cache
+    store.insert(QuadRef::new(ex, ex, ex, GraphNameRef::DefaultGraph))?;
+    let after_insert = random(query)?;
+    assert_ne!(after_insert, cached);
+    assert_eq!(random(query)?, after_insert);
+    store.update("DELETE WHERE { ?s ?p ?o }")?;
+    assert_ne!(random(query)?, after_insert);
+
+    // The least recently used results are evicted
+    let cached = random(query)?;
+    random("SELECT (RAND() AS ?r1) WHERE {}")?;
+    assert_eq!(random(query)?, cached);
+    random("SELECT (RAND() AS ?r2) WHERE {}")?;
+    random("SELECT (RAND() AS ?r3) WHERE {}")?;
+    assert_ne!(random(query)?, cached);
+
+    // The result limits apply to the cached results
+    for quad in quads(GraphNameRef::DefaultGraph) {
+        store.insert(quad)?;
+    }
+    let all = "SELECT * WHERE { ?s ?p ?o }";
+    assert_eq!(
+        solutions_as_strings(&store, all, QueryOptions::default(), false)?.len(),
+        NUMBER_OF_TRIPLES
+    );
+    assert!(matches!(
+        solutions_as_strings(
+            &store,
+            all,
+            QueryOptions::default().with_max_result_rows(1),
+            false
+        ),
+        Err(EvaluationError::LimitExceeded(_))
+    ));
+
+    // The results bigger than the cache memory are not cached
+    let store = Store::new()?.with_query_cache(2, 500);
+    for quad in quads(GraphNameRef::DefaultGraph) {
+        store.insert(quad)?;
+    }
+    let random = |query: &str| solutions_as_strings(&store, query, QueryOptions::default(), false);
+    let small = random(query)?;
+    assert_eq!(random(query)?, small);
+    let big_query = "SELECT ?s (RAND() AS ?r) WHERE { ?s ?p ?o }";
+    let big = random(big_query)?;
+    assert_eq!(big.len(), NUMBER_OF_TRIPLES);
+    assert_ne!(random(big_query)?, big);
+    Ok(())
+}
+
+#[test]
+fn test_text_index() -> std::result::Result<(), EvaluationError> {
+    let store = Store::new()?;
+    store.load_graph(
+        Cursor::new(DATA),
+        GraphFormat::Turtle,
+        GraphNameRef::DefaultGraph,
+        None,
+    )?;
+    let search = |query: &str| {
+        solutions_as_strings(
+            &store,
+            format!(
+                "PREFIX text: <http://oxigraph.org/text#> PREFIX schema: <http://schema.org/> {}",
+                query
+            )
+            .as_str(),
+            QueryOptions::default(),
+            false,
+        )
+    };
+    assert!(search("SELECT ?l WHERE { ?l text:match \"ville\" }").is_err());
+
+    store.enable_text_index(
+        TextIndexOptions::default().with_language_tokenizer("zh", Tokenizer::Characters),
+    )?;
+    assert_eq!(
+        search("SELECT ?l WHERE { ?l text:match \"VILLE\" }")?,
+        vec!["?l \"la ville lumière\"@fr"]
+    );
+    assert_eq!(
+        search("SELECT ?l WHERE { ?l text:match \"lumi* la\" }")?,
+        vec!["?l \"la ville lumière\"@fr"]
+    );
+    assert!(search("SELECT ?l WHERE { ?l text:match \"ville paris\" }")?.is_empty());
+    assert!(search("SELECT ?l WHERE { ?l text:match \"lumi\" }")?.is_empty());
+    // Only plain literals are indexed
+    assert!(search("SELECT ?l WHERE { ?l text:match \"www\" }")?.is_empty());
+    assert_eq!(
+        search("SELECT ?s WHERE { ?l text:match \"paris\" . ?s schema:name ?l }")?,
+        vec!["?s <http://www.wikidata.org/entity/Q90>"]
+    );
+    assert_eq!(
+        search("SELECT ?s WHERE { ?s schema:name ?l . ?l text:match \"paris\" }")?,
+        vec!["?s <http://www.wikidata.org/entity/Q90>"]
+    );
+    assert!(matches!(
+        store.query(
+            "PREFIX text: <http://oxigraph.org/text#> ASK { \"la ville lumière\"@fr text:match \"ville\" }"
+        )?,
+        QueryResults::Boolean(true)
+    ));
+
+    // The index follows the writes
+    store.update(
+        "PREFIX schema: <http://schema.org/> DELETE DATA { <http://www.wikidata.org/entity/Q90> schema:name \"la ville lumière\"@fr }",
+    )?;
+    assert!(search("SELECT ?l WHERE { ?l text:match \"ville\" }")?.is_empty());
+    store.update(
+        "PREFIX schema: <http://schema.org/> INSERT DATA { <http://www.wikidata.org/entity/Q90> schema:name \"巴黎市\"@zh-Hans }",
+    )?;
+    assert_eq!(
+        search("SELECT ?l WHERE { ?l text:match \"黎\" }")?,
+        vec!["?l \"巴黎市\"@zh-hans"]
+    );
+
+    store.disable_text_index()?;
+    assert!(search("SELECT ?l WHERE { ?l text:match \"paris\" }").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_geosparql() -> std::result::Result<(), EvaluationError> {
+    let store = Store::new()?;
+    store.load_graph(
+        Cursor::new(
+            r#"
+@prefix geo: <http://www.opengis.net/ont/geosparql#> .
+@prefix ex: <http://example.com/> .
+
+ex:paris geo:asWKT "POINT(2.3522 48.8566)"^^geo:wktLiteral .
+ex:london geo:asWKT "POINT(-0.1276 51.5072)"^^geo:wktLiteral .
+ex:seine geo:asWKT "LINESTRING(4.7 47.8, 2.35 48.85, 0.1 49.5)"^^geo:wktLiteral .
+ex:notgeo geo:asWKT "POINT(2.35 48.85)" .
+"#,
+        ),
+        GraphFormat::Turtle,
+        GraphNameRef::DefaultGraph,
+        None,
+    )?;
+    let prefixes = "PREFIX geo: <http://www.opengis.net/ont/geosparql#> PREFIX geof: <http://www.opengis.net/def/function/geosparql/> PREFIX uom: <http://www.opengis.net/def/uom/OGC/1.0/>";
+    let query = |query: &str| {
+        solutions_as_strings(
+            &store,
+            format!("{} {}", prefixes, query).as_str(),
+            QueryOptions::default(),
+            false,
+        )
+    };
+    let france = "\"POLYGON((-5 42, 8 42, 8 51, -5 51, -5 42))\"^^geo:wktLiteral";
+    let in_france = format!(
+        "SELECT ?s WHERE {{ ?s geo:asWKT ?wkt FILTER(geof:sfWithin(?wkt, {})) }}",
+        france
+    );
+    let intersects_france = format!(
+        "SELECT ?s WHERE {{ ?s geo:asWKT ?wkt FILTER(geof:sfIntersects({}, ?wkt)) }}",
+        france
+    );
+    let expected_in_france = vec![
+        "?s <http://example.com/paris>",
+        "?s <http://example.com/seine>",
+    ];
+    assert_eq!(query(&in_france)?, expected_in_france);
+    assert_eq!(query(&intersects_france)?, expected_in_france);
+    assert_eq!(
+        query("SELECT ?s WHERE { ?s geo:asWKT ?wkt FILTER(geof:sfContains(?wkt, \"POINT(2.35 48.85)\"^^geo:wktLiteral)) }")?,
+        vec!["?s <http://example.com/seine>"]
+    );
+    assert_eq!(
+        query("SELECT (ROUND(geof:distance(?p, ?l, uom:metre) / 1000) AS ?d) WHERE { <http://example.com/paris> geo:asWKT ?p . <http://example.com/london> geo:asWKT ?l }")?,
+        vec!["?d \"344\"^^<http://www.w3.org/2001/XMLSchema#double>"]
+    );
+    assert!(query("SELECT ?s WHERE { ?s geo:asWKT ?wkt FILTER(geof:sfFoo(?wkt, ?wkt)) }").is_err());
+
+    // The spatial index gives the same results
+    store.enable_geo_index()?;
+    assert!(store
+        .explain(format!("{} {}", prefixes, in_france).as_str())?
+        .to_string()
+        .contains("GeoSearch"));
+    assert_eq!(query(&in_france)?, expected_in_france);
+    assert_eq!(query(&intersects_france)?, expected_in_france);
+    store.update(
+        "PREFIX geo: <http://www.opengis.net/ont/geosparql#> DELETE WHERE { <http://example.com/seine> geo:asWKT ?wkt }",
+    )?;
+    store.update(
+        "PREFIX geo: <http://www.opengis.net/ont/geosparql#> INSERT DATA { <http://example.com/lyon> geo:asWKT \"POINT(4.8357 45.764)\"^^geo:wktLiteral }",
+    )?;
+    assert_eq!(
+        query(&in_france)?,
+        vec![
+            "?s <http://example.com/lyon>",
+            "?s <http://example.com/paris>"
+        ]
+    );
+    assert!(query("SELECT ?s WHERE { ?s geo:asWKT ?wkt FILTER(geof:sfWithin(?wkt, \"POLYGON((100 0, 101 0, 101 1, 100 1, 100 0))\"^^geo:wktLiteral)) }")?.is_empty());
+
+    store.disable_geo_index()?;
+    assert!(!store
+        .explain(format!("{} {}", prefixes, in_france).as_str())?
+        .to_string()
+        .contains("GeoSearch"));
+    assert_eq!(
+        query(&in_france)?,
+        vec![
+            "?s <http://example.com/lyon>",
+            "?s <http://example.com/paris>"
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_rdfs_entailment() -> std::result::Result<(), EvaluationError> {
+    // The cached results without entailment should not be returned with it
+    let store = Store::new()?.with_query_cache(10, 1_000_000);
+    store.load_graph(
+        Cursor::new(
+            r#"
+@prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+@prefix ex: <http://example.com/> .
+
+ex:Cat rdfs:subClassOf ex:Mammal .
+ex:Mammal rdfs:subClassOf ex:Animal .
+ex:hasKitten rdfs:subPropertyOf ex:hasChild .
+ex:hasChild rdfs:domain ex:Parent ; rdfs:range ex:Animal .
+
+ex:tom a ex:Cat ; ex:hasKitten ex:kitty .
+"#,
+        ),
+        GraphFormat::Turtle,
+        GraphNameRef::DefaultGraph,
+        None,
+    )?;
+    let prefixes =
+        "PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#> PREFIX ex: <http://example.com/>";
+    let query = |query: &str, options: QueryOptions| {
+        solutions_as_strings(
+            &store,
+            format!("{} {}", prefixes, query).as_str(),
+            options,
+            false,
+        )
+    };
+    let entailed = || QueryOptions::default().with_rdfs_entailment();
+
+    let animals = "SELECT ?s WHERE { ?s a ex:Animal }";
+    assert!(query(animals, QueryOptions::default())?.is_empty());
+    assert_eq!(
+        query(animals, entailed())?,
+        vec![
+            "?s <http://example.com/kitty>",
+            "?s <http://example.com/tom>"
+        ]
+    );
+    assert_eq!(
+        query("SELECT ?s WHERE { ?s a ex:Parent }", entailed())?,
+        vec!["?s <http://example.com/tom>"]
+    );
+    assert_eq!(
+        query("SELECT ?s ?o WHERE { ?s ex:hasChild ?o }", entailed())?,
+        vec!["?s <http://example.com/tom> ?o <http://example.com/kitty>"]
+    );
+    assert_eq!(
+        query("SELECT ?c WHERE { ex:Cat rdfs:subClassOf ?c }", entailed())?,
+        vec![
+            "?c <http://example.com/Animal>",
+            "?c <http://example.com/Mammal>"
+        ]
+    );
+    assert_eq!(
+        query("SELECT ?c WHERE { ex:tom a ?c }", entailed())?,
+        vec![
+            "?c <http://example.com/Animal>",
+            "?c <http://example.com/Cat>",
+            "?c <http://example.com/Mammal>",
+            "?c <http://example.com/Parent>"
+        ]
+    );
+    // Each entailed triple is returned once
+    assert_eq!(
+        query("SELECT ?p WHERE { ex:tom ?p ex:kitty }", entailed())?,
+        vec![
+            "?p <http://example.com/hasChild>",
+            "?p <http://example.com/hasKitten>"
+        ]
+    );
+    assert_eq!(
+        query(
+            "SELECT ?s WHERE { ?s a ex:Mammal ; ex:hasChild [ a ex:Animal ] }",
+            entailed()
+        )?,
+        vec!["?s <http://example.com/tom>"]
+    );
+    // Blank nodes do not join with variables of the same name
+    assert_eq!(
+        query(
+            "SELECT ?s ?b WHERE { ?s ex:hasChild _:b . ?b a ex:Cat }",
+            entailed()
+        )?,
+        vec!["?s <http://example.com/tom> ?b <http://example.com/tom>"]
+    );
+
+    // The schema is read during the evaluation
+    let prepared = store.prepare_opt(
+        format!("{} ASK {{ ex:kitty a ex:Cat }}", prefixes).as_str(),
+        entailed(),
+    )?;
+    assert!(matches!(
+        prepared.execute([])?,
+        QueryResults::Boolean(false)
+    ));
+    store.update(
+        "PREFIX rdfs: <http://www.w3.org/2000/01/rdf-schema#> PREFIX ex: <http://example.com/> INSERT DATA { ex:hasKitten rdfs:range ex:Cat }",
+    )?;
+    assert!(matches!(prepared.execute([])?, QueryResults::Boolean(true)));
+    Ok(())
+}
+
+#[test]
+fn test_materialization() -> std::result::Result<(), EvaluationError> {
+    const ONTOLOGY: &str = r#"
+@prefix owl: <http://www.w3.org/2002/07/owl#> .
+@prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+@prefix ex: <http://example.com/> .
+
+ex:hasParent owl:inverseOf ex:hasChild ; rdfs:subPropertyOf ex:hasAncestor .
+ex:hasAncestor a owl:TransitiveProperty .
+ex:Mother owl:intersectionOf ( ex:Parent ex:Woman ) .
+ex:hasChild rdfs:domain ex:Parent .
+
+ex:a ex:hasChild ex:b .
+ex:b ex:hasChild ex:c .
+"#;
+    let ex = |name: &str| NamedNode::new_unchecked(format!("http://example.com/{}", name));
+    let inferred = ex("inferred");
+    let is_inferred = |store: &Store, s: &str, p: &str, o: &str| -> Result<bool> {
+        store.contains(QuadRef::new(&ex(s), &ex(p), &ex(o), &inferred))
+    };
+    let store_dir = TempDir::default();
+    {
+        let store = Store::open(&store_dir.0)?;
+        store.load_graph(
+            Cursor::new(ONTOLOGY),
+            GraphFormat::Turtle,
+            GraphNameRef::DefaultGraph,
+            None,
+        )?;
+        store.materialize(Ruleset::Owl2Rl, &inferred)?;
+        assert!(is_inferred(&store, "b", "hasParent", "a")?);
+        assert!(is_inferred(&store, "c", "hasAncestor", "a")?);
+        assert!(!is_inferred(&store, "a", "hasChild", "b")?);
+
+        // Removals are propagated
+        store.remove(QuadRef::new(
+            &ex("b"),
+            &ex("hasChild"),
+            &ex("c"),
+            GraphNameRef::DefaultGraph,
+        ))?;
+        assert!(!is_inferred(&store, "c", "hasParent", "b")?);
+        assert!(!is_inferred(&store, "c", "hasAncestor", "a")?);
+        assert!(is_inferred(&store, "b", "hasAncestor", "a")?);
+    }
+
+    // The materialization is still maintained after reopening the store
+    let store = Store::open(&store_dir.0)?;
+    store.update(
+        "PREFIX ex: <http://example.com/> INSERT DATA { ex:b ex:hasChild ex:d . ex:b a ex:Woman }",
+    )?;
+    assert!(is_inferred(&store, "d", "hasAncestor", "a")?);
+    assert!(store.contains(QuadRef::new(&ex("b"), rdf::TYPE, &ex("Mother"), &inferred))?);
+
+    // Changes of the lists are taken into account
+    store
+        .update("PREFIX owl: <http://www.w3.org/2002/07/owl#> PREFIX ex: <http://example.com/> INSERT DATA { ex:Sister owl:unionOf ( ex:Woman ) }")?;
+    assert!(store.contains(QuadRef::new(&ex("b"), rdf::TYPE, &ex("Sister"), &inferred))?);
+
+    // The inferred triples are kept after stopping the materialization
+    store.stop_materialization()?;
+    store.remove(QuadRef::new(
+        &ex("b"),
+        &ex("hasChild"),
+        &ex("d"),
+        GraphNameRef::DefaultGraph,
+    ))?;
+    assert!(is_inferred(&store, "d", "hasAncestor", "a")?);
+
+    // In-memory datasets
+    let mut dataset = Dataset::new();
+    dataset.load(Cursor::new(ONTOLOGY), DatasetFormat::TriG, None)?;
+    materialize(&mut dataset, Ruleset::Rdfs, &inferred);
+    assert!(dataset.contains(QuadRef::new(&ex("a"), rdf::TYPE, &ex("Parent"), &inferred)));
+    assert!(!dataset.contains(QuadRef::new(
+        &ex("b"),
+        &ex("hasParent"),
+        &ex("a"),
+        &inferred
+    )));
+    Ok(())
+}
+
+#[test]
+fn test_materialization_with_concurrent_removal() -> Result<()> {
+    let ex = |name: &str| NamedNode::new_unchecked(format!("http://example.com/{}", name));
+    let inferred = ex("inferred");
+    let store = Store::new()?;
+    store.materialize(Ruleset::Rdfs, &inferred)?;
+    for i in 0..100 {
+        let class = ex(&format!("C{}", i));
+        let subject = ex(&format!("s{}", i));
+        let property = ex(&format!("p{}", i));
+        let domain = Quad::new(
+            property.clone(),
+            rdfs::DOMAIN,
+            class.clone(),
+            GraphName::DefaultGraph,
+        );
+        store.insert(&domain)?;
+
+        // The maintenance of the insertion must not miss the concurrent removal of the domain
+        let insertion = {
+            let store = store.clone();
+            let quad = Quad::new(subject.clone(), property, ex("o"), GraphName::DefaultGraph);
+            spawn(move || store.insert(&quad))
+        };
+        let removal = {
+            let store = store.clone();
+            spawn(move || store.remove(&domain))
+        };
+        insertion.join().unwrap()?;
+        removal.join().unwrap()?;
+        assert!(!store.contains(QuadRef::new(&subject, rdf::TYPE, &class, &inferred))?);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_materialization_with_concurrent_writers() -> Result<()> {
+    let ex = |name: &str| NamedNode::new_unchecked(format!("http://example.com/{}", name));
+    let inferred = ex("inferred");
+    let store = Store::new()?;
+    store.materialize(Ruleset::Rdfs, &inferred)?;
+    let inferred_quads = |store: &Store| {
+        store
+            .quads_for_pattern(None, None, None, Some(inferred.as_ref().into()))
+            .collect::<Result<HashSet<_>>>()
+    };
+
+    // Each thread extends the class hierarchy and adds instances that depend on the other threads hierarchy
+    let threads = (0..8)
+        .map(|i| {
+            let store = store.clone();
+            spawn(move || {
+                for j in 0..20 {
+                    let class = ex(&format!("C{}-{}", i, j));
+                    store.insert(QuadRef::new(
+                        &class,
+                        rdfs::SUB_CLASS_OF,
+                        &ex(&format!("C{}-{}", (i + 1) % 8, j)),
+                        GraphNameRef::DefaultGraph,
+                    ))?;
+                    store.insert(QuadRef::new(
+                        &ex(&format!("s{}-{}", i, j)),
+                        rdf::TYPE,
+                        &class,
+                        GraphNameRef::DefaultGraph,
+                    ))?;
+                    if j % 2 == 0 {
+                        store.remove(QuadRef::new(
+                            &class,
+                            rdfs::SUB_CLASS_OF,
+                            &ex(&format!("C{}-{}", (i + 1) % 8, j)),
+                            GraphNameRef::DefaultGraph,
+                        ))?;
+                    }
+                }
+                Ok::<_, Error>(())
+            })
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        thread.join().unwrap()?;
+    }
+
+    // The maintained inferred triples are the ones computed from scratch
+    let maintained = inferred_quads(&store)?;
+    store.materialize(Ruleset::Rdfs, &inferred)?;
+    assert_eq!(maintained, inferred_quads(&store)?);
+    Ok(())
+}
+
+#[test]
+fn test_construct_rules() -> std::result::Result<(), EvaluationError> {
+    let rule =
+        |rule: &str| Query::parse(&format!("PREFIX ex: <http://example.com/> {}", rule), None);
+    let ex = |name: &str| NamedNode::new_unchecked(format!("http://example.com/{}", name));
+    let store = Store::new()?;
+    for (s, o) in [("a", "b"), ("b", "c"), ("c", "d")] {
+        store.insert(QuadRef::new(
+            &ex(s),
+            &ex("parent"),
+            &ex(o),
+            GraphNameRef::DefaultGraph,
+        ))?;
+    }
+    let inferred = ex("inferred");
+
+    let rules = ConstructRules::new([
+        // The rules are not given in evaluation order
+        rule("CONSTRUCT { ?x ex:hasLineage [ ex:root ?y ] } WHERE { ?x ex:ancestor ?y FILTER NOT EXISTS { ?y ex:parent ?z } }")?,
+        rule("CONSTRUCT { ?x ex:ancestor ?y } WHERE { ?x ex:parent ?y }")?,
+        rule("CONSTRUCT { ?x ex:ancestor ?z } WHERE { ?x ex:ancestor ?y . ?y ex:ancestor ?z }")?,
+    ])?;
+    assert_eq!(store.apply_rules(&rules, &inferred)?, 12);
+    assert!(store.contains(QuadRef::new(&ex("a"), &ex("ancestor"), &ex("d"), &inferred))?);
+    // The rule creating blank nodes is only evaluated once all the ancestors are known
+    assert_eq!(
+        store
+            .quads_for_pattern(None, Some(ex("root").as_ref()), None, None)
+            .count(),
+        3
+    );
+    // Nothing new is inferred
+    let transitivity = ConstructRules::new([rule(
+        "CONSTRUCT { ?x ex:ancestor ?z } WHERE { ?x ex:ancestor ?y . ?y ex:ancestor ?z }",
+    )?])?;
+    assert_eq!(store.apply_rules(&transitivity, &inferred)?, 0);
+
+    // Recursive rules creating new terms are rejected
+    assert!(ConstructRules::new([rule(
+        "CONSTRUCT { ?x ex:parent ?y } WHERE { ?x ex:parent ?z BIND(BNODE() AS ?y) }"
+    )?])
+    .is_err());
+    assert!(ConstructRules::new([rule("SELECT * WHERE { ?s ?p ?o }")?]).is_err());
+
+    // Maximal number of iterations
+    let counter = ConstructRules::new([rule(
+        "CONSTRUCT { ex:counter ex:value ?next } WHERE { ex:counter ex:value ?v FILTER(?v < 100) BIND(?v + 1 AS ?next) }",
+    )?])?
+    .with_max_iterations(10);
+    store.insert(QuadRef::new(
+        &ex("counter"),
+        &ex("value"),
+        LiteralRef::from(&Literal::from(0)),
+        GraphNameRef::DefaultGraph,
+    ))?;
+    assert!(matches!(
+        store.apply_rules(&counter, &inferred),
+        Err(EvaluationError::LimitExceeded(_))
+    ));
+    // Nothing is inserted if the evaluation fails
+    assert_eq!(
+        store
+            .quads_for_pattern(Some(ex("counter").as_ref().into()), None, None, None)
+            .count(),
+        1
+    );
+    assert_eq!(store.named_graphs().count(), 1);
+
+    // Longer chains only use the triples produced by the previous iteration
+    let store = Store::new()?;
+    for i in 0..20 {
+        store.insert(QuadRef::new(
+            &ex(&i.to_string()),
+            &ex("parent"),
+            &ex(&(i + 1).to_string()),
+            GraphNameRef::DefaultGraph,
+        ))?;
+    }
+    store.enable_change_log()?;
+    let rules = ConstructRules::new([
+        rule("CONSTRUCT { ?x ex:ancestor ?y } WHERE { ?x ex:parent ?y }")?,
+        rule("CONSTRUCT { ?x ex:ancestor ?z } WHERE { ?x ex:ancestor ?y . ?y ex:ancestor ?z }")?,
+        rule("CONSTRUCT { ?z ex:descendant ?x } WHERE { ?x ex:parent|ex:ancestor ?y . ?y ex:ancestor ?z FILTER(?x != ?z) }")?,
+    ])?;
+    assert_eq!(store.apply_rules(&rules, &inferred)?, 2 * 20 * 21 / 2 - 20);
+    // Only the inferred triples are logged
+    let changes = store
+        .changes_since(0)?
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    assert_eq!(changes.len(), 2 * 20 * 21 / 2 - 20);
+    for change in changes {
+        assert_eq!(change.kind, ChangeKind::Insertion);
+        assert_eq!(change.quad.graph_name, inferred.clone().into());
+        assert!(store.contains(&change.quad)?);
+    }
+    assert!(store.contains(QuadRef::new(
+        &ex("0"),
+        &ex("ancestor"),
+        &ex("20"),
+        &inferred
+    ))?);
+    assert!(store.contains(QuadRef::new(
+        &ex("20"),
+        &ex("descendant"),
+        &ex("0"),
+        &inferred
+    ))?);
+    assert_eq!(store.named_graphs().count(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_profiling() -> std::result::Result<(), EvaluationError> {
+    let store = Store::new()?;
+    let ex = NamedNodeRef::new_unchecked("http://example.com");
+    for i in 0..3 {
+        store.insert(QuadRef::new(
+            ex,
+            ex,
+            LiteralRef::from(&Literal::from(i)),
+            GraphNameRef::DefaultGraph,
+        ))?;
+    }
+    let profile = QueryProfile::new();
+    assert!(profile.explanation().is_none());
+    if let QueryResults::Solutions(solutions) = store.query_opt(
+        "SELECT ?o WHERE { ?s <http://example.com> ?o } LIMIT 2",
+        QueryOptions::default().with_profiling(profile.clone()),
+    )? {
+        assert_eq!(
+            solutions.collect::<std::result::Result<Vec<_>, _>>()?.len(),
+            2
+        );
+    } else {
+        panic!("SELECT query should return solutions")
+    }
+    let explanation = profile.explanation().unwrap().to_string();
+    let lines = explanation.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0], "Select");
+    assert!(lines[1].starts_with("  Limit count=2 rows=2 duration="));
+    assert!(lines[2].starts_with("    Project variables=?o rows=2 duration="));
+    assert!(lines[3].starts_with("      QuadPattern subject=?s predicate=<http://example.com> object=?o graph=DEFAULT rows=2 duration="));
+    Ok(())
+}
+
+#[test]
+fn test_backup() -> Result<()> {
+    let quad = QuadRef::new(
+        NamedNodeRef::new_unchecked("http://example.com/s"),
+        NamedNodeRef::new_unchecked("http://example.com/p"),
+        NamedNodeRef::new_unchecked("http://example.com/o"),
+        GraphNameRef::DefaultGraph,
     );
-    let store = Store::new()?;
+    let store_dir = TempDir::default();
+    let backup_dir = TempDir::default();
+
+    let store = Store::open(&store_dir.0)?;
     store.insert(quad)?;
-    let iter = store.iter();
+    store.backup(&backup_dir.0)?;
     store.remove(quad)?;
-    assert_eq!(iter.collect::<Result<Vec<_>>>()?, vec![quad.into_owned()]);
+
+    assert!(!store.contains(quad)?);
+    let backup = Store::open(&backup_dir.0)?;
+    assert!(backup.contains(quad)?);
     Ok(())
 }
 
 #[test]
-fn test_bulk_load_on_existing_delete_overrides_the_delete() -> Result<()> {
+fn test_backup_on_read_only_and_secondary() -> Result<()> {
     let quad = QuadRef::new(
         NamedNodeRef::new_unchecked("http://example.com/s"),
         NamedNodeRef::new_unchecked("http://example.com/p"),
         NamedNodeRef::new_unchecked("http://example.com/o"),
-        NamedNodeRef::new_unchecked("http://example.com/g"),
+        GraphNameRef::DefaultGraph,
+    );
+    let primary_dir = TempDir::default();
+    let secondary_dir = TempDir::default();
+    let backup_dir = TempDir::default();
+
+    // The primary is still open and writing while the other stores try to backup
+    let primary = Store::open(&primary_dir.0)?;
+    primary.insert(quad)?;
+    primary.flush()?;
+    let read_only = Store::open_read_only(&primary_dir.0)?;
+    let secondary = Store::open_secondary(&primary_dir.0, &secondary_dir.0)?;
+    primary.remove(quad)?;
+    for store in [read_only, secondary] {
+        assert_eq!(
+            store.backup(&backup_dir.0).unwrap_err().kind(),
+            ErrorKind::PermissionDenied
+        );
+        assert!(!backup_dir.0.exists());
+    }
+    primary.backup(&backup_dir.0)?;
+    Ok(())
+}
+
+#[test]
+fn test_backup_on_in_memory() -> Result<()> {
+    let backup_dir = TempDir::default();
+    assert!(Store::new()?.backup(&backup_dir.0).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_shacl_validation() -> std::result::Result<(), EvaluationError> {
+    let mut shapes = Graph::new();
+    shapes.load(
+        r#"
+        @prefix ex: <http://example.com/> .
+        @prefix rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#> .
+        @prefix sh: <http://www.w3.org/ns/shacl#> .
+        @prefix xsd: <http://www.w3.org/2001/XMLSchema#> .
+
+        ex:PersonShape a sh:NodeShape ;
+            sh:targetClass ex:Person ;
+            sh:closed true ;
+            sh:ignoredProperties ( rdf:type ) ;
+            sh:property [
+                sh:path ex:name ;
+                sh:minCount 1 ;
+                sh:maxCount 1 ;
+                sh:datatype xsd:string ;
+                sh:pattern "^[A-Z]"
+            ] , [
+                sh:path ex:age ;
+                sh:datatype xsd:integer ;
+                sh:minInclusive 0 ;
+                sh:maxExclusive 150
+            ] , [
+                sh:path ex:knows ;
+                sh:class ex:Person ;
+                sh:node ex:PersonShape
+            ] , [
+                sh:path ( ex:knows ex:name ) ;
+                sh:disjoint ex:name
+            ] , [
+                sh:path [ sh:inversePath ex:knows ] ;
+                sh:maxCount 2
+            ] .
+
+        ex:EmailShape sh:targetSubjectsOf ex:email ;
+            sh:property [
+                sh:path ex:email ;
+                sh:nodeKind sh:IRI ;
+                sh:severity sh:Warning ;
+                sh:message "Emails should be IRIs"
+            ] .
+
+        ex:BobShape sh:targetNode ex:bob ;
+            sh:not [ sh:class ex:Robot ] ;
+            sh:or ( [ sh:path ex:age ; sh:minCount 1 ] [ sh:path ex:name ; sh:minCount 2 ] ) ."#
+            .as_bytes(),
+        GraphFormat::Turtle,
+        None,
+    )?;
+    let shapes = ShapesGraph::new(&shapes)?;
+
+    let data = r#"
+        @prefix ex: <http://example.com/> .
+        ex:alice a ex:Person ; ex:name "Alice" ; ex:age 30 ; ex:knows ex:bob .
+        ex:bob a ex:Person ; ex:name "bob" ; ex:age 200 ; ex:email "bob@example.com" ."#;
+    let mut graph = Graph::new();
+    graph.load(data.as_bytes(), GraphFormat::Turtle, None)?;
+    let report = shapes.validate(&graph)?;
+    assert!(!report.conforms());
+    let ex = |name: &str| NamedNode::new_unchecked(format!("http://example.com/{}", name));
+    let sh = |name: &str| NamedNode::new_unchecked(format!("http://www.w3.org/ns/shacl#{}", name));
+    let results = report
+        .results()
+        .iter()
+        .map(|result| {
+            (
+                result.focus_node().clone(),
+                result.source_constraint_component().into_owned(),
+                result.value().cloned(),
+            )
+        })
+        .collect::<HashSet<_>>();
+    assert_eq!(
+        results,
+        [
+            (
+                ex("bob").into(),
+                sh("PatternConstraintComponent"),
+                Some(Literal::from("bob").into())
+            ),
+            (
+                ex("bob").into(),
+                sh("MaxExclusiveConstraintComponent"),
+                Some(Literal::from(200).into())
+            ),
+            (
+                ex("bob").into(),
+                sh("ClosedConstraintComponent"),
+                Some(Literal::from("bob@example.com").into())
+            ),
+            (
+                ex("bob").into(),
+                sh("NodeKindConstraintComponent"),
+                Some(Literal::from("bob@example.com").into())
+            ),
+            (
+                ex("alice").into(),
+                sh("NodeConstraintComponent"),
+                Some(ex("bob").into())
+            ),
+        ]
+        .into_iter()
+        .collect()
+    );
+    let warning = report
+        .results()
+        .iter()
+        .find(|result| result.severity() == sh("Warning").as_ref())
+        .unwrap();
+    assert_eq!(warning.path(), Some(&ex("email").into()));
+    assert_eq!(warning.messages(), [Literal::from("Emails should be IRIs")]);
+    let report_graph = report.graph();
+    assert_eq!(
+        report_graph
+            .triples_for_predicate(sh("result").as_ref())
+            .count(),
+        5
     );
+    assert!(report_graph
+        .triples_for_predicate(sh("conforms").as_ref())
+        .all(|t| t.object == Literal::from(false).as_ref().into()));
+
+    // The same validation on a store
     let store = Store::new()?;
-    store.remove(quad)?;
-    store.bulk_extend([quad.into_owned()])?;
-    assert_eq!(store.len()?, 1);
+    let graph_name = NamedNodeRef::new_unchecked("http://example.com/g");
+    store.load_graph(data.as_bytes(), GraphFormat::Turtle, graph_name, None)?;
+    assert_eq!(store.validate(&shapes, graph_name)?.results().len(), 5);
+    // sh:targetNode applies even if the node is not in the data graph
+    let report = store.validate(&shapes, GraphNameRef::DefaultGraph)?;
+    assert_eq!(report.results().len(), 1);
+    assert_eq!(
+        report.results()[0].source_constraint_component(),
+        sh("OrConstraintComponent").as_ref()
+    );
+    store.remove_named_graph(graph_name)?;
+    store.load_graph(
+        r#"
+        @prefix ex: <http://example.com/> .
+        ex:alice a ex:Person ; ex:name "Alice" ; ex:knows ex:bob .
+        ex:bob a ex:Person ; ex:name "Bob" ; ex:age 20 ."#
+            .as_bytes(),
+        GraphFormat::Turtle,
+        graph_name,
+        None,
+    )?;
+    let report = store.validate(&shapes, graph_name)?;
+    assert!(report.conforms());
+    assert!(report.graph().contains(TripleRef::new(
+        report.graph().iter().next().unwrap().subject,
+        sh("conforms").as_ref(),
+        &Literal::from(true)
+    )));
+
+    // Invalid shapes
+    let mut invalid = Graph::new();
+    invalid.insert(TripleRef::new(
+        ex("shape").as_ref(),
+        sh("targetNode").as_ref(),
+        ex("alice").as_ref(),
+    ));
+    invalid.insert(TripleRef::new(
+        ex("shape").as_ref(),
+        sh("minCount").as_ref(),
+        &Literal::from("one"),
+    ));
+    assert_eq!(
+        ShapesGraph::new(&invalid).unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+    Ok(())
+}
+
+#[test]
+fn test_shacl_sparql_constraints() -> std::result::Result<(), EvaluationError> {
+    let mut shapes = Graph::new();
+    shapes.load(
+        r#"
+        @prefix sh: <http://www.w3.org/ns/shacl#> .
+        @prefix ex: <http://example.com/> .
+        ex: sh:declare [ sh:prefix "ex" ; sh:namespace "http://example.com/"^^<http://www.w3.org/2001/XMLSchema#anyURI> ] .
+
+        ex:PersonShape a sh:NodeShape ;
+            sh:targetClass ex:Person ;
+            sh:sparql [
+                sh:prefixes ex: ;
+                sh:message "{$this} knows itself" ;
+                sh:select "SELECT $this WHERE { $this ex:knows $this }"
+            ] ;
+            sh:property [
+                sh:path ( ex:knows ex:name ) ;
+                sh:sparql [
+                    sh:prefixes ex: ;
+                    sh:message "Friend name {?value} is too short" ;
+                    sh:select "SELECT ?value WHERE { $this $PATH ?value FILTER(STRLEN(?value) < 3) }"
+                ]
+            ] ."#
+            .as_bytes(),
+        GraphFormat::Turtle,
+        None,
+    )?;
+    let shapes = ShapesGraph::new(&shapes)?;
+
+    let data = r#"
+        @prefix ex: <http://example.com/> .
+        ex:alice a ex:Person ; ex:name "Alice" ; ex:knows ex:alice, ex:bob .
+        ex:bob a ex:Person ; ex:name "Bo" ."#;
+    let mut graph = Graph::new();
+    graph.load(data.as_bytes(), GraphFormat::Turtle, None)?;
+    let report = shapes.validate(&graph)?;
+    let sh = |name: &str| NamedNode::new_unchecked(format!("http://www.w3.org/ns/shacl#{}", name));
+    let alice = NamedNode::new_unchecked("http://example.com/alice");
+    let mut results = report
+        .results()
+        .iter()
+        .map(|r| {
+            (
+                r.focus_node().clone(),
+                r.value().cloned(),
+                r.messages()
+                    .iter()
+                    .map(|m| m.value().to_owned())
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect::<Vec<_>>();
+    results.sort_by_key(|(_, _, messages)| messages.clone());
+    assert_eq!(
+        results,
+        vec![
+            (
+                alice.clone().into(),
+                Some(alice.clone().into()),
+                vec![format!("{} knows itself", alice)]
+            ),
+            (
+                alice.clone().into(),
+                Some(Literal::from("Bo").into()),
+                vec!["Friend name Bo is too short".to_owned()]
+            ),
+        ]
+    );
+    for result in report.results() {
+        assert_eq!(
+            result.source_constraint_component(),
+            sh("SPARQLConstraintComponent").as_ref()
+        );
+        assert!(result.source_constraint().is_some());
+    }
+
+    // The same validation on a store named graph
+    let store = Store::new()?;
+    let graph_name = NamedNodeRef::new_unchecked("http://example.com/g");
+    store.load_graph(data.as_bytes(), GraphFormat::Turtle, graph_name, None)?;
+    assert_eq!(store.validate(&shapes, graph_name)?.results().len(), 2);
+    assert!(store
+        .validate(&shapes, GraphNameRef::DefaultGraph)?
+        .conforms());
+
+    // Invalid queries
+    let mut invalid = Graph::new();
+    invalid.load(
+        r#"
+        @prefix sh: <http://www.w3.org/ns/shacl#> .
+        <http://example.com/s> sh:targetNode <http://example.com/alice> ;
+            sh:sparql [ sh:select "ASK { $this ?p ?o }" ] ."#
+            .as_bytes(),
+        GraphFormat::Turtle,
+        None,
+    )?;
+    assert_eq!(
+        ShapesGraph::new(&invalid).unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+    Ok(())
+}
+
+#[test]
+fn test_registered_shapes() -> std::result::Result<(), EvaluationError> {
+    let mut shapes = Graph::new();
+    shapes.load(
+        r#"
+        @prefix sh: <http://www.w3.org/ns/shacl#> .
+        @prefix ex: <http://example.com/> .
+        ex:PersonShape sh:targetClass ex:Person ;
+            sh:property [ sh:path ex:name ; sh:minCount 1 ] ;
+            sh:property [ sh:path ex:email ; sh:nodeKind sh:IRI ; sh:severity sh:Warning ] ."#
+            .as_bytes(),
+        GraphFormat::Turtle,
+        None,
+    )?;
+    let shapes = ShapesGraph::new(&shapes)?;
+    let ex = |name: &str| NamedNode::new_unchecked(format!("http://example.com/{}", name));
+    let graph_name = ex("g");
+    let store = Store::new()?;
+    store.register_shapes(shapes, &graph_name);
+    let validation_error = |error: &Error| {
+        let error = error
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<ValidationError>())
+            .unwrap();
+        assert_eq!(error.graph_name(), graph_name.as_ref().into());
+        error.report().results().len()
+    };
+
+    // Simple insertion
+    let error = store
+        .insert(QuadRef::new(
+            &ex("alice"),
+            rdf::TYPE,
+            &ex("Person"),
+            &graph_name,
+        ))
+        .unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    assert_eq!(validation_error(&error), 1);
+    assert!(store.is_empty()?);
+
+    // Other graphs are not validated
+    store.insert(QuadRef::new(
+        &ex("alice"),
+        rdf::TYPE,
+        &ex("Person"),
+        GraphNameRef::DefaultGraph,
+    ))?;
+
+    // Update
+    match store.update(
+        "INSERT DATA { GRAPH <http://example.com/g> { <http://example.com/alice> a <http://example.com/Person> } }",
+    ) {
+        Err(EvaluationError::Io(error)) => assert_eq!(validation_error(&error), 1),
+        r => panic!("Unexpected result: {:?}", r),
+    }
+    store.update("INSERT DATA { GRAPH <http://example.com/g> { <http://example.com/alice> a <http://example.com/Person> ; <http://example.com/name> \"Alice\" ; <http://example.com/email> \"alice@example.com\" } }")?;
+    assert_eq!(store.len()?, 4);
+
+    // Transaction
+    let error = store
+        .transaction(|mut transaction| {
+            transaction.insert(QuadRef::new(
+                &ex("bob"),
+                rdf::TYPE,
+                &ex("Person"),
+                &graph_name,
+            ))?;
+            transaction.remove(QuadRef::new(
+                &ex("alice"),
+                &ex("name"),
+                &Literal::from("Alice"),
+                &graph_name,
+            ))
+        })
+        .unwrap_err();
+    assert_eq!(validation_error(&error), 3); // The report also contains the warning on ex:email
+    assert_eq!(store.len()?, 4);
+
+    // Once unregistered the shapes are not validated anymore
+    assert!(store.unregister_shapes(&graph_name));
+    assert!(!store.unregister_shapes(&graph_name));
+    store.insert(QuadRef::new(
+        &ex("bob"),
+        rdf::TYPE,
+        &ex("Person"),
+        &graph_name,
+    ))?;
+    Ok(())
+}
+
+#[test]
+fn test_registered_shapes_validate_changed_nodes() -> std::result::Result<(), EvaluationError> {
+    let mut shapes = Graph::new();
+    shapes.load(
+        r#"
+        @prefix sh: <http://www.w3.org/ns/shacl#> .
+        @prefix ex: <http://example.com/> .
+        ex:PersonShape sh:targetClass ex:Person ;
+            sh:property [ sh:path ex:name ; sh:minCount 1 ] ;
+            sh:property [ sh:path ex:nickname ; sh:maxCount 1 ] ;
+            sh:property [ sh:path ex:address ; sh:node ex:AddressShape ] .
+        ex:AddressShape sh:property [ sh:path ex:city ; sh:minCount 1 ] ."#
+            .as_bytes(),
+        GraphFormat::Turtle,
+        None,
+    )?;
+    let shapes = ShapesGraph::new(&shapes)?;
+    let ex = |name: &str| NamedNode::new_unchecked(format!("http://example.com/{}", name));
+    let store = Store::new()?;
+    store.update(
+        "PREFIX ex: <http://example.com/>
+        INSERT DATA {
+            ex:alice a ex:Person ; ex:name \"Alice\" ; ex:address ex:aliceAddress .
+            ex:aliceAddress ex:city \"Paris\" .
+            ex:bob a ex:Person .
+        }",
+    )?;
+    store.register_shapes(shapes, GraphNameRef::DefaultGraph);
+
+    // ex:bob is not valid but is not changed
+    store.insert(QuadRef::new(
+        &ex("carol"),
+        &ex("name"),
+        &Literal::from("Carol"),
+        GraphNameRef::DefaultGraph,
+    ))?;
+
+    // The change of the address is validated from ex:alice
+    assert!(store
+        .remove(QuadRef::new(
+            &ex("aliceAddress"),
+            &ex("city"),
+            &Literal::from("Paris"),
+            GraphNameRef::DefaultGraph,
+        ))
+        .is_err());
+
+    // Concurrent transactions are validated one after the other
+    for i in 0..20 {
+        let person = ex(&format!("person{}", i));
+        store.update(&format!(
+            "INSERT DATA {{ {} a <http://example.com/Person> ; <http://example.com/name> \"A\" }}",
+            person
+        ))?;
+        let threads = ["B", "C"]
+            .iter()
+            .map(|nickname| {
+                let store = store.clone();
+                let quad = Quad::new(
+                    person.clone(),
+                    ex("nickname"),
+                    Literal::from(*nickname),
+                    GraphName::DefaultGraph,
+                );
+                spawn(move || store.insert(&quad))
+            })
+            .collect::<Vec<_>>();
+        let committed = threads
+            .into_iter()
+            .map(|thread| thread.join().unwrap())
+            .filter(Result::is_ok)
+            .count();
+        assert_eq!(committed, 1);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_registered_shapes_with_concurrent_writers() -> std::result::Result<(), EvaluationError> {
+    let mut shapes = Graph::new();
+    shapes.load(
+        r#"
+        @prefix sh: <http://www.w3.org/ns/shacl#> .
+        @prefix ex: <http://example.com/> .
+        ex:PersonShape sh:targetClass ex:Person ;
+            sh:property [ sh:path ex:nickname ; sh:maxCount 1 ] ."#
+            .as_bytes(),
+        GraphFormat::Turtle,
+        None,
+    )?;
+    let ex = |name: &str| NamedNode::new_unchecked(format!("http://example.com/{}", name));
+    let graph_name = ex("g");
+    let store_dir = TempDir::default();
+    let store = Store::open(&store_dir.0)?;
+    store.register_shapes(ShapesGraph::new(&shapes)?, &graph_name);
+    for i in 0..10 {
+        store.insert(QuadRef::new(
+            &ex(&format!("person{}", i)),
+            rdf::TYPE,
+            &ex("Person"),
+            &graph_name,
+        ))?;
+    }
+
+    // The writers to the validated graph conflict, the other ones are not affected
+    let threads = (0..16)
+        .map(|i| {
+            let store = store.clone();
+            let graph_name = if i % 2 == 0 {
+                GraphName::from(graph_name.clone())
+            } else {
+                GraphName::DefaultGraph
+            };
+            spawn(move || {
+                (0..10)
+                    .filter(|j| {
+                        store
+                            .insert(&Quad::new(
+                                ex(&format!("person{}", j)),
+                                ex("nickname"),
+                                Literal::from(i),
+                                graph_name.clone(),
+                            ))
+                            .is_ok()
+                    })
+                    .count()
+            })
+        })
+        .collect::<Vec<_>>();
+    for (i, thread) in threads.into_iter().enumerate() {
+        let committed = thread.join().unwrap();
+        if i % 2 == 1 {
+            assert_eq!(committed, 10);
+        }
+    }
+    for i in 0..10 {
+        assert_eq!(
+            store
+                .quads_for_pattern(
+                    Some(ex(&format!("person{}", i)).as_ref().into()),
+                    Some(ex("nickname").as_ref()),
+                    None,
+                    Some(graph_name.as_ref().into()),
+                )
+                .count(),
+            1
+        );
+    }
+    assert!(store
+        .validate(&ShapesGraph::new(&shapes)?, &graph_name)?
+        .conforms());
+    Ok(())
+}
+
+#[test]
+fn test_read_only() -> Result<()> {
+    let quad = QuadRef::new(
+        NamedNodeRef::new_unchecked("http://example.com/s"),
+        NamedNodeRef::new_unchecked("http://example.com/p"),
+        NamedNodeRef::new_unchecked("http://example.com/o"),
+        GraphNameRef::DefaultGraph,
+    );
+    let store_dir = TempDir::default();
+    {
+        let store = Store::open(&store_dir.0)?;
+        store.insert(quad)?;
+    }
+
+    let store = Store::open_read_only(&store_dir.0)?;
+    assert!(store.contains(quad)?);
+    assert_eq!(
+        store.remove(quad).unwrap_err().kind(),
+        ErrorKind::PermissionDenied
+    );
+    assert!(store.catch_up_with_primary().is_err());
+    Ok(())
+}
+
+#[test]
+fn test_secondary() -> Result<()> {
+    let quad = QuadRef::new(
+        NamedNodeRef::new_unchecked("http://example.com/s"),
+        NamedNodeRef::new_unchecked("http://example.com/p"),
+        NamedNodeRef::new_unchecked("http://example.com/o"),
+        GraphNameRef::DefaultGraph,
+    );
+    let primary_dir = TempDir::default();
+    let secondary_dir = TempDir::default();
+
+    let primary = Store::open(&primary_dir.0)?;
+    let secondary = Store::open_secondary(&primary_dir.0, &secondary_dir.0)?;
+    primary.insert(quad)?;
+    primary.flush()?;
+    assert!(!secondary.contains(quad)?);
+    secondary.catch_up_with_primary()?;
+    assert!(secondary.contains(quad)?);
+    assert_eq!(
+        secondary.remove(quad).unwrap_err().kind(),
+        ErrorKind::PermissionDenied
+    );
+    Ok(())
+}
+
+#[test]
+fn test_secondary_change_log() -> Result<()> {
+    let quad = QuadRef::new(
+        NamedNodeRef::new_unchecked("http://example.com/s"),
+        NamedNodeRef::new_unchecked("http://example.com/p"),
+        NamedNodeRef::new_unchecked("http://example.com/o"),
+        GraphNameRef::DefaultGraph,
+    );
+    let primary_dir = TempDir::default();
+    let secondary_dir = TempDir::default();
+
+    // The secondary is opened before the primary enables the log
+    let primary = Store::open(&primary_dir.0)?;
+    let secondary = Store::open_secondary(&primary_dir.0, &secondary_dir.0)?;
+    primary.enable_change_log()?;
+    primary.insert(quad)?;
+    primary.flush()?;
+    secondary.catch_up_with_primary()?;
+    secondary.enable_change_log()?; // Nothing to do, the log is already enabled
+    assert_eq!(secondary.last_change_sequence_number()?, Some(1));
+    assert_eq!(secondary.changes_since(0)?.count(), 1);
     Ok(())
 }
 
@@ -225,3 +2347,19 @@ fn reset_dir(dir: &str) -> Result<()> {
         .success());
     Ok(())
 }
+
+struct TempDir(PathBuf);
+
+impl Default for TempDir {
+    fn default() -> Self {
+        Self(temp_dir().join(format!("oxigraph-test-{}", random::<u128>())))
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        if self.0.is_dir() {
+            remove_dir_all(&self.0).unwrap();
+        }
+    }
+}
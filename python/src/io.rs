@@ -25,6 +25,7 @@ pub fn add_to_module(module: &PyModule) -> PyResult<()> {
 /// * `Turtle <https://www.w3.org/TR/turtle/>`_ (``text/turtle``)
 /// * `TriG <https://www.w3.org/TR/trig/>`_ (``application/trig``)
 /// * `RDF/XML <https://www.w3.org/TR/rdf-syntax-grammar/>`_ (``application/rdf+xml``)
+/// * `JSON-LD <https://www.w3.org/TR/json-ld11/>`_ (``application/ld+json``)
 ///
 /// It supports also some MIME type aliases.
 /// For example ``application/turtle`` could also be used for `Turtle <https://www.w3.org/TR/turtle/>`_
@@ -92,6 +93,7 @@ pub fn parse(
 /// * `Turtle <https://www.w3.org/TR/turtle/>`_ (``text/turtle``)
 /// * `TriG <https://www.w3.org/TR/trig/>`_ (``application/trig``)
 /// * `RDF/XML <https://www.w3.org/TR/rdf-syntax-grammar/>`_ (``application/rdf+xml``)
+/// * `JSON-LD <https://www.w3.org/TR/json-ld11/>`_ (``application/ld+json``)
 ///
 /// It supports also some MIME type aliases.
 /// For example ``application/turtle`` could also be used for `Turtle <https://www.w3.org/TR/turtle/>`_
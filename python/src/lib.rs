@@ -10,6 +10,7 @@
 
 mod io;
 mod model;
+mod shacl;
 mod sparql;
 mod store;
 
@@ -36,5 +37,7 @@ fn pyoxigraph(_py: Python<'_>, module: &PyModule) -> PyResult<()> {
     module.add_class::<PyQuerySolutions>()?;
     module.add_class::<PyQuerySolution>()?;
     module.add_class::<PyQueryTriples>()?;
-    io::add_to_module(module)
+    module.add_class::<PyPreparedQuery>()?;
+    io::add_to_module(module)?;
+    shacl::add_to_module(module)
 }
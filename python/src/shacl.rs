@@ -0,0 +1,114 @@
+use crate::io::map_io_err;
+use crate::model::*;
+use crate::sparql::map_evaluation_error;
+use crate::store::PyStore;
+use oxigraph::model::{Graph, GraphName, Triple};
+use oxigraph::shacl::{ShapesGraph, ValidationReport};
+use oxigraph::store::Store;
+use pyo3::prelude::*;
+use pyo3::wrap_pyfunction;
+use std::vec::IntoIter;
+
+pub fn add_to_module(module: &PyModule) -> PyResult<()> {
+    module.add_class::<PyValidationReport>()?;
+    module.add_wrapped(wrap_pyfunction!(validate))
+}
+
+/// Validates RDF data against `SHACL Core <https://www.w3.org/TR/shacl/#core-components>`_ shapes.
+///
+/// `SPARQL-based constraints <https://www.w3.org/TR/shacl/#sparql-constraints>`_ are also supported.
+///
+/// :param data: the data graph to validate, either an iterable of triples or a store.
+/// :type data: iter(Triple) or Store
+/// :param shapes: the triples of the shapes graph.
+/// :type shapes: iter(Triple)
+/// :param graph_name: the graph of the store to validate. By default the default graph is validated.
+/// :type graph_name: NamedNode or BlankNode or DefaultGraph or None, optional
+/// :return: the validation report.
+/// :rtype: ValidationReport
+/// :raises SyntaxError: if the shapes are not valid.
+/// :raises IOError: if an I/O error happens while reading the store.
+/// :raises ValueError: if the evaluation of a SPARQL-based constraint fails.
+///
+/// >>> shapes = [Triple(NamedNode('http://example.com/s'), NamedNode('http://www.w3.org/ns/shacl#targetNode'), NamedNode('http://example.com')), Triple(NamedNode('http://example.com/s'), NamedNode('http://www.w3.org/ns/shacl#class'), NamedNode('http://example.com/C'))]
+/// >>> validate([], shapes).conforms
+/// False
+/// >>> validate([Triple(NamedNode('http://example.com'), NamedNode('http://www.w3.org/1999/02/22-rdf-syntax-ns#type'), NamedNode('http://example.com/C'))], shapes).conforms
+/// True
+#[pyfunction]
+#[pyo3(text_signature = "(data, shapes, *, graph_name = None)")]
+pub fn validate(
+    data: &PyAny,
+    shapes: &PyAny,
+    graph_name: Option<PyGraphName>,
+) -> PyResult<PyValidationReport> {
+    let shapes = ShapesGraph::new(&extract_graph(shapes)?).map_err(map_io_err)?;
+    let inner = if let Ok(store) = data.extract::<PyStore>() {
+        let graph_name = graph_name.map_or(GraphName::DefaultGraph, GraphName::from);
+        Store::from(store).validate(&shapes, &graph_name)
+    } else {
+        shapes.validate(&extract_graph(data)?)
+    }
+    .map_err(map_evaluation_error)?;
+    Ok(PyValidationReport { inner })
+}
+
+fn extract_graph(triples: &PyAny) -> PyResult<Graph> {
+    let mut graph = Graph::new();
+    for triple in triples.iter()? {
+        graph.insert(&*triple?.downcast::<PyCell<PyTriple>>()?.borrow());
+    }
+    Ok(graph)
+}
+
+/// A `SHACL validation report <https://www.w3.org/TR/shacl/#validation-report>`_ returned by :py:func:`validate`.
+///
+/// It is iterable and returns the triples of the report graph.
+///
+/// >>> report = validate([], [Triple(NamedNode('http://example.com/s'), NamedNode('http://www.w3.org/ns/shacl#targetNode'), NamedNode('http://example.com'))])
+/// >>> report.conforms
+/// True
+/// >>> len(list(report))
+/// 2
+#[pyclass(name = "ValidationReport", module = "oxigraph")]
+pub struct PyValidationReport {
+    inner: ValidationReport,
+}
+
+#[pymethods]
+impl PyValidationReport {
+    /// :return: if the data conforms to the shapes, i.e. there are no validation results.
+    /// :rtype: bool
+    #[getter]
+    fn conforms(&self) -> bool {
+        self.inner.conforms()
+    }
+
+    fn __iter__(&self) -> ValidationReportIter {
+        ValidationReportIter {
+            inner: self
+                .inner
+                .graph()
+                .iter()
+                .map(|t| t.into_owned())
+                .collect::<Vec<_>>()
+                .into_iter(),
+        }
+    }
+}
+
+#[pyclass(module = "oxigraph")]
+pub struct ValidationReportIter {
+    inner: IntoIter<Triple>,
+}
+
+#[pymethods]
+impl ValidationReportIter {
+    fn __iter__(slf: PyRef<'_, Self>) -> Py<Self> {
+        slf.into()
+    }
+
+    fn __next__(&mut self) -> Option<PyTriple> {
+        self.inner.next().map(PyTriple::from)
+    }
+}
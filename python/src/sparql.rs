@@ -4,6 +4,7 @@ use oxigraph::model::Term;
 use oxigraph::sparql::*;
 use pyo3::exceptions::{PyRuntimeError, PySyntaxError, PyTypeError, PyValueError};
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
 use pyo3::{Py, PyRef};
 use std::vec::IntoIter;
 
@@ -223,6 +224,58 @@ impl PyQueryTriples {
     }
 }
 
+/// A SPARQL query parsed and planned once in order to be executed multiple times.
+///
+/// It is returned by :py:func:`Store.prepare`.
+/// Only the variables of the query top level scope could be bound:
+/// the variables returned in the solutions for ``SELECT`` queries and the variables of the ``WHERE`` clause for the other query forms.
+///
+/// >>> store = Store()
+/// >>> store.add(Quad(NamedNode('http://example.com'), NamedNode('http://example.com/p'), Literal('1')))
+/// >>> prepared = store.prepare('ASK { ?s ?p ?o }')
+/// >>> prepared.execute({Variable('o'): Literal('1')})
+/// True
+/// >>> prepared.execute({Variable('o'): Literal('2')})
+/// False
+#[pyclass(unsendable, name = "PreparedQuery", module = "oxigraph")]
+pub struct PyPreparedQuery {
+    pub inner: PreparedQuery,
+}
+
+#[pymethods]
+impl PyPreparedQuery {
+    /// Executes the query against the current content of the store.
+    ///
+    /// :param bindings: values for some of the query variables, indexed by variable name (:py:class:`Variable` or :py:class:`str`).
+    /// :type bindings: dict(Variable or str, NamedNode or BlankNode or Literal or Triple) or None, optional
+    /// :return: a :py:class:`bool` for ``ASK`` queries, an iterator of :py:class:`Triple` for ``CONSTRUCT`` and ``DESCRIBE`` queries and an iterator of :py:class:`QuerySolution` for ``SELECT`` queries.
+    /// :rtype: QuerySolutions or QueryTriples or bool
+    /// :raises ValueError: if one of the variables is not in the query top level scope.
+    /// :raises IOError: if an I/O error happens while reading the store.
+    #[pyo3(text_signature = "($self, bindings = None)")]
+    #[args(bindings = "None")]
+    fn execute(&self, bindings: Option<&PyDict>, py: Python<'_>) -> PyResult<PyObject> {
+        let mut values = Vec::new();
+        if let Some(bindings) = bindings {
+            for (variable, value) in bindings {
+                let variable = if let Ok(variable) = <&str>::extract(variable) {
+                    Variable::new(variable).map_err(|e| PyValueError::new_err(e.to_string()))?
+                } else if let Ok(variable) = variable.downcast::<PyCell<PyVariable>>() {
+                    <&Variable>::from(&*variable.borrow()).clone()
+                } else {
+                    return Err(PyTypeError::new_err(format!(
+                        "{} is not a string or a Variable",
+                        variable.get_type().name()?,
+                    )));
+                };
+                values.push((variable, Term::from(PyTerm::extract(value)?)));
+            }
+        }
+        let results = self.inner.execute(values).map_err(map_evaluation_error)?;
+        query_results_to_python(py, results)
+    }
+}
+
 pub fn map_evaluation_error(error: EvaluationError) -> PyErr {
     match error {
         EvaluationError::Parsing(error) => PySyntaxError::new_err(error.to_string()),
@@ -38,6 +38,12 @@ pub struct PyStore {
     inner: Store,
 }
 
+impl From<PyStore> for Store {
+    fn from(store: PyStore) -> Self {
+        store.inner
+    }
+}
+
 #[pymethods]
 impl PyStore {
     #[new]
@@ -192,6 +198,60 @@ impl PyStore {
         query_results_to_python(py, results)
     }
 
+    /// Parses and plans a `SPARQL 1.1 query <https://www.w3.org/TR/sparql11-query/>`_ once in order to execute it multiple times.
+    ///
+    /// Each execution of the returned query reads the current content of the store.
+    ///
+    /// :param query: the query to prepare.
+    /// :type query: str
+    /// :param base_iri: the base IRI used to resolve the relative IRIs in the SPARQL query or :py:const:`None` if relative IRI resolution should not be done.
+    /// :type base_iri: str or None, optional
+    /// :param use_default_graph_as_union: if the SPARQL query should look for triples in all the dataset graphs by default (i.e. without `GRAPH` operations). Disabled by default.
+    /// :type use_default_graph_as_union: bool, optional
+    /// :param default_graph: list of the graphs that should be used as the query default graph. By default, the store default graph is used.
+    /// :type default_graph: NamedNode or BlankNode or DefaultGraph or list(NamedNode or BlankNode or DefaultGraph) or None, optional
+    /// :param named_graphs: list of the named graphs that could be used in SPARQL `GRAPH` clause. By default, all the store named graphs are available.
+    /// :type named_graphs: list(NamedNode or BlankNode) or None, optional
+    /// :return: the prepared query.
+    /// :rtype: PreparedQuery
+    /// :raises SyntaxError: if the provided query is invalid.
+    /// :raises IOError: if an I/O error happens while reading the store.
+    ///
+    /// >>> store = Store()
+    /// >>> store.add(Quad(NamedNode('http://example.com'), NamedNode('http://example.com/p'), Literal('1')))
+    /// >>> prepared = store.prepare('SELECT ?o WHERE { ?s ?p ?o }')
+    /// >>> list(solution['o'] for solution in prepared.execute({'s': NamedNode('http://example.com')}))
+    /// [<Literal value=1 datatype=<NamedNode value=http://www.w3.org/2001/XMLSchema#string>>]
+    #[pyo3(
+        text_signature = "($self, query, *, base_iri, use_default_graph_as_union, default_graph, named_graphs)"
+    )]
+    #[args(
+        query,
+        "*",
+        base_iri = "None",
+        use_default_graph_as_union = "false",
+        default_graph = "None",
+        named_graphs = "None"
+    )]
+    fn prepare(
+        &self,
+        query: &str,
+        base_iri: Option<&str>,
+        use_default_graph_as_union: bool,
+        default_graph: Option<&PyAny>,
+        named_graphs: Option<&PyAny>,
+    ) -> PyResult<PyPreparedQuery> {
+        let query = parse_query(
+            query,
+            base_iri,
+            use_default_graph_as_union,
+            default_graph,
+            named_graphs,
+        )?;
+        let inner = self.inner.prepare(query).map_err(map_evaluation_error)?;
+        Ok(PyPreparedQuery { inner })
+    }
+
     /// Executes a `SPARQL 1.1 update <https://www.w3.org/TR/sparql11-update/>`_.
     ///
     /// Updates are applied in a transactional manner: either the full operation succeeds or nothing is written to the database.
@@ -249,6 +309,7 @@ impl PyStore {
     /// * `Turtle <https://www.w3.org/TR/turtle/>`_ (``text/turtle``)
     /// * `TriG <https://www.w3.org/TR/trig/>`_ (``application/trig``)
     /// * `RDF/XML <https://www.w3.org/TR/rdf-syntax-grammar/>`_ (``application/rdf+xml``)
+    /// * `JSON-LD <https://www.w3.org/TR/json-ld11/>`_ (``application/ld+json``)
     ///
     /// It supports also some MIME type aliases.
     /// For example ``application/turtle`` could also be used for `Turtle <https://www.w3.org/TR/turtle/>`_
@@ -325,6 +386,7 @@ impl PyStore {
     /// * `Turtle <https://www.w3.org/TR/turtle/>`_ (``text/turtle``)
     /// * `TriG <https://www.w3.org/TR/trig/>`_ (``application/trig``)
     /// * `RDF/XML <https://www.w3.org/TR/rdf-syntax-grammar/>`_ (``application/rdf+xml``)
+    /// * `JSON-LD <https://www.w3.org/TR/json-ld11/>`_ (``application/ld+json``)
     ///
     /// It supports also some MIME type aliases.
     /// For example ``application/turtle`` could also be used for `Turtle <https://www.w3.org/TR/turtle/>`_
@@ -396,6 +458,7 @@ impl PyStore {
     /// * `Turtle <https://www.w3.org/TR/turtle/>`_ (``text/turtle``)
     /// * `TriG <https://www.w3.org/TR/trig/>`_ (``application/trig``)
     /// * `RDF/XML <https://www.w3.org/TR/rdf-syntax-grammar/>`_ (``application/rdf+xml``)
+    /// * `JSON-LD <https://www.w3.org/TR/json-ld11/>`_ (``application/ld+json``)
     ///
     /// It supports also some MIME type aliases.
     /// For example ``application/turtle`` could also be used for `Turtle <https://www.w3.org/TR/turtle/>`_
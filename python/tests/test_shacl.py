@@ -0,0 +1,53 @@
+import unittest
+
+from pyoxigraph import *
+
+sh = "http://www.w3.org/ns/shacl#"
+shape = NamedNode("http://example.com/shape")
+foo = NamedNode("http://foo")
+bar = NamedNode("http://bar")
+graph = NamedNode("http://graph")
+shapes = [
+    Triple(shape, NamedNode(sh + "targetSubjectsOf"), bar),
+    Triple(shape, NamedNode(sh + "property"), BlankNode("p")),
+    Triple(BlankNode("p"), NamedNode(sh + "path"), bar),
+    Triple(BlankNode("p"), NamedNode(sh + "datatype"), NamedNode("http://www.w3.org/2001/XMLSchema#string")),
+]
+
+
+class TestShacl(unittest.TestCase):
+    def test_validate_triples(self):
+        self.assertTrue(validate([Triple(foo, bar, Literal("1"))], shapes).conforms)
+        report = validate([Triple(foo, bar, bar)], shapes)
+        self.assertFalse(report.conforms)
+        self.assertIn(
+            NamedNode(sh + "DatatypeConstraintComponent"),
+            [t.object for t in report if t.predicate == NamedNode(sh + "sourceConstraintComponent")],
+        )
+        self.assertIn(foo, [t.object for t in report if t.predicate == NamedNode(sh + "focusNode")])
+
+    def test_validate_store(self):
+        store = Store()
+        store.add(Quad(foo, bar, bar, graph))
+        self.assertTrue(validate(store, shapes).conforms)
+        self.assertFalse(validate(store, shapes, graph_name=graph).conforms)
+
+    def test_validate_sparql_constraint(self):
+        sparql_shapes = [
+            Triple(shape, NamedNode(sh + "targetSubjectsOf"), bar),
+            Triple(shape, NamedNode(sh + "sparql"), BlankNode("c")),
+            Triple(BlankNode("c"), NamedNode(sh + "select"), Literal("SELECT $this WHERE { $this <http://bar> $this }")),
+        ]
+        store = Store()
+        store.add(Quad(foo, bar, bar, graph))
+        self.assertTrue(validate(store, sparql_shapes, graph_name=graph).conforms)
+        store.add(Quad(foo, bar, foo, graph))
+        self.assertFalse(validate(store, sparql_shapes, graph_name=graph).conforms)
+
+    def test_invalid_shapes(self):
+        with self.assertRaises(SyntaxError):
+            validate([], [Triple(shape, NamedNode(sh + "targetNode"), foo), Triple(shape, NamedNode(sh + "minCount"), Literal("one"))])
+
+
+if __name__ == "__main__":
+    unittest.main()
@@ -150,6 +150,21 @@ class TestStore(unittest.TestCase):
         )
         self.assertEqual(len(list(results)), 2)
 
+    def test_prepared_query(self):
+        store = Store()
+        store.add(Quad(foo, bar, baz))
+        store.add(Quad(bar, bar, foo))
+        prepared = store.prepare("SELECT ?s ?o WHERE { ?s ?p ?o }")
+        self.assertEqual(len(list(prepared.execute())), 2)
+        solutions = list(prepared.execute({"s": foo}))
+        self.assertEqual(len(solutions), 1)
+        self.assertEqual(solutions[0]["o"], baz)
+        self.assertEqual(len(list(prepared.execute({Variable("o"): bar}))), 0)
+        store.add(Quad(foo, bar, bar))
+        self.assertEqual(len(list(prepared.execute({Variable("o"): bar}))), 1)
+        with self.assertRaises(ValueError):
+            prepared.execute({"p": bar})
+
     def test_update_insert_data(self):
         store = Store()
         store.update('INSERT DATA { <http://foo> <http://foo> <http://foo> }')
@@ -221,6 +236,16 @@ class TestStore(unittest.TestCase):
         )
         self.assertEqual(set(store), {Quad(foo, bar, baz, graph)})
 
+    def test_load_json_ld(self):
+        store = Store()
+        store.load(
+            BytesIO(
+                b'{"@id": "http://graph", "@graph": {"@id": "http://foo", "http://bar": {"@id": "http://baz"}}}'
+            ),
+            mime_type="application/ld+json",
+        )
+        self.assertEqual(set(store), {Quad(foo, bar, baz, graph)})
+
     def test_dump_ntriples(self):
         store = Store()
         store.add(Quad(foo, bar, baz, graph))
@@ -240,6 +265,16 @@ class TestStore(unittest.TestCase):
             b"<http://foo> <http://bar> <http://baz> <http://graph> .\n",
         )
 
+    def test_dump_json_ld(self):
+        store = Store()
+        store.add(Quad(foo, bar, baz))
+        output = BytesIO()
+        store.dump(output, "application/ld+json")
+        self.assertEqual(
+            output.getvalue(),
+            b'[{"@id":"http://foo","http://bar":[{"@id":"http://baz"}]}]',
+        )
+
     def test_write_in_read(self):
         store = Store()
         store.add(Quad(foo, bar, bar))
@@ -1,9 +1,9 @@
 use anyhow::{anyhow, Result};
-use oxigraph::io::{DatasetFormat, GraphFormat};
-use oxigraph::model::{Dataset, Graph, GraphNameRef};
+use oxigraph::io::{DatasetFormat, DatasetParser, GraphFormat};
+use oxigraph::model::{Dataset, Graph, GraphNameRef, Triple};
 use oxigraph::store::Store;
 use std::fs::File;
-use std::io::{BufRead, BufReader, Read};
+use std::io::{self, BufRead, BufReader, Read};
 use std::path::PathBuf;
 
 pub fn read_file(url: &str) -> Result<impl BufRead> {
@@ -22,7 +22,9 @@ pub fn read_file(url: &str) -> Result<impl BufRead> {
             "http://www.w3.org/2009/sparql/docs/tests/",
             "rdf-tests/sparql11/",
         ))
-    } else if url.starts_with("https://w3c.github.io/rdf-star/") {
+    } else if url.starts_with("https://w3c.github.io/rdf-star/")
+        || url.starts_with("https://w3c.github.io/json-ld-api/")
+    {
         Ok(url.replace("https://w3c.github.io/", ""))
     } else if url.starts_with("https://github.com/oxigraph/oxigraph/tests/") {
         Ok(url.replace(
@@ -41,6 +43,22 @@ pub fn read_file_to_string(url: &str) -> Result<String> {
     Ok(buf)
 }
 
+/// Loads the remote JSON-LD documents from the test suites files
+pub fn load_document(url: &str) -> io::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    read_file(url)
+        .map_err(|e| io::Error::new(io::ErrorKind::NotFound, e.to_string()))?
+        .read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+/// A JSON-LD parser loading the remote contexts from the test suites files
+pub fn json_ld_parser(base_iri: &str) -> Result<DatasetParser> {
+    Ok(DatasetParser::from_format(DatasetFormat::JsonLd)
+        .with_base_iri(base_iri)?
+        .with_document_loader(load_document))
+}
+
 pub fn load_to_store<'a>(
     url: &str,
     store: &Store,
@@ -84,6 +102,13 @@ pub fn load_to_graph(url: &str, graph: &mut Graph) -> Result<()> {
         graph.load(read_file(url)?, GraphFormat::Turtle, Some(url))?
     } else if url.ends_with(".rdf") {
         graph.load(read_file(url)?, GraphFormat::RdfXml, Some(url))?
+    } else if url.ends_with(".jsonld") {
+        for quad in json_ld_parser(url)?.read_quads(read_file(url)?)? {
+            let quad = quad?;
+            if quad.graph_name.is_default_graph() {
+                graph.insert(&Triple::from(quad));
+            }
+        }
     } else {
         return Err(anyhow!("Serialization type not found for {}", url));
     }
@@ -117,6 +142,10 @@ pub fn load_to_dataset<'a>(
         dataset.load(read_file(url)?, DatasetFormat::NQuads, Some(url))?
     } else if url.ends_with(".trig") {
         dataset.load(read_file(url)?, DatasetFormat::TriG, Some(url))?
+    } else if url.ends_with(".jsonld") {
+        for quad in json_ld_parser(url)?.read_quads(read_file(url)?)? {
+            dataset.insert(&quad?);
+        }
     } else {
         return Err(anyhow!("Serialization type not found for {}", url));
     }
@@ -0,0 +1,172 @@
+//! Evaluation of the [W3C JSON-LD 1.1 API test suite](https://w3c.github.io/json-ld-api/tests/).
+//!
+//! Oxigraph only exposes JSON-LD as an RDF serialization: the results of the expansion, compaction and flattening tests
+//! are compared with the expected documents after converting both of them to RDF.
+
+use crate::evaluator::TestEvaluator;
+use crate::files::{json_ld_parser, load_dataset, load_document, read_file, read_file_to_string};
+use crate::manifest::Test;
+use crate::report::dataset_diff;
+use crate::vocab::jld;
+use anyhow::{anyhow, Result};
+use oxigraph::io::{DatasetFormat, DatasetSerializer};
+use oxigraph::model::{Dataset, Term};
+use std::io::Cursor;
+
+pub fn register_jsonld_tests(evaluator: &mut TestEvaluator) {
+    evaluator.register(
+        "https://w3c.github.io/json-ld-api/tests/vocab#ToRDFTest",
+        evaluate_to_rdf_test,
+    );
+    evaluator.register(
+        "https://w3c.github.io/json-ld-api/tests/vocab#FromRDFTest",
+        evaluate_from_rdf_test,
+    );
+    evaluator.register(
+        "https://w3c.github.io/json-ld-api/tests/vocab#ExpandTest",
+        |test| {
+            evaluate_serialization_test(test, DatasetSerializer::from_format(DatasetFormat::JsonLd))
+        },
+    );
+    evaluator.register(
+        "https://w3c.github.io/json-ld-api/tests/vocab#CompactTest",
+        |test| {
+            evaluate_serialization_test(test, DatasetSerializer::from_format(DatasetFormat::JsonLd))
+        },
+    );
+    evaluator.register(
+        "https://w3c.github.io/json-ld-api/tests/vocab#FlattenTest",
+        |test| {
+            evaluate_serialization_test(
+                test,
+                DatasetSerializer::from_format(DatasetFormat::JsonLd).with_flattening(),
+            )
+        },
+    );
+}
+
+fn evaluate_to_rdf_test(test: &Test) -> Result<()> {
+    let action = action(test)?;
+    let parsed = parse_json_ld(action, base_iri(test, action));
+    if is_negative(test) {
+        return match parsed {
+            Ok(_) => Err(anyhow!("File parsed without error even if it should not")),
+            Err(_) => Ok(()),
+        };
+    }
+    let actual = parsed.map_err(|e| anyhow!("Parse error on file {}: {}", action, e))?;
+    if let Some(result) = &test.result {
+        assert_isomorphic(load_dataset(result)?, actual)
+    } else {
+        Ok(()) // Syntax test
+    }
+}
+
+fn evaluate_from_rdf_test(test: &Test) -> Result<()> {
+    let action = action(test)?;
+    let input = load_dataset(action)?;
+    let result = test
+        .result
+        .as_deref()
+        .ok_or_else(|| anyhow!("No tests result found"))?;
+    let actual = write_and_parse(
+        &input,
+        &DatasetSerializer::from_format(DatasetFormat::JsonLd),
+        action,
+    )?;
+    assert_isomorphic(parse_json_ld(result, result)?, actual)
+}
+
+/// Parses the input document, writes it back with the serializer and compares the result with the expected document
+fn evaluate_serialization_test(test: &Test, serializer: DatasetSerializer) -> Result<()> {
+    let action = action(test)?;
+    let base_iri = base_iri(test, action);
+    let actual = parse_json_ld(action, base_iri).and_then(|input| {
+        let serializer = if let Some(context) = &test.context {
+            serializer.with_context(&read_file_to_string(context)?)?
+        } else {
+            serializer
+        };
+        write_and_parse(
+            &input,
+            &serializer.with_document_loader(load_document),
+            base_iri,
+        )
+    });
+    if is_negative(test) {
+        return match actual {
+            Ok(_) => Err(anyhow!(
+                "File processed without error even if it should not"
+            )),
+            Err(_) => Ok(()),
+        };
+    }
+    let actual = actual.map_err(|e| anyhow!("Error on file {}: {}", action, e))?;
+    if let Some(result) = &test.result {
+        assert_isomorphic(parse_json_ld(result, base_iri)?, actual)
+    } else {
+        Ok(()) // Syntax test
+    }
+}
+
+fn action(test: &Test) -> Result<&str> {
+    test.action
+        .as_deref()
+        .ok_or_else(|| anyhow!("No action found for test {}", test))
+}
+
+fn is_negative(test: &Test) -> bool {
+    test.other_kinds
+        .iter()
+        .any(|kind| kind.as_ref() == jld::NEGATIVE_EVALUATION_TEST)
+}
+
+/// The base IRI of the input document: its URL or the `base` option
+fn base_iri<'a>(test: &'a Test, action: &'a str) -> &'a str {
+    test.options
+        .iter()
+        .find_map(|(option, value)| match value {
+            Term::NamedNode(base) if option.as_ref() == jld::BASE => Some(base.as_str()),
+            _ => None,
+        })
+        .unwrap_or(action)
+}
+
+fn parse_json_ld(url: &str, base_iri: &str) -> Result<Dataset> {
+    let mut dataset = Dataset::new();
+    for quad in json_ld_parser(base_iri)?.read_quads(read_file(url)?)? {
+        dataset.insert(&quad?);
+    }
+    Ok(dataset)
+}
+
+fn write_and_parse(
+    dataset: &Dataset,
+    serializer: &DatasetSerializer,
+    base_iri: &str,
+) -> Result<Dataset> {
+    let mut buffer = Vec::new();
+    let mut writer = serializer.quad_writer(&mut buffer)?;
+    for quad in dataset {
+        writer.write(quad)?;
+    }
+    writer.finish()?;
+    let mut result = Dataset::new();
+    for quad in json_ld_parser(base_iri)?.read_quads(Cursor::new(buffer))? {
+        result.insert(&quad?);
+    }
+    Ok(result)
+}
+
+fn assert_isomorphic(mut expected: Dataset, mut actual: Dataset) -> Result<()> {
+    expected.canonicalize();
+    actual.canonicalize();
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "The two files are not isomorphic. Diff:\n{}",
+            dataset_diff(&expected, &actual)
+        ))
+    }
+}
@@ -12,6 +12,7 @@
 
 pub mod evaluator;
 pub mod files;
+pub mod jsonld_evaluator;
 pub mod manifest;
 pub mod parser_evaluator;
 pub mod report;
@@ -1,6 +1,7 @@
 use anyhow::Result;
 use clap::{App, Arg};
 use oxigraph_testsuite::evaluator::TestEvaluator;
+use oxigraph_testsuite::jsonld_evaluator::register_jsonld_tests;
 use oxigraph_testsuite::manifest::TestManifest;
 use oxigraph_testsuite::parser_evaluator::register_parser_tests;
 use oxigraph_testsuite::report::build_report;
@@ -19,6 +20,7 @@ fn main() -> Result<()> {
     let mut evaluator = TestEvaluator::default();
     register_parser_tests(&mut evaluator);
     register_sparql_tests(&mut evaluator);
+    register_jsonld_tests(&mut evaluator);
     let manifest = TestManifest::new(vec![matches.value_of("manifest").unwrap()]);
     let results = evaluator.evaluate(manifest)?;
     print!("{}", build_report(results));
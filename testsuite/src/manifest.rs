@@ -9,6 +9,8 @@ use std::fmt;
 pub struct Test {
     pub id: NamedNode,
     pub kind: NamedNode,
+    /// The other types of the test, like the positive or negative evaluation types of the JSON-LD tests
+    pub other_kinds: Vec<NamedNode>,
     pub name: Option<String>,
     pub comment: Option<String>,
     pub action: Option<String>,
@@ -19,6 +21,11 @@ pub struct Test {
     pub service_data: Vec<(String, String)>,
     pub result: Option<String>,
     pub result_graph_data: Vec<(NamedNode, String)>,
+    pub entailment_regimes: Vec<NamedNode>,
+    /// The JSON-LD context to compact with
+    pub context: Option<String>,
+    /// The JSON-LD processing options
+    pub options: Vec<(NamedNode, Term)>,
 }
 
 impl fmt::Display for Test {
@@ -92,12 +99,26 @@ impl Iterator for TestManifest {
                         Some(TermRef::Literal(c)) => Some(c.value().to_string()),
                         _ => None,
                     };
-                    let kind = match self
+                    let mut other_kinds = self
                         .graph
-                        .object_for_subject_predicate(&test_node, rdf::TYPE)
-                    {
-                        Some(TermRef::NamedNode(c)) => c.into_owned(),
-                        _ => {
+                        .objects_for_subject_predicate(&test_node, rdf::TYPE)
+                        .filter_map(|kind| match kind {
+                            TermRef::NamedNode(kind) => Some(kind.into_owned()),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>();
+                    // The JSON-LD tests have both a generic and an operation specific type
+                    let kind = match other_kinds.iter().position(|kind| {
+                        ![
+                            jld::POSITIVE_EVALUATION_TEST,
+                            jld::NEGATIVE_EVALUATION_TEST,
+                            jld::POSITIVE_SYNTAX_TEST,
+                        ]
+                        .contains(&kind.as_ref())
+                    }) {
+                        Some(position) => other_kinds.remove(position),
+                        None if !other_kinds.is_empty() => other_kinds.remove(0),
+                        None => {
                             return Some(Err(anyhow!(
                                 "The test {} named {} has no rdf:type",
                                 test_node,
@@ -256,9 +277,44 @@ impl Iterator for TestManifest {
                         Some(_) => return Some(Err(anyhow!("invalid result"))),
                         None => (None, Vec::new()),
                     };
+                    let entailment_regimes = self
+                        .graph
+                        .objects_for_subject_predicate(&test_node, sd::ENTAILMENT_REGIME)
+                        .flat_map(|regime| match regime {
+                            TermRef::NamedNode(regime) => vec![regime.into_owned()],
+                            TermRef::BlankNode(list) => {
+                                RdfListIterator::iter(&self.graph, list.into())
+                                    .filter_map(|regime| match regime {
+                                        Term::NamedNode(regime) => Some(regime),
+                                        _ => None,
+                                    })
+                                    .collect()
+                            }
+                            _ => Vec::new(),
+                        })
+                        .collect();
+                    let context = match self
+                        .graph
+                        .object_for_subject_predicate(&test_node, jld::CONTEXT)
+                    {
+                        Some(TermRef::NamedNode(context)) => Some(context.as_str().to_owned()),
+                        _ => None,
+                    };
+                    let options = match self
+                        .graph
+                        .object_for_subject_predicate(&test_node, jld::OPTION)
+                    {
+                        Some(TermRef::BlankNode(option)) => self
+                            .graph
+                            .triples_for_subject(option)
+                            .map(|t| (t.predicate.into_owned(), t.object.into_owned()))
+                            .collect(),
+                        _ => Vec::new(),
+                    };
                     Some(Ok(Test {
                         id: test_node,
                         kind,
+                        other_kinds,
                         name,
                         comment,
                         action,
@@ -269,6 +325,9 @@ impl Iterator for TestManifest {
                         service_data,
                         result,
                         result_graph_data,
+                        entailment_regimes,
+                        context,
+                        options,
                     }))
                 }
                 Some(Err(error)) => Some(Err(error)),
@@ -94,8 +94,18 @@ fn evaluate_evaluation_test(test: &Test) -> Result<()> {
         .query
         .as_deref()
         .ok_or_else(|| anyhow!("No action found for test {}", test))?;
-    let options = QueryOptions::default()
+    let mut options = QueryOptions::default()
         .with_service_handler(StaticServiceHandler::new(&test.service_data)?);
+    if !test.entailment_regimes.is_empty() {
+        if test.entailment_regimes.iter().any(|r| *r == ent::RDFS) {
+            options = options.with_rdfs_entailment();
+        } else {
+            return Err(anyhow!(
+                "None of the entailment regimes of {} is supported",
+                test
+            ));
+        }
+    }
     match Query::parse(&read_file_to_string(query_file)?, Some(query_file)) {
         Err(error) => Err(anyhow!(
             "Failure to parse query of {} with error: {}",
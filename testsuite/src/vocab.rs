@@ -66,6 +66,41 @@ pub mod qt {
     );
 }
 
+pub mod sd {
+    use oxigraph::model::NamedNodeRef;
+
+    pub const ENTAILMENT_REGIME: NamedNodeRef<'_> = NamedNodeRef::new_unchecked(
+        "http://www.w3.org/ns/sparql-service-description#entailmentRegime",
+    );
+}
+
+pub mod ent {
+    use oxigraph::model::NamedNodeRef;
+
+    pub const RDFS: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("http://www.w3.org/ns/entailment/RDFS");
+}
+
+pub mod jld {
+    use oxigraph::model::NamedNodeRef;
+
+    pub const POSITIVE_EVALUATION_TEST: NamedNodeRef<'_> = NamedNodeRef::new_unchecked(
+        "https://w3c.github.io/json-ld-api/tests/vocab#PositiveEvaluationTest",
+    );
+    pub const NEGATIVE_EVALUATION_TEST: NamedNodeRef<'_> = NamedNodeRef::new_unchecked(
+        "https://w3c.github.io/json-ld-api/tests/vocab#NegativeEvaluationTest",
+    );
+    pub const POSITIVE_SYNTAX_TEST: NamedNodeRef<'_> = NamedNodeRef::new_unchecked(
+        "https://w3c.github.io/json-ld-api/tests/vocab#PositiveSyntaxTest",
+    );
+    pub const CONTEXT: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("https://w3c.github.io/json-ld-api/tests/vocab#context");
+    pub const OPTION: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("https://w3c.github.io/json-ld-api/tests/vocab#option");
+    pub const BASE: NamedNodeRef<'_> =
+        NamedNodeRef::new_unchecked("https://w3c.github.io/json-ld-api/tests/vocab#base");
+}
+
 pub mod ut {
     use oxigraph::model::NamedNodeRef;
     pub const DATA: NamedNodeRef<'_> =
@@ -0,0 +1,97 @@
+use anyhow::Result;
+use oxigraph::model::Term;
+use oxigraph_testsuite::evaluator::TestEvaluator;
+use oxigraph_testsuite::jsonld_evaluator::register_jsonld_tests;
+use oxigraph_testsuite::manifest::TestManifest;
+
+/// The JSON-LD processing options that are not available in Oxigraph with the value they require.
+/// The tests using them are skipped.
+const UNSUPPORTED_OPTIONS: [(&str, Option<&str>); 10] = [
+    // The tests only valid for JSON-LD 1.0 processors
+    ("specVersion", Some("json-ld-1.0")),
+    ("processingMode", Some("json-ld-1.0")),
+    // Generalized RDF is not supported
+    ("produceGeneralizedRdf", Some("true")),
+    // The parser has no expansion context and always drops the base direction of the strings
+    ("expandContext", None),
+    ("rdfDirection", None),
+    // The serializer always writes the literals as value objects and rdf:type as @type
+    ("useNativeTypes", Some("true")),
+    ("useRdfType", Some("true")),
+    // The serializer does not provide these compaction options
+    ("compactArrays", Some("false")),
+    ("compactToRelative", Some("false")),
+    ("omitGraph", None),
+];
+
+fn run_testsuite(manifest_url: &str, ignored_tests: Vec<&str>) -> Result<()> {
+    let mut evaluator = TestEvaluator::default();
+    register_jsonld_tests(&mut evaluator);
+    let manifest = TestManifest::new(vec![manifest_url]).filter(|test| {
+        test.as_ref().map_or(true, |test| {
+            !test.options.iter().any(|(option, value)| {
+                UNSUPPORTED_OPTIONS.iter().any(|(name, unsupported_value)| {
+                    option.as_str()
+                        == format!("https://w3c.github.io/json-ld-api/tests/vocab#{}", name)
+                        && unsupported_value.map_or(true, |unsupported_value| match value {
+                            Term::Literal(value) => value.value() == unsupported_value,
+                            _ => false,
+                        })
+                })
+            })
+        })
+    });
+    let results = evaluator.evaluate(manifest)?;
+
+    let mut errors = Vec::default();
+    for result in results {
+        if let Err(error) = &result.outcome {
+            if !ignored_tests.contains(&result.test.as_str()) {
+                errors.push(format!("{}: failed with error {}", result.test, error))
+            }
+        }
+    }
+
+    assert!(errors.is_empty(), "\n{}\n", errors.join("\n"));
+    Ok(())
+}
+
+#[test]
+fn jsonld_to_rdf_w3c_testsuite() -> Result<()> {
+    run_testsuite(
+        "https://w3c.github.io/json-ld-api/tests/toRdf-manifest.jsonld",
+        vec![],
+    )
+}
+
+#[test]
+fn jsonld_from_rdf_w3c_testsuite() -> Result<()> {
+    run_testsuite(
+        "https://w3c.github.io/json-ld-api/tests/fromRdf-manifest.jsonld",
+        vec![],
+    )
+}
+
+#[test]
+fn jsonld_expand_w3c_testsuite() -> Result<()> {
+    run_testsuite(
+        "https://w3c.github.io/json-ld-api/tests/expand-manifest.jsonld",
+        vec![],
+    )
+}
+
+#[test]
+fn jsonld_compact_w3c_testsuite() -> Result<()> {
+    run_testsuite(
+        "https://w3c.github.io/json-ld-api/tests/compact-manifest.jsonld",
+        vec![],
+    )
+}
+
+#[test]
+fn jsonld_flatten_w3c_testsuite() -> Result<()> {
+    run_testsuite(
+        "https://w3c.github.io/json-ld-api/tests/flatten-manifest.jsonld",
+        vec![],
+    )
+}